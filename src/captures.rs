@@ -0,0 +1,235 @@
+//! Variables captured from responses.
+//!
+//! `--capture token=$.access_token` extracts a value from a JSON response,
+//! or `--capture reqid=header:X-Request-Id` from a response header, and
+//! stores it under the active profile, so later invocations can use
+//! `{{token}}` without copying it by hand. Captured values are kept in
+//! `captured.json` in the config directory.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::config;
+use crate::error::{Result, RurlError};
+use crate::filter::JsonPath;
+use crate::http::HttpResponse;
+
+/// Key used for captures made without `--profile`.
+const DEFAULT_PROFILE: &str = "default";
+
+/// Where a captured value is taken from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureSource {
+    /// Path selecting the value in a JSON response body
+    Body(JsonPath),
+    /// Response header, written `header:NAME`
+    Header(String),
+}
+
+impl fmt::Display for CaptureSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureSource::Body(path) => write!(f, "{}", path),
+            CaptureSource::Header(name) => write!(f, "header:{}", name),
+        }
+    }
+}
+
+/// A `name=path` or `name=header:NAME` capture definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    /// Variable name to store the value under
+    pub name: String,
+    /// Where the value comes from
+    pub source: CaptureSource,
+}
+
+impl Capture {
+    /// Returns true if the value is taken from the response body.
+    pub fn needs_body(&self) -> bool {
+        matches!(self.source, CaptureSource::Body(_))
+    }
+
+    /// Extracts the captured value from a response.
+    ///
+    /// Repeated headers are joined with `, `.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error unless a body path matches exactly one value, or if
+    /// the header is missing.
+    pub fn extract(&self, response: &HttpResponse) -> Result<String> {
+        match &self.source {
+            CaptureSource::Body(path) => path.extract_one(&response.body),
+            CaptureSource::Header(name) => header_value(response, name)
+                .ok_or_else(|| RurlError::FilterError(format!("response has no '{}' header", name))),
+        }
+    }
+}
+
+/// Returns a response header's value, joining repeated headers with `, `.
+pub(crate) fn header_value(response: &HttpResponse, name: &str) -> Option<String> {
+    let values: Vec<&str> = response
+        .headers
+        .get_all(name)
+        .iter()
+        .map(|value| value.to_str().unwrap_or_default())
+        .collect();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+impl FromStr for Capture {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('=') {
+            Some((name, source)) if !name.trim().is_empty() => {
+                let source = match source.trim().strip_prefix("header:") {
+                    Some(header) if !header.trim().is_empty() => CaptureSource::Header(header.trim().to_string()),
+                    Some(_) => return Err(RurlError::FilterError(format!("missing header name in '{}'", s))),
+                    None => CaptureSource::Body(source.parse()?),
+                };
+                Ok(Self {
+                    name: name.trim().to_string(),
+                    source,
+                })
+            }
+            _ => Err(RurlError::FilterError(format!("expected name=path, got '{}'", s))),
+        }
+    }
+}
+
+/// Captured variables for each profile, persisted between runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CapturedVars {
+    #[serde(flatten)]
+    profiles: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl CapturedVars {
+    /// Returns the default store location, `captured.json` in the config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(config::config_dir()?.join("captured.json"))
+    }
+
+    /// Loads captured variables, returning an empty store if the file doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| RurlError::ConfigError(format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the store, creating its directory if needed.
+    ///
+    /// Captured values are often tokens, so on Unix the file is only
+    /// readable by its owner, including a file written by older versions.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        std::io::Write::write_all(&mut file, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the variables captured under a profile.
+    pub fn vars(&self, profile: Option<&str>) -> Vec<(String, String)> {
+        self.profiles
+            .get(profile.unwrap_or(DEFAULT_PROFILE))
+            .map(|vars| vars.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default()
+    }
+
+    /// Stores a variable under a profile, replacing any previous value.
+    pub fn set(&mut self, profile: Option<&str>, name: &str, value: String) {
+        self.profiles
+            .entry(profile.unwrap_or(DEFAULT_PROFILE).to_string())
+            .or_default()
+            .insert(name.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    fn response(body: &str) -> HttpResponse {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", HeaderValue::from_static("req-42"));
+        headers.append("vary", HeaderValue::from_static("Accept"));
+        headers.append("vary", HeaderValue::from_static("Origin"));
+        HttpResponse::new(StatusCode::OK, headers, body.to_string(), Duration::ZERO)
+    }
+
+    #[test]
+    fn test_capture_extract() {
+        let capture: Capture = "token=$.auth.access_token".parse().unwrap();
+        assert_eq!(capture.name, "token");
+        assert_eq!(capture.extract(&response(r#"{"auth": {"access_token": "abc"}}"#)).unwrap(), "abc");
+        assert_eq!(
+            "n=$.count".parse::<Capture>().unwrap().extract(&response(r#"{"count": 3}"#)).unwrap(),
+            "3"
+        );
+        assert!("ids=$.items[*]".parse::<Capture>().unwrap().extract(&response(r#"{"items": [1, 2]}"#)).is_err());
+        assert!("$.token".parse::<Capture>().is_err());
+    }
+
+    #[test]
+    fn test_capture_header() {
+        let capture: Capture = "reqid=header:X-Request-Id".parse().unwrap();
+        assert_eq!(capture.source, CaptureSource::Header("X-Request-Id".to_string()));
+        assert_eq!(capture.source.to_string(), "header:X-Request-Id");
+        assert!(!capture.needs_body());
+        assert_eq!(capture.extract(&response("")).unwrap(), "req-42");
+        assert_eq!("v=header:vary".parse::<Capture>().unwrap().extract(&response("")).unwrap(), "Accept, Origin");
+        assert!("e=header:etag".parse::<Capture>().unwrap().extract(&response("")).is_err());
+        assert!("e=header:".parse::<Capture>().is_err());
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let path = std::env::temp_dir().join(format!("hurley_test_captured_{}.json", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        assert_eq!(CapturedVars::load(&path).unwrap(), CapturedVars::default());
+
+        let mut store = CapturedVars::default();
+        store.set(None, "token", "abc".to_string());
+        store.set(Some("staging"), "token", "xyz".to_string());
+        store.save(&path).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+            // A store written world-readable before is tightened on save
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            store.save(&path).unwrap();
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        let loaded = CapturedVars::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.vars(None), vec![("token".to_string(), "abc".to_string())]);
+        assert_eq!(loaded.vars(Some("staging")), vec![("token".to_string(), "xyz".to_string())]);
+        assert!(loaded.vars(Some("prod")).is_empty());
+    }
+}
@@ -3,8 +3,27 @@
 //! This module uses the `clap` crate with derive macros to define
 //! command-line arguments for both single HTTP requests and performance testing.
 
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Parser, Subcommand};
+use std::io::IsTerminal;
+use std::net::IpAddr;
 use std::path::PathBuf;
+use chrono::NaiveTime;
+use std::time::Duration;
+
+use crate::captures::Capture;
+use crate::config::Profile;
+use crate::duration::parse_duration;
+use crate::filter::JsonPath;
+use crate::http::render::BUILTIN_RENDERERS;
+use crate::http::range::parse_length;
+use crate::http::{check_method, AcceptValue, ByteRange, DnsServer, ResolveEntry, DEFAULT_MAX_REDIRECTS};
+use crate::perf::{Assertion, LoadProfile, OutputTarget, ReportFormat, Sample, Slo, Threshold, DEFAULT_EXACT_SAMPLES, DEFAULT_MAX_DEFERRED};
+use crate::request_file::RequestFile;
+use crate::schedule::{delay_until_local, parse_time_of_day};
+use crate::session::Session;
+use crate::template::parse_var;
+use crate::write_out::WriteOut;
 
 /// A curl-like HTTP client with performance testing capabilities.
 ///
@@ -29,9 +48,52 @@ use std::path::PathBuf;
 #[command(author = "Dursun Koc <dursunkoc@gmail.com>")]
 #[command(version = "0.1.1")]
 #[command(about = "A curl-like HTTP client with performance testing capabilities", long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 pub struct Cli {
     /// Target URL for the HTTP request.
-    pub url: String,
+    ///
+    /// `[1-100]`, `[a-z]` and `{a,b,c}` globs expand it curl-style into one
+    /// request per URL (see `--globoff`). In a performance test the URLs
+    /// are requested in turn, like the entries of a dataset.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley 'https://api.example.com/items/[1-100]' -c 10 -n 1000
+    /// ```
+    #[arg(required_unless_present = "session")]
+    pub url: Option<String>,
+
+    /// More URLs to request with the same options, curl-style.
+    ///
+    /// They are requested one after another, or together with
+    /// `--parallel`. Each response is printed under a `==> URL <==` line,
+    /// in the order given; `-o` can be repeated to save them in that order.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://example.com/a.json https://example.com/b.json -o a.json -o b.json --parallel
+    /// ```
+    #[arg(
+        value_name = "URL",
+        conflicts_with_all = [
+            "perf_file", "openapi_file", "total_requests", "concurrency", "duration", "rate", "stages",
+            "iterations", "keepalive", "raw_request", "negotiate", "plan", "workers", "save_parts",
+        ]
+    )]
+    pub more_urls: Vec<String>,
+
+    /// Request several URLs at the same time instead of one after another.
+    ///
+    /// Responses are still printed in the order the URLs were given.
+    #[arg(short = 'Z', long = "parallel", requires = "more_urls")]
+    pub parallel: bool,
+
+    /// Take `[]` and `{}` in URLs literally instead of as globs.
+    #[arg(short = 'g', long = "globoff")]
+    pub globoff: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
 
     /// HTTP method (GET, POST, PUT, DELETE, PATCH, HEAD).
     ///
@@ -39,149 +101,2311 @@ pub struct Cli {
     #[arg(short = 'X', long, default_value = "GET")]
     pub method: String,
 
+    /// Send a HEAD request and print only the status and headers, like `curl -I`.
+    #[arg(short = 'I', long = "head", conflicts_with_all = ["method", "get", "data", "body_file", "graphql"])]
+    pub head: bool,
+
+    /// Send `-d` data as query parameters of a GET request, like `curl -G`.
+    ///
+    /// The data is appended to the URL's query string as given, after a
+    /// `&` if it already has one; `@file` and `@-` work as usual.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/search --get -d 'q=rust&page=2'
+    /// ```
+    #[arg(short = 'G', long = "get", conflicts_with_all = ["method", "body_file", "graphql"])]
+    pub get: bool,
+
+    /// Send HTTP methods that are not standard or listed as known.
+    ///
+    /// Without it, an unknown method (in `-X` or a dataset) is refused, so
+    /// typos like `GTE` are caught before reaching the server. With it, they
+    /// are sent with a warning.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://dav.example.com/files -X PROPFIND --allow-custom-methods
+    /// ```
+    #[arg(long = "allow-custom-methods")]
+    pub allow_custom_methods: bool,
+
+    /// Non-standard methods to accept as known (comma-separated).
+    ///
+    /// Also read from `custom_methods` in the config file.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://cdn.example.com/asset.js -X PURGE --custom-method PURGE
+    /// ```
+    #[arg(long = "custom-method", value_delimiter = ',')]
+    pub custom_methods: Vec<String>,
+
     /// Request headers (can be used multiple times).
     ///
     /// Format: "Header-Name: Header-Value"
     ///
+    /// `${ENV_VAR}` references are expanded from the environment (here and
+    /// in the URL, `-d` data, and the paths, header values and bodies of
+    /// dataset entries); write `$${...}` for a literal `${...}`.
+    ///
     /// # Example
     /// ```bash
     /// hurley https://api.example.com -H "Content-Type: application/json" -H "Authorization: Bearer token"
+    /// hurley https://api.example.com -H 'Authorization: Bearer ${API_TOKEN}'
     /// ```
     #[arg(short = 'H', long = "header")]
     pub headers: Vec<String>,
 
+    /// `User-Agent` to send instead of the default `hurley/<version>`.
+    ///
+    /// A `User-Agent` given with `-H` takes precedence.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://example.com -A 'Mozilla/5.0 (compatible; uptime-check)'
+    /// ```
+    #[arg(short = 'A', long = "user-agent", value_name = "AGENT")]
+    pub user_agent: Option<String>,
+
+    /// Send no `User-Agent` header at all.
+    #[arg(long = "no-user-agent", conflicts_with = "user_agent")]
+    pub no_user_agent: bool,
+
+    /// Send the ETag saved in FILE as `If-None-Match`, so an unchanged
+    /// resource is answered with `304 Not Modified`.
+    ///
+    /// Nothing is sent if the file doesn't exist yet. In a performance
+    /// test every request carries the validator, and 304s count as
+    /// successes and are reported separately.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/catalog --etag-save etag.txt
+    /// hurley https://api.example.com/catalog --etag-compare etag.txt -c 20 -n 5000
+    /// ```
+    #[arg(long = "etag-compare", value_name = "FILE")]
+    pub etag_compare: Option<PathBuf>,
+
+    /// Save the response's `ETag` to FILE, for `--etag-compare`.
+    #[arg(long = "etag-save", value_name = "FILE")]
+    pub etag_save: Option<PathBuf>,
+
+    /// Send `If-Modified-Since`: an HTTP date, an RFC 3339 timestamp, or a
+    /// file whose modification time is used.
+    #[arg(long = "if-modified-since", value_name = "DATE|FILE")]
+    pub if_modified_since: Option<String>,
+
+    /// Cache responses on disk, honoring `Cache-Control`, `ETag` and
+    /// `Last-Modified`.
+    ///
+    /// Fresh responses are answered from the cache and stale ones are
+    /// revalidated. Stored in DIR, or by default in `~/.cache/hurley`. With
+    /// a performance test, the report shows cache hits, revalidations and misses.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/catalog --cache
+    /// hurley https://api.example.com/catalog --cache=/tmp/hurley-cache -c 20 -n 5000
+    /// ```
+    #[arg(long = "cache", value_name = "DIR", num_args = 0..=1, require_equals = true)]
+    pub cache: Option<Option<PathBuf>>,
+
+    /// Ask for a representation: `json`, `xml`, `html`, `any` or a media type.
+    ///
+    /// Shorthand for an `Accept` header; an explicit `-H 'Accept: ...'`
+    /// wins. Several values are sent as one list, or tried one by one with
+    /// `--negotiate`.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/users/1 --accept xml
+    /// ```
+    #[arg(long = "accept", value_delimiter = ',')]
+    pub accept: Vec<AcceptValue>,
+
+    /// Request the URL once per `--accept` value (every preset by default)
+    /// and summarize which representations the server offers.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/users/1 --negotiate
+    /// hurley https://api.example.com/report --negotiate --accept json,text/csv
+    /// ```
+    #[arg(long = "negotiate", conflicts_with_all = ["raw_request", "keepalive", "plan"])]
+    pub negotiate: bool,
+
+    /// Print the cookies the response sets, with a security attribute audit.
+    ///
+    /// Shows name, domain, path, flags and expiry of each `Set-Cookie`,
+    /// warning about missing `Secure`, `HttpOnly` or `SameSite` and cookies
+    /// set twice. Also shown after the headers with `-i`.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://example.com/login -X POST -d @creds.json --cookies
+    /// ```
+    #[arg(long = "cookies")]
+    pub cookies: bool,
+
     /// Request body (inline data).
     ///
+    /// Prefix the value with `@` to read the body from a file, curl-style,
+    /// or use `@-` to read it from stdin. For file bodies, `Content-Type` is set from the file extension unless
+    /// given explicitly with `-H`.
+    ///
     /// # Example
     /// ```bash
     /// hurley -X POST https://api.example.com -d '{"name": "test"}'
+    /// hurley -X POST https://api.example.com -d @payload.json
+    /// cat payload.json | hurley -X POST https://api.example.com -d @-
     /// ```
     #[arg(short = 'd', long = "data")]
     pub data: Option<String>,
 
-    /// Read request body from file.
+    /// Read request body from file, or from stdin with `-`.
+    ///
+    /// `Content-Type` is set from the file extension unless given explicitly.
     ///
     /// # Example
     /// ```bash
     /// hurley -X POST https://api.example.com -f payload.json
+    /// cat payload.json | hurley -X POST https://api.example.com -f -
     /// ```
     #[arg(short = 'f', long = "file")]
     pub body_file: Option<PathBuf>,
 
+    /// Send a GraphQL operation built from `--gql-query` and `--gql-variables`.
+    ///
+    /// The query is wrapped in the standard JSON envelope and sent as
+    /// `POST` unless `-X` says otherwise. The response's `data` and
+    /// `errors` are printed separately.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/graphql --graphql \
+    ///   --gql-query 'query($id: ID!) { user(id: $id) { name } }' \
+    ///   --gql-variables '{"id": "42"}'
+    /// ```
+    #[arg(long = "graphql", requires = "gql_query", conflicts_with_all = ["data", "body_file"])]
+    pub graphql: bool,
+
+    /// GraphQL query document, inline or `@file`.
+    #[arg(long = "gql-query", requires = "graphql")]
+    pub gql_query: Option<String>,
+
+    /// GraphQL variables as a JSON object, inline or `@file`.
+    #[arg(long = "gql-variables", requires = "graphql")]
+    pub gql_variables: Option<String>,
+
+    /// Write the response body to a file instead of stdout.
+    ///
+    /// The body is streamed to disk as it arrives, so large and binary
+    /// downloads are saved byte-for-byte without being buffered in memory.
+    /// With several URLs, repeat `-o` to save each response in turn; URLs
+    /// past the last `-o` are printed.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://example.com/archive.tar.gz -o archive.tar.gz
+    /// ```
+    #[arg(short = 'o', long = "output-file")]
+    pub output_file: Vec<PathBuf>,
+
+    /// Print only the parts of a JSON response selected by a path.
+    ///
+    /// Accepts JSONPath (`$.data.items[0].id`) or the jq equivalent
+    /// (`.data.items[0].id`), with `[*]` wildcards and negative indices.
+    /// Strings are printed without quotes; other values as JSON.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/users --filter '$.data[*].email'
+    /// ```
+    #[arg(long = "filter", conflicts_with = "output_file")]
+    pub filter: Option<JsonPath>,
+
+    /// Render the response body as the given type, ignoring its Content-Type.
+    ///
+    /// By default the body is rendered by Content-Type on a terminal: JSON is
+    /// pretty-printed, XML indented, HTML reduced to text, CSV shown as a
+    /// table, images summarized, and multipart bodies split into their
+    /// parts. When stdout is not a terminal the body is printed exactly as
+    /// received unless this asks for a renderer. `raw` prints the body
+    /// unchanged.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://example.com --render raw
+    /// ```
+    #[arg(long = "render", value_parser = BUILTIN_RENDERERS, conflicts_with = "output_file")]
+    pub render: Option<String>,
+
+    /// Request only these bytes of the body (can be repeated).
+    ///
+    /// Takes `FIRST-LAST` (inclusive), `FIRST-` or `-LAST_N` and sends a
+    /// `Range` header. The response is checked to be a `206 Partial Content`
+    /// with a matching `Content-Range` and that many bytes, or a
+    /// `multipart/byteranges` body for several ranges; a warning is printed
+    /// if it isn't. In a performance test such responses fail with the
+    /// `range` error.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://cdn.example.com/video.mp4 --range 0-1023 -o head.bin
+    /// ```
+    #[arg(long = "range", value_name = "RANGE", allow_hyphen_values = true)]
+    pub ranges: Vec<ByteRange>,
+
+    /// Save each part of a multipart response to its own file in this directory.
+    ///
+    /// Parts are written as received to `part-1.json`, `part-2.bin`, and so
+    /// on, with the extension taken from the part's Content-Type. Works for
+    /// `multipart/mixed` batch responses and `multipart/byteranges` answers
+    /// to multi-range requests.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://example.com/video.mp4 --range 0-99 --range 1000-1099 --save-parts ranges/
+    /// ```
+    #[arg(long = "save-parts", conflicts_with = "output_file")]
+    pub save_parts: Option<PathBuf>,
+
+    /// Save a value from the response as a variable (can be used multiple times).
+    ///
+    /// Takes `name=path` using the `--filter` path syntax, where the path
+    /// must match exactly one value, or `name=header:NAME` for a response
+    /// header. The value is stored under the active profile and available
+    /// as `{{name}}` in later invocations. In performance tests, values are
+    /// recorded per request in the `--request-log` instead.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley -X POST https://api.example.com/login -d @creds.json --capture token=$.access_token
+    /// hurley https://api.example.com/me -H 'Authorization: Bearer {{token}}' --capture reqid=header:X-Request-Id
+    /// ```
+    #[arg(long = "capture", conflicts_with = "output_file")]
+    pub captures: Vec<Capture>,
+
+    /// Replay and extend a named session.
+    ///
+    /// The session's request (method, URL, headers and body) and `--auth`
+    /// fill in what isn't given on the command line, its cookies are sent
+    /// to the URLs they apply to, and cookies set by the response are
+    /// stored back into it. A NAME containing `/` is a file path.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/cart --session api
+    /// hurley --session api
+    /// ```
+    #[arg(long = "session", value_name = "NAME")]
+    pub session: Option<String>,
+
+    /// Save the request, its `--auth` and the cookies the response sets as
+    /// a named session, for `--session`.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley -X POST https://api.example.com/login -d @creds.json --save-session api
+    /// ```
+    #[arg(long = "save-session", value_name = "NAME")]
+    pub save_session: Option<String>,
+
+    /// Print a line built from the response after it.
+    ///
+    /// `%{http_code}`, `%{time_total}`, `%{size_download}`,
+    /// `%{content_type}`, `%{url}` and `%{header:NAME}` are replaced with
+    /// values from the response; `\n` and `\t` are unescaped.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/orders -w '%{http_code} %{header:x-request-id}\n'
+    /// ```
+    #[arg(short = 'w', long = "write-out")]
+    pub write_out: Option<WriteOut>,
+
+    /// Append a JSON line with each request's phase timings to this file.
+    ///
+    /// Records status, total time, DNS, connect, TLS, time to first byte,
+    /// transfer and body size. The file is appended to, so repeated runs
+    /// build up a timing history; works for single requests and
+    /// performance tests alike.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/health --timing-log timings.ndjson
+    /// ```
+    #[arg(long = "timing-log", conflicts_with_all = ["raw_request", "negotiate", "keepalive", "plan"])]
+    pub timing_log: Option<PathBuf>,
+
+    /// Template variable (can be used multiple times).
+    ///
+    /// Replaces `{{name}}` placeholders in the URL, headers, and body.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley 'https://api.example.com/users/{{id}}' --var id=42
+    /// ```
+    #[arg(long = "var", value_parser = parse_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// Response-time budget for single requests (e.g. `300ms`, `2s`).
+    ///
+    /// The timing line is shown and colored yellow when the response is
+    /// slower than this, and red when it is more than twice as slow.
+    #[arg(long = "slow-threshold", value_parser = parse_duration)]
+    pub slow_threshold: Option<Duration>,
+
+    /// Exit with code 3 when the response exceeds `--slow-threshold`.
+    #[arg(long = "fail-slow", requires = "slow_threshold")]
+    pub fail_slow: bool,
+
+    /// Print the fully resolved request as a curl command instead of sending it.
+    ///
+    /// `--auth` basic, bearer and sigv4 become curl's own options; OAuth2
+    /// and script credentials are fetched and printed as headers.
+    #[arg(long = "as-curl")]
+    pub as_curl: bool,
+
+    /// Ask for a compressed response and decompress it before display.
+    ///
+    /// Sends `Accept-Encoding: gzip, br, deflate, zstd` and undoes the
+    /// `Content-Encoding` of the response, also when saving with `-o`.
+    /// With `-v` the sizes before and after are shown. In a performance
+    /// test the bytes received are counted as they arrive, compressed.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://example.com/app.js --compressed -v
+    /// ```
+    #[arg(long = "compressed")]
+    pub compressed: bool,
+
+    /// Gzip the request body and send it with `Content-Encoding: gzip`.
+    ///
+    /// For APIs that accept compressed uploads. The body is compressed
+    /// once, so a performance test sends the same compressed bytes with
+    /// every request and reports them as bytes sent.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley -X POST https://api.example.com/events -d @events.json --compress-body
+    /// ```
+    #[arg(long = "compress-body")]
+    pub compress_body: bool,
+
     /// Include response headers in output.
     #[arg(short = 'i', long = "include")]
     pub include_headers: bool,
 
-    /// Follow HTTP redirects (up to 10 redirects).
+    /// Follow HTTP redirects (up to 10, see `--max-redirs`).
     #[arg(short = 'L', long = "location")]
     pub follow_redirects: bool,
 
+    /// Most redirects to follow with `-L`: `0` fails on any redirect, `-1`
+    /// follows any number.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley -L --max-redirs 3 https://example.com/old-path
+    /// ```
+    #[arg(long = "max-redirs", allow_hyphen_values = true, value_parser = clap::value_parser!(i64).range(-1..))]
+    pub max_redirs: Option<i64>,
+
+    /// Refuse redirects that downgrade HTTPS to HTTP or send credential
+    /// headers to another origin.
+    ///
+    /// Without this flag such redirects are followed with a warning.
+    #[arg(long = "strict-redirects")]
+    pub strict_redirects: bool,
+
     /// Verbose output showing request details.
     #[arg(short = 'v', long = "verbose")]
     pub verbose: bool,
 
-    /// Request timeout in seconds.
-    #[arg(long, default_value = "30")]
+    /// Write the bytes sent and received on each connection to this file.
+    ///
+    /// Each chunk is framed with its direction, connection id and size;
+    /// non-printable bytes are escaped. Bodies are left out unless
+    /// `--trace-wire-bodies` is given. For HTTPS, the decrypted bytes are shown.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/users --trace-wire wire.log
+    /// ```
+    #[arg(long = "trace-wire")]
+    pub trace_wire: Option<PathBuf>,
+
+    /// Include request and response bodies in the `--trace-wire` file.
+    #[arg(long = "trace-wire-bodies", requires = "trace_wire")]
+    pub trace_wire_bodies: bool,
+
+    /// Send the bytes of this file as-is instead of building a request.
+    ///
+    /// The URL only selects the host, port and TLS; nothing is added to or
+    /// normalized in the file, so malformed framing and line endings reach
+    /// the server unchanged. The raw response is printed to stdout.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com --raw-request smuggle.http
+    /// ```
+    #[arg(long = "raw-request", conflicts_with_all = ["data", "body_file", "graphql", "keepalive"])]
+    pub raw_request: Option<PathBuf>,
+
+    /// Keep at most this many bytes of a `--raw-request` response.
+    ///
+    /// Accepts a `K`, `M` or `G` suffix. Reading stops at the limit and a
+    /// warning reports the truncation.
+    #[arg(long = "raw-max-bytes", value_parser = parse_length, default_value = "16M", requires = "raw_request")]
+    pub raw_max_bytes: u64,
+
+    /// Maximum time for each request in seconds, from connecting to
+    /// reading the last byte of the body (`-m`/`--max-time` as in curl).
+    ///
+    /// In performance mode with `--request-timeout`, this instead bounds
+    /// the duration of the whole run.
+    #[arg(short = 'm', long, visible_alias = "max-time", default_value = "30")]
     pub timeout: u64,
 
-    /// Run performance test with dataset file (JSON format).
+    /// Maximum time to open a connection, DNS lookup and TLS handshake
+    /// included (e.g. `2s`, `500ms`).
     ///
-    /// The dataset should be a JSON array of request objects:
-    /// ```json
-    /// [
-    ///   {"method": "GET", "path": "/users"},
-    ///   {"method": "POST", "path": "/users", "body": {"name": "test"}}
-    /// ]
+    /// Tells a slow or unreachable server apart from a slow response: a
+    /// connect timeout fails fast while the response keeps the full
+    /// `--timeout`.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/report --connect-timeout 2s --max-time 120
     /// ```
-    #[arg(long = "perf")]
-    pub perf_file: Option<PathBuf>,
+    #[arg(long = "connect-timeout", value_parser = parse_duration)]
+    pub connect_timeout: Option<Duration>,
 
-    /// Number of concurrent connections for performance test.
-    #[arg(short = 'c', long = "concurrency", default_value = "1")]
-    pub concurrency: usize,
+    /// Fail a request whose response body receives no data for this long
+    /// (e.g. `10s`), however long the whole transfer takes.
+    #[arg(long = "read-timeout", value_parser = parse_duration)]
+    pub read_timeout: Option<Duration>,
 
-    /// Total number of requests for performance test.
-    #[arg(short = 'n', long = "requests", default_value = "1")]
-    pub total_requests: usize,
+    /// Per-request timeout in seconds for performance tests.
+    ///
+    /// When set, `--timeout` limits the total run time instead, so a slow
+    /// request and a long run can be bounded independently.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com -c 10 -n 100000 --request-timeout 2 --timeout 600
+    /// ```
+    #[arg(long = "request-timeout")]
+    pub request_timeout: Option<u64>,
 
-    /// Output format for performance results (text, json).
-    #[arg(long = "output", default_value = "text")]
-    pub output_format: String,
-}
+    /// Close pooled connections after they have been idle this many seconds.
+    ///
+    /// Set this below the idle timeout of any load balancer in front of the
+    /// target to avoid reusing connections it has already dropped.
+    #[arg(long = "pool-idle-timeout")]
+    pub pool_idle_timeout: Option<u64>,
 
-impl Cli {
-    /// Returns true if the CLI arguments indicate performance test mode.
+    /// Retry a request once if its connection was reset by the peer.
     ///
-    /// Performance mode is activated when:
-    /// - A performance dataset file is specified (`--perf`)
-    /// - Total requests is greater than 1 (`-n`)
-    /// - Concurrency is greater than 1 (`-c`)
-    pub fn is_perf_mode(&self) -> bool {
-        self.perf_file.is_some() || self.total_requests > 1 || self.concurrency > 1
-    }
-}
+    /// Only idempotent requests (GET, HEAD, OPTIONS, TRACE, PUT, DELETE) are
+    /// retried; a POST or PATCH may already have been applied by the server.
+    #[arg(long = "retry-on-connection-reset")]
+    pub retry_on_connection_reset: bool,
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Open a new connection for every performance test request.
+    ///
+    /// By default all requests of a run share one connection pool, so
+    /// TCP and TLS handshakes are paid once per connection. This measures
+    /// the cost of connection setup instead.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com -c 10 -n 1000 --disable-keepalive
+    /// ```
+    #[arg(long = "disable-keepalive")]
+    pub disable_keepalive: bool,
 
-    #[test]
-    fn test_default_values() {
-        let cli = Cli::parse_from(["hurley", "https://example.com"]);
-        assert_eq!(cli.url, "https://example.com");
-        assert_eq!(cli.method, "GET");
-        assert_eq!(cli.timeout, 30);
-        assert_eq!(cli.concurrency, 1);
-        assert_eq!(cli.total_requests, 1);
-        assert!(!cli.is_perf_mode());
-    }
+    /// Bind connections to this local source address.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com -c 50 -n 100000 --local-addr 10.0.0.21
+    /// ```
+    #[arg(long = "local-addr", conflicts_with = "interface")]
+    pub local_addr: Option<IpAddr>,
 
-    #[test]
-    fn test_post_with_data() {
-        let cli = Cli::parse_from([
-            "hurley",
-            "-X", "POST",
-            "https://example.com",
-            "-d", r#"{"key": "value"}"#,
-        ]);
-        assert_eq!(cli.method, "POST");
-        assert_eq!(cli.data, Some(r#"{"key": "value"}"#.to_string()));
-    }
+    /// Bind connections to the address of a network interface (a name such
+    /// as `eth1`, or an IP address).
+    #[arg(long = "interface")]
+    pub interface: Option<String>,
 
-    #[test]
-    fn test_headers() {
+    /// Don't set `TCP_NODELAY`, letting small writes be coalesced (Nagle's
+    /// algorithm).
+    #[arg(long = "no-tcp-nodelay")]
+    pub no_tcp_nodelay: bool,
+
+    /// Send TCP keepalive probes after a connection has been idle this
+    /// long (e.g. `30s`).
+    #[arg(long = "tcp-keepalive", value_parser = parse_duration)]
+    pub tcp_keepalive: Option<Duration>,
+
+    /// Send requests for a host to a specific address (`host:port:address`).
+    ///
+    /// Can be given multiple times. Mapping the same host to several
+    /// addresses in a performance test runs the workload against each
+    /// backend in turn and prints a latency comparison.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -n 200 \
+    ///   --resolve api.example.com:443:10.0.0.5 \
+    ///   --resolve api.example.com:443:10.1.0.5
+    /// ```
+    #[arg(long = "resolve")]
+    pub resolve: Vec<ResolveEntry>,
+
+    /// Read `--resolve` entries from a file, one per line.
+    #[arg(long = "resolve-file")]
+    pub resolve_file: Option<PathBuf>,
+
+    /// Look up host names with these DNS servers instead of the system
+    /// resolver (`address[:port]`, comma-separated or repeated).
+    ///
+    /// Lookups aren't cached, so every new connection's DNS time, shown
+    /// in verbose output and performance reports, is that of the server.
+    /// `--resolve` overrides still take precedence.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -v --dns-server 1.1.1.1,8.8.8.8
+    /// ```
+    #[arg(long = "dns-server", value_delimiter = ',', conflicts_with = "doh_url")]
+    pub dns_servers: Vec<DnsServer>,
+
+    /// Look up host names over DNS-over-HTTPS with this endpoint.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -n 500 --doh-url https://cloudflare-dns.com/dns-query --disable-keepalive
+    /// ```
+    #[arg(long = "doh-url")]
+    pub doh_url: Option<String>,
+
+    /// Client certificate (PEM) for mutual TLS.
+    ///
+    /// The file holds the certificate chain and, unless `--key` is given,
+    /// the PKCS#8 private key.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://internal.example.com --cert client.pem --key client.key
+    /// ```
+    #[arg(long = "cert")]
+    pub cert: Option<PathBuf>,
+
+    /// Private key (PKCS#8 PEM) for `--cert`.
+    #[arg(long = "key", requires = "cert")]
+    pub key: Option<PathBuf>,
+
+    /// Re-read `--cert` and `--key` at this interval during a run.
+    ///
+    /// Lets soak tests outlive short-lived certificates renewed on disk;
+    /// connections opened after a reload use the new certificate. On Unix,
+    /// `SIGHUP` also triggers a reload.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://internal.example.com -z 24h -c 20 --cert client.pem --cert-reload 1h
+    /// ```
+    #[arg(long = "cert-reload", value_parser = parse_duration, requires = "cert")]
+    pub cert_reload: Option<Duration>,
+
+    /// Authenticate requests with a provider (`KIND:...`).
+    ///
+    /// Credentials are resolved before every send and cached between
+    /// them; on a `401` they are refreshed and the request is retried once.
+    /// Can be given multiple times. Kinds: `basic:USER:PASSWORD`,
+    /// `bearer:TOKEN`,
+    /// `oauth2:token_url=URL,client_id=ID,client_secret=SECRET[,scope=SCOPE]`,
+    /// `sigv4:region=REGION,service=SERVICE` (keys from `AWS_ACCESS_KEY_ID`
+    /// and `AWS_SECRET_ACCESS_KEY`), and `script:COMMAND`, whose output is
+    /// `Name: value` header lines or a bare bearer token.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com -n 1000 \
+    ///   --auth 'oauth2:token_url=https://idp.example.com/token,client_id=load,client_secret=${SECRET}'
+    /// ```
+    #[arg(long = "auth", conflicts_with_all = ["raw_request", "keepalive"])]
+    pub auth: Vec<String>,
+
+    /// Run performance test with dataset file (JSON format).
+    ///
+    /// The dataset should be a JSON array of request objects:
+    /// ```json
+    /// [
+    ///   {"method": "GET", "path": "/users"},
+    ///   {"method": "POST", "path": "/users", "body": {"name": "test"}}
+    /// ]
+    /// ```
+    #[arg(long = "perf", group = "dataset_source")]
+    pub perf_file: Option<PathBuf>,
+
+    /// Run performance test with a dataset generated from an OpenAPI 3 spec.
+    ///
+    /// One request is synthesized per operation, using the spec's examples,
+    /// defaults, or schemas for parameters and bodies. JSON and YAML specs
+    /// are supported.
+    #[arg(long = "openapi", conflicts_with = "perf_file", group = "dataset_source")]
+    pub openapi_file: Option<PathBuf>,
+
+    /// Run only a random subset of the dataset.
+    ///
+    /// Accepts an entry count (`1000`) or a percentage (`10%`). Requires
+    /// `--perf` or `--openapi`.
+    #[arg(long = "sample", requires = "dataset_source")]
+    pub sample: Option<Sample>,
+
+    /// Seed for `--sample`, to reproduce the same subset across runs.
+    ///
+    /// A random seed is chosen and printed if not given.
+    #[arg(long = "seed", requires = "sample")]
+    pub seed: Option<u64>,
+
+    /// Number of concurrent connections for performance test.
+    ///
+    /// With `--iterations`, the number of virtual users (`--vus`).
+    #[arg(short = 'c', long = "concurrency", visible_alias = "vus", default_value = "1")]
+    pub concurrency: usize,
+
+    /// Run the dataset as a scenario this many times per virtual user.
+    ///
+    /// Each of the `--vus` virtual users sends the dataset entries in
+    /// order, one at a time, and starts over until it has done so this
+    /// many times. Replaces `-n`; the time per iteration is reported
+    /// alongside the request latencies.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com --perf checkout.json --vus 50 --iterations 20
+    /// ```
+    #[arg(
+        long = "iterations",
+        value_parser = parse_iterations,
+        conflicts_with_all = ["total_requests", "duration", "rate", "ramp_up", "stages", "workers"]
+    )]
+    pub iterations: Option<usize>,
+
+    /// Total number of requests for performance test.
+    #[arg(short = 'n', long = "requests", default_value = "1")]
+    pub total_requests: usize,
+
+    /// Start requests at a fixed rate (requests per second).
+    ///
+    /// Requests start on schedule no matter how slowly the server responds
+    /// (an open model), instead of each of `-c` workers waiting for its
+    /// previous response. `-c` is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com --rate 200 --duration 1m
+    /// ```
+    #[arg(long = "rate", value_parser = parse_rate)]
+    pub rate: Option<f64>,
+
+    /// Run the performance test for a fixed time instead of `-n` requests.
+    ///
+    /// Requests keep cycling through the dataset until the duration (e.g.
+    /// `30s`, `5m`) elapses.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -c 20 --duration 30s
+    /// ```
+    #[arg(short = 'z', long = "duration", value_parser = parse_duration, conflicts_with = "total_requests")]
+    pub duration: Option<Duration>,
+
+    /// Warm up for this long before measuring (e.g. `10s`).
+    ///
+    /// Warmup requests are sent at the same concurrency or rate but left out
+    /// of the results, so connection setup and cold caches don't skew them.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -c 20 -n 5000 --warmup 10s
+    /// ```
+    #[arg(long = "warmup", value_parser = parse_duration, conflicts_with = "warmup_requests")]
+    pub warmup: Option<Duration>,
+
+    /// Wait until this local time (`HH:MM` or `HH:MM:SS`) before starting.
+    ///
+    /// A time that has already passed today means tomorrow. The remaining
+    /// wait is shown and Ctrl+C cancels it; reports still go to `--output`
+    /// and `--report-file` once the run finishes.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -c 50 -z 10m --start-at 02:00 --report-file night.html
+    /// ```
+    #[arg(long = "start-at", value_parser = parse_time_of_day, conflicts_with = "start_in")]
+    pub start_at: Option<NaiveTime>,
+
+    /// Wait this long before starting (e.g. `30m`).
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -c 50 -z 10m --start-in 30m
+    /// ```
+    #[arg(long = "start-in", value_parser = parse_duration)]
+    pub start_in: Option<Duration>,
+
+    /// Window length of the latency-over-time timeline (default: 1s).
+    ///
+    /// Requests per second and p50/p95/p99 are reported per window in the
+    /// JSON report and as sparklines in the text report.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -c 20 -z 10m --timeline-window 10s
+    /// ```
+    #[arg(long = "timeline-window", value_parser = parse_duration, default_value = "1s")]
+    pub timeline_window: Duration,
+
+    /// Send this many unmeasured warmup requests before the measured ones.
+    #[arg(long = "warmup-requests", default_value = "0")]
+    pub warmup_requests: usize,
+
+    /// Ramp the load up linearly from zero over this time, then hold it.
+    ///
+    /// Ramps concurrency up to `-c`, or the arrival rate up to `--rate`.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -c 100 --duration 5m --ramp-up 30s
+    /// ```
+    #[arg(long = "ramp-up", value_parser = parse_duration, conflicts_with = "stages")]
+    pub ramp_up: Option<Duration>,
+
+    /// Vary the load over time in `duration:target` stages.
+    ///
+    /// Each stage ramps linearly from the previous target (0 for the first)
+    /// to its own. Targets are concurrency levels, or requests per second
+    /// with a `/s` suffix. The run ends with the last stage, and results
+    /// are broken down per stage.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com --stages 1m:50,2m:200,1m:0
+    /// hurley https://api.example.com --stages 30s:100/s,5m:100/s
+    /// ```
+    #[arg(
+        long = "stages",
+        conflicts_with_all = ["duration", "total_requests", "rate", "concurrency"]
+    )]
+    pub stages: Option<LoadProfile>,
+
+    /// Write one NDJSON line per performance test request to this file.
+    ///
+    /// A path ending in `.gz` is gzip-compressed while writing.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -n 1000000 -c 50 --request-log requests.ndjson.gz
+    /// ```
+    #[arg(long = "request-log")]
+    pub request_log: Option<PathBuf>,
+
+    /// Write one CSV row per performance test request to this file.
+    ///
+    /// Columns: seq, timestamp_ms, label, method, path, status, duration_ms,
+    /// bytes, success, error. A path ending in `.gz` is gzip-compressed.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -n 1000 -c 10 --results-csv results.csv
+    /// ```
+    #[arg(long = "results-csv")]
+    pub results_csv: Option<PathBuf>,
+
+    /// Send live request metrics to a StatsD server at `HOST:PORT` during the run.
+    ///
+    /// Emits `<prefix>.request.duration` timings, `request.count` and
+    /// `request.errors` counters, and an `rps` gauge over UDP.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -z 5m -c 20 --statsd localhost:8125 --statsd-tag env:staging
+    /// ```
+    #[arg(long = "statsd")]
+    pub statsd: Option<String>,
+
+    /// Prefix of the StatsD metric names.
+    #[arg(long = "statsd-prefix", default_value = "hurley", requires = "statsd")]
+    pub statsd_prefix: String,
+
+    /// Tag StatsD metrics with endpoint, status and error (DogStatsD format).
+    #[arg(long = "dogstatsd", requires = "statsd")]
+    pub dogstatsd: bool,
+
+    /// Extra DogStatsD `key:value` tag on every metric (can be used multiple times).
+    ///
+    /// Implies `--dogstatsd`.
+    #[arg(long = "statsd-tag", value_delimiter = ',', requires = "statsd")]
+    pub statsd_tags: Vec<String>,
+
+    /// Include every request as a `records` array in JSON reports.
+    ///
+    /// Records are spooled to a temporary file during the run and streamed
+    /// into the report, so large runs don't need to fit in memory.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -n 1000000 -c 50 --report-records --output json=report.json
+    /// ```
+    #[arg(long = "report-records")]
+    pub report_records: bool,
+
+    /// Fail the performance test unless a latency or error-rate limit is met.
+    ///
+    /// Can be given multiple times. Prefix with an endpoint label or dataset
+    /// tag to scope it. A failed threshold exits with code 3.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com --perf data.json -n 1000 \
+    ///   --threshold 'p99 < 500ms' --threshold 'checkout p99 < 400ms' \
+    ///   --threshold 'error_rate < 1%'
+    /// ```
+    #[arg(long = "threshold")]
+    pub thresholds: Vec<Threshold>,
+
+    /// Check a latency or error-rate objective in fixed windows of the run.
+    ///
+    /// The report shows how many `--slo-window` windows met the objective
+    /// and, with `--slo-target`, the error-budget burn rate. Meant for long
+    /// `--duration` soak tests; the run's exit code is not affected.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -c 20 -z 2h \
+    ///   --slo 'p99 < 300ms' --slo-window 5m --slo-target 99
+    /// ```
+    #[arg(long = "slo")]
+    pub slo: Option<Threshold>,
+
+    /// Window length for `--slo` (default: 5m).
+    #[arg(long = "slo-window", value_parser = parse_duration, default_value = "5m", requires = "slo")]
+    pub slo_window: Duration,
+
+    /// Percentage of `--slo` windows that must meet the objective.
+    #[arg(long = "slo-target", value_parser = parse_percent, requires = "slo")]
+    pub slo_target: Option<f64>,
+
+    /// Check every response of a performance test (can be repeated).
+    ///
+    /// Checks the status (`status == 200`, `status < 400`), the body
+    /// (`body contains "id"`), or a JSONPath (`$.ok == true`,
+    /// `$.items[0] exists`). Pass/fail counts and example failures are
+    /// shown in a Validation section of the report.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com/orders -n 500 -c 20 \
+    ///   --assert 'status == 200' --assert '$.status == "ok"'
+    /// ```
+    #[arg(long = "assert")]
+    pub assertions: Vec<Assertion>,
+
+    /// Count distinct responses in a performance test, overall and per endpoint.
+    ///
+    /// Each status and body is hashed; a run that only ever sees one
+    /// distinct response is likely exercising a cached or error page.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com --perf data.json -n 1000 --hash-responses
+    /// ```
+    #[arg(long = "hash-responses")]
+    pub hash_responses: bool,
+
+    /// Compute exact latency percentiles for runs of up to this many requests.
+    ///
+    /// Longer runs use an HdrHistogram, accurate to 3 significant digits;
+    /// the report says which was used. 0 always uses the histogram.
+    #[arg(long = "exact-samples", default_value_t = DEFAULT_EXACT_SAMPLES)]
+    pub exact_samples: usize,
+
+    /// Read LENGTH bytes at a random offset of the object per request.
+    ///
+    /// For benchmarking how a CDN or object store serves ranges across a
+    /// large object. The object's size is taken from a one-byte range
+    /// request before the run; every response is checked as with `--range`.
+    /// Takes a byte count with an optional K, M or G suffix.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://cdn.example.com/dataset.parquet -c 32 -z 2m --random-range 256K
+    /// ```
+    #[arg(
+        long = "random-range",
+        value_name = "LENGTH",
+        value_parser = parse_length,
+        conflicts_with_all = ["ranges", "perf_file", "openapi_file", "workers"]
+    )]
+    pub random_range: Option<u64>,
+
+    /// Dataset tags whose requests go first when every connection is busy.
+    ///
+    /// The mix of requests is unchanged, only their order, and the report
+    /// compares priority and background traffic with the rate each tag
+    /// achieved.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com --perf mixed.json -c 20 -z 5m --priority checkout,login
+    /// ```
+    #[arg(long = "priority", value_delimiter = ',', conflicts_with_all = ["iterations", "workers"])]
+    pub priority: Vec<String>,
+
+    /// Most background requests `--priority` tags may hold back at once.
+    ///
+    /// Lower values are fairer to background traffic; 0 only splits the report.
+    #[arg(long = "max-deferred", default_value_t = DEFAULT_MAX_DEFERRED, requires = "priority")]
+    pub max_deferred: usize,
+
+    /// Print the performance test's execution plan as JSON and exit.
+    ///
+    /// Shows the total and per-entry request counts, the load schedule and
+    /// the expected duration, without sending any request.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com --perf data.json -z 10m --rate 200 --plan
+    /// ```
+    #[arg(long = "plan", conflicts_with_all = ["keepalive", "raw_request"])]
+    pub plan: bool,
+
+    /// Distribute the performance test across `hurley worker` processes.
+    ///
+    /// Requests, concurrency and rate are split between the workers, which
+    /// stream their results back to be merged into one report. Per-request
+    /// outputs, timing logs, stages, client certificates, local addresses,
+    /// DNS servers and the response cache are not supported.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com --perf data.json -z 5m --rate 3000 --workers load1:7878,load2:7878,load3:7878
+    /// ```
+    #[arg(
+        long = "workers",
+        value_delimiter = ',',
+        conflicts_with_all = [
+            "stages", "ramp_up", "request_log", "results_csv", "report_records", "statsd", "keepalive", "cert", "auth",
+            "local_addr", "interface", "dns_servers", "doh_url", "cache", "timing_log",
+        ]
+    )]
+    pub workers: Vec<String>,
+
+    /// Hosts that performance tests are allowed to target.
+    ///
+    /// When set, a performance test refuses to start against any other host.
+    /// Patterns like `*.staging.example.com` match subdomains. Can be given
+    /// multiple times, comma-separated, or via `HURLEY_ALLOWED_HOSTS`.
+    #[arg(long = "allowed-host", env = "HURLEY_ALLOWED_HOSTS", value_delimiter = ',')]
+    pub allowed_hosts: Vec<String>,
+
+    /// Hosts that are test environments (comma-separated, or via
+    /// `HURLEY_TEST_HOSTS`).
+    ///
+    /// A performance test that would send more than a handful of `DELETE`,
+    /// `PUT` or `PATCH` requests to any other host asks for confirmation
+    /// first. Also read from `test_hosts` in the config file.
+    #[arg(long = "test-host", env = "HURLEY_TEST_HOSTS", value_delimiter = ',')]
+    pub test_hosts: Vec<String>,
+
+    /// Don't ask before a destructive performance test against a host that
+    /// is not a test environment.
+    ///
+    /// Without a terminal to ask on, such runs are refused unless this is
+    /// given.
+    ///
+    /// # Example
+    /// ```bash
+    /// hurley https://api.example.com --perf deletes.json -n 500 --yes
+    /// ```
+    #[arg(short = 'y', long = "yes")]
+    pub yes: bool,
+
+    /// Run the performance test even if the host is not on the allowlist.
+    #[arg(long = "force")]
+    pub force: bool,
+
+    /// Skip the preflight canary request.
+    ///
+    /// Without it, the first dataset entry with a safe method (GET, HEAD,
+    /// OPTIONS, TRACE) is sent once before the run, which aborts early on a
+    /// DNS, connect or TLS failure, a timeout, or a 401/403/407 response.
+    /// Datasets of only unsafe requests get no canary.
+    #[arg(long = "no-preflight")]
+    pub no_preflight: bool,
+
+    /// Error output format (text, json).
+    ///
+    /// With `json`, failures are written to stderr as a single JSON object
+    /// with `kind`, `message`, `os_error_code` and `target` fields.
+    #[arg(long = "errors", default_value = "text")]
+    pub error_format: String,
+
+    /// Non-interactive mode: no prompts, progress bars, or colors.
+    ///
+    /// Enabled automatically when stdin or stdout is not a terminal, e.g.
+    /// when run from cron, CI, or another program.
+    #[arg(long = "batch")]
+    pub batch: bool,
+
+    /// Config file to load defaults from.
+    ///
+    /// Defaults to `~/.config/hurley/config.toml` if it exists.
+    #[arg(long = "config")]
+    pub config: Option<PathBuf>,
+
+    /// Named profile from the config file to apply.
+    #[arg(long = "profile", env = "HURLEY_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Keep-alive benchmark: hold this many idle connections and probe them.
+    ///
+    /// Connection `k` idles for `k * --probe-interval` before being probed,
+    /// showing how long the server or load balancer keeps idle connections
+    /// alive and whether they can be reused.
+    #[arg(long = "keepalive")]
+    pub keepalive: Option<usize>,
+
+    /// Idle time step between keep-alive benchmark connections (e.g. `5s`).
+    #[arg(long = "probe-interval", default_value = "5s", value_parser = parse_duration)]
+    pub probe_interval: Duration,
+
+    /// Report format for performance results (can be used multiple times).
+    ///
+    /// Takes `FORMAT` or `FORMAT=FILE`, where FORMAT is text, json, html,
+    /// or junit. Without a file the report goes to stdout; only one report
+    /// can. Keep-alive and backend comparison reports use the first format.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -n 1000 --output text --output json=report.json --output junit=results.xml
+    /// ```
+    #[arg(long = "output", default_value = "text")]
+    pub outputs: Vec<OutputTarget>,
+
+    /// File to write the report to, for an `--output` given without one.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -n 1000 -c 10 --output html --report-file report.html
+    /// ```
+    #[arg(long = "report-file")]
+    pub report_file: Option<PathBuf>,
+}
+
+/// Subcommands.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Send the request described by a template file.
+    ///
+    /// The file holds YAML frontmatter (method, url, headers, vars) followed
+    /// by the raw body. Options given before `send` override the file.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley --var id=7 send create-user.hurl
+    /// ```
+    Send {
+        /// Request template file
+        file: PathBuf,
+    },
+
+    /// Run the performance workload at several concurrency levels.
+    ///
+    /// Options given before `sweep` (URL, `-n`, `--duration`, dataset, ...)
+    /// describe the workload, which runs once per level. Prints RPS and
+    /// latency percentiles against concurrency.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley https://api.example.com -n 2000 sweep --concurrency 1,2,4,8,16,32 --csv sweep.csv
+    /// ```
+    Sweep {
+        /// Concurrency levels to run, comma-separated
+        #[arg(long = "concurrency", value_delimiter = ',', required = true, value_parser = parse_concurrency)]
+        levels: Vec<usize>,
+
+        /// Also write the results as CSV to this file
+        #[arg(long = "csv")]
+        csv: Option<PathBuf>,
+    },
+
+    /// Wait for a controller and run its share of a distributed performance test.
+    ///
+    /// Serves one run at a time until interrupted. The protocol has no
+    /// authentication, so only listen on a trusted network.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley worker --listen 0.0.0.0:7878
+    /// ```
+    Worker {
+        /// Address to listen on for controllers
+        #[arg(long = "listen")]
+        listen: String,
+    },
+
+    /// Print the man page, or write a page per command into a directory.
+    ///
+    /// The main page documents every option, including the performance
+    /// test options, the dataset format, config files and exit codes.
+    ///
+    /// # Example
+    ///
+    /// ```bash
+    /// hurley man | man -l -
+    /// hurley man --output-dir target/man
+    /// ```
+    Man {
+        /// Write `hurley.1` and the subcommand pages into this directory
+        #[arg(short = 'o', long = "output-dir", value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+}
+
+/// Parses a positive concurrency level.
+fn parse_concurrency(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(level) if level > 0 => Ok(level),
+        _ => Err(format!("expected a positive concurrency level, got '{}'", s)),
+    }
+}
+
+/// Parses a positive iteration count.
+fn parse_iterations(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("expected a positive number of iterations, got '{}'", s)),
+    }
+}
+
+/// Parses a positive requests-per-second rate.
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!("expected a positive number of requests per second, got '{}'", s)),
+    }
+}
+
+/// Parses a percentage between 0 and 100, with or without a `%` sign.
+fn parse_percent(s: &str) -> Result<f64, String> {
+    match s.trim().trim_end_matches('%').parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err(format!("expected a percentage between 0 and 100, got '{}'", s)),
+    }
+}
+
+impl Cli {
+    /// Returns true if the CLI arguments indicate performance test mode.
+    ///
+    /// Performance mode is activated when:
+    /// - A performance dataset file is specified (`--perf`)
+    /// - An OpenAPI spec is specified (`--openapi`)
+    /// - Total requests is greater than 1 (`-n`)
+    /// - A run duration, arrival rate or load profile is given (`--duration`,
+    ///   `--rate`, `--ramp-up`, `--stages`)
+    /// - Concurrency is greater than 1 (`-c`)
+    /// - Virtual user iterations are given (`--iterations`)
+    pub fn is_perf_mode(&self) -> bool {
+        self.perf_file.is_some()
+            || self.iterations.is_some()
+            || self.openapi_file.is_some()
+            || self.duration.is_some()
+            || self.rate.is_some()
+            || self.ramp_up.is_some()
+            || self.stages.is_some()
+            || self.total_requests > 1
+            || self.concurrency > 1
+            || !self.workers.is_empty()
+    }
+
+    /// Returns how long a performance run lasts, if it is time-based.
+    ///
+    /// Set by `--duration` or by the end of the last `--stages` stage;
+    /// otherwise the run lasts for `-n` requests.
+    pub fn run_length(&self) -> Option<Duration> {
+        self.duration.or_else(|| self.stages.as_ref().and_then(LoadProfile::end))
+    }
+
+    /// Returns how long to wait before a performance run, from now.
+    ///
+    /// Set by `--start-at` or `--start-in`.
+    pub fn start_delay(&self) -> Option<Duration> {
+        self.start_at.map(delay_until_local).or(self.start_in)
+    }
+
+    /// Returns the `--output` targets, with `--report-file` as the file of
+    /// the first one that has none.
+    pub fn output_targets(&self) -> Vec<OutputTarget> {
+        let mut targets = self.outputs.clone();
+        if let Some(file) = &self.report_file {
+            if let Some(target) = targets.iter_mut().find(|target| target.path.is_none()) {
+                target.path = Some(file.clone());
+            }
+        }
+        targets
+    }
+
+    /// Returns the report format for reports that support only one output.
+    ///
+    /// This is the format of the first `--output`.
+    pub fn output_format(&self) -> ReportFormat {
+        self.outputs.first().map_or(ReportFormat::Text, |target| target.format)
+    }
+
+    /// Returns true if hurley should run without any interactive output.
+    ///
+    /// True when `--batch` is given or stdin/stdout is not a terminal.
+    pub fn is_batch(&self) -> bool {
+        self.batch || !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal()
+    }
+
+    /// Returns the most redirects to follow, `None` for no limit.
+    pub fn max_redirects(&self) -> Option<usize> {
+        match self.max_redirs {
+            Some(-1) => None,
+            Some(max) => Some(max as usize),
+            None => Some(DEFAULT_MAX_REDIRECTS),
+        }
+    }
+
+    /// Returns the timeout applied to each individual request.
+    pub fn per_request_timeout(&self) -> u64 {
+        self.request_timeout.unwrap_or(self.timeout)
+    }
+
+    /// Returns the limit on the whole performance run, if any.
+    ///
+    /// Only set when `--request-timeout` is given, in which case `--timeout`
+    /// bounds the run rather than each request.
+    pub fn run_timeout(&self) -> Option<u64> {
+        self.request_timeout.map(|_| self.timeout)
+    }
+
+    /// Fills in request settings from a template file.
+    ///
+    /// The file provides the URL and defaults; an explicit `-X`, `-d` or
+    /// `-f` wins, `-H` headers are placed after the file's headers, and
+    /// `--var` values override the file's vars.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Loaded request template
+    /// * `matches` - Parsed arguments, used to tell defaults from explicit flags
+    pub fn apply_request_file(&mut self, file: RequestFile, matches: &ArgMatches) {
+        self.url = Some(file.url);
+
+        if let Some(method) = file.method {
+            if matches.value_source("method") == Some(ValueSource::DefaultValue) {
+                self.method = method;
+            }
+        }
+
+        let mut headers: Vec<String> = file.headers
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect();
+        headers.append(&mut self.headers);
+        self.headers = headers;
+
+        if self.data.is_none() && self.body_file.is_none() {
+            self.data = file.body;
+        }
+
+        let mut vars = file.vars;
+        vars.append(&mut self.vars);
+        self.vars = vars;
+    }
+
+    /// Fills in the request of a `--session` where the command line leaves
+    /// it open.
+    ///
+    /// Without a URL on the command line, the session's request is replayed
+    /// (its method and body unless given). Session headers are placed before
+    /// `-H` headers so the command line wins on conflicts, and the session's
+    /// `--auth` is used only if none is given.
+    pub fn apply_session(&mut self, session: &Session, matches: &ArgMatches) {
+        if let Some(request) = &session.request {
+            // Without a URL the stored request is replayed; with one, only
+            // its headers carry over to the new request
+            if self.url.is_none() {
+                self.url = Some(request.url.clone());
+                if matches.value_source("method") == Some(ValueSource::DefaultValue) {
+                    self.method = request.method.clone();
+                }
+                if self.data.is_none() && self.body_file.is_none() {
+                    self.data = request.body.clone();
+                }
+            }
+            let mut headers: Vec<String> = request.headers
+                .iter()
+                .map(|(key, value)| format!("{}: {}", key, value))
+                .collect();
+            headers.append(&mut self.headers);
+            self.headers = headers;
+        }
+        if self.auth.is_empty() {
+            self.auth = session.auth.clone();
+        }
+    }
+
+    /// Fills in settings from a config profile that weren't given on the command line.
+    ///
+    /// Profile headers are placed before `-H` headers so the command line
+    /// wins on conflicts, and a relative URL is joined to the profile's
+    /// base URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - Effective config settings
+    /// * `matches` - Parsed arguments, used to tell defaults from explicit flags
+    pub fn apply_profile(&mut self, profile: &Profile, matches: &ArgMatches) {
+        self.url = self.url.as_deref().map(|url| profile.resolve_url(url));
+        self.more_urls = self.more_urls.iter().map(|url| profile.resolve_url(url)).collect();
+
+        // -H replaces a configured header of the same name in any case
+        let given: Vec<&str> = self.headers
+            .iter()
+            .filter_map(|header| header.split_once(':'))
+            .map(|(name, _)| name.trim())
+            .collect();
+        let mut config_headers: Vec<String> = profile.headers
+            .iter()
+            .filter(|(key, _)| !given.iter().any(|name| name.eq_ignore_ascii_case(key)))
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect();
+        config_headers.sort();
+        config_headers.append(&mut self.headers);
+        self.headers = config_headers;
+
+        if let Some(timeout) = profile.timeout {
+            if matches.value_source("timeout") == Some(ValueSource::DefaultValue) {
+                self.timeout = timeout;
+            }
+        }
+
+        if !self.follow_redirects {
+            self.follow_redirects = profile.follow_redirects.unwrap_or(false);
+        }
+
+        if self.allowed_hosts.is_empty() {
+            self.allowed_hosts = profile.allowed_hosts.clone();
+        }
+
+        if self.auth.is_empty() {
+            self.auth = profile.auth.clone();
+        }
+
+        self.custom_methods.extend(profile.custom_methods.iter().cloned());
+        self.test_hosts.extend(profile.test_hosts.iter().cloned());
+    }
+
+    /// Checks a method against the standard and configured custom methods.
+    ///
+    /// Returns a warning to show for an unknown method sent with
+    /// `--allow-custom-methods`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::InvalidMethod`](crate::error::RurlError::InvalidMethod)
+    /// for an unknown method without `--allow-custom-methods`.
+    pub fn check_method(&self, method: &str) -> crate::error::Result<Option<String>> {
+        check_method(method, &self.custom_methods, self.allow_custom_methods)
+    }
+
+    /// Returns the `--slo` objective with its window and target.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the objective can't be checked per window.
+    pub fn slo(&self) -> crate::error::Result<Option<Slo>> {
+        self.slo
+            .clone()
+            .map(|objective| Ok(Slo::new(objective, self.slo_window)?.target(self.slo_target)))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_values() {
+        let cli = Cli::parse_from(["hurley", "https://example.com"]);
+        assert_eq!(cli.url.as_deref(), Some("https://example.com"));
+        assert_eq!(cli.method, "GET");
+        assert_eq!(cli.timeout, 30);
+        assert_eq!(cli.concurrency, 1);
+        assert_eq!(cli.total_requests, 1);
+        assert!(!cli.is_perf_mode());
+    }
+
+    #[test]
+    fn test_post_with_data() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "-X", "POST",
+            "https://example.com",
+            "-d", r#"{"key": "value"}"#,
+        ]);
+        assert_eq!(cli.method, "POST");
+        assert_eq!(cli.data, Some(r#"{"key": "value"}"#.to_string()));
+    }
+
+    #[test]
+    fn test_headers() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com",
+            "-H", "Content-Type: application/json",
+            "-H", "Authorization: Bearer token",
+        ]);
+        assert_eq!(cli.headers.len(), 2);
+        assert_eq!(cli.headers[0], "Content-Type: application/json");
+    }
+
+    #[test]
+    fn test_user_agent() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-A", "probe/1.0"]);
+        assert_eq!(cli.user_agent.as_deref(), Some("probe/1.0"));
+        assert!(!cli.no_user_agent);
+        assert!(Cli::parse_from(["hurley", "https://example.com", "--no-user-agent"]).no_user_agent);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "-A", "x", "--no-user-agent"]).is_err());
+    }
+
+    #[test]
+    fn test_perf_mode_with_concurrency() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com",
+            "-c", "10",
+            "-n", "100",
+        ]);
+        assert!(cli.is_perf_mode());
+        assert_eq!(cli.concurrency, 10);
+        assert_eq!(cli.total_requests, 100);
+    }
+
+    #[test]
+    fn test_multiple_urls() {
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com/a", "https://example.com/b", "-o", "a.json", "-o", "b.json", "-Z",
+        ]);
+        assert_eq!(cli.url.as_deref(), Some("https://example.com/a"));
+        assert_eq!(cli.more_urls, ["https://example.com/b"]);
+        assert_eq!(cli.output_file, [PathBuf::from("a.json"), PathBuf::from("b.json")]);
+        assert!(cli.parallel);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--parallel"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com/a", "https://example.com/b", "-c", "5"]).is_err());
+        assert!(Cli::parse_from(["hurley", "https://example.com/[1-5]", "-g"]).globoff);
+
+        // A subcommand after the URL is not taken for another URL
+        let cli = Cli::parse_from(["hurley", "https://example.com", "sweep", "--concurrency", "1,2"]);
+        assert!(cli.more_urls.is_empty());
+        assert!(matches!(cli.command, Some(Command::Sweep { .. })));
+    }
+
+    #[test]
+    fn test_priority() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-c", "5", "--priority", "checkout,login"]);
+        assert_eq!(cli.priority, ["checkout", "login"]);
+        assert_eq!(cli.max_deferred, DEFAULT_MAX_DEFERRED);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--max-deferred", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_vus_and_iterations() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--vus", "50", "--iterations", "20"]);
+        assert!(cli.is_perf_mode());
+        assert_eq!(cli.concurrency, 50);
+        assert_eq!(cli.iterations, Some(20));
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--iterations", "0"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--iterations", "5", "-n", "100"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--iterations", "5", "-z", "1m"]).is_err());
+    }
+
+    #[test]
+    fn test_flags() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com",
+            "-i", "-L", "-v",
+        ]);
+        assert!(cli.include_headers);
+        assert!(cli.follow_redirects);
+        assert!(cli.verbose);
+    }
+
+    #[test]
+    fn test_compressed() {
+        assert!(Cli::parse_from(["hurley", "https://example.com", "--compressed"]).compressed);
+        assert!(!Cli::parse_from(["hurley", "https://example.com"]).compressed);
+        assert!(Cli::parse_from(["hurley", "https://example.com", "-d", "x", "--compress-body"]).compress_body);
+    }
+
+    #[test]
+    fn test_dns_options() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--dns-server", "1.1.1.1,8.8.8.8:5353", "--dns-server", "::1"]);
+        let servers: Vec<String> = cli.dns_servers.iter().map(|s| s.0.to_string()).collect();
+        assert_eq!(servers, ["1.1.1.1:53", "8.8.8.8:5353", "[::1]:53"]);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--dns-server", "dns.google"]).is_err());
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--doh-url", "https://1.1.1.1/dns-query"]);
+        assert_eq!(cli.doh_url.as_deref(), Some("https://1.1.1.1/dns-query"));
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--dns-server", "1.1.1.1", "--doh-url", "https://1.1.1.1"]).is_err());
+    }
+
+    #[test]
+    fn test_verb_shortcuts() {
+        assert!(Cli::parse_from(["hurley", "https://example.com", "-I"]).head);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "-I", "-X", "GET"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "-I", "-d", "x"]).is_err());
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-G", "-d", "q=rust"]);
+        assert!(cli.get);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--get", "-X", "POST"]).is_err());
+    }
+
+    #[test]
+    fn test_trace_wire() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--trace-wire", "wire.log", "--trace-wire-bodies"]);
+        assert_eq!(cli.trace_wire, Some(PathBuf::from("wire.log")));
+        assert!(cli.trace_wire_bodies);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--trace-wire-bodies"]).is_err());
+    }
+
+    #[test]
+    fn test_scheduled_start() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "100", "--start-in", "30m"]);
+        assert_eq!(cli.start_delay(), Some(Duration::from_secs(1800)));
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "100", "--start-at", "02:00"]);
+        assert_eq!(cli.start_at, NaiveTime::from_hms_opt(2, 0, 0));
+        assert!(cli.start_delay().unwrap() <= Duration::from_secs(25 * 3600));
+
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--start-at", "02:00", "--start-in", "1h"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--start-at", "2am"]).is_err());
+    }
+
+    #[test]
+    fn test_slo() {
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com", "-z", "1h",
+            "--slo", "p99 < 300ms", "--slo-target", "99.5%",
+        ]);
+        assert_eq!(cli.slo_window, Duration::from_secs(300));
+        assert_eq!(cli.slo_target, Some(99.5));
+        assert!(cli.slo().unwrap().is_some());
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-z", "1h", "--slo", "avg < 300ms"]);
+        assert!(cli.slo().is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--slo-target", "99"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--slo", "p99 < 1s", "--slo-target", "120"]).is_err());
+    }
+
+    #[test]
+    fn test_hash_responses() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "100", "--hash-responses"]);
+        assert!(cli.hash_responses);
+    }
+
+    #[test]
+    fn test_raw_request() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--raw-request", "req.http"]);
+        assert_eq!(cli.raw_request, Some(PathBuf::from("req.http")));
+        assert_eq!(cli.raw_max_bytes, 16 << 20);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--raw-request", "req.http", "-d", "{}"]).is_err());
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--raw-request", "req.http", "--raw-max-bytes", "64K"]);
+        assert_eq!(cli.raw_max_bytes, 64 << 10);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--raw-max-bytes", "64K"]).is_err());
+    }
+
+    #[test]
+    fn test_connection_flags() {
         let cli = Cli::parse_from([
             "hurley",
             "https://example.com",
-            "-H", "Content-Type: application/json",
-            "-H", "Authorization: Bearer token",
+            "--pool-idle-timeout", "5",
+            "--retry-on-connection-reset",
+            "--disable-keepalive",
         ]);
-        assert_eq!(cli.headers.len(), 2);
-        assert_eq!(cli.headers[0], "Content-Type: application/json");
+        assert_eq!(cli.pool_idle_timeout, Some(5));
+        assert!(cli.retry_on_connection_reset);
+        assert!(cli.disable_keepalive);
     }
 
     #[test]
-    fn test_perf_mode_with_concurrency() {
+    fn test_socket_flags() {
         let cli = Cli::parse_from([
             "hurley",
             "https://example.com",
-            "-c", "10",
-            "-n", "100",
+            "--local-addr", "10.0.0.21",
+            "--no-tcp-nodelay",
+            "--tcp-keepalive", "30s",
+        ]);
+        assert_eq!(cli.local_addr, Some(IpAddr::from([10, 0, 0, 21])));
+        assert!(cli.no_tcp_nodelay);
+        assert_eq!(cli.tcp_keepalive, Some(Duration::from_secs(30)));
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--local-addr", "eth0"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--local-addr", "10.0.0.21", "--interface", "eth0"]).is_err());
+    }
+
+    #[test]
+    fn test_graphql() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com/graphql",
+            "--graphql",
+            "--gql-query", "@query.graphql",
+            "--gql-variables", r#"{"id": 1}"#,
+        ]);
+        assert!(cli.graphql);
+        assert_eq!(cli.gql_query.as_deref(), Some("@query.graphql"));
+
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--graphql"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--gql-query", "{ a }"]).is_err());
+        assert!(Cli::try_parse_from([
+            "hurley", "https://example.com", "--graphql", "--gql-query", "{ a }", "-d", "{}",
+        ]).is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--render", "xml"]);
+        assert_eq!(cli.render.as_deref(), Some("xml"));
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--render", "yaml"]).is_err());
+    }
+
+    #[test]
+    fn test_timing_log() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--timing-log", "timings.ndjson"]);
+        assert_eq!(cli.timing_log, Some(PathBuf::from("timings.ndjson")));
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--timing-log", "t.ndjson", "--negotiate"]).is_err());
+    }
+
+    #[test]
+    fn test_write_out() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-w", "%{header:x-request-id}\\n"]);
+        assert_eq!(cli.write_out.unwrap().to_string(), "%{header:x-request-id}\\n");
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "-w", "%{nope}"]).is_err());
+    }
+
+    #[test]
+    fn test_capture() {
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com", "--capture", "token=$.access_token", "--capture", "id=.user.id",
+        ]);
+        assert_eq!(cli.captures.len(), 2);
+        assert_eq!(cli.captures[0].name, "token");
+        assert_eq!(cli.captures[1].source.to_string(), ".user.id");
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--capture", "$.token"]).is_err());
+    }
+
+    #[test]
+    fn test_filter() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--filter", "$.data[0].id"]);
+        assert_eq!(cli.filter.unwrap().to_string(), "$.data[0].id");
+
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--filter", "data"]).is_err());
+        assert!(Cli::try_parse_from([
+            "hurley", "https://example.com", "--filter", ".id", "-o", "out.json",
+        ]).is_err());
+    }
+
+    #[test]
+    fn test_save_parts() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--save-parts", "parts"]);
+        assert_eq!(cli.save_parts, Some(PathBuf::from("parts")));
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--save-parts", "p", "-o", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_ranges() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--range", "0-1023", "--range", "-500"]);
+        assert_eq!(cli.ranges, [ByteRange::Span(0, 1023), ByteRange::Last(500)]);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--range", "9-1"]).is_err());
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "100", "--random-range", "64K"]);
+        assert_eq!(cli.random_range, Some(65536));
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--random-range", "1K", "--range", "0-1"]).is_err());
+    }
+
+    #[test]
+    fn test_output_file() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-o", "out.bin"]);
+        assert_eq!(cli.output_file, [PathBuf::from("out.bin")]);
+        assert_eq!(cli.output_format(), ReportFormat::Text);
+    }
+
+    #[test]
+    fn test_allowed_hosts() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "http://localhost",
+            "--allowed-host", "localhost,*.staging.example.com",
+            "--force",
+        ]);
+        assert_eq!(cli.allowed_hosts, vec!["localhost", "*.staging.example.com"]);
+        assert!(cli.force);
+        assert!(!cli.no_preflight);
+    }
+
+    #[test]
+    fn test_no_preflight() {
+        let cli = Cli::parse_from(["hurley", "http://localhost", "-n", "10", "--no-preflight"]);
+        assert!(cli.no_preflight);
+        assert!(!cli.force);
+    }
+
+    #[test]
+    fn test_sample() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com",
+            "--perf", "data.json",
+            "--sample", "10%",
+            "--seed", "7",
         ]);
+        assert_eq!(cli.sample, Some(Sample::Percent(10.0)));
+        assert_eq!(cli.seed, Some(7));
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--openapi", "api.yaml", "--sample", "5"]);
+        assert_eq!(cli.sample, Some(Sample::Count(5)));
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--sample", "10%"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--perf", "data.json", "--seed", "7"]).is_err());
+    }
+
+    #[test]
+    fn test_vars() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com/users/{{id}}",
+            "--var", "id=42",
+            "--var", "tenant=acme",
+        ]);
+        assert_eq!(cli.vars.len(), 2);
+        assert_eq!(cli.vars[0], ("id".to_string(), "42".to_string()));
+    }
+
+    #[test]
+    fn test_error_format() {
+        let cli = Cli::parse_from(["hurley", "https://example.com"]);
+        assert_eq!(cli.error_format, "text");
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--errors", "json"]);
+        assert_eq!(cli.error_format, "json");
+    }
+
+    #[test]
+    fn test_max_redirs() {
+        assert_eq!(Cli::parse_from(["hurley", "https://example.com", "-L"]).max_redirects(), Some(10));
+        assert_eq!(Cli::parse_from(["hurley", "https://example.com", "-L", "--max-redirs", "0"]).max_redirects(), Some(0));
+        assert_eq!(Cli::parse_from(["hurley", "https://example.com", "-L", "--max-redirs", "-1"]).max_redirects(), None);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--max-redirs", "-2"]).is_err());
+    }
+
+    #[test]
+    fn test_conditional_flags() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com",
+            "--etag-compare", "etag.txt",
+            "--etag-save", "etag.txt",
+            "--if-modified-since", "Wed, 21 Oct 2015 07:28:00 GMT",
+        ]);
+        assert_eq!(cli.etag_compare, Some(PathBuf::from("etag.txt")));
+        assert_eq!(cli.etag_save, Some(PathBuf::from("etag.txt")));
+        assert_eq!(cli.if_modified_since.as_deref(), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+    }
+
+    #[test]
+    fn test_cache_flag() {
+        assert_eq!(Cli::parse_from(["hurley", "https://example.com"]).cache, None);
+        assert_eq!(Cli::parse_from(["hurley", "--cache", "https://example.com"]).cache, Some(None));
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--cache=/tmp/cache"]);
+        assert_eq!(cli.cache, Some(Some(PathBuf::from("/tmp/cache"))));
+    }
+
+    #[test]
+    fn test_split_timeouts() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com",
+            "--max-time", "120",
+            "--connect-timeout", "2s",
+            "--read-timeout", "500ms",
+        ]);
+        assert_eq!(cli.timeout, 120);
+        assert_eq!(cli.connect_timeout, Some(Duration::from_secs(2)));
+        assert_eq!(cli.read_timeout, Some(Duration::from_millis(500)));
+        assert_eq!(Cli::parse_from(["hurley", "https://example.com", "-m", "5"]).timeout, 5);
+    }
+
+    #[test]
+    fn test_request_timeout() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "10", "--timeout", "60"]);
+        assert_eq!(cli.per_request_timeout(), 60);
+        assert_eq!(cli.run_timeout(), None);
+
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com",
+            "-n", "10",
+            "--request-timeout", "2",
+            "--timeout", "600",
+        ]);
+        assert_eq!(cli.per_request_timeout(), 2);
+        assert_eq!(cli.run_timeout(), Some(600));
+    }
+
+    #[test]
+    fn test_batch_flag() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--batch"]);
+        assert!(cli.batch);
+        assert!(cli.is_batch());
+    }
+
+    #[test]
+    fn test_slow_threshold() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com",
+            "--slow-threshold", "300ms",
+            "--fail-slow",
+        ]);
+        assert_eq!(cli.slow_threshold, Some(Duration::from_millis(300)));
+        assert!(cli.fail_slow);
+
+        let result = Cli::try_parse_from(["hurley", "https://example.com", "--fail-slow"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_openapi_is_perf_mode() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--openapi", "spec.yaml"]);
+        assert_eq!(cli.openapi_file, Some(PathBuf::from("spec.yaml")));
         assert!(cli.is_perf_mode());
-        assert_eq!(cli.concurrency, 10);
-        assert_eq!(cli.total_requests, 100);
     }
 
     #[test]
-    fn test_flags() {
+    fn test_keepalive() {
         let cli = Cli::parse_from([
             "hurley",
             "https://example.com",
-            "-i", "-L", "-v",
+            "--keepalive", "6",
+            "--probe-interval", "10s",
         ]);
-        assert!(cli.include_headers);
+        assert_eq!(cli.keepalive, Some(6));
+        assert_eq!(cli.probe_interval, Duration::from_secs(10));
+        assert!(!cli.is_perf_mode());
+    }
+
+    #[test]
+    fn test_auth() {
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com", "--auth", "basic:a:b", "--auth", "script:./token.sh",
+        ]);
+        assert_eq!(cli.auth, ["basic:a:b", "script:./token.sh"]);
+        assert!(Cli::try_parse_from([
+            "hurley", "https://example.com", "--auth", "bearer:x", "--workers", "10.0.0.1:7000",
+        ]).is_err());
+    }
+
+    #[test]
+    fn test_client_cert() {
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com",
+            "--cert", "client.pem", "--key", "client.key", "--cert-reload", "1h",
+        ]);
+        assert_eq!(cli.cert, Some(PathBuf::from("client.pem")));
+        assert_eq!(cli.key, Some(PathBuf::from("client.key")));
+        assert_eq!(cli.cert_reload, Some(Duration::from_secs(3600)));
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--cert-reload", "1h"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--key", "client.key"]).is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let cli = Cli::parse_from([
+            "hurley",
+            "https://example.com",
+            "--resolve", "example.com:443:10.0.0.1",
+            "--resolve", "example.com:443:[::1]",
+            "--resolve-file", "backends.txt",
+        ]);
+        assert_eq!(cli.resolve.len(), 2);
+        assert_eq!(cli.resolve[1].to_string(), "example.com:443:[::1]");
+        assert_eq!(cli.resolve_file, Some(PathBuf::from("backends.txt")));
+    }
+
+    #[test]
+    fn test_request_log() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "10", "--request-log", "log.ndjson.gz"]);
+        assert_eq!(cli.request_log, Some(PathBuf::from("log.ndjson.gz")));
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "10", "--results-csv", "results.csv"]);
+        assert_eq!(cli.results_csv, Some(PathBuf::from("results.csv")));
+        assert_eq!(cli.request_log, None);
+        assert!(!cli.report_records);
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com", "-n", "10",
+            "--threshold", "p99 < 500ms",
+            "--threshold", "checkout p95 <= 1s",
+        ]);
+        assert_eq!(cli.thresholds.len(), 2);
+        assert_eq!(cli.thresholds[1].scope.as_deref(), Some("checkout"));
+
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--threshold", "p99"]).is_err());
+    }
+
+    #[test]
+    fn test_strict_redirects() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-L", "--strict-redirects"]);
         assert!(cli.follow_redirects);
+        assert!(cli.strict_redirects);
+    }
+
+    #[test]
+    fn test_duration() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-z", "30s"]);
+        assert_eq!(cli.duration, Some(Duration::from_secs(30)));
+        assert!(cli.is_perf_mode());
+
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "-z", "30s", "-n", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_warmup() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "100", "--warmup", "10s"]);
+        assert_eq!(cli.warmup, Some(Duration::from_secs(10)));
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "100", "--warmup-requests", "20"]);
+        assert_eq!(cli.warmup_requests, 20);
+        assert!(Cli::try_parse_from([
+            "hurley", "https://example.com", "--warmup", "10s", "--warmup-requests", "20",
+        ]).is_err());
+    }
+
+    #[test]
+    fn test_sweep_command() {
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com", "-n", "100", "sweep", "--concurrency", "1,2,4", "--csv", "sweep.csv",
+        ]);
+        assert_eq!(cli.url.as_deref(), Some("https://example.com"));
+        assert_eq!(cli.total_requests, 100);
+        let Some(Command::Sweep { levels, csv }) = cli.command else {
+            panic!("expected sweep command");
+        };
+        assert_eq!(levels, vec![1, 2, 4]);
+        assert_eq!(csv, Some(PathBuf::from("sweep.csv")));
+
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "sweep", "--concurrency", "0,2"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "sweep"]).is_err());
+    }
+
+    #[test]
+    fn test_accept() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--accept", "json,text/csv", "--negotiate"]);
+        assert_eq!(cli.accept.len(), 2);
+        assert_eq!(cli.accept[0].value(), "application/json");
+        assert!(cli.negotiate);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--accept", "yaml"]).is_err());
+    }
+
+    #[test]
+    fn test_cookies() {
+        assert!(Cli::parse_from(["hurley", "https://example.com", "--cookies"]).cookies);
+        assert!(!Cli::parse_from(["hurley", "https://example.com"]).cookies);
+    }
+
+    #[test]
+    fn test_plan() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "100", "--plan"]);
+        assert!(cli.plan);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--plan", "--keepalive", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_workers() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--workers", "load1:7878,load2:7878"]);
+        assert_eq!(cli.workers, vec!["load1:7878", "load2:7878"]);
+        assert!(cli.is_perf_mode());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--workers", "a:1", "--ramp-up", "10s"]).is_err());
+        // Settings workers can't apply
+        for flag in [
+            "--local-addr=10.0.0.21",
+            "--interface=eth1",
+            "--dns-server=1.1.1.1",
+            "--doh-url=https://1.1.1.1/dns-query",
+            "--cache",
+            "--timing-log=timings.ndjson",
+        ] {
+            assert!(Cli::try_parse_from(["hurley", "https://example.com", "-n", "10", flag]).is_ok(), "{}", flag);
+            assert!(Cli::try_parse_from(["hurley", "https://example.com", "--workers", "a:1", flag]).is_err(), "{}", flag);
+        }
+
+        let cli = Cli::parse_from(["hurley", "worker", "--listen", "0.0.0.0:7878"]);
+        let Some(Command::Worker { listen }) = cli.command else {
+            panic!("expected worker command");
+        };
+        assert_eq!(listen, "0.0.0.0:7878");
+        assert!(Cli::try_parse_from(["hurley", "worker"]).is_err());
+    }
+
+    #[test]
+    fn test_man_subcommand() {
+        let cli = Cli::parse_from(["hurley", "man"]);
+        assert!(matches!(cli.command, Some(Command::Man { dir: None })));
+        let cli = Cli::parse_from(["hurley", "man", "-o", "target/man"]);
+        assert!(matches!(cli.command, Some(Command::Man { dir: Some(ref dir) }) if dir == &PathBuf::from("target/man")));
+    }
+
+    #[test]
+    fn test_statsd() {
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com", "-n", "10", "--statsd", "localhost:8125", "--statsd-tag", "env:ci,team:api",
+        ]);
+        assert_eq!(cli.statsd.as_deref(), Some("localhost:8125"));
+        assert_eq!(cli.statsd_prefix, "hurley");
+        assert_eq!(cli.statsd_tags, vec!["env:ci", "team:api"]);
+        assert!(!cli.dogstatsd);
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--dogstatsd"]).is_err());
+    }
+
+    #[test]
+    fn test_outputs() {
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com", "-n", "10", "--output", "json", "--output", "junit=results.xml",
+        ]);
+        assert_eq!(cli.outputs.len(), 2);
+        assert_eq!(cli.output_format(), ReportFormat::Json);
+        assert_eq!(cli.outputs[1].path, Some(PathBuf::from("results.xml")));
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--output", "yaml"]).is_err());
+
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com", "-n", "10", "--output", "html", "--report-file", "report.html",
+        ]);
+        let targets = cli.output_targets();
+        assert_eq!(targets[0].format, ReportFormat::Html);
+        assert_eq!(targets[0].path, Some(PathBuf::from("report.html")));
+    }
+
+    #[test]
+    fn test_stages() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--stages", "1m:50,2m:200,1m:0"]);
+        assert!(cli.is_perf_mode());
+        assert_eq!(cli.run_length(), Some(Duration::from_secs(240)));
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-c", "20", "-z", "1m", "--ramp-up", "10s"]);
+        assert_eq!(cli.ramp_up, Some(Duration::from_secs(10)));
+        assert_eq!(cli.run_length(), Some(Duration::from_secs(60)));
+
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--stages", "1m"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--stages", "1m:5", "-z", "1m"]).is_err());
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--stages", "1m:5", "--ramp-up", "5s"]).is_err());
+    }
+
+    #[test]
+    fn test_rate() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--rate", "200", "-n", "1000"]);
+        assert_eq!(cli.rate, Some(200.0));
+        assert!(cli.is_perf_mode());
+
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--rate", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_as_curl() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--as-curl"]);
+        assert!(cli.as_curl);
+    }
+
+    fn parse_with_matches(args: &[&str]) -> (Cli, ArgMatches) {
+        use clap::{CommandFactory, FromArgMatches};
+        let matches = Cli::command().get_matches_from(args);
+        (Cli::from_arg_matches(&matches).unwrap(), matches)
+    }
+
+    #[test]
+    fn test_send_subcommand() {
+        let cli = Cli::parse_from(["hurley", "-v", "send", "request.hurl"]);
+        assert!(cli.url.is_none());
         assert!(cli.verbose);
+        assert!(matches!(cli.command, Some(Command::Send { ref file }) if file == &PathBuf::from("request.hurl")));
+
+        assert!(Cli::try_parse_from(["hurley"]).is_err());
+    }
+
+    #[test]
+    fn test_apply_request_file() {
+        let file = RequestFile::parse(
+            "---\nmethod: POST\nurl: /users/{{id}}\nheaders:\n  Accept: application/json\nvars:\n  id: 1\n---\n{}\n",
+        )
+        .unwrap();
+
+        let (mut cli, matches) = parse_with_matches(&[
+            "hurley", "-H", "X-Custom: 1", "--var", "id=2", "send", "request.hurl",
+        ]);
+        cli.apply_request_file(file.clone(), &matches);
+        assert_eq!(cli.url.as_deref(), Some("/users/{{id}}"));
+        assert_eq!(cli.method, "POST");
+        assert_eq!(cli.headers, vec!["Accept: application/json", "X-Custom: 1"]);
+        assert_eq!(cli.data.as_deref(), Some("{}"));
+        assert_eq!(cli.vars.last(), Some(&("id".to_string(), "2".to_string())));
+
+        let (mut cli, matches) = parse_with_matches(&["hurley", "-X", "PUT", "-d", "x", "send", "r.hurl"]);
+        cli.apply_request_file(file, &matches);
+        assert_eq!(cli.method, "PUT");
+        assert_eq!(cli.data.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn test_apply_session() {
+        use crate::http::HttpRequest;
+
+        let mut session = Session::default();
+        let request = HttpRequest::new("https://api.example.com/login")
+            .method("POST")
+            .unwrap()
+            .body("{}");
+        session.record(&request, &["Accept: application/json".to_string()], &["bearer:abc".to_string()], None);
+
+        // A session alone is enough to replay its request
+        let (mut cli, matches) = parse_with_matches(&["hurley", "--session", "api"]);
+        cli.apply_session(&session, &matches);
+        assert_eq!(cli.url.as_deref(), Some("https://api.example.com/login"));
+        assert_eq!(cli.method, "POST");
+        assert_eq!(cli.headers, vec!["Accept: application/json"]);
+        assert_eq!(cli.data.as_deref(), Some("{}"));
+        assert_eq!(cli.auth, vec!["bearer:abc"]);
+
+        let (mut cli, matches) = parse_with_matches(&[
+            "hurley", "https://api.example.com/cart", "-H", "Accept: text/csv", "--auth", "basic:a:b", "--session", "api",
+        ]);
+        cli.apply_session(&session, &matches);
+        assert_eq!(cli.url.as_deref(), Some("https://api.example.com/cart"));
+        assert_eq!((cli.method.as_str(), cli.data.as_deref()), ("GET", None));
+        assert_eq!(cli.headers, vec!["Accept: application/json", "Accept: text/csv"]);
+        assert_eq!(cli.auth, vec!["basic:a:b"]);
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let profile = Profile {
+            base_url: Some("https://staging.example.com".to_string()),
+            headers: [("Authorization".to_string(), "Bearer config".to_string())].into_iter().collect(),
+            timeout: Some(5),
+            follow_redirects: Some(true),
+            allowed_hosts: vec!["staging.example.com".to_string()],
+            custom_methods: vec!["PURGE".to_string()],
+            test_hosts: vec!["staging.example.com".to_string()],
+            auth: vec!["bearer:config".to_string()],
+        };
+
+        let (mut cli, matches) = parse_with_matches(&["hurley", "/health", "-H", "X-Custom: 1"]);
+        cli.apply_profile(&profile, &matches);
+        assert_eq!(cli.url.as_deref(), Some("https://staging.example.com/health"));
+        assert_eq!(cli.headers, vec!["Authorization: Bearer config", "X-Custom: 1"]);
+        assert_eq!(cli.timeout, 5);
+        assert!(cli.follow_redirects);
+        assert_eq!(cli.allowed_hosts, vec!["staging.example.com"]);
+        assert_eq!(cli.test_hosts, vec!["staging.example.com"]);
+        assert_eq!(cli.auth, vec!["bearer:config"]);
+        assert_eq!(cli.check_method("purge").unwrap(), None);
+
+        let (mut cli, matches) = parse_with_matches(&["hurley", "/health", "-H", "authorization: Bearer cli", "--auth", "bearer:cli"]);
+        cli.apply_profile(&profile, &matches);
+        assert_eq!(cli.headers, vec!["authorization: Bearer cli"]);
+        assert_eq!(cli.auth, vec!["bearer:cli"]);
+    }
+
+    #[test]
+    fn test_timeline_window() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "10"]);
+        assert_eq!(cli.timeline_window, Duration::from_secs(1));
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-n", "10", "--timeline-window", "250ms"]);
+        assert_eq!(cli.timeline_window, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_assertions() {
+        let cli = Cli::parse_from([
+            "hurley", "https://example.com", "-n", "10",
+            "--assert", "status == 200", "--assert", "$.ok == true",
+        ]);
+        assert_eq!(cli.assertions.len(), 2);
+        assert_eq!(cli.assertions[1].to_string(), "$.ok == true");
+        assert!(Cli::try_parse_from(["hurley", "https://example.com", "--assert", "latency < 1s"]).is_err());
+    }
+
+    #[test]
+    fn test_custom_methods() {
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-X", "GTE"]);
+        assert!(cli.check_method(&cli.method).is_err());
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "-X", "GTE", "--allow-custom-methods"]);
+        assert!(cli.check_method(&cli.method).unwrap().unwrap().contains("did you mean GET?"));
+
+        let cli = Cli::parse_from(["hurley", "https://example.com", "--custom-method", "PURGE,BAN"]);
+        assert_eq!(cli.check_method("BAN").unwrap(), None);
+    }
+
+    #[test]
+    fn test_cli_overrides_profile() {
+        let profile = Profile {
+            timeout: Some(5),
+            ..Profile::default()
+        };
+
+        let (mut cli, matches) = parse_with_matches(&["hurley", "https://example.com", "--timeout", "60"]);
+        cli.apply_profile(&profile, &matches);
+        assert_eq!(cli.url.as_deref(), Some("https://example.com"));
+        assert_eq!(cli.timeout, 60);
     }
 }
@@ -0,0 +1,265 @@
+//! Configuration file and named profiles.
+//!
+//! Defaults for headers, timeout, base URL and other settings can be stored
+//! in `~/.config/hurley/config.toml` so they don't need to be repeated on
+//! every invocation:
+//!
+//! ```toml
+//! timeout = 10
+//!
+//! [headers]
+//! Accept = "application/json"
+//!
+//! [profiles.staging]
+//! base_url = "https://staging.example.com"
+//! allowed_hosts = ["staging.example.com"]
+//! test_hosts = ["staging.example.com"]
+//! custom_methods = ["PURGE"]
+//! auth = ["bearer:${STAGING_TOKEN}"]
+//!
+//! [profiles.staging.headers]
+//! X-Tenant = "acme"
+//! ```
+//!
+//! Top-level settings apply to every run; a profile selected with
+//! `--profile` is layered on top, and command-line flags override both.
+//! Headers are merged by name regardless of case, so `-H "accept: ..."`
+//! replaces a configured `Accept`.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, RurlError};
+
+/// Settings that can be given in the config file, at the top level or per profile.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Profile {
+    /// Base URL that relative request URLs (starting with `/`) are joined to
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Headers added to every request
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// Request timeout in seconds
+    #[serde(default)]
+    pub timeout: Option<u64>,
+
+    /// Whether to follow HTTP redirects
+    #[serde(default)]
+    pub follow_redirects: Option<bool>,
+
+    /// Hosts that performance tests are allowed to target
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+
+    /// Non-standard HTTP methods accepted without `--allow-custom-methods`
+    #[serde(default)]
+    pub custom_methods: Vec<String>,
+
+    /// Hosts that are test environments, load tested with destructive
+    /// methods without confirmation
+    #[serde(default)]
+    pub test_hosts: Vec<String>,
+
+    /// Authentication providers, as given to `--auth`
+    #[serde(default)]
+    pub auth: Vec<String>,
+}
+
+impl Profile {
+    /// Returns a profile with `other`'s settings layered on top of this one.
+    ///
+    /// A header of `other` replaces one of this profile's with the same
+    /// name in any case.
+    pub fn merge(&self, other: &Profile) -> Profile {
+        let mut headers = self.headers.clone();
+        headers.retain(|name, _| !other.headers.keys().any(|own| own.eq_ignore_ascii_case(name)));
+        headers.extend(other.headers.clone());
+
+        Profile {
+            base_url: other.base_url.clone().or_else(|| self.base_url.clone()),
+            headers,
+            timeout: other.timeout.or(self.timeout),
+            follow_redirects: other.follow_redirects.or(self.follow_redirects),
+            allowed_hosts: if other.allowed_hosts.is_empty() {
+                self.allowed_hosts.clone()
+            } else {
+                other.allowed_hosts.clone()
+            },
+            custom_methods: self.custom_methods
+                .iter()
+                .chain(&other.custom_methods)
+                .cloned()
+                .collect(),
+            test_hosts: self.test_hosts.iter().chain(&other.test_hosts).cloned().collect(),
+            auth: if other.auth.is_empty() {
+                self.auth.clone()
+            } else {
+                other.auth.clone()
+            },
+        }
+    }
+
+    /// Joins a relative URL (starting with `/`) to the base URL, if one is set.
+    ///
+    /// Absolute URLs are returned unchanged.
+    pub fn resolve_url(&self, url: &str) -> String {
+        match &self.base_url {
+            Some(base) if url.starts_with('/') => format!("{}{}", base.trim_end_matches('/'), url),
+            _ => url.to_string(),
+        }
+    }
+}
+
+/// Returns hurley's config directory.
+///
+/// Uses `$XDG_CONFIG_HOME/hurley`, falling back to `~/.config/hurley`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("hurley"))
+}
+
+/// Parsed configuration file.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    /// Settings applied to every run
+    #[serde(flatten)]
+    pub defaults: Profile,
+
+    /// Named profiles, selected with `--profile`
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+impl Config {
+    /// Returns the default config file location.
+    ///
+    /// Uses `$XDG_CONFIG_HOME/hurley/config.toml`, falling back to
+    /// `~/.config/hurley/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(config_dir()?.join("config.toml"))
+    }
+
+    /// Loads the configuration.
+    ///
+    /// An explicitly given `path` must exist. Without one, the default
+    /// location is used if present, and an empty config otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::from_file(path),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => Self::from_file(&path),
+                _ => Ok(Self::default()),
+            },
+        }
+    }
+
+    /// Loads the configuration from a TOML file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_toml(&content)
+            .map_err(|e| RurlError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parses the configuration from a TOML string.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        toml::from_str(content).map_err(|e| RurlError::ConfigError(e.to_string()))
+    }
+
+    /// Returns the effective settings for the named profile.
+    ///
+    /// With no name, only the top-level defaults are returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::ConfigError`] if the profile does not exist.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        match name {
+            Some(name) => {
+                let profile = self.profiles.get(name).ok_or_else(|| {
+                    RurlError::ConfigError(format!("Unknown profile: {}", name))
+                })?;
+                Ok(self.defaults.merge(profile))
+            }
+            None => Ok(self.defaults.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+timeout = 10
+custom_methods = ["PROPFIND"]
+
+[headers]
+Accept = "application/json"
+Authorization = "Bearer default"
+
+[profiles.staging]
+base_url = "https://staging.example.com/"
+allowed_hosts = ["staging.example.com"]
+custom_methods = ["PURGE"]
+test_hosts = ["staging.example.com"]
+auth = ["bearer:${STAGING_TOKEN}"]
+
+[profiles.staging.headers]
+authorization = "Bearer staging"
+"#;
+
+    #[test]
+    fn test_parse_defaults() {
+        let config = Config::from_toml(SAMPLE).unwrap();
+        let profile = config.profile(None).unwrap();
+        assert_eq!(profile.timeout, Some(10));
+        assert_eq!(profile.base_url, None);
+        assert_eq!(profile.headers.get("Authorization"), Some(&"Bearer default".to_string()));
+    }
+
+    #[test]
+    fn test_profile_overrides_defaults() {
+        let config = Config::from_toml(SAMPLE).unwrap();
+        let profile = config.profile(Some("staging")).unwrap();
+        assert_eq!(profile.timeout, Some(10));
+        assert_eq!(profile.headers.get("Accept"), Some(&"application/json".to_string()));
+        // The profile's header replaces the default in another case
+        assert_eq!(profile.headers.get("authorization"), Some(&"Bearer staging".to_string()));
+        assert!(!profile.headers.contains_key("Authorization"));
+        assert_eq!(profile.custom_methods, vec!["PROPFIND", "PURGE"]);
+        assert_eq!(profile.allowed_hosts, vec!["staging.example.com"]);
+        assert_eq!(profile.test_hosts, vec!["staging.example.com"]);
+        assert_eq!(profile.auth, vec!["bearer:${STAGING_TOKEN}"]);
+        assert!(config.profile(None).unwrap().auth.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_profile() {
+        let config = Config::from_toml(SAMPLE).unwrap();
+        assert!(config.profile(Some("prod")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let config = Config::from_toml(SAMPLE).unwrap();
+        let profile = config.profile(Some("staging")).unwrap();
+        assert_eq!(profile.resolve_url("/health"), "https://staging.example.com/health");
+        assert_eq!(profile.resolve_url("https://other.example.com"), "https://other.example.com");
+    }
+
+    #[test]
+    fn test_invalid_toml() {
+        assert!(Config::from_toml("timeout = \"soon\"").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Human-friendly duration parsing.
+//!
+//! Accepts values like `300ms`, `30s`, `5m`, or `1h`. A bare number is
+//! taken as seconds.
+
+use std::time::Duration;
+
+/// Parses a duration such as `300ms`, `1.5s`, `2m`, or `1h`.
+///
+/// Used as a clap value parser for duration flags.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", s))?;
+
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        other => {
+            return Err(format!(
+                "invalid duration unit '{}' in '{}' (use ms, s, m, or h)",
+                other, s
+            ))
+        }
+    };
+
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("duration '{}' is out of range", s))
+}
+
+/// Formats a duration in the largest whole unit [`parse_duration`] accepts.
+///
+/// `90s` stays `90s`, `120s` becomes `2m`, and sub-second values use `ms`.
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    match millis {
+        0 => "0s".to_string(),
+        m if m % 3_600_000 == 0 => format!("{}h", m / 3_600_000),
+        m if m % 60_000 == 0 => format!("{}m", m / 60_000),
+        m if m % 1000 == 0 => format!("{}s", m / 1000),
+        m => format!("{}ms", m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_duration("300ms"), Ok(Duration::from_millis(300)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("10"), Ok(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+        assert_eq!(format_duration(Duration::from_secs(120)), "2m");
+        assert_eq!(format_duration(Duration::from_secs(7200)), "2h");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1500ms");
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("fast").is_err());
+        assert!(parse_duration("10d").is_err());
+    }
+
+    #[test]
+    fn test_parse_out_of_range() {
+        let err = parse_duration("99999999999999999999999h").unwrap_err();
+        assert!(err.contains("out of range"), "{}", err);
+        for input in ["NaN", "nan", "inf", "-inf", "infs"] {
+            assert!(parse_duration(input).is_err(), "{}", input);
+        }
+    }
+}
@@ -3,6 +3,8 @@
 //! This module defines custom error types using `thiserror` for clean
 //! error handling throughout the application.
 
+use serde::Serialize;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Main error type for rurl operations.
@@ -42,6 +44,200 @@ pub enum RurlError {
     /// Performance test execution error
     #[error("Performance test error: {0}")]
     PerfError(String),
+
+    /// Configuration file or profile error
+    #[error("Config error: {0}")]
+    ConfigError(String),
+
+    /// Template placeholder could not be rendered
+    #[error("Template error: {0}")]
+    TemplateError(String),
+
+    /// Response took longer than the configured slow threshold
+    #[error("Response time {0:.3}ms exceeded the slow threshold of {1:.3}ms")]
+    SlowResponse(f64, f64),
+
+    /// Performance test target is not on the host allowlist
+    #[error("Host not allowed for performance testing: {0} (add it to the allowlist or pass --force)")]
+    HostNotAllowed(String),
+
+    /// A destructive performance test was not confirmed
+    #[error("Destructive performance test not confirmed: {0} (pass --yes to run it anyway)")]
+    NotConfirmed(String),
+
+    /// Invalid `--resolve` entry or combination of entries
+    #[error("Resolve error: {0}")]
+    ResolveError(String),
+
+    /// Invalid `--filter` expression or body that could not be filtered
+    #[error("Filter error: {0}")]
+    FilterError(String),
+
+    /// The canary request sent before a performance run failed
+    #[error("Preflight request failed: {0} (pass --force to run anyway)")]
+    PreflightFailed(String),
+
+    /// A `--raw-request` could not be sent
+    #[error("Raw request failed: {0}")]
+    RawRequestError(String),
+
+    /// A distributed run failed on the controller or a worker
+    #[error("Distributed run failed: {0}")]
+    DistributedError(String),
+
+    /// Credentials for a request could not be obtained
+    #[error("Authentication failed: {0}")]
+    AuthError(String),
+
+    /// A compressed response body could not be decompressed
+    #[error("Failed to decompress response body: {0}")]
+    DecodeError(String),
+
+    /// The response body stalled for longer than the read timeout
+    #[error("Response body stalled: no data received for {0:?}")]
+    ReadTimeout(Duration),
+
+    /// Some of several requested URLs failed; each error was already printed
+    #[error("{0} of {1} requests failed")]
+    RequestsFailed(usize, usize),
+
+    /// One or more performance thresholds were not met
+    #[error("{0} of {1} performance thresholds failed: {2}")]
+    ThresholdsFailed(usize, usize, String),
+}
+
+impl RurlError {
+    /// Returns a short, stable identifier for the kind of error.
+    ///
+    /// Network failures are classified further into `timeout`, `dns`,
+    /// `connect`, `tls`, `redirect` and `body`, so tooling can branch on
+    /// the cause.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RurlError::RequestError(e) => {
+                if e.is_timeout() {
+                    "timeout"
+                } else if is_tls_error(e) {
+                    "tls"
+                } else if e.is_connect() && is_dns_error(e) {
+                    "dns"
+                } else if e.is_connect() {
+                    "connect"
+                } else if e.is_redirect() {
+                    "redirect"
+                } else if e.is_body() || e.is_decode() {
+                    "body"
+                } else {
+                    "request"
+                }
+            }
+            RurlError::InvalidUrl(_) => "invalid_url",
+            RurlError::InvalidMethod(_) => "invalid_method",
+            RurlError::InvalidHeader(_) => "invalid_header",
+            RurlError::FileError(_) => "file",
+            RurlError::JsonError(_) => "json",
+            RurlError::DatasetError(_) => "dataset",
+            RurlError::PerfError(_) => "perf",
+            RurlError::ConfigError(_) => "config",
+            RurlError::TemplateError(_) => "template",
+            RurlError::HostNotAllowed(_) => "host_not_allowed",
+            RurlError::NotConfirmed(_) => "not_confirmed",
+            RurlError::SlowResponse(..) => "slow_response",
+            RurlError::ResolveError(_) => "resolve",
+            RurlError::FilterError(_) => "filter",
+            RurlError::PreflightFailed(_) => "preflight",
+            RurlError::RawRequestError(_) => "raw_request",
+            RurlError::DistributedError(_) => "distributed",
+            RurlError::AuthError(_) => "auth",
+            RurlError::DecodeError(_) => "decode",
+            RurlError::ReadTimeout(_) => "timeout",
+            RurlError::RequestsFailed(..) => "requests_failed",
+            RurlError::ThresholdsFailed(..) => "thresholds_failed",
+        }
+    }
+
+    /// Returns the process exit code for this error.
+    ///
+    /// Most errors exit with `1`; a slow response or failed performance
+    /// threshold exits with `3` so scripts can tell a blown budget apart
+    /// from a failed request.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RurlError::SlowResponse(..) | RurlError::ThresholdsFailed(..) => 3,
+            _ => 1,
+        }
+    }
+
+    /// Returns the underlying OS error code, if the error came from the OS.
+    pub fn os_error_code(&self) -> Option<i32> {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(self);
+        while let Some(err) = source {
+            if let Some(code) = err.downcast_ref::<std::io::Error>().and_then(|io| io.raw_os_error()) {
+                return Some(code);
+            }
+            source = err.source();
+        }
+        None
+    }
+
+    /// Returns the URL the failed request was sent to, if known.
+    pub fn target(&self) -> Option<String> {
+        match self {
+            RurlError::RequestError(e) => e.url().map(|url| url.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Builds a structured report of this error for machine consumption.
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport {
+            kind: self.kind(),
+            message: self.to_string(),
+            os_error_code: self.os_error_code(),
+            target: self.target(),
+        }
+    }
+}
+
+/// Returns true if a request error was caused by TLS negotiation.
+fn is_tls_error(error: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(err) = source {
+        let message = err.to_string().to_lowercase();
+        if message.contains("ssl") || message.contains("tls") || message.contains("certificate") {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// Returns true if a request error was caused by failing to resolve the host.
+fn is_dns_error(error: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(err) = source {
+        let message = err.to_string();
+        if message.starts_with("dns error") || message.contains("failed to lookup address") {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// Structured description of an error, emitted by `--errors json`.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    /// Error kind, see [`RurlError::kind`]
+    pub kind: &'static str,
+    /// Human-readable error message
+    pub message: String,
+    /// OS error code (e.g. `111` for connection refused on Linux)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_error_code: Option<i32>,
+    /// URL the failed request was sent to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
 }
 
 /// Result type alias using [`RurlError`].
@@ -68,4 +264,37 @@ mod tests {
         let error = RurlError::DatasetError("empty file".to_string());
         assert!(error.to_string().contains("Dataset error"));
     }
+
+    #[test]
+    fn test_host_not_allowed_error() {
+        let error = RurlError::HostNotAllowed("prod.example.com".to_string());
+        assert!(error.to_string().contains("--force"));
+    }
+
+    #[test]
+    fn test_error_report() {
+        let error = RurlError::InvalidUrl("bad".to_string());
+        let report = error.report();
+        assert_eq!(report.kind, "invalid_url");
+        assert_eq!(report.os_error_code, None);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["kind"], "invalid_url");
+        assert!(json.get("target").is_none());
+    }
+
+    #[test]
+    fn test_slow_response_exit_code() {
+        let error = RurlError::SlowResponse(450.0, 300.0);
+        assert_eq!(error.exit_code(), 3);
+        assert_eq!(RurlError::PerfError("x".to_string()).exit_code(), 1);
+    }
+
+    #[test]
+    fn test_os_error_code() {
+        let io = std::io::Error::from_raw_os_error(2);
+        let error = RurlError::FileError(io);
+        assert_eq!(error.kind(), "file");
+        assert_eq!(error.os_error_code(), Some(2));
+    }
 }
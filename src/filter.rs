@@ -0,0 +1,250 @@
+//! JSONPath-style response filtering.
+//!
+//! Supports the subset of JSONPath (and the equivalent jq paths) needed to
+//! pull fields out of a response body: `$.data.items[0].id`,
+//! `.data.items[-1]`, `$['odd key']`, and `[*]` / `.*` wildcards.
+
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::error::{Result, RurlError};
+
+/// One step of a parsed path.
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// Object member by name
+    Key(String),
+    /// Array element; negative indices count from the end
+    Index(i64),
+    /// Every member of an object or element of an array
+    Wildcard,
+}
+
+/// A parsed JSONPath expression.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let path: JsonPath = "$.data.items[0].id".parse()?;
+/// let ids = path.select(&json);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    expr: String,
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    /// Returns every value in `root` matched by this path.
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![root];
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|value| select_segment(value, segment))
+                .collect();
+        }
+        current
+    }
+
+    /// Applies the path to a JSON response body and formats the matches.
+    ///
+    /// Each match is printed on its own line: strings raw, everything else
+    /// as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::FilterError`] if the body is not JSON or nothing
+    /// matches.
+    pub fn apply(&self, body: &str) -> Result<String> {
+        let json: Value = serde_json::from_str(body)
+            .map_err(|e| RurlError::FilterError(format!("response body is not JSON: {}", e)))?;
+
+        let matches = self.select(&json);
+        if matches.is_empty() {
+            return Err(RurlError::FilterError(format!("no match for '{}'", self.expr)));
+        }
+
+        let lines: Vec<String> = matches
+            .into_iter()
+            .map(|value| match value {
+                Value::String(s) => s.clone(),
+                other => serde_json::to_string_pretty(other).unwrap_or_else(|_| other.to_string()),
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+}
+
+impl JsonPath {
+    /// Applies the path to a JSON body, requiring exactly one match.
+    ///
+    /// Strings are returned raw, anything else as compact JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::FilterError`] if the body is not JSON or the
+    /// path matches nothing or several values.
+    pub fn extract_one(&self, body: &str) -> Result<String> {
+        let json: Value = serde_json::from_str(body)
+            .map_err(|e| RurlError::FilterError(format!("response body is not JSON: {}", e)))?;
+        match self.select(&json).as_slice() {
+            [Value::String(s)] => Ok(s.clone()),
+            [value] => Ok(value.to_string()),
+            [] => Err(RurlError::FilterError(format!("no match for '{}'", self.expr))),
+            matches => Err(RurlError::FilterError(format!(
+                "'{}' matched {} values, expected one",
+                self.expr,
+                matches.len()
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = RurlError;
+
+    /// Parses `$`-rooted JSONPath or a jq-style path starting with `.`.
+    fn from_str(s: &str) -> Result<Self> {
+        let expr = s.trim();
+        let invalid = |reason: &str| RurlError::FilterError(format!("invalid filter '{}': {}", expr, reason));
+
+        let rest = expr.strip_prefix('$').unwrap_or(expr);
+        if rest.len() == expr.len() && !rest.is_empty() && !rest.starts_with('.') && !rest.starts_with('[') {
+            return Err(invalid("must start with '$' or '.'"));
+        }
+
+        let chars: Vec<char> = rest.chars().collect();
+        let mut segments = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '.' => {
+                    i += 1;
+                    let start = i;
+                    while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                        i += 1;
+                    }
+                    let name: String = chars[start..i].iter().collect();
+                    match name.as_str() {
+                        // A bare `.` (jq identity) or `.[0]`
+                        "" if i == chars.len() || chars[i] == '[' => {}
+                        "" => return Err(invalid("empty key")),
+                        "*" => segments.push(Segment::Wildcard),
+                        _ => segments.push(Segment::Key(name)),
+                    }
+                }
+                '[' => {
+                    let close = chars[i..]
+                        .iter()
+                        .position(|&c| c == ']')
+                        .map(|offset| i + offset)
+                        .ok_or_else(|| invalid("unclosed '['"))?;
+                    let inner: String = chars[i + 1..close].iter().collect();
+                    let inner = inner.trim();
+                    let segment = if inner == "*" {
+                        Segment::Wildcard
+                    } else if let Some(key) = unquote(inner) {
+                        Segment::Key(key.to_string())
+                    } else {
+                        Segment::Index(inner.parse().map_err(|_| invalid("expected an index, '*' or a quoted key in brackets"))?)
+                    };
+                    segments.push(segment);
+                    i = close + 1;
+                }
+                c => return Err(invalid(&format!("unexpected '{}'", c))),
+            }
+        }
+
+        Ok(Self {
+            expr: expr.to_string(),
+            segments,
+        })
+    }
+}
+
+/// Strips matching single or double quotes.
+fn unquote(s: &str) -> Option<&str> {
+    ['\'', '"'].iter().find_map(|&q| s.strip_prefix(q)?.strip_suffix(q))
+}
+
+fn select_segment<'a>(value: &'a Value, segment: &Segment) -> Vec<&'a Value> {
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+        (Segment::Index(index), Value::Array(items)) => {
+            let len = items.len() as i64;
+            let index = if *index < 0 { len + index } else { *index };
+            if (0..len).contains(&index) {
+                vec![&items[index as usize]]
+            } else {
+                Vec::new()
+            }
+        }
+        (Segment::Wildcard, Value::Array(items)) => items.iter().collect(),
+        (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        json!({
+            "data": {
+                "items": [
+                    {"id": 1, "name": "first"},
+                    {"id": 2, "name": "second"}
+                ],
+                "odd key": true
+            }
+        })
+    }
+
+    fn select(expr: &str) -> Vec<Value> {
+        let path: JsonPath = expr.parse().unwrap();
+        path.select(&sample()).into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_select_paths() {
+        assert_eq!(select("$.data.items[0].id"), vec![json!(1)]);
+        assert_eq!(select(".data.items[-1].name"), vec![json!("second")]);
+        assert_eq!(select("$.data.items[*].id"), vec![json!(1), json!(2)]);
+        assert_eq!(select("$['data']['odd key']"), vec![json!(true)]);
+        assert_eq!(select("."), vec![sample()]);
+        assert!(select("$.data.items[5]").is_empty());
+        assert!(select("$.missing.path").is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("data.items".parse::<JsonPath>().is_err());
+        assert!("$.data[0".parse::<JsonPath>().is_err());
+        assert!("$.data[abc]".parse::<JsonPath>().is_err());
+        assert!("$..data".parse::<JsonPath>().is_err());
+    }
+
+    #[test]
+    fn test_apply_formats_matches() {
+        let body = sample().to_string();
+        let names: JsonPath = "$.data.items[*].name".parse().unwrap();
+        assert_eq!(names.apply(&body).unwrap(), "first\nsecond");
+
+        let item: JsonPath = "$.data.items[0]".parse().unwrap();
+        assert!(item.apply(&body).unwrap().contains("\"id\": 1"));
+
+        assert!(names.apply("not json").is_err());
+        let missing: JsonPath = "$.nope".parse().unwrap();
+        assert!(missing.apply(&body).is_err());
+    }
+}
@@ -0,0 +1,643 @@
+//! Authentication providers (`--auth`).
+//!
+//! Every way of authenticating a request goes through [`AuthProvider`]:
+//! before each send, single request and performance test alike, the
+//! request's [`AuthChain`] asks its providers for the headers to add.
+//! Providers cache what they can, so a token is fetched once and reused by
+//! every request of a run until it expires. When the server answers `401`,
+//! the chain drops cached credentials and the request is sent once more
+//! with fresh ones.
+//!
+//! Built-in providers, as given to `--auth`:
+//!
+//! | Spec | Provider |
+//! |------|----------|
+//! | `basic:USER:PASSWORD` | [`Basic`] |
+//! | `bearer:TOKEN` | [`Bearer`] |
+//! | `oauth2:token_url=URL,client_id=ID,client_secret=SECRET[,scope=SCOPE]` | [`OAuth2`] client credentials grant |
+//! | `sigv4:region=REGION,service=SERVICE` | [`SigV4`] with credentials from `AWS_*` variables |
+//! | `script:COMMAND` | [`Script`] |
+//!
+//! Anything else can be plugged in as a script, or by implementing
+//! [`AuthProvider`] when using hurley as a library.
+
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::Client;
+use serde::Deserialize;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use tokio::sync::Mutex;
+
+use crate::error::{Result, RurlError};
+use super::request::PreparedRequest;
+use super::sigv4::SigV4;
+
+/// Headers a provider adds to a request.
+pub type Credentials = Vec<(HeaderName, HeaderValue)>;
+
+/// curl options as option and unquoted value, like `("-u", "user:password")`.
+pub type CurlArgs = Vec<(&'static str, String)>;
+
+/// Future returned by [`AuthProvider::authorize`].
+pub type AuthFuture<'a> = Pin<Box<dyn Future<Output = Result<Credentials>> + Send + 'a>>;
+
+/// A source of credentials for requests.
+pub trait AuthProvider: Send + Sync {
+    /// Short name shown in verbose output and errors (e.g. `oauth2`).
+    fn name(&self) -> &'static str;
+
+    /// Returns the headers to add to `request`, from the cache if possible.
+    fn authorize<'a>(&'a self, request: &'a PreparedRequest) -> AuthFuture<'a>;
+
+    /// Drops cached credentials after the server rejected them.
+    ///
+    /// Returns true if anything was dropped, so retrying may help.
+    fn invalidate(&self) -> bool {
+        false
+    }
+
+    /// Returns the curl options that authenticate the same way, or `None`
+    /// if curl has none.
+    fn curl_args(&self) -> Option<CurlArgs> {
+        None
+    }
+}
+
+/// Providers applied to a request in order; a later provider's header
+/// replaces an earlier one of the same name.
+#[derive(Default)]
+pub struct AuthChain {
+    providers: Vec<Box<dyn AuthProvider>>,
+}
+
+impl AuthChain {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a chain from `--auth` specs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::AuthError`] if a spec is malformed or its
+    /// provider cannot be set up.
+    pub fn from_specs(specs: &[String]) -> Result<Self> {
+        specs.iter().try_fold(Self::new(), |chain, spec| Ok(chain.provider(spec.parse::<AuthSpec>()?.build()?)))
+    }
+
+    /// Appends a provider.
+    pub fn provider(mut self, provider: Box<dyn AuthProvider>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    /// Returns true if the chain has no providers.
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Returns the headers every provider adds to `request`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first provider error.
+    pub async fn authorize(&self, request: &PreparedRequest) -> Result<Credentials> {
+        let mut credentials = Credentials::new();
+        for provider in &self.providers {
+            credentials.extend(provider.authorize(request).await?);
+        }
+        Ok(credentials)
+    }
+
+    /// Returns the curl options that authenticate `request` as the chain
+    /// does, for `--as-curl`.
+    ///
+    /// Providers curl has no options for, such as OAuth2 and scripts, are
+    /// asked for credentials, which are passed as headers.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first provider error.
+    pub async fn curl_args(&self, request: &PreparedRequest) -> Result<CurlArgs> {
+        let mut args = CurlArgs::new();
+        for provider in &self.providers {
+            match provider.curl_args() {
+                Some(provider_args) => args.extend(provider_args),
+                None => {
+                    for (name, value) in provider.authorize(request).await? {
+                        args.push(("-H", format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes()))));
+                    }
+                }
+            }
+        }
+        Ok(args)
+    }
+
+    /// Drops every provider's cached credentials.
+    ///
+    /// Returns true if any were dropped.
+    pub fn invalidate(&self) -> bool {
+        // Every provider, not just up to the first that drops something
+        self.providers.iter().filter(|provider| provider.invalidate()).count() > 0
+    }
+}
+
+impl fmt::Debug for AuthChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.providers.iter().map(|p| p.name())).finish()
+    }
+}
+
+/// A parsed `--auth` value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthSpec {
+    /// `basic:USER:PASSWORD`
+    Basic { user: String, password: String },
+    /// `bearer:TOKEN`
+    Bearer(String),
+    /// `oauth2:token_url=URL,client_id=ID,client_secret=SECRET[,scope=SCOPE]`
+    OAuth2 { token_url: String, client_id: String, client_secret: String, scope: Option<String> },
+    /// `sigv4:region=REGION,service=SERVICE`
+    SigV4 { region: String, service: String },
+    /// `script:COMMAND`
+    Script(String),
+}
+
+impl FromStr for AuthSpec {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // The value is left out of errors, as it usually holds a secret
+        let invalid = |reason: &str| RurlError::AuthError(format!("invalid --auth value: {}", reason));
+        let (kind, rest) = s.split_once(':').ok_or_else(|| invalid("expected KIND:..."))?;
+        match kind {
+            "basic" => {
+                let (user, password) = rest.split_once(':').ok_or_else(|| invalid("expected basic:USER:PASSWORD"))?;
+                Ok(AuthSpec::Basic { user: user.to_string(), password: password.to_string() })
+            }
+            "bearer" if !rest.is_empty() => Ok(AuthSpec::Bearer(rest.to_string())),
+            "bearer" => Err(invalid("expected bearer:TOKEN")),
+            "oauth2" | "sigv4" => {
+                let mut params = Vec::new();
+                for pair in rest.split(',').filter(|pair| !pair.is_empty()) {
+                    let (key, value) = pair.split_once('=').ok_or_else(|| invalid("expected key=value"))?;
+                    params.push((key.trim(), value.trim().to_string()));
+                }
+                let known: &[&str] = if kind == "oauth2" {
+                    &["token_url", "client_id", "client_secret", "scope"]
+                } else {
+                    &["region", "service"]
+                };
+                if let Some((key, _)) = params.iter().find(|(key, _)| !known.contains(key)) {
+                    return Err(invalid(&format!("unknown parameter '{}'", key)));
+                }
+                let get = |key: &str| params.iter().find(|(k, _)| *k == key).map(|(_, v)| v.clone());
+                let require = |key: &str| get(key).ok_or_else(|| invalid(&format!("missing {}", key)));
+                if kind == "oauth2" {
+                    Ok(AuthSpec::OAuth2 {
+                        token_url: require("token_url")?,
+                        client_id: require("client_id")?,
+                        client_secret: require("client_secret")?,
+                        scope: get("scope"),
+                    })
+                } else {
+                    Ok(AuthSpec::SigV4 { region: require("region")?, service: require("service")? })
+                }
+            }
+            "script" if !rest.trim().is_empty() => Ok(AuthSpec::Script(rest.trim().to_string())),
+            "script" => Err(invalid("expected script:COMMAND")),
+            _ => Err(invalid("expected one of basic, bearer, oauth2, sigv4, script")),
+        }
+    }
+}
+
+impl AuthSpec {
+    /// Sets up the provider.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::AuthError`] if the credentials cannot be sent
+    /// in a header, or SigV4 credentials are missing from the environment.
+    pub fn build(self) -> Result<Box<dyn AuthProvider>> {
+        Ok(match self {
+            AuthSpec::Basic { user, password } => Box::new(Basic::new(&user, &password)?),
+            AuthSpec::Bearer(token) => Box::new(Bearer::new(&token)?),
+            AuthSpec::OAuth2 { token_url, client_id, client_secret, scope } => {
+                Box::new(OAuth2::new(token_url, client_id, client_secret, scope))
+            }
+            AuthSpec::SigV4 { region, service } => Box::new(SigV4::from_env(&region, &service)?),
+            AuthSpec::Script(command) => Box::new(Script::new(command)),
+        })
+    }
+}
+
+fn header_value(value: &str, what: &str) -> Result<HeaderValue> {
+    let mut value = HeaderValue::from_str(value)
+        .map_err(|_| RurlError::AuthError(format!("{} cannot be sent in a header", what)))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// HTTP Basic authentication.
+pub struct Basic {
+    user_password: String,
+    header: HeaderValue,
+}
+
+impl Basic {
+    /// # Errors
+    ///
+    /// Returns [`RurlError::AuthError`] if the credentials hold control characters.
+    pub fn new(user: &str, password: &str) -> Result<Self> {
+        let user_password = format!("{}:{}", user, password);
+        let header = header_value(&format!("Basic {}", STANDARD.encode(&user_password)), "basic credentials")?;
+        Ok(Self { user_password, header })
+    }
+}
+
+impl AuthProvider for Basic {
+    fn name(&self) -> &'static str {
+        "basic"
+    }
+
+    fn authorize<'a>(&'a self, _request: &'a PreparedRequest) -> AuthFuture<'a> {
+        Box::pin(async move { Ok(vec![(AUTHORIZATION, self.header.clone())]) })
+    }
+
+    fn curl_args(&self) -> Option<CurlArgs> {
+        Some(vec![("-u", self.user_password.clone())])
+    }
+}
+
+/// A fixed bearer token.
+pub struct Bearer {
+    header: HeaderValue,
+}
+
+impl Bearer {
+    /// # Errors
+    ///
+    /// Returns [`RurlError::AuthError`] if the token holds control characters.
+    pub fn new(token: &str) -> Result<Self> {
+        Ok(Self { header: header_value(&format!("Bearer {}", token), "bearer token")? })
+    }
+}
+
+impl AuthProvider for Bearer {
+    fn name(&self) -> &'static str {
+        "bearer"
+    }
+
+    fn authorize<'a>(&'a self, _request: &'a PreparedRequest) -> AuthFuture<'a> {
+        Box::pin(async move { Ok(vec![(AUTHORIZATION, self.header.clone())]) })
+    }
+
+    fn curl_args(&self) -> Option<CurlArgs> {
+        Some(vec![("-H", format!("Authorization: {}", String::from_utf8_lossy(self.header.as_bytes())))])
+    }
+}
+
+/// How long before its expiry a token is refreshed, so it doesn't expire
+/// while a request is in flight.
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// OAuth 2.0 client credentials grant (RFC 6749 section 4.4).
+///
+/// The token is fetched on first use and shared by all requests until it
+/// is within a few seconds of `expires_in`; a token without `expires_in`
+/// is kept until the server rejects it. Concurrent requests wait for a
+/// single fetch instead of each fetching their own.
+pub struct OAuth2 {
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+    scope: Option<String>,
+    client: Client,
+    token: Mutex<Option<(HeaderValue, Option<Instant>)>>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+impl OAuth2 {
+    /// Creates a provider fetching tokens from `token_url`.
+    pub fn new(token_url: String, client_id: String, client_secret: String, scope: Option<String>) -> Self {
+        Self {
+            token_url,
+            client_id,
+            client_secret,
+            scope,
+            client: Client::new(),
+            token: Mutex::new(None),
+        }
+    }
+
+    async fn fetch(&self) -> Result<(HeaderValue, Option<Instant>)> {
+        let mut form = vec![
+            ("grant_type", "client_credentials".to_string()),
+            ("client_id", self.client_id.clone()),
+            ("client_secret", self.client_secret.clone()),
+        ];
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope.clone()));
+        }
+        let request = self.client.post(&self.token_url).form(&form);
+        // A task of its own, so the token endpoint's connection isn't
+        // timed as the request's
+        let response = tokio::spawn(async move {
+            let response = request.send().await?;
+            let status = response.status();
+            Ok::<_, reqwest::Error>((status, response.text().await?))
+        })
+        .await
+        .map_err(|e| RurlError::AuthError(format!("token request aborted: {}", e)))?;
+        let (status, body) = response.map_err(|e| RurlError::AuthError(format!("token request failed: {}", e)))?;
+        if !status.is_success() {
+            return Err(RurlError::AuthError(format!("token endpoint returned {}: {}", status, body.trim())));
+        }
+        let token: TokenResponse = serde_json::from_str(&body)
+            .map_err(|e| RurlError::AuthError(format!("bad token response: {}", e)))?;
+        let expires = token.expires_in.map(|secs| Instant::now() + Duration::from_secs(secs));
+        Ok((header_value(&format!("Bearer {}", token.access_token), "access token")?, expires))
+    }
+}
+
+impl AuthProvider for OAuth2 {
+    fn name(&self) -> &'static str {
+        "oauth2"
+    }
+
+    fn authorize<'a>(&'a self, _request: &'a PreparedRequest) -> AuthFuture<'a> {
+        Box::pin(async move {
+            let mut token = self.token.lock().await;
+            let fresh = |expires: &Option<Instant>| expires.is_none_or(|at| Instant::now() + REFRESH_MARGIN < at);
+            match &*token {
+                Some((header, expires)) if fresh(expires) => Ok(vec![(AUTHORIZATION, header.clone())]),
+                _ => {
+                    let fetched = self.fetch().await?;
+                    let header = fetched.0.clone();
+                    *token = Some(fetched);
+                    Ok(vec![(AUTHORIZATION, header)])
+                }
+            }
+        })
+    }
+
+    fn invalidate(&self) -> bool {
+        // A request holding the lock is fetching a new token already
+        self.token.try_lock().is_ok_and(|mut token| token.take().is_some())
+    }
+}
+
+impl AuthProvider for SigV4 {
+    fn name(&self) -> &'static str {
+        "sigv4"
+    }
+
+    fn authorize<'a>(&'a self, request: &'a PreparedRequest) -> AuthFuture<'a> {
+        Box::pin(async move {
+            let body = request.body().map_or(&[][..], |body| &body[..]);
+            let signed = self.sign(request.request().method.as_str(), &request.request().url, body, chrono::Utc::now())?;
+            signed
+                .into_iter()
+                .map(|(name, value)| Ok((HeaderName::from_static(name), header_value(&value, "signature")?)))
+                .collect()
+        })
+    }
+
+    fn curl_args(&self) -> Option<CurlArgs> {
+        Some(self.curl_args())
+    }
+}
+
+/// Credentials printed by a command.
+///
+/// The command runs through the shell on first use. Each `Name: value`
+/// line it prints is added as a header; output that is a single line
+/// without a header name is sent as a bearer token. The output is reused
+/// until the server rejects it, then the command runs again.
+pub struct Script {
+    command: String,
+    cached: Mutex<Option<Credentials>>,
+}
+
+impl Script {
+    /// Creates a provider running `command`.
+    pub fn new(command: impl Into<String>) -> Self {
+        Self { command: command.into(), cached: Mutex::new(None) }
+    }
+
+    async fn run(&self) -> Result<Credentials> {
+        let mut command = if cfg!(windows) {
+            let mut command = tokio::process::Command::new("cmd");
+            command.arg("/C");
+            command
+        } else {
+            let mut command = tokio::process::Command::new("sh");
+            command.arg("-c");
+            command
+        };
+        let output = command
+            .arg(&self.command)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .await
+            .map_err(|e| RurlError::AuthError(format!("cannot run '{}': {}", self.command, e)))?;
+        if !output.status.success() {
+            return Err(RurlError::AuthError(format!(
+                "'{}' failed ({}): {}",
+                self.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        parse_script_output(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// Reads the headers printed by an auth script.
+fn parse_script_output(output: &str) -> Result<Credentials> {
+    let lines: Vec<&str> = output.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    fn header(line: &str) -> Option<(HeaderName, &str)> {
+        let (name, value) = line.split_once(':')?;
+        Some((HeaderName::from_bytes(name.trim().as_bytes()).ok()?, value.trim()))
+    }
+    match lines.as_slice() {
+        [] => Err(RurlError::AuthError("auth script printed nothing".to_string())),
+        [token] if header(token).is_none() => Ok(vec![(AUTHORIZATION, header_value(&format!("Bearer {}", token), "token")?)]),
+        lines => lines
+            .iter()
+            .map(|line| {
+                let (name, value) = header(line)
+                    .ok_or_else(|| RurlError::AuthError(format!("auth script printed a bad header line: {}", line)))?;
+                Ok((name, header_value(value, "header")?))
+            })
+            .collect(),
+    }
+}
+
+impl AuthProvider for Script {
+    fn name(&self) -> &'static str {
+        "script"
+    }
+
+    fn authorize<'a>(&'a self, _request: &'a PreparedRequest) -> AuthFuture<'a> {
+        Box::pin(async move {
+            let mut cached = self.cached.lock().await;
+            if let Some(credentials) = &*cached {
+                return Ok(credentials.clone());
+            }
+            let credentials = self.run().await?;
+            *cached = Some(credentials.clone());
+            Ok(credentials)
+        })
+    }
+
+    fn invalidate(&self) -> bool {
+        self.cached.try_lock().is_ok_and(|mut cached| cached.take().is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpRequest;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    fn prepared() -> PreparedRequest {
+        HttpRequest::new("https://api.example.com/users").prepare().unwrap()
+    }
+
+    #[test]
+    fn test_parse_specs() {
+        assert_eq!(
+            "basic:alice:pa:ss".parse::<AuthSpec>().unwrap(),
+            AuthSpec::Basic { user: "alice".to_string(), password: "pa:ss".to_string() }
+        );
+        assert_eq!("bearer:abc".parse::<AuthSpec>().unwrap(), AuthSpec::Bearer("abc".to_string()));
+        assert_eq!(
+            "oauth2:token_url=https://idp/token,client_id=id,client_secret=s".parse::<AuthSpec>().unwrap(),
+            AuthSpec::OAuth2 {
+                token_url: "https://idp/token".to_string(),
+                client_id: "id".to_string(),
+                client_secret: "s".to_string(),
+                scope: None,
+            }
+        );
+        assert_eq!(
+            "sigv4:region=eu-west-1,service=execute-api".parse::<AuthSpec>().unwrap(),
+            AuthSpec::SigV4 { region: "eu-west-1".to_string(), service: "execute-api".to_string() }
+        );
+        assert_eq!("script:./token.sh --env dev".parse::<AuthSpec>().unwrap(), AuthSpec::Script("./token.sh --env dev".to_string()));
+
+        for bad in ["basic", "basic:alice", "bearer:", "oauth2:client_id=x", "sigv4:region=x,zone=y", "digest:x"] {
+            assert!(bad.parse::<AuthSpec>().is_err(), "{}", bad);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_later_provider_wins() {
+        let chain = AuthChain::from_specs(&["basic:alice:secret".to_string()]).unwrap();
+        let credentials = chain.authorize(&prepared()).await.unwrap();
+        assert_eq!(credentials, vec![(AUTHORIZATION, HeaderValue::from_static("Basic YWxpY2U6c2VjcmV0"))]);
+        assert!(!chain.invalidate());
+
+        let chain = chain.provider(Box::new(Bearer::new("t0k").unwrap()));
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in chain.authorize(&prepared()).await.unwrap() {
+            headers.insert(name, value);
+        }
+        assert_eq!(headers[AUTHORIZATION], "Bearer t0k");
+        assert_eq!(format!("{:?}", chain), r#"["basic", "bearer"]"#);
+    }
+
+    #[tokio::test]
+    async fn test_curl_args() {
+        let chain = AuthChain::new()
+            .provider(Box::new(Basic::new("alice", "secret").unwrap()))
+            .provider(Box::new(Bearer::new("t0k").unwrap()))
+            .provider(Box::new(SigV4::new("AKID", "SK", "eu-west-1", "execute-api").session_token(Some("tok".to_string()))))
+            .provider(Box::new(Script::new("echo 'X-Api-Key: k1'")));
+        assert_eq!(
+            chain.curl_args(&prepared()).await.unwrap(),
+            vec![
+                ("-u", "alice:secret".to_string()),
+                ("-H", "Authorization: Bearer t0k".to_string()),
+                ("--aws-sigv4", "aws:amz:eu-west-1:execute-api".to_string()),
+                ("-u", "AKID:SK".to_string()),
+                ("-H", "x-amz-security-token: tok".to_string()),
+                ("-H", "x-api-key: k1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_script_output() {
+        assert_eq!(parse_script_output("abc.def\n").unwrap()[0].1, "Bearer abc.def");
+        let credentials = parse_script_output("Authorization: Token x\nX-Api-Key: k\n").unwrap();
+        assert_eq!(credentials.len(), 2);
+        assert_eq!(credentials[1].0, "x-api-key");
+        assert!(parse_script_output("").is_err());
+        assert!(parse_script_output("a b\nc d").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_script_cached_until_invalidated() {
+        let counter = std::env::temp_dir().join("hurley_test_auth_script_runs");
+        std::fs::remove_file(&counter).ok();
+        let script = Script::new(format!("echo run >> {} && echo tok", counter.display()));
+        script.authorize(&prepared()).await.unwrap();
+        script.authorize(&prepared()).await.unwrap();
+        assert!(script.invalidate());
+        let credentials = script.authorize(&prepared()).await.unwrap();
+        let runs = std::fs::read_to_string(&counter).unwrap().lines().count();
+        std::fs::remove_file(&counter).ok();
+        assert_eq!(runs, 2);
+        assert_eq!(credentials[0].1, "Bearer tok");
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_token_cached() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            // Serves exactly one token request
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 4096];
+            while !String::from_utf8_lossy(&request).contains("scope=read") {
+                let n = stream.read(&mut chunk).unwrap();
+                assert!(n > 0);
+                request.extend_from_slice(&chunk[..n]);
+            }
+            let body = r#"{"access_token":"tk1","token_type":"Bearer","expires_in":3600}"#;
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        let provider = OAuth2::new(format!("http://{}/token", addr), "id".to_string(), "secret".to_string(), Some("read".to_string()));
+        for _ in 0..3 {
+            let credentials = provider.authorize(&prepared()).await.unwrap();
+            assert_eq!(credentials[0].1, "Bearer tk1");
+        }
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /token"));
+        assert!(request.contains("grant_type=client_credentials"));
+        assert!(provider.invalidate());
+        assert!(provider.authorize(&prepared()).await.is_err());
+    }
+}
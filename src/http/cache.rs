@@ -0,0 +1,496 @@
+//! On-disk HTTP cache (`--cache`), following RFC 7234 for a private cache.
+//!
+//! A `GET` response is stored when `Cache-Control`, `Expires` or its
+//! validators make it reusable, one file per URL. A later request for the
+//! same URL is then:
+//!
+//! - a **hit**, answered from the cache without contacting the server,
+//!   while the stored response is fresh (`max-age`, `Expires`, or 10% of
+//!   the time since `Last-Modified`, at most a day)
+//! - **revalidated** with `If-None-Match`/`If-Modified-Since` once it is
+//!   stale or marked `no-cache`; a `304 Not Modified` refreshes the stored
+//!   response, anything else replaces it
+//! - a **miss** otherwise, fetched and stored
+//!
+//! `no-store` on either side keeps a response out of the cache, and a
+//! request's own `no-cache` or `max-age` forces revalidation. Responses
+//! varying on request headers (`Vary`) are only reused for matching
+//! requests; `Vary: *` is never stored. A successful `POST`, `PUT`,
+//! `PATCH` or `DELETE` drops the stored response for its URL.
+//!
+//! Requests carrying their own validators or a `Range`, and downloads to a
+//! file, bypass the cache.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bytes::Bytes;
+use chrono::DateTime;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::encode::hex;
+use crate::error::Result;
+use super::request::HttpRequest;
+use super::response::HttpResponse;
+
+/// Status codes a response may be cached with on heuristic freshness
+/// alone (RFC 7231 section 6.1).
+const HEURISTICALLY_CACHEABLE: [u16; 11] = [200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// Longest heuristic freshness given to a response with only `Last-Modified`.
+const MAX_HEURISTIC_FRESHNESS: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Response headers not taken over from a `304 Not Modified`.
+const KEPT_ON_REVALIDATION: [&str; 3] = ["content-length", "content-encoding", "transfer-encoding"];
+
+/// How a request was answered with respect to the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatus {
+    /// Answered from a fresh stored response
+    Hit,
+    /// A stale stored response the server confirmed with a 304
+    Revalidated,
+    /// Fetched from the server, and stored if cacheable
+    Miss,
+}
+
+impl fmt::Display for CacheStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CacheStatus::Hit => "hit",
+            CacheStatus::Revalidated => "revalidated",
+            CacheStatus::Miss => "miss",
+        })
+    }
+}
+
+/// Returns hurley's default cache directory.
+///
+/// Uses `$XDG_CACHE_HOME/hurley`, falling back to `~/.cache/hurley`.
+pub fn default_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("hurley"))
+}
+
+/// An HTTP cache stored in a directory.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let cache = HttpCache::open(cache::default_dir().unwrap())?;
+/// let request = HttpRequest::new("https://example.com/catalog").cache(Some(cache));
+/// let response = HttpClient::new(false).execute(&request).await?;
+/// println!("{:?}", response.cache);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpCache {
+    dir: PathBuf,
+}
+
+impl HttpCache {
+    /// Opens the cache in `dir`, creating the directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory can't be created.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Returns the directory the cache is stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns true if the cache may answer or store the response to `request`.
+    pub(crate) fn applies_to(request: &HttpRequest) -> bool {
+        request.method == Method::GET
+            && !request.is_conditional()
+            && !request.has_header("Range")
+            && !request_directives(request).has("no-store")
+    }
+
+    /// Returns the stored response for a request, if there is one the
+    /// request's `Vary` headers match.
+    pub(crate) async fn lookup(&self, request: &HttpRequest) -> Option<CachedResponse> {
+        let data = tokio::fs::read(self.path(&request.url)).await.ok()?;
+        let entry: Entry = serde_json::from_slice(&data).ok()?;
+        let matches = entry.url == request.url
+            && entry.vary.iter().all(|(name, value)| request.header_value(name) == value.as_deref());
+        matches.then_some(CachedResponse { entry })
+    }
+
+    /// Stores the response to `request` if it may be reused, returning
+    /// whether it was stored.
+    ///
+    /// `body` is the body as it will be served, already decompressed if
+    /// the request asked for that.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry can't be written.
+    pub(crate) async fn store(&self, request: &HttpRequest, response: &HttpResponse, body: &[u8]) -> Result<bool> {
+        if !is_storable(&response.status, &response.headers) {
+            return Ok(false);
+        }
+        let vary = header_values(&response.headers, "vary")
+            .flat_map(|value| value.split(','))
+            .map(|name| name.trim().to_ascii_lowercase())
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                let value = request.header_value(&name).map(str::to_string);
+                (name, value)
+            })
+            .collect();
+        // The body is kept decoded, so its encoding no longer applies
+        let decoded = response.decompressed.is_some();
+        let headers = response
+            .headers
+            .iter()
+            .filter(|(name, _)| !decoded || !matches!(name.as_str(), "content-encoding" | "content-length"))
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let entry = Entry {
+            url: request.url.clone(),
+            status: response.status.as_u16(),
+            headers,
+            vary,
+            body: STANDARD.encode(body),
+            stored_at_ms: now_ms(),
+        };
+        self.save(&entry).await?;
+        Ok(true)
+    }
+
+    /// Stores a response refreshed by a `304 Not Modified`.
+    pub(crate) async fn save_refreshed(&self, cached: &CachedResponse) -> Result<()> {
+        self.save(&cached.entry).await
+    }
+
+    /// Drops the stored response for the URL of an unsafe request the
+    /// server accepted (RFC 7234 section 4.4).
+    pub(crate) async fn invalidate_after(&self, request: &HttpRequest, status: StatusCode) {
+        let unsafe_method = !matches!(request.method, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE);
+        if unsafe_method && (status.is_success() || status.is_redirection()) {
+            let _ = tokio::fs::remove_file(self.path(&request.url)).await;
+        }
+    }
+
+    async fn save(&self, entry: &Entry) -> Result<()> {
+        let path = self.path(&entry.url);
+        // Written aside and renamed, so concurrent readers never see half an entry
+        let partial = path.with_extension(format!("{}-{}.tmp", std::process::id(), rand::random::<u32>()));
+        tokio::fs::write(&partial, serde_json::to_vec(entry)?).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", &hex(&Sha256::digest(url.as_bytes()))[..32]))
+    }
+}
+
+/// A stored response, as written to disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Request headers named by `Vary`, lowercased, with the values they
+    /// had (`None` if absent)
+    vary: Vec<(String, Option<String>)>,
+    /// Body, base64-encoded
+    body: String,
+    /// When the response was received, in milliseconds since the Unix epoch
+    stored_at_ms: u64,
+}
+
+/// A response found in the cache.
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    entry: Entry,
+}
+
+impl CachedResponse {
+    /// Returns true if the response may be served without asking the
+    /// server, as far as it and the request's `Cache-Control` allow.
+    pub(crate) fn is_fresh(&self, request: &HttpRequest) -> bool {
+        let request = request_directives(request);
+        if request.has("no-cache") {
+            return false;
+        }
+        let headers = self.headers();
+        let age = self.age(&headers);
+        let fresh = age < freshness_lifetime(StatusCode::from_u16(self.entry.status).ok(), &headers);
+        let young_enough = request.seconds("max-age").is_none_or(|max_age| age <= max_age);
+        fresh && young_enough && !Directives::of(&headers).has("no-cache")
+    }
+
+    /// Returns the request revalidating this response, or `None` if it has
+    /// no validators.
+    pub(crate) fn revalidation(&self, request: &HttpRequest) -> Option<HttpRequest> {
+        let headers = self.headers();
+        let etag = headers.get("etag").and_then(|v| v.to_str().ok());
+        let last_modified = headers.get("last-modified").and_then(|v| v.to_str().ok());
+        if etag.is_none() && last_modified.is_none() {
+            return None;
+        }
+        let mut request = request.clone();
+        if let Some(etag) = etag {
+            request = request.header("If-None-Match", etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request = request.header("If-Modified-Since", last_modified);
+        }
+        Some(request)
+    }
+
+    /// Takes over the headers of a `304 Not Modified` and restarts the
+    /// response's age (RFC 7234 section 4.3.4).
+    pub(crate) fn refresh(mut self, not_modified: &HeaderMap) -> Self {
+        for name in not_modified.keys() {
+            if KEPT_ON_REVALIDATION.contains(&name.as_str()) {
+                continue;
+            }
+            self.entry.headers.retain(|(stored, _)| !stored.eq_ignore_ascii_case(name.as_str()));
+            for value in not_modified.get_all(name).iter().filter_map(|v| v.to_str().ok()) {
+                self.entry.headers.push((name.to_string(), value.to_string()));
+            }
+        }
+        self.entry.stored_at_ms = now_ms();
+        self
+    }
+
+    /// Returns the stored status, headers (with `Age` set) and body.
+    pub(crate) fn response(&self) -> (StatusCode, HeaderMap, Bytes) {
+        let mut headers = self.headers();
+        let age = self.age(&headers);
+        headers.insert("age", HeaderValue::from(age.as_secs()));
+        let status = StatusCode::from_u16(self.entry.status).unwrap_or(StatusCode::OK);
+        let body = STANDARD.decode(&self.entry.body).unwrap_or_default();
+        (status, headers, Bytes::from(body))
+    }
+
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::with_capacity(self.entry.headers.len());
+        for (name, value) in &self.entry.headers {
+            if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+                headers.append(name, value);
+            }
+        }
+        headers
+    }
+
+    /// Time since the response was generated: its `Age` when stored plus
+    /// the time spent in the cache (RFC 7234 section 4.2.3, simplified).
+    fn age(&self, headers: &HeaderMap) -> Duration {
+        let age_header = headers
+            .get("age")
+            .and_then(|v| v.to_str().ok()?.trim().parse::<u64>().ok())
+            .map_or(Duration::ZERO, Duration::from_secs);
+        let resident = Duration::from_millis(now_ms().saturating_sub(self.entry.stored_at_ms));
+        age_header + resident
+    }
+}
+
+/// Parsed `Cache-Control` directives.
+struct Directives(Vec<(String, Option<String>)>);
+
+impl Directives {
+    fn parse<'a>(values: impl Iterator<Item = &'a str>) -> Self {
+        let directives = values
+            .flat_map(|value| value.split(','))
+            .filter_map(|directive| {
+                let (name, value) = match directive.split_once('=') {
+                    Some((name, value)) => (name, Some(value.trim().trim_matches('"').to_string())),
+                    None => (directive, None),
+                };
+                let name = name.trim().to_ascii_lowercase();
+                (!name.is_empty()).then_some((name, value))
+            })
+            .collect();
+        Self(directives)
+    }
+
+    fn of(headers: &HeaderMap) -> Self {
+        Self::parse(header_values(headers, "cache-control"))
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.0.iter().any(|(directive, _)| directive == name)
+    }
+
+    fn seconds(&self, name: &str) -> Option<Duration> {
+        self.0
+            .iter()
+            .find(|(directive, _)| directive == name)
+            .and_then(|(_, value)| value.as_deref()?.parse::<u64>().ok())
+            .map(Duration::from_secs)
+    }
+}
+
+/// Returns the `Cache-Control` directives of a request, with
+/// `Pragma: no-cache` standing for `no-cache`.
+fn request_directives(request: &HttpRequest) -> Directives {
+    let mut directives = Directives::parse(request.header_value("cache-control").into_iter());
+    if request.header_value("pragma").is_some_and(|pragma| pragma.to_ascii_lowercase().contains("no-cache")) {
+        directives.0.push(("no-cache".to_string(), None));
+    }
+    directives
+}
+
+fn header_values<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers.get_all(name).iter().filter_map(|value| value.to_str().ok())
+}
+
+/// Returns true if a response may be stored for reuse.
+fn is_storable(status: &StatusCode, headers: &HeaderMap) -> bool {
+    let directives = Directives::of(headers);
+    if directives.has("no-store") || header_values(headers, "vary").any(|vary| vary.trim() == "*") {
+        return false;
+    }
+    let explicit = directives.has("max-age") || headers.contains_key("expires");
+    let validators = headers.contains_key("etag") || headers.contains_key("last-modified");
+    let usable = !freshness_lifetime(Some(*status), headers).is_zero() || validators;
+    (explicit || HEURISTICALLY_CACHEABLE.contains(&status.as_u16())) && usable
+}
+
+/// How long a response stays fresh after it was generated (RFC 7234
+/// section 4.2.1): `max-age`, else `Expires` minus `Date`, else a tenth
+/// of the time since `Last-Modified`.
+fn freshness_lifetime(status: Option<StatusCode>, headers: &HeaderMap) -> Duration {
+    if let Some(max_age) = Directives::of(headers).seconds("max-age") {
+        return max_age;
+    }
+    let date = |name: &str| {
+        let value = headers.get(name)?.to_str().ok()?;
+        DateTime::parse_from_rfc2822(value).ok().map(|date| date.timestamp())
+    };
+    let response_date = date("date").unwrap_or_else(|| (now_ms() / 1000) as i64);
+    if headers.contains_key("expires") {
+        // An invalid date, such as `0`, means already expired
+        let expires = date("expires").unwrap_or(i64::MIN);
+        return Duration::from_secs(expires.saturating_sub(response_date).max(0) as u64);
+    }
+    match (status, date("last-modified")) {
+        (Some(status), Some(modified)) if HEURISTICALLY_CACHEABLE.contains(&status.as_u16()) => {
+            Duration::from_secs((response_date.saturating_sub(modified).max(0) / 10) as u64).min(MAX_HEURISTIC_FRESHNESS)
+        }
+        _ => Duration::ZERO,
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn response(status: u16, pairs: &[(&'static str, &'static str)]) -> HttpResponse {
+        HttpResponse::new(StatusCode::from_u16(status).unwrap(), headers(pairs), "body".to_string(), Duration::ZERO)
+    }
+
+    fn temp_cache(name: &str) -> HttpCache {
+        let dir = std::env::temp_dir().join(format!("hurley-cache-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        HttpCache::open(dir).unwrap()
+    }
+
+    #[test]
+    fn test_freshness_lifetime() {
+        let ok = Some(StatusCode::OK);
+        assert_eq!(freshness_lifetime(ok, &headers(&[("cache-control", "public, max-age=60")])), Duration::from_secs(60));
+        let expires = headers(&[("date", "Wed, 21 Oct 2015 07:28:00 GMT"), ("expires", "Wed, 21 Oct 2015 07:30:00 GMT")]);
+        assert_eq!(freshness_lifetime(ok, &expires), Duration::from_secs(120));
+        assert_eq!(freshness_lifetime(ok, &headers(&[("expires", "0")])), Duration::ZERO);
+        let heuristic = headers(&[("date", "Wed, 21 Oct 2015 07:28:00 GMT"), ("last-modified", "Wed, 21 Oct 2015 07:11:20 GMT")]);
+        assert_eq!(freshness_lifetime(ok, &heuristic), Duration::from_secs(100));
+        assert_eq!(freshness_lifetime(Some(StatusCode::CREATED), &heuristic), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_storable() {
+        let ok = StatusCode::OK;
+        assert!(is_storable(&ok, &headers(&[("cache-control", "max-age=60")])));
+        assert!(is_storable(&ok, &headers(&[("etag", "\"v1\"")])));
+        assert!(!is_storable(&ok, &headers(&[])));
+        assert!(!is_storable(&ok, &headers(&[("cache-control", "no-store, max-age=60")])));
+        assert!(!is_storable(&ok, &headers(&[("cache-control", "max-age=60"), ("vary", "*")])));
+        assert!(!is_storable(&StatusCode::CREATED, &headers(&[("etag", "\"v1\"")])));
+        assert!(is_storable(&StatusCode::CREATED, &headers(&[("cache-control", "max-age=60")])));
+    }
+
+    #[tokio::test]
+    async fn test_hit_and_revalidation() {
+        let cache = temp_cache("hit");
+        let request = HttpRequest::new("http://example.com/catalog");
+        let fresh = response(200, &[("cache-control", "max-age=60"), ("etag", "\"v1\"")]);
+        assert!(cache.store(&request, &fresh, b"catalog").await.unwrap());
+
+        let cached = cache.lookup(&request).await.unwrap();
+        assert!(cached.is_fresh(&request));
+        let (status, stored, body) = cached.response();
+        assert_eq!((status, &body[..]), (StatusCode::OK, &b"catalog"[..]));
+        assert_eq!(stored.get("age").unwrap(), "0");
+        // The request can demand revalidation
+        assert!(!cached.is_fresh(&request.clone().header("Cache-Control", "no-cache")));
+        assert!(!cached.is_fresh(&request.clone().header("Pragma", "no-cache")));
+
+        let revalidation = cached.revalidation(&request).unwrap();
+        assert_eq!(revalidation.header_value("if-none-match"), Some("\"v1\""));
+        let refreshed = cached.refresh(&headers(&[("etag", "\"v1\""), ("cache-control", "max-age=120")]));
+        assert_eq!(Directives::of(&refreshed.headers()).seconds("max-age"), Some(Duration::from_secs(120)));
+        std::fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stale_vary_and_invalidation() {
+        let cache = temp_cache("vary");
+        let request = HttpRequest::new("http://example.com/users").header("Accept", "application/json");
+        let stale = response(200, &[("cache-control", "no-cache"), ("etag", "\"v1\""), ("vary", "Accept")]);
+        assert!(cache.store(&request, &stale, b"[]").await.unwrap());
+
+        let cached = cache.lookup(&request).await.unwrap();
+        assert!(!cached.is_fresh(&request));
+        assert!(cache.lookup(&request.clone().header("Accept", "text/csv")).await.is_none());
+
+        let post = HttpRequest::new("http://example.com/users").method("POST").unwrap();
+        cache.invalidate_after(&post, StatusCode::BAD_REQUEST).await;
+        assert!(cache.lookup(&request).await.is_some());
+        cache.invalidate_after(&post, StatusCode::CREATED).await;
+        assert!(cache.lookup(&request).await.is_none());
+        std::fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_applies_to() {
+        let get = HttpRequest::new("http://example.com/");
+        assert!(HttpCache::applies_to(&get));
+        assert!(!HttpCache::applies_to(&get.clone().method("POST").unwrap()));
+        assert!(!HttpCache::applies_to(&get.clone().header("If-None-Match", "\"v1\"")));
+        assert!(!HttpCache::applies_to(&get.clone().header("Range", "bytes=0-9")));
+        assert!(!HttpCache::applies_to(&get.header("Cache-Control", "no-store")));
+    }
+}
@@ -2,21 +2,203 @@
 //!
 //! Provides the [`HttpClient`] which executes HTTP requests using reqwest.
 
+use bytes::{Bytes, BytesMut};
 use reqwest::redirect::Policy;
-use reqwest::Client;
-use std::time::Instant;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, StatusCode};
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+#[cfg(feature = "cli")]
 use colored::Colorize;
 
-use crate::error::Result;
-use super::request::HttpRequest;
+use crate::error::{Result, RurlError};
+use super::auth::Credentials;
+use super::cache::{CacheStatus, HttpCache};
+use super::dns::DnsResolver;
+use super::encoding::{BodySizes, Decoder};
+use super::middleware::Middleware;
+use super::redirect::{self, RedirectWarning};
+use super::request::{HttpRequest, PreparedRequest};
+use super::socket::SocketOptions;
+use super::tls::ClientIdentity;
+use super::version::HttpVersion;
 use super::response::HttpResponse;
+use super::timing::{self, PhaseTimings};
+use super::wire;
+
+/// A sent request's raw response plus what was observed while sending it.
+struct Sent {
+    response: reqwest::Response,
+    /// Time spent building the client and getting credentials
+    client_setup: Duration,
+    /// Unsafe redirects that were followed
+    redirect_warnings: Vec<RedirectWarning>,
+}
+
+/// How the response body is read.
+#[derive(Clone, Copy)]
+enum ReadBody {
+    /// Decoded into the response's `body`
+    Text,
+    /// Kept as received, and decoded into `body` for display
+    Raw,
+    /// Counted and dropped
+    Count,
+}
 
 /// HTTP client for executing requests.
 ///
 /// The client handles request execution with configurable verbosity
-/// for debugging request/response details.
+/// for debugging request/response details. Verbose output is only
+/// printed when the `cli` feature is enabled.
+///
+/// A client from [`new`](Self::new) builds a fresh connection for every
+/// request; one from [`pooled`](Self::pooled) keeps a single underlying
+/// client so connections are reused across requests.
+///
+/// [`Middleware`] registered with [`middleware`](Self::middleware) run
+/// around every request the client sends.
 pub struct HttpClient {
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
     verbose: bool,
+    shared: Option<SharedClient>,
+    middleware: Vec<Arc<dyn Middleware>>,
+}
+
+/// Client-wide settings of a pooled client, kept to rebuild it when the
+/// client certificate changes.
+struct SharedClient {
+    settings: ClientSettings,
+    /// A client per pinned HTTP version, with the certificate generation
+    /// it was built with
+    clients: Mutex<Vec<(Option<HttpVersion>, Client, u64)>>,
+}
+
+/// Settings fixed when a reqwest client is built rather than per request.
+#[derive(Clone)]
+struct ClientSettings {
+    http_version: Option<HttpVersion>,
+    redirect: Option<RedirectSettings>,
+    pool_idle_timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    keepalive: bool,
+    resolve: Vec<(String, SocketAddr)>,
+    dns: Option<DnsResolver>,
+    socket: SocketOptions,
+    identity: Option<Arc<ClientIdentity>>,
+}
+
+#[derive(Clone)]
+struct RedirectSettings {
+    /// Most redirects followed, `None` for no limit
+    max: Option<usize>,
+    strict: bool,
+    /// Request headers whose forwarding across origins is unsafe
+    headers: Vec<String>,
+}
+
+impl ClientSettings {
+    fn for_request(prepared: &PreparedRequest) -> Self {
+        let request = prepared.request();
+        let mut resolve: Vec<_> = request.resolve.iter().map(|e| (e.host.clone(), e.socket_addr())).collect();
+        if let Some(route) = prepared.zone_route() {
+            resolve.push((route.host.clone(), route.addr));
+        }
+        Self {
+            http_version: request.http_version,
+            redirect: request.follow_redirects.then(|| RedirectSettings {
+                max: request.max_redirects,
+                strict: request.strict_redirects,
+                headers: request.headers.keys().cloned().collect(),
+            }),
+            pool_idle_timeout: request.pool_idle_timeout,
+            connect_timeout: request.connect_timeout,
+            keepalive: true,
+            resolve,
+            dns: request.dns.clone(),
+            socket: request.socket,
+            identity: request.client_identity.clone(),
+        }
+    }
+
+    /// Merges the settings of several requests sharing one client: the
+    /// first request's policies, with every request's overrides.
+    fn for_requests<'a>(requests: impl IntoIterator<Item = &'a PreparedRequest>) -> Result<Self> {
+        let mut requests = requests.into_iter();
+        let mut settings = match requests.next() {
+            Some(first) => Self::for_request(first),
+            None => Self::for_request(&HttpRequest::new("").prepare()?),
+        };
+        for prepared in requests {
+            let other = Self::for_request(prepared);
+            if let (Some(redirect), Some(theirs)) = (&mut settings.redirect, other.redirect) {
+                redirect.headers.extend(theirs.headers);
+            }
+            settings.resolve.extend(other.resolve);
+        }
+        if let Some(redirect) = &mut settings.redirect {
+            redirect.headers.sort();
+            redirect.headers.dedup();
+        }
+        settings.resolve.sort();
+        settings.resolve.dedup();
+        Ok(settings)
+    }
+
+    /// Builds a client speaking `version`, recording unsafe redirect hops
+    /// in `warnings`.
+    fn build_version(
+        &self,
+        version: Option<HttpVersion>,
+        warnings: Option<Arc<Mutex<Vec<RedirectWarning>>>>,
+    ) -> Result<Client> {
+        ClientSettings { http_version: version, ..self.clone() }.build(warnings)
+    }
+
+    /// Builds a client, recording unsafe redirect hops in `warnings`.
+    fn build(&self, warnings: Option<Arc<Mutex<Vec<RedirectWarning>>>>) -> Result<Client> {
+        let redirect_policy = match &self.redirect {
+            Some(redirect) => redirect::policy(redirect.max, redirect.strict, redirect.headers.clone(), warnings),
+            None => Policy::none(),
+        };
+
+        let mut builder = Client::builder()
+            .redirect(redirect_policy)
+            .connection_verbose(wire::is_enabled());
+        builder = self.socket.apply(builder);
+
+        if let Some(idle) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(idle);
+        }
+        if let Some(connect) = self.connect_timeout {
+            builder = builder.connect_timeout(connect);
+        }
+        if !self.keepalive {
+            builder = builder.pool_max_idle_per_host(0);
+        }
+        match self.http_version {
+            Some(HttpVersion::Http10 | HttpVersion::Http11) => builder = builder.http1_only(),
+            Some(HttpVersion::Http2) => builder = builder.http2_prior_knowledge(),
+            None => {}
+        }
+
+        if let Some(dns) = &self.dns {
+            builder = builder.dns_resolver(Arc::new(dns.clone()));
+        }
+        for (host, addr) in &self.resolve {
+            builder = builder.resolve(host, *addr);
+        }
+
+        if let Some(identity) = &self.identity {
+            builder = builder.identity(identity.identity());
+        }
+
+        Ok(builder.build()?)
+    }
 }
 
 impl HttpClient {
@@ -26,7 +208,53 @@ impl HttpClient {
     ///
     /// * `verbose` - Whether to print verbose request/response details
     pub fn new(verbose: bool) -> Self {
-        Self { verbose }
+        Self { verbose, shared: None, middleware: Vec::new() }
+    }
+
+    /// Creates a client that sends all of `requests` over one connection pool.
+    ///
+    /// Redirect, idle-timeout, connect-timeout, name server, socket and
+    /// client certificate settings are taken from the first request; `--resolve` overrides and zoned IPv6 hosts
+    /// of every request are honored. Timeouts stay per request, and
+    /// requests pinned to an HTTP version get a client of their own. The client
+    /// is rebuilt when the client certificate is reloaded, so new
+    /// connections present the new certificate. Redirect warnings are not
+    /// collected.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests` - The requests that will be sent with this client
+    /// * `keepalive` - Whether to reuse connections; if false, every
+    ///   request opens a new one
+    /// * `verbose` - Whether to print verbose request/response details
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client cannot be built.
+    pub fn pooled<'a>(
+        requests: impl IntoIterator<Item = &'a PreparedRequest>,
+        keepalive: bool,
+        verbose: bool,
+    ) -> Result<Self> {
+        let mut settings = ClientSettings::for_requests(requests)?;
+        settings.keepalive = keepalive;
+        let generation = settings.identity.as_ref().map_or(0, |identity| identity.generation());
+        let client = settings.build(None)?;
+        Ok(Self {
+            verbose,
+            shared: Some(SharedClient {
+                clients: Mutex::new(vec![(settings.http_version, client, generation)]),
+                settings,
+            }),
+            middleware: Vec::new(),
+        })
+    }
+
+    /// Adds a middleware run around every request, after the ones already
+    /// added; see [`Middleware`] for the order they run in.
+    pub fn middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware.push(middleware);
+        self
     }
 
     /// Executes an HTTP request and returns the response.
@@ -47,48 +275,393 @@ impl HttpClient {
     /// let response = client.execute(&request).await?;
     /// ```
     pub async fn execute(&self, request: &HttpRequest) -> Result<HttpResponse> {
-        let redirect_policy = if request.follow_redirects {
-            Policy::limited(10)
-        } else {
-            Policy::none()
-        };
+        self.execute_prepared(&request.clone().prepare()?).await
+    }
 
-        let client = Client::builder()
-            .timeout(request.timeout)
-            .redirect(redirect_policy)
-            .build()?;
+    /// Executes a [`PreparedRequest`] and returns the response.
+    ///
+    /// Use this when sending the same request repeatedly, so headers and
+    /// body are compiled once rather than on every send.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails (network error, timeout, etc.).
+    pub async fn execute_prepared(&self, prepared: &PreparedRequest) -> Result<HttpResponse> {
+        Ok(self.execute_reading(prepared, ReadBody::Text).await?.0)
+    }
 
-        if self.verbose {
-            self.print_request_info(request);
+    /// Executes an HTTP request, also returning the body exactly as received.
+    ///
+    /// The response's `body` is the same bytes decoded as UTF-8, with
+    /// invalid sequences replaced, for display. Use this when binary
+    /// content has to be preserved, as when saving multipart parts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails (network error, timeout, etc.).
+    pub async fn execute_raw(&self, request: &HttpRequest) -> Result<(HttpResponse, Bytes)> {
+        let (response, _, raw) = self.execute_reading(&request.clone().prepare()?, ReadBody::Raw).await?;
+        Ok((response, raw.unwrap_or_default()))
+    }
+
+    /// Executes a [`PreparedRequest`], counting the response body instead
+    /// of keeping it.
+    ///
+    /// The body is read chunk by chunk and dropped, so large responses are
+    /// never held in memory. The returned response has an empty `body`; the
+    /// size of the body is returned alongside it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails (network error, timeout, etc.).
+    pub async fn execute_discarding(&self, prepared: &PreparedRequest) -> Result<(HttpResponse, u64)> {
+        let (response, size, _) = self.execute_reading(prepared, ReadBody::Count).await?;
+        Ok((response, size))
+    }
+
+    /// Executes a request, reading the body as `read` says, and returns the
+    /// response with the size of its body and, for [`ReadBody::Raw`], the
+    /// body as received.
+    ///
+    /// A request with a cache is answered from it when it can be.
+    async fn execute_reading(&self, prepared: &PreparedRequest, read: ReadBody) -> Result<(HttpResponse, u64, Option<Bytes>)> {
+        if self.middleware.is_empty() {
+            return self.dispatch(prepared, read).await;
         }
+        let prepared = self.before(prepared)?;
+        let sent = self.dispatch(&prepared, read).await?;
+        self.after(&prepared, &sent.0);
+        Ok(sent)
+    }
 
-        let start = Instant::now();
+    /// Runs the `before` side of the middleware on a copy of the request.
+    fn before(&self, prepared: &PreparedRequest) -> Result<PreparedRequest> {
+        let mut request = prepared.request().clone();
+        for middleware in &self.middleware {
+            middleware.before(&mut request)?;
+        }
+        request.prepare()
+    }
 
-        let mut req_builder = client.request(request.method.clone(), &request.url);
+    /// Runs the `after` side of the middleware, innermost first.
+    fn after(&self, prepared: &PreparedRequest, response: &HttpResponse) {
+        for middleware in self.middleware.iter().rev() {
+            middleware.after(prepared.request(), response);
+        }
+    }
 
-        // Add headers
-        for (key, value) in &request.headers {
-            req_builder = req_builder.header(key, value);
+    /// Sends a request through the cache, if it has one, or straight out.
+    async fn dispatch(&self, prepared: &PreparedRequest, read: ReadBody) -> Result<(HttpResponse, u64, Option<Bytes>)> {
+        let request = prepared.request();
+        match &request.cache {
+            Some(cache) if HttpCache::applies_to(request) => self.execute_cached(cache, prepared, read).await,
+            Some(cache) => {
+                let sent = self.send_reading(prepared, read).await?;
+                cache.invalidate_after(request, sent.0.status).await;
+                Ok(sent)
+            }
+            None => self.send_reading(prepared, read).await,
         }
+    }
 
-        // Add body
-        if let Some(body) = &request.body {
-            req_builder = req_builder.body(body.clone());
+    /// Answers a request from the cache if it holds a fresh response,
+    /// revalidates a stale one, and otherwise sends the request and stores
+    /// the response.
+    async fn execute_cached(
+        &self,
+        cache: &HttpCache,
+        prepared: &PreparedRequest,
+        read: ReadBody,
+    ) -> Result<(HttpResponse, u64, Option<Bytes>)> {
+        let request = prepared.request();
+        let start = Instant::now();
+        let cached = cache.lookup(request).await;
+        if let Some(cached) = cached.as_ref().filter(|cached| cached.is_fresh(request)) {
+            #[cfg(feature = "cli")]
+            if self.verbose {
+                self.print_request_info(prepared);
+            }
+            let (status, headers, body) = cached.response();
+            let mut response = HttpResponse::new(status, headers, String::new(), start.elapsed());
+            response.cache = Some(CacheStatus::Hit);
+            return Ok(read_whole(response, body, read));
+        }
+
+        let revalidation = match cached.as_ref().and_then(|cached| cached.revalidation(request)) {
+            Some(revalidation) => Some(revalidation.prepare()?),
+            None => None,
+        };
+        // The body is kept whole, as it may be stored
+        let (mut response, _, body) = self.send_reading(revalidation.as_ref().unwrap_or(prepared), ReadBody::Raw).await?;
+        let mut body = body.unwrap_or_default();
+        match cached {
+            Some(cached) if revalidation.is_some() && response.status == StatusCode::NOT_MODIFIED => {
+                let refreshed = cached.refresh(&response.headers);
+                cache.save_refreshed(&refreshed).await?;
+                (response.status, response.headers, body) = refreshed.response();
+                response.cache = Some(CacheStatus::Revalidated);
+            }
+            _ => {
+                cache.store(request, &response, &body).await?;
+                response.cache = Some(CacheStatus::Miss);
+            }
         }
+        Ok(read_whole(response, body, read))
+    }
 
-        let response = req_builder.send().await?;
-        let duration = start.elapsed();
+    /// Sends a request, reading the body as `read` says.
+    async fn send_reading(&self, prepared: &PreparedRequest, read: ReadBody) -> Result<(HttpResponse, u64, Option<Bytes>)> {
+        let request = prepared.request();
+        let start = Instant::now();
+        let (sent, marks) = timing::record(self.send_request(prepared)).await;
+        let Sent { mut response, client_setup, redirect_warnings } = sent?;
+        let headers_at = Instant::now();
+        let duration = headers_at - start;
 
         let status = response.status();
         let headers = response.headers().clone();
-        let body = response.text().await?;
+        let version = response.version();
+        let tls = response.url().scheme() == "https";
 
-        Ok(HttpResponse::new(status, headers, body, duration))
+        // A HEAD response never has a body, so don't wait for one
+        let mut decompressed = None;
+        let (mut response, size, raw) = if request.method == Method::HEAD {
+            (HttpResponse::head(status, headers, duration), 0, None)
+        } else {
+            match read {
+                ReadBody::Text | ReadBody::Raw if request.compressed => {
+                    let (raw, sizes) = decompress(&headers, read_body(response, request.read_timeout).await?)?;
+                    decompressed = sizes;
+                    let (body, size) = (String::from_utf8_lossy(&raw).into_owned(), raw.len() as u64);
+                    let raw = matches!(read, ReadBody::Raw).then_some(raw);
+                    (HttpResponse::new(status, headers, body, duration), size, raw)
+                }
+                ReadBody::Text if request.read_timeout.is_none() => {
+                    let body = response.text().await?;
+                    let size = body.len() as u64;
+                    (HttpResponse::new(status, headers, body, duration), size, None)
+                }
+                ReadBody::Text | ReadBody::Raw => {
+                    let raw = read_body(response, request.read_timeout).await?;
+                    let body = String::from_utf8_lossy(&raw).into_owned();
+                    let size = raw.len() as u64;
+                    let raw = matches!(read, ReadBody::Raw).then_some(raw);
+                    (HttpResponse::new(status, headers, body, duration), size, raw)
+                }
+                ReadBody::Count => {
+                    let mut size = 0u64;
+                    while let Some(chunk) = next_chunk(&mut response, request.read_timeout).await? {
+                        size += chunk.len() as u64;
+                    }
+                    (HttpResponse::new(status, headers, String::new(), duration), size, None)
+                }
+            }
+        };
+        response.client_setup = client_setup;
+        response.redirect_warnings = redirect_warnings;
+        response.version = version;
+        response.phases = PhaseTimings::new(marks, start + client_setup, headers_at, Instant::now(), tls);
+        response.decompressed = decompressed;
+
+        Ok((response, size, raw))
+    }
+
+    /// Executes an HTTP request, streaming the response body to a file.
+    ///
+    /// The body is written chunk by chunk as it arrives, so large downloads
+    /// are never held in memory and binary content is preserved exactly.
+    /// The returned response has an empty `body`; the number of bytes
+    /// written is returned alongside it.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The HTTP request to execute
+    /// * `path` - Destination file, created or truncated
+    /// * `on_progress` - Called after each chunk with the bytes written so far
+    ///   and the expected total from `Content-Length`, if known
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the file cannot be written.
+    pub async fn execute_to_file<F>(
+        &self,
+        request: &HttpRequest,
+        path: &Path,
+        mut on_progress: F,
+    ) -> Result<(HttpResponse, u64)>
+    where
+        F: FnMut(u64, Option<u64>),
+    {
+        let prepared = match self.middleware.is_empty() {
+            true => request.clone().prepare()?,
+            false => self.before(&request.clone().prepare()?)?,
+        };
+        let start = Instant::now();
+        let (sent, marks) = timing::record(self.send_request(&prepared)).await;
+        let Sent { mut response, client_setup, redirect_warnings } = sent?;
+        let headers_at = Instant::now();
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let version = response.version();
+        let total = response.content_length();
+        let tls = response.url().scheme() == "https";
+
+        // Progress counts bytes received, which is what Content-Length gives
+        let mut decoder = match prepared.request().compressed {
+            true => Decoder::for_headers(&headers).transpose().map_err(decode_error)?,
+            false => None,
+        };
+        let mut file = tokio::fs::File::create(path).await?;
+        let (mut received, mut written) = (0u64, 0u64);
+        while let Some(chunk) = next_chunk(&mut response, prepared.request().read_timeout).await? {
+            received += chunk.len() as u64;
+            match &mut decoder {
+                Some(decoder) => {
+                    let decoded = decoder.write(&chunk).map_err(decode_error)?;
+                    file.write_all(&decoded).await?;
+                    written += decoded.len() as u64;
+                }
+                None => {
+                    file.write_all(&chunk).await?;
+                    written += chunk.len() as u64;
+                }
+            }
+            on_progress(received, total);
+        }
+        let decompressed = match decoder {
+            Some(decoder) => {
+                let rest = decoder.finish().map_err(decode_error)?;
+                file.write_all(&rest).await?;
+                written += rest.len() as u64;
+                Some(BodySizes { received, decoded: written })
+            }
+            None => None,
+        };
+        file.flush().await?;
+        let end = Instant::now();
+
+        let mut response = HttpResponse::new(status, headers, String::new(), end - start);
+        response.decompressed = decompressed;
+        response.redirect_warnings = redirect_warnings;
+        response.version = version;
+        response.phases = PhaseTimings::new(marks, start + client_setup, headers_at, end, tls);
+        self.after(&prepared, &response);
+        Ok((response, written))
+    }
+
+    /// Returns the shared client, rebuilt if the client certificate
+    /// changed, or builds one for the request.
+    fn client(&self, prepared: &PreparedRequest, warnings: &Arc<Mutex<Vec<RedirectWarning>>>) -> Result<Client> {
+        let Some(shared) = &self.shared else {
+            return ClientSettings::for_request(prepared).build(Some(Arc::clone(warnings)));
+        };
+        let version = prepared.request().http_version;
+        let generation = shared.settings.identity.as_ref().map_or(0, |identity| identity.generation());
+        let mut clients = shared.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let index = match clients.iter().position(|(v, _, _)| *v == version) {
+            Some(index) => index,
+            None => {
+                let client = shared.settings.build_version(version, None)?;
+                clients.push((version, client, generation));
+                clients.len() - 1
+            }
+        };
+        let (_, client, built_with) = &mut clients[index];
+        if *built_with != generation {
+            *client = shared.settings.build_version(version, None)?;
+            *built_with = generation;
+        }
+        // Clones share the connection pool
+        Ok(client.clone())
     }
 
-    fn print_request_info(&self, request: &HttpRequest) {
+    /// Gets a client for the request and sends it.
+    async fn send_request(&self, prepared: &PreparedRequest) -> Result<Sent> {
+        let request = prepared.request();
+        let setup_start = Instant::now();
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let client = self.client(prepared, &warnings)?;
+        let mut credentials = match &request.auth {
+            Some(auth) => auth.authorize(prepared).await?,
+            None => Credentials::new(),
+        };
+        let client_setup = setup_start.elapsed();
+
+        #[cfg(feature = "cli")]
+        if self.verbose {
+            self.print_request_info(prepared);
+        }
+
+        let mut response = match Self::send(&client, prepared, &credentials).await {
+            // Only idempotent requests: the server may have applied the first attempt
+            Err(e) if request.retry_on_connection_reset && is_idempotent(&request.method) && is_connection_reset(&e) => {
+                #[cfg(feature = "cli")]
+                if self.verbose {
+                    println!("{}", "Connection reset, retrying on a new connection".dimmed());
+                }
+                Self::send(&client, prepared, &credentials).await?
+            }
+            result => result?,
+        };
+
+        // Rejected credentials may just be stale: refresh them and try once more
+        if let Some(auth) = &request.auth {
+            if response.status() == StatusCode::UNAUTHORIZED && auth.invalidate() {
+                #[cfg(feature = "cli")]
+                if self.verbose {
+                    println!("{}", "Credentials rejected, retrying with fresh ones".dimmed());
+                }
+                credentials = auth.authorize(prepared).await?;
+                response = Self::send(&client, prepared, &credentials).await?;
+            }
+        }
+
+        let redirect_warnings = std::mem::take(&mut *warnings.lock().unwrap_or_else(|e| e.into_inner()));
+        Ok(Sent {
+            response,
+            client_setup,
+            redirect_warnings,
+        })
+    }
+
+    async fn send(client: &Client, prepared: &PreparedRequest, credentials: &Credentials) -> reqwest::Result<reqwest::Response> {
+        let request = prepared.request();
+        // A zoned IPv6 host goes through its placeholder name
+        let url = prepared.zone_route().map_or(request.url.as_str(), |route| route.url.as_str());
+        let mut headers = prepared.headers().clone();
+        for (name, value) in credentials {
+            headers.insert(name, value.clone());
+        }
+        let mut req_builder = client
+            .request(request.method.clone(), url)
+            .timeout(request.timeout)
+            .headers(headers);
+        if let Some(version) = request.http_version {
+            req_builder = req_builder.version(version.as_reqwest());
+        }
+        if let Some(route) = prepared.zone_route() {
+            req_builder = req_builder.header(reqwest::header::HOST, &route.host_header);
+        }
+
+        // Bytes clones share the buffer rather than copying it
+        if let Some(body) = prepared.body() {
+            req_builder = req_builder.body(body.clone());
+        }
+
+        // Errors would otherwise name the placeholder host
+        let zoned = prepared.zone_route().is_some();
+        req_builder.send().await.map_err(|e| if zoned { e.without_url() } else { e })
+    }
+
+    #[cfg(feature = "cli")]
+    fn print_request_info(&self, prepared: &PreparedRequest) {
+        let request = prepared.request();
         println!("{}", ">>> Request".blue().bold());
         println!("{} {}", request.method.as_str().green(), request.url.cyan());
+        if let Some(dns) = &request.dns {
+            println!("{}", format!("(resolving with {})", dns).dimmed());
+        }
         
         for (key, value) in &request.headers {
             println!("{}: {}", key.yellow(), value);
@@ -106,9 +679,145 @@ impl HttpClient {
             } else {
                 println!("{}", body);
             }
+            if let (true, Some(sent)) = (request.compress_body, prepared.body()) {
+                let line = format!("(sent gzip-compressed: {} bytes, {} before)", sent.len(), body.len());
+                println!("{}", line.dimmed());
+            }
         }
         
         println!();
         println!("{}", "<<< Response".blue().bold());
     }
 }
+
+/// Reads the next chunk of a response body, failing if none arrives
+/// within `read_timeout`.
+async fn next_chunk(response: &mut reqwest::Response, read_timeout: Option<Duration>) -> Result<Option<Bytes>> {
+    match read_timeout {
+        Some(limit) => match tokio::time::timeout(limit, response.chunk()).await {
+            Ok(chunk) => Ok(chunk?),
+            Err(_) => Err(RurlError::ReadTimeout(limit)),
+        },
+        None => Ok(response.chunk().await?),
+    }
+}
+
+/// Reads a whole response body, failing if it stalls for longer than
+/// `read_timeout`.
+async fn read_body(mut response: reqwest::Response, read_timeout: Option<Duration>) -> Result<Bytes> {
+    if read_timeout.is_none() {
+        return Ok(response.bytes().await?);
+    }
+    let mut body = BytesMut::new();
+    while let Some(chunk) = next_chunk(&mut response, read_timeout).await? {
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.freeze())
+}
+
+/// Shapes a body read in full as `read` asks for.
+fn read_whole(mut response: HttpResponse, body: Bytes, read: ReadBody) -> (HttpResponse, u64, Option<Bytes>) {
+    let size = body.len() as u64;
+    match read {
+        ReadBody::Text | ReadBody::Raw => response.body = String::from_utf8_lossy(&body).into_owned(),
+        ReadBody::Count => response.body.clear(),
+    }
+    let raw = matches!(read, ReadBody::Raw).then_some(body);
+    (response, size, raw)
+}
+
+/// Decompresses a body according to the response's `Content-Encoding`,
+/// returning it with its sizes before and after.
+///
+/// A body without a supported encoding is returned as received.
+fn decompress(headers: &HeaderMap, raw: Bytes) -> Result<(Bytes, Option<BodySizes>)> {
+    let Some(decoder) = Decoder::for_headers(headers) else {
+        return Ok((raw, None));
+    };
+    let mut decoder = decoder.map_err(decode_error)?;
+    let mut decoded = decoder.write(&raw).map_err(decode_error)?;
+    decoded.extend(decoder.finish().map_err(decode_error)?);
+    let sizes = BodySizes { received: raw.len() as u64, decoded: decoded.len() as u64 };
+    Ok((Bytes::from(decoded), Some(sizes)))
+}
+
+fn decode_error(error: std::io::Error) -> RurlError {
+    RurlError::DecodeError(error.to_string())
+}
+
+/// Returns true for methods that can be sent twice with the same effect
+/// as once.
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+/// Returns true if the error was caused by the peer dropping the connection.
+///
+/// This is the typical symptom of reusing a pooled connection that a load
+/// balancer has already closed for being idle. The error chain is checked
+/// for a reset, aborted or broken-pipe I/O error, or hyper's error for a
+/// connection closed before the response.
+fn is_connection_reset(error: &reqwest::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(err) = source {
+        if let Some(io) = err.downcast_ref::<std::io::Error>() {
+            if matches!(
+                io.kind(),
+                ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe
+            ) {
+                return true;
+            }
+        }
+        if err.downcast_ref::<hyper::Error>().is_some_and(hyper::Error::is_incomplete_message) {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncReadExt;
+
+    #[test]
+    fn test_settings_take_client_identity() {
+        let identity = Arc::new(crate::http::tls::tests::test_identity());
+        let request = HttpRequest::new("https://localhost/").client_identity(Some(Arc::clone(&identity)));
+        let settings = ClientSettings::for_request(&request.prepare().unwrap());
+        assert!(settings.identity.is_some_and(|used| Arc::ptr_eq(&used, &identity)));
+    }
+
+    /// Starts a server that reads each request and closes the connection
+    /// without answering, and returns its URL and connection count.
+    async fn spawn_closing_server() -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&connections);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+            }
+        });
+        (format!("http://{}", addr), connections)
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_connection_reset_only_idempotent() {
+        let client = HttpClient::new(false);
+        for (method, attempts) in [("GET", 2), ("PUT", 2), ("POST", 1), ("PATCH", 1)] {
+            let (url, connections) = spawn_closing_server().await;
+            let request = HttpRequest::new(url).method(method).unwrap().retry_on_connection_reset(true);
+            assert!(client.execute(&request).await.is_err());
+            assert_eq!(connections.load(Ordering::SeqCst), attempts, "{}", method);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Conditional requests with validators (`--etag-compare`, `--etag-save`,
+//! `--if-modified-since`).
+//!
+//! An ETag saved from one response and sent back as `If-None-Match`, or a
+//! date sent as `If-Modified-Since`, lets the server answer with
+//! `304 Not Modified` instead of the full body. Repeating such a request
+//! exercises the revalidation path of a server or cache; a 304 to a
+//! conditional request counts as a success.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, ETAG};
+
+use crate::error::{Result, RurlError};
+
+/// Headers that make a request conditional on a validator.
+pub const CONDITIONAL_HEADERS: [&str; 4] = ["If-None-Match", "If-Modified-Since", "If-Match", "If-Unmodified-Since"];
+
+/// Reads the ETag saved by [`save_etag`].
+///
+/// Returns `None` if the file doesn't exist yet or is empty, so the first
+/// of a series of requests fetches the full response.
+///
+/// # Errors
+///
+/// Returns an error if the file exists but can't be read.
+pub fn load_etag(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content.trim().to_string()).filter(|etag| !etag.is_empty())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes the `ETag` of a response to `path`, as sent (quotes and `W/`
+/// prefix included).
+///
+/// A response without an ETag, such as a 304 that doesn't repeat it,
+/// leaves the file as it is. Returns whether the file was written.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be written.
+pub fn save_etag(path: &Path, headers: &HeaderMap) -> Result<bool> {
+    let Some(etag) = headers.get(ETAG).and_then(|value| value.to_str().ok()) else {
+        return Ok(false);
+    };
+    fs::write(path, format!("{}\n", etag))?;
+    Ok(true)
+}
+
+/// Returns the `If-Modified-Since` value for `--if-modified-since`: an
+/// HTTP date, an RFC 3339 timestamp, or the path of a file whose
+/// modification time is used, as with curl's `-z`.
+///
+/// # Errors
+///
+/// Returns [`RurlError::InvalidHeader`] if the value is neither a date
+/// nor an existing file.
+pub fn if_modified_since(value: &str) -> Result<String> {
+    let value = value.trim();
+    let date = if let Ok(date) = DateTime::parse_from_rfc2822(value) {
+        date.with_timezone(&Utc)
+    } else if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        date.with_timezone(&Utc)
+    } else if let Ok(modified) = fs::metadata(value).and_then(|m| m.modified()) {
+        DateTime::<Utc>::from(modified)
+    } else {
+        return Err(RurlError::InvalidHeader(format!(
+            "If-Modified-Since: '{}' is not a date (e.g. 'Wed, 21 Oct 2015 07:28:00 GMT' or 2015-10-21T07:28:00Z) or an existing file",
+            value
+        )));
+    };
+    Ok(http_date(date))
+}
+
+/// Formats a time as an HTTP date (`Wed, 21 Oct 2015 07:28:00 GMT`).
+pub fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_etag_round_trip() {
+        let path = std::env::temp_dir().join(format!("hurley-etag-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(load_etag(&path).unwrap(), None);
+
+        let mut headers = HeaderMap::new();
+        assert!(!save_etag(&path, &headers).unwrap());
+        headers.insert(ETAG, HeaderValue::from_static("W/\"33a64df5\""));
+        assert!(save_etag(&path, &headers).unwrap());
+        assert_eq!(load_etag(&path).unwrap().as_deref(), Some("W/\"33a64df5\""));
+
+        fs::write(&path, "\n").unwrap();
+        assert_eq!(load_etag(&path).unwrap(), None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_if_modified_since() {
+        let expected = "Wed, 21 Oct 2015 07:28:00 GMT";
+        assert_eq!(if_modified_since(expected).unwrap(), expected);
+        assert_eq!(if_modified_since("2015-10-21T09:28:00+02:00").unwrap(), expected);
+        assert!(if_modified_since("yesterday").is_err());
+
+        let file = std::env::temp_dir();
+        assert!(if_modified_since(file.to_str().unwrap()).unwrap().ends_with(" GMT"));
+    }
+}
@@ -0,0 +1,254 @@
+//! `Set-Cookie` parsing and attribute audit.
+//!
+//! Cookies set by a response are summarized one per line (name, domain,
+//! path, flags, expiry) with warnings for missing security attributes and
+//! for cookies set more than once, which the raw header lines make easy to
+//! miss. Shown with `-i` or on its own with `--cookies`.
+
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use std::fmt;
+
+/// A cookie set by a `Set-Cookie` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetCookie {
+    /// Cookie name
+    pub name: String,
+    /// Cookie value
+    pub value: String,
+    /// `Domain` attribute, without a leading dot
+    pub domain: Option<String>,
+    /// `Path` attribute
+    pub path: Option<String>,
+    /// `Secure` flag
+    pub secure: bool,
+    /// `HttpOnly` flag
+    pub http_only: bool,
+    /// `SameSite` attribute as sent (`Strict`, `Lax` or `None`)
+    pub same_site: Option<String>,
+    /// `Expires` attribute as sent
+    pub expires: Option<String>,
+    /// `Max-Age` attribute, in seconds
+    pub max_age: Option<i64>,
+}
+
+impl SetCookie {
+    /// Parses a `Set-Cookie` header value.
+    ///
+    /// Returns `None` if it has no `name=value` pair. Unknown attributes
+    /// are ignored, as browsers do.
+    pub fn parse(header: &str) -> Option<Self> {
+        let mut parts = header.split(';');
+        let (name, value) = parts.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let mut cookie = SetCookie {
+            name: name.to_string(),
+            value: value.trim().to_string(),
+            domain: None,
+            path: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+            expires: None,
+            max_age: None,
+        };
+        for attribute in parts {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim())),
+                None => (attribute.trim(), None),
+            };
+            match (key.to_ascii_lowercase().as_str(), value) {
+                ("domain", Some(domain)) => cookie.domain = Some(domain.trim_start_matches('.').to_ascii_lowercase()),
+                ("path", Some(path)) => cookie.path = Some(path.to_string()),
+                ("secure", _) => cookie.secure = true,
+                ("httponly", _) => cookie.http_only = true,
+                ("samesite", Some(same_site)) => cookie.same_site = Some(same_site.to_string()),
+                ("expires", Some(expires)) => cookie.expires = Some(expires.to_string()),
+                ("max-age", Some(age)) => cookie.max_age = age.parse().ok(),
+                _ => {}
+            }
+        }
+        Some(cookie)
+    }
+
+    /// Describes when the cookie expires: a date, an age, or `session`.
+    ///
+    /// `Max-Age` wins over `Expires`, as in browsers.
+    pub fn expiry(&self) -> String {
+        match (self.max_age, &self.expires) {
+            (Some(age), _) if age <= 0 => "deleted".to_string(),
+            (Some(age), _) => format!("max-age {}s", age),
+            (None, Some(expires)) => expires.clone(),
+            (None, None) => "session".to_string(),
+        }
+    }
+
+    fn same_site_none(&self) -> bool {
+        self.same_site.as_deref().is_some_and(|s| s.eq_ignore_ascii_case("none"))
+    }
+}
+
+/// A questionable cookie attribute, or a cookie set twice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CookieWarning {
+    /// Sent over plain HTTP too
+    MissingSecure(String),
+    /// Readable from JavaScript
+    MissingHttpOnly(String),
+    /// Cross-site behavior left to the browser default
+    MissingSameSite(String),
+    /// `SameSite=None` without `Secure`, which browsers reject
+    SameSiteNoneWithoutSecure(String),
+    /// A `__Secure-` or `__Host-` prefix whose requirements aren't met
+    InvalidPrefix(String),
+    /// Set more than once for the same domain and path
+    Duplicate(String),
+}
+
+impl fmt::Display for CookieWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookieWarning::MissingSecure(name) => write!(f, "cookie '{}' is missing Secure", name),
+            CookieWarning::MissingHttpOnly(name) => {
+                write!(f, "cookie '{}' is missing HttpOnly and is readable from JavaScript", name)
+            }
+            CookieWarning::MissingSameSite(name) => write!(f, "cookie '{}' has no SameSite attribute", name),
+            CookieWarning::SameSiteNoneWithoutSecure(name) => {
+                write!(f, "cookie '{}' has SameSite=None without Secure and will be rejected", name)
+            }
+            CookieWarning::InvalidPrefix(name) => write!(
+                f,
+                "cookie '{}' does not meet its prefix requirements (Secure; __Host- also needs Path=/ and no Domain)",
+                name
+            ),
+            CookieWarning::Duplicate(name) => write!(f, "cookie '{}' is set more than once", name),
+        }
+    }
+}
+
+/// Cookies set by a response and what's wrong with them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CookieAudit {
+    /// Cookies in header order
+    pub cookies: Vec<SetCookie>,
+    /// Warnings in cookie order
+    pub warnings: Vec<CookieWarning>,
+}
+
+impl CookieAudit {
+    /// Parses and audits every `Set-Cookie` header.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let cookies: Vec<_> = headers
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(SetCookie::parse)
+            .collect();
+
+        let mut warnings = Vec::new();
+        for (index, cookie) in cookies.iter().enumerate() {
+            let name = || cookie.name.clone();
+            let earlier = &cookies[..index];
+            if earlier
+                .iter()
+                .any(|c| c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path)
+            {
+                if !warnings.contains(&CookieWarning::Duplicate(name())) {
+                    warnings.push(CookieWarning::Duplicate(name()));
+                }
+                continue;
+            }
+            if cookie.max_age.is_some_and(|age| age <= 0) {
+                // Deleting a cookie, its attributes no longer matter
+                continue;
+            }
+            if cookie.same_site_none() && !cookie.secure {
+                warnings.push(CookieWarning::SameSiteNoneWithoutSecure(name()));
+            } else if !cookie.secure {
+                warnings.push(CookieWarning::MissingSecure(name()));
+            }
+            if !cookie.http_only {
+                warnings.push(CookieWarning::MissingHttpOnly(name()));
+            }
+            if cookie.same_site.is_none() {
+                warnings.push(CookieWarning::MissingSameSite(name()));
+            }
+            let host_prefix_ok = cookie.secure && cookie.domain.is_none() && cookie.path.as_deref() == Some("/");
+            if (cookie.name.starts_with("__Secure-") && !cookie.secure)
+                || (cookie.name.starts_with("__Host-") && !host_prefix_ok)
+            {
+                warnings.push(CookieWarning::InvalidPrefix(name()));
+            }
+        }
+        Self { cookies, warnings }
+    }
+
+    /// Returns true if the response set no cookies.
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn audit(headers: &[&str]) -> CookieAudit {
+        let mut map = HeaderMap::new();
+        for header in headers {
+            map.append(SET_COOKIE, HeaderValue::from_str(header).unwrap());
+        }
+        CookieAudit::from_headers(&map)
+    }
+
+    #[test]
+    fn test_parse() {
+        let cookie = SetCookie::parse("sid=abc123; Domain=.Example.com; Path=/; Secure; HttpOnly; SameSite=Lax; Max-Age=3600")
+            .unwrap();
+        assert_eq!(cookie.name, "sid");
+        assert_eq!(cookie.value, "abc123");
+        assert_eq!(cookie.domain.as_deref(), Some("example.com"));
+        assert_eq!(cookie.path.as_deref(), Some("/"));
+        assert!(cookie.secure && cookie.http_only);
+        assert_eq!(cookie.same_site.as_deref(), Some("Lax"));
+        assert_eq!(cookie.expiry(), "max-age 3600s");
+
+        assert_eq!(SetCookie::parse("theme=dark").unwrap().expiry(), "session");
+        assert_eq!(SetCookie::parse("=x"), None);
+        assert_eq!(SetCookie::parse("garbage"), None);
+    }
+
+    #[test]
+    fn test_audit_warnings() {
+        let report = audit(&["sid=1; Secure; HttpOnly; SameSite=Strict", "theme=dark", "track=1; SameSite=None; HttpOnly"]);
+        assert_eq!(report.cookies.len(), 3);
+        assert_eq!(
+            report.warnings,
+            vec![
+                CookieWarning::MissingSecure("theme".to_string()),
+                CookieWarning::MissingHttpOnly("theme".to_string()),
+                CookieWarning::MissingSameSite("theme".to_string()),
+                CookieWarning::SameSiteNoneWithoutSecure("track".to_string()),
+            ]
+        );
+
+        let report = audit(&["__Host-id=1; Secure; HttpOnly; SameSite=Lax; Domain=example.com; Path=/"]);
+        assert_eq!(report.warnings, vec![CookieWarning::InvalidPrefix("__Host-id".to_string())]);
+    }
+
+    #[test]
+    fn test_audit_duplicates() {
+        let ok = "sid=1; Secure; HttpOnly; SameSite=Lax; Path=/";
+        let report = audit(&[ok, "sid=2; Secure; HttpOnly; SameSite=Lax; Path=/", "sid=3; Secure; HttpOnly; SameSite=Lax; Path=/"]);
+        assert_eq!(report.warnings, vec![CookieWarning::Duplicate("sid".to_string())]);
+
+        // Same name on another path is a different cookie
+        assert!(audit(&[ok, "sid=2; Secure; HttpOnly; SameSite=Lax; Path=/admin"]).warnings.is_empty());
+        // Deletions need no security attributes
+        assert!(audit(&["old=; Max-Age=0"]).warnings.is_empty());
+        assert!(audit(&[]).is_empty());
+    }
+}
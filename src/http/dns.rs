@@ -0,0 +1,283 @@
+//! Custom name servers (`--dns-server`, `--doh-url`).
+//!
+//! By default host names are looked up with the system resolver. A
+//! [`DnsResolver`] looks them up with plain DNS servers of its own, or over
+//! DNS-over-HTTPS, so that name resolution can be pinned and measured apart
+//! from the machine's configuration. Its lookups aren't cached: every new
+//! connection asks the server, and the time it takes shows up as the DNS
+//! phase of the request.
+//!
+//! `--resolve` overrides still take precedence over the resolver.
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use hickory_resolver::config::{LookupIpStrategy, NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use tokio::sync::OnceCell;
+use url::Url;
+
+use crate::error::{Result, RurlError};
+
+/// Port of plain DNS servers given without one.
+pub const DNS_PORT: u16 = 53;
+
+/// Path DNS-over-HTTPS queries are sent to (RFC 8484).
+pub const DOH_PATH: &str = "/dns-query";
+
+/// A plain DNS server, e.g. `1.1.1.1`, `1.1.1.1:5353` or
+/// `[2606:4700:4700::1111]:53`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnsServer(pub SocketAddr);
+
+impl FromStr for DnsServer {
+    type Err = RurlError;
+
+    /// Parses an IP address with an optional port; IPv6 addresses with a
+    /// port must be bracketed.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Ok(addr) = s.parse::<SocketAddr>() {
+            return Ok(Self(addr));
+        }
+        s.trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .map(|ip| Self(SocketAddr::new(ip, DNS_PORT)))
+            .map_err(|_| RurlError::ResolveError(format!("'{}': expected a DNS server address like 1.1.1.1 or 1.1.1.1:53", s)))
+    }
+}
+
+impl fmt::Display for DnsServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.port() {
+            DNS_PORT => write!(f, "{}", self.0.ip()),
+            _ => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// Where a [`DnsResolver`] sends its queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Upstream {
+    /// Plain DNS over UDP, falling back to TCP for truncated answers
+    Servers(Vec<DnsServer>),
+    /// DNS-over-HTTPS
+    Https(Url),
+}
+
+/// Looks up host names with the given name servers instead of the system
+/// resolver.
+///
+/// The resolver is cheap to clone; clones share the connections to the
+/// name servers.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let dns = DnsResolver::doh("https://cloudflare-dns.com/dns-query")?;
+/// let request = HttpRequest::new("https://example.com").dns(Some(dns));
+/// ```
+#[derive(Clone)]
+pub struct DnsResolver {
+    upstream: Arc<Upstream>,
+    /// Set up on the first lookup, as the DoH server may need resolving
+    resolver: Arc<OnceCell<TokioAsyncResolver>>,
+}
+
+impl DnsResolver {
+    /// Creates a resolver querying plain DNS servers, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::ResolveError`] if `servers` is empty.
+    pub fn servers(servers: Vec<DnsServer>) -> Result<Self> {
+        if servers.is_empty() {
+            return Err(RurlError::ResolveError("no DNS server given".to_string()));
+        }
+        Ok(Self::with_upstream(Upstream::Servers(servers)))
+    }
+
+    /// Creates a resolver querying a DNS-over-HTTPS endpoint, e.g.
+    /// `https://cloudflare-dns.com/dns-query`.
+    ///
+    /// The endpoint's own host name, if it isn't an IP address, is looked
+    /// up once with the system resolver.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::ResolveError`] if the URL isn't an `https` URL
+    /// or has a path other than [`DOH_PATH`].
+    pub fn doh(url: &str) -> Result<Self> {
+        let invalid = |reason: &str| RurlError::ResolveError(format!("DoH URL '{}': {}", url, reason));
+        let mut parsed = Url::parse(url).map_err(|e| invalid(&e.to_string()))?;
+        if parsed.scheme() != "https" {
+            return Err(invalid("must be an https URL"));
+        }
+        if parsed.host().is_none() {
+            return Err(invalid("missing host"));
+        }
+        match parsed.path() {
+            "" | "/" => parsed.set_path(DOH_PATH),
+            DOH_PATH => {}
+            _ => return Err(invalid(&format!("only the standard {} path is supported", DOH_PATH))),
+        }
+        if parsed.query().is_some() {
+            return Err(invalid("must not have a query"));
+        }
+        Ok(Self::with_upstream(Upstream::Https(parsed)))
+    }
+
+    fn with_upstream(upstream: Upstream) -> Self {
+        Self { upstream: Arc::new(upstream), resolver: Arc::new(OnceCell::new()) }
+    }
+
+    /// Returns the curl options querying the same name servers:
+    /// `--dns-servers` or `--doh-url`.
+    pub fn curl_args(&self) -> [String; 2] {
+        match &*self.upstream {
+            Upstream::Servers(servers) => ["--dns-servers".to_string(), join(servers)],
+            Upstream::Https(url) => ["--doh-url".to_string(), url.to_string()],
+        }
+    }
+
+    /// Returns the resolver, setting it up on first use.
+    async fn resolver(&self) -> Result<&TokioAsyncResolver> {
+        self.resolver
+            .get_or_try_init(|| async {
+                let config = ResolverConfig::from_parts(None, Vec::new(), self.name_servers().await?);
+                let mut opts = ResolverOpts::default();
+                // Every lookup goes to the server, so its time is measured
+                opts.cache_size = 0;
+                // A and AAAA at once, like the system resolver, and no
+                // retries of a server that doesn't answer, so a dead one
+                // fails as a DNS error well before the request times out
+                opts.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+                opts.attempts = 1;
+                Ok(TokioAsyncResolver::tokio(config, opts))
+            })
+            .await
+    }
+
+    async fn name_servers(&self) -> Result<NameServerConfigGroup> {
+        match &*self.upstream {
+            Upstream::Servers(servers) => {
+                let mut group = NameServerConfigGroup::new();
+                for server in servers {
+                    group.merge(NameServerConfigGroup::from_ips_clear(&[server.0.ip()], server.0.port(), true));
+                }
+                Ok(group)
+            }
+            Upstream::Https(url) => {
+                let host = url.host_str().unwrap_or_default();
+                let port = url.port_or_known_default().unwrap_or(443);
+                let ips: Vec<IpAddr> = match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+                    Ok(ip) => vec![ip],
+                    Err(_) => tokio::net::lookup_host((host, port))
+                        .await
+                        .map_err(|e| RurlError::ResolveError(format!("DoH server {}: {}", host, e)))?
+                        .map(|addr| addr.ip())
+                        .collect(),
+                };
+                Ok(NameServerConfigGroup::from_ips_https(&ips, port, host.to_string(), true))
+            }
+        }
+    }
+}
+
+impl Resolve for DnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let this = self.clone();
+        Box::pin(async move {
+            let lookup = this.resolver().await?.lookup_ip(name.as_str()).await?;
+            let addrs: Addrs = Box::new(lookup.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+impl fmt::Display for DnsResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &*self.upstream {
+            Upstream::Servers(servers) => f.write_str(&join(servers)),
+            Upstream::Https(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+impl fmt::Debug for DnsResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DnsResolver").field(&*self.upstream).finish()
+    }
+}
+
+fn join(servers: &[DnsServer]) -> String {
+    servers.iter().map(DnsServer::to_string).collect::<Vec<_>>().join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_servers() {
+        let server = |s: &str| s.parse::<DnsServer>().map(|server| server.0.to_string());
+        assert_eq!(server("1.1.1.1").unwrap(), "1.1.1.1:53");
+        assert_eq!(server("8.8.8.8:5353").unwrap(), "8.8.8.8:5353");
+        assert_eq!(server("2606:4700:4700::1111").unwrap(), "[2606:4700:4700::1111]:53");
+        assert_eq!(server("[::1]:5353").unwrap(), "[::1]:5353");
+        assert!(server("dns.google").is_err());
+        assert!(server("1.1.1.1:99999").is_err());
+    }
+
+    #[test]
+    fn test_doh_urls() {
+        assert_eq!(DnsResolver::doh("https://1.1.1.1").unwrap().to_string(), "https://1.1.1.1/dns-query");
+        let dns = DnsResolver::doh("https://cloudflare-dns.com/dns-query").unwrap();
+        assert_eq!(dns.curl_args(), ["--doh-url", "https://cloudflare-dns.com/dns-query"]);
+        assert!(DnsResolver::doh("http://1.1.1.1/dns-query").is_err());
+        assert!(DnsResolver::doh("https://dns.example/resolve").is_err());
+        assert!(DnsResolver::doh("not a url").is_err());
+    }
+
+    #[test]
+    fn test_servers_display() {
+        let servers = vec!["1.1.1.1".parse().unwrap(), "[::1]:5353".parse().unwrap()];
+        let dns = DnsResolver::servers(servers).unwrap();
+        assert_eq!(dns.to_string(), "1.1.1.1,[::1]:5353");
+        assert_eq!(dns.curl_args(), ["--dns-servers", "1.1.1.1,[::1]:5353"]);
+        assert!(DnsResolver::servers(Vec::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolves_with_local_server() {
+        // A UDP server answering every A query with 127.0.0.7
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                let mut answer = buf[..len].to_vec();
+                // Response, recursion available, one answer
+                answer[2] |= 0x80;
+                answer[3] |= 0x80;
+                answer[7] = 1;
+                let qtype = &buf[len - 4..len - 2];
+                if qtype == [0, 1] {
+                    answer.extend([0xc0, 0x0c, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 127, 0, 0, 7]);
+                } else {
+                    answer[7] = 0;
+                }
+                let _ = socket.send_to(&answer, peer).await;
+            }
+        });
+
+        let dns = DnsResolver::servers(vec![DnsServer(addr)]).unwrap();
+        let addrs: Vec<_> = dns.resolve(Name::from_str("api.test").unwrap()).await.unwrap().collect();
+        assert_eq!(addrs, [SocketAddr::from(([127, 0, 0, 7], 0))]);
+    }
+}
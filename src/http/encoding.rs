@@ -0,0 +1,261 @@
+//! Response decompression (`--compressed`).
+//!
+//! A request sent with [`HttpRequest::compressed`](super::HttpRequest::compressed)
+//! advertises [`ACCEPT_ENCODING`], and the body of the response is
+//! decompressed according to its `Content-Encoding` before it is shown or
+//! saved. Encodings applied in sequence (`Content-Encoding: gzip, br`) are
+//! undone in reverse order. The sizes before and after are kept as
+//! [`BodySizes`] for the verbose output.
+//!
+//! Request bodies can be compressed the other way with [`gzip`]
+//! (`--compress-body`).
+
+use std::io::{self, Write};
+
+use brotli_decompressor::DecompressorWriter;
+use flate2::write::{DeflateDecoder, GzDecoder, GzEncoder, ZlibDecoder};
+use flate2::Compression;
+use reqwest::header::{HeaderMap, CONTENT_ENCODING};
+
+/// `Accept-Encoding` sent by `--compressed`.
+pub const ACCEPT_ENCODING: &str = "gzip, br, deflate, zstd";
+
+/// Buffer size of the brotli decoder.
+const BROTLI_BUFFER: usize = 8 * 1024;
+
+/// Size of a response body as received and after decompression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodySizes {
+    /// Bytes received over the wire
+    pub received: u64,
+    /// Bytes after decompression
+    pub decoded: u64,
+}
+
+/// Decoder for one content coding, writing into a buffer that is drained
+/// after every chunk.
+enum Stage {
+    Gzip(GzDecoder<Vec<u8>>),
+    /// `deflate`, which is zlib-wrapped by the spec but sent raw by some
+    /// servers; decided on the first bytes
+    Deflate(Option<DeflateStage>),
+    Brotli(Box<DecompressorWriter<Vec<u8>>>),
+    Zstd(zstd::stream::write::Decoder<'static, Vec<u8>>),
+}
+
+enum DeflateStage {
+    Zlib(ZlibDecoder<Vec<u8>>),
+    Raw(DeflateDecoder<Vec<u8>>),
+}
+
+impl Stage {
+    /// Returns the decoder for a content coding, or `None` if it isn't supported.
+    fn for_coding(coding: &str) -> Option<io::Result<Self>> {
+        match coding.to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Ok(Self::Gzip(GzDecoder::new(Vec::new())))),
+            "deflate" => Some(Ok(Self::Deflate(None))),
+            "br" => Some(Ok(Self::Brotli(Box::new(DecompressorWriter::new(Vec::new(), BROTLI_BUFFER))))),
+            "zstd" => Some(zstd::stream::write::Decoder::new(Vec::new()).map(Self::Zstd)),
+            _ => None,
+        }
+    }
+
+    /// Decodes a chunk, returning what could be decoded so far.
+    fn write(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        if chunk.is_empty() {
+            return Ok(Vec::new());
+        }
+        let output = match self {
+            Self::Gzip(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+            Self::Deflate(stage) => {
+                let stage = stage.get_or_insert_with(|| match is_zlib(chunk) {
+                    true => DeflateStage::Zlib(ZlibDecoder::new(Vec::new())),
+                    false => DeflateStage::Raw(DeflateDecoder::new(Vec::new())),
+                });
+                match stage {
+                    DeflateStage::Zlib(decoder) => {
+                        decoder.write_all(chunk)?;
+                        decoder.get_mut()
+                    }
+                    DeflateStage::Raw(decoder) => {
+                        decoder.write_all(chunk)?;
+                        decoder.get_mut()
+                    }
+                }
+            }
+            Self::Brotli(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+            Self::Zstd(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+        };
+        Ok(std::mem::take(output))
+    }
+
+    /// Ends the stream, returning the rest of the decoded output.
+    fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Gzip(decoder) => decoder.finish(),
+            Self::Deflate(None) => Ok(Vec::new()),
+            Self::Deflate(Some(DeflateStage::Zlib(decoder))) => decoder.finish(),
+            Self::Deflate(Some(DeflateStage::Raw(decoder))) => decoder.finish(),
+            Self::Brotli(mut decoder) => {
+                decoder.close()?;
+                Ok(std::mem::take(decoder.get_mut()))
+            }
+            Self::Zstd(mut decoder) => {
+                decoder.flush()?;
+                Ok(decoder.into_inner())
+            }
+        }
+    }
+}
+
+/// Returns true if a deflate stream starts with a zlib header.
+fn is_zlib(chunk: &[u8]) -> bool {
+    match chunk {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => true,
+    }
+}
+
+/// Streaming decoder for a response's `Content-Encoding`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut decoder = Decoder::new("gzip").unwrap()?;
+/// let mut body = decoder.write(&chunk)?;
+/// body.extend(decoder.finish()?);
+/// ```
+pub struct Decoder {
+    /// Stages in the order they are undone
+    stages: Vec<Stage>,
+}
+
+impl Decoder {
+    /// Creates a decoder for a `Content-Encoding` header value.
+    ///
+    /// Returns `None` if the value names a coding that isn't supported, or
+    /// only `identity`.
+    pub fn new(content_encoding: &str) -> Option<io::Result<Self>> {
+        let codings = content_encoding
+            .split(',')
+            .map(str::trim)
+            .filter(|coding| !coding.is_empty() && !coding.eq_ignore_ascii_case("identity"));
+        let mut stages = Vec::new();
+        for coding in codings.rev() {
+            match Stage::for_coding(coding)? {
+                Ok(stage) => stages.push(stage),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        (!stages.is_empty()).then_some(Ok(Self { stages }))
+    }
+
+    /// Creates a decoder for the `Content-Encoding` of a response, if it
+    /// has a supported one.
+    pub fn for_headers(headers: &HeaderMap) -> Option<io::Result<Self>> {
+        Self::new(headers.get(CONTENT_ENCODING)?.to_str().ok()?)
+    }
+
+    /// Decodes a chunk of the body, returning what could be decoded so far.
+    pub fn write(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        let mut data = chunk.to_vec();
+        for stage in &mut self.stages {
+            data = stage.write(&data)?;
+        }
+        Ok(data)
+    }
+
+    /// Ends the body, returning the rest of the decoded output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body was cut short or corrupt.
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        let mut pending = Vec::new();
+        for mut stage in self.stages {
+            let mut output = stage.write(&pending)?;
+            output.extend(stage.finish()?);
+            pending = output;
+        }
+        Ok(pending)
+    }
+}
+
+/// Decompresses a whole body according to a `Content-Encoding` value.
+///
+/// Returns `None` if the encoding isn't supported, so the body is left as
+/// received.
+pub fn decode(content_encoding: &str, body: &[u8]) -> Option<io::Result<Vec<u8>>> {
+    let decoder = Decoder::new(content_encoding)?;
+    Some(decoder.and_then(|mut decoder| {
+        let mut decoded = decoder.write(body)?;
+        decoded.extend(decoder.finish()?);
+        Ok(decoded)
+    }))
+}
+
+/// Compresses a request body with gzip (`--compress-body`).
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(data.len() / 2), Compression::default());
+    // Writing to a Vec can't fail
+    encoder.write_all(data).expect("write to Vec");
+    encoder.finish().expect("write to Vec")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{DeflateEncoder, ZlibEncoder};
+
+    const TEXT: &[u8] = b"hurley hurley hurley hurley hurley hurley hurley hurley";
+
+    #[test]
+    fn test_decode_codings() {
+        assert_eq!(decode("gzip", &gzip(TEXT)).unwrap().unwrap(), TEXT);
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(TEXT).unwrap();
+        assert_eq!(decode("deflate", &zlib.finish().unwrap()).unwrap().unwrap(), TEXT);
+        let mut raw = DeflateEncoder::new(Vec::new(), Compression::default());
+        raw.write_all(TEXT).unwrap();
+        assert_eq!(decode("deflate", &raw.finish().unwrap()).unwrap().unwrap(), TEXT);
+
+        let zstd = zstd::encode_all(TEXT, 3).unwrap();
+        assert_eq!(decode("zstd", &zstd).unwrap().unwrap(), TEXT);
+
+        let mut brotli = Vec::new();
+        brotli::BrotliCompress(&mut &TEXT[..], &mut brotli, &Default::default()).unwrap();
+        assert_eq!(decode("br", &brotli).unwrap().unwrap(), TEXT);
+    }
+
+    #[test]
+    fn test_decode_in_chunks_and_layers() {
+        // Applied gzip, then zstd
+        let layered = zstd::encode_all(&gzip(TEXT)[..], 3).unwrap();
+        let mut decoder = Decoder::new("gzip, zstd").unwrap().unwrap();
+        let mut decoded = Vec::new();
+        for chunk in layered.chunks(3) {
+            decoded.extend(decoder.write(chunk).unwrap());
+        }
+        decoded.extend(decoder.finish().unwrap());
+        assert_eq!(decoded, TEXT);
+    }
+
+    #[test]
+    fn test_unsupported_or_corrupt() {
+        assert!(decode("identity", TEXT).is_none());
+        assert!(decode("compress", TEXT).is_none());
+        assert!(decode("gzip", TEXT).unwrap().is_err());
+        let truncated = gzip(TEXT);
+        assert!(decode("gzip", &truncated[..truncated.len() / 2]).unwrap().is_err());
+    }
+}
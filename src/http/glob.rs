@@ -0,0 +1,219 @@
+//! curl-style URL globbing (`https://host/item/[1-100]`, `{a,b,c}`).
+//!
+//! A URL with globs stands for one URL per combination of their values,
+//! the leftmost glob varying slowest:
+//!
+//! - `[1-100]`, `[001-100]` (zero-padded to the first number's width),
+//!   `[a-z]`, and any of them with a step: `[0-100:10]`
+//! - `{a,b,c}`: each of the comma-separated values in turn
+//!
+//! Brackets that don't hold a range, like an IPv6 address
+//! (`http://[::1]:8080/`), and braces without a comma or doubled as a
+//! `{{var}}` placeholder are left as they are. `\[`, `\]`, `\{` and `\}`
+//! stand for the character itself.
+
+use crate::error::{Result, RurlError};
+
+/// Most URLs the globs of one URL may expand to, to catch typos like `[1-1000000000]`.
+pub const MAX_URLS: usize = 1_000_000;
+
+/// One glob of a URL, or the text between globs.
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Values(Vec<String>),
+}
+
+/// Expands the globs of a URL into the URLs they stand for.
+///
+/// A URL without globs expands to itself.
+///
+/// # Errors
+///
+/// Returns an error for a malformed range, a nested glob, or more than
+/// [`MAX_URLS`] URLs.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let urls = expand("https://example.com/{users,orders}/[1-2]")?;
+/// assert_eq!(urls, [
+///     "https://example.com/users/1", "https://example.com/users/2",
+///     "https://example.com/orders/1", "https://example.com/orders/2",
+/// ]);
+/// ```
+pub fn expand(url: &str) -> Result<Vec<String>> {
+    let segments = parse(url)?;
+    let count = segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Values(values) => Some(values.len()),
+            Segment::Literal(_) => None,
+        })
+        .try_fold(1usize, |count, n| count.checked_mul(n).filter(|count| *count <= MAX_URLS))
+        .ok_or_else(|| RurlError::InvalidUrl(format!("{} expands to more than {} URLs", url, MAX_URLS)))?;
+
+    let mut urls = vec![String::new()];
+    urls.reserve(count);
+    for segment in &segments {
+        urls = match segment {
+            Segment::Literal(text) => urls.into_iter().map(|url| url + text).collect(),
+            Segment::Values(values) => urls
+                .iter()
+                .flat_map(|url| values.iter().map(move |value| format!("{}{}", url, value)))
+                .collect(),
+        };
+    }
+    Ok(urls)
+}
+
+/// Splits a URL into literal text and globs.
+fn parse(url: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = url;
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+        match c {
+            '\\' if after.starts_with(['[', ']', '{', '}']) => {
+                literal.push_str(&after[..1]);
+                rest = &after[1..];
+            }
+            // A {{var}} placeholder, filled in later
+            '{' if after.starts_with('{') => {
+                let end = after.find("}}").map_or(rest.len(), |i| i + 3);
+                literal.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+            '{' | '[' => {
+                let close = if c == '{' { '}' } else { ']' };
+                let glob = match after.find(close) {
+                    Some(end) => glob_values(c, &after[..end], url)?.map(|values| (end, values)),
+                    None => None,
+                };
+                match glob {
+                    Some((end, values)) => {
+                        if !literal.is_empty() {
+                            segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                        }
+                        segments.push(Segment::Values(values));
+                        rest = &after[end + 1..];
+                    }
+                    None => {
+                        literal.push(c);
+                        rest = after;
+                    }
+                }
+            }
+            _ => {
+                literal.push(c);
+                rest = after;
+            }
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+/// Returns the values of a `{...}` or `[...]` glob, or `None` if the text
+/// between the delimiters isn't one.
+fn glob_values(open: char, inner: &str, url: &str) -> Result<Option<Vec<String>>> {
+    let invalid = |problem: &str| RurlError::InvalidUrl(format!("{}: {} in '{}{}'", url, problem, open, inner));
+    if inner.contains(['[', '{']) {
+        return Err(invalid("nested globs are not supported"));
+    }
+    match open {
+        '{' if inner.contains(',') => Ok(Some(inner.split(',').map(str::to_string).collect())),
+        '{' => Ok(None),
+        _ => {
+            let (range, step) = match inner.split_once(':') {
+                Some((range, step)) => (range, Some(step)),
+                None => (inner, None),
+            };
+            let Some((first, last)) = range.split_once('-') else { return Ok(None) };
+            let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+            let letter = |s: &str| match s.as_bytes() {
+                [b] if b.is_ascii_alphabetic() => Some(b.is_ascii_lowercase()),
+                _ => None,
+            };
+            let numeric = is_number(first) && is_number(last);
+            // Both letters of the same case
+            let alphabetic = letter(first).is_some() && letter(first) == letter(last);
+            if !numeric && !alphabetic {
+                // Not a range, e.g. an IPv6 address
+                return Ok(None);
+            }
+            let step = match step.map(str::parse::<u64>) {
+                None => 1,
+                Some(Ok(step)) if step > 0 => step,
+                Some(_) => return Err(invalid("the step must be a positive number")),
+            };
+            let values: Vec<String> = if numeric {
+                let (start, end) = (first.parse::<u64>(), last.parse::<u64>());
+                let (Ok(start), Ok(end)) = (start, end) else { return Err(invalid("the range is too large")) };
+                if start > end {
+                    return Err(invalid("the range ends before it starts"));
+                }
+                if (end - start) / step >= MAX_URLS as u64 {
+                    return Err(invalid(&format!("the range has more than {} values", MAX_URLS)));
+                }
+                let width = if first.starts_with('0') { first.len() } else { 0 };
+                (start..=end).step_by(step as usize).map(|n| format!("{:0width$}", n, width = width)).collect()
+            } else {
+                let (start, end) = (first.as_bytes()[0], last.as_bytes()[0]);
+                if start > end {
+                    return Err(invalid("the range ends before it starts"));
+                }
+                (start..=end).step_by(step.min(26) as usize).map(|b| (b as char).to_string()).collect()
+            };
+            Ok(Some(values))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numeric_ranges() {
+        assert_eq!(expand("http://h/item/[1-3]").unwrap(), ["http://h/item/1", "http://h/item/2", "http://h/item/3"]);
+        assert_eq!(expand("http://h/[08-10].txt").unwrap(), ["http://h/08.txt", "http://h/09.txt", "http://h/10.txt"]);
+        assert_eq!(expand("http://h/?page=[0-100:50]").unwrap(), ["http://h/?page=0", "http://h/?page=50", "http://h/?page=100"]);
+        assert_eq!(expand("http://h/[x-z]").unwrap(), ["http://h/x", "http://h/y", "http://h/z"]);
+    }
+
+    #[test]
+    fn test_sets_and_combinations() {
+        assert_eq!(
+            expand("http://h/{users,orders}/[1-2]").unwrap(),
+            ["http://h/users/1", "http://h/users/2", "http://h/orders/1", "http://h/orders/2"]
+        );
+        assert_eq!(expand("http://{a,b}.example.com/").unwrap(), ["http://a.example.com/", "http://b.example.com/"]);
+    }
+
+    #[test]
+    fn test_literals_left_alone() {
+        for url in [
+            "http://h/plain",
+            "http://[::1]:8080/",
+            "http://[fe80::1%25eth0]/health",
+            "http://h/{{id}}/x",
+            "http://h/{id}",
+            "http://h/\\[1-2\\]",
+        ] {
+            assert_eq!(expand(url).unwrap().len(), 1, "{}", url);
+        }
+        assert_eq!(expand("http://h/\\[1-2\\]").unwrap(), ["http://h/[1-2]"]);
+    }
+
+    #[test]
+    fn test_invalid_globs() {
+        assert!(expand("http://h/[5-1]").unwrap_err().to_string().contains("ends before"));
+        assert!(expand("http://h/[1-5:0]").is_err());
+        assert!(expand("http://h/{a,[1-2]}").unwrap_err().to_string().contains("nested"));
+        assert!(expand("http://h/[1-1000]/[1-1000]/[1-10]").unwrap_err().to_string().contains("more than"));
+    }
+}
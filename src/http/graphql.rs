@@ -0,0 +1,148 @@
+//! GraphQL request support.
+//!
+//! Wraps a query and its variables into the standard JSON envelope
+//! (`{"query": ..., "variables": ...}`) and splits responses into their
+//! `data` and `errors` parts.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Result, RurlError};
+
+/// A GraphQL operation: query document plus optional variables.
+///
+/// Deserializes from the same shape it is sent as, so dataset entries can
+/// carry a `"graphql": {"query": "...", "variables": {...}}` block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphQlQuery {
+    /// Query or mutation document
+    pub query: String,
+    /// Variables referenced by the document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Value>,
+    /// Operation to run when the document defines several
+    #[serde(default, rename = "operationName", skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<String>,
+}
+
+impl GraphQlQuery {
+    /// Creates a query without variables.
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            variables: None,
+            operation_name: None,
+        }
+    }
+
+    /// Parses and sets the variables from a JSON object string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `json` is not a JSON object.
+    pub fn variables_json(mut self, json: &str) -> Result<Self> {
+        let variables: Value = serde_json::from_str(json)?;
+        if !variables.is_object() {
+            return Err(RurlError::JsonError(serde::de::Error::custom(
+                "GraphQL variables must be a JSON object",
+            )));
+        }
+        self.variables = Some(variables);
+        Ok(self)
+    }
+
+    /// Returns the JSON request body.
+    pub fn to_body(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Reads an argument that is either inline text or `@path` to a file.
+///
+/// # Errors
+///
+/// Returns an error if the referenced file cannot be read.
+pub fn inline_or_file(arg: &str) -> Result<String> {
+    match arg.strip_prefix('@') {
+        Some(path) => Ok(std::fs::read_to_string(path)?),
+        None => Ok(arg.to_string()),
+    }
+}
+
+/// The `data` and `errors` parts of a GraphQL response.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GraphQlResponse {
+    /// Result of the operation, `null` if it failed entirely
+    #[serde(default)]
+    pub data: Option<Value>,
+    /// Errors reported by the server
+    #[serde(default)]
+    pub errors: Vec<Value>,
+}
+
+impl GraphQlResponse {
+    /// Parses a response body, returning `None` if it isn't a GraphQL response.
+    pub fn parse(body: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(body).ok()?;
+        let object = value.as_object()?;
+        if !object.contains_key("data") && !object.contains_key("errors") {
+            return None;
+        }
+        serde_json::from_value(value).ok()
+    }
+
+    /// Returns a one-line summary of each error: its message and path.
+    pub fn error_messages(&self) -> Vec<String> {
+        self.errors
+            .iter()
+            .map(|error| {
+                let message = error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string());
+                match error.get("path").and_then(Value::as_array) {
+                    Some(path) if !path.is_empty() => {
+                        let path: Vec<String> = path
+                            .iter()
+                            .map(|p| p.as_str().map(str::to_string).unwrap_or_else(|| p.to_string()))
+                            .collect();
+                        format!("{} (at {})", message, path.join("."))
+                    }
+                    _ => message,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope() {
+        let query = GraphQlQuery::new("query($id: ID!) { user(id: $id) { name } }")
+            .variables_json(r#"{"id": "1"}"#)
+            .unwrap();
+        let body: Value = serde_json::from_str(&query.to_body()).unwrap();
+        assert_eq!(body["query"], "query($id: ID!) { user(id: $id) { name } }");
+        assert_eq!(body["variables"]["id"], "1");
+        assert!(body.get("operationName").is_none());
+
+        assert!(GraphQlQuery::new("{ a }").variables_json("[1]").is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let response = GraphQlResponse::parse(
+            r#"{"data": {"user": null}, "errors": [{"message": "not found", "path": ["user", 0]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(response.data, Some(serde_json::json!({"user": null})));
+        assert_eq!(response.error_messages(), vec!["not found (at user.0)"]);
+
+        assert!(GraphQlResponse::parse(r#"{"user": 1}"#).is_none());
+        assert!(GraphQlResponse::parse("plain text").is_none());
+    }
+}
@@ -0,0 +1,59 @@
+//! The process-wide `log` logger.
+//!
+//! hurley reads two kinds of records from its HTTP stack: reqwest's
+//! per-connection reads and writes for the wire trace ([`super::wire`]), and
+//! hyper's connection events for phase timing ([`super::timing`]). Only one
+//! logger can be installed per process, so this one hands each record to
+//! whichever of them is enabled.
+
+use std::sync::OnceLock;
+
+use crate::error::{Result, RurlError};
+use super::{timing, wire};
+
+/// Targets of hyper's connection events.
+const HYPER_TARGETS: [&str; 2] = ["hyper::client::connect::http", "hyper::client::client"];
+
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        let target = metadata.target();
+        (target == wire::VERBOSE_TARGET && wire::is_enabled())
+            || (HYPER_TARGETS.contains(&target) && timing::is_enabled())
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if record.target() == wire::VERBOSE_TARGET {
+            wire::record(&record.args().to_string());
+        } else {
+            timing::observe(&record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {
+        wire::flush();
+    }
+}
+
+/// Installs the logger, if it isn't already.
+///
+/// # Errors
+///
+/// Returns an error if another logger is already installed.
+pub(crate) fn install() -> Result<()> {
+    static INSTALLED: OnceLock<std::result::Result<(), String>> = OnceLock::new();
+    INSTALLED
+        .get_or_init(|| {
+            log::set_logger(&LOGGER).map_err(|e| e.to_string())?;
+            log::set_max_level(log::LevelFilter::Trace);
+            Ok(())
+        })
+        .clone()
+        .map_err(|e| RurlError::ConfigError(format!("cannot install logger: {}", e)))
+}
@@ -0,0 +1,135 @@
+//! Request middleware.
+//!
+//! A [`Middleware`] registered on an [`HttpClient`](super::HttpClient)
+//! sees every request the client sends and every response it gets back.
+//! This is a hook API for library users to add behavior of their own, such
+//! as request IDs or logging, without changing the client. The client's
+//! own features (User-Agent, authentication, caching, retries) are not
+//! built on it, and the command-line tool registers no middleware.
+//!
+//! Middleware run in the order they were registered before a request is
+//! sent, and in reverse order after the response arrives, so the first one
+//! registered wraps all the others.
+//!
+//! `before` runs ahead of the response cache and of authentication, so a
+//! header it adds is part of what gets cached and what an
+//! [`AuthProvider`](super::AuthProvider) such as SigV4 signs.
+//!
+//! Ready-made middleware:
+//!
+//! | Middleware | Does |
+//! |------------|------|
+//! | [`SetHeaders`] | Adds headers to every request |
+//! | [`LogRequests`] | Logs each exchange through the `log` crate |
+
+use crate::error::Result;
+use super::request::HttpRequest;
+use super::response::HttpResponse;
+
+/// Code run around every request a client sends.
+///
+/// Both methods default to doing nothing, so a middleware only implements
+/// the side it needs.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// struct RequestId(AtomicU64);
+///
+/// impl Middleware for RequestId {
+///     fn before(&self, request: &mut HttpRequest) -> Result<()> {
+///         let id = self.0.fetch_add(1, Ordering::Relaxed);
+///         request.headers.insert("X-Request-Id".into(), id.to_string());
+///         Ok(())
+///     }
+/// }
+///
+/// let client = HttpClient::new(false).middleware(Arc::new(RequestId(AtomicU64::new(1))));
+/// ```
+pub trait Middleware: Send + Sync {
+    /// Changes `request` before it is sent.
+    ///
+    /// # Errors
+    ///
+    /// An error fails the request without sending it.
+    fn before(&self, _request: &mut HttpRequest) -> Result<()> {
+        Ok(())
+    }
+
+    /// Inspects the response to `request`, which is the request as sent.
+    ///
+    /// Not called when the request fails without a response. The body is
+    /// empty for responses read with
+    /// [`execute_discarding`](super::HttpClient::execute_discarding) or
+    /// [`execute_to_file`](super::HttpClient::execute_to_file).
+    fn after(&self, _request: &HttpRequest, _response: &HttpResponse) {}
+}
+
+/// Adds headers to every request, replacing ones of the same name.
+#[derive(Debug, Clone, Default)]
+pub struct SetHeaders {
+    headers: Vec<(String, String)>,
+}
+
+impl SetHeaders {
+    /// Creates a middleware adding no headers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a header to set on every request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+impl Middleware for SetHeaders {
+    fn before(&self, request: &mut HttpRequest) -> Result<()> {
+        for (name, value) in &self.headers {
+            // Header names are case-insensitive, so drop any spelling of it
+            request.headers.retain(|existing, _| !existing.eq_ignore_ascii_case(name));
+            request.headers.insert(name.clone(), value.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Logs each exchange at `info` level through the `log` crate, as
+/// `GET https://host/path -> 200 OK in 12.3 ms`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogRequests;
+
+impl Middleware for LogRequests {
+    fn after(&self, request: &HttpRequest, response: &HttpResponse) {
+        log::info!(
+            "{} {} -> {} in {:.1} ms",
+            request.method,
+            request.url,
+            response.status,
+            response.duration.as_secs_f64() * 1000.0
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_headers_replaces_any_case() {
+        let mut request = HttpRequest::new("http://localhost/")
+            .header("x-api-key", "old")
+            .header("Accept", "text/plain");
+        SetHeaders::new()
+            .header("X-Api-Key", "new")
+            .header("X-Trace", "1")
+            .before(&mut request)
+            .unwrap();
+        assert_eq!(request.header_value("x-api-key"), Some("new"));
+        assert_eq!(request.headers.get("X-Api-Key").map(String::as_str), Some("new"));
+        assert!(!request.headers.contains_key("x-api-key"));
+        assert_eq!(request.header_value("x-trace"), Some("1"));
+        assert_eq!(request.header_value("accept"), Some("text/plain"));
+    }
+}
@@ -4,11 +4,72 @@
 //! - [`HttpClient`] - Executes HTTP requests
 //! - [`HttpRequest`] - Request builder with method, headers, body
 //! - [`HttpResponse`] - Response with status, headers, body, timing
+//! - [`GraphQlQuery`] - GraphQL query envelope (`--graphql`)
+//! - [`ResolveEntry`] - curl-style DNS overrides (`--resolve`)
+//! - [`DnsResolver`] - Custom name servers and DNS-over-HTTPS (`--dns-server`, `--doh-url`)
+//! - [`glob`] - curl-style URL globbing (`[1-100]`, `{a,b,c}`)
+//! - [`RendererRegistry`] - Content-type driven body rendering
+//! - [`multipart`] - Splitting `multipart/mixed` and `multipart/byteranges` bodies into parts
+//! - [`ByteRange`] - Byte-range requests and checking their answers (`--range`)
+//! - [`WireTrace`] - Dump of the bytes sent and received (`--trace-wire`)
+//! - [`PhaseTimings`] - DNS, connect, TLS, time to first byte and transfer per request
+//! - [`AcceptValue`] - `Accept` presets and content negotiation testing (`--accept`, `--negotiate`)
+//! - [`encoding`] - Response decompression (`--compressed`)
+//! - [`HttpCache`] - On-disk response cache honoring `Cache-Control` and validators (`--cache`)
+//! - [`conditional`] - ETag and `If-Modified-Since` validators for 304 testing (`--etag-compare`, `--if-modified-since`)
+//! - [`CookieAudit`] - `Set-Cookie` summary and attribute audit (`--cookies`)
+//! - [`AuthChain`] - Authentication providers resolved per request (`--auth`)
+//! - [`Middleware`] - Hooks run before each request and after its response
+//! - [`ClientIdentity`] - Client certificates for mutual TLS, reloadable (`--cert`)
+//! - [`SocketOptions`] - Local bind address, `TCP_NODELAY` and TCP keepalive (`--local-addr`, `--tcp-keepalive`)
+//! - [`HttpVersion`] - Pins a request to HTTP/1.0, HTTP/1.1 or HTTP/2
+//! - [`send_raw`] - Sends hand-written request bytes (`--raw-request`)
+//! - [`zone`] - IPv6 zone identifiers in URLs (`http://[fe80::1%25eth0]/`)
 
+pub mod auth;
+pub mod cache;
 pub mod client;
+pub mod conditional;
+pub mod cookies;
+pub mod dns;
+pub mod encoding;
+pub mod glob;
+pub mod graphql;
+mod logger;
+pub mod middleware;
+pub mod multipart;
+pub mod negotiate;
+pub mod range;
+pub mod raw;
+pub mod redirect;
+pub mod render;
 pub mod request;
+pub mod resolve;
 pub mod response;
+pub mod sigv4;
+pub mod socket;
+pub mod timing;
+pub mod tls;
+pub mod version;
+pub mod wire;
+pub mod zone;
 
+pub use auth::{AuthChain, AuthProvider, AuthSpec, CurlArgs};
+pub use cache::{CacheStatus, HttpCache};
 pub use client::HttpClient;
-pub use request::HttpRequest;
+pub use cookies::{CookieAudit, CookieWarning, SetCookie};
+pub use dns::{DnsResolver, DnsServer};
+pub use graphql::GraphQlQuery;
+pub use middleware::{LogRequests, Middleware, SetHeaders};
+pub use negotiate::{negotiate, AcceptValue, NegotiationReport};
+pub use range::ByteRange;
+pub use raw::{send_raw, RawResponse, RAW_MAX_BYTES};
+pub use render::{BodyRenderer, RendererRegistry};
+pub use request::{check_method, HttpRequest, PreparedRequest, DEFAULT_MAX_REDIRECTS, DEFAULT_USER_AGENT};
+pub use resolve::ResolveEntry;
 pub use response::HttpResponse;
+pub use socket::SocketOptions;
+pub use timing::PhaseTimings;
+pub use tls::ClientIdentity;
+pub use version::HttpVersion;
+pub use wire::WireTrace;
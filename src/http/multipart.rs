@@ -0,0 +1,222 @@
+//! Multipart response bodies (`multipart/mixed`, `multipart/byteranges`).
+//!
+//! Batch APIs answer with `multipart/mixed` and range requests for several
+//! ranges with `multipart/byteranges`. [`parse`] splits such a body into
+//! its [`Part`]s so each can be shown with its own headers and renderer,
+//! or saved to a file of its own (`--save-parts`).
+
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, RurlError};
+
+/// One part of a multipart body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    /// Headers of the part
+    pub headers: HeaderMap,
+    /// Body of the part, as received
+    pub body: Bytes,
+}
+
+impl Part {
+    /// Returns the part's `Content-Type`, if it has one.
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok())
+    }
+
+    /// Returns the file name a part is saved under: `part-N` with an
+    /// extension for its content type.
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - 1-based position of the part
+    pub fn file_name(&self, number: usize) -> String {
+        let mime = self.content_type().unwrap_or_default();
+        let essence = mime.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        let extension = match essence.as_str() {
+            "application/json" => "json",
+            m if m.ends_with("+json") => "json",
+            "application/xml" | "text/xml" => "xml",
+            m if m.ends_with("+xml") => "xml",
+            "text/html" => "html",
+            "text/csv" => "csv",
+            "text/plain" => "txt",
+            "image/png" => "png",
+            "image/jpeg" => "jpg",
+            "image/gif" => "gif",
+            "application/pdf" => "pdf",
+            _ => "bin",
+        };
+        format!("part-{}.{}", number, extension)
+    }
+}
+
+/// Returns true for a content type whose body can be split into parts.
+pub fn is_multipart(content_type: &str) -> bool {
+    content_type.trim().to_ascii_lowercase().starts_with("multipart/")
+}
+
+/// Returns the `boundary` parameter of a multipart content type.
+pub fn boundary(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim().trim_matches('"').to_string())
+            .filter(|b| !b.is_empty())
+    })
+}
+
+/// Splits a multipart body into its parts.
+///
+/// Lines may end in CRLF or a bare LF. The preamble before the first
+/// boundary and the epilogue after the last are ignored.
+///
+/// # Errors
+///
+/// Returns [`RurlError::FilterError`] if the content type has no boundary
+/// or the body has no parts.
+pub fn parse(body: &[u8], content_type: &str) -> Result<Vec<Part>> {
+    let boundary = boundary(content_type)
+        .ok_or_else(|| RurlError::FilterError(format!("no multipart boundary in '{}'", content_type)))?;
+    let delimiter = format!("--{}", boundary).into_bytes();
+
+    // The first delimiter may start the body; later ones start a line
+    let mut at = if body.starts_with(&delimiter) {
+        0
+    } else {
+        find(body, &[b"\n", &delimiter[..]].concat(), 0)
+            .map(|i| i + 1)
+            .ok_or_else(|| RurlError::FilterError("multipart body has no parts".to_string()))?
+    };
+    let mut parts = Vec::new();
+    loop {
+        let rest = &body[at + delimiter.len()..];
+        if rest.starts_with(b"--") {
+            break;
+        }
+        // Anything after the delimiter on its line is padding
+        let Some(line_end) = find(rest, b"\n", 0) else { break };
+        let start = at + delimiter.len() + line_end + 1;
+        let next = find(body, &[b"\n", &delimiter[..]].concat(), start.saturating_sub(1));
+        let end = next.unwrap_or(body.len());
+        let mut content = &body[start.min(end)..end];
+        if content.ends_with(b"\r") {
+            content = &content[..content.len() - 1];
+        }
+        parts.push(parse_part(content));
+        match next {
+            Some(next) => at = next + 1,
+            None => break,
+        }
+    }
+    if parts.is_empty() {
+        return Err(RurlError::FilterError("multipart body has no parts".to_string()));
+    }
+    Ok(parts)
+}
+
+/// Splits a part into its headers and body.
+fn parse_part(content: &[u8]) -> Part {
+    let (head, body) = if content.starts_with(b"\r\n") {
+        (&content[..0], &content[2..])
+    } else if content.starts_with(b"\n") {
+        (&content[..0], &content[1..])
+    } else {
+        match (find(content, b"\r\n\r\n", 0), find(content, b"\n\n", 0)) {
+            (Some(crlf), Some(lf)) if lf < crlf => (&content[..lf], &content[lf + 2..]),
+            (Some(crlf), _) => (&content[..crlf], &content[crlf + 4..]),
+            (None, Some(lf)) => (&content[..lf], &content[lf + 2..]),
+            (None, None) => (content, &content[content.len()..]),
+        }
+    };
+    let mut headers = HeaderMap::new();
+    for line in String::from_utf8_lossy(head).lines() {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.trim().as_bytes()), HeaderValue::from_str(value.trim())) {
+            headers.append(name, value);
+        }
+    }
+    Part { headers, body: Bytes::copy_from_slice(body) }
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack.get(from..)?.windows(needle.len()).position(|w| w == needle).map(|i| i + from)
+}
+
+/// Writes each part to `dir` as `part-N.EXT`, creating the directory if needed.
+///
+/// # Errors
+///
+/// Returns an error if the directory or a file cannot be written.
+pub fn save_parts(parts: &[Part], dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| {
+            let path = dir.join(part.file_name(index + 1));
+            std::fs::write(&path, &part.body)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIXED: &str = "preamble\r\n--b1\r\nContent-Type: application/json\r\n\r\n{\"a\":1}\r\n\
+                         --b1\r\nContent-Type: text/plain\r\nX-Id: 2\r\n\r\nhello\r\nworld\r\n--b1--\r\nepilogue";
+
+    #[test]
+    fn test_boundary() {
+        assert_eq!(boundary("multipart/mixed; boundary=b1").as_deref(), Some("b1"));
+        assert_eq!(boundary(r#"multipart/byteranges; charset=x; Boundary="a b""#).as_deref(), Some("a b"));
+        assert_eq!(boundary("multipart/mixed"), None);
+        assert!(is_multipart("Multipart/Mixed; boundary=x"));
+        assert!(!is_multipart("application/json"));
+    }
+
+    #[test]
+    fn test_parse_mixed() {
+        let parts = parse(MIXED.as_bytes(), "multipart/mixed; boundary=b1").unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].content_type(), Some("application/json"));
+        assert_eq!(parts[0].body, "{\"a\":1}");
+        assert_eq!(parts[1].headers["x-id"], "2");
+        assert_eq!(parts[1].body, "hello\r\nworld");
+        assert_eq!(parts[0].file_name(1), "part-1.json");
+        assert_eq!(parts[1].file_name(2), "part-2.txt");
+    }
+
+    #[test]
+    fn test_parse_byteranges_with_lf() {
+        let body = "--r\nContent-Type: application/octet-stream\nContent-Range: bytes 0-3/10\n\n\x00\x01\x02\x03\n--r\n\
+                    Content-Range: bytes 8-9/10\n\n\x08\x09\n--r--\n";
+        let parts = parse(body.as_bytes(), "multipart/byteranges; boundary=r").unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(&parts[0].body[..], &[0, 1, 2, 3]);
+        assert_eq!(parts[1].headers["content-range"], "bytes 8-9/10");
+        assert_eq!(parts[1].file_name(2), "part-2.bin");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(b"--x\r\n\r\nbody\r\n--x--", "multipart/mixed").is_err());
+        assert!(parse(b"no parts here", "multipart/mixed; boundary=x").is_err());
+    }
+
+    #[test]
+    fn test_save_parts() {
+        let dir = std::env::temp_dir().join("hurley_test_parts");
+        std::fs::remove_dir_all(&dir).ok();
+        let parts = parse(MIXED.as_bytes(), "multipart/mixed; boundary=b1").unwrap();
+        let paths = save_parts(&parts, &dir).unwrap();
+        assert_eq!(std::fs::read_to_string(&paths[0]).unwrap(), "{\"a\":1}");
+        assert_eq!(paths[1].file_name().unwrap(), "part-2.txt");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,264 @@
+//! `Accept` header presets and content negotiation testing.
+//!
+//! `--accept json` is shorthand for `-H 'Accept: application/json'`. With
+//! `--negotiate`, the same URL is requested once per `--accept` value (all
+//! presets by default) and the report shows which representations the
+//! server offers, and whether it honors `Accept` at all:
+//!
+//! ```text
+//! hurley https://api.example.com/users/1 --negotiate --accept json,xml,text/csv
+//! ```
+
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "cli")]
+use colored::Colorize;
+
+use crate::error::{Result, RurlError};
+use super::{HttpClient, HttpRequest};
+
+/// Built-in `Accept` presets and the header values they send.
+const PRESETS: &[(&str, &str)] = &[
+    ("json", "application/json"),
+    ("xml", "application/xml, text/xml;q=0.9"),
+    ("html", "text/html, application/xhtml+xml;q=0.9"),
+    ("any", "*/*"),
+];
+
+/// An `Accept` header value: a preset name or a literal media range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptValue {
+    name: String,
+    value: String,
+}
+
+impl AcceptValue {
+    /// Returns every preset, for negotiating without explicit values.
+    pub fn presets() -> Vec<Self> {
+        PRESETS
+            .iter()
+            .map(|(name, value)| Self {
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+            .collect()
+    }
+
+    /// Returns the header value to send.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns true if a response of `content_type` satisfies this value.
+    ///
+    /// Parameters are ignored; `*/*` and `type/*` ranges match by wildcard.
+    pub fn accepts(&self, content_type: &str) -> bool {
+        let media_type = essence(content_type);
+        self.value.split(',').map(essence).any(|range| match range.split_once('/') {
+            Some(("*", "*")) => true,
+            Some((kind, "*")) => media_type.split_once('/').is_some_and(|(k, _)| k == kind),
+            _ => range == media_type,
+        })
+    }
+}
+
+impl FromStr for AcceptValue {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some((name, value)) = PRESETS.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+            return Ok(Self {
+                name: name.to_string(),
+                value: value.to_string(),
+            });
+        }
+        if s.contains('/') {
+            return Ok(Self {
+                name: s.to_string(),
+                value: s.to_string(),
+            });
+        }
+        let names: Vec<_> = PRESETS.iter().map(|(name, _)| *name).collect();
+        Err(RurlError::InvalidHeader(format!(
+            "unknown Accept preset '{}' (expected {} or a media type like text/csv)",
+            s,
+            names.join(", ")
+        )))
+    }
+}
+
+impl fmt::Display for AcceptValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Returns the lowercased media type without parameters.
+fn essence(media_type: &str) -> String {
+    media_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+/// The response to one `Accept` value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NegotiationResult {
+    /// Preset name or media range as given
+    pub accept: String,
+    /// `Accept` header sent
+    pub accept_header: String,
+    /// Response status code
+    pub status: u16,
+    /// Response media type, without parameters
+    pub content_type: Option<String>,
+    /// Response body size in bytes
+    pub bytes: usize,
+    /// Whether the server answered successfully with an acceptable type
+    pub satisfied: bool,
+}
+
+/// Which representations a server offers for one URL.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NegotiationReport {
+    /// Requested URL
+    pub url: String,
+    /// One result per `Accept` value, in the order given
+    pub results: Vec<NegotiationResult>,
+    /// Distinct media types served with a successful status
+    pub offered: Vec<String>,
+}
+
+impl NegotiationReport {
+    /// Returns true if every successful response had the same media type
+    /// although different ones were asked for, i.e. `Accept` is ignored.
+    pub fn ignores_accept(&self) -> bool {
+        self.offered.len() == 1 && self.results.iter().filter(|r| (200..300).contains(&r.status)).count() > 1
+    }
+}
+
+/// Terminal output, only available with the `cli` feature.
+#[cfg(feature = "cli")]
+impl NegotiationReport {
+    /// Prints one line per `Accept` value and the offered representations.
+    pub fn print(&self) {
+        println!();
+        for result in &self.results {
+            let status = match result.status {
+                200..=299 => result.status.to_string().green(),
+                400..=499 => result.status.to_string().yellow(),
+                _ => result.status.to_string().red(),
+            };
+            let served = result.content_type.as_deref().unwrap_or("(no content type)");
+            let mark = if result.satisfied { "✓".green() } else { "✗".red() };
+            println!(
+                "   {} {} {}  {:<32} {:>8} bytes",
+                mark,
+                format!("{:<18}", result.accept).cyan(),
+                status,
+                served,
+                result.bytes
+            );
+        }
+        println!();
+        if self.offered.is_empty() {
+            println!("   Offered: {}", "none".red());
+        } else {
+            println!("   Offered: {}", self.offered.join(", "));
+        }
+        if self.ignores_accept() {
+            println!("   {}", "The server ignores Accept and always serves the same type".yellow());
+        }
+    }
+}
+
+/// Requests `request` once per `Accept` value and summarizes the responses.
+///
+/// Any `Accept` header on the request is replaced.
+///
+/// # Errors
+///
+/// Returns an error if a request cannot be sent.
+pub async fn negotiate(client: &HttpClient, request: &HttpRequest, accepts: &[AcceptValue]) -> Result<NegotiationReport> {
+    let mut results = Vec::new();
+    let mut offered: Vec<String> = Vec::new();
+    for accept in accepts {
+        let mut request = request.clone();
+        request.headers.retain(|name, _| !name.eq_ignore_ascii_case("accept"));
+        let response = client.execute(&request.header("Accept", accept.value())).await?;
+
+        let content_type = response
+            .headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(essence);
+        let success = response.status.is_success();
+        if let Some(content_type) = content_type.as_ref().filter(|_| success) {
+            if !offered.contains(content_type) {
+                offered.push(content_type.clone());
+            }
+        }
+        results.push(NegotiationResult {
+            accept: accept.to_string(),
+            accept_header: accept.value().to_string(),
+            status: response.status.as_u16(),
+            satisfied: success && content_type.as_deref().is_some_and(|t| accept.accepts(t)),
+            content_type,
+            bytes: response.body.len(),
+        });
+    }
+    Ok(NegotiationReport {
+        url: request.url.clone(),
+        results,
+        offered,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_presets_and_media_types() {
+        let json: AcceptValue = "JSON".parse().unwrap();
+        assert_eq!(json.value(), "application/json");
+        assert_eq!(json.to_string(), "json");
+
+        let csv: AcceptValue = "text/csv".parse().unwrap();
+        assert_eq!(csv.value(), "text/csv");
+        assert!("yaml".parse::<AcceptValue>().is_err());
+        assert_eq!(AcceptValue::presets().len(), 4);
+    }
+
+    #[test]
+    fn test_accepts() {
+        let xml: AcceptValue = "xml".parse().unwrap();
+        assert!(xml.accepts("text/xml; charset=utf-8"));
+        assert!(!xml.accepts("application/json"));
+
+        let any: AcceptValue = "any".parse().unwrap();
+        assert!(any.accepts("image/png"));
+        let text: AcceptValue = "text/*".parse().unwrap();
+        assert!(text.accepts("Text/HTML"));
+        assert!(!text.accepts("application/json"));
+    }
+
+    #[test]
+    fn test_ignores_accept() {
+        let result = |accept: &str, status| NegotiationResult {
+            accept: accept.to_string(),
+            accept_header: accept.to_string(),
+            status,
+            content_type: Some("application/json".to_string()),
+            bytes: 2,
+            satisfied: accept == "json",
+        };
+        let mut report = NegotiationReport {
+            url: "http://example.com".to_string(),
+            results: vec![result("json", 200), result("xml", 200)],
+            offered: vec!["application/json".to_string()],
+        };
+        assert!(report.ignores_accept());
+        report.results[1].status = 406;
+        assert!(!report.ignores_accept());
+    }
+}
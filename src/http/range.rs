@@ -0,0 +1,324 @@
+//! Byte-range requests (`--range`, `--random-range`).
+//!
+//! A [`ByteRange`] becomes a `Range: bytes=...` header, and [`check`]
+//! verifies the server answered it properly: `206 Partial Content` with a
+//! `Content-Range` for the bytes asked for and a body of that length, or a
+//! `multipart/byteranges` body for several ranges. Performance tests can
+//! also read a window at a random offset of a large object per request,
+//! to benchmark how CDNs and object stores serve ranges.
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, CONTENT_ENCODING, CONTENT_RANGE, CONTENT_TYPE};
+use reqwest::StatusCode;
+use std::fmt;
+use std::str::FromStr;
+
+use super::multipart;
+use super::HttpResponse;
+
+/// One range of a `Range` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `FIRST-LAST`, both inclusive
+    Span(u64, u64),
+    /// `FIRST-`: from an offset to the end
+    From(u64),
+    /// `-N`: the last N bytes
+    Last(u64),
+}
+
+impl ByteRange {
+    /// Returns a range of `length` bytes at a random offset of an object
+    /// of `size` bytes, or the whole object if it is shorter.
+    pub fn random(length: u64, size: u64, rng: &mut impl Rng) -> Self {
+        let length = length.clamp(1, size.max(1));
+        let first = rng.gen_range(0..=size.saturating_sub(length));
+        Self::Span(first, first + length - 1)
+    }
+
+    /// Returns the first and last byte this range selects from an object
+    /// of `size` bytes, or `None` if it selects nothing.
+    pub fn resolve(&self, size: u64) -> Option<(u64, u64)> {
+        match *self {
+            _ if size == 0 => None,
+            Self::Span(first, last) => (first < size).then(|| (first, last.min(size - 1))),
+            Self::From(first) => (first < size).then(|| (first, size - 1)),
+            Self::Last(0) => None,
+            Self::Last(n) => Some((size.saturating_sub(n), size - 1)),
+        }
+    }
+
+    /// Returns true if a `Content-Range` of `first..=last` answers this
+    /// range. Without the object size only the known ends are compared.
+    fn answered_by(&self, first: u64, last: u64, size: Option<u64>) -> bool {
+        if let Some(size) = size {
+            return self.resolve(size) == Some((first, last));
+        }
+        match *self {
+            Self::Span(start, end) => first == start && last <= end,
+            Self::From(start) => first == start,
+            Self::Last(n) => last - first < n,
+        }
+    }
+}
+
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Span(first, last) => write!(f, "{}-{}", first, last),
+            Self::From(first) => write!(f, "{}-", first),
+            Self::Last(n) => write!(f, "-{}", n),
+        }
+    }
+}
+
+impl FromStr for ByteRange {
+    type Err = String;
+
+    /// Parses `FIRST-LAST`, `FIRST-` or `-N`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected a byte range like 0-1023, 1024- or -500, got '{}'", s);
+        let (first, last) = s.trim().split_once('-').ok_or_else(invalid)?;
+        let number = |n: &str| n.trim().parse::<u64>().map_err(|_| invalid());
+        match (first.trim().is_empty(), last.trim().is_empty()) {
+            (false, false) => {
+                let (first, last) = (number(first)?, number(last)?);
+                if first > last {
+                    return Err(format!("range '{}' ends before it starts", s));
+                }
+                Ok(Self::Span(first, last))
+            }
+            (false, true) => Ok(Self::From(number(first)?)),
+            (true, false) => match number(last)? {
+                0 => Err(format!("range '{}' selects no bytes", s)),
+                n => Ok(Self::Last(n)),
+            },
+            (true, true) => Err(invalid()),
+        }
+    }
+}
+
+/// Returns the `Range` header value for `ranges`, e.g. `bytes=0-1023,4096-`.
+pub fn header_value(ranges: &[ByteRange]) -> String {
+    let ranges: Vec<_> = ranges.iter().map(ToString::to_string).collect();
+    format!("bytes={}", ranges.join(","))
+}
+
+/// A parsed `Content-Range` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    /// First and last byte sent, `None` for an unsatisfied range (`bytes */SIZE`)
+    pub range: Option<(u64, u64)>,
+    /// Size of the whole object, `None` if the server didn't say (`/*`)
+    pub size: Option<u64>,
+}
+
+impl ContentRange {
+    /// Reads the `Content-Range` header of a response, if it has a valid one.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        headers.get(CONTENT_RANGE)?.to_str().ok()?.parse().ok()
+    }
+}
+
+impl FromStr for ContentRange {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (unit, rest) = s.trim().split_once(' ').ok_or(())?;
+        if !unit.eq_ignore_ascii_case("bytes") {
+            return Err(());
+        }
+        let (range, size) = rest.trim().split_once('/').ok_or(())?;
+        let size = match size.trim() {
+            "*" => None,
+            size => Some(size.parse().map_err(|_| ())?),
+        };
+        let range = match range.trim() {
+            "*" => None,
+            range => {
+                let (first, last) = range.split_once('-').ok_or(())?;
+                let (first, last): (u64, u64) = (first.parse().map_err(|_| ())?, last.parse().map_err(|_| ())?);
+                if first > last {
+                    return Err(());
+                }
+                Some((first, last))
+            }
+        };
+        Ok(Self { range, size })
+    }
+}
+
+/// Checks that a response answers a request for `ranges`.
+///
+/// Responses other than 200, 206 and 416 are left to the usual status
+/// handling. A single-part 206 to several ranges is accepted if it starts
+/// where one of them does, since servers may coalesce ranges.
+///
+/// # Arguments
+///
+/// * `ranges` - Ranges the request asked for
+/// * `response` - The response
+/// * `size` - Number of body bytes received
+///
+/// # Errors
+///
+/// Returns what is wrong with the response.
+pub fn check(ranges: &[ByteRange], response: &HttpResponse, size: u64) -> Result<(), String> {
+    let content_range = ContentRange::from_headers(&response.headers);
+    match response.status {
+        StatusCode::PARTIAL_CONTENT => {}
+        StatusCode::OK => return Err("the server ignored the Range header and sent the whole body (200)".to_string()),
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            return Err(match content_range.and_then(|c| c.size) {
+                Some(total) => format!("range not satisfiable for a {}-byte object (416)", total),
+                None => "range not satisfiable (416)".to_string(),
+            })
+        }
+        _ => return Ok(()),
+    }
+
+    let content_type = response.headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    if multipart::is_multipart(content_type) {
+        return match ranges.len() {
+            1 => Err("a single range was answered with a multipart body".to_string()),
+            _ => Ok(()),
+        };
+    }
+    let Some(ContentRange { range: Some((first, last)), size: total }) = content_range else {
+        return Err(match response.headers.get(CONTENT_RANGE) {
+            Some(value) => format!("206 with an invalid Content-Range '{}'", value.to_str().unwrap_or_default()),
+            None => "206 without a Content-Range".to_string(),
+        });
+    };
+    let answered = match ranges {
+        [range] => range.answered_by(first, last, total),
+        ranges => ranges
+            .iter()
+            .filter_map(|range| range.resolve(total.unwrap_or(u64::MAX)))
+            .any(|(start, _)| start == first),
+    };
+    if !answered {
+        return Err(format!("Content-Range bytes {}-{} doesn't match the requested {}", first, last, header_value(ranges)));
+    }
+    // A compressed body's length can't be compared with the range
+    let expected = last - first + 1;
+    if !response.is_head && !response.headers.contains_key(CONTENT_ENCODING) && size != expected {
+        return Err(format!("Content-Range promises {} bytes but {} arrived", expected, size));
+    }
+    Ok(())
+}
+
+/// Parses a byte count with an optional `K`, `M` or `G` suffix (powers of 1024).
+///
+/// Used as a clap value parser for `--random-range` and `--raw-max-bytes`.
+pub fn parse_length(s: &str) -> Result<u64, String> {
+    let trimmed = s.trim();
+    let lower = trimmed.to_ascii_lowercase();
+    let number = lower.trim_end_matches("ib").trim_end_matches('b');
+    let (digits, scale) = match number.chars().last() {
+        Some('k') => (&number[..number.len() - 1], 1 << 10),
+        Some('m') => (&number[..number.len() - 1], 1 << 20),
+        Some('g') => (&number[..number.len() - 1], 1 << 30),
+        _ => (number, 1),
+    };
+    match digits.trim().parse::<u64>() {
+        Ok(n) if n > 0 => n.checked_mul(scale).ok_or_else(|| format!("length '{}' is too large", s)),
+        _ => Err(format!("expected a positive length like 65536 or 64K, got '{}'", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use reqwest::header::HeaderValue;
+    use std::time::Duration;
+
+    fn response(status: u16, headers: &[(&'static str, &str)]) -> HttpResponse {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        HttpResponse::new(StatusCode::from_u16(status).unwrap(), map, String::new(), Duration::ZERO)
+    }
+
+    #[test]
+    fn test_parse_ranges() {
+        assert_eq!("0-1023".parse(), Ok(ByteRange::Span(0, 1023)));
+        assert_eq!("1024-".parse(), Ok(ByteRange::From(1024)));
+        assert_eq!("-500".parse(), Ok(ByteRange::Last(500)));
+        assert!("10-5".parse::<ByteRange>().is_err());
+        assert!("-0".parse::<ByteRange>().is_err());
+        assert!("-".parse::<ByteRange>().is_err());
+        assert!("abc".parse::<ByteRange>().is_err());
+        let ranges = [ByteRange::Span(0, 1023), ByteRange::From(4096), ByteRange::Last(10)];
+        assert_eq!(header_value(&ranges), "bytes=0-1023,4096-,-10");
+    }
+
+    #[test]
+    fn test_resolve_and_random() {
+        assert_eq!(ByteRange::Span(0, 1023).resolve(100), Some((0, 99)));
+        assert_eq!(ByteRange::Span(200, 300).resolve(100), None);
+        assert_eq!(ByteRange::From(90).resolve(100), Some((90, 99)));
+        assert_eq!(ByteRange::Last(10).resolve(100), Some((90, 99)));
+        assert_eq!(ByteRange::Last(500).resolve(100), Some((0, 99)));
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..100 {
+            let ByteRange::Span(first, last) = ByteRange::random(64, 1000, &mut rng) else { panic!() };
+            assert_eq!(last - first + 1, 64);
+            assert!(last < 1000);
+        }
+        assert_eq!(ByteRange::random(64, 10, &mut rng), ByteRange::Span(0, 9));
+    }
+
+    #[test]
+    fn test_content_range() {
+        let parsed: ContentRange = "bytes 0-1023/4096".parse().unwrap();
+        assert_eq!(parsed, ContentRange { range: Some((0, 1023)), size: Some(4096) });
+        let parsed: ContentRange = "bytes */4096".parse().unwrap();
+        assert_eq!(parsed, ContentRange { range: None, size: Some(4096) });
+        let parsed: ContentRange = "bytes 5-9/*".parse().unwrap();
+        assert_eq!(parsed.size, None);
+        assert!("items 0-1/2".parse::<ContentRange>().is_err());
+        assert!("bytes 9-5/10".parse::<ContentRange>().is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let first_kb = [ByteRange::Span(0, 1023)];
+        let ok = response(206, &[("content-range", "bytes 0-1023/4096")]);
+        assert_eq!(check(&first_kb, &ok, 1024), Ok(()));
+        assert!(check(&first_kb, &ok, 10).unwrap_err().contains("1024 bytes but 10 arrived"));
+        assert!(check(&first_kb, &response(200, &[]), 4096).unwrap_err().contains("ignored"));
+        assert!(check(&first_kb, &response(206, &[]), 1024).unwrap_err().contains("without a Content-Range"));
+        let wrong = response(206, &[("content-range", "bytes 1024-2047/4096")]);
+        assert!(check(&first_kb, &wrong, 1024).unwrap_err().contains("doesn't match"));
+        let short = response(416, &[("content-range", "bytes */100")]);
+        assert!(check(&first_kb, &short, 0).unwrap_err().contains("100-byte object"));
+        assert_eq!(check(&first_kb, &response(404, &[]), 0), Ok(()));
+
+        // Clamped to the object's end
+        let tail = response(206, &[("content-range", "bytes 0-99/100")]);
+        assert_eq!(check(&first_kb, &tail, 100), Ok(()));
+
+        // Several ranges: multipart, or coalesced into one
+        let two = [ByteRange::Span(0, 9), ByteRange::Span(10, 19)];
+        let parts = response(206, &[("content-type", "multipart/byteranges; boundary=x")]);
+        assert_eq!(check(&two, &parts, 300), Ok(()));
+        assert!(check(&first_kb, &parts, 300).is_err());
+        let coalesced = response(206, &[("content-range", "bytes 0-19/100")]);
+        assert_eq!(check(&two, &coalesced, 20), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_length() {
+        assert_eq!(parse_length("65536"), Ok(65536));
+        assert_eq!(parse_length("64K"), Ok(65536));
+        assert_eq!(parse_length("1MiB"), Ok(1 << 20));
+        assert_eq!(parse_length("2g"), Ok(2 << 30));
+        assert!(parse_length("0").is_err());
+        assert!(parse_length("lots").is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Raw request mode (`--raw-request`).
+//!
+//! Sends the bytes of a file unchanged over a plain TCP or TLS connection,
+//! bypassing the HTTP client, and returns whatever the server sends back.
+//! This is for testing how proxies and servers handle requests the client
+//! would never produce: conflicting `Content-Length` and
+//! `Transfer-Encoding`, bare `\n` line endings, pipelined requests.
+
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use url::Url;
+
+use crate::error::{Result, RurlError};
+use super::resolve::ResolveEntry;
+
+/// Time without new data after which a raw response is considered complete.
+///
+/// Servers often keep the connection open after answering, so the end of
+/// the response can't be told apart from a pause any other way.
+pub const RAW_IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Default cap on the bytes kept from a raw response (16 MiB).
+///
+/// A server that streams without end would otherwise grow the buffer until
+/// the timeout.
+pub const RAW_MAX_BYTES: u64 = 16 << 20;
+
+/// A byte stream to a server, plain or TLS.
+pub(crate) trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+/// Opens a TCP connection to the URL's host, with TLS for `https`.
+///
+/// A matching `--resolve` entry overrides the address connected to; the
+/// TLS server name stays the URL's host.
+pub(crate) async fn connect(url: &Url, resolve: &[ResolveEntry]) -> std::io::Result<Box<dyn Connection>> {
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addr_host = host.trim_start_matches('[').trim_end_matches(']');
+    let tcp = match resolve.iter().find(|e| e.host.eq_ignore_ascii_case(addr_host) && e.port == port) {
+        Some(entry) => TcpStream::connect(entry.socket_addr()).await?,
+        None => TcpStream::connect((addr_host, port)).await?,
+    };
+
+    if url.scheme() == "https" {
+        let connector = native_tls::TlsConnector::new().map_err(std::io::Error::other)?;
+        let connector = tokio_native_tls::TlsConnector::from(connector);
+        let tls = connector.connect(addr_host, tcp).await.map_err(std::io::Error::other)?;
+        Ok(Box::new(tls))
+    } else {
+        Ok(Box::new(tcp))
+    }
+}
+
+/// What came back from a raw request.
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    /// Every byte received, unparsed
+    pub bytes: Vec<u8>,
+    /// Time from sending the first byte to the end of the response
+    pub duration: Duration,
+    /// Whether the server closed the connection, rather than going idle
+    pub closed: bool,
+    /// Whether reading stopped at the byte limit, dropping the rest
+    pub truncated: bool,
+}
+
+/// Sends `payload` as-is to the server of `url` and reads the response.
+///
+/// Reading stops when the server closes the connection, after
+/// [`RAW_IDLE_TIMEOUT`] without data once something was received, when
+/// `timeout` elapses, or once `max_bytes` have arrived.
+///
+/// # Errors
+///
+/// Returns an error if the URL is invalid, the connection can't be opened,
+/// or sending fails.
+pub async fn send_raw(
+    url: &str,
+    payload: &[u8],
+    resolve: &[ResolveEntry],
+    timeout: Duration,
+    max_bytes: u64,
+) -> Result<RawResponse> {
+    let url = Url::parse(url).map_err(|e| RurlError::InvalidUrl(format!("{}: {}", url, e)))?;
+    let failed = |e: std::io::Error| RurlError::RawRequestError(format!("{}: {}", url, e));
+
+    let mut stream = tokio::time::timeout(timeout, connect(&url, resolve))
+        .await
+        .map_err(|_| RurlError::RawRequestError(format!("{}: connect timed out", url)))?
+        .map_err(failed)?;
+
+    let start = Instant::now();
+    stream.write_all(payload).await.map_err(failed)?;
+    stream.flush().await.map_err(failed)?;
+
+    let mut bytes = Vec::new();
+    let mut buf = [0u8; 8192];
+    let mut closed = false;
+    let mut truncated = false;
+    loop {
+        let remaining = timeout.saturating_sub(start.elapsed());
+        let wait = if bytes.is_empty() { remaining } else { remaining.min(RAW_IDLE_TIMEOUT) };
+        match tokio::time::timeout(wait, stream.read(&mut buf)).await {
+            Ok(Ok(0)) => {
+                closed = true;
+                break;
+            }
+            Ok(Ok(n)) => {
+                let room = max_bytes.saturating_sub(bytes.len() as u64);
+                if n as u64 > room {
+                    bytes.extend_from_slice(&buf[..room as usize]);
+                    truncated = true;
+                    break;
+                }
+                bytes.extend_from_slice(&buf[..n]);
+            }
+            // A reset after the response still leaves what was received
+            Ok(Err(_)) if !bytes.is_empty() => {
+                closed = true;
+                break;
+            }
+            Ok(Err(e)) => return Err(failed(e)),
+            Err(_) => break,
+        }
+    }
+
+    Ok(RawResponse {
+        bytes,
+        duration: start.elapsed(),
+        closed,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_raw_passes_bytes_through() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut received = vec![0u8; 64];
+            let n = socket.read(&mut received).await.unwrap();
+            socket.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await.unwrap();
+            received.truncate(n);
+            received
+        });
+
+        let payload = b"GET / HTTP/1.1\nHost: x\nTransfer-Encoding: chunked\n\n";
+        let response = send_raw(&format!("http://{}", addr), payload, &[], Duration::from_secs(5), RAW_MAX_BYTES)
+            .await
+            .unwrap();
+        assert_eq!(server.await.unwrap(), payload);
+        assert_eq!(response.bytes, b"HTTP/1.1 400 Bad Request\r\n\r\n");
+        assert!(response.closed);
+        assert!(!response.truncated);
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_stops_at_max_bytes() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let chunk = [b'x'; 4096];
+            while socket.write_all(&chunk).await.is_ok() {}
+        });
+
+        let response = send_raw(&format!("http://{}", addr), b"GET /\r\n\r\n", &[], Duration::from_secs(5), 10_000)
+            .await
+            .unwrap();
+        assert_eq!(response.bytes.len(), 10_000);
+        assert!(response.truncated);
+        assert!(!response.closed);
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_uses_resolve_override() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(b"ok").await.unwrap();
+        });
+
+        let resolve: ResolveEntry = format!("backend.test:{}:127.0.0.1", port).parse().unwrap();
+        let url = format!("http://backend.test:{}", port);
+        let response = send_raw(&url, b"PING\r\n", &[resolve], Duration::from_secs(5), RAW_MAX_BYTES)
+            .await
+            .unwrap();
+        assert_eq!(response.bytes, b"ok");
+    }
+}
@@ -0,0 +1,177 @@
+//! Redirect safety checks.
+//!
+//! When following redirects, flags hops that downgrade from `https` to
+//! `http` and hops to another origin that would carry credential headers
+//! along. reqwest already drops `Authorization` and `Cookie` when the host
+//! or port changes, but custom headers such as `X-Api-Key` are forwarded
+//! as-is.
+
+use reqwest::redirect::{Attempt, Policy};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Headers reqwest removes by itself when a redirect changes host or port.
+const STRIPPED_BY_REQWEST: [&str; 4] = ["authorization", "cookie", "proxy-authorization", "www-authenticate"];
+
+/// A potentially unsafe redirect hop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedirectWarning {
+    /// Redirect from `https` to plain `http`
+    Downgrade {
+        /// URL that sent the redirect
+        from: String,
+        /// Insecure redirect target
+        to: String,
+    },
+    /// Redirect to another origin that forwards credential headers
+    CredentialsCrossOrigin {
+        /// URL that sent the redirect
+        from: String,
+        /// Target on a different origin
+        to: String,
+        /// Credential headers that will be sent to the target
+        headers: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for RedirectWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RedirectWarning::Downgrade { from, to } => {
+                write!(f, "redirect downgrades from HTTPS to HTTP: {} -> {}", from, to)
+            }
+            RedirectWarning::CredentialsCrossOrigin { from, to, headers } => write!(
+                f,
+                "redirect to another origin forwards {}: {} -> {}",
+                headers.join(", "),
+                from,
+                to
+            ),
+        }
+    }
+}
+
+/// Returns true if a header name looks like it carries credentials.
+pub fn is_credential_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    STRIPPED_BY_REQWEST.contains(&name.as_str())
+        || ["token", "secret", "api-key", "apikey", "auth", "session", "password"]
+            .iter()
+            .any(|marker| name.contains(marker))
+}
+
+/// Checks a single redirect hop.
+///
+/// # Arguments
+///
+/// * `previous` - URL that returned the redirect
+/// * `next` - Redirect target
+/// * `headers` - Names of the headers sent with the request
+pub fn check_redirect(previous: &Url, next: &Url, headers: &[String]) -> Vec<RedirectWarning> {
+    let mut warnings = Vec::new();
+
+    if previous.scheme() == "https" && next.scheme() == "http" {
+        warnings.push(RedirectWarning::Downgrade {
+            from: previous.to_string(),
+            to: next.to_string(),
+        });
+    }
+
+    let host_changed = previous.host_str() != next.host_str()
+        || previous.port_or_known_default() != next.port_or_known_default();
+    if previous.origin() != next.origin() {
+        let forwarded: Vec<String> = headers
+            .iter()
+            .filter(|name| is_credential_header(name))
+            .filter(|name| !(host_changed && STRIPPED_BY_REQWEST.contains(&name.to_ascii_lowercase().as_str())))
+            .cloned()
+            .collect();
+        if !forwarded.is_empty() {
+            warnings.push(RedirectWarning::CredentialsCrossOrigin {
+                from: previous.to_string(),
+                to: next.to_string(),
+                headers: forwarded,
+            });
+        }
+    }
+
+    warnings
+}
+
+/// Builds a redirect policy that follows up to `max_redirects` hops (any
+/// number if `None`) and records unsafe ones in `warnings`.
+///
+/// With `strict`, the first unsafe hop stops the request with an error
+/// instead.
+pub(crate) fn policy(
+    max_redirects: Option<usize>,
+    strict: bool,
+    headers: Vec<String>,
+    warnings: Option<Arc<Mutex<Vec<RedirectWarning>>>>,
+) -> Policy {
+    Policy::custom(move |attempt: Attempt| {
+        if let Some(max) = max_redirects.filter(|max| attempt.previous().len() > *max) {
+            return attempt.error(format!("too many redirects (limit {}, see --max-redirs)", max));
+        }
+
+        let Some(previous) = attempt.previous().last() else {
+            return attempt.follow();
+        };
+        let found = check_redirect(previous, attempt.url(), &headers);
+        if found.is_empty() {
+            return attempt.follow();
+        }
+        if strict {
+            let message = format!("refusing unsafe redirect (--strict-redirects): {}", found[0]);
+            return attempt.error(message);
+        }
+        if let Some(warnings) = &warnings {
+            warnings.lock().unwrap_or_else(|e| e.into_inner()).extend(found);
+        }
+        attempt.follow()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_downgrade() {
+        let warnings = check_redirect(&url("https://a.example/x"), &url("http://a.example/x"), &[]);
+        assert!(matches!(warnings.as_slice(), [RedirectWarning::Downgrade { .. }]));
+
+        assert!(check_redirect(&url("http://a.example/"), &url("https://a.example/"), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_credentials_cross_origin() {
+        let headers = vec!["Authorization".to_string(), "X-Api-Key".to_string(), "Accept".to_string()];
+
+        // Authorization is stripped by reqwest on host change; X-Api-Key is not
+        let warnings = check_redirect(&url("https://a.example/"), &url("https://b.example/"), &headers);
+        assert_eq!(
+            warnings,
+            vec![RedirectWarning::CredentialsCrossOrigin {
+                from: "https://a.example/".to_string(),
+                to: "https://b.example/".to_string(),
+                headers: vec!["X-Api-Key".to_string()],
+            }]
+        );
+
+        // Same origin is fine
+        assert!(check_redirect(&url("https://a.example/x"), &url("https://a.example/y"), &headers).is_empty());
+    }
+
+    #[test]
+    fn test_is_credential_header() {
+        assert!(is_credential_header("Authorization"));
+        assert!(is_credential_header("X-Auth-Token"));
+        assert!(is_credential_header("x-api-key"));
+        assert!(!is_credential_header("Accept"));
+    }
+}
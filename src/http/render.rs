@@ -0,0 +1,585 @@
+//! Content-type driven response body rendering.
+//!
+//! A [`RendererRegistry`] maps a response's `Content-Type` to a
+//! [`BodyRenderer`] that turns the body into something readable: pretty
+//! JSON, indented XML, plain text from HTML, aligned CSV tables, a
+//! summary for images, or each part of a multipart body on its own.
+//! Custom renderers can be registered on top of the built-in ones.
+
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, CONTENT_TYPE};
+
+use super::multipart;
+
+/// Turns a response body into display text.
+pub trait BodyRenderer: Send + Sync {
+    /// Name used to select the renderer explicitly (e.g. `--render xml`).
+    fn name(&self) -> &'static str;
+
+    /// Returns true if this renderer handles the given MIME type.
+    ///
+    /// `mime` is lowercase and has no parameters (`text/html`, not
+    /// `text/html; charset=utf-8`).
+    fn handles(&self, mime: &str) -> bool;
+
+    /// Renders the body for display.
+    fn render(&self, body: &str, headers: &HeaderMap) -> String;
+}
+
+/// Names of the built-in renderers, in registration order.
+pub const BUILTIN_RENDERERS: [&str; 7] = ["json", "xml", "html", "csv", "image", "multipart", "raw"];
+
+/// Set of renderers looked up by name or content type.
+///
+/// Renderers registered later take precedence, so a custom renderer can
+/// override a built-in one for the same content type.
+pub struct RendererRegistry {
+    renderers: Vec<Box<dyn BodyRenderer>>,
+}
+
+impl Default for RendererRegistry {
+    /// Creates a registry with the built-in renderers.
+    fn default() -> Self {
+        Self::new()
+            .register(JsonRenderer)
+            .register(XmlRenderer)
+            .register(HtmlRenderer)
+            .register(CsvRenderer)
+            .register(ImageRenderer)
+            .register(MultipartRenderer)
+            .register(RawRenderer)
+    }
+}
+
+impl RendererRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self { renderers: Vec::new() }
+    }
+
+    /// Adds a renderer.
+    pub fn register(mut self, renderer: impl BodyRenderer + 'static) -> Self {
+        self.renderers.push(Box::new(renderer));
+        self
+    }
+
+    /// Returns the renderer with the given name.
+    pub fn get(&self, name: &str) -> Option<&dyn BodyRenderer> {
+        self.renderers
+            .iter()
+            .rev()
+            .find(|r| r.name().eq_ignore_ascii_case(name))
+            .map(|r| r.as_ref())
+    }
+
+    /// Returns the renderer for a `Content-Type` header value, if any handles it.
+    pub fn for_content_type(&self, content_type: &str) -> Option<&dyn BodyRenderer> {
+        let mime = mime_essence(content_type);
+        self.renderers
+            .iter()
+            .rev()
+            .find(|r| r.handles(&mime))
+            .map(|r| r.as_ref())
+    }
+
+    /// Renders a body with the renderer matching its headers.
+    ///
+    /// Falls back to the JSON renderer, which prints anything that isn't
+    /// JSON unchanged.
+    pub fn render(&self, body: &str, headers: &HeaderMap) -> String {
+        let renderer = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|ct| self.for_content_type(ct));
+        match renderer {
+            Some(renderer) => renderer.render(body, headers),
+            None => JsonRenderer.render(body, headers),
+        }
+    }
+}
+
+/// Strips parameters from a content type and lowercases it.
+fn mime_essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Pretty-prints JSON; anything that doesn't parse is shown unchanged.
+pub struct JsonRenderer;
+
+impl BodyRenderer for JsonRenderer {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == "application/json" || mime.ends_with("+json")
+    }
+
+    fn render(&self, body: &str, _headers: &HeaderMap) -> String {
+        serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|json| serde_json::to_string_pretty(&json).ok())
+            .unwrap_or_else(|| body.to_string())
+    }
+}
+
+/// Indents XML, one element per line.
+pub struct XmlRenderer;
+
+impl BodyRenderer for XmlRenderer {
+    fn name(&self) -> &'static str {
+        "xml"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == "application/xml" || mime == "text/xml" || mime.ends_with("+xml")
+    }
+
+    fn render(&self, body: &str, _headers: &HeaderMap) -> String {
+        let tokens = xml_tokens(body);
+        let mut lines = Vec::new();
+        let mut depth = 0usize;
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = tokens[i];
+            let indent = "  ".repeat(depth);
+            if token.starts_with("</") {
+                depth = depth.saturating_sub(1);
+                lines.push(format!("{}{}", "  ".repeat(depth), token));
+            } else if is_open_tag(token) {
+                // Keep `<name>text</name>` on one line
+                match (tokens.get(i + 1), tokens.get(i + 2)) {
+                    (Some(text), Some(close)) if !text.starts_with('<') && close.starts_with("</") => {
+                        lines.push(format!("{}{}{}{}", indent, token, text, close));
+                        i += 3;
+                        continue;
+                    }
+                    (Some(close), _) if close.starts_with("</") => {
+                        lines.push(format!("{}{}{}", indent, token, close));
+                        i += 2;
+                        continue;
+                    }
+                    _ => {
+                        lines.push(format!("{}{}", indent, token));
+                        depth += 1;
+                    }
+                }
+            } else {
+                lines.push(format!("{}{}", indent, token));
+            }
+            i += 1;
+        }
+        lines.join("\n")
+    }
+}
+
+/// Splits XML into tags and trimmed, non-empty text runs.
+fn xml_tokens(body: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = body;
+    while !rest.is_empty() {
+        let (token, next) = if rest.starts_with('<') {
+            let end = rest.find('>').map_or(rest.len(), |i| i + 1);
+            rest.split_at(end)
+        } else {
+            rest.split_at(rest.find('<').unwrap_or(rest.len()))
+        };
+        let token = token.trim();
+        if !token.is_empty() {
+            tokens.push(token);
+        }
+        rest = next;
+    }
+    tokens
+}
+
+/// Returns true for a start tag that opens an element with content.
+fn is_open_tag(token: &str) -> bool {
+    token.starts_with('<')
+        && !token.starts_with("</")
+        && !token.starts_with("<?")
+        && !token.starts_with("<!")
+        && !token.ends_with("/>")
+}
+
+/// Extracts readable text from HTML.
+pub struct HtmlRenderer;
+
+/// Tags whose content is never shown.
+const HTML_HIDDEN_TAGS: [&str; 4] = ["script", "style", "head", "noscript"];
+
+/// Tags that start a new line of text.
+const HTML_BLOCK_TAGS: [&str; 20] = [
+    "p", "div", "br", "li", "ul", "ol", "tr", "table", "h1", "h2", "h3", "h4", "h5", "h6",
+    "section", "article", "header", "footer", "pre", "blockquote",
+];
+
+impl BodyRenderer for HtmlRenderer {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == "text/html" || mime == "application/xhtml+xml"
+    }
+
+    fn render(&self, body: &str, _headers: &HeaderMap) -> String {
+        let mut text = String::new();
+        let mut hidden: Option<String> = None;
+        let mut rest = body;
+        while !rest.is_empty() {
+            if let Some(tag_body) = rest.strip_prefix('<') {
+                let end = tag_body.find('>').unwrap_or(tag_body.len());
+                let tag = &tag_body[..end];
+                rest = tag_body.get(end + 1..).unwrap_or_default();
+
+                let closing = tag.starts_with('/');
+                let name = tag
+                    .trim_start_matches('/')
+                    .split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+
+                match &hidden {
+                    Some(open) if closing && *open == name => hidden = None,
+                    Some(_) => {}
+                    None if !closing && HTML_HIDDEN_TAGS.contains(&name.as_str()) => hidden = Some(name),
+                    None if HTML_BLOCK_TAGS.contains(&name.as_str()) => text.push('\n'),
+                    None => {}
+                }
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                if hidden.is_none() {
+                    text.push_str(&decode_entities(&rest[..end]));
+                }
+                rest = &rest[end..];
+            }
+        }
+
+        text.lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Decodes the common named and numeric HTML entities.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            }?;
+            Some((c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Shows CSV as an aligned table.
+pub struct CsvRenderer;
+
+impl BodyRenderer for CsvRenderer {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == "text/csv" || mime == "application/csv"
+    }
+
+    fn render(&self, body: &str, _headers: &HeaderMap) -> String {
+        let rows = parse_csv(body);
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|col| {
+                rows.iter()
+                    .filter_map(|row| row.get(col))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let format_row = |row: &Vec<String>| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut lines = Vec::with_capacity(rows.len() + 1);
+        if let Some((header, data)) = rows.split_first() {
+            lines.push(format_row(header));
+            lines.push(
+                widths
+                    .iter()
+                    .map(|&w| "-".repeat(w))
+                    .collect::<Vec<_>>()
+                    .join("  "),
+            );
+            lines.extend(data.iter().map(format_row));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Parses CSV with RFC 4180 quoting; blank lines are skipped.
+fn parse_csv(body: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = body.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut cell)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut cell));
+                if row.iter().any(|c| !c.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            _ => cell.push(c),
+        }
+    }
+    row.push(cell);
+    if row.iter().any(|c| !c.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+/// Summarizes image responses instead of printing binary data.
+pub struct ImageRenderer;
+
+impl BodyRenderer for ImageRenderer {
+    fn name(&self) -> &'static str {
+        "image"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime.starts_with("image/") && mime != "image/svg+xml"
+    }
+
+    fn render(&self, body: &str, headers: &HeaderMap) -> String {
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(mime_essence)
+            .unwrap_or_else(|| "image".to_string());
+        let size = headers
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or(body.len() as u64);
+        format!("[{}, {} bytes; use -o to save it]", content_type, size)
+    }
+}
+
+/// Shows each part of a multipart body with its headers, rendered by its
+/// own content type.
+///
+/// Parts that aren't text are summarized. A body that can't be split is
+/// shown unchanged.
+pub struct MultipartRenderer;
+
+impl BodyRenderer for MultipartRenderer {
+    fn name(&self) -> &'static str {
+        "multipart"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        multipart::is_multipart(mime)
+    }
+
+    fn render(&self, body: &str, headers: &HeaderMap) -> String {
+        let content_type = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or_default();
+        let Ok(parts) = multipart::parse(body.as_bytes(), content_type) else {
+            return body.to_string();
+        };
+        let registry = RendererRegistry::default();
+        let mut sections = Vec::with_capacity(parts.len());
+        for (index, part) in parts.iter().enumerate() {
+            let mut section = format!("--- Part {} of {} ---\n", index + 1, parts.len());
+            for (name, value) in &part.headers {
+                section.push_str(&format!("{}: {}\n", name, value.to_str().unwrap_or_default()));
+            }
+            section.push('\n');
+            match std::str::from_utf8(&part.body) {
+                // NULs or replacement characters mean the part is binary
+                Ok(text) if !text.contains(['\0', char::REPLACEMENT_CHARACTER]) => section.push_str(&registry.render(text, &part.headers)),
+                // No size: the decoded text may be longer than what was received
+                _ => section.push_str(&format!(
+                    "[{} data; use --save-parts to save it]",
+                    part.content_type().map_or("binary".to_string(), mime_essence)
+                )),
+            }
+            sections.push(section);
+        }
+        sections.join("\n\n")
+    }
+}
+
+/// Shows the body unchanged.
+pub struct RawRenderer;
+
+impl BodyRenderer for RawRenderer {
+    fn name(&self) -> &'static str {
+        "raw"
+    }
+
+    fn handles(&self, _mime: &str) -> bool {
+        false
+    }
+
+    fn render(&self, body: &str, _headers: &HeaderMap) -> String {
+        body.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(content_type: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, content_type.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_lookup() {
+        let registry = RendererRegistry::default();
+        let name = |ct| registry.for_content_type(ct).map(|r| r.name());
+        assert_eq!(name("application/json; charset=utf-8"), Some("json"));
+        assert_eq!(name("application/problem+json"), Some("json"));
+        assert_eq!(name("text/XML"), Some("xml"));
+        assert_eq!(name("text/html"), Some("html"));
+        assert_eq!(name("text/csv"), Some("csv"));
+        assert_eq!(name("image/png"), Some("image"));
+        assert_eq!(name("multipart/byteranges; boundary=x"), Some("multipart"));
+        assert_eq!(name("text/plain"), None);
+        assert_eq!(registry.get("RAW").map(|r| r.name()), Some("raw"));
+        assert!(registry.get("yaml").is_none());
+    }
+
+    #[test]
+    fn test_custom_renderer_overrides_builtin() {
+        struct Shout;
+        impl BodyRenderer for Shout {
+            fn name(&self) -> &'static str {
+                "shout"
+            }
+            fn handles(&self, mime: &str) -> bool {
+                mime == "text/html"
+            }
+            fn render(&self, body: &str, _headers: &HeaderMap) -> String {
+                body.to_uppercase()
+            }
+        }
+
+        let registry = RendererRegistry::default().register(Shout);
+        assert_eq!(registry.render("<b>hi</b>", &headers("text/html")), "<B>HI</B>");
+    }
+
+    #[test]
+    fn test_unknown_type_falls_back_to_json_sniffing() {
+        let registry = RendererRegistry::default();
+        assert_eq!(registry.render(r#"{"a":1}"#, &HeaderMap::new()), "{\n  \"a\": 1\n}");
+        assert_eq!(registry.render("plain", &headers("text/plain")), "plain");
+    }
+
+    #[test]
+    fn test_xml() {
+        let body = r#"<?xml version="1.0"?><users><user id="1"><name>Ann</name><tags/></user></users>"#;
+        assert_eq!(
+            XmlRenderer.render(body, &HeaderMap::new()),
+            "<?xml version=\"1.0\"?>\n<users>\n  <user id=\"1\">\n    <name>Ann</name>\n    <tags/>\n  </user>\n</users>"
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let body = "<html><head><title>T</title><style>p{}</style></head>\
+                    <body><h1>Hello &amp; welcome</h1><p>Some   <b>bold</b> text&#33;</p>\
+                    <script>alert(1)</script></body></html>";
+        assert_eq!(HtmlRenderer.render(body, &HeaderMap::new()), "Hello & welcome\nSome bold text!");
+    }
+
+    #[test]
+    fn test_csv() {
+        let body = "id,name\r\n1,\"Smith, Jo\"\r\n22,\"say \"\"hi\"\"\"\r\n";
+        assert_eq!(
+            CsvRenderer.render(body, &HeaderMap::new()),
+            "id  name\n--  ---------\n1   Smith, Jo\n22  say \"hi\""
+        );
+    }
+
+    #[test]
+    fn test_multipart() {
+        let body = "--b\r\nContent-Type: application/json\r\n\r\n{\"a\":1}\r\n\
+                    --b\r\nContent-Type: application/octet-stream\r\n\r\n\0\x01\r\n--b--\r\n";
+        assert_eq!(
+            MultipartRenderer.render(body, &headers("multipart/mixed; boundary=b")),
+            "--- Part 1 of 2 ---\ncontent-type: application/json\n\n{\n  \"a\": 1\n}\n\n\
+             --- Part 2 of 2 ---\ncontent-type: application/octet-stream\n\n\
+             [application/octet-stream data; use --save-parts to save it]"
+        );
+        assert_eq!(MultipartRenderer.render("x", &headers("multipart/mixed")), "x");
+    }
+
+    #[test]
+    fn test_image() {
+        let mut headers = headers("image/png");
+        headers.insert(CONTENT_LENGTH, "2048".parse().unwrap());
+        assert_eq!(
+            ImageRenderer.render("", &headers),
+            "[image/png, 2048 bytes; use -o to save it]"
+        );
+    }
+}
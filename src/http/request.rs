@@ -3,12 +3,40 @@
 //! Provides a builder pattern for constructing HTTP requests with
 //! method, headers, body, timeout, and redirect settings.
 
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_ENCODING};
 use reqwest::Method;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
+use url::{ParseError, Url};
+
 use crate::error::{Result, RurlError};
+use crate::template::{self, Vars};
+use super::auth::AuthChain;
+use super::cache::HttpCache;
+use super::conditional::CONDITIONAL_HEADERS;
+use super::dns::DnsResolver;
+use super::encoding::{self, ACCEPT_ENCODING};
+use super::graphql::GraphQlQuery;
+use super::resolve::ResolveEntry;
+use super::socket::SocketOptions;
+use super::tls::ClientIdentity;
+use super::version::HttpVersion;
+use super::zone::{self, ZoneRoute};
+
+/// Methods defined by RFC 9110, plus `PATCH` from RFC 5789.
+pub const STANDARD_METHODS: [&str; 9] =
+    ["GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH"];
+
+/// `User-Agent` the command-line tool sends unless told otherwise.
+pub const DEFAULT_USER_AGENT: &str = concat!("hurley/", env!("CARGO_PKG_VERSION"));
+
+/// Redirects followed before giving up, unless set with
+/// [`HttpRequest::max_redirects`].
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
 
 /// HTTP request configuration.
 ///
@@ -31,10 +59,41 @@ pub struct HttpRequest {
     pub headers: HashMap<String, String>,
     /// Request body (optional)
     pub body: Option<String>,
-    /// Request timeout
+    /// Limit on the whole request, from connecting to reading the last
+    /// byte of the body
     pub timeout: Duration,
+    /// Limit on opening a connection (DNS, TCP and TLS)
+    pub connect_timeout: Option<Duration>,
+    /// Longest the response body may go without receiving data
+    pub read_timeout: Option<Duration>,
     /// Whether to follow HTTP redirects
     pub follow_redirects: bool,
+    /// Most redirects followed, `None` for no limit
+    pub max_redirects: Option<usize>,
+    /// How long idle pooled connections are kept before being closed
+    pub pool_idle_timeout: Option<Duration>,
+    /// Whether to retry once when a reused connection turns out to be dead
+    pub retry_on_connection_reset: bool,
+    /// DNS overrides applied when connecting
+    pub resolve: Vec<ResolveEntry>,
+    /// Name servers used instead of the system resolver
+    pub dns: Option<DnsResolver>,
+    /// Local address and TCP options of the connections opened
+    pub socket: SocketOptions,
+    /// Whether to refuse unsafe redirects instead of warning about them
+    pub strict_redirects: bool,
+    /// Client certificate presented for mutual TLS
+    pub client_identity: Option<Arc<ClientIdentity>>,
+    /// HTTP version to use instead of negotiating one
+    pub http_version: Option<HttpVersion>,
+    /// Providers of the credentials added to every send
+    pub auth: Option<Arc<AuthChain>>,
+    /// Whether to ask for a compressed response and decompress its body
+    pub compressed: bool,
+    /// Whether to send the body gzip-compressed
+    pub compress_body: bool,
+    /// Cache responses are answered from and stored in
+    pub cache: Option<HttpCache>,
 }
 
 impl HttpRequest {
@@ -55,7 +114,22 @@ impl HttpRequest {
             headers: HashMap::new(),
             body: None,
             timeout: Duration::from_secs(30),
+            connect_timeout: None,
+            read_timeout: None,
             follow_redirects: true,
+            max_redirects: Some(DEFAULT_MAX_REDIRECTS),
+            pool_idle_timeout: None,
+            retry_on_connection_reset: false,
+            resolve: Vec::new(),
+            dns: None,
+            socket: SocketOptions::default(),
+            strict_redirects: false,
+            client_identity: None,
+            http_version: None,
+            auth: None,
+            compressed: false,
+            compress_body: false,
+            cache: None,
         }
     }
 
@@ -117,21 +191,335 @@ impl HttpRequest {
         self
     }
 
+    /// Appends data to the URL's query string, as `--get` does with `-d`.
+    ///
+    /// The data is added as given, after a `&` if the URL already has a
+    /// query; line breaks, as left by reading it from a file, are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Query parameters, e.g. `q=rust&page=2`
+    pub fn query(mut self, data: &str) -> Self {
+        let data = data.replace(['\r', '\n'], "");
+        if data.is_empty() {
+            return self;
+        }
+        let (url, fragment) = match self.url.split_once('#') {
+            Some((url, fragment)) => (url.to_string(), Some(fragment.to_string())),
+            None => (self.url.clone(), None),
+        };
+        let separator = match url.find('?') {
+            Some(at) if at + 1 < url.len() && !url.ends_with('&') => "&",
+            Some(_) => "",
+            None => "?",
+        };
+        self.url = format!("{}{}{}", url, separator, data);
+        if let Some(fragment) = fragment {
+            self.url = format!("{}#{}", self.url, fragment);
+        }
+        self
+    }
+
+    /// Sets a GraphQL operation as the JSON body.
+    ///
+    /// Also sets `Content-Type` and `Accept` to `application/json` unless
+    /// they are already present.
+    pub fn graphql(mut self, query: &GraphQlQuery) -> Self {
+        for name in ["Content-Type", "Accept"] {
+            if !self.has_header(name) {
+                self = self.header(name, "application/json");
+            }
+        }
+        self.body = Some(query.to_body());
+        self
+    }
+
     /// Reads the request body from a file.
     ///
+    /// If no `Content-Type` header has been set, one is derived from the
+    /// file extension, falling back to sniffing the content itself.
+    ///
     /// # Arguments
     ///
     /// * `path` - Path to the file containing the request body
     ///
     /// # Errors
     ///
-    /// Returns [`RurlError::FileError`] if the file cannot be read.
+    /// Returns [`RurlError::FileError`] if the file cannot be read or is not
+    /// UTF-8.
     pub fn body_from_file(mut self, path: &PathBuf) -> Result<Self> {
-        let content = std::fs::read_to_string(path)?;
+        let content = utf8_body(std::fs::read(path)?, &format!("body file {}", path.display()))?;
+        if !self.has_header("Content-Type") {
+            if let Some(content_type) = content_type_for_file(path, &content) {
+                self = self.header("Content-Type", content_type);
+            }
+        }
         self.body = Some(content);
         Ok(self)
     }
 
+    /// Reads the request body from a reader, such as stdin.
+    ///
+    /// If no `Content-Type` header has been set, one is sniffed from the
+    /// content, as for a file without a known extension.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::FileError`] if the reader fails or the body is
+    /// not UTF-8.
+    pub fn body_from_reader(mut self, mut reader: impl std::io::Read) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let content = utf8_body(bytes, "request body")?;
+        if !self.has_header("Content-Type") {
+            if let Some(content_type) = content_type_for_file(Path::new("-"), &content) {
+                self = self.header("Content-Type", content_type);
+            }
+        }
+        self.body = Some(content);
+        Ok(self)
+    }
+
+    /// Replaces `{{name}}` placeholders in the URL, headers, and body.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::TemplateError`] if a placeholder cannot be rendered.
+    pub fn render_vars(mut self, vars: &Vars) -> Result<Self> {
+        self.url = template::render(&self.url, vars)?;
+        self.headers = self.headers
+            .iter()
+            .map(|(key, value)| Ok((template::render(key, vars)?, template::render(value, vars)?)))
+            .collect::<Result<_>>()?;
+        if let Some(body) = &self.body {
+            self.body = Some(template::render(body, vars)?);
+        }
+        Ok(self)
+    }
+
+    /// Refuses redirects that downgrade to HTTP or leak credential headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - Whether to fail instead of warning
+    pub fn strict_redirects(mut self, strict: bool) -> Self {
+        self.strict_redirects = strict;
+        self
+    }
+
+    /// Sets DNS overrides, replacing any previously set.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - Host-to-address overrides
+    pub fn resolve(mut self, entries: Vec<ResolveEntry>) -> Self {
+        self.resolve = entries;
+        self
+    }
+
+    /// Sets the name servers host names are looked up with, instead of
+    /// the system resolver.
+    ///
+    /// `--resolve` overrides still take precedence.
+    pub fn dns(mut self, dns: Option<DnsResolver>) -> Self {
+        self.dns = dns;
+        self
+    }
+
+    /// Sets the local address and TCP options of the connections opened.
+    pub fn socket_options(mut self, options: SocketOptions) -> Self {
+        self.socket = options;
+        self
+    }
+
+    /// Sets the client certificate for mutual TLS.
+    ///
+    /// The identity is shared, so every clone of the request picks up a
+    /// reloaded certificate.
+    pub fn client_identity(mut self, identity: Option<Arc<ClientIdentity>>) -> Self {
+        self.client_identity = identity;
+        self
+    }
+
+    /// Sets the authentication providers.
+    ///
+    /// Credentials are resolved on every send, so clones of the request
+    /// share cached tokens and see refreshed ones.
+    pub fn auth(mut self, auth: Option<Arc<AuthChain>>) -> Self {
+        self.auth = auth.filter(|chain| !chain.is_empty());
+        self
+    }
+
+    /// Pins the HTTP version instead of negotiating it.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - Version to speak, or `None` to negotiate
+    pub fn http_version(mut self, version: Option<HttpVersion>) -> Self {
+        self.http_version = version;
+        self
+    }
+
+    /// Asks for a compressed response and decompresses its body.
+    ///
+    /// Sends `Accept-Encoding: gzip, br, deflate, zstd` unless an
+    /// `Accept-Encoding` header is already set. See [`encoding`](super::encoding).
+    pub fn compressed(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        if compressed && !self.has_header("Accept-Encoding") {
+            self = self.header("Accept-Encoding", ACCEPT_ENCODING);
+        }
+        self
+    }
+
+    /// Sends the body gzip-compressed, with `Content-Encoding: gzip`.
+    ///
+    /// The body is compressed once, when the request is prepared; a
+    /// request without a body is sent unchanged.
+    pub fn compress_body(mut self, compress: bool) -> Self {
+        self.compress_body = compress;
+        self
+    }
+
+    /// Answers the request from an HTTP cache when it can, and stores
+    /// cacheable responses in it.
+    pub fn cache(mut self, cache: Option<HttpCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Validates the URL and normalizes it in place.
+    ///
+    /// See [`normalize_url`] for the rules applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::InvalidUrl`] if the URL is not valid.
+    pub fn normalize_url(mut self) -> Result<Self> {
+        self.url = normalize_url(&self.url)?;
+        Ok(self)
+    }
+
+    /// Renders the request as an equivalent `curl` command line.
+    ///
+    /// Headers are sorted by name so the output is stable, and every value
+    /// is single-quoted for POSIX shells. `--auth` becomes curl's own
+    /// options where it has them; other providers are asked for
+    /// credentials, which are passed as headers. A body sent compressed is
+    /// piped through `gzip`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request can't be prepared or an auth
+    /// provider fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let request = HttpRequest::new("https://api.example.com/users")
+    ///     .method("POST")?
+    ///     .body(r#"{"name": "test"}"#);
+    /// println!("{}", request.to_curl().await?);
+    /// // curl -X POST 'https://api.example.com/users' \
+    /// //   --data-raw '{"name": "test"}' ...
+    /// ```
+    pub async fn to_curl(&self) -> Result<String> {
+        let method = match self.method {
+            Method::GET => String::new(),
+            Method::HEAD => "-I ".to_string(),
+            ref method => format!("-X {} ", method.as_str()),
+        };
+        let gzipped = self.body.as_ref().filter(|_| self.compress_body);
+        let pipe = match gzipped {
+            Some(body) => format!("printf '%s' {} | gzip | ", shell_quote(body)),
+            None => String::new(),
+        };
+        let mut parts = vec![format!("{}curl {}{}", pipe, method, shell_quote(&self.url))];
+
+        let mut headers: Vec<_> = self
+            .headers
+            .iter()
+            .filter(|(key, _)| gzipped.is_none() || !key.eq_ignore_ascii_case("Content-Encoding"))
+            .collect();
+        headers.sort();
+        for (key, value) in headers {
+            parts.push(format!("-H {}", shell_quote(&format!("{}: {}", key, value))));
+        }
+        if let Some(auth) = &self.auth {
+            for (option, value) in auth.curl_args(&self.clone().prepare()?).await? {
+                parts.push(format!("{} {}", option, shell_quote(&value)));
+            }
+        }
+
+        match (&self.body, gzipped) {
+            (_, Some(_)) => {
+                parts.push("-H 'Content-Encoding: gzip'".to_string());
+                parts.push("--data-binary @-".to_string());
+            }
+            (Some(body), None) => parts.push(format!("--data-raw {}", shell_quote(body))),
+            (None, None) => {}
+        }
+        if self.compressed {
+            parts.push("--compressed".to_string());
+        }
+
+        if self.follow_redirects {
+            parts.push("-L".to_string());
+            match self.max_redirects {
+                Some(DEFAULT_MAX_REDIRECTS) => {}
+                Some(max) => parts.push(format!("--max-redirs {}", max)),
+                None => parts.push("--max-redirs -1".to_string()),
+            }
+        }
+        for entry in &self.resolve {
+            parts.push(format!("--resolve {}", shell_quote(&entry.to_string())));
+        }
+        if let Some(dns) = &self.dns {
+            let [option, value] = dns.curl_args();
+            parts.push(format!("{} {}", option, shell_quote(&value)));
+        }
+        parts.extend(self.socket.curl_args());
+        if let Some(identity) = &self.client_identity {
+            parts.push(format!("--cert {}", shell_quote(&identity.cert_path().display().to_string())));
+            if let Some(key) = identity.key_path() {
+                parts.push(format!("--key {}", shell_quote(&key.display().to_string())));
+            }
+        }
+        match self.http_version {
+            Some(HttpVersion::Http10) => parts.push("--http1.0".to_string()),
+            Some(HttpVersion::Http11) => parts.push("--http1.1".to_string()),
+            Some(HttpVersion::Http2) => parts.push("--http2-prior-knowledge".to_string()),
+            None => {}
+        }
+        if let Some(connect) = self.connect_timeout {
+            parts.push(format!("--connect-timeout {}", connect.as_secs_f64()));
+        }
+        // curl has no idle timeout; aborting below 1 byte/s over the same
+        // whole seconds comes closest
+        if let Some(read) = self.read_timeout {
+            parts.push(format!("--speed-limit 1 --speed-time {}", read.as_secs_f64().ceil().max(1.0)));
+        }
+        parts.push(format!("--max-time {}", self.timeout.as_secs_f64()));
+
+        Ok(parts.join(" \\\n  "))
+    }
+
+    /// Returns true if a header with the given name is set (case-insensitive).
+    pub fn has_header(&self, name: &str) -> bool {
+        self.headers.keys().any(|key| key.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the value of a header (case-insensitive), if it is set.
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    /// Returns true if the request carries a validator (`If-None-Match`,
+    /// `If-Modified-Since`, ...), so `304 Not Modified` is an expected answer.
+    pub fn is_conditional(&self) -> bool {
+        CONDITIONAL_HEADERS.iter().any(|name| self.has_header(name))
+    }
+
     /// Sets the request timeout.
     ///
     /// # Arguments
@@ -142,6 +530,20 @@ impl HttpRequest {
         self
     }
 
+    /// Limits the time to open a connection, DNS lookup and TLS handshake
+    /// included, within the overall timeout.
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Fails the request if its response body stalls for longer than
+    /// `timeout` between chunks.
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
     /// Sets whether to follow HTTP redirects.
     ///
     /// # Arguments
@@ -151,12 +553,323 @@ impl HttpRequest {
         self.follow_redirects = follow;
         self
     }
+
+    /// Sets how many redirects are followed before the request fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - Most redirects to follow; `Some(0)` fails on any
+    ///   redirect, `None` follows any number
+    pub fn max_redirects(mut self, max: Option<usize>) -> Self {
+        self.max_redirects = max;
+        self
+    }
+
+    /// Sets how long idle pooled connections are kept alive.
+    ///
+    /// Set this below the idle timeout of any load balancer in front of the
+    /// target so connections are dropped by the client first.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - Idle timeout, or `None` for the reqwest default
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.pool_idle_timeout = timeout;
+        self
+    }
+
+    /// Sets whether to retry once on connection reset.
+    ///
+    /// Only requests with an idempotent method are retried, as the server
+    /// may already have applied the first attempt of any other.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry` - true to retry requests that failed on a dead connection
+    pub fn retry_on_connection_reset(mut self, retry: bool) -> Self {
+        self.retry_on_connection_reset = retry;
+        self
+    }
+
+    /// Compiles the request into a reusable [`PreparedRequest`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::InvalidHeader`] if a header name or value
+    /// cannot be sent over HTTP.
+    pub fn prepare(self) -> Result<PreparedRequest> {
+        PreparedRequest::new(self)
+    }
+}
+
+/// An [`HttpRequest`] compiled for repeated sending.
+///
+/// Headers are parsed into a [`HeaderMap`] and the body is copied into
+/// [`Bytes`] once, so sending the same request again only bumps reference
+/// counts instead of re-validating and copying every header and body.
+#[derive(Debug, Clone)]
+pub struct PreparedRequest {
+    request: HttpRequest,
+    headers: HeaderMap,
+    body: Option<Bytes>,
+    zone_route: Option<ZoneRoute>,
+}
+
+impl PreparedRequest {
+    /// Compiles a request.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::InvalidHeader`] if a header name or value
+    /// cannot be sent over HTTP, or [`RurlError::InvalidUrl`] if the URL
+    /// has an IPv6 zone naming an unknown interface.
+    pub fn new(request: HttpRequest) -> Result<Self> {
+        let mut headers = HeaderMap::with_capacity(request.headers.len());
+        for (key, value) in &request.headers {
+            let invalid = || RurlError::InvalidHeader(format!("{}: {}", key, value));
+            let name = HeaderName::from_bytes(key.as_bytes()).map_err(|_| invalid())?;
+            let value = HeaderValue::from_str(value).map_err(|_| invalid())?;
+            headers.insert(name, value);
+        }
+        let body = match &request.body {
+            Some(body) if request.compress_body => {
+                headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+                Some(Bytes::from(encoding::gzip(body.as_bytes())))
+            }
+            Some(body) => Some(Bytes::copy_from_slice(body.as_bytes())),
+            None => None,
+        };
+        let zone_route = ZoneRoute::for_url(&request.url)?;
+        Ok(Self { request, headers, body, zone_route })
+    }
+
+    /// Returns how to reach a zoned IPv6 host, if the URL has one.
+    pub(crate) fn zone_route(&self) -> Option<&ZoneRoute> {
+        self.zone_route.as_ref()
+    }
+
+    /// Returns the request this was compiled from.
+    pub fn request(&self) -> &HttpRequest {
+        &self.request
+    }
+
+    /// Returns the compiled headers.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the compiled body, if any.
+    pub fn body(&self) -> Option<&Bytes> {
+        self.body.as_ref()
+    }
+
+    /// Returns a copy of this request with one header set or replaced.
+    ///
+    /// Used for headers that change per send, like a random `Range`.
+    pub fn with_header(&self, name: HeaderName, value: HeaderValue) -> Self {
+        let mut copy = self.clone();
+        if let Ok(text) = value.to_str() {
+            copy.request.headers.retain(|key, _| !key.eq_ignore_ascii_case(name.as_str()));
+            copy.request.headers.insert(name.to_string(), text.to_string());
+        }
+        copy.headers.insert(name, value);
+        copy
+    }
+}
+
+/// Checks a method against the standard methods and a list of known custom ones.
+///
+/// Any method name is valid on the wire, so a typo like `GTE` would
+/// otherwise be sent as-is. Methods are compared case-insensitively.
+///
+/// # Arguments
+///
+/// * `method` - Method name to check
+/// * `custom` - Non-standard methods that are accepted as known (e.g. `PURGE`)
+/// * `allow_custom` - Whether unknown methods are allowed at all
+///
+/// # Returns
+///
+/// `None` for a known method, or a warning for an allowed unknown one.
+///
+/// # Errors
+///
+/// Returns [`RurlError::InvalidMethod`] for an unknown method unless
+/// `allow_custom` is set.
+pub fn check_method(method: &str, custom: &[String], allow_custom: bool) -> Result<Option<String>> {
+    let method = method.to_uppercase();
+    let known: Vec<&str> = STANDARD_METHODS
+        .iter()
+        .copied()
+        .chain(custom.iter().map(String::as_str))
+        .collect();
+    if known.iter().any(|known| known.eq_ignore_ascii_case(&method)) {
+        return Ok(None);
+    }
+
+    let limit = if method.len() > 5 { 2 } else { 1 };
+    let suggestion = known
+        .iter()
+        .map(|known| (edit_distance(&method, &known.to_uppercase()), known))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| format!(" (did you mean {}?)", known.to_uppercase()))
+        .unwrap_or_default();
+
+    if allow_custom {
+        Ok(Some(format!("'{}' is not a standard HTTP method{}", method, suggestion)))
+    } else {
+        Err(RurlError::InvalidMethod(format!(
+            "'{}' is not a known HTTP method{}; use --allow-custom-methods to send it anyway",
+            method, suggestion
+        )))
+    }
+}
+
+/// Counts the edits (insert, delete, substitute, swap adjacent) between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1).min(row[j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Returns true if `input` starts with a scheme and `://`, as opposed to
+/// containing `://` later on, say in a query parameter.
+fn has_scheme(input: &str) -> bool {
+    let Some((scheme, _)) = input.split_once("://") else {
+        return false;
+    };
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Quotes a string for safe use as a single POSIX shell word.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Validates and normalizes a request URL.
+///
+/// - Defaults the scheme to `https://` when omitted (`example.com/health`)
+/// - Converts internationalized host names to their ASCII (punycode) form
+/// - Rejects schemes other than `http` and `https`
+/// - Keeps an IPv6 zone (`[fe80::1%eth0]`), written as `%25eth0`
+///
+/// # Errors
+///
+/// Returns [`RurlError::InvalidUrl`] describing what is wrong with the URL.
+pub fn normalize_url(input: &str) -> Result<String> {
+    let input = input.trim();
+    // The url crate rejects IPv6 zones; normalize without and put it back
+    if let Some((bare, zone)) = zone::split_zone(input) {
+        return Ok(zone::with_zone(&normalize_url(&bare)?, &zone));
+    }
+    let candidate = if has_scheme(input) {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+
+    let invalid = |reason: &str| RurlError::InvalidUrl(format!("'{}': {}", input, reason));
+
+    let url = Url::parse(&candidate).map_err(|e| match e {
+        ParseError::EmptyHost => invalid("missing host name"),
+        ParseError::InvalidPort => invalid("port must be a number between 0 and 65535"),
+        ParseError::InvalidIpv4Address => invalid("malformed IPv4 address"),
+        ParseError::InvalidIpv6Address => invalid("malformed IPv6 address (use brackets, e.g. http://[::1]:8080)"),
+        ParseError::IdnaError => invalid("host is not a valid international domain name"),
+        ParseError::InvalidDomainCharacter => invalid("host contains characters not allowed in a domain name"),
+        other => invalid(&other.to_string()),
+    })?;
+
+    match url.scheme() {
+        "http" | "https" => {}
+        scheme => {
+            return Err(invalid(&format!(
+                "unsupported scheme '{}' (only http and https are supported)",
+                scheme
+            )))
+        }
+    }
+
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(invalid("missing host name"));
+    }
+
+    Ok(url.to_string())
+}
+
+/// Checks that a body read as `bytes` is UTF-8; `source` names it in the error.
+fn utf8_body(bytes: Vec<u8>, source: &str) -> Result<String> {
+    String::from_utf8(bytes).map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{} is not UTF-8", source)).into()
+    })
+}
+
+/// Guesses the content type of a body file.
+///
+/// The file extension is consulted first; files without a known extension
+/// are sniffed for JSON or XML content.
+pub fn content_type_for_file(path: &Path, content: &str) -> Option<&'static str> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+
+    let by_extension = match extension.as_deref() {
+        Some("json") => Some("application/json"),
+        Some("xml") => Some("application/xml"),
+        Some("html") | Some("htm") => Some("text/html"),
+        Some("txt") => Some("text/plain"),
+        Some("csv") => Some("text/csv"),
+        Some("yaml") | Some("yml") => Some("application/yaml"),
+        Some("js") => Some("application/javascript"),
+        Some("form") => Some("application/x-www-form-urlencoded"),
+        _ => None,
+    };
+    if by_extension.is_some() {
+        return by_extension;
+    }
+
+    let trimmed = content.trim_start();
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(content).is_ok()
+    {
+        Some("application/json")
+    } else if trimmed.starts_with("<?xml") {
+        Some("application/xml")
+    } else {
+        None
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_graphql_body() {
+        let request = HttpRequest::new("https://example.com/graphql")
+            .header("Accept", "application/graphql-response+json")
+            .graphql(&GraphQlQuery::new("{ viewer { login } }"));
+        assert_eq!(request.body.as_deref(), Some(r#"{"query":"{ viewer { login } }"}"#));
+        assert_eq!(request.headers.get("Content-Type").map(String::as_str), Some("application/json"));
+        assert_eq!(request.headers.get("Accept").map(String::as_str), Some("application/graphql-response+json"));
+    }
+
     #[test]
     fn test_new_request() {
         let request = HttpRequest::new("https://example.com");
@@ -197,6 +910,21 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_check_method() {
+        assert_eq!(check_method("get", &[], false).unwrap(), None);
+        assert_eq!(check_method("PURGE", &["purge".to_string()], false).unwrap(), None);
+
+        let err = check_method("GTE", &[], false).unwrap_err();
+        assert!(err.to_string().contains("'GTE' is not a known HTTP method (did you mean GET?)"));
+        assert!(err.to_string().contains("--allow-custom-methods"));
+
+        let warning = check_method("DELTE", &[], true).unwrap().unwrap();
+        assert_eq!(warning, "'DELTE' is not a standard HTTP method (did you mean DELETE?)");
+        let warning = check_method("PROPFIND", &[], true).unwrap().unwrap();
+        assert_eq!(warning, "'PROPFIND' is not a standard HTTP method");
+    }
+
     #[test]
     fn test_headers() {
         let request = HttpRequest::new("https://example.com")
@@ -235,9 +963,264 @@ mod tests {
     }
 
     #[test]
-    fn test_timeout() {
+    fn test_query() {
+        let request = HttpRequest::new("https://example.com/search").query("q=rust&page=2\n");
+        assert_eq!(request.url, "https://example.com/search?q=rust&page=2");
+        let request = HttpRequest::new("https://example.com/search?lang=en#top").query("q=rust");
+        assert_eq!(request.url, "https://example.com/search?lang=en&q=rust#top");
+        let request = HttpRequest::new("https://example.com/search?").query("q=rust");
+        assert_eq!(request.url, "https://example.com/search?q=rust");
+        assert_eq!(HttpRequest::new("https://example.com/").query("").url, "https://example.com/");
+    }
+
+    #[test]
+    fn test_prepare() {
+        let prepared = HttpRequest::new("https://example.com")
+            .header("X-Custom", "value")
+            .body("payload")
+            .prepare()
+            .unwrap();
+        assert_eq!(prepared.headers().get("x-custom").unwrap(), "value");
+        assert_eq!(prepared.body().map(|b| b.as_ref()), Some(&b"payload"[..]));
+        assert_eq!(prepared.request().url, "https://example.com");
+
+        let err = HttpRequest::new("https://example.com")
+            .header("Bad Name", "value")
+            .prepare()
+            .unwrap_err();
+        assert!(matches!(err, RurlError::InvalidHeader(_)));
+        let err = HttpRequest::new("https://example.com")
+            .header("X-Bad", "line\nbreak")
+            .prepare()
+            .unwrap_err();
+        assert!(matches!(err, RurlError::InvalidHeader(_)));
+    }
+
+    #[test]
+    fn test_prepare_compressed_body() {
+        let prepared = HttpRequest::new("https://example.com")
+            .body("payload ".repeat(100))
+            .compress_body(true)
+            .prepare()
+            .unwrap();
+        assert_eq!(prepared.headers().get("content-encoding").unwrap(), "gzip");
+        let body = prepared.body().unwrap();
+        assert!(body.len() < 800);
+        assert_eq!(encoding::decode("gzip", body).unwrap().unwrap(), "payload ".repeat(100).as_bytes());
+
+        let prepared = HttpRequest::new("https://example.com").compress_body(true).prepare().unwrap();
+        assert!(prepared.body().is_none());
+        assert!(prepared.headers().get("content-encoding").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
         let request = HttpRequest::new("https://example.com")
             .timeout(Duration::from_secs(60));
         assert_eq!(request.timeout, Duration::from_secs(60));
+
+        let request = HttpRequest::new("https://example.com")
+            .connect_timeout(Some(Duration::from_millis(1500)))
+            .read_timeout(Some(Duration::from_secs(5)));
+        assert_eq!(request.connect_timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(request.read_timeout, Some(Duration::from_secs(5)));
+        assert!(request
+            .to_curl()
+            .await
+            .unwrap()
+            .contains("--connect-timeout 1.5 \\\n  --speed-limit 1 --speed-time 5 \\\n  --max-time 30"));
+        let request = request.read_timeout(Some(Duration::from_millis(200))).timeout(Duration::from_millis(2500));
+        assert!(request.to_curl().await.unwrap().ends_with("--speed-time 1 \\\n  --max-time 2.5"));
+    }
+
+    #[test]
+    fn test_render_vars() {
+        let vars: Vars = [("id".to_string(), "42".to_string())].into_iter().collect();
+        let request = HttpRequest::new("https://example.com/users/{{id}}")
+            .header("X-User", "{{id}}")
+            .body(r#"{"id": {{id}}}"#)
+            .render_vars(&vars)
+            .unwrap();
+        assert_eq!(request.url, "https://example.com/users/42");
+        assert_eq!(request.headers.get("X-User"), Some(&"42".to_string()));
+        assert_eq!(request.body, Some(r#"{"id": 42}"#.to_string()));
+    }
+
+    #[test]
+    fn test_connection_settings() {
+        let request = HttpRequest::new("https://example.com")
+            .pool_idle_timeout(Some(Duration::from_secs(5)))
+            .retry_on_connection_reset(true);
+        assert_eq!(request.pool_idle_timeout, Some(Duration::from_secs(5)));
+        assert!(request.retry_on_connection_reset);
+    }
+
+    #[tokio::test]
+    async fn test_to_curl() {
+        let request = HttpRequest::new("https://example.com/users")
+            .method("POST")
+            .unwrap()
+            .header("X-B", "2")
+            .header("Content-Type", "application/json")
+            .body(r#"{"name": "it's"}"#)
+            .follow_redirects(false);
+        let curl = request.to_curl().await.unwrap();
+        assert_eq!(
+            curl,
+            "curl -X POST 'https://example.com/users' \\\n  \
+             -H 'Content-Type: application/json' \\\n  \
+             -H 'X-B: 2' \\\n  \
+             --data-raw '{\"name\": \"it'\\''s\"}' \\\n  \
+             --max-time 30"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_to_curl_auth_and_compressed_body() {
+        let auth = AuthChain::from_specs(&["basic:alice:it's".to_string()]).unwrap();
+        let request = HttpRequest::new("https://example.com/upload")
+            .method("POST")
+            .unwrap()
+            .header("content-encoding", "identity")
+            .body("payload")
+            .compress_body(true)
+            .auth(Some(Arc::new(auth)));
+        assert_eq!(
+            request.to_curl().await.unwrap(),
+            "printf '%s' 'payload' | gzip | curl -X POST 'https://example.com/upload' \\\n  \
+             -u 'alice:it'\\''s' \\\n  \
+             -H 'Content-Encoding: gzip' \\\n  \
+             --data-binary @- \\\n  \
+             -L \\\n  \
+             --max-time 30"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compressed() {
+        let request = HttpRequest::new("https://example.com").compressed(true);
+        assert_eq!(request.headers.get("Accept-Encoding").map(String::as_str), Some(ACCEPT_ENCODING));
+        assert!(request.to_curl().await.unwrap().contains("--compressed"));
+        let request = HttpRequest::new("https://example.com").header("Accept-Encoding", "gzip").compressed(true);
+        assert_eq!(request.headers.get("Accept-Encoding").map(String::as_str), Some("gzip"));
+        assert!(!HttpRequest::new("https://example.com").compressed(false).has_header("Accept-Encoding"));
+    }
+
+    #[tokio::test]
+    async fn test_to_curl_get_and_head() {
+        let get = HttpRequest::new("https://example.com").to_curl().await.unwrap();
+        assert!(get.starts_with("curl 'https://example.com'"));
+        assert!(get.contains("-L"));
+
+        let head = HttpRequest::new("https://example.com").method("HEAD").unwrap().to_curl().await.unwrap();
+        assert!(head.starts_with("curl -I "));
+    }
+
+    #[tokio::test]
+    async fn test_to_curl_max_redirects() {
+        let request = HttpRequest::new("https://example.com");
+        assert!(!request.to_curl().await.unwrap().contains("--max-redirs"));
+        assert!(request.clone().max_redirects(Some(0)).to_curl().await.unwrap().contains("-L \\\n  --max-redirs 0"));
+        assert!(request.clone().max_redirects(None).to_curl().await.unwrap().contains("--max-redirs -1"));
+        assert!(!request.max_redirects(Some(3)).follow_redirects(false).to_curl().await.unwrap().contains("--max-redirs"));
+    }
+
+    #[tokio::test]
+    async fn test_to_curl_dns() {
+        let dns = DnsResolver::servers(vec!["1.1.1.1".parse().unwrap(), "8.8.8.8:5353".parse().unwrap()]).unwrap();
+        let curl = HttpRequest::new("https://example.com").dns(Some(dns)).to_curl().await.unwrap();
+        assert!(curl.contains("--dns-servers '1.1.1.1,8.8.8.8:5353'"));
+        let doh = DnsResolver::doh("https://1.1.1.1/dns-query").unwrap();
+        let curl = HttpRequest::new("https://example.com").dns(Some(doh)).to_curl().await.unwrap();
+        assert!(curl.contains("--doh-url 'https://1.1.1.1/dns-query'"));
+    }
+
+    #[test]
+    fn test_normalize_url_defaults_scheme() {
+        assert_eq!(normalize_url("example.com/health").unwrap(), "https://example.com/health");
+        assert_eq!(normalize_url("localhost:8080/x").unwrap(), "https://localhost:8080/x");
+        assert_eq!(normalize_url("http://example.com").unwrap(), "http://example.com/");
+        // A URL in the query doesn't count as the scheme
+        assert_eq!(
+            normalize_url("example.com/r?to=http://x").unwrap(),
+            "https://example.com/r?to=http://x"
+        );
+        assert_eq!(normalize_url("example.com:8080/a://b").unwrap(), "https://example.com:8080/a://b");
+        assert!(normalize_url("ftp://example.com/?u=http://x").is_err());
+    }
+
+    #[test]
+    fn test_normalize_url_idn() {
+        assert_eq!(normalize_url("https://bücher.example/").unwrap(), "https://xn--bcher-kva.example/");
+    }
+
+    #[test]
+    fn test_normalize_url_errors() {
+        let err = normalize_url("ftp://example.com").unwrap_err();
+        assert!(err.to_string().contains("unsupported scheme 'ftp'"));
+
+        let err = normalize_url("http://example.com:99999").unwrap_err();
+        assert!(err.to_string().contains("port"));
+
+        assert!(normalize_url("http://").is_err());
+    }
+
+    #[test]
+    fn test_content_type_by_extension() {
+        assert_eq!(content_type_for_file(Path::new("data.json"), ""), Some("application/json"));
+        assert_eq!(content_type_for_file(Path::new("DATA.XML"), ""), Some("application/xml"));
+        assert_eq!(content_type_for_file(Path::new("notes.txt"), "{}"), Some("text/plain"));
+    }
+
+    #[test]
+    fn test_content_type_sniffing() {
+        assert_eq!(content_type_for_file(Path::new("payload"), r#"{"a": 1}"#), Some("application/json"));
+        assert_eq!(content_type_for_file(Path::new("payload"), "<?xml version=\"1.0\"?><a/>"), Some("application/xml"));
+        assert_eq!(content_type_for_file(Path::new("payload"), "plain text"), None);
+    }
+
+    #[test]
+    fn test_body_from_file_keeps_explicit_content_type() {
+        let path = std::env::temp_dir().join("hurley_test_body_explicit.json");
+        std::fs::write(&path, r#"{"key": "value"}"#).unwrap();
+        let request = HttpRequest::new("https://example.com")
+            .header("content-type", "text/plain")
+            .body_from_file(&path)
+            .unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(request.headers.len(), 1);
+        assert_eq!(request.headers.get("content-type"), Some(&"text/plain".to_string()));
+    }
+
+    #[test]
+    fn test_body_from_file_sets_content_type() {
+        let path = std::env::temp_dir().join("hurley_test_body_detect.json");
+        std::fs::write(&path, r#"{"key": "value"}"#).unwrap();
+        let request = HttpRequest::new("https://example.com")
+            .body_from_file(&path)
+            .unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(request.headers.get("Content-Type"), Some(&"application/json".to_string()));
+    }
+
+    #[test]
+    fn test_body_from_reader() {
+        let request = HttpRequest::new("https://example.com")
+            .body_from_reader(&b"[1, 2, 3]\n"[..])
+            .unwrap();
+        assert_eq!(request.body.as_deref(), Some("[1, 2, 3]\n"));
+        assert_eq!(request.headers.get("Content-Type"), Some(&"application/json".to_string()));
+        let error = HttpRequest::new("https://example.com").body_from_reader(&[0xff, 0xfe][..]).unwrap_err();
+        assert!(error.to_string().contains("request body is not UTF-8"), "{}", error);
+    }
+
+    #[test]
+    fn test_body_from_file_rejects_binary() {
+        let path = std::env::temp_dir().join("hurley_test_body_binary.bin");
+        std::fs::write(&path, [0x89, b'P', b'N', b'G', 0xff]).unwrap();
+        let error = HttpRequest::new("https://example.com").body_from_file(&path).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert!(matches!(error, RurlError::FileError(_)));
+        assert!(error.to_string().contains("hurley_test_body_binary.bin is not UTF-8"), "{}", error);
     }
 }
@@ -0,0 +1,204 @@
+//! DNS resolution overrides.
+//!
+//! Supports curl-style `--resolve host:port:address` entries, which send
+//! requests for `host:port` to a specific address while keeping the
+//! original host name for the `Host` header and TLS SNI.
+
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{Result, RurlError};
+
+/// A single DNS override, e.g. `example.com:443:10.0.0.5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveEntry {
+    /// Host name to override
+    pub host: String,
+    /// Port the override applies to
+    pub port: u16,
+    /// Address to connect to instead
+    pub addr: IpAddr,
+}
+
+impl ResolveEntry {
+    /// Returns the socket address to connect to.
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.addr, self.port)
+    }
+
+    /// Returns the `host:port` key this override applies to.
+    pub fn target(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+impl std::fmt::Display for ResolveEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.addr {
+            IpAddr::V6(addr) => write!(f, "{}:{}:[{}]", self.host, self.port, addr),
+            IpAddr::V4(addr) => write!(f, "{}:{}:{}", self.host, self.port, addr),
+        }
+    }
+}
+
+impl FromStr for ResolveEntry {
+    type Err = RurlError;
+
+    /// Parses `host:port:address`; IPv6 addresses may be bracketed.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            RurlError::ResolveError(format!("'{}': {} (expected host:port:address)", s, reason))
+        };
+
+        let mut parts = s.trim().splitn(3, ':');
+        let host = parts.next().filter(|h| !h.is_empty()).ok_or_else(|| invalid("missing host"))?;
+        let port = parts
+            .next()
+            .ok_or_else(|| invalid("missing port"))?
+            .parse::<u16>()
+            .map_err(|_| invalid("invalid port"))?;
+        let addr = parts
+            .next()
+            .ok_or_else(|| invalid("missing address"))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .map_err(|_| invalid("invalid IP address"))?;
+
+        Ok(Self {
+            host: host.to_ascii_lowercase(),
+            port,
+            addr,
+        })
+    }
+}
+
+/// Reads resolve entries from a file, one `host:port:address` per line.
+///
+/// Blank lines and lines starting with `#` are ignored.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or an entry is malformed.
+pub fn resolve_entries_from_file(path: &Path) -> Result<Vec<ResolveEntry>> {
+    let content = std::fs::read_to_string(path)?;
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::parse)
+        .collect()
+}
+
+/// Splits resolve entries into one set per backend to compare.
+///
+/// When a single `host:port` is mapped to several addresses, returns one
+/// set per address, each also containing the entries for every other
+/// target. Otherwise returns the entries unchanged as a single set.
+///
+/// # Errors
+///
+/// Returns an error if more than one `host:port` has several addresses,
+/// since it would be ambiguous which one is being compared.
+pub fn backend_sets(entries: &[ResolveEntry]) -> Result<Vec<Vec<ResolveEntry>>> {
+    let mut targets: Vec<String> = entries.iter().map(ResolveEntry::target).collect();
+    targets.sort();
+    targets.dedup();
+
+    let mut varying = targets
+        .iter()
+        .filter(|target| entries.iter().filter(|e| &e.target() == *target).count() > 1);
+    let Some(target) = varying.next() else {
+        return Ok(vec![entries.to_vec()]);
+    };
+    if let Some(other) = varying.next() {
+        return Err(RurlError::ResolveError(format!(
+            "both {} and {} have several addresses; only one host can be compared at a time",
+            target, other
+        )));
+    }
+
+    let fixed: Vec<ResolveEntry> = entries.iter().filter(|e| &e.target() != target).cloned().collect();
+    Ok(entries
+        .iter()
+        .filter(|e| &e.target() == target)
+        .map(|backend| {
+            let mut set = vec![backend.clone()];
+            set.extend(fixed.iter().cloned());
+            set
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ipv4() {
+        let entry: ResolveEntry = "Example.com:443:10.0.0.5".parse().unwrap();
+        assert_eq!(entry.host, "example.com");
+        assert_eq!(entry.port, 443);
+        assert_eq!(entry.socket_addr(), "10.0.0.5:443".parse().unwrap());
+        assert_eq!(entry.to_string(), "example.com:443:10.0.0.5");
+    }
+
+    #[test]
+    fn test_parse_ipv6() {
+        let entry: ResolveEntry = "example.com:80:[::1]".parse().unwrap();
+        assert_eq!(entry.addr, "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(entry.to_string(), "example.com:80:[::1]");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("example.com".parse::<ResolveEntry>().is_err());
+        assert!("example.com:http:10.0.0.1".parse::<ResolveEntry>().is_err());
+        assert!("example.com:80:not-an-ip".parse::<ResolveEntry>().is_err());
+    }
+
+    #[test]
+    fn test_backend_sets() {
+        let entries: Vec<ResolveEntry> = [
+            "api.example.com:443:10.0.0.1",
+            "api.example.com:443:10.0.1.1",
+            "auth.example.com:443:10.0.9.9",
+        ]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+
+        let sets = backend_sets(&entries).unwrap();
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0][0].to_string(), "api.example.com:443:10.0.0.1");
+        assert_eq!(sets[1][0].to_string(), "api.example.com:443:10.0.1.1");
+        assert!(sets.iter().all(|set| set.len() == 2));
+
+        assert_eq!(backend_sets(&entries[1..]).unwrap().len(), 1);
+        assert_eq!(backend_sets(&[]).unwrap(), vec![Vec::new()]);
+    }
+
+    #[test]
+    fn test_backend_sets_rejects_two_varying_hosts() {
+        let entries: Vec<ResolveEntry> = [
+            "a.example.com:443:10.0.0.1",
+            "a.example.com:443:10.0.0.2",
+            "b.example.com:443:10.0.1.1",
+            "b.example.com:443:10.0.1.2",
+        ]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+        assert!(backend_sets(&entries).is_err());
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join("hurley_test_resolve.txt");
+        std::fs::write(&path, "# backends\nexample.com:443:10.0.0.1\n\nexample.com:443:10.0.0.2\n").unwrap();
+        let entries = resolve_entries_from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(entries.len(), 2);
+    }
+}
@@ -4,8 +4,25 @@
 //! status codes and headers.
 
 use reqwest::header::HeaderMap;
-use reqwest::StatusCode;
+use reqwest::{StatusCode, Version};
+#[cfg(feature = "cli")]
+use std::io::{self, IsTerminal, Write};
+#[cfg(feature = "cli")]
+use std::path::Path;
+#[cfg(feature = "cli")]
+use super::cookies::CookieAudit;
+#[cfg(feature = "cli")]
+use super::graphql::GraphQlResponse;
 use std::time::Duration;
+use super::cache::CacheStatus;
+use super::encoding::BodySizes;
+use super::redirect::RedirectWarning;
+use super::timing::PhaseTimings;
+#[cfg(feature = "cli")]
+use super::version::version_name;
+#[cfg(feature = "cli")]
+use super::render::{BodyRenderer, RendererRegistry};
+#[cfg(feature = "cli")]
 use colored::Colorize;
 
 /// HTTP response with timing information.
@@ -22,6 +39,21 @@ pub struct HttpResponse {
     pub body: String,
     /// Time taken to receive the response
     pub duration: Duration,
+    /// Whether this is the response to a HEAD request (no body was read)
+    pub is_head: bool,
+    /// Part of `duration` spent setting up the client and getting
+    /// credentials before sending
+    pub client_setup: Duration,
+    /// Unsafe redirects that were followed to get this response
+    pub redirect_warnings: Vec<RedirectWarning>,
+    /// Protocol version the response was received over
+    pub version: Version,
+    /// How long each phase of the request took
+    pub phases: PhaseTimings,
+    /// Body sizes before and after decompression, if it was decompressed
+    pub decompressed: Option<BodySizes>,
+    /// How the response cache answered the request, if one was used
+    pub cache: Option<CacheStatus>,
 }
 
 impl HttpResponse {
@@ -37,21 +69,101 @@ impl HttpResponse {
             headers,
             body,
             duration,
+            is_head: false,
+            client_setup: Duration::ZERO,
+            redirect_warnings: Vec::new(),
+            version: Version::HTTP_11,
+            phases: PhaseTimings::default(),
+            decompressed: None,
+            cache: None,
         }
     }
 
+    /// Creates a response to a HEAD request, which never carries a body.
+    pub fn head(status: StatusCode, headers: HeaderMap, duration: Duration) -> Self {
+        Self {
+            is_head: true,
+            ..Self::new(status, headers, String::new(), duration)
+        }
+    }
+
+    /// Returns the body size announced by the `Content-Length` header.
+    pub fn content_length(&self) -> Option<u64> {
+        self.headers
+            .get(reqwest::header::CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
     /// Returns true if the response status is successful (2xx).
     pub fn is_success(&self) -> bool {
         self.status.is_success()
     }
 
+    /// Formats the response duration in milliseconds.
+    pub fn format_duration(&self) -> String {
+        format!("Time: {:.3}ms", self.duration.as_secs_f64() * 1000.0)
+    }
+
+    /// Formats the time spent in each phase of the request in milliseconds.
+    ///
+    /// A request sent over a reused connection has no DNS, connect or TLS
+    /// phase; it is marked as reused instead.
+    pub fn format_phases(&self) -> String {
+        let ms = |d: Duration| format!("{:.3}ms", d.as_secs_f64() * 1000.0);
+        let mut parts = Vec::new();
+        if !self.phases.new_connection() {
+            parts.push("Connection reused".to_string());
+        }
+        let connection = [("DNS", self.phases.dns), ("Connect", self.phases.connect), ("TLS", self.phases.tls)];
+        for (name, phase) in connection {
+            if let Some(phase) = phase {
+                parts.push(format!("{}: {}", name, ms(phase)));
+            }
+        }
+        parts.push(format!("TTFB: {}", ms(self.phases.ttfb)));
+        if !self.is_head {
+            parts.push(format!("Transfer: {}", ms(self.phases.transfer)));
+        }
+        parts.join("  ")
+    }
+
+    /// Formats the body size before and after decompression, e.g.
+    /// `Size: 4096 bytes (1024 received, gzip)`, if it was decompressed.
+    pub fn format_sizes(&self) -> Option<String> {
+        let sizes = self.decompressed?;
+        let encoding = self
+            .headers
+            .get(reqwest::header::CONTENT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        Some(format!("Size: {} bytes ({} received, {})", sizes.decoded, sizes.received, encoding))
+    }
+
+    /// Returns true if the response took longer than `threshold`.
+    pub fn is_slow(&self, threshold: Duration) -> bool {
+        self.duration > threshold
+    }
+}
+
+/// Terminal output, only available with the `cli` feature.
+#[cfg(feature = "cli")]
+impl HttpResponse {
     /// Formats the status line with color based on status code.
     ///
     /// - 2xx: Green
     /// - 4xx: Yellow
     /// - 5xx: Red
     pub fn format_status(&self) -> String {
-        let status_str = format!("HTTP/1.1 {} {}", self.status.as_u16(), self.status.canonical_reason().unwrap_or(""));
+        let status_str = format!(
+            "{} {} {}",
+            version_name(self.version),
+            self.status.as_u16(),
+            self.status.canonical_reason().unwrap_or("")
+        );
         
         if self.status.is_success() {
             status_str.green().to_string()
@@ -77,20 +189,166 @@ impl HttpResponse {
         result
     }
 
-    /// Formats the response duration in milliseconds.
-    pub fn format_duration(&self) -> String {
-        format!("Time: {:.3}ms", self.duration.as_secs_f64() * 1000.0)
+    /// Formats the timing line, colored against a response-time budget.
+    ///
+    /// - Within the threshold (or no threshold): dimmed
+    /// - Over the threshold: yellow
+    /// - Over twice the threshold: red
+    pub fn format_timing(&self, slow_threshold: Option<Duration>) -> String {
+        let line = self.format_duration();
+        match slow_threshold {
+            Some(threshold) if self.duration > threshold * 2 => line.red().bold().to_string(),
+            Some(threshold) if self.duration > threshold => line.yellow().bold().to_string(),
+            _ => line.dimmed().to_string(),
+        }
     }
 
     /// Prints the response to stdout.
     ///
+    /// On a terminal the body is rendered according to its `Content-Type`
+    /// (see [`RendererRegistry`]); otherwise it is printed unchanged, so
+    /// `hurley URL > page.html` saves the page as served.
+    ///
+    /// # Arguments
+    ///
+    /// * `include_headers` - Whether to print response headers
+    /// * `verbose` - Whether to print timing information
+    /// * `slow_threshold` - Response-time budget; when set, timing is always
+    ///   printed and colored against it
+    pub fn print(&self, include_headers: bool, verbose: bool, slow_threshold: Option<Duration>) {
+        if self.is_head {
+            // HEAD responses have no body; the headers are the whole answer.
+            self.print_head(true, verbose, slow_threshold);
+            let size = match self.content_length() {
+                Some(len) => format!("{} bytes", len),
+                None => "unknown".to_string(),
+            };
+            println!("{}", format!("Expected body size: {}", size).dimmed());
+            return;
+        }
+
+        self.print_head(include_headers, verbose, slow_threshold);
+        self.print_body(None);
+    }
+
+    /// Prints the response like [`print`](Self::print), with `raw`, the
+    /// body as received, written to stdout byte for byte when it is not a
+    /// terminal.
+    pub fn print_received(&self, raw: &[u8], include_headers: bool, verbose: bool, slow_threshold: Option<Duration>) {
+        if self.is_head {
+            self.print(include_headers, verbose, slow_threshold);
+            return;
+        }
+        self.print_head(include_headers, verbose, slow_threshold);
+        self.print_body(Some(raw));
+    }
+
+    fn print_body(&self, raw: Option<&[u8]>) {
+        let stdout = io::stdout();
+        let terminal = stdout.is_terminal();
+        // A closed pipe just ends the output
+        let _ = self.write_body(&mut stdout.lock(), raw, terminal);
+    }
+
+    /// Writes the body: rendered for its `Content-Type` on a terminal, and
+    /// otherwise exactly as received (`raw`, if kept, else `body`), with
+    /// nothing added.
+    fn write_body(&self, out: &mut dyn Write, raw: Option<&[u8]>, terminal: bool) -> io::Result<()> {
+        if terminal {
+            writeln!(out, "{}", RendererRegistry::default().render(&self.body, &self.headers))
+        } else {
+            out.write_all(raw.unwrap_or(self.body.as_bytes()))?;
+            out.flush()
+        }
+    }
+
+    /// Prints the response with a specific renderer, ignoring its content type.
+    ///
+    /// # Arguments
+    ///
+    /// * `renderer` - Renderer for the body
+    /// * `include_headers` - Whether to print response headers
+    /// * `verbose` - Whether to print timing information
+    /// * `slow_threshold` - Response-time budget for coloring the timing line
+    pub fn print_with(
+        &self,
+        renderer: &dyn BodyRenderer,
+        include_headers: bool,
+        verbose: bool,
+        slow_threshold: Option<Duration>,
+    ) {
+        if self.is_head {
+            self.print(include_headers, verbose, slow_threshold);
+            return;
+        }
+        self.print_head(include_headers, verbose, slow_threshold);
+        println!("{}", renderer.render(&self.body, &self.headers));
+    }
+
+    /// Prints the response for a body that was saved to a file.
+    ///
+    /// Status and headers go to stdout as usual; a one-line summary of the
+    /// saved file goes to stderr so it never mixes with piped output.
+    ///
     /// # Arguments
     ///
+    /// * `path` - File the body was written to
+    /// * `bytes` - Number of bytes written
     /// * `include_headers` - Whether to print response headers
     /// * `verbose` - Whether to print timing information
-    pub fn print(&self, include_headers: bool, verbose: bool) {
-        if verbose {
-            println!("{}", self.format_duration().dimmed());
+    /// * `slow_threshold` - Response-time budget for coloring the timing line
+    pub fn print_saved(
+        &self,
+        path: &Path,
+        bytes: u64,
+        include_headers: bool,
+        verbose: bool,
+        slow_threshold: Option<Duration>,
+    ) {
+        self.print_head(include_headers, verbose, slow_threshold);
+        eprintln!("{}", format!("Saved {} bytes to {}", bytes, path.display()).dimmed());
+    }
+
+    /// Prints a GraphQL response with `data` and `errors` shown separately.
+    ///
+    /// Falls back to [`print`](Self::print) if the body isn't a GraphQL
+    /// response.
+    pub fn print_graphql(&self, include_headers: bool, verbose: bool, slow_threshold: Option<Duration>) {
+        let Some(graphql) = GraphQlResponse::parse(&self.body) else {
+            self.print(include_headers, verbose, slow_threshold);
+            return;
+        };
+
+        self.print_head(include_headers, verbose, slow_threshold);
+
+        if let Some(data) = &graphql.data {
+            println!("{}", "data:".green().bold());
+            println!("{}", serde_json::to_string_pretty(data).unwrap_or_else(|_| data.to_string()));
+        }
+
+        if !graphql.errors.is_empty() {
+            if graphql.data.is_some() {
+                println!();
+            }
+            println!("{}", format!("errors ({}):", graphql.errors.len()).red().bold());
+            for message in graphql.error_messages() {
+                println!("  {} {}", "-".red(), message);
+            }
+        }
+    }
+
+    fn print_head(&self, include_headers: bool, verbose: bool, slow_threshold: Option<Duration>) {
+        if verbose || slow_threshold.is_some() {
+            println!("{}", self.format_timing(slow_threshold));
+            if verbose {
+                println!("{}", self.format_phases().dimmed());
+                if let Some(sizes) = self.format_sizes() {
+                    println!("{}", sizes.dimmed());
+                }
+                if let Some(cache) = self.cache {
+                    println!("{}", format!("Cache: {}", cache).dimmed());
+                }
+            }
             println!();
         }
 
@@ -98,17 +356,47 @@ impl HttpResponse {
             println!("{}", self.format_status());
             print!("{}", self.format_headers());
             println!();
+            self.print_cookies();
         }
+    }
 
-        // Try to pretty print JSON
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&self.body) {
-            if let Ok(pretty) = serde_json::to_string_pretty(&json) {
-                println!("{}", pretty);
-                return;
+    /// Prints a summary of the cookies the response sets, with warnings
+    /// for missing security attributes and duplicates.
+    ///
+    /// Prints nothing if there are no `Set-Cookie` headers.
+    pub fn print_cookies(&self) {
+        let audit = CookieAudit::from_headers(&self.headers);
+        if audit.is_empty() {
+            return;
+        }
+        println!("{}", "Cookies".blue().bold());
+        for cookie in &audit.cookies {
+            let mut flags = Vec::new();
+            if cookie.secure {
+                flags.push("Secure".to_string());
+            }
+            if cookie.http_only {
+                flags.push("HttpOnly".to_string());
             }
+            if let Some(same_site) = &cookie.same_site {
+                flags.push(format!("SameSite={}", same_site));
+            }
+            if flags.is_empty() {
+                flags.push("(no flags)".to_string());
+            }
+            println!(
+                "  {}  domain={} path={} {} expires={}",
+                cookie.name.cyan(),
+                cookie.domain.as_deref().unwrap_or("(host only)"),
+                cookie.path.as_deref().unwrap_or("(default)"),
+                flags.join(" "),
+                cookie.expiry()
+            );
         }
-
-        println!("{}", self.body);
+        for warning in &audit.warnings {
+            println!("  {} {}", "Warning:".yellow().bold(), warning);
+        }
+        println!();
     }
 }
 
@@ -148,4 +436,80 @@ mod tests {
         );
         assert!(response.format_duration().contains("150"));
     }
+
+    #[test]
+    fn test_is_slow() {
+        let response = HttpResponse::new(
+            StatusCode::OK,
+            HeaderMap::new(),
+            "OK".to_string(),
+            Duration::from_millis(450),
+        );
+        assert!(response.is_slow(Duration::from_millis(300)));
+        assert!(!response.is_slow(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_format_phases() {
+        let mut response = HttpResponse::new(StatusCode::OK, HeaderMap::new(), String::new(), Duration::from_millis(60));
+        response.phases = PhaseTimings {
+            dns: Some(Duration::from_millis(2)),
+            connect: Some(Duration::from_millis(8)),
+            tls: Some(Duration::from_millis(20)),
+            ttfb: Duration::from_millis(30),
+            transfer: Duration::from_micros(1500),
+        };
+        assert_eq!(
+            response.format_phases(),
+            "DNS: 2.000ms  Connect: 8.000ms  TLS: 20.000ms  TTFB: 30.000ms  Transfer: 1.500ms"
+        );
+
+        response.phases = PhaseTimings { ttfb: Duration::from_millis(12), ..PhaseTimings::default() };
+        assert_eq!(response.format_phases(), "Connection reused  TTFB: 12.000ms  Transfer: 0.000ms");
+    }
+
+    #[test]
+    fn test_format_sizes() {
+        let mut headers = HeaderMap::new();
+        headers.insert(reqwest::header::CONTENT_ENCODING, "br".parse().unwrap());
+        let mut response = HttpResponse::new(StatusCode::OK, headers, String::new(), Duration::ZERO);
+        assert_eq!(response.format_sizes(), None);
+        response.decompressed = Some(BodySizes { received: 1024, decoded: 4096 });
+        assert_eq!(response.format_sizes().as_deref(), Some("Size: 4096 bytes (1024 received, br)"));
+    }
+
+    #[test]
+    fn test_head_response() {
+        let mut headers = HeaderMap::new();
+        headers.insert(reqwest::header::CONTENT_LENGTH, "1234".parse().unwrap());
+        let response = HttpResponse::head(StatusCode::OK, headers, Duration::from_millis(10));
+        assert!(response.is_head);
+        assert!(response.body.is_empty());
+        assert_eq!(response.content_length(), Some(1234));
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_write_body_unchanged_off_terminal() {
+        let mut headers = HeaderMap::new();
+        headers.insert(reqwest::header::CONTENT_TYPE, "text/html".parse().unwrap());
+        let html = "<html><body><p>Hi</p>\n</body></html>";
+        let response = HttpResponse::new(StatusCode::OK, headers, html.to_string(), Duration::ZERO);
+
+        let mut out = Vec::new();
+        response.write_body(&mut out, None, false).unwrap();
+        assert_eq!(out, html.as_bytes());
+
+        // The body as received wins over the decoded text
+        let raw = b"<p>\xff</p>";
+        let mut out = Vec::new();
+        response.write_body(&mut out, Some(raw), false).unwrap();
+        assert_eq!(out, raw);
+
+        let mut out = Vec::new();
+        response.write_body(&mut out, None, true).unwrap();
+        let rendered = String::from_utf8(out).unwrap();
+        assert!(!rendered.contains("<p>"));
+        assert!(rendered.contains("Hi"));
+    }
 }
@@ -0,0 +1,147 @@
+//! Options of the TCP sockets a client opens: the local address they are
+//! bound to (`--local-addr`, `--interface`), `TCP_NODELAY`
+//! (`--no-tcp-nodelay`) and TCP keepalive probes (`--tcp-keepalive`).
+//!
+//! Binding a load test to a source address spreads it over the addresses
+//! of a multi-homed machine, or keeps it on the interface a firewall rule
+//! expects.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use reqwest::ClientBuilder;
+
+use crate::error::{Result, RurlError};
+
+/// Socket settings applied to every connection a client opens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// Local address connections are bound to, `None` to let the OS pick
+    pub local_address: Option<IpAddr>,
+    /// Whether `TCP_NODELAY` is set, sending small writes without waiting
+    /// to coalesce them
+    pub tcp_nodelay: bool,
+    /// Idle time before TCP keepalive probes are sent, `None` to leave the
+    /// OS default
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl Default for SocketOptions {
+    fn default() -> Self {
+        Self { local_address: None, tcp_nodelay: true, tcp_keepalive: None }
+    }
+}
+
+impl SocketOptions {
+    /// Applies the options to a client being built.
+    pub(crate) fn apply(&self, builder: ClientBuilder) -> ClientBuilder {
+        builder
+            .local_address(self.local_address)
+            .tcp_nodelay(self.tcp_nodelay)
+            .tcp_keepalive(self.tcp_keepalive)
+    }
+
+    /// Returns the curl options setting the same socket options.
+    pub fn curl_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(addr) = self.local_address {
+            args.push(format!("--interface {}", addr));
+        }
+        if !self.tcp_nodelay {
+            args.push("--no-tcp-nodelay".to_string());
+        }
+        if let Some(idle) = self.tcp_keepalive {
+            // curl takes whole seconds
+            args.push(format!("--keepalive-time {}", idle.as_secs().max(1)));
+        }
+        args
+    }
+}
+
+/// Returns the local address to bind to for `--interface`: an IP address,
+/// or the first address of the named network interface, IPv4 preferred.
+///
+/// # Errors
+///
+/// Returns [`RurlError::ConfigError`] if there is no such interface or it
+/// has no address.
+pub fn interface_address(interface: &str) -> Result<IpAddr> {
+    if let Ok(addr) = interface.parse::<IpAddr>() {
+        return Ok(addr);
+    }
+    let addrs = interface_addresses(interface);
+    addrs
+        .iter()
+        .find(|addr| addr.is_ipv4())
+        .or_else(|| addrs.first())
+        .copied()
+        .ok_or_else(|| RurlError::ConfigError(format!("network interface '{}' not found or has no address", interface)))
+}
+
+#[cfg(unix)]
+fn interface_addresses(name: &str) -> Vec<IpAddr> {
+    use std::ffi::CStr;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    let mut addrs = Vec::new();
+    let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: `list` is only read on success, and freed once below
+    if unsafe { libc::getifaddrs(&mut list) } != 0 {
+        return addrs;
+    }
+    let mut entry = list;
+    while !entry.is_null() {
+        // SAFETY: `entry` is a node of the list returned by getifaddrs,
+        // which stays valid until freeifaddrs
+        let ifa = unsafe { &*entry };
+        entry = ifa.ifa_next;
+        // SAFETY: `ifa_name` is a NUL-terminated string
+        if ifa.ifa_addr.is_null() || unsafe { CStr::from_ptr(ifa.ifa_name) }.to_bytes() != name.as_bytes() {
+            continue;
+        }
+        // SAFETY: the address family says which sockaddr `ifa_addr` points to
+        match i32::from(unsafe { (*ifa.ifa_addr).sa_family }) {
+            libc::AF_INET => {
+                let addr = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in) };
+                addrs.push(IpAddr::V4(Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr))));
+            }
+            libc::AF_INET6 => {
+                let addr = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in6) };
+                addrs.push(IpAddr::V6(Ipv6Addr::from(addr.sin6_addr.s6_addr)));
+            }
+            _ => {}
+        }
+    }
+    // SAFETY: `list` came from a successful getifaddrs and isn't used after
+    unsafe { libc::freeifaddrs(list) };
+    addrs
+}
+
+#[cfg(not(unix))]
+fn interface_addresses(_name: &str) -> Vec<IpAddr> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interface_address() {
+        assert_eq!(interface_address("10.0.0.7").unwrap(), IpAddr::from([10, 0, 0, 7]));
+        assert!(interface_address("no-such-if0").is_err());
+        #[cfg(target_os = "linux")]
+        assert_eq!(interface_address("lo").unwrap(), IpAddr::from([127, 0, 0, 1]));
+    }
+
+    #[test]
+    fn test_curl_args() {
+        assert!(SocketOptions::default().curl_args().is_empty());
+        let options = SocketOptions {
+            local_address: Some(IpAddr::from([10, 0, 0, 7])),
+            tcp_nodelay: false,
+            tcp_keepalive: Some(Duration::from_secs(30)),
+        };
+        assert_eq!(options.curl_args(), ["--interface 10.0.0.7", "--no-tcp-nodelay", "--keepalive-time 30"]);
+    }
+}
@@ -0,0 +1,204 @@
+//! Connection phase timing: DNS lookup, TCP connect, TLS handshake, time
+//! to first byte and content transfer.
+//!
+//! reqwest doesn't expose its connector, so the connection phases are read
+//! off the events hyper logs while opening a connection:
+//!
+//! - `connecting to ADDR` once the host is resolved
+//! - `connected to ADDR` once the TCP connection is up
+//! - `handshake complete` once TLS and the HTTP handshake are done
+//!
+//! hyper opens the connection from the task sending the request, so each
+//! request collects its own events in a task-local [`Marks`]. A request
+//! sent over a reused connection sees none of them and has no connection
+//! phases. Nothing is recorded until [`enable`] installs the logger.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use super::logger;
+
+/// Whether connection events are being recorded.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+tokio::task_local! {
+    static MARKS: Cell<Marks>;
+}
+
+/// Starts recording connection phases for the rest of the process.
+///
+/// # Errors
+///
+/// Returns an error if a logger other than hurley's is already installed.
+pub fn enable() -> Result<()> {
+    logger::install()?;
+    ENABLED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Returns true if connection phases are being recorded.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// When each connection phase of a request ended.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Marks {
+    resolved: Option<Instant>,
+    connected: Option<Instant>,
+    ready: Option<Instant>,
+}
+
+/// Runs `future`, collecting the connection events logged while it runs.
+pub(crate) async fn record<F: Future>(future: F) -> (F::Output, Marks) {
+    MARKS
+        .scope(Cell::new(Marks::default()), async move {
+            let output = future.await;
+            (output, MARKS.with(Cell::get))
+        })
+        .await
+}
+
+/// Notes a hyper connection event for the request being sent, if any.
+pub(crate) fn observe(message: &str) {
+    let now = Instant::now();
+    let _ = MARKS.try_with(|marks| {
+        let mut m = marks.get();
+        if message.starts_with("connecting to ") {
+            // A connection after a finished one (a redirect, or a retry)
+            // starts over; another address for the same one doesn't
+            if m.connected.is_some() {
+                m = Marks::default();
+            }
+            m.resolved.get_or_insert(now);
+        } else if message.starts_with("connected to ") {
+            m.connected = Some(now);
+        } else if message.starts_with("handshake complete") {
+            m.ready = Some(now);
+        }
+        marks.set(m);
+    });
+}
+
+/// How long each phase of a request took.
+///
+/// With redirects, the connection phases are those of the last connection
+/// opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PhaseTimings {
+    /// Time to resolve the host, `None` if no connection was opened
+    pub dns: Option<Duration>,
+    /// Time to open the TCP connection, `None` if none was opened
+    pub connect: Option<Duration>,
+    /// Time for the TLS handshake, `None` for plain HTTP or if no
+    /// connection was opened
+    pub tls: Option<Duration>,
+    /// Time from the connection being ready to the response headers
+    pub ttfb: Duration,
+    /// Time to read the response body
+    pub transfer: Duration,
+}
+
+impl PhaseTimings {
+    /// Splits a request into phases.
+    ///
+    /// # Arguments
+    ///
+    /// * `marks` - Connection events recorded while sending
+    /// * `sent` - When the request was handed to the client
+    /// * `headers` - When the response headers arrived
+    /// * `end` - When the response body was read
+    /// * `tls` - Whether the connection was over TLS
+    pub(crate) fn new(marks: Marks, sent: Instant, headers: Instant, end: Instant, tls: bool) -> Self {
+        let since = |later: Instant, earlier: Instant| later.saturating_duration_since(earlier);
+        match (marks.resolved, marks.connected, marks.ready) {
+            (Some(resolved), Some(connected), Some(ready)) => Self {
+                dns: Some(since(resolved, sent)),
+                connect: Some(since(connected, resolved)),
+                tls: tls.then(|| since(ready, connected)),
+                ttfb: since(headers, ready),
+                transfer: since(end, headers),
+            },
+            _ => Self {
+                ttfb: since(headers, sent),
+                transfer: since(end, headers),
+                ..Self::default()
+            },
+        }
+    }
+
+    /// Returns true if the request opened a new connection.
+    pub fn new_connection(&self) -> bool {
+        self.connect.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Phase timing relies on the wording of hyper's debug messages, so a
+    /// hyper upgrade that changes them has to fail here rather than
+    /// silently report no phases.
+    #[tokio::test]
+    async fn test_hyper_logs_connection_events() {
+        enable().unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            socket.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").await.unwrap();
+        });
+
+        let client = reqwest::Client::new();
+        let (response, marks) = record(client.get(format!("http://{}/", addr)).send()).await;
+        assert_eq!(response.unwrap().status(), 204);
+        assert!(marks.resolved.is_some(), "hyper no longer logs \"connecting to ADDR\"");
+        assert!(marks.connected.is_some(), "hyper no longer logs \"connected to ADDR\"");
+        assert!(marks.ready.is_some(), "hyper no longer logs \"handshake complete\"");
+    }
+
+    #[tokio::test]
+    async fn test_observe_in_scope() {
+        let (_, marks) = record(async {
+            observe("connecting to 10.0.0.1:443");
+            observe("connect error for 10.0.0.1:443: refused");
+            observe("connecting to 10.0.0.2:443");
+            observe("connected to 10.0.0.2:443");
+            observe("handshake complete, spawning background dispatcher task");
+        })
+        .await;
+        assert!(marks.resolved.is_some() && marks.connected.is_some() && marks.ready.is_some());
+        assert!(marks.resolved <= marks.connected && marks.connected <= marks.ready);
+
+        // Outside a request nothing is recorded, and nothing fails
+        observe("connecting to 10.0.0.1:443");
+        let (_, marks) = record(async {}).await;
+        assert!(marks.resolved.is_none());
+    }
+
+    #[test]
+    fn test_phases() {
+        let sent = Instant::now();
+        let at = |ms| sent + Duration::from_millis(ms);
+        let marks = Marks { resolved: Some(at(5)), connected: Some(at(15)), ready: Some(at(45)) };
+        let phases = PhaseTimings::new(marks, sent, at(100), at(110), true);
+        assert_eq!(phases.dns, Some(Duration::from_millis(5)));
+        assert_eq!(phases.connect, Some(Duration::from_millis(10)));
+        assert_eq!(phases.tls, Some(Duration::from_millis(30)));
+        assert_eq!(phases.ttfb, Duration::from_millis(55));
+        assert_eq!(phases.transfer, Duration::from_millis(10));
+        assert!(PhaseTimings::new(marks, sent, at(100), at(110), false).tls.is_none());
+
+        // A reused connection has no connection phases
+        let phases = PhaseTimings::new(Marks::default(), sent, at(40), at(50), true);
+        assert!(!phases.new_connection() && phases.dns.is_none() && phases.tls.is_none());
+        assert_eq!(phases.ttfb, Duration::from_millis(40));
+    }
+}
@@ -0,0 +1,112 @@
+//! HTTP protocol version selection.
+//!
+//! By default the version is negotiated. A request can instead be pinned
+//! to HTTP/1.0, HTTP/1.1 or HTTP/2; HTTP/2 is then spoken with prior
+//! knowledge, which also works for cleartext (`h2c`) servers.
+
+use reqwest::Version;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Result, RurlError};
+
+/// An HTTP version a request can be pinned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HttpVersion {
+    /// HTTP/1.0
+    Http10,
+    /// HTTP/1.1
+    Http11,
+    /// HTTP/2, with prior knowledge
+    Http2,
+}
+
+impl HttpVersion {
+    /// Returns the version as reqwest represents it.
+    pub fn as_reqwest(self) -> Version {
+        match self {
+            HttpVersion::Http10 => Version::HTTP_10,
+            HttpVersion::Http11 => Version::HTTP_11,
+            HttpVersion::Http2 => Version::HTTP_2,
+        }
+    }
+}
+
+/// Returns a negotiated version as reported, e.g. `HTTP/1.1`.
+pub fn version_name(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_11 => "HTTP/1.1",
+        Version::HTTP_2 => "HTTP/2",
+        Version::HTTP_3 => "HTTP/3",
+        _ => "unknown",
+    }
+}
+
+impl FromStr for HttpVersion {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let version = s.trim();
+        let version = version
+            .strip_prefix("HTTP/")
+            .or_else(|| version.strip_prefix("http/"))
+            .unwrap_or(version);
+        match version {
+            "1.0" => Ok(HttpVersion::Http10),
+            "1.1" => Ok(HttpVersion::Http11),
+            "2" | "2.0" => Ok(HttpVersion::Http2),
+            _ => Err(RurlError::ConfigError(format!(
+                "unsupported HTTP version '{}' (expected 1.0, 1.1 or 2)",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HttpVersion::Http10 => "1.0",
+            HttpVersion::Http11 => "1.1",
+            HttpVersion::Http2 => "2",
+        })
+    }
+}
+
+impl Serialize for HttpVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for HttpVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!("1.1".parse::<HttpVersion>().unwrap(), HttpVersion::Http11);
+        assert_eq!("HTTP/1.0".parse::<HttpVersion>().unwrap(), HttpVersion::Http10);
+        assert_eq!("2.0".parse::<HttpVersion>().unwrap(), HttpVersion::Http2);
+        assert!("3".parse::<HttpVersion>().is_err());
+        assert_eq!(HttpVersion::Http2.to_string(), "2");
+        assert_eq!(version_name(Version::HTTP_11), "HTTP/1.1");
+    }
+
+    #[test]
+    fn test_serde() {
+        let version: HttpVersion = serde_json::from_str("\"1.1\"").unwrap();
+        assert_eq!(version, HttpVersion::Http11);
+        assert_eq!(serde_json::to_string(&HttpVersion::Http2).unwrap(), "\"2\"");
+        assert!(serde_json::from_str::<HttpVersion>("\"1.2\"").is_err());
+    }
+}
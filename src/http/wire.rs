@@ -0,0 +1,209 @@
+//! Wire trace of the bytes exchanged on each connection (`--trace-wire`).
+//!
+//! reqwest logs every read and write on a connection when built with
+//! `connection_verbose`; [`WireTrace`] installs a logger that collects those
+//! records and writes them to a file, one framed block per chunk:
+//!
+//! ```text
+//! => [7f3a91c2] send 78 bytes
+//! | GET /users HTTP/1.1\r\n
+//! | host: api.example.com\r\n
+//! | \r\n
+//! <= [7f3a91c2] recv 120 bytes
+//! | HTTP/1.1 200 OK\r\n
+//! | content-length: 42\r\n
+//! | \r\n
+//! | [42 body bytes omitted]
+//! ```
+//!
+//! Data lines start with `| ` and escape anything that isn't printable
+//! ASCII, so binary payloads can't be confused with the framing. For TLS
+//! connections the bytes are those inside the encrypted stream. Bodies are
+//! left out unless requested, which is a best-effort cut at the end of the
+//! headers of each message.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::error::{Result, RurlError};
+use super::logger;
+
+/// Target of reqwest's per-connection read/write records.
+pub(crate) const VERBOSE_TARGET: &str = "reqwest::connect::verbose";
+
+/// The installed wire trace; checked when building clients.
+static TRACE: OnceLock<WireTrace> = OnceLock::new();
+
+/// Returns true if a wire trace is being recorded.
+pub fn is_enabled() -> bool {
+    TRACE.get().is_some()
+}
+
+/// Process-wide recorder of connection traffic.
+pub struct WireTrace {
+    out: Mutex<BufWriter<File>>,
+    bodies: bool,
+}
+
+impl WireTrace {
+    /// Starts tracing connection traffic to `path` for the rest of the process.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created, a trace is already
+    /// being recorded or another logger is already installed.
+    pub fn install(path: &Path, bodies: bool) -> Result<()> {
+        let trace = WireTrace {
+            out: Mutex::new(BufWriter::new(File::create(path)?)),
+            bodies,
+        };
+        logger::install()?;
+        TRACE
+            .set(trace)
+            .map_err(|_| RurlError::ConfigError("cannot start wire trace: already started".to_string()))
+    }
+
+    /// Formats one read or write record, or `None` for other messages.
+    fn format(&self, message: &str) -> Option<String> {
+        let (id, rest) = message.split_once(' ')?;
+        let (direction, rest) = if let Some(rest) = rest.strip_prefix("read: ") {
+            ("<= ", rest)
+        } else if let Some(rest) = rest.strip_prefix("write (vectored): ") {
+            ("=> ", rest)
+        } else {
+            ("=> ", rest.strip_prefix("write: ")?)
+        };
+        let bytes = unescape(rest);
+        let verb = if direction == "<= " { "recv" } else { "send" };
+
+        let mut block = format!("{}[{}] {} {} bytes\n", direction, id, verb, bytes.len());
+        let shown = if self.bodies { bytes.len() } else { header_len(&bytes, direction == "<= ") };
+        for line in bytes[..shown].split_inclusive(|&b| b == b'\n') {
+            block.push_str("| ");
+            block.push_str(&escape(line));
+            block.push('\n');
+        }
+        if shown < bytes.len() {
+            block.push_str(&format!("| [{} body bytes omitted]\n", bytes.len() - shown));
+        }
+        Some(block)
+    }
+}
+
+/// Writes one of reqwest's connection records to the wire trace.
+pub(crate) fn record(message: &str) {
+    let Some(trace) = TRACE.get() else { return };
+    if let Some(block) = trace.format(message) {
+        let mut out = trace.out.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = out.write_all(block.as_bytes());
+    }
+}
+
+/// Flushes the wire trace, if one is installed.
+pub fn flush() {
+    if let Some(trace) = TRACE.get() {
+        let _ = trace.out.lock().unwrap_or_else(|e| e.into_inner()).flush();
+    }
+}
+
+/// Returns how many leading bytes of a chunk are message headers.
+///
+/// A chunk starting with a request or status line is shown up to the blank
+/// line ending its headers; any other chunk is body data.
+fn header_len(chunk: &[u8], response: bool) -> usize {
+    let first_line = chunk.split(|&b| b == b'\n').next().unwrap_or_default();
+    let starts_message = if response {
+        chunk.starts_with(b"HTTP/")
+    } else {
+        first_line.windows(7).any(|w| w == b" HTTP/1")
+    };
+    if !starts_message {
+        return 0;
+    }
+    chunk
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map_or(chunk.len(), |at| at + 4)
+}
+
+/// Decodes reqwest's `b"..."` byte-string rendering.
+fn unescape(rendered: &str) -> Vec<u8> {
+    // Vectored writes render each buffer as its own `b"..."`
+    let mut bytes = Vec::new();
+    let mut chars = rendered.chars();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (false, '"') => quoted = true,
+            (false, _) => {}
+            (true, '"') => quoted = false,
+            (true, '\\') => match chars.next() {
+                Some('n') => bytes.push(b'\n'),
+                Some('r') => bytes.push(b'\r'),
+                Some('t') => bytes.push(b'\t'),
+                Some('0') => bytes.push(0),
+                Some('x') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    bytes.push(u8::from_str_radix(&hex, 16).unwrap_or(b'?'));
+                }
+                Some(other) => bytes.push(other as u8),
+                None => {}
+            },
+            (true, _) => bytes.push(c as u8),
+        }
+    }
+    bytes
+}
+
+/// Renders bytes as printable ASCII, escaping everything else.
+fn escape(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for &b in bytes {
+        match b {
+            b'\n' => text.push_str("\\n"),
+            b'\r' => text.push_str("\\r"),
+            b'\t' => text.push_str("\\t"),
+            b'\\' => text.push_str("\\\\"),
+            0x20..=0x7e => text.push(b as char),
+            _ => text.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(bodies: bool) -> WireTrace {
+        let path = std::env::temp_dir().join("hurley_test_wire.log");
+        WireTrace {
+            out: Mutex::new(BufWriter::new(File::create(path).unwrap())),
+            bodies,
+        }
+    }
+
+    #[test]
+    fn test_unescape_round_trip() {
+        let rendered = r#"b"GET / HTTP/1.1\r\nx: \"a\\b\"\r\n\x00\xff""#;
+        let bytes = unescape(rendered);
+        assert_eq!(bytes, b"GET / HTTP/1.1\r\nx: \"a\\b\"\r\n\x00\xff");
+        assert_eq!(escape(&bytes[16..]), r#"x: "a\\b"\r\n\x00\xff"#);
+    }
+
+    #[test]
+    fn test_format_omits_bodies() {
+        let message = r#"0000beef read: b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nhello""#;
+        assert_eq!(
+            trace(false).format(message).unwrap(),
+            "<= [0000beef] recv 43 bytes\n| HTTP/1.1 200 OK\\r\\n\n| content-length: 5\\r\\n\n| \\r\\n\n| [5 body bytes omitted]\n"
+        );
+        assert!(trace(true).format(message).unwrap().ends_with("| hello\n"));
+
+        let body_only = r#"0000beef write: b"{\"id\": 1}""#;
+        assert_eq!(trace(false).format(body_only).unwrap(), "=> [0000beef] send 9 bytes\n| [9 body bytes omitted]\n");
+        assert!(trace(false).format("tunneling HTTPS over proxy").is_none());
+    }
+}
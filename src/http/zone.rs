@@ -0,0 +1,190 @@
+//! IPv6 zone identifiers in URLs (`http://[fe80::1%25eth0]:8080/health`).
+//!
+//! Link-local addresses are only meaningful together with the interface
+//! they are reached through, written after a `%` in the address (encoded
+//! as `%25` in URLs, per RFC 6874; a bare `%` is accepted too). The `url`
+//! crate rejects zones, so they are split off before parsing and the
+//! request is routed through a placeholder host name resolved to the
+//! scoped socket address. The `Host` header carries the address without
+//! the zone, which is meaningless to the server.
+
+use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
+
+use crate::error::{Result, RurlError};
+
+/// Returns the byte range of the URL's authority (`user@host:port`).
+fn authority(url: &str) -> (usize, usize) {
+    let start = url.find("://").map_or(0, |i| i + 3);
+    let end = url[start..].find(['/', '?', '#']).map_or(url.len(), |i| start + i);
+    (start, end)
+}
+
+/// Splits the zone off a bracketed IPv6 host.
+///
+/// Returns the URL without the zone and the zone, or `None` if the host
+/// has none.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let (url, zone) = split_zone("http://[fe80::1%25eth0]:8080/").unwrap();
+/// assert_eq!(url, "http://[fe80::1]:8080/");
+/// assert_eq!(zone, "eth0");
+/// ```
+pub fn split_zone(url: &str) -> Option<(String, String)> {
+    let (start, end) = authority(url);
+    let open = start + url[start..end].find('[')?;
+    let close = open + url[open..end].find(']')?;
+    let percent = open + url[open..close].find('%')?;
+    let raw = &url[percent + 1..close];
+    let zone = raw.strip_prefix("25").filter(|z| !z.is_empty()).unwrap_or(raw);
+    if zone.is_empty() {
+        return None;
+    }
+    Some((format!("{}{}", &url[..percent], &url[close..]), zone.to_string()))
+}
+
+/// Returns the URL with any zone removed, for code that only needs its host.
+pub fn strip_zone(url: &str) -> String {
+    split_zone(url).map_or_else(|| url.to_string(), |(url, _)| url)
+}
+
+/// Puts `zone` back into a URL's bracketed IPv6 host, `%25`-encoded.
+pub(crate) fn with_zone(url: &str, zone: &str) -> String {
+    let (start, end) = authority(url);
+    match url[start..end].find(']') {
+        Some(close) => format!("{}%25{}{}", &url[..start + close], zone, &url[start + close..]),
+        None => url.to_string(),
+    }
+}
+
+/// Returns the interface index for a zone: a number, or an interface name.
+///
+/// # Errors
+///
+/// Returns [`RurlError::InvalidUrl`] if there is no such interface.
+pub fn scope_id(zone: &str) -> Result<u32> {
+    if let Ok(index) = zone.parse() {
+        return Ok(index);
+    }
+    interface_index(zone).ok_or_else(|| RurlError::InvalidUrl(format!("unknown network interface '{}' in IPv6 zone", zone)))
+}
+
+#[cfg(unix)]
+fn interface_index(name: &str) -> Option<u32> {
+    let name = std::ffi::CString::new(name).ok()?;
+    // SAFETY: `name` is a valid NUL-terminated string for the duration of the call
+    let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    (index != 0).then_some(index)
+}
+
+#[cfg(not(unix))]
+fn interface_index(_name: &str) -> Option<u32> {
+    None
+}
+
+/// How to send a request whose URL has a zoned IPv6 host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ZoneRoute {
+    /// The URL with the host replaced by a placeholder name
+    pub url: String,
+    /// Placeholder host name, resolved to `addr`
+    pub host: String,
+    /// Scoped address to connect to
+    pub addr: SocketAddr,
+    /// `Host` header value: the address and port, without the zone
+    pub host_header: String,
+}
+
+impl ZoneRoute {
+    /// Returns the route for `url`, or `None` if its host has no zone.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL or zone is invalid.
+    pub(crate) fn for_url(url: &str) -> Result<Option<Self>> {
+        let Some((bare, zone)) = split_zone(url) else {
+            return Ok(None);
+        };
+        let invalid = |reason: &str| RurlError::InvalidUrl(format!("'{}': {}", url, reason));
+        let mut parsed = url::Url::parse(&bare).map_err(|e| invalid(&e.to_string()))?;
+        let Some(url::Host::Ipv6(ip)) = parsed.host() else {
+            return Err(invalid("a zone is only allowed on an IPv6 address"));
+        };
+        let port = parsed.port_or_known_default().unwrap_or(80);
+        let scope = scope_id(&zone)?;
+
+        let host_header = match parsed.port() {
+            Some(port) => format!("[{}]:{}", ip, port),
+            None => format!("[{}]", ip),
+        };
+        let host = placeholder_host(ip, scope);
+        parsed.set_host(Some(&host)).map_err(|e| invalid(&e.to_string()))?;
+        Ok(Some(Self {
+            url: parsed.to_string(),
+            host,
+            addr: SocketAddr::V6(SocketAddrV6::new(ip, port, 0, scope)),
+            host_header,
+        }))
+    }
+}
+
+/// Host name standing in for a zoned address; `.invalid` never resolves.
+fn placeholder_host(ip: Ipv6Addr, scope: u32) -> String {
+    format!("ipv6-{}-s{}.invalid", ip.to_string().replace(':', "-"), scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_zone() {
+        let split = |url| split_zone(url).unwrap();
+        assert_eq!(split("http://[fe80::1%25eth0]:8080/health"), ("http://[fe80::1]:8080/health".to_string(), "eth0".to_string()));
+        assert_eq!(split("http://[fe80::1%eth0]/"), ("http://[fe80::1]/".to_string(), "eth0".to_string()));
+        assert_eq!(split("[fe80::1%253]:8080").1, "3");
+        assert_eq!(split_zone("http://[fe80::1]:8080/a%25b"), None);
+        assert_eq!(split_zone("http://example.com/%5B%25x%5D"), None);
+        assert_eq!(with_zone("http://[fe80::1]:8080/health", "eth0"), "http://[fe80::1%25eth0]:8080/health");
+    }
+
+    #[test]
+    fn test_zone_route() {
+        let route = ZoneRoute::for_url("http://[fe80::1%252]:8080/health?x=1").unwrap().unwrap();
+        assert_eq!(route.url, "http://ipv6-fe80--1-s2.invalid:8080/health?x=1");
+        assert_eq!(route.host, "ipv6-fe80--1-s2.invalid");
+        assert_eq!(route.addr, "[fe80::1%2]:8080".parse().unwrap());
+        assert_eq!(route.host_header, "[fe80::1]:8080");
+
+        assert_eq!(ZoneRoute::for_url("http://[fe80::1]/").unwrap(), None);
+        assert!(ZoneRoute::for_url("http://[fe80::1%25no-such-if0]/").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_request_to_zoned_loopback() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Skip where IPv6 is unavailable
+        let Ok(listener) = tokio::net::TcpListener::bind("[::1]:0").await else { return };
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let host = request.lines().find_map(|l| l.strip_prefix("host: ")).unwrap_or_default().to_string();
+            let response = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}", host.len(), host);
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let url = crate::http::request::normalize_url(&format!("http://[::1%lo]:{}/health", port)).unwrap();
+        assert_eq!(url, format!("http://[::1%25lo]:{}/health", port));
+        let response = crate::http::HttpClient::new(false)
+            .execute(&crate::http::HttpRequest::new(url))
+            .await
+            .unwrap();
+        assert_eq!(response.body, format!("[::1]:{}", port));
+    }
+}
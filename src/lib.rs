@@ -0,0 +1,82 @@
+//! # hurley - HTTP URL Client
+//!
+//! A curl-like HTTP client with performance testing capabilities.
+//!
+//! ## Features
+//!
+//! - **HTTP Methods**: GET, POST, PUT, DELETE, PATCH, HEAD
+//! - **Custom Headers**: `-H "Content-Type: application/json"`
+//! - **Request Body**: Inline (`-d`) or from file (`-f`)
+//! - **Performance Testing**: Concurrent requests with latency metrics
+//!
+//! ## Library Usage
+//!
+//! The HTTP client and performance engine can be embedded in other programs.
+//! Disable default features to avoid pulling in the terminal dependencies
+//! (`clap`, `indicatif`, `colored`) used by the command-line tool:
+//!
+//! ```toml
+//! hurley = { version = "0.1", default-features = false }
+//! ```
+//!
+//! The main types are re-exported at the crate root. Nothing here prints
+//! or exits the process: errors come back as [`RurlError`] and results as
+//! values, so a run can be checked in a test or fed to another program.
+//! Output only happens with `verbose` set, and only with the `cli` feature.
+//!
+//! ```no_run
+//! use hurley::{Dataset, HttpClient, HttpRequest, PerfRunner};
+//!
+//! # async fn example() -> hurley::Result<()> {
+//! let request = HttpRequest::new("https://httpbin.org/get");
+//! let response = HttpClient::new(false).execute(&request).await?;
+//! assert!(response.status.is_success());
+//!
+//! // 100 requests, 10 at a time
+//! let runner = PerfRunner::new(request.url.clone(), request, 10, 100, false);
+//! let metrics = runner.run(&Dataset::simple(100)).await?;
+//! println!("p99: {:.1} ms", metrics.latency_p99_ms);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Usage Examples
+//!
+//! ```bash
+//! # Simple GET request
+//! hurley https://httpbin.org/get
+//!
+//! # POST with JSON body
+//! hurley -X POST https://httpbin.org/post \
+//!   -H "Content-Type: application/json" \
+//!   -d '{"name": "test"}'
+//!
+//! # Performance test: 100 requests, 10 concurrent
+//! hurley https://httpbin.org/get -c 10 -n 100
+//!
+//! # Performance test with dataset
+//! hurley https://httpbin.org --perf data.json -c 20 -n 500
+//! ```
+
+pub mod captures;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod config;
+pub mod duration;
+pub(crate) mod encode;
+pub mod error;
+pub mod filter;
+pub mod http;
+#[cfg(feature = "cli")]
+pub mod man;
+pub mod perf;
+pub mod request_file;
+pub mod schedule;
+pub mod session;
+pub mod template;
+pub mod timing_log;
+pub mod write_out;
+
+pub use error::{Result, RurlError};
+pub use http::{HttpClient, HttpRequest, HttpResponse};
+pub use perf::{Dataset, PerfMetrics, PerfRunner};
@@ -1,69 +1,365 @@
-//! # hurley - HTTP URL Client
-//!
-//! A curl-like HTTP client with performance testing capabilities.
-//!
-//! ## Features
-//!
-//! - **HTTP Methods**: GET, POST, PUT, DELETE, PATCH, HEAD
-//! - **Custom Headers**: `-H "Content-Type: application/json"`
-//! - **Request Body**: Inline (`-d`) or from file (`-f`)
-//! - **Performance Testing**: Concurrent requests with latency metrics
-//!
-//! ## Usage Examples
-//!
-//! ```bash
-//! # Simple GET request
-//! hurley https://httpbin.org/get
-//!
-//! # POST with JSON body
-//! hurley -X POST https://httpbin.org/post \
-//!   -H "Content-Type: application/json" \
-//!   -d '{"name": "test"}'
-//!
-//! # Performance test: 100 requests, 10 concurrent
-//! hurley https://httpbin.org/get -c 10 -n 100
-//!
-//! # Performance test with dataset
-//! hurley https://httpbin.org --perf data.json -c 20 -n 500
-//! ```
-
-pub mod cli;
-pub mod error;
-pub mod http;
-pub mod perf;
-
-use clap::Parser;
-use std::time::Duration;
+//! Command-line entry point for hurley.
+
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use colored::Colorize;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use tokio_util::sync::CancellationToken;
+
+use hurley::captures::CapturedVars;
+use hurley::cli::{Cli, Command};
+use hurley::config::Config;
+use hurley::man;
+use hurley::error::{Result, RurlError};
+use hurley::http::graphql::{inline_or_file, GraphQlQuery};
+use hurley::http::{cache, conditional, glob, multipart, range};
+use hurley::http::resolve::{backend_sets, resolve_entries_from_file};
+use hurley::http::socket::interface_address;
+use hurley::http::{
+    negotiate, send_raw, timing, wire, AcceptValue, AuthChain, ClientIdentity, CookieAudit, DnsResolver, HttpCache, HttpClient, HttpRequest, HttpResponse, RendererRegistry, SocketOptions, WireTrace,
+    DEFAULT_USER_AGENT,
+};
+use hurley::template::{expand_env, Vars};
+use hurley::timing_log::{TimingLog, TimingRecord};
+use hurley::request_file::RequestFile;
+use hurley::session::Session;
+use hurley::perf::{sweep, thresholds};
+use hurley::perf::{
+    run_distributed, serve_job, Dataset, DestructiveLoad, HostAllowlist, KeepAliveBenchmark, LogFormat, OutputTarget, PerfMetrics, PerfRunner,
+    PerfReport, ReportFormat, RequestLog, RollingLatency, RunEvent, RunReport, StatsdSink, SweepPoint, Threshold, ThresholdResult, WorkerJob,
+};
 
-use cli::Cli;
-use error::Result;
-use http::{HttpClient, HttpRequest};
-use perf::{Dataset, PerfRunner, PerfReport};
+/// Number of recent requests the live p99 in the progress bar covers.
+const LIVE_P99_WINDOW: usize = 1000;
 
 #[tokio::main]
 async fn main() {
-    if let Err(e) = run().await {
-        eprintln!("{} {}", "Error:".red().bold(), e);
-        std::process::exit(1);
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let error_format = cli.error_format.clone();
+
+    let result = run(cli, &matches).await;
+    wire::flush();
+    if let Err(e) = result {
+        match error_format.to_lowercase().as_str() {
+            "json" => match serde_json::to_string(&e.report()) {
+                Ok(json) => eprintln!("{}", json),
+                Err(_) => eprintln!("Error: {}", e),
+            },
+            _ => eprintln!("{} {}", "Error:".red().bold(), e),
+        }
+        std::process::exit(e.exit_code());
     }
 }
 
-async fn run() -> Result<()> {
-    let cli = Cli::parse();
+async fn run(mut cli: Cli, matches: &ArgMatches) -> Result<()> {
+    // Later values win: request file vars, then previously captured
+    // values, then --var (the request file's go in front below)
+    if let Some(path) = CapturedVars::default_path() {
+        let mut vars = CapturedVars::load(&path)?.vars(cli.profile.as_deref());
+        vars.append(&mut cli.vars);
+        cli.vars = vars;
+    }
+
+    // `hurley send FILE` takes the request from a template file
+    let mut sweep = None;
+    match cli.command.take() {
+        Some(Command::Send { file }) => {
+            let request_file = RequestFile::from_file(&file)?;
+            cli.apply_request_file(request_file, matches);
+        }
+        Some(Command::Sweep { levels, csv }) => sweep = Some((levels, csv)),
+        Some(Command::Worker { listen }) => return run_worker(&listen).await,
+        Some(Command::Man { dir: Some(dir) }) => {
+            for page in man::generate_to(&dir)? {
+                eprintln!("{}", format!("Wrote {}", page.display()).dimmed());
+            }
+            return Ok(());
+        }
+        Some(Command::Man { dir: None }) => {
+            let mut out = std::io::stdout().lock();
+            man::render(&mut out)?;
+            return Ok(out.flush()?);
+        }
+        None => {}
+    }
+
+    // A --session fills in what the command line leaves open
+    let session = match &cli.session {
+        Some(name) => Some(Session::load(&Session::path(name)?)?),
+        None => None,
+    };
+    if let Some(session) = &session {
+        cli.apply_session(session, matches);
+        if cli.url.is_none() {
+            let name = cli.session.as_deref().unwrap_or_default();
+            return Err(RurlError::ConfigError(format!("session '{}' has no request to replay, give a URL", name)));
+        }
+    }
+
+    // Layer config file defaults under the command-line flags
+    let config = Config::load(cli.config.as_deref())?;
+    let profile = config.profile(cli.profile.as_deref())?;
+    cli.apply_profile(&profile, matches);
+
+    // Connection phases are read from hyper's events through our logger,
+    // so it is only installed when they are reported
+    if cli.verbose || cli.timing_log.is_some() || cli.is_perf_mode() || sweep.is_some() {
+        timing::enable()?;
+    }
+
+    // Sessions keep ${ENV_VAR} references rather than their values; a
+    // `-d @file` body is saved as read
+    let session_input = (
+        cli.headers.clone(),
+        cli.auth.clone(),
+        cli.data.clone().filter(|data| !data.starts_with('@')),
+    );
+
+    if cli.is_batch() {
+        colored::control::set_override(false);
+    }
+
+    if let Some(path) = &cli.trace_wire {
+        WireTrace::install(path, cli.trace_wire_bodies)?;
+    }
+
+    // Expand ${ENV_VAR} references so secrets can come from the environment
+    let url = expand_env(cli.url.as_deref().unwrap_or_default())?;
+    cli.headers = cli.headers.iter().map(|h| expand_env(h)).collect::<Result<_>>()?;
+    cli.auth = cli.auth.iter().map(|a| expand_env(a)).collect::<Result<_>>()?;
+    if let Some(data) = &cli.data {
+        cli.data = Some(expand_env(data)?);
+    }
+
+    // -I is HEAD with only the status and headers shown, which HEAD does anyway
+    if cli.head {
+        cli.method = "HEAD".to_string();
+    }
+
+    // Refuse typos like `-X GTE` unless custom methods are allowed
+    if let Some(warning) = cli.check_method(&cli.method)? {
+        eprintln!("{} {}", "Warning:".yellow().bold(), warning);
+    }
 
     // Build base request from CLI arguments
-    let mut request = HttpRequest::new(&cli.url)
+    let mut request = HttpRequest::new(&url)
         .method(&cli.method)?
         .headers_from_strings(&cli.headers)?
-        .timeout(Duration::from_secs(cli.timeout))
-        .follow_redirects(cli.follow_redirects);
+        .timeout(Duration::from_secs(cli.per_request_timeout()))
+        .connect_timeout(cli.connect_timeout)
+        .read_timeout(cli.read_timeout)
+        .follow_redirects(cli.follow_redirects)
+        .max_redirects(cli.max_redirects())
+        .strict_redirects(cli.strict_redirects)
+        .pool_idle_timeout(cli.pool_idle_timeout.map(Duration::from_secs))
+        .retry_on_connection_reset(cli.retry_on_connection_reset);
+
+    // Collect DNS overrides from the command line and --resolve-file
+    let mut resolve = cli.resolve.clone();
+    if let Some(file) = &cli.resolve_file {
+        resolve.extend(resolve_entries_from_file(file)?);
+    }
+    request = request.resolve(resolve);
+    if let Some(url) = &cli.doh_url {
+        request = request.dns(Some(DnsResolver::doh(url)?));
+    } else if !cli.dns_servers.is_empty() {
+        request = request.dns(Some(DnsResolver::servers(cli.dns_servers.clone())?));
+    }
+    let local_address = match &cli.interface {
+        Some(interface) => Some(interface_address(interface)?),
+        None => cli.local_addr,
+    };
+    request = request.socket_options(SocketOptions {
+        local_address,
+        tcp_nodelay: !cli.no_tcp_nodelay,
+        tcp_keepalive: cli.tcp_keepalive,
+    });
+
+    if let Some(cert) = &cli.cert {
+        let identity = Arc::new(ClientIdentity::load(cert, cli.key.as_deref())?.reload_every(cli.cert_reload));
+        reload_on_hangup(Arc::clone(&identity));
+        request = request.client_identity(Some(identity));
+    }
+
+    if !cli.auth.is_empty() {
+        request = request.auth(Some(Arc::new(AuthChain::from_specs(&cli.auth)?)));
+    }
 
     // Add body from CLI
-    if let Some(data) = &cli.data {
-        request = request.body(data.clone());
+    // `-d @-` and `-f -` read the body from stdin
+    // --get sends the data in the query string instead, added per URL below
+    let mut query = None;
+    if let (Some(data), true) = (&cli.data, cli.get) {
+        query = Some(match data.strip_prefix('@') {
+            Some("-") => std::io::read_to_string(std::io::stdin().lock())?,
+            Some(file) => std::fs::read_to_string(file)?,
+            None => data.clone(),
+        });
+    } else if let Some(data) = &cli.data {
+        match data.strip_prefix('@') {
+            Some("-") => request = request.body_from_reader(std::io::stdin().lock())?,
+            Some(file) => request = request.body_from_file(&PathBuf::from(file))?,
+            None => request = request.body(data.clone()),
+        }
     } else if let Some(file) = &cli.body_file {
-        request = request.body_from_file(file)?;
+        if file.as_os_str() == "-" {
+            request = request.body_from_reader(std::io::stdin().lock())?;
+        } else {
+            request = request.body_from_file(file)?;
+        }
+    }
+
+    // Wrap --gql-query/--gql-variables in a GraphQL envelope
+    if cli.graphql {
+        if matches.value_source("method") == Some(ValueSource::DefaultValue) {
+            request = request.method("POST")?;
+        }
+        let mut query = GraphQlQuery::new(inline_or_file(cli.gql_query.as_deref().unwrap_or_default())?);
+        if let Some(variables) = &cli.gql_variables {
+            query = query.variables_json(&inline_or_file(variables)?)?;
+        }
+        request = request.graphql(&query);
+    }
+
+    if !cli.ranges.is_empty() {
+        request = request.header("Range", range::header_value(&cli.ranges));
+    }
+
+    if cli.compressed {
+        request = request.compressed(true);
+    }
+    request = request.compress_body(cli.compress_body);
+
+    // Identify as hurley (or as -A says) unless -H or --no-user-agent decides
+    if !cli.no_user_agent && !request.has_header("User-Agent") {
+        request = request.header("User-Agent", cli.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+    }
+
+    // Validators for a conditional request, unless given with -H
+    if let Some(path) = &cli.etag_compare {
+        if let (Some(etag), false) = (conditional::load_etag(path)?, request.has_header("If-None-Match")) {
+            request = request.header("If-None-Match", etag);
+        }
+    }
+    if let (Some(since), false) = (&cli.if_modified_since, request.has_header("If-Modified-Since")) {
+        request = request.header("If-Modified-Since", conditional::if_modified_since(since)?);
+    }
+
+    if let Some(dir) = &cli.cache {
+        let dir = match dir {
+            Some(dir) => dir.clone(),
+            None => cache::default_dir()
+                .ok_or_else(|| RurlError::ConfigError("cannot locate cache directory, give one with --cache=DIR".to_string()))?,
+        };
+        request = request.cache(Some(HttpCache::open(dir)?));
+    }
+
+    // --accept presets, unless an Accept header was given explicitly
+    if !cli.accept.is_empty() && !cli.negotiate && !request.has_header("Accept") {
+        let accept: Vec<_> = cli.accept.iter().map(|a| a.value()).collect();
+        request = request.header("Accept", accept.join(", "));
+    }
+
+    // Expand [1-100] and {a,b} globs, unless --globoff
+    let mut urls = vec![url];
+    for url in &cli.more_urls {
+        urls.push(expand_env(url)?);
+    }
+    if !cli.globoff {
+        urls = urls.iter().map(|url| glob::expand(url)).collect::<Result<Vec<_>>>()?.concat();
+    }
+
+    // Fill in {{var}} placeholders; further URLs share everything else
+    let vars: Vars = cli.vars.iter().cloned().collect();
+    let mut requests = Vec::with_capacity(urls.len());
+    for url in urls {
+        let mut more = request.clone();
+        more.url = url;
+        if let Some(query) = &query {
+            more = more.query(query);
+        }
+        let mut more = more.render_vars(&vars)?.normalize_url()?;
+        if let Some(cookie) = session.as_ref().and_then(|session| session.cookie_header(&more.url)) {
+            if !more.has_header("Cookie") {
+                more = more.header("Cookie", cookie);
+            }
+        }
+        requests.push(more);
+    }
+
+    if cli.as_curl {
+        for request in &requests {
+            println!("{}", request.to_curl().await?);
+        }
+        return Ok(());
+    }
+
+    if let Some(name) = cli.save_session.as_ref().or(cli.session.as_ref()) {
+        let mut saved = session.unwrap_or_default();
+        let (headers, auth, body) = &session_input;
+        saved.record(&requests[0], headers, auth, body.as_deref());
+        saved.save(&Session::path(name)?)?;
+    }
+
+    if requests.len() > 1 {
+        let single = [
+            ("--raw-request", cli.raw_request.is_some()),
+            ("--keepalive", cli.keepalive.is_some()),
+            ("--negotiate", cli.negotiate),
+            ("--perf", cli.perf_file.is_some()),
+            ("--openapi", cli.openapi_file.is_some()),
+        ];
+        if let Some((flag, _)) = single.iter().find(|(_, set)| *set) {
+            return Err(RurlError::InvalidUrl(format!(
+                "{} expands to {} URLs, but {} takes one (use -g to turn off globbing)",
+                cli.url.as_deref().unwrap_or_default(),
+                requests.len(),
+                flag
+            )));
+        }
+        if !cli.is_perf_mode() && !cli.plan && sweep.is_none() {
+            return run_multiple_requests(&cli, requests).await;
+        }
+        // A globbed URL drives the performance test like a dataset; only
+        // globs get here, since more URLs conflict with performance flags
+        cli.more_urls = requests.drain(1..).map(|request| request.url).collect();
+    }
+    let request = requests.remove(0);
+
+    // `hurley URL sweep --concurrency 1,2,4` runs the workload per level
+    if let Some((levels, csv)) = sweep {
+        return run_sweep(&cli, request, &levels, csv.as_deref()).await;
+    }
+
+    // Expert mode: send the file's bytes unchanged
+    if let Some(path) = &cli.raw_request {
+        return run_raw_request(&cli, &request, path).await;
+    }
+
+    // Keep-alive benchmark mode
+    if let Some(connections) = cli.keepalive {
+        return run_keepalive_test(&cli, request, connections).await;
+    }
+
+    // Content negotiation: one request per Accept value
+    if cli.negotiate {
+        return run_negotiation(&cli, &request).await;
+    }
+
+    // Dry run: print what the performance test would do
+    if cli.plan {
+        return print_plan(&cli, request);
+    }
+
+    if cli.random_range.is_some() && !cli.is_perf_mode() {
+        return Err(RurlError::PerfError("--random-range needs a performance test (-n, -c or --duration)".to_string()));
     }
 
     // Performance test mode
@@ -79,37 +375,1014 @@ async fn run() -> Result<()> {
 
 async fn run_single_request(cli: &Cli, request: HttpRequest) -> Result<()> {
     let client = HttpClient::new(cli.verbose);
-    let response = client.execute(&request).await?;
-    response.print(cli.include_headers, cli.verbose);
+    let timings = cli.timing_log.as_deref().map(TimingLog::open).transpose()?;
+    let output = cli.output_file.first().cloned();
+    // Saved parts and range checks need the body as received
+    // Output that isn't a terminal gets the body exactly as received
+    let keep_raw = cli.save_parts.is_some() || !cli.ranges.is_empty() || !std::io::stdout().is_terminal();
+    let fetched = fetch(&client, &request, output.as_deref(), timings.as_ref(), cli.is_batch(), keep_raw).await?;
+    show(cli, &request, fetched)
+}
+
+/// Requests several URLs (`hurley URL URL...`) and prints each response
+/// under its URL, in the order given.
+///
+/// With `--parallel` all requests are in flight at once. A failed request
+/// doesn't stop the others; its error is printed in its place.
+async fn run_multiple_requests(cli: &Cli, requests: Vec<HttpRequest>) -> Result<()> {
+    let client = Arc::new(HttpClient::new(cli.verbose));
+    let timings = cli.timing_log.as_deref().map(TimingLog::open).transpose()?.map(Arc::new);
+    let quiet = cli.is_batch() || cli.parallel;
+    let fetch_one = |index: usize| {
+        let (client, timings) = (Arc::clone(&client), timings.clone());
+        let request = requests[index].clone();
+        let output = cli.output_file.get(index).cloned();
+        let keep_raw = !cli.ranges.is_empty();
+        async move { fetch(&client, &request, output.as_deref(), timings.as_deref(), quiet, keep_raw).await }
+    };
+
+    let mut pending: Vec<_> = match cli.parallel {
+        true => (0..requests.len()).map(|index| Some(tokio::spawn(fetch_one(index)))).collect(),
+        false => Vec::new(),
+    };
+    let mut failed = 0;
+    for (index, request) in requests.iter().enumerate() {
+        let result = match pending.get_mut(index).and_then(Option::take) {
+            Some(task) => task.await.map_err(|e| RurlError::PerfError(e.to_string()))?,
+            None => fetch_one(index).await,
+        };
+        println!("{}", format!("==> {} <==", request.url).bold());
+        if let Err(e) = result.and_then(|fetched| show(cli, request, fetched)) {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            failed += 1;
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(RurlError::RequestsFailed(failed, requests.len())),
+    }
+}
+
+/// A response of [`fetch`], ready to be shown.
+enum Fetched {
+    /// Streamed to an output file, with the number of bytes written
+    Saved(HttpResponse, u64, PathBuf),
+    /// Read into memory, with the body as received if `--save-parts` needs it
+    Read(HttpResponse, Option<bytes::Bytes>),
+}
+
+/// Sends one request, to the output file if there is one, and logs its timing.
+///
+/// `quiet` hides the download progress bar; `keep_raw` keeps the body as
+/// received for `--save-parts` and `--range`.
+async fn fetch(
+    client: &HttpClient,
+    request: &HttpRequest,
+    output: Option<&Path>,
+    timings: Option<&TimingLog>,
+    quiet: bool,
+    keep_raw: bool,
+) -> Result<Fetched> {
+    let started = SystemTime::now();
+    if let Some(path) = output {
+        let mut pb: Option<ProgressBar> = None;
+        let result = client
+            .execute_to_file(request, path, |written, total| {
+                pb.get_or_insert_with(|| download_progress_bar(total, quiet))
+                    .set_position(written);
+            })
+            .await;
+        if let Some(pb) = pb {
+            pb.finish_and_clear();
+        }
+        let (response, bytes) = log_timing(timings, request, started, result, |(r, bytes)| (r, *bytes))?;
+        Ok(Fetched::Saved(response, bytes, path.to_path_buf()))
+    } else {
+        // Saving parts needs the body as received, not decoded text
+        let (result, raw) = if keep_raw {
+            match client.execute_raw(request).await {
+                Ok((response, raw)) => (Ok(response), Some(raw)),
+                Err(e) => (Err(e), None),
+            }
+        } else {
+            (client.execute(request).await, None)
+        };
+        let response = log_timing(timings, request, started, result, |r| (r, r.body.len() as u64))?;
+        Ok(Fetched::Read(response, raw))
+    }
+}
+
+/// Prints a fetched response the way the command-line options ask for.
+fn show(cli: &Cli, request: &HttpRequest, fetched: Fetched) -> Result<()> {
+    match fetched {
+        Fetched::Saved(response, bytes, path) => {
+            warn_redirects(&response);
+            warn_range(cli, &response, bytes);
+            save_session_cookies(cli, request, &response)?;
+            save_etag(cli, &response)?;
+            if cli.cookies && !cli.include_headers {
+                response.print_cookies();
+            }
+            response.print_saved(&path, bytes, cli.include_headers, cli.verbose, cli.slow_threshold);
+            if let Some(write_out) = &cli.write_out {
+                print_write_out(&write_out.render(&response, &request.url))?;
+            }
+            check_slow(cli, &response)
+        }
+        Fetched::Read(mut response, raw) => {
+            warn_redirects(&response);
+            warn_range(cli, &response, raw.as_ref().map_or(response.body.len(), |raw| raw.len()) as u64);
+            if let (Some(dir), Some(raw)) = (&cli.save_parts, &raw) {
+                save_parts(&response, raw, dir)?;
+            }
+            save_captures(cli, &response)?;
+            save_session_cookies(cli, request, &response)?;
+            save_etag(cli, &response)?;
+            // Rendered before --filter replaces the body
+            let write_out = cli.write_out.as_ref().map(|w| w.render(&response, &request.url));
+            if let Some(filter) = &cli.filter {
+                response.body = filter.apply(&response.body)?;
+            }
+            if cli.cookies && !cli.include_headers {
+                response.print_cookies();
+            }
+            let registry = RendererRegistry::default();
+            if let Some(renderer) = cli.render.as_deref().and_then(|name| registry.get(name)) {
+                response.print_with(renderer, cli.include_headers, cli.verbose, cli.slow_threshold);
+            } else if cli.graphql && cli.filter.is_none() {
+                response.print_graphql(cli.include_headers, cli.verbose, cli.slow_threshold);
+            } else if let (Some(raw), None) = (&raw, &cli.filter) {
+                response.print_received(raw, cli.include_headers, cli.verbose, cli.slow_threshold);
+            } else {
+                response.print(cli.include_headers, cli.verbose, cli.slow_threshold);
+            }
+            if let Some(write_out) = write_out {
+                print_write_out(&write_out)?;
+            }
+            check_slow(cli, &response)
+        }
+    }
+}
+
+/// Writes the response's ETag to the `--etag-save` file, if any.
+fn save_etag(cli: &Cli, response: &HttpResponse) -> Result<()> {
+    if let Some(path) = &cli.etag_save {
+        if !conditional::save_etag(path, &response.headers)? && response.status != reqwest::StatusCode::NOT_MODIFIED {
+            eprintln!("{} response has no ETag, {} left unchanged", "Warning:".yellow().bold(), path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Writes the parts of a multipart response to `dir` (`--save-parts`).
+fn save_parts(response: &HttpResponse, raw: &[u8], dir: &Path) -> Result<()> {
+    let content_type = response
+        .headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !multipart::is_multipart(content_type) {
+        eprintln!("{} response is not multipart, no parts saved", "Warning:".yellow().bold());
+        return Ok(());
+    }
+    let paths = multipart::save_parts(&multipart::parse(raw, content_type)?, dir)?;
+    eprintln!("{}", format!("Saved {} parts to {}", paths.len(), dir.display()).dimmed());
+    Ok(())
+}
+
+/// Appends the outcome of a single request to the `--timing-log`, if any,
+/// and passes it through.
+///
+/// `response` picks the response and body size out of a successful result.
+fn log_timing<T>(
+    log: Option<&TimingLog>,
+    request: &HttpRequest,
+    started: SystemTime,
+    result: Result<T>,
+    response: impl Fn(&T) -> (&HttpResponse, u64),
+) -> Result<T> {
+    let Some(log) = log else { return result };
+    let method = request.method.as_str();
+    let record = match &result {
+        Ok(sent) => {
+            let (r, bytes) = response(sent);
+            TimingRecord::from_response(method, &request.url, started, r, bytes)
+        }
+        Err(e) => {
+            let elapsed = started.elapsed().unwrap_or_default();
+            TimingRecord::from_error(method, &request.url, started, elapsed, e)
+        }
+    };
+    log.record(&record);
+    log.finish()?;
+    result
+}
+
+/// Sends the `--raw-request` file and prints the raw response to stdout.
+async fn run_raw_request(cli: &Cli, request: &HttpRequest, path: &Path) -> Result<()> {
+    let payload = std::fs::read(path)?;
+    if cli.verbose {
+        eprintln!("{}", format!("> {} raw bytes to {}", payload.len(), request.url).dimmed());
+    }
+    let response = send_raw(&request.url, &payload, &request.resolve, request.timeout, cli.raw_max_bytes).await?;
+    let mut out = std::io::stdout().lock();
+    out.write_all(&response.bytes)?;
+    out.flush()?;
+    if response.truncated {
+        eprintln!(
+            "{} response truncated at {} bytes (--raw-max-bytes)",
+            "Warning:".yellow().bold(),
+            response.bytes.len()
+        );
+    }
+    if cli.verbose {
+        let end = if response.closed { "connection closed" } else { "connection idle" };
+        eprintln!(
+            "{}",
+            format!("< {} bytes in {:.2}ms ({})", response.bytes.len(), response.duration.as_secs_f64() * 1000.0, end).dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Prints the `--write-out` text, which may not end in a newline.
+fn print_write_out(text: &str) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    out.write_all(text.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Stores `--capture` values from the response under the active profile.
+fn save_captures(cli: &Cli, response: &HttpResponse) -> Result<()> {
+    if cli.captures.is_empty() {
+        return Ok(());
+    }
+    let path = CapturedVars::default_path()
+        .ok_or_else(|| RurlError::ConfigError("cannot locate config directory to store captures".to_string()))?;
+    let mut store = CapturedVars::load(&path)?;
+    for capture in &cli.captures {
+        let value = capture.extract(response)?;
+        store.set(cli.profile.as_deref(), &capture.name, value);
+        eprintln!("{}", format!("Captured {}", capture.name).dimmed());
+    }
+    store.save(&path)
+}
+
+/// Stores the cookies the response sets in the `--session` or
+/// `--save-session` session.
+fn save_session_cookies(cli: &Cli, request: &HttpRequest, response: &HttpResponse) -> Result<()> {
+    let Some(name) = cli.save_session.as_ref().or(cli.session.as_ref()) else {
+        return Ok(());
+    };
+    let path = Session::path(name)?;
+    let mut session = Session::load(&path)?;
+    if session.store_cookies(&request.url, CookieAudit::from_headers(&response.headers).cookies) > 0 {
+        session.save(&path)?;
+    }
+    Ok(())
+}
+
+/// Prints a warning for each unsafe redirect that was followed.
+fn warn_redirects(response: &HttpResponse) {
+    for warning in &response.redirect_warnings {
+        eprintln!("{} {}", "Warning:".yellow().bold(), warning);
+    }
+}
+
+/// Prints a warning if the response doesn't answer the `--range` request.
+///
+/// `size` is the number of body bytes received.
+fn warn_range(cli: &Cli, response: &HttpResponse, size: u64) {
+    if cli.ranges.is_empty() {
+        return;
+    }
+    if let Err(problem) = range::check(&cli.ranges, response, size) {
+        eprintln!("{} {}", "Warning:".yellow().bold(), problem);
+    }
+}
+
+/// Fails with a distinct exit code if `--fail-slow` is set and the response was slow.
+fn check_slow(cli: &Cli, response: &HttpResponse) -> Result<()> {
+    match cli.slow_threshold {
+        Some(threshold) if cli.fail_slow && response.is_slow(threshold) => Err(RurlError::SlowResponse(
+            response.duration.as_secs_f64() * 1000.0,
+            threshold.as_secs_f64() * 1000.0,
+        )),
+        _ => Ok(()),
+    }
+}
+
+async fn run_keepalive_test(cli: &Cli, request: HttpRequest, connections: usize) -> Result<()> {
+    println!("{}", "🔌 Starting Keep-Alive Benchmark".cyan().bold());
+    println!("   URL: {}", request.url.yellow());
+    println!("   Connections: {}", connections);
+    println!(
+        "   Idle times: {:.1}s to {:.1}s",
+        cli.probe_interval.as_secs_f64(),
+        cli.probe_interval.as_secs_f64() * connections as f64
+    );
+
+    let report = KeepAliveBenchmark::new(request, connections, cli.probe_interval).run().await?;
+    PerfReport::print_keepalive(&report, cli.output_format().as_str());
+    Ok(())
+}
+
+async fn run_negotiation(cli: &Cli, request: &HttpRequest) -> Result<()> {
+    let accepts = if cli.accept.is_empty() { AcceptValue::presets() } else { cli.accept.clone() };
+    let report = negotiate(&HttpClient::new(cli.verbose), request, &accepts).await?;
+    if cli.output_format() == ReportFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    println!("{}", "🤝 Content Negotiation".cyan().bold());
+    println!("   URL: {}", request.url.yellow());
+    report.print();
     Ok(())
 }
 
 async fn run_perf_test(cli: &Cli, base_request: HttpRequest) -> Result<()> {
+    let outputs = cli.output_targets();
+    if outputs.iter().filter(|target| target.path.is_none()).count() > 1 {
+        return Err(RurlError::PerfError("only one --output can write to stdout".to_string()));
+    }
+    // Several addresses for one host: run the workload against each backend
+    let backends = backend_sets(&base_request.resolve)?;
+    let compare = backends.len() > 1 && cli.workers.is_empty();
+    if compare {
+        refuse_report_files(&outputs, "a backend comparison")?;
+    }
     println!("{}", "🚀 Starting Performance Test".cyan().bold());
-    println!("   URL: {}", cli.url.yellow());
-    println!("   Concurrency: {}", cli.concurrency);
-    println!("   Total Requests: {}", cli.total_requests);
+    println!("   URL: {}", base_request.url.yellow());
+    if !cli.workers.is_empty() {
+        println!("   Workers: {}", cli.workers.join(", "));
+    }
+    match (&cli.stages, cli.rate) {
+        _ if cli.iterations.is_some() => println!("   Virtual Users: {}", cli.concurrency),
+        (Some(stages), _) => println!("   Stages: {}", stages),
+        (None, Some(rate)) => println!("   Rate: {} req/s", rate),
+        (None, None) => println!("   Concurrency: {}", cli.concurrency),
+    }
+    if let Some(length) = cli.random_range {
+        println!("   Random ranges: {} bytes", length);
+    }
+    match cli.warmup {
+        Some(warmup) => println!("   Warmup: {:.1}s", warmup.as_secs_f64()),
+        None if cli.warmup_requests > 0 => println!("   Warmup: {} requests", cli.warmup_requests),
+        None => {}
+    }
+    if let Some(ramp_up) = cli.ramp_up {
+        println!("   Ramp-up: {:.1}s", ramp_up.as_secs_f64());
+    }
+    match (cli.run_length(), cli.iterations) {
+        (_, Some(iterations)) => println!("   Iterations: {} per virtual user", iterations),
+        (Some(duration), None) => println!("   Duration: {:.1}s", duration.as_secs_f64()),
+        (None, None) => println!("   Total Requests: {}", cli.total_requests),
+    }
     println!();
 
-    // Load dataset
-    let dataset = if let Some(file) = &cli.perf_file {
-        println!("   Dataset: {}", file.display().to_string().yellow());
-        Dataset::from_file(file)?
+    let dataset = load_perf_dataset(cli, &base_request)?;
+    confirm_destructive(cli, &base_request, &dataset)?;
+    let cancel = cancel_on_interrupt();
+
+    let mut observers = RunObservers::open(cli)?;
+    // Spool per-request records for JSON reports; removed once written.
+    // The name is unguessable and the file new and private to this user,
+    // as the temporary directory may be shared.
+    let records = cli.report_records.then(|| {
+        let name = format!("hurley-records-{}-{:016x}.ndjson", std::process::id(), rand::random::<u64>());
+        std::env::temp_dir().join(name)
+    });
+    if let Some(path) = &records {
+        observers.logs.push(RequestLog::create_private(path)?);
+    }
+    let observers = Arc::new(observers);
+    // Opened first so a bad output path fails before the wait
+    if !wait_for_start(cli, &cancel).await {
+        if let Some(path) = &records {
+            let _ = std::fs::remove_file(path);
+        }
+        return Ok(());
+    }
+
+    if compare {
+        let mut results = Vec::new();
+        for backend in backends {
+            let label = backend[0].socket_addr().to_string();
+            println!();
+            println!("   Backend: {}", label.yellow());
+            let request = base_request.clone().resolve(backend);
+            let metrics = run_workload(cli, cli.concurrency, request, &dataset, &cancel, observers.clone()).await?;
+            results.push((label, metrics));
+            if cancel.is_cancelled() {
+                break;
+            }
+        }
+        observers.finish()?;
+        // Comparison reports don't embed records
+        if let Some(path) = &records {
+            let _ = std::fs::remove_file(path);
+        }
+        PerfReport::print_comparison(&results, cli.output_format().as_str());
+        let runs = results.iter().map(|(label, metrics)| (label.clone(), metrics));
+        return check_each_thresholds(&cli.thresholds, runs);
+    }
+
+    // Create report files up front so a bad path fails before the run
+    let mut sinks = outputs.iter().map(OutputTarget::open).collect::<Result<Vec<_>>>()?;
+
+    let metrics = if cli.workers.is_empty() {
+        run_workload(cli, cli.concurrency, base_request, &dataset, &cancel, observers.clone()).await?
+    } else {
+        run_distributed_workload(cli, &base_request, &dataset, &cancel).await?
+    };
+    observers.finish()?;
+
+    let results = thresholds::evaluate(&cli.thresholds, &metrics);
+    let report = RunReport { metrics: &metrics, thresholds: &results, records: records.as_deref() };
+    let written = sinks.iter_mut().try_for_each(|sink| sink.write(&report));
+    if let Some(path) = &records {
+        let _ = std::fs::remove_file(path);
+    }
+    written?;
+
+    check_thresholds(&results)
+}
+
+/// Loads the performance dataset and checks it against the host allowlist
+/// and known methods.
+fn load_perf_dataset(cli: &Cli, base_request: &HttpRequest) -> Result<Dataset> {
+    // A --plan prints JSON on stdout, so progress notes go to stderr
+    let note = |line: String| if cli.plan { eprintln!("{}", line) } else { println!("{}", line) };
+    let loaded = if let Some(file) = &cli.perf_file {
+        note(format!("   Dataset: {}", file.display().to_string().yellow()));
+        Some(Dataset::from_file(file)?)
+    } else if let Some(file) = &cli.openapi_file {
+        let dataset = Dataset::from_openapi(file)?;
+        note(format!(
+            "   OpenAPI: {} ({} operations)",
+            file.display().to_string().yellow(),
+            dataset.len()
+        ));
+        Some(dataset)
+    } else if !cli.more_urls.is_empty() {
+        let mut urls = vec![base_request.url.clone()];
+        urls.extend(cli.more_urls.iter().cloned());
+        let pattern = cli.url.as_deref().unwrap_or_default();
+        note(format!("   URLs: {} from {}", urls.len(), pattern.yellow()));
+        Some(Dataset::from_urls(&urls, base_request.method.as_str(), pattern))
+    } else {
+        None
+    };
+
+    let dataset = if let Some(dataset) = loaded {
+        match cli.sample {
+            Some(sample) => {
+                let seed = cli.seed.unwrap_or_else(rand::random);
+                let sampled = dataset.sample(sample, seed);
+                note(format!("   Sample: {} of {} entries (seed {})", sampled.len(), dataset.len(), seed));
+                sampled
+            }
+            None => dataset,
+        }
     } else {
-        Dataset::simple(cli.total_requests)
+        Dataset::simple(if cli.run_length().is_some() || cli.iterations.is_some() { 1 } else { cli.total_requests })
     };
 
-    let runner = PerfRunner::new(
-        cli.url.clone(),
+    if !cli.force {
+        HostAllowlist::new(&cli.allowed_hosts).check(&base_request.url, &dataset)?;
+    }
+
+    let methods: BTreeSet<&str> = dataset.entries.iter().map(|entry| entry.method.as_str()).collect();
+    for method in methods {
+        if let Some(warning) = cli.check_method(method)? {
+            eprintln!("{} {}", "Warning:".yellow().bold(), warning);
+        }
+    }
+
+    Ok(dataset)
+}
+
+/// Returns a token cancelled on Ctrl-C.
+///
+/// The run stops gracefully and reports what was collected; a second
+/// Ctrl-C exits immediately.
+fn cancel_on_interrupt() -> CancellationToken {
+    let cancel = CancellationToken::new();
+    let on_interrupt = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            on_interrupt.cancel();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+        }
+    });
+    cancel
+}
+
+/// Asks before a run sends `DELETE`/`PUT`/`PATCH` at scale to a host that
+/// is not a test environment, unless `--yes` is given.
+///
+/// Without a terminal to ask on, such a run is refused.
+fn confirm_destructive(cli: &Cli, base_request: &HttpRequest, dataset: &Dataset) -> Result<()> {
+    if cli.yes {
+        return Ok(());
+    }
+    let plan = perf_runner(cli, cli.concurrency, base_request.clone())?.plan(dataset)?;
+    let Some(load) = DestructiveLoad::from_plan(&plan, &HostAllowlist::new(&cli.test_hosts))? else {
+        return Ok(());
+    };
+    if cli.batch || !std::io::stdin().is_terminal() {
+        return Err(RurlError::NotConfirmed(load.to_string()));
+    }
+    eprint!("{} This run sends {}. Continue? [y/N] ", "Warning:".yellow().bold(), load);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    match answer.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err(RurlError::NotConfirmed(load.to_string())),
+    }
+}
+
+/// Waits out `--start-at`/`--start-in`, showing the time left.
+///
+/// Returns false if the wait was cancelled with Ctrl+C.
+async fn wait_for_start(cli: &Cli, cancel: &CancellationToken) -> bool {
+    let Some(delay) = cli.start_delay() else {
+        return true;
+    };
+    let start = tokio::time::Instant::now() + delay;
+    let pb = if cli.is_batch() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new_spinner()
+    };
+    pb.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} Waiting to start: {msg} left (Ctrl+C to cancel)")
+            .expect("Invalid progress bar template"),
+    );
+    println!("   Scheduled start in {}", format_countdown(delay).yellow());
+
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                pb.finish_and_clear();
+                eprintln!("{}", "Scheduled run cancelled".yellow());
+                return false;
+            }
+            _ = tokio::time::sleep_until(start) => break,
+            _ = tick.tick() => {
+                pb.set_message(format_countdown(start.saturating_duration_since(tokio::time::Instant::now())));
+                pb.tick();
+            }
+        }
+    }
+    pb.finish_and_clear();
+    true
+}
+
+/// Formats a wait as `HH:MM:SS`.
+fn format_countdown(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Reloads the client certificate whenever the process gets `SIGHUP`.
+#[cfg(unix)]
+fn reload_on_hangup(identity: Arc<ClientIdentity>) {
+    use tokio::signal::unix::{signal, SignalKind};
+    let Ok(mut hangups) = signal(SignalKind::hangup()) else { return };
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match identity.reload() {
+                Ok(()) => eprintln!("{}", "Reloaded client certificate".dimmed()),
+                Err(e) => eprintln!("{} {}", "Warning:".yellow().bold(), e),
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn reload_on_hangup(_identity: Arc<ClientIdentity>) {}
+
+/// Runs the workload once per concurrency level and compares the results.
+async fn run_sweep(cli: &Cli, base_request: HttpRequest, levels: &[usize], csv: Option<&Path>) -> Result<()> {
+    refuse_report_files(&cli.output_targets(), "a sweep")?;
+    println!("{}", "📈 Starting Concurrency Sweep".cyan().bold());
+    println!("   URL: {}", base_request.url.yellow());
+    let list: Vec<String> = levels.iter().map(ToString::to_string).collect();
+    println!("   Concurrency: {}", list.join(", "));
+    match cli.run_length() {
+        Some(duration) => println!("   Duration: {:.1}s per level", duration.as_secs_f64()),
+        None => println!("   Total Requests: {} per level", cli.total_requests),
+    }
+    println!();
+
+    let dataset = load_perf_dataset(cli, &base_request)?;
+    confirm_destructive(cli, &base_request, &dataset)?;
+    let cancel = cancel_on_interrupt();
+
+    // Create the CSV up front so a bad path fails before the run
+    let csv = csv.map(File::create).transpose()?;
+
+    let observers = Arc::new(RunObservers::open(cli)?);
+    if !wait_for_start(cli, &cancel).await {
+        return Ok(());
+    }
+
+    let mut points = Vec::new();
+    for &concurrency in levels {
+        println!();
+        println!("   Concurrency: {}", concurrency.to_string().yellow());
+        let metrics =
+            run_workload(cli, concurrency, base_request.clone(), &dataset, &cancel, observers.clone()).await?;
+        points.push(SweepPoint { concurrency, metrics });
+        if cancel.is_cancelled() {
+            break;
+        }
+    }
+
+    observers.finish()?;
+
+    if let Some(file) = csv {
+        sweep::write_csv(&points, BufWriter::new(file))?;
+    }
+    PerfReport::print_sweep(&points, cli.output_format().as_str());
+    let runs = points.iter().map(|point| (format!("concurrency {}", point.concurrency), &point.metrics));
+    check_each_thresholds(&cli.thresholds, runs)
+}
+
+/// Fails if any `--threshold` limit was not met.
+fn check_thresholds(results: &[ThresholdResult]) -> Result<()> {
+    let failed: Vec<String> = results
+        .iter()
+        .filter(|r| !r.passed)
+        .map(|r| r.threshold.to_string())
+        .collect();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(RurlError::ThresholdsFailed(failed.len(), results.len(), failed.join(", ")))
+    }
+}
+
+/// Fails if `--output FORMAT=FILE` asks for a report file in a mode that
+/// prints a comparison of several runs instead of one run's report, so the
+/// file isn't silently left unwritten.
+fn refuse_report_files(outputs: &[OutputTarget], mode: &str) -> Result<()> {
+    let Some((format, path)) = outputs.iter().find_map(|target| Some((target.format.as_str(), target.path.as_ref()?))) else {
+        return Ok(());
+    };
+    Err(RurlError::PerfError(format!(
+        "--output {}={}: report files are not written for {}; use --output {} to print it",
+        format,
+        path.display(),
+        mode,
+        format
+    )))
+}
+
+/// Fails if any run of a backend comparison or sweep missed a
+/// `--threshold` limit, naming the run each failure belongs to.
+fn check_each_thresholds<'a>(
+    limits: &[Threshold],
+    runs: impl IntoIterator<Item = (String, &'a PerfMetrics)>,
+) -> Result<()> {
+    let results = thresholds::evaluate_each(limits, runs);
+    let failed: Vec<String> = results
+        .iter()
+        .filter(|(_, r)| !r.passed)
+        .map(|(run, r)| format!("{} ({})", r.threshold, run))
+        .collect();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(RurlError::ThresholdsFailed(failed.len(), results.len(), failed.join(", ")))
+    }
+}
+
+/// Per-request consumers of run events: request logs, the timing log and
+/// live StatsD metrics.
+#[derive(Default)]
+struct RunObservers {
+    logs: Vec<RequestLog>,
+    timings: Option<TimingLog>,
+    statsd: Option<StatsdSink>,
+}
+
+impl RunObservers {
+    /// Opens the `--request-log`, `--results-csv`, `--timing-log` and `--statsd` targets.
+    fn open(cli: &Cli) -> Result<Self> {
+        let mut observers = Self::default();
+        if let Some(path) = &cli.request_log {
+            observers.logs.push(RequestLog::create(path)?);
+        }
+        if let Some(path) = &cli.results_csv {
+            observers.logs.push(RequestLog::create_with_format(path, LogFormat::Csv)?);
+        }
+        if let Some(path) = &cli.timing_log {
+            observers.timings = Some(TimingLog::open(path)?);
+        }
+        if let Some(addr) = &cli.statsd {
+            let sink = StatsdSink::connect(addr)?
+                .prefix(cli.statsd_prefix.as_str())
+                .dogstatsd(cli.dogstatsd)
+                .tags(cli.statsd_tags.clone());
+            observers.statsd = Some(sink);
+        }
+        Ok(observers)
+    }
+
+    fn record(&self, event: &RunEvent) {
+        for log in &self.logs {
+            log.record(event);
+        }
+        if let Some(timings) = &self.timings {
+            timings.record_event(event);
+        }
+        if let Some(statsd) = &self.statsd {
+            statsd.record(event);
+        }
+    }
+
+    /// Flushes everything once the last run is done.
+    fn finish(&self) -> Result<()> {
+        for log in &self.logs {
+            log.finish()?;
+        }
+        if let Some(timings) = &self.timings {
+            timings.finish()?;
+        }
+        if let Some(statsd) = &self.statsd {
+            statsd.flush();
+        }
+        Ok(())
+    }
+}
+
+/// Builds the performance runner the command-line options describe.
+fn perf_runner(cli: &Cli, concurrency: usize, base_request: HttpRequest) -> Result<PerfRunner> {
+    Ok(PerfRunner::new(
+        base_request.url.clone(),
         base_request,
-        cli.concurrency,
+        concurrency,
         cli.total_requests,
         cli.verbose,
+    )
+    .run_timeout(cli.run_timeout().map(Duration::from_secs))
+    .duration(cli.duration)
+    .rate(cli.rate)
+    .ramp_up(cli.ramp_up)
+    .stages(cli.stages.clone())
+    .warmup(cli.warmup)
+    .warmup_requests(cli.warmup_requests)
+    .assertions(cli.assertions.clone())
+    .captures(cli.captures.clone())
+    .timeline_window(Some(cli.timeline_window))
+    .slo(cli.slo()?)
+    .hash_responses(cli.hash_responses)
+    .keepalive(!cli.disable_keepalive)
+    .iterations(cli.iterations)
+    .priority(cli.priority.clone())
+    .max_deferred(cli.max_deferred)
+    .exact_samples(cli.exact_samples)
+    .ranges(cli.ranges.clone())
+    .random_range(cli.random_range))
+}
+
+/// Prints the `--plan` of a performance run as JSON, without sending anything.
+fn print_plan(cli: &Cli, base_request: HttpRequest) -> Result<()> {
+    let dataset = load_perf_dataset(cli, &base_request)?;
+    let plan = perf_runner(cli, cli.concurrency, base_request)?.plan(&dataset)?;
+    println!("{}", serde_json::to_string_pretty(&plan)?);
+    Ok(())
+}
+
+/// Runs one performance workload with a progress bar and returns its metrics.
+async fn run_workload(
+    cli: &Cli,
+    concurrency: usize,
+    base_request: HttpRequest,
+    dataset: &Dataset,
+    cancel: &CancellationToken,
+    observers: Arc<RunObservers>,
+) -> Result<PerfMetrics> {
+    // Create progress bar, tracking seconds for a time-based run
+    let total = cli.iterations.map_or(cli.total_requests, |iterations| iterations * concurrency * dataset.len());
+    let (length, unit) = match cli.run_length() {
+        Some(duration) => (duration.as_secs().max(1), "s"),
+        None => (total as u64, ""),
+    };
+    let pb = ProgressBar::new(length);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(&format!(
+                "{{spinner:.green}} [{{elapsed_precise}}] [{{bar:40.cyan/blue}}] {{pos}}/{{len}}{} {{msg}}",
+                unit
+            ))
+            .expect("Invalid progress bar template")
+            .progress_chars("#>-")
+    );
+    if cli.is_batch() {
+        pb.set_draw_target(ProgressDrawTarget::hidden());
+    }
+
+    // Live RPS, error count and p99 over the most recent requests
+    let progress = pb.clone();
+    let rolling = Mutex::new(RollingLatency::new(LIVE_P99_WINDOW));
+    let runner = perf_runner(cli, concurrency, base_request)?.cancellation_token(cancel.clone());
+
+    if cli.warmup.is_some() || cli.warmup_requests > 0 {
+        pb.set_message("warming up");
+    }
+
+    // One canary request first, so a broken setup fails fast
+    if !cli.no_preflight {
+        runner.preflight(dataset).await?;
+    }
+
+    let by_time = cli.run_length().is_some();
+
+    let metrics = runner
+        .run_with_events(dataset, move |event| {
+            match &event {
+                RunEvent::RequestDone { completed, duration, .. } => {
+                    if !by_time {
+                        progress.set_position(*completed as u64);
+                    }
+                    rolling.lock().unwrap_or_else(|e| e.into_inner()).record(*duration);
+                }
+                RunEvent::IntervalStats(stats) => {
+                    if by_time {
+                        progress.set_position(stats.elapsed.as_secs());
+                    }
+                    let p99 = rolling.lock().unwrap_or_else(|e| e.into_inner()).percentile(99.0);
+                    progress.set_message(live_stats_message(
+                        stats.interval_requests_per_second,
+                        stats.metrics.failed_requests,
+                        p99,
+                    ));
+                }
+                RunEvent::WarmupFinished => {
+                    progress.reset_elapsed();
+                    progress.set_message("");
+                }
+                RunEvent::Finished(_) => {}
+            }
+            observers.record(&event);
+        })
+        .await?;
+    if cancel.is_cancelled() {
+        pb.abandon_with_message("Interrupted");
+        eprintln!("{}", "Interrupted, reporting partial results".yellow());
+    } else if !by_time && metrics.total_requests < total {
+        pb.abandon_with_message("Timed out");
+        eprintln!("{}", "Run timeout reached, reporting partial results".yellow());
+    } else {
+        pb.finish_with_message("Done!");
+    }
+
+    Ok(metrics)
+}
+
+/// Runs the workload split across `--workers` and returns the merged metrics.
+async fn run_distributed_workload(
+    cli: &Cli,
+    base_request: &HttpRequest,
+    dataset: &Dataset,
+    cancel: &CancellationToken,
+) -> Result<PerfMetrics> {
+    let slo = cli.slo()?;
+    let job = WorkerJob::new(base_request, dataset, cli.concurrency, cli.total_requests)
+        .duration(cli.duration)
+        .run_timeout(cli.run_timeout().map(Duration::from_secs))
+        .rate(cli.rate)
+        .warmup(cli.warmup)
+        .warmup_requests(cli.warmup_requests)
+        .assertions(&cli.assertions)
+        .timeline_window(Some(cli.timeline_window))
+        .slo(slo.as_ref())
+        .hash_responses(cli.hash_responses)
+        .keepalive(!cli.disable_keepalive)
+        .preflight(!cli.no_preflight);
+
+    let by_time = cli.run_length().is_some();
+    let pb = ProgressBar::new(match cli.run_length() {
+        Some(duration) => duration.as_secs().max(1),
+        None => cli.total_requests as u64,
+    });
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(&format!(
+                "{{spinner:.green}} [{{elapsed_precise}}] [{{bar:40.cyan/blue}}] {{pos}}/{{len}}{} {{msg}}",
+                if by_time { "s" } else { "" }
+            ))
+            .expect("Invalid progress bar template")
+            .progress_chars("#>-")
     );
+    if cli.is_batch() {
+        pb.set_draw_target(ProgressDrawTarget::hidden());
+    }
 
-    let metrics = runner.run(&dataset).await?;
-    
-    PerfReport::print(&metrics, &cli.output_format);
+    let progress = pb.clone();
+    let mut collector = run_distributed(&cli.workers, &job, cancel, move |update| {
+        if by_time {
+            progress.set_position(progress.elapsed().as_secs());
+        } else {
+            progress.set_position(update.completed as u64);
+        }
+        progress.set_message(live_stats_message(update.requests_per_second, update.failed_requests, None));
+    })
+    .await
+    .inspect_err(|_| pb.abandon())?;
+    collector.set_slo(slo);
+    let metrics = collector.compute_metrics();
 
-    Ok(())
+    if cancel.is_cancelled() {
+        pb.abandon_with_message("Interrupted");
+        eprintln!("{}", "Interrupted, reporting partial results".yellow());
+    } else {
+        pb.finish_with_message("Done!");
+    }
+    Ok(metrics)
+}
+
+/// Serves controllers of distributed runs, one run at a time, until Ctrl-C.
+async fn run_worker(listen: &str) -> Result<()> {
+    timing::enable()?;
+    let listener = tokio::net::TcpListener::bind(listen).await?;
+    println!("{}", "🛰  Worker ready".cyan().bold());
+    println!("   Listening on {}", listener.local_addr()?.to_string().yellow());
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        };
+        let served = serve_job(stream, |job| {
+            let size = match job.total_requests() {
+                Some(total) => format!("{} requests", total),
+                None => "time-based".to_string(),
+            };
+            println!("   Job from {}: {} ({})", peer, job.url().yellow(), size);
+        })
+        .await;
+        match served {
+            Ok(metrics) => println!(
+                "   Done: {} requests, {} failed, {:.1} req/s",
+                metrics.total_requests, metrics.failed_requests, metrics.requests_per_second
+            ),
+            Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+        }
+    }
+}
+
+/// Formats the live stats shown next to the performance test progress bar.
+fn live_stats_message(rps: f64, errors: usize, p99: Option<Duration>) -> String {
+    let errors = if errors > 0 {
+        format!("{} errors", errors).red().to_string()
+    } else {
+        "0 errors".to_string()
+    };
+    let p99 = match p99 {
+        Some(p99) => format!("p99 {:.1} ms", p99.as_secs_f64() * 1000.0),
+        None => "p99 -".to_string(),
+    };
+    format!("{:.1} req/s | {} | {}", rps, errors, p99)
+}
+
+/// Creates the progress bar shown while downloading a response body.
+///
+/// Shows a bar with ETA when the total size is known from `Content-Length`,
+/// and a spinner with the byte count otherwise. Hidden in batch mode.
+fn download_progress_bar(total: Option<u64>, batch: bool) -> ProgressBar {
+    if batch {
+        return ProgressBar::hidden();
+    }
+
+    match total {
+        Some(len) => {
+            let pb = ProgressBar::new(len);
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, ETA {eta})")
+                    .expect("Invalid progress bar template")
+                    .progress_chars("#>-")
+            );
+            pb
+        }
+        None => {
+            let pb = ProgressBar::new_spinner();
+            pb.set_style(
+                ProgressStyle::default_spinner()
+                    .template("{spinner:.green} [{elapsed_precise}] {bytes} ({bytes_per_sec})")
+                    .expect("Invalid progress bar template")
+            );
+            pb
+        }
+    }
 }
@@ -0,0 +1,214 @@
+//! Man page generation (`hurley man`).
+//!
+//! Renders the command-line interface as roff man pages for packaging:
+//! `hurley.1` with every option and notes on dataset files, config files
+//! and exit codes, plus a page per subcommand (`hurley-sweep.1`, ...).
+
+use clap::CommandFactory;
+use clap_mangen::roff::{bold, italic, roman, Roff};
+use clap_mangen::Man;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::cli::Cli;
+use crate::error::Result;
+
+/// Man page section of hurley's pages (user commands).
+pub const SECTION: &str = "1";
+
+/// Example dataset shown in the DATASET FORMAT section.
+const DATASET_EXAMPLE: &str = r#"{
+  "hosts": {"api.example.com": "10.0.0.5"},
+  "defaults": {"method": "POST", "headers": {"Content-Type": "application/json"}, "timeout_ms": 5000},
+  "requests": [
+    {"path": "/orders", "body": {"sku": "A-1"}, "tag": "checkout"},
+    {"method": "GET", "path": "/orders/1", "http_version": "2"}
+  ]
+}"#;
+
+/// Writes the `hurley.1` page.
+pub fn render(out: &mut dyn Write) -> Result<()> {
+    let cmd = Cli::command();
+    let source = source(&cmd);
+    render_page(cmd, &source, true, out)
+}
+
+/// Writes `hurley.1` and a page per subcommand into `dir`, creating it if
+/// needed, and returns the files written.
+///
+/// # Errors
+///
+/// Returns an error if a page can't be written.
+pub fn generate_to(dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut cmd = Cli::command();
+    // Building names the subcommands after their parent, `hurley-sweep`
+    cmd.build();
+    let subcommands: Vec<_> = cmd
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+        .cloned()
+        .collect();
+    let source = source(&cmd);
+
+    let mut written = Vec::with_capacity(subcommands.len() + 1);
+    let pages = std::iter::once((cmd, true)).chain(subcommands.into_iter().map(|sub| (sub, false)));
+    for (cmd, main) in pages {
+        let path = dir.join(page(cmd.clone(), &source).get_filename());
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        render_page(cmd, &source, main, &mut file)?;
+        file.flush()?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Returns the source line of every page, `hurley VERSION`.
+fn source(cmd: &clap::Command) -> String {
+    format!("{} {}", cmd.get_name(), cmd.get_version().unwrap_or_default())
+}
+
+fn page(cmd: clap::Command, source: &str) -> Man {
+    Man::new(cmd).section(SECTION).source(source).manual("hurley manual")
+}
+
+/// Renders one page; the main page also gets the sections about files and
+/// formats that aren't tied to an option.
+fn render_page(cmd: clap::Command, source: &str, main: bool, out: &mut dyn Write) -> Result<()> {
+    let man = page(cmd.clone(), source);
+    man.render_title(out)?;
+    man.render_name_section(out)?;
+    man.render_synopsis_section(out)?;
+    man.render_description_section(out)?;
+    if cmd.get_arguments().any(|arg| !arg.is_hide_set()) {
+        man.render_options_section(out)?;
+    }
+    if cmd.has_subcommands() {
+        man.render_subcommands_section(out)?;
+    }
+    if main {
+        notes().to_writer(out)?;
+        man.render_version_section(out)?;
+        man.render_authors_section(out)?;
+    }
+    Ok(())
+}
+
+/// Sections of the main page beyond the options.
+fn notes() -> Roff {
+    let mut roff = Roff::new();
+
+    roff.control("SH", ["DATASET FORMAT"]);
+    roff.text([
+        roman("A performance test dataset ("),
+        bold("--perf"),
+        roman(" FILE) is a JSON array of request entries, a single entry, newline-delimited JSON with one entry per line, or an object with a "),
+        bold("requests"),
+        roman(" array and settings for all of them:"),
+    ]);
+    roff.control("IP", ["hosts", "4"]);
+    roff.text([roman("Host names resolved to fixed addresses for every entry.")]);
+    roff.control("IP", ["defaults", "4"]);
+    roff.text([
+        roman("The "),
+        italic("method"),
+        roman(", "),
+        italic("headers"),
+        roman(", "),
+        italic("timeout_ms"),
+        roman(" and "),
+        italic("http_version"),
+        roman(" of entries that don't set them."),
+    ]);
+    roff.control("PP", []);
+    roff.text([roman("Each entry may set:")]);
+    for (field, description) in [
+        ("method", "HTTP method, GET by default."),
+        ("path", "Path appended to the URL."),
+        ("body", "JSON request body."),
+        ("headers", "Object of extra request headers."),
+        ("graphql", "GraphQL operation (query, variables, operationName) sent as a POST body."),
+        ("tag", "Name the entry's metrics are reported and thresholds scoped under, instead of METHOD path."),
+        ("timeout_ms", "Request timeout in milliseconds, overriding --timeout."),
+        ("http_version", "HTTP version to send with: \"1.0\", \"1.1\" or \"2\"."),
+    ] {
+        roff.control("IP", [field, "4"]);
+        roff.text([roman(description)]);
+    }
+    roff.control("PP", []);
+    roff.text([roman("Strings in entries may use {{var}} placeholders. ${ENV} references are expanded in paths, header values, bodies and GraphQL variables. For example:")]);
+    roff.control("PP", []);
+    roff.control("nf", []);
+    roff.text([roman(DATASET_EXAMPLE)]);
+    roff.control("fi", []);
+
+    roff.control("SH", ["FILES"]);
+    for (file, description) in [
+        ("$XDG_CONFIG_HOME/hurley/config.toml", "Defaults and named profiles (--config, --profile)."),
+        ("$XDG_CONFIG_HOME/hurley/captured.json", "Values stored by --capture, per profile."),
+        ("$XDG_CONFIG_HOME/hurley/sessions/NAME.json", "Sessions of --session and --save-session."),
+        ("$XDG_CACHE_HOME/hurley", "Responses stored by --cache."),
+    ] {
+        roff.control("TP", []);
+        roff.text([italic(file)]);
+        roff.text([roman(description)]);
+    }
+
+    roff.control("SH", ["ENVIRONMENT"]);
+    for (variable, description) in [
+        ("XDG_CONFIG_HOME", "Base of the config directory, ~/.config if unset."),
+        ("XDG_CACHE_HOME", "Base of the cache directory, ~/.cache if unset."),
+        ("HOME", "Used when the XDG variables are unset."),
+    ] {
+        roff.control("TP", []);
+        roff.text([bold(variable)]);
+        roff.text([roman(description)]);
+    }
+
+    roff.control("SH", ["EXIT STATUS"]);
+    for (code, description) in [
+        ("0", "Success."),
+        ("1", "The request or test failed, or the options were invalid."),
+        ("2", "The command line couldn't be parsed."),
+        ("3", "A response was slower than --fail-slow allows, or a performance threshold failed."),
+    ] {
+        roff.control("TP", []);
+        roff.text([bold(code)]);
+        roff.text([roman(description)]);
+    }
+    roff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_main_page() {
+        let mut out = Vec::new();
+        render(&mut out).unwrap();
+        let page = String::from_utf8(out).unwrap();
+        assert!(page.starts_with(".ie \\n(.g .ds Aq \\(aq"));
+        assert!(page.contains(".TH hurley 1"));
+        for section in ["SYNOPSIS", "OPTIONS", "SUBCOMMANDS", "DATASET FORMAT", "FILES", "EXIT STATUS"] {
+            assert!(page.contains(&format!(".SH {}", section)) || page.contains(&format!(".SH \"{}\"", section)), "{}", section);
+        }
+        // Performance options are documented along with the others
+        assert!(page.contains("\\fB\\-c\\fR, \\fB\\-\\-concurrency\\fR"));
+        assert!(page.contains("timeout_ms"));
+    }
+
+    #[test]
+    fn test_generate_pages() {
+        let dir = std::env::temp_dir().join(format!("hurley-man-{}", std::process::id()));
+        let pages = generate_to(&dir).unwrap();
+        let names: Vec<_> = pages.iter().filter_map(|p| p.file_name()?.to_str()).collect();
+        assert!(names.contains(&"hurley.1"));
+        assert!(names.contains(&"hurley-sweep.1"));
+        let sweep = std::fs::read_to_string(dir.join("hurley-sweep.1")).unwrap();
+        assert!(sweep.contains(".TH hurley-sweep 1"));
+        assert!(!names.contains(&"hurley-help.1"));
+        assert!(!sweep.contains("DATASET FORMAT"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
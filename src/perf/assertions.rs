@@ -0,0 +1,230 @@
+//! Response assertions checked on every request of a performance run.
+//!
+//! An assertion checks the status, the body, or a JSONPath in the body:
+//!
+//! ```text
+//! status == 200
+//! status < 400
+//! body contains "order_id"
+//! $.items[0].id exists
+//! $.status == "ok"
+//! $.count != 0
+//! ```
+//!
+//! Assertions don't change whether a request counts as failed; their pass
+//! and fail counts are reported separately, with a few example failures.
+
+use serde_json::Value;
+use std::str::FromStr;
+
+use crate::error::{Result, RurlError};
+use crate::filter::JsonPath;
+use crate::http::HttpResponse;
+
+/// Comparison operator in a status assertion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    /// Splits `s` at the first operator, longest operators first.
+    fn split(s: &str) -> Option<(&str, Op, &str)> {
+        let ops = [("==", Op::Eq), ("!=", Op::Ne), ("<=", Op::Le), (">=", Op::Ge), ("<", Op::Lt), (">", Op::Gt)];
+        ops.iter()
+            .filter_map(|(token, op)| s.find(token).map(|at| (at, token.len(), *op)))
+            .min_by_key(|(at, len, _)| (*at, usize::MAX - len))
+            .map(|(at, len, op)| (&s[..at], op, &s[at + len..]))
+    }
+
+    fn compare(self, actual: u16, expected: u16) -> bool {
+        match self {
+            Op::Eq => actual == expected,
+            Op::Ne => actual != expected,
+            Op::Lt => actual < expected,
+            Op::Le => actual <= expected,
+            Op::Gt => actual > expected,
+            Op::Ge => actual >= expected,
+        }
+    }
+}
+
+/// What an assertion checks.
+#[derive(Debug, Clone, PartialEq)]
+enum Check {
+    Status(Op, u16),
+    BodyContains(String),
+    JsonExists(JsonPath),
+    JsonEquals { path: JsonPath, expected: Value, negate: bool },
+}
+
+/// A single response assertion, e.g. `status == 200`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assertion {
+    expr: String,
+    check: Check,
+}
+
+impl Assertion {
+    /// Returns true if checking the assertion needs the response body.
+    pub fn needs_body(&self) -> bool {
+        !matches!(self.check, Check::Status(..))
+    }
+
+    /// Checks a response.
+    ///
+    /// Returns `None` if the assertion holds, or why it doesn't.
+    pub fn check(&self, response: &HttpResponse) -> Option<String> {
+        match &self.check {
+            Check::Status(op, expected) => {
+                let status = response.status.as_u16();
+                (!op.compare(status, *expected)).then(|| format!("status was {}", status))
+            }
+            Check::BodyContains(text) => {
+                (!response.body.contains(text.as_str())).then(|| format!("body did not contain {:?}", text))
+            }
+            Check::JsonExists(path) => match serde_json::from_str::<Value>(&response.body) {
+                Ok(json) if path.select(&json).is_empty() => Some(format!("'{}' not found", path)),
+                Ok(_) => None,
+                Err(_) => Some("body is not JSON".to_string()),
+            },
+            Check::JsonEquals { path, expected, negate } => {
+                let Ok(json) = serde_json::from_str::<Value>(&response.body) else {
+                    return Some("body is not JSON".to_string());
+                };
+                let matches = path.select(&json);
+                let Some(first) = matches.first() else {
+                    return Some(format!("'{}' not found", path));
+                };
+                let equal = matches.contains(&expected);
+                (equal == *negate).then(|| format!("'{}' was {}", path, first))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Assertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+impl FromStr for Assertion {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            RurlError::PerfError(format!("invalid assertion '{}': {} (expected e.g. 'status == 200')", s, reason))
+        };
+        let expr = s.trim();
+
+        let check = if let Some(rest) = expr.strip_prefix("status") {
+            let (left, op, right) = Op::split(rest).ok_or_else(|| invalid("missing comparison"))?;
+            if !left.trim().is_empty() {
+                return Err(invalid("unexpected text before the comparison"));
+            }
+            let status = right.trim().parse().map_err(|_| invalid("status must be a number"))?;
+            Check::Status(op, status)
+        } else if let Some(rest) = expr.strip_prefix("body contains") {
+            let text = rest.trim();
+            let text = text
+                .strip_prefix('"')
+                .and_then(|t| t.strip_suffix('"'))
+                .unwrap_or(text);
+            if text.is_empty() {
+                return Err(invalid("missing text to look for"));
+            }
+            Check::BodyContains(text.to_string())
+        } else if expr.starts_with('$') || expr.starts_with('.') {
+            if let Some(path) = expr.strip_suffix(" exists") {
+                Check::JsonExists(path.trim().parse().map_err(|e: RurlError| invalid(&e.to_string()))?)
+            } else {
+                let (path, negate, value) = if let Some((path, value)) = expr.split_once("==") {
+                    (path, false, value)
+                } else if let Some((path, value)) = expr.split_once("!=") {
+                    (path, true, value)
+                } else {
+                    return Err(invalid("expected 'exists', '==' or '!=' after the path"));
+                };
+                let value = value.trim();
+                Check::JsonEquals {
+                    path: path.trim().parse().map_err(|e: RurlError| invalid(&e.to_string()))?,
+                    // Bare words compare as strings, so `$.status == ok` works
+                    expected: serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string())),
+                    negate,
+                }
+            }
+        } else {
+            return Err(invalid("must start with 'status', 'body contains' or a JSONPath"));
+        };
+
+        Ok(Self { expr: expr.to_string(), check })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderMap;
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    fn response(status: u16, body: &str) -> HttpResponse {
+        HttpResponse::new(
+            StatusCode::from_u16(status).unwrap(),
+            HeaderMap::new(),
+            body.to_string(),
+            Duration::ZERO,
+        )
+    }
+
+    fn check(assertion: &str, status: u16, body: &str) -> Option<String> {
+        assertion.parse::<Assertion>().unwrap().check(&response(status, body))
+    }
+
+    #[test]
+    fn test_status() {
+        assert_eq!(check("status == 200", 200, ""), None);
+        assert_eq!(check("status == 200", 500, "").as_deref(), Some("status was 500"));
+        assert_eq!(check("status<400", 302, ""), None);
+        assert!(check("status >= 400", 302, "").is_some());
+        assert_eq!(check("status != 429", 200, ""), None);
+        assert!(!"status == 200".parse::<Assertion>().unwrap().needs_body());
+        assert!("$.id exists".parse::<Assertion>().unwrap().needs_body());
+    }
+
+    #[test]
+    fn test_body_contains() {
+        assert_eq!(check(r#"body contains "order_id""#, 200, r#"{"order_id": 1}"#), None);
+        assert_eq!(
+            check("body contains ok", 200, "error").as_deref(),
+            Some(r#"body did not contain "ok""#)
+        );
+    }
+
+    #[test]
+    fn test_json_path() {
+        let body = r#"{"status": "ok", "items": [{"id": 1}], "count": 0}"#;
+        assert_eq!(check("$.items[0].id exists", 200, body), None);
+        assert_eq!(check("$.missing exists", 200, body).as_deref(), Some("'$.missing' not found"));
+        assert_eq!(check(r#"$.status == "ok""#, 200, body), None);
+        assert_eq!(check("$.status == ok", 200, body), None);
+        assert_eq!(check("$.count != 0", 200, body).as_deref(), Some("'$.count' was 0"));
+        assert_eq!(check("$.items[*].id == 1", 200, body), None);
+        assert_eq!(check("$.status == ok", 200, "<html>").as_deref(), Some("body is not JSON"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("latency < 1s".parse::<Assertion>().is_err());
+        assert!("status 200".parse::<Assertion>().is_err());
+        assert!("status == abc".parse::<Assertion>().is_err());
+        assert!("$.ok".parse::<Assertion>().is_err());
+        assert_eq!("  status == 200 ".parse::<Assertion>().unwrap().to_string(), "status == 200");
+    }
+}
@@ -4,18 +4,36 @@
 //! - JSON array: `[{"method": "GET"}, {"method": "POST", "body": {...}}]`
 //! - Single object: `{"method": "GET", "path": "/api"}`
 //! - Newline-delimited JSON (NDJSON)
+//! - Object with dataset-level settings: `{"hosts": {...}, "requests": [...]}`
+//!
+//! In the object form, a `defaults` block sets the `method`, `headers`,
+//! `timeout_ms` and `http_version` of every entry that doesn't set them
+//! itself:
+//!
+//! ```json
+//! {
+//!   "defaults": {"method": "POST", "headers": {"Content-Type": "application/json"}, "timeout_ms": 5000},
+//!   "requests": [{"path": "/orders"}, {"method": "GET", "path": "/orders/1"}]
+//! }
+//! ```
 
-use serde::Deserialize;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use crate::error::{Result, RurlError};
+use crate::http::{GraphQlQuery, HttpVersion};
+use crate::template;
 
 /// A single entry in a performance test dataset.
 ///
 /// Each entry defines an HTTP request with optional method, path, body, and headers.
 /// Fields default to sensible values if not specified.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatasetEntry {
     /// HTTP method (defaults to "GET")
     #[serde(default = "default_method")]
@@ -32,6 +50,31 @@ pub struct DatasetEntry {
     /// Additional headers for this request
     #[serde(default)]
     pub headers: Option<HashMap<String, String>>,
+
+    /// Name to group this entry's metrics under instead of "METHOD path"
+    ///
+    /// Entries sharing a tag are reported together, and thresholds can be
+    /// scoped to it (e.g. `checkout p99 < 400ms`).
+    #[serde(default)]
+    pub tag: Option<String>,
+
+    /// GraphQL operation sent as the body instead of `body`
+    ///
+    /// Entries with a GraphQL block are sent as `POST` unless another
+    /// method than the default `GET` is given.
+    #[serde(default)]
+    pub graphql: Option<GraphQlQuery>,
+
+    /// Request timeout in milliseconds, overriding `--timeout`
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+
+    /// HTTP version to use (`"1.0"`, `"1.1"` or `"2"`) instead of negotiating
+    ///
+    /// Lets one run mix protocols; the versions responses arrived over are
+    /// counted in the report.
+    #[serde(default)]
+    pub http_version: Option<HttpVersion>,
 }
 
 fn default_method() -> String {
@@ -43,6 +86,51 @@ impl DatasetEntry {
     pub fn get_body_string(&self) -> Option<String> {
         self.body.as_ref().map(|v| v.to_string())
     }
+
+    /// Returns the body to send with `content_type`, if present.
+    ///
+    /// Bodies are sent as JSON, except that a string body is sent as is
+    /// when the content type is given and isn't JSON, such as a form or an
+    /// XML document.
+    pub fn body_for(&self, content_type: Option<&str>) -> Option<String> {
+        match &self.body {
+            Some(serde_json::Value::String(text)) if content_type.is_some_and(|ct| !ct.contains("json")) => {
+                Some(text.clone())
+            }
+            _ => self.get_body_string(),
+        }
+    }
+
+    /// Returns the label metrics are grouped under: the tag if set,
+    /// otherwise the method and path (e.g. "GET /api/v1/users").
+    pub fn label(&self) -> String {
+        match &self.tag {
+            Some(tag) => tag.clone(),
+            None => format!("{} {}", self.method, self.path.as_deref().unwrap_or("/")),
+        }
+    }
+}
+
+/// Expands `${NAME}` references in every string of a JSON value.
+fn expand_json<F>(value: &mut serde_json::Value, lookup: F) -> Result<()>
+where
+    F: Fn(&str) -> Option<String> + Copy,
+{
+    match value {
+        serde_json::Value::String(s) => *s = template::expand_with(s, lookup)?,
+        serde_json::Value::Array(items) => {
+            for item in items {
+                expand_json(item, lookup)?;
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for item in fields.values_mut() {
+                expand_json(item, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
 }
 
 /// A collection of dataset entries for performance testing.
@@ -57,11 +145,69 @@ impl DatasetEntry {
 pub struct Dataset {
     /// List of request entries
     pub entries: Vec<DatasetEntry>,
+    /// Host names resolved to fixed addresses for every entry
+    pub hosts: HashMap<String, IpAddr>,
+}
+
+/// Dataset file with settings that apply to all entries.
+#[derive(Deserialize)]
+struct DatasetFile {
+    #[serde(default)]
+    hosts: HashMap<String, IpAddr>,
+    #[serde(default)]
+    defaults: EntryDefaults,
+    requests: Vec<serde_json::Value>,
+}
+
+/// Entry settings given once in a dataset file's `defaults` block.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EntryDefaults {
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    #[serde(default)]
+    http_version: Option<HttpVersion>,
+}
+
+impl EntryDefaults {
+    /// Fills in whatever the raw entry doesn't set itself.
+    ///
+    /// Headers are merged, with the entry's own winning regardless of case.
+    fn apply(&self, entry: &mut serde_json::Value) {
+        let Some(entry) = entry.as_object_mut() else { return };
+        if let Some(method) = &self.method {
+            entry.entry("method").or_insert_with(|| method.clone().into());
+        }
+        if let Some(timeout) = self.timeout_ms {
+            entry.entry("timeout_ms").or_insert_with(|| timeout.into());
+        }
+        if let Some(version) = self.http_version {
+            entry.entry("http_version").or_insert_with(|| version.to_string().into());
+        }
+        if !self.headers.is_empty() {
+            let headers = entry.entry("headers").or_insert_with(|| serde_json::json!({}));
+            if let Some(headers) = headers.as_object_mut() {
+                for (key, value) in &self.headers {
+                    if !headers.keys().any(|own| own.eq_ignore_ascii_case(key)) {
+                        headers.insert(key.clone(), value.clone().into());
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl Dataset {
     /// Loads a dataset from a JSON file.
     ///
+    /// `${ENV_VAR}` references are expanded after parsing, in the path,
+    /// header values, body and GraphQL variables of each entry, so values
+    /// are inserted as they are, quotes and all.
+    ///
     /// # Arguments
     ///
     /// * `path` - Path to the JSON file
@@ -71,7 +217,39 @@ impl Dataset {
     /// Returns an error if the file cannot be read or parsed.
     pub fn from_file(path: &PathBuf) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
-        Self::from_json(&content)
+        let mut dataset = Self::from_json(&content)?;
+        dataset.expand_with(|name| std::env::var(name).ok())?;
+        Ok(dataset)
+    }
+
+    /// Expands `${NAME}` references in the string fields of every entry,
+    /// looking names up with `lookup`.
+    fn expand_with<F>(&mut self, lookup: F) -> Result<()>
+    where
+        F: Fn(&str) -> Option<String> + Copy,
+    {
+        for entry in &mut self.entries {
+            if let Some(path) = &mut entry.path {
+                *path = template::expand_with(path, lookup)?;
+            }
+            for value in entry.headers.iter_mut().flat_map(HashMap::values_mut) {
+                *value = template::expand_with(value, lookup)?;
+            }
+            if let Some(body) = &mut entry.body {
+                expand_json(body, lookup)?;
+            }
+            if let Some(variables) = entry.graphql.as_mut().and_then(|graphql| graphql.variables.as_mut()) {
+                expand_json(variables, lookup)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates a dataset from an OpenAPI 3 spec (JSON or YAML).
+    ///
+    /// See [`openapi`](super::openapi) for how requests are synthesized.
+    pub fn from_openapi(path: &Path) -> Result<Self> {
+        super::openapi::dataset_from_file(path)
     }
 
     /// Parses a dataset from a JSON string.
@@ -80,15 +258,38 @@ impl Dataset {
     /// - JSON array: `[{...}, {...}]`
     /// - Single object: `{...}`
     /// - Newline-delimited JSON
+    /// - Object with a `requests` array and dataset-level settings such as
+    ///   a `hosts` map overriding DNS resolution for every entry, and a
+    ///   `defaults` block for entry fields
     pub fn from_json(content: &str) -> Result<Self> {
         // Try parsing as array first
         if let Ok(entries) = serde_json::from_str::<Vec<DatasetEntry>>(content) {
-            return Ok(Self { entries });
+            return Self::new(entries).non_empty();
+        }
+
+        // Try parsing as an object with dataset-level settings
+        let value = serde_json::from_str::<serde_json::Value>(content).ok();
+        if let Some(value) = value.filter(|v| v.get("requests").is_some()) {
+            let file: DatasetFile = serde_json::from_value(value)
+                .map_err(|e| RurlError::DatasetError(format!("Invalid dataset: {}", e)))?;
+            let hosts = file.hosts
+                .into_iter()
+                .map(|(host, addr)| (host.to_ascii_lowercase(), addr))
+                .collect();
+            let entries = file.requests
+                .into_iter()
+                .map(|mut entry| {
+                    file.defaults.apply(&mut entry);
+                    serde_json::from_value(entry)
+                })
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|e| RurlError::DatasetError(format!("Invalid dataset entry: {}", e)))?;
+            return Self { entries, hosts }.non_empty();
         }
 
         // Try parsing as single object
         if let Ok(entry) = serde_json::from_str::<DatasetEntry>(content) {
-            return Ok(Self { entries: vec![entry] });
+            return Ok(Self::new(vec![entry]));
         }
 
         // Try parsing as newline-delimited JSON (NDJSON)
@@ -103,11 +304,23 @@ impl Dataset {
             entries.push(entry);
         }
 
-        if entries.is_empty() {
-            return Err(RurlError::DatasetError("Empty dataset".to_string()));
+        Self::new(entries).non_empty()
+    }
+
+    /// Fails for a dataset without entries, which would run no requests.
+    fn non_empty(self) -> Result<Self> {
+        match self.entries.is_empty() {
+            true => Err(RurlError::DatasetError("Empty dataset".to_string())),
+            false => Ok(self),
         }
+    }
 
-        Ok(Self { entries })
+    /// Creates a dataset from entries, without dataset-level settings.
+    pub fn new(entries: Vec<DatasetEntry>) -> Self {
+        Self {
+            entries,
+            hosts: HashMap::new(),
+        }
     }
 
     /// Creates a simple dataset with GET requests (no path override).
@@ -124,9 +337,40 @@ impl Dataset {
                 path: None,
                 body: None,
                 headers: None,
+                tag: None,
+                graphql: None,
+                timeout_ms: None,
+                http_version: None,
             })
             .collect();
-        Self { entries }
+        Self::new(entries)
+    }
+
+    /// Creates a dataset with one entry per URL, all sent with `method`.
+    ///
+    /// Used for the URLs a glob like `https://host/item/[1-100]` expands
+    /// to; entries are tagged with the glob so they are reported together.
+    ///
+    /// # Arguments
+    ///
+    /// * `urls` - Absolute URLs, requested in this order
+    /// * `method` - HTTP method of every entry
+    /// * `tag` - Label to report the entries under
+    pub fn from_urls(urls: &[String], method: &str, tag: &str) -> Self {
+        let entries = urls
+            .iter()
+            .map(|url| DatasetEntry {
+                method: method.to_string(),
+                path: Some(url.clone()),
+                body: None,
+                headers: None,
+                tag: Some(tag.to_string()),
+                graphql: None,
+                timeout_ms: None,
+                http_version: None,
+            })
+            .collect();
+        Self::new(entries)
     }
 
     /// Returns the number of entries in the dataset.
@@ -138,6 +382,72 @@ impl Dataset {
     pub fn is_empty(&self) -> bool {
         self.entries.is_empty()
     }
+
+    /// Returns a random subset of the dataset.
+    ///
+    /// Entries are picked without replacement and keep their original order.
+    /// The same `seed` always yields the same subset.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample` - How many entries to keep
+    /// * `seed` - Seed for the random number generator
+    pub fn sample(&self, sample: Sample, seed: u64) -> Self {
+        let amount = sample.count_of(self.len());
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut indices = rand::seq::index::sample(&mut rng, self.len(), amount).into_vec();
+        indices.sort_unstable();
+
+        let entries = indices.into_iter().map(|i| self.entries[i].clone()).collect();
+        Self {
+            entries,
+            hosts: self.hosts.clone(),
+        }
+    }
+}
+
+/// Size of a dataset sample, as an absolute count or a percentage.
+///
+/// Parses from strings like `"1000"` or `"10%"`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sample {
+    /// Keep this many entries
+    Count(usize),
+    /// Keep this percentage of entries (0-100)
+    Percent(f64),
+}
+
+impl Sample {
+    /// Returns the number of entries to keep out of `total`.
+    ///
+    /// Never exceeds `total`; a non-zero percentage keeps at least one entry.
+    pub fn count_of(&self, total: usize) -> usize {
+        match *self {
+            Sample::Count(n) => n.min(total),
+            Sample::Percent(p) => {
+                let n = (total as f64 * p / 100.0).round() as usize;
+                if p > 0.0 { n.clamp(1, total) } else { 0 }
+            }
+        }
+    }
+}
+
+impl FromStr for Sample {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || RurlError::DatasetError(format!("Invalid sample size: {}", s));
+        let s = s.trim();
+        if let Some(percent) = s.strip_suffix('%') {
+            let p: f64 = percent.trim().parse().map_err(|_| invalid())?;
+            if !(0.0..=100.0).contains(&p) {
+                return Err(invalid());
+            }
+            Ok(Sample::Percent(p))
+        } else {
+            s.parse().map(Sample::Count).map_err(|_| invalid())
+        }
+    }
 }
 
 #[cfg(test)]
@@ -170,6 +480,74 @@ mod tests {
         assert_eq!(dataset.len(), 2);
     }
 
+    #[test]
+    fn test_parse_graphql_entry() {
+        let json = r#"{"path": "/graphql", "graphql": {"query": "{ me { id } }", "variables": {"x": 1}}}"#;
+        let dataset = Dataset::from_json(json).unwrap();
+        let graphql = dataset.entries[0].graphql.as_ref().unwrap();
+        assert_eq!(graphql.query, "{ me { id } }");
+        assert_eq!(graphql.variables, Some(serde_json::json!({"x": 1})));
+    }
+
+    #[test]
+    fn test_entry_label() {
+        let dataset = Dataset::from_json(
+            r#"[{"method": "POST", "path": "/cart/pay", "tag": "checkout"}, {"path": "/search"}]"#,
+        )
+        .unwrap();
+        assert_eq!(dataset.entries[0].label(), "checkout");
+        assert_eq!(dataset.entries[1].label(), "GET /search");
+    }
+
+    #[test]
+    fn test_parse_hosts_block() {
+        let json = r#"{"hosts": {"API.internal": "10.1.2.3"}, "requests": [{"path": "/a"}, {"path": "/b"}]}"#;
+        let dataset = Dataset::from_json(json).unwrap();
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.hosts.get("api.internal"), Some(&"10.1.2.3".parse().unwrap()));
+        assert_eq!(dataset.sample(Sample::Count(1), 1).hosts.len(), 1);
+
+        assert!(Dataset::from_json(r#"{"hosts": {"a": "not-an-ip"}, "requests": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_empty_requests_error() {
+        for json in [r#"{"requests": []}"#, r#"{"hosts": {"a": "10.0.0.1"}, "requests": []}"#, "[]"] {
+            let error = Dataset::from_json(json).unwrap_err();
+            assert!(matches!(&error, RurlError::DatasetError(message) if message == "Empty dataset"), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_parse_defaults_block() {
+        let json = r#"{
+            "defaults": {"method": "POST", "headers": {"Content-Type": "application/json", "X-Env": "test"}, "timeout_ms": 5000, "http_version": "2"},
+            "requests": [
+                {"path": "/orders"},
+                {"method": "GET", "path": "/orders/1", "headers": {"x-env": "prod"}, "timeout_ms": 100, "http_version": "1.1"}
+            ]
+        }"#;
+        let dataset = Dataset::from_json(json).unwrap();
+
+        let first = &dataset.entries[0];
+        assert_eq!(first.method, "POST");
+        assert_eq!(first.timeout_ms, Some(5000));
+        assert_eq!(first.http_version, Some(HttpVersion::Http2));
+        assert_eq!(first.headers.as_ref().unwrap().len(), 2);
+
+        let second = &dataset.entries[1];
+        assert_eq!(second.method, "GET");
+        assert_eq!(second.timeout_ms, Some(100));
+        assert_eq!(second.http_version, Some(HttpVersion::Http11));
+        let headers = second.headers.as_ref().unwrap();
+        assert_eq!(headers.get("x-env").map(String::as_str), Some("prod"));
+        assert_eq!(headers.get("Content-Type").map(String::as_str), Some("application/json"));
+        assert!(!headers.contains_key("X-Env"));
+
+        let err = Dataset::from_json(r#"{"defaults": {"tiemout_ms": 1}, "requests": [{}]}"#).unwrap_err();
+        assert!(err.to_string().contains("tiemout_ms"));
+    }
+
     #[test]
     fn test_default_method() {
         let json = r#"[{}]"#;
@@ -184,6 +562,16 @@ mod tests {
         assert!(!dataset.is_empty());
     }
 
+    #[test]
+    fn test_dataset_from_urls() {
+        let urls = ["http://h/item/1".to_string(), "http://h/item/2".to_string()];
+        let dataset = Dataset::from_urls(&urls, "HEAD", "http://h/item/[1-2]");
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.entries[1].method, "HEAD");
+        assert_eq!(dataset.entries[1].path.as_deref(), Some("http://h/item/2"));
+        assert_eq!(dataset.entries[0].label(), "http://h/item/[1-2]");
+    }
+
     #[test]
     fn test_body_with_json() {
         let json = r#"[{"method": "POST", "body": {"key": "value"}}]"#;
@@ -192,9 +580,84 @@ mod tests {
         assert!(body.contains("key"));
     }
 
+    #[test]
+    fn test_expand_env_after_parsing() {
+        let json = r#"{"requests": [{
+            "path": "/users/${ID}",
+            "headers": {"Authorization": "Bearer ${TOKEN}"},
+            "body": {"name": "${NAME}", "tags": ["${ID}"]},
+            "tag": "${NOT_EXPANDED}"
+        }]}"#;
+        let mut dataset = Dataset::from_json(json).unwrap();
+        let lookup = |name: &str| match name {
+            "ID" => Some("7".to_string()),
+            "TOKEN" => Some(r#"a"b\c"#.to_string()),
+            "NAME" => Some(r#"x", "admin": true, "y": ""#.to_string()),
+            _ => None,
+        };
+        dataset.expand_with(lookup).unwrap();
+
+        let entry = &dataset.entries[0];
+        assert_eq!(entry.path.as_deref(), Some("/users/7"));
+        let headers = entry.headers.as_ref().unwrap();
+        assert_eq!(headers["Authorization"], r#"Bearer a"b\c"#);
+        // A quote in a value stays inside the string it was expanded into
+        let body = entry.body.as_ref().unwrap();
+        assert_eq!(body["name"], r#"x", "admin": true, "y": ""#);
+        assert!(body.get("admin").is_none());
+        assert_eq!(body["tags"][0], "7");
+        // Fields that aren't expanded may hold a literal reference
+        assert_eq!(entry.tag.as_deref(), Some("${NOT_EXPANDED}"));
+    }
+
+    #[test]
+    fn test_body_for_content_type() {
+        let entry: DatasetEntry = serde_json::from_str(r#"{"body": "a=1&b=two"}"#).unwrap();
+        assert_eq!(entry.body_for(Some("application/x-www-form-urlencoded")).as_deref(), Some("a=1&b=two"));
+        assert_eq!(entry.body_for(Some("application/json")).as_deref(), Some(r#""a=1&b=two""#));
+        assert_eq!(entry.body_for(None).as_deref(), Some(r#""a=1&b=two""#));
+
+        let entry: DatasetEntry = serde_json::from_str(r#"{"body": {"a": 1}}"#).unwrap();
+        assert_eq!(entry.body_for(Some("text/plain")).as_deref(), Some(r#"{"a":1}"#));
+    }
+
     #[test]
     fn test_empty_dataset_error() {
         let result = Dataset::from_json("");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_sample() {
+        assert_eq!("1000".parse::<Sample>().unwrap(), Sample::Count(1000));
+        assert_eq!("10%".parse::<Sample>().unwrap(), Sample::Percent(10.0));
+        assert!("150%".parse::<Sample>().is_err());
+        assert!("ten".parse::<Sample>().is_err());
+    }
+
+    #[test]
+    fn test_sample_count_of() {
+        assert_eq!(Sample::Count(5).count_of(3), 3);
+        assert_eq!(Sample::Percent(10.0).count_of(200), 20);
+        assert_eq!(Sample::Percent(0.1).count_of(10), 1);
+    }
+
+    #[test]
+    fn test_sample_is_seeded_and_ordered() {
+        let json = (0..100)
+            .map(|i| format!(r#"{{"path": "/{}"}}"#, i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let dataset = Dataset::from_json(&json).unwrap();
+
+        let a = dataset.sample(Sample::Percent(10.0), 42);
+        let b = dataset.sample(Sample::Percent(10.0), 42);
+        assert_eq!(a.len(), 10);
+
+        let paths = |d: &Dataset| d.entries.iter().map(|e| e.path.clone().unwrap()).collect::<Vec<_>>();
+        assert_eq!(paths(&a), paths(&b));
+
+        let numbers: Vec<usize> = paths(&a).iter().map(|p| p[1..].parse().unwrap()).collect();
+        assert!(numbers.windows(2).all(|w| w[0] < w[1]));
+    }
 }
@@ -0,0 +1,639 @@
+//! Distributed load generation across several machines.
+//!
+//! A worker (`hurley worker --listen 0.0.0.0:7878`) waits for a controller
+//! to connect. The controller (`hurley URL ... --workers host1:7878,host2:7878`)
+//! splits the run between its workers, sending each a [`WorkerJob`] with the
+//! dataset and its share of the requests, concurrency and rate. Workers
+//! stream [`WorkerProgress`] back while running and finish with their
+//! serialized [`MetricsCollector`], which the controller merges into one
+//! report.
+//!
+//! The protocol is newline-delimited JSON over plain TCP, with no
+//! authentication: anyone who can reach a worker can make it send load.
+//! Only listen on a trusted network.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{Result, RurlError};
+use crate::http::{HttpRequest, HttpVersion, ResolveEntry, SocketOptions};
+use super::assertions::Assertion;
+use super::dataset::{Dataset, DatasetEntry};
+use super::events::RunEvent;
+use super::metrics::{MetricsCollector, PerfMetrics};
+use super::runner::PerfRunner;
+use super::slo::Slo;
+
+/// Version of the controller/worker protocol; both sides must match.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// How often workers report progress to the controller.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One worker's share of a distributed run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerJob {
+    url: String,
+    method: String,
+    headers: HashMap<String, String>,
+    body: Option<String>,
+    timeout_ms: u64,
+    connect_timeout_ms: Option<u64>,
+    read_timeout_ms: Option<u64>,
+    follow_redirects: bool,
+    max_redirects: Option<usize>,
+    strict_redirects: bool,
+    pool_idle_timeout_ms: Option<u64>,
+    retry_on_connection_reset: bool,
+    resolve: Vec<String>,
+    http_version: Option<HttpVersion>,
+    compressed: bool,
+    compress_body: bool,
+    tcp_nodelay: bool,
+    tcp_keepalive_ms: Option<u64>,
+    entries: Vec<DatasetEntry>,
+    hosts: HashMap<String, IpAddr>,
+    concurrency: usize,
+    total_requests: usize,
+    duration_ms: Option<u64>,
+    run_timeout_ms: Option<u64>,
+    rate: Option<f64>,
+    warmup_ms: Option<u64>,
+    warmup_requests: usize,
+    assertions: Vec<String>,
+    timeline_window_ms: Option<u64>,
+    slo: Option<(String, u64)>,
+    hash_responses: bool,
+    keepalive: bool,
+    preflight: bool,
+}
+
+impl WorkerJob {
+    /// Creates a job sending `request` with the entries of `dataset`.
+    ///
+    /// A client certificate on the request is not sent along; workers
+    /// would need their own copy of the files. Neither is the local
+    /// address connections are bound to, which belongs to this machine.
+    pub fn new(request: &HttpRequest, dataset: &Dataset, concurrency: usize, total_requests: usize) -> Self {
+        Self {
+            url: request.url.clone(),
+            method: request.method.to_string(),
+            headers: request.headers.clone(),
+            body: request.body.clone(),
+            timeout_ms: request.timeout.as_millis() as u64,
+            connect_timeout_ms: request.connect_timeout.map(|t| t.as_millis() as u64),
+            read_timeout_ms: request.read_timeout.map(|t| t.as_millis() as u64),
+            follow_redirects: request.follow_redirects,
+            max_redirects: request.max_redirects,
+            strict_redirects: request.strict_redirects,
+            pool_idle_timeout_ms: request.pool_idle_timeout.map(|t| t.as_millis() as u64),
+            retry_on_connection_reset: request.retry_on_connection_reset,
+            resolve: request.resolve.iter().map(ToString::to_string).collect(),
+            http_version: request.http_version,
+            compressed: request.compressed,
+            compress_body: request.compress_body,
+            tcp_nodelay: request.socket.tcp_nodelay,
+            tcp_keepalive_ms: request.socket.tcp_keepalive.map(|t| t.as_millis() as u64),
+            entries: dataset.entries.clone(),
+            hosts: dataset.hosts.clone(),
+            concurrency,
+            total_requests,
+            duration_ms: None,
+            run_timeout_ms: None,
+            rate: None,
+            warmup_ms: None,
+            warmup_requests: 0,
+            assertions: Vec::new(),
+            timeline_window_ms: Some(1000),
+            slo: None,
+            hash_responses: false,
+            keepalive: true,
+            preflight: false,
+        }
+    }
+
+    /// Runs for a fixed wall-clock duration; see [`PerfRunner::duration`].
+    pub fn duration(mut self, duration: Option<Duration>) -> Self {
+        self.duration_ms = duration.map(|d| d.as_millis() as u64);
+        self
+    }
+
+    /// Bounds the duration of the whole run; see [`PerfRunner::run_timeout`].
+    pub fn run_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.run_timeout_ms = timeout.map(|t| t.as_millis() as u64);
+        self
+    }
+
+    /// Starts requests at a fixed total rate; see [`PerfRunner::rate`].
+    pub fn rate(mut self, rate: Option<f64>) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Sets a warmup phase; see [`PerfRunner::warmup`].
+    pub fn warmup(mut self, warmup: Option<Duration>) -> Self {
+        self.warmup_ms = warmup.map(|w| w.as_millis() as u64);
+        self
+    }
+
+    /// Sets a number of warmup requests; see [`PerfRunner::warmup_requests`].
+    pub fn warmup_requests(mut self, requests: usize) -> Self {
+        self.warmup_requests = requests;
+        self
+    }
+
+    /// Checks every response; see [`PerfRunner::assertions`].
+    pub fn assertions(mut self, assertions: &[Assertion]) -> Self {
+        self.assertions = assertions.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Sets the timeline window; see [`PerfRunner::timeline_window`].
+    pub fn timeline_window(mut self, window: Option<Duration>) -> Self {
+        self.timeline_window_ms = window.map(|w| w.as_millis() as u64);
+        self
+    }
+
+    /// Records the windows `slo` is checked in.
+    ///
+    /// The SLO itself is evaluated by the controller on the merged windows.
+    pub fn slo(mut self, slo: Option<&Slo>) -> Self {
+        self.slo = slo.map(|slo| (slo.objective().to_string(), slo.window().as_millis() as u64));
+        self
+    }
+
+    /// Counts distinct responses; see [`PerfRunner::hash_responses`].
+    pub fn hash_responses(mut self, enabled: bool) -> Self {
+        self.hash_responses = enabled;
+        self
+    }
+
+    /// Reuses connections between requests; see [`PerfRunner::keepalive`].
+    pub fn keepalive(mut self, enabled: bool) -> Self {
+        self.keepalive = enabled;
+        self
+    }
+
+    /// Sends one canary request from each worker before the run.
+    pub fn preflight(mut self, enabled: bool) -> Self {
+        self.preflight = enabled;
+        self
+    }
+
+    /// Returns the target URL.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the number of requests, or `None` for a time-based run.
+    pub fn total_requests(&self) -> Option<usize> {
+        self.duration_ms.is_none().then_some(self.total_requests)
+    }
+
+    /// Splits the job into `workers` shares.
+    ///
+    /// Requests and concurrency are divided as evenly as possible, with
+    /// every worker keeping at least one connection; the rate is divided
+    /// exactly.
+    pub fn split(&self, workers: usize) -> Vec<WorkerJob> {
+        let workers = workers.max(1);
+        let share = |total: usize, index: usize| total / workers + usize::from(index < total % workers);
+        (0..workers)
+            .map(|index| {
+                let mut job = self.clone();
+                job.total_requests = share(self.total_requests, index);
+                job.concurrency = share(self.concurrency, index).max(1);
+                job.warmup_requests = share(self.warmup_requests, index);
+                job.rate = self.rate.map(|rate| rate / workers as f64);
+                job
+            })
+            .collect()
+    }
+
+    /// Builds the base request this job describes.
+    fn request(&self) -> Result<HttpRequest> {
+        let millis = |ms: Option<u64>| ms.map(Duration::from_millis);
+        let resolve = self.resolve.iter().map(|r| r.parse()).collect::<Result<Vec<ResolveEntry>>>()?;
+        let mut request = HttpRequest::new(&self.url)
+            .method(&self.method)?
+            .timeout(Duration::from_millis(self.timeout_ms))
+            .connect_timeout(millis(self.connect_timeout_ms))
+            .read_timeout(millis(self.read_timeout_ms))
+            .follow_redirects(self.follow_redirects)
+            .max_redirects(self.max_redirects)
+            .strict_redirects(self.strict_redirects)
+            .pool_idle_timeout(millis(self.pool_idle_timeout_ms))
+            .retry_on_connection_reset(self.retry_on_connection_reset)
+            .resolve(resolve)
+            .http_version(self.http_version)
+            .compress_body(self.compress_body)
+            .socket_options(SocketOptions {
+                local_address: None,
+                tcp_nodelay: self.tcp_nodelay,
+                tcp_keepalive: millis(self.tcp_keepalive_ms),
+            });
+        // Headers come along as they are, Accept-Encoding included
+        request.compressed = self.compressed;
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        if let Some(body) = &self.body {
+            request = request.body(body.clone());
+        }
+        Ok(request)
+    }
+
+    /// Builds the runner and dataset this job describes.
+    fn runner(&self, cancel: CancellationToken) -> Result<(PerfRunner, Dataset)> {
+        let millis = |ms: Option<u64>| ms.map(Duration::from_millis);
+        let request = self.request()?;
+
+        let assertions = self.assertions.iter().map(|a| a.parse()).collect::<Result<Vec<Assertion>>>()?;
+        let slo = match &self.slo {
+            Some((objective, window)) => Some(Slo::new(objective.parse()?, Duration::from_millis(*window))?),
+            None => None,
+        };
+
+        let runner = PerfRunner::new(self.url.clone(), request, self.concurrency, self.total_requests, false)
+            .cancellation_token(cancel)
+            .stats_interval(PROGRESS_INTERVAL)
+            .run_timeout(millis(self.run_timeout_ms))
+            .duration(millis(self.duration_ms))
+            .rate(self.rate)
+            .warmup(millis(self.warmup_ms))
+            .warmup_requests(self.warmup_requests)
+            .assertions(assertions)
+            .timeline_window(millis(self.timeline_window_ms))
+            .slo(slo)
+            .hash_responses(self.hash_responses)
+            .keepalive(self.keepalive);
+        let dataset = Dataset {
+            entries: self.entries.clone(),
+            hosts: self.hosts.clone(),
+        };
+        Ok((runner, dataset))
+    }
+}
+
+/// Progress of a worker, or of the whole run when summed over workers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkerProgress {
+    /// Requests completed so far
+    pub completed: usize,
+    /// Requests failed so far
+    pub failed_requests: usize,
+    /// Requests per second over the last interval
+    pub requests_per_second: f64,
+}
+
+/// Message from the controller to a worker.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ControllerMessage {
+    /// Start a run
+    Run { version: u32, job: Box<WorkerJob> },
+    /// Stop the run early and report what was collected
+    Stop,
+}
+
+/// Message from a worker to the controller.
+///
+/// Externally tagged: an internal tag would buffer the collector and lose
+/// its integer map keys.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WorkerMessage {
+    /// Periodic progress while running
+    Progress(WorkerProgress),
+    /// The run finished; carries everything the worker recorded
+    Done { collector: Box<MetricsCollector> },
+    /// The run could not be started or failed
+    Failed { error: String },
+}
+
+async fn write_message<W, T>(out: &mut W, message: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    out.write_all(&line).await?;
+    out.flush().await?;
+    Ok(())
+}
+
+async fn read_message<T>(lines: &mut Lines<BufReader<OwnedReadHalf>>) -> Result<Option<T>>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    match lines.next_line().await? {
+        Some(line) => Ok(Some(serde_json::from_str(&line)?)),
+        None => Ok(None),
+    }
+}
+
+/// Serves one controller connection: runs its job and reports the results.
+///
+/// `on_job` is called once the job is received, before the run starts.
+/// The run stops early if the controller asks for it or disconnects.
+/// Returns this worker's own metrics for the job.
+///
+/// # Errors
+///
+/// Returns an error if the controller sends something other than a job
+/// of this protocol version, or the job can't be run. The error is also
+/// reported to the controller when possible.
+pub async fn serve_job<F>(stream: TcpStream, on_job: F) -> Result<PerfMetrics>
+where
+    F: FnOnce(&WorkerJob),
+{
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+
+    let result = match read_message(&mut lines).await {
+        Ok(Some(ControllerMessage::Run { version, job })) if version == PROTOCOL_VERSION => {
+            on_job(&job);
+            run_job(&job, lines, &mut write).await
+        }
+        Ok(Some(ControllerMessage::Run { version, .. })) => Err(RurlError::DistributedError(format!(
+            "controller speaks protocol version {}, this worker speaks {}",
+            version, PROTOCOL_VERSION
+        ))),
+        Ok(_) => Err(RurlError::DistributedError("expected a job from the controller".to_string())),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok((collector, metrics)) => {
+            write_message(&mut write, &WorkerMessage::Done { collector: Box::new(collector) }).await?;
+            Ok(metrics)
+        }
+        Err(e) => {
+            let _ = write_message(&mut write, &WorkerMessage::Failed { error: e.to_string() }).await;
+            Err(e)
+        }
+    }
+}
+
+/// Runs a job, streaming progress until it finishes.
+async fn run_job(
+    job: &WorkerJob,
+    mut lines: Lines<BufReader<OwnedReadHalf>>,
+    write: &mut OwnedWriteHalf,
+) -> Result<(MetricsCollector, PerfMetrics)> {
+    let cancel = CancellationToken::new();
+    let (runner, dataset) = job.runner(cancel.clone())?;
+    if job.preflight {
+        runner.preflight(&dataset).await?;
+    }
+
+    // Stop when asked to, or when the controller goes away
+    let stop = cancel.clone();
+    let listener = tokio::spawn(async move {
+        while let Ok(Some(message)) = read_message::<ControllerMessage>(&mut lines).await {
+            if matches!(message, ControllerMessage::Stop) {
+                break;
+            }
+        }
+        stop.cancel();
+    });
+
+    let (progress, mut updates) = mpsc::unbounded_channel();
+    let on_event = Arc::new(move |event: RunEvent| {
+        if let RunEvent::IntervalStats(stats) = event {
+            let _ = progress.send(WorkerProgress {
+                completed: stats.completed,
+                failed_requests: stats.metrics.failed_requests,
+                requests_per_second: stats.interval_requests_per_second,
+            });
+        }
+    });
+
+    let run = runner.run_collector(&dataset, on_event);
+    tokio::pin!(run);
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            Some(update) = updates.recv() => write_message(write, &WorkerMessage::Progress(update)).await?,
+        }
+    };
+    listener.abort();
+
+    let collector = result?;
+    let metrics = collector.compute_metrics();
+    Ok((collector, metrics))
+}
+
+/// Runs `job` split across `workers` and merges what they recorded.
+///
+/// `on_progress` receives the progress summed over all workers. When
+/// `cancel` is cancelled, workers are asked to stop and the results
+/// collected so far are merged. The returned collector spans the whole
+/// distributed run, as seen from the controller.
+///
+/// # Errors
+///
+/// Returns an error if a worker can't be reached, rejects the job, or
+/// fails during the run; the other workers are then stopped.
+pub async fn run_distributed<F>(
+    workers: &[String],
+    job: &WorkerJob,
+    cancel: &CancellationToken,
+    on_progress: F,
+) -> Result<MetricsCollector>
+where
+    F: Fn(WorkerProgress) + Send + Sync + 'static,
+{
+    let stop = cancel.child_token();
+    let on_progress = Arc::new(on_progress);
+    let progress = Arc::new(Mutex::new(vec![WorkerProgress::default(); workers.len()]));
+
+    let mut merged = MetricsCollector::new();
+    merged.start();
+    let tasks: Vec<_> = workers
+        .iter()
+        .zip(job.split(workers.len()))
+        .enumerate()
+        .map(|(index, (addr, share))| {
+            let addr = addr.clone();
+            let stop = stop.clone();
+            let progress = Arc::clone(&progress);
+            let on_progress = Arc::clone(&on_progress);
+            tokio::spawn(async move {
+                let report = |update: WorkerProgress| {
+                    let mut all = progress.lock().unwrap_or_else(|e| e.into_inner());
+                    all[index] = update;
+                    on_progress(all.iter().fold(WorkerProgress::default(), |sum, p| WorkerProgress {
+                        completed: sum.completed + p.completed,
+                        failed_requests: sum.failed_requests + p.failed_requests,
+                        requests_per_second: sum.requests_per_second + p.requests_per_second,
+                    }));
+                };
+                let result = drive_worker(&addr, share, &stop, report).await;
+                if result.is_err() {
+                    stop.cancel();
+                }
+                result.map_err(|e| {
+                    let reason = match e {
+                        RurlError::DistributedError(reason) => reason,
+                        e => e.to_string(),
+                    };
+                    RurlError::DistributedError(format!("worker {}: {}", addr, reason))
+                })
+            })
+        })
+        .collect();
+
+    let mut first_error = None;
+    for task in tasks {
+        match task.await.map_err(|e| RurlError::DistributedError(e.to_string()))? {
+            Ok(collector) => merged.merge(&collector),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    merged.finish();
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(merged),
+    }
+}
+
+/// Sends a worker its share of the run and waits for its results.
+async fn drive_worker<F>(addr: &str, job: WorkerJob, stop: &CancellationToken, on_progress: F) -> Result<MetricsCollector>
+where
+    F: Fn(WorkerProgress),
+{
+    let stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| RurlError::DistributedError(format!("cannot connect: {}", e)))?;
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let run = ControllerMessage::Run {
+        version: PROTOCOL_VERSION,
+        job: Box::new(job),
+    };
+    write_message(&mut write, &run).await?;
+
+    let mut stopping = false;
+    loop {
+        let message = tokio::select! {
+            message = read_message(&mut lines) => message?,
+            _ = stop.cancelled(), if !stopping => {
+                stopping = true;
+                write_message(&mut write, &ControllerMessage::Stop).await?;
+                continue;
+            }
+        };
+        match message {
+            Some(WorkerMessage::Progress(update)) => on_progress(update),
+            Some(WorkerMessage::Done { collector }) => return Ok(*collector),
+            Some(WorkerMessage::Failed { error }) => return Err(RurlError::DistributedError(error)),
+            None => return Err(RurlError::DistributedError("connection closed before the run finished".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn job(concurrency: usize, total: usize) -> WorkerJob {
+        WorkerJob::new(&HttpRequest::new("http://127.0.0.1:1"), &Dataset::simple(1), concurrency, total)
+    }
+
+    #[test]
+    fn test_split_shares() {
+        let shares = job(5, 10).rate(Some(90.0)).split(3);
+        assert_eq!(shares.iter().map(|j| j.total_requests).collect::<Vec<_>>(), [4, 3, 3]);
+        assert_eq!(shares.iter().map(|j| j.concurrency).collect::<Vec<_>>(), [2, 2, 1]);
+        assert!(shares.iter().all(|j| j.rate == Some(30.0)));
+
+        let shares = job(1, 1).split(2);
+        assert_eq!(shares.iter().map(|j| (j.concurrency, j.total_requests)).collect::<Vec<_>>(), [(1, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn test_job_carries_request_settings() {
+        let request = HttpRequest::new("http://127.0.0.1:1")
+            .http_version(Some(HttpVersion::Http2))
+            .compressed(true)
+            .compress_body(true)
+            .socket_options(SocketOptions {
+                local_address: Some("10.0.0.21".parse().unwrap()),
+                tcp_nodelay: false,
+                tcp_keepalive: Some(Duration::from_secs(30)),
+            });
+        let dataset = Dataset::from_json(r#"[{"path": "/a", "http_version": "1.1"}]"#).unwrap();
+        let job = WorkerJob::new(&request, &dataset, 1, 1);
+        let job: WorkerJob = serde_json::from_str(&serde_json::to_string(&job).unwrap()).unwrap();
+
+        let sent = job.request().unwrap();
+        assert_eq!(sent.http_version, Some(HttpVersion::Http2));
+        assert!(sent.compressed);
+        assert!(sent.compress_body);
+        assert_eq!(
+            sent.socket,
+            SocketOptions { local_address: None, tcp_nodelay: false, tcp_keepalive: Some(Duration::from_secs(30)) }
+        );
+        assert_eq!(sent.headers, request.headers);
+        assert_eq!(job.entries[0].http_version, Some(HttpVersion::Http11));
+    }
+
+    #[tokio::test]
+    async fn test_distributed_run_merges_workers() {
+        // Target server answering every request with 200
+        let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", target.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = target.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    use tokio::io::AsyncReadExt;
+                    while let Ok(n) = socket.read(&mut buf).await {
+                        if n == 0 || socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok").await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        let mut workers = Vec::new();
+        for _ in 0..2 {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            workers.push(listener.local_addr().unwrap().to_string());
+            tokio::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                serve_job(stream, |_| {}).await
+            });
+        }
+
+        let job = WorkerJob::new(&HttpRequest::new(&url), &Dataset::simple(1), 2, 7);
+        let collector = run_distributed(&workers, &job, &CancellationToken::new(), |_| {}).await.unwrap();
+        let metrics = collector.compute_metrics();
+        assert_eq!(metrics.total_requests, 7);
+        assert_eq!(metrics.successful_requests, 7);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_worker_fails() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let result = run_distributed(&[addr], &job(1, 1), &CancellationToken::new(), |_| {}).await;
+        assert!(matches!(result, Err(RurlError::DistributedError(_))));
+    }
+}
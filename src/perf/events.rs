@@ -0,0 +1,66 @@
+//! Live events emitted while a performance test is running.
+//!
+//! Consumers such as progress displays or GUIs receive these through
+//! [`PerfRunner::run_with_events`](super::PerfRunner::run_with_events).
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::http::{PhaseTimings, PreparedRequest};
+use super::metrics::PerfMetrics;
+
+/// An event emitted by the runner during a performance test.
+#[derive(Debug, Clone)]
+pub enum RunEvent {
+    /// A single request has completed.
+    RequestDone {
+        /// Number of requests completed so far, including this one
+        completed: usize,
+        /// Total number of requests in the run, or 0 for a duration-based run
+        total: usize,
+        /// Endpoint label (e.g. "GET /users")
+        label: String,
+        /// The request that was sent
+        request: Arc<PreparedRequest>,
+        /// When the request was issued (or due, at a fixed rate)
+        started: SystemTime,
+        /// HTTP status code, or `None` if the request failed without a response
+        status: Option<u16>,
+        /// Request latency
+        duration: Duration,
+        /// Size of the response body in bytes
+        bytes: u64,
+        /// Whether the request counted as successful
+        success: bool,
+        /// Why the request failed: an error kind such as `timeout`, or `non_2xx`
+        error: Option<&'static str>,
+        /// Time spent in each phase, or `None` if there was no response
+        phases: Option<PhaseTimings>,
+        /// `--capture` values extracted from the response, as `(name, value)`
+        captures: Vec<(String, String)>,
+    },
+
+    /// The warmup phase has finished; requests from now on are measured.
+    WarmupFinished,
+
+    /// Periodic statistics, emitted at the runner's stats interval.
+    IntervalStats(IntervalStats),
+
+    /// The run has finished; carries the final metrics.
+    Finished(PerfMetrics),
+}
+
+/// Statistics reported periodically during a run.
+#[derive(Debug, Clone)]
+pub struct IntervalStats {
+    /// Time since the run started
+    pub elapsed: Duration,
+    /// Number of requests completed so far
+    pub completed: usize,
+    /// Number of requests completed during this interval
+    pub interval_requests: usize,
+    /// Throughput during this interval
+    pub interval_requests_per_second: f64,
+    /// Cumulative metrics up to this point
+    pub metrics: PerfMetrics,
+}
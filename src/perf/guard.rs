@@ -0,0 +1,246 @@
+//! Safety guards for performance tests.
+//!
+//! Prevents accidentally load testing production or third-party APIs by
+//! refusing to start a run whose targets are not on a configured allowlist,
+//! and flags runs that would send `DELETE`, `PUT` or `PATCH` at scale to
+//! hosts not marked as test environments, so the CLI can ask first.
+
+use reqwest::Url;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::error::{Result, RurlError};
+use crate::http::zone::strip_zone;
+use super::dataset::Dataset;
+use super::plan::RunPlan;
+
+/// Methods that change or delete data on the server.
+pub const DESTRUCTIVE_METHODS: [&str; 3] = ["DELETE", "PUT", "PATCH"];
+
+/// Destructive requests a run may send to a non-test host without confirmation.
+pub const DESTRUCTIVE_REQUEST_LIMIT: usize = 10;
+
+/// List of host patterns, e.g. the hosts performance tests may target.
+///
+/// Patterns are matched case-insensitively against the URL host. A pattern
+/// of the form `*.example.com` matches any subdomain of `example.com`.
+/// An empty allowlist disables the guard.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let allowlist = HostAllowlist::new(["localhost", "*.staging.example.com"]);
+/// allowlist.check("https://api.staging.example.com", &dataset)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HostAllowlist {
+    patterns: Vec<String>,
+}
+
+impl HostAllowlist {
+    /// Creates an allowlist from host patterns.
+    pub fn new<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(|p| p.into().trim().to_ascii_lowercase())
+            .filter(|p| !p.is_empty())
+            .collect();
+        Self { patterns }
+    }
+
+    /// Returns true if no hosts are configured, i.e. the guard is disabled.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns true if the host matches any pattern in the allowlist.
+    pub fn allows(&self, host: &str) -> bool {
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+        self.patterns.iter().any(|pattern| match pattern.strip_prefix("*.") {
+            Some(domain) => host.ends_with(&format!(".{}", domain)),
+            None => host == *pattern,
+        })
+    }
+
+    /// Verifies that every host targeted by a run is allowed.
+    ///
+    /// Checks the base URL and any absolute URLs used as dataset paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::HostNotAllowed`] for the first host not on the
+    /// allowlist, or [`RurlError::InvalidUrl`] if a URL cannot be parsed.
+    pub fn check(&self, base_url: &str, dataset: &Dataset) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let absolute_paths = dataset
+            .entries
+            .iter()
+            .filter_map(|entry| entry.path.as_deref())
+            .filter(|path| path.starts_with("http://") || path.starts_with("https://"));
+
+        for url in std::iter::once(base_url).chain(absolute_paths) {
+            let parsed = Url::parse(&strip_zone(url)).map_err(|e| RurlError::InvalidUrl(format!("{}: {}", url, e)))?;
+            let host = parsed.host_str().unwrap_or_default();
+            if !self.allows(host) {
+                return Err(RurlError::HostNotAllowed(host.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Destructive requests a run would send to hosts not marked as test
+/// environments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DestructiveLoad {
+    /// Destructive methods used
+    pub methods: BTreeSet<String>,
+    /// Hosts they are sent to
+    pub hosts: BTreeSet<String>,
+    /// Number of destructive requests; `None` for a time-based run
+    pub requests: Option<usize>,
+}
+
+impl DestructiveLoad {
+    /// Returns the destructive load of a planned run that needs confirming.
+    ///
+    /// Entries sent to a host matching `test_hosts` are ignored. Returns
+    /// `None` if what remains is at most [`DESTRUCTIVE_REQUEST_LIMIT`]
+    /// requests.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::InvalidUrl`] if an entry URL cannot be parsed.
+    pub fn from_plan(plan: &RunPlan, test_hosts: &HostAllowlist) -> Result<Option<Self>> {
+        let mut load = DestructiveLoad {
+            methods: BTreeSet::new(),
+            hosts: BTreeSet::new(),
+            requests: Some(0),
+        };
+        for entry in &plan.entries {
+            if !DESTRUCTIVE_METHODS.iter().any(|m| m.eq_ignore_ascii_case(&entry.method)) {
+                continue;
+            }
+            let parsed = Url::parse(&strip_zone(&entry.url))
+                .map_err(|e| RurlError::InvalidUrl(format!("{}: {}", entry.url, e)))?;
+            let host = parsed.host_str().unwrap_or_default();
+            if test_hosts.allows(host) || entry.requests == Some(0) {
+                continue;
+            }
+            load.methods.insert(entry.method.to_ascii_uppercase());
+            load.hosts.insert(host.to_string());
+            load.requests = load.requests.zip(entry.requests).map(|(a, b)| a + b);
+        }
+        let at_scale = !load.hosts.is_empty() && load.requests.is_none_or(|n| n > DESTRUCTIVE_REQUEST_LIMIT);
+        Ok(at_scale.then_some(load))
+    }
+}
+
+impl fmt::Display for DestructiveLoad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let methods: Vec<_> = self.methods.iter().map(String::as_str).collect();
+        let hosts: Vec<_> = self.hosts.iter().map(String::as_str).collect();
+        match self.requests {
+            Some(n) => write!(f, "{} {} requests", n, methods.join("/"))?,
+            None => write!(f, "{} requests for the whole run", methods.join("/"))?,
+        }
+        write!(f, " to {}, not marked as a test environment", hosts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perf::plan::PlannedEntry;
+
+    #[test]
+    fn test_empty_allowlist_allows_everything() {
+        let allowlist = HostAllowlist::new(Vec::<String>::new());
+        let dataset = Dataset::simple(1);
+        assert!(allowlist.check("https://prod.example.com", &dataset).is_ok());
+    }
+
+    #[test]
+    fn test_exact_and_wildcard_match() {
+        let allowlist = HostAllowlist::new(["localhost", "*.staging.example.com"]);
+        assert!(allowlist.allows("localhost"));
+        assert!(allowlist.allows("API.staging.example.com"));
+        assert!(!allowlist.allows("staging.example.com"));
+        assert!(!allowlist.allows("prod.example.com"));
+    }
+
+    #[test]
+    fn test_check_rejects_base_url() {
+        let allowlist = HostAllowlist::new(["localhost"]);
+        let dataset = Dataset::simple(1);
+        let result = allowlist.check("https://prod.example.com/api", &dataset);
+        assert!(matches!(result, Err(RurlError::HostNotAllowed(host)) if host == "prod.example.com"));
+    }
+
+    fn plan(entries: &[(&str, &str, Option<usize>)]) -> RunPlan {
+        RunPlan {
+            url: "https://api.example.com".to_string(),
+            total_requests: None,
+            warmup_requests: 0,
+            warmup_seconds: None,
+            duration_seconds: None,
+            estimated_duration_seconds: None,
+            entries: entries
+                .iter()
+                .map(|(method, url, requests)| PlannedEntry {
+                    label: method.to_string(),
+                    method: method.to_string(),
+                    url: url.to_string(),
+                    requests: *requests,
+                })
+                .collect(),
+            schedule: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_destructive_load() {
+        let none = HostAllowlist::default();
+        let load = DestructiveLoad::from_plan(
+            &plan(&[
+                ("GET", "https://api.example.com/a", Some(100)),
+                ("DELETE", "https://api.example.com/a", Some(30)),
+                ("patch", "https://api.example.com/b", Some(20)),
+            ]),
+            &none,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(load.requests, Some(50));
+        assert_eq!(
+            load.to_string(),
+            "50 DELETE/PATCH requests to api.example.com, not marked as a test environment"
+        );
+
+        // Few requests, test hosts and time-based runs
+        let few = plan(&[("DELETE", "https://api.example.com/a", Some(10))]);
+        assert_eq!(DestructiveLoad::from_plan(&few, &none).unwrap(), None);
+        let timed = plan(&[("PUT", "https://api.example.com/a", None)]);
+        assert_eq!(DestructiveLoad::from_plan(&timed, &none).unwrap().unwrap().requests, None);
+        let test_hosts = HostAllowlist::new(["*.example.com"]);
+        assert_eq!(DestructiveLoad::from_plan(&timed, &test_hosts).unwrap(), None);
+    }
+
+    #[test]
+    fn test_check_rejects_absolute_dataset_path() {
+        let allowlist = HostAllowlist::new(["localhost"]);
+        let dataset = Dataset::from_json(
+            r#"[{"path": "/ok"}, {"path": "https://third-party.example.com/x"}]"#,
+        )
+        .unwrap();
+        assert!(allowlist.check("http://localhost:8080", &dataset).is_err());
+    }
+}
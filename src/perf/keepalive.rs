@@ -0,0 +1,358 @@
+//! Keep-alive idle connection benchmark.
+//!
+//! Opens several keep-alive connections, lets each one sit idle for a
+//! different amount of time, and then probes it to see whether the
+//! connection can still be reused. Connections closed by the server (or a
+//! load balancer in between) while idle are detected as soon as the close
+//! arrives, giving the effective idle timeout of the path to the server.
+//!
+//! Connection `k` (1-based) idles for `k * probe_interval` before being
+//! probed, so `N` connections cover idle times up to `N * probe_interval`.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use url::Url;
+
+use crate::error::{Result, RurlError};
+use crate::http::raw::{connect, Connection};
+use crate::http::HttpRequest;
+
+/// Largest response head accepted from the server.
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// Outcome of probing one idle connection.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "outcome", content = "detail", rename_all = "snake_case")]
+pub enum ProbeOutcome {
+    /// The connection was still open and served the probe
+    Reused,
+    /// The server closed the connection while it was idle
+    ClosedByServer,
+    /// The server answered the first request without keep-alive (e.g. `Connection: close`)
+    NotKeptAlive,
+    /// The connection looked open but the probe failed on it
+    Reset(String),
+    /// The connection could not be established or the first request failed
+    Failed(String),
+}
+
+/// Result for one connection of the benchmark.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionProbe {
+    /// Connection number, starting at 1
+    pub connection: usize,
+    /// How long the connection was left idle before probing
+    pub idle_ms: f64,
+    /// What happened when the connection was probed
+    #[serde(flatten)]
+    pub outcome: ProbeOutcome,
+    /// Idle time after which the server closed the connection, if it did
+    pub closed_after_ms: Option<f64>,
+    /// Latency of the probe request on the reused connection
+    pub probe_latency_ms: Option<f64>,
+}
+
+/// Results of a keep-alive benchmark, ordered by idle time.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeepAliveReport {
+    /// Target URL
+    pub url: String,
+    /// Per-connection results
+    pub probes: Vec<ConnectionProbe>,
+}
+
+impl KeepAliveReport {
+    /// Longest idle time after which a connection was successfully reused.
+    pub fn max_reused_idle_ms(&self) -> Option<f64> {
+        self.probes
+            .iter()
+            .filter(|p| p.outcome == ProbeOutcome::Reused)
+            .map(|p| p.idle_ms)
+            .reduce(f64::max)
+    }
+
+    /// Shortest idle time after which the server closed a connection.
+    pub fn min_closed_after_ms(&self) -> Option<f64> {
+        self.probes
+            .iter()
+            .filter_map(|p| p.closed_after_ms)
+            .reduce(f64::min)
+    }
+}
+
+/// Benchmark measuring how long idle keep-alive connections survive.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let bench = KeepAliveBenchmark::new(request, 6, Duration::from_secs(10));
+/// let report = bench.run().await?;
+/// println!("{:?}", report.min_closed_after_ms());
+/// ```
+pub struct KeepAliveBenchmark {
+    request: HttpRequest,
+    connections: usize,
+    probe_interval: Duration,
+}
+
+impl KeepAliveBenchmark {
+    /// Creates a new keep-alive benchmark.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Target request; its URL, headers and timeout are used for probes
+    /// * `connections` - Number of connections to hold open
+    /// * `probe_interval` - Idle time step between connections
+    pub fn new(request: HttpRequest, connections: usize, probe_interval: Duration) -> Self {
+        Self {
+            request,
+            connections,
+            probe_interval,
+        }
+    }
+
+    /// Runs the benchmark, holding all connections concurrently.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::InvalidUrl`] if the request URL has no host.
+    /// Failures on individual connections are reported in the result.
+    pub async fn run(&self) -> Result<KeepAliveReport> {
+        let url = Url::parse(&self.request.url)
+            .map_err(|e| RurlError::InvalidUrl(format!("{}: {}", self.request.url, e)))?;
+        if url.host_str().is_none() {
+            return Err(RurlError::InvalidUrl(format!("{}: missing host", self.request.url)));
+        }
+
+        let head = probe_request(&url, &self.request);
+        let mut handles = Vec::new();
+        for connection in 1..=self.connections {
+            let idle = self.probe_interval * connection as u32;
+            let url = url.clone();
+            let head = head.clone();
+            let timeout = self.request.timeout;
+            handles.push(tokio::spawn(async move {
+                probe_connection(connection, &url, &head, idle, timeout).await
+            }));
+        }
+
+        let mut probes = Vec::new();
+        for handle in handles {
+            if let Ok(probe) = handle.await {
+                probes.push(probe);
+            }
+        }
+
+        Ok(KeepAliveReport {
+            url: self.request.url.clone(),
+            probes,
+        })
+    }
+}
+
+/// Builds the raw HTTP/1.1 HEAD request used to probe connections.
+fn probe_request(url: &Url, request: &HttpRequest) -> String {
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    let mut head = format!("HEAD {} HTTP/1.1\r\nHost: {}\r\n", target, host);
+    for (key, value) in &request.headers {
+        if !key.eq_ignore_ascii_case("host") && !key.eq_ignore_ascii_case("connection") {
+            head.push_str(&format!("{}: {}\r\n", key, value));
+        }
+    }
+    head.push_str("Connection: keep-alive\r\n\r\n");
+    head
+}
+
+async fn probe_connection(
+    connection: usize,
+    url: &Url,
+    head: &str,
+    idle: Duration,
+    timeout: Duration,
+) -> ConnectionProbe {
+    let mut probe = ConnectionProbe {
+        connection,
+        idle_ms: idle.as_secs_f64() * 1000.0,
+        outcome: ProbeOutcome::Reused,
+        closed_after_ms: None,
+        probe_latency_ms: None,
+    };
+
+    let mut stream = match tokio::time::timeout(timeout, connect(url, &[])).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => {
+            probe.outcome = ProbeOutcome::Failed(e.to_string());
+            return probe;
+        }
+        Err(_) => {
+            probe.outcome = ProbeOutcome::Failed("connect timed out".to_string());
+            return probe;
+        }
+    };
+
+    match tokio::time::timeout(timeout, exchange(&mut stream, head)).await {
+        Ok(Ok(response)) if !is_kept_alive(&response) => {
+            probe.outcome = ProbeOutcome::NotKeptAlive;
+            return probe;
+        }
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => {
+            probe.outcome = ProbeOutcome::Failed(format!("initial request: {}", e));
+            return probe;
+        }
+        Err(_) => {
+            probe.outcome = ProbeOutcome::Failed("initial request timed out".to_string());
+            return probe;
+        }
+    }
+
+    // Sit idle, watching for the server closing the connection
+    let idle_start = Instant::now();
+    let mut buf = [0u8; 1];
+    tokio::select! {
+        _ = tokio::time::sleep(idle) => {}
+        _ = stream.read(&mut buf) => {
+            // Any read result while idle (EOF, reset, or unexpected data)
+            // means the connection is no longer usable
+            probe.closed_after_ms = Some(idle_start.elapsed().as_secs_f64() * 1000.0);
+            probe.outcome = ProbeOutcome::ClosedByServer;
+            return probe;
+        }
+    }
+
+    let start = Instant::now();
+    probe.outcome = match tokio::time::timeout(timeout, exchange(&mut stream, head)).await {
+        Ok(Ok(_)) => {
+            probe.probe_latency_ms = Some(start.elapsed().as_secs_f64() * 1000.0);
+            ProbeOutcome::Reused
+        }
+        Ok(Err(e)) => ProbeOutcome::Reset(e.to_string()),
+        Err(_) => ProbeOutcome::Reset("probe timed out".to_string()),
+    };
+    probe
+}
+
+/// Returns true if the response head allows the connection to be reused.
+///
+/// HTTP/1.1 is persistent unless `Connection: close` is sent; HTTP/1.0 is
+/// only persistent with an explicit `Connection: keep-alive`.
+fn is_kept_alive(response: &str) -> bool {
+    let lower = response.to_ascii_lowercase();
+    if lower.contains("\r\nconnection: close") {
+        false
+    } else if lower.starts_with("http/1.0") {
+        lower.contains("\r\nconnection: keep-alive")
+    } else {
+        true
+    }
+}
+
+/// Sends a request and reads the response head (HEAD responses have no body).
+async fn exchange(stream: &mut Box<dyn Connection>, head: &str) -> std::io::Result<String> {
+    stream.write_all(head.as_bytes()).await?;
+    stream.flush().await?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "connection closed before response",
+            ));
+        }
+        response.extend_from_slice(&buf[..n]);
+        if response.windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+        if response.len() > MAX_HEAD_SIZE {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "response head too large"));
+        }
+    }
+
+    let text = String::from_utf8_lossy(&response).to_string();
+    if !text.starts_with("HTTP/1.") {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "not an HTTP/1.x response"));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(idle_ms: f64, outcome: ProbeOutcome, closed_after_ms: Option<f64>) -> ConnectionProbe {
+        ConnectionProbe {
+            connection: 1,
+            idle_ms,
+            outcome,
+            closed_after_ms,
+            probe_latency_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_probe_request() {
+        let url = Url::parse("http://example.com:8080/health?full=1").unwrap();
+        let request = HttpRequest::new(url.as_str()).header("Authorization", "Bearer x");
+        let head = probe_request(&url, &request);
+        assert!(head.starts_with("HEAD /health?full=1 HTTP/1.1\r\nHost: example.com:8080\r\n"));
+        assert!(head.contains("Authorization: Bearer x\r\n"));
+        assert!(head.ends_with("Connection: keep-alive\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_is_kept_alive() {
+        assert!(is_kept_alive("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"));
+        assert!(!is_kept_alive("HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n"));
+        assert!(!is_kept_alive("HTTP/1.0 200 OK\r\nServer: x\r\n\r\n"));
+        assert!(is_kept_alive("HTTP/1.0 200 OK\r\nConnection: keep-alive\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_report_summary() {
+        let report = KeepAliveReport {
+            url: "http://example.com".to_string(),
+            probes: vec![
+                probe(5000.0, ProbeOutcome::Reused, None),
+                probe(10000.0, ProbeOutcome::Reused, None),
+                probe(15000.0, ProbeOutcome::ClosedByServer, Some(12000.0)),
+                probe(20000.0, ProbeOutcome::ClosedByServer, Some(12010.0)),
+            ],
+        };
+        assert_eq!(report.max_reused_idle_ms(), Some(10000.0));
+        assert_eq!(report.min_closed_after_ms(), Some(12000.0));
+    }
+
+    #[tokio::test]
+    async fn test_detects_server_close() {
+        use tokio::net::TcpListener;
+
+        // Server that answers one request, then closes the connection
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let _ = socket.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await;
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        });
+
+        let request = HttpRequest::new(format!("http://{}/", addr));
+        let bench = KeepAliveBenchmark::new(request, 1, Duration::from_secs(5));
+        let report = bench.run().await.unwrap();
+        assert_eq!(report.probes[0].outcome, ProbeOutcome::ClosedByServer);
+        assert!(report.probes[0].closed_after_ms.unwrap() < 5000.0);
+    }
+}
@@ -3,16 +3,20 @@
 //! Uses HdrHistogram for accurate latency percentile calculations
 //! (p50, p95, p99) with minimal memory overhead.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::AddAssign;
 use std::time::Duration;
 use hdrhistogram::Histogram;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::http::PhaseTimings;
+use super::slo::{Slo, SloReport};
 
 /// Performance test metrics.
 ///
 /// Contains aggregate statistics about request execution including
 /// latency distribution and throughput.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PerfMetrics {
     /// Total number of requests made
     pub total_requests: usize,
@@ -41,43 +45,461 @@ pub struct PerfMetrics {
     /// Metrics per endpoint (label)
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub endpoints: HashMap<String, PerfMetrics>,
+    /// Metrics per HTTP status class ("2xx", "4xx", "5xx", ...)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub status_classes: HashMap<String, PerfMetrics>,
+    /// Number of responses per HTTP status code
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub status_codes: BTreeMap<u16, usize>,
+    /// Number of failed requests per cause (`timeout`, `dns`, `connect`, `tls`, `non_2xx`, ...)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub error_categories: BTreeMap<String, usize>,
+    /// Number of responses per negotiated protocol version (`HTTP/1.1`, `HTTP/2`, ...)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub http_versions: BTreeMap<String, usize>,
+    /// Number of responses per response cache outcome (`hit`, `revalidated`, `miss`), with `--cache`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub cache_statuses: BTreeMap<String, usize>,
+    /// Pass/fail counts per response assertion (`--assert`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub validation: Vec<AssertionMetrics>,
+    /// Throughput and latency per time window, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timeline: Vec<TimelinePoint>,
+    /// Latency of successful requests only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success_latency: Option<LatencySummary>,
+    /// Latency of failed requests only, which fast errors would otherwise
+    /// pull into the overall percentiles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_latency: Option<LatencySummary>,
+    /// Request and response body bytes, for the overall run only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer: Option<TransferMetrics>,
+    /// Time spent in each connection phase, for the overall run only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phases: Option<PhaseMetrics>,
+    /// Latency distribution as log-spaced buckets, for the overall run only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub histogram: Vec<LatencyBucket>,
+    /// Split of latency into dispatch delay and time after dispatch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispatch_delay: Option<DispatchDelayMetrics>,
+    /// Metrics per load stage, in order (`--stages`, `--ramp-up`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<StageMetrics>,
+    /// Per-window compliance with the `--slo` objective
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slo: Option<SloReport>,
+    /// How many distinct response bodies were seen (`--hash-responses`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub responses: Option<ResponseUniqueness>,
+    /// Scenario iterations run by virtual users (`--vus`, `--iterations`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iterations: Option<IterationMetrics>,
+    /// Priority and background traffic compared (`--priority`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub traffic_classes: Vec<TrafficClassMetrics>,
+    /// How the overall latency figures were computed, for the overall run only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percentile_method: Option<PercentileMethod>,
+}
+
+/// How latency percentiles were computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PercentileMethod {
+    /// From every latency sample, for runs of up to
+    /// [`MetricsCollector::set_exact_samples`] requests
+    Exact,
+    /// From an HdrHistogram, accurate to 3 significant digits
+    Histogram,
+}
+
+impl std::fmt::Display for PercentileMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PercentileMethod::Exact => write!(f, "exact"),
+            PercentileMethod::Histogram => write!(f, "histogram (3 significant digits)"),
+        }
+    }
+}
+
+/// Latency percentiles for a subset of requests.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencySummary {
+    /// Number of requests in the subset
+    pub requests: usize,
+    /// Minimum latency in milliseconds
+    pub min_ms: f64,
+    /// Maximum latency in milliseconds
+    pub max_ms: f64,
+    /// Average latency in milliseconds
+    pub avg_ms: f64,
+    /// Median latency in milliseconds
+    pub p50_ms: f64,
+    /// 95th percentile latency in milliseconds
+    pub p95_ms: f64,
+    /// 99th percentile latency in milliseconds
+    pub p99_ms: f64,
+}
+
+impl LatencySummary {
+    fn from_histogram(histogram: &Histogram<u64>) -> Option<Self> {
+        if histogram.is_empty() {
+            return None;
+        }
+        let to_ms = |micros: u64| micros as f64 / 1000.0;
+        Some(Self {
+            requests: histogram.len() as usize,
+            min_ms: to_ms(histogram.min()),
+            max_ms: to_ms(histogram.max()),
+            avg_ms: to_ms(histogram.mean() as u64),
+            p50_ms: to_ms(histogram.value_at_percentile(50.0)),
+            p95_ms: to_ms(histogram.value_at_percentile(95.0)),
+            p99_ms: to_ms(histogram.value_at_percentile(99.0)),
+        })
+    }
+}
+
+/// Time spent in each phase of a request.
+///
+/// The DNS, connect and TLS phases only cover requests that opened a new
+/// connection, so their `requests` count how many did. When time to first
+/// byte dominates, latency is on the server; when the connection phases
+/// do, it is in the network.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseMetrics {
+    /// Host name resolution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<LatencySummary>,
+    /// TCP connection setup
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect: Option<LatencySummary>,
+    /// TLS handshake
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<LatencySummary>,
+    /// From the connection being ready to the response headers
+    pub ttfb: LatencySummary,
+    /// Reading the response body
+    pub transfer: LatencySummary,
+}
+
+impl PhaseMetrics {
+    /// Returns each phase that was seen with its name, in request order.
+    pub fn phases(&self) -> Vec<(&'static str, &LatencySummary)> {
+        [
+            ("dns", self.dns.as_ref()),
+            ("connect", self.connect.as_ref()),
+            ("tls", self.tls.as_ref()),
+            ("ttfb", Some(&self.ttfb)),
+            ("transfer", Some(&self.transfer)),
+        ]
+        .into_iter()
+        .filter_map(|(name, summary)| Some((name, summary?)))
+        .collect()
+    }
+}
+
+/// One bar of the latency histogram: requests with latency in `(from_ms, to_ms]`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyBucket {
+    /// Lower bound in milliseconds
+    pub from_ms: f64,
+    /// Upper bound in milliseconds
+    pub to_ms: f64,
+    /// Number of requests in the bucket
+    pub count: u64,
+}
+
+/// Requests that finished during one window of the run.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelinePoint {
+    /// Window start, in milliseconds since the run started
+    pub start_ms: f64,
+    /// Window end, in milliseconds since the run started
+    pub end_ms: f64,
+    /// Number of requests finished in the window
+    pub requests: usize,
+    /// Number of those requests that failed
+    pub failed_requests: usize,
+    /// Requests per second over the window
+    pub requests_per_second: f64,
+    /// Median latency in milliseconds
+    pub latency_p50_ms: f64,
+    /// 95th percentile latency in milliseconds
+    pub latency_p95_ms: f64,
+    /// 99th percentile latency in milliseconds
+    pub latency_p99_ms: f64,
+}
+
+/// Pass/fail counts for one response assertion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssertionMetrics {
+    /// The assertion as given (e.g. "status == 200")
+    pub assertion: String,
+    /// Number of responses it held for
+    pub passed: usize,
+    /// Number of responses it failed for
+    pub failed: usize,
+    /// The first few failures, prefixed with the endpoint label
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+}
+
+/// Number of example failures kept per assertion.
+const ASSERTION_EXAMPLES: usize = 3;
+
+/// Metrics for the requests started during one load stage.
+#[derive(Debug, Clone, Serialize)]
+pub struct StageMetrics {
+    /// Stage description (e.g. "50 → 200 over 2m")
+    pub stage: String,
+    /// Metrics for the stage; throughput is over the stage's own duration
+    #[serde(flatten)]
+    pub metrics: PerfMetrics,
+}
+
+/// Metrics for the priority or the background requests of a run.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrafficClassMetrics {
+    /// `priority` or `background`
+    pub class: String,
+    /// Endpoint labels in the class, sorted; each has its own entry in
+    /// [`PerfMetrics::endpoints`] with its achieved rate
+    pub labels: Vec<String>,
+    /// Metrics for the class; throughput is over the whole run
+    #[serde(flatten)]
+    pub metrics: PerfMetrics,
+}
+
+/// How much of the measured latency passed before hurley dispatched requests.
+///
+/// Dispatch delay is the time between the runner issuing a request and the
+/// request being handed to the HTTP client: the delay in starting its task
+/// plus setting up the client. It does not include waiting for a pooled
+/// connection, which happens after dispatch and so counts toward the
+/// `network_*` figures along with connecting and the exchange itself. When
+/// the delay is a large share of latency, the numbers reflect contention in
+/// hurley rather than the server.
+#[derive(Debug, Clone, Serialize)]
+pub struct DispatchDelayMetrics {
+    /// Average dispatch delay in milliseconds
+    pub delay_avg_ms: f64,
+    /// 99th percentile dispatch delay in milliseconds
+    pub delay_p99_ms: f64,
+    /// Dispatch delay as a percentage of total latency
+    pub delay_percent: f64,
+    /// Median latency with dispatch delay subtracted
+    pub network_p50_ms: f64,
+    /// 95th percentile latency with dispatch delay subtracted
+    pub network_p95_ms: f64,
+    /// 99th percentile latency with dispatch delay subtracted
+    pub network_p99_ms: f64,
+}
+
+/// How much body data a run sent and received.
+///
+/// Sizes are of request and response bodies, without headers. MB are
+/// decimal megabytes (1,000,000 bytes).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransferMetrics {
+    /// Request body bytes sent
+    pub bytes_sent: u64,
+    /// Response body bytes received
+    pub bytes_received: u64,
+    /// Average request body size in bytes
+    pub avg_request_bytes: f64,
+    /// Average response body size in bytes, over requests that got a response
+    pub avg_response_bytes: f64,
+    /// Request body bytes sent per second, in MB/s
+    pub sent_mb_per_second: f64,
+    /// Response body bytes received per second, in MB/s
+    pub received_mb_per_second: f64,
+}
+
+/// Scenario iterations run by virtual users.
+///
+/// An iteration is one pass of a virtual user through every dataset
+/// entry; it failed if any of its requests did. Iterations cut short by
+/// cancellation are not counted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IterationMetrics {
+    /// Number of completed iterations
+    pub iterations: usize,
+    /// Number of those with at least one failed request
+    pub failed_iterations: usize,
+    /// Completed iterations per second
+    pub iterations_per_second: f64,
+    /// Time to run one iteration
+    pub duration: LatencySummary,
+}
+
+/// How varied the responses of a run were.
+///
+/// A load test that only ever gets one distinct response back is often
+/// hitting a cached page or an error page rather than the real endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResponseUniqueness {
+    /// Number of distinct status and body combinations
+    pub distinct: usize,
+    /// Number of responses identical to the most common one
+    pub most_common: usize,
+    /// Share of responses identical to the most common one
+    pub most_common_percent: f64,
+    /// Whether tracking stopped at [`MAX_DISTINCT_RESPONSES`], making
+    /// `distinct` a lower bound
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub saturated: bool,
+}
+
+/// Distinct responses tracked per bucket before new ones stop being counted.
+pub const MAX_DISTINCT_RESPONSES: usize = 100_000;
+
+impl PerfMetrics {
+    /// Returns the timeline windows to chart.
+    ///
+    /// A last window cut short by the end of the run would show a misleading
+    /// spike or dip, so it is left out unless it ran for at least half a window.
+    pub fn charted_timeline(&self) -> Vec<&TimelinePoint> {
+        let Some(first) = self.timeline.first() else {
+            return Vec::new();
+        };
+        let full = first.end_ms - first.start_ms;
+        self.timeline
+            .iter()
+            .filter(|p| p.end_ms - p.start_ms >= full / 2.0)
+            .collect()
+    }
+}
+
+/// Creates a histogram of microsecond latencies up to 60 seconds.
+fn latency_histogram() -> Histogram<u64> {
+    // sigfig=3 gives us good precision for latency measurements
+    Histogram::new_with_bounds(1, 60_000_000, 3).expect("Failed to create histogram")
+}
+
+/// Serde default for the exact-sample limit of a deserialized collector.
+fn default_exact_limit() -> usize {
+    DEFAULT_EXACT_SAMPLES
+}
+
+/// Runs of up to this many requests get exact percentiles by default.
+pub const DEFAULT_EXACT_SAMPLES: usize = 10_000;
+
+/// Returns the sample at percentile `p` of sorted samples, by nearest rank.
+fn nearest_rank(sorted: &[u64], p: f64) -> u64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Number of bars in the latency histogram.
+const HISTOGRAM_BUCKETS: usize = 20;
+
+/// Splits a histogram into `buckets` log-spaced buckets between its min and max.
+///
+/// Log spacing keeps the bulk of the distribution readable when there is a
+/// long tail, while bimodal shapes still show as two humps.
+fn latency_buckets(histogram: &Histogram<u64>, buckets: usize) -> Vec<LatencyBucket> {
+    if histogram.is_empty() {
+        return Vec::new();
+    }
+    let min = histogram.min().max(1) as f64;
+    let max = histogram.max() as f64;
+    let buckets = if max / min < 1.001 { 1 } else { buckets };
+    let ratio = (max / min).powf(1.0 / buckets as f64);
+    let edges: Vec<f64> = (0..=buckets).map(|i| min * ratio.powi(i as i32)).collect();
+
+    let mut counts = vec![0u64; buckets];
+    for value in histogram.iter_recorded() {
+        let micros = value.value_iterated_to() as f64;
+        let index = edges[1..].iter().position(|edge| micros <= *edge).unwrap_or(buckets - 1);
+        counts[index] += value.count_at_value();
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| LatencyBucket {
+            from_ms: edges[i] / 1000.0,
+            to_ms: edges[i + 1] / 1000.0,
+            count,
+        })
+        .collect()
+}
+
+/// Records a duration in microseconds, clamped to the histogram's range.
+fn record_micros(histogram: &mut Histogram<u64>, duration: Duration) {
+    let micros = (duration.as_micros() as u64).min(histogram.high());
+    let _ = histogram.record(micros);
+}
+
+/// Serializes histograms in the compressed HdrHistogram V2 format, base64-encoded.
+mod encoded_histogram {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use hdrhistogram::serialization::{Deserializer, Serializer as _, V2DeflateSerializer};
+    use hdrhistogram::{Counter, Histogram};
+    use serde::{de, Deserialize, Serializer};
+
+    pub fn serialize<T: Counter, S: Serializer>(histogram: &Histogram<T>, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::new();
+        V2DeflateSerializer::new()
+            .serialize(histogram, &mut bytes)
+            .map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, T: Counter, D: de::Deserializer<'de>>(deserializer: D) -> Result<Histogram<T>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let bytes = STANDARD.decode(encoded).map_err(de::Error::custom)?;
+        Deserializer::new().deserialize(&mut bytes.as_slice()).map_err(de::Error::custom)
+    }
 }
 
 /// Internal bucket for collecting stats (histogram + counts).
+///
+/// Failures are also kept in a histogram of their own; the successes are
+/// the difference, which saves keeping a third one.
+#[derive(Clone, Serialize, Deserialize)]
 struct StatsBucket {
+    #[serde(with = "encoded_histogram")]
     histogram: Histogram<u64>,
+    #[serde(with = "encoded_histogram", default = "latency_histogram")]
+    failures: Histogram<u64>,
     successful: usize,
     failed: usize,
 }
 
 impl StatsBucket {
     fn new() -> Self {
-        // Create histogram with max value of 60 seconds (in microseconds)
-        // sigfig=3 gives us good precision for latency measurements
-        let histogram = Histogram::new_with_bounds(1, 60_000_000, 3)
-            .expect("Failed to create histogram");
-        
         Self {
-            histogram,
+            histogram: latency_histogram(),
+            failures: latency_histogram(),
             successful: 0,
             failed: 0,
         }
     }
 
     fn record_success(&mut self, duration: Duration) {
-        let micros = duration.as_micros() as u64;
-        let micros = micros.min(self.histogram.high());
-        let _ = self.histogram.record(micros);
+        record_micros(&mut self.histogram, duration);
         self.successful += 1;
     }
 
     fn record_failure(&mut self, duration: Duration) {
-        let micros = duration.as_micros() as u64;
-        let micros = micros.min(self.histogram.high());
-        let _ = self.histogram.record(micros);
+        record_micros(&mut self.histogram, duration);
+        record_micros(&mut self.failures, duration);
         self.failed += 1;
     }
 
+    fn merge(&mut self, other: &StatsBucket) {
+        // Both histograms share the same bounds, so adding cannot fail
+        let _ = self.histogram.add(&other.histogram);
+        let _ = self.failures.add(&other.failures);
+        self.successful += other.successful;
+        self.failed += other.failed;
+    }
+
     fn compute_metrics(&self, total_duration: Duration) -> PerfMetrics {
         let total = self.successful + self.failed;
         
@@ -98,6 +520,10 @@ impl StatsBucket {
         // Convert microseconds to milliseconds
         let to_ms = |micros: u64| micros as f64 / 1000.0;
 
+        let mut successes = self.histogram.clone();
+        // Every failure was recorded in both, so this cannot underflow
+        let _ = successes.subtract(&self.failures);
+
         PerfMetrics {
             total_requests: total,
             successful_requests: self.successful,
@@ -112,18 +538,264 @@ impl StatsBucket {
             requests_per_second,
             error_rate_percent: error_rate,
             endpoints: HashMap::new(), // Leaf nodes don't have endpoints
+            status_classes: HashMap::new(),
+            status_codes: BTreeMap::new(),
+            error_categories: BTreeMap::new(),
+            http_versions: BTreeMap::new(),
+            cache_statuses: BTreeMap::new(),
+            validation: Vec::new(),
+            timeline: Vec::new(),
+            success_latency: LatencySummary::from_histogram(&successes),
+            failure_latency: LatencySummary::from_histogram(&self.failures),
+            transfer: None,
+            phases: None,
+            histogram: Vec::new(),
+            dispatch_delay: None,
+            stages: Vec::new(),
+            slo: None,
+            responses: None,
+            iterations: None,
+            traffic_classes: Vec::new(),
+            percentile_method: None,
+        }
+    }
+}
+
+/// Histograms of the time spent in each phase of a request.
+#[derive(Clone, Serialize, Deserialize)]
+struct PhaseHistograms {
+    #[serde(with = "encoded_histogram")]
+    dns: Histogram<u64>,
+    #[serde(with = "encoded_histogram")]
+    connect: Histogram<u64>,
+    #[serde(with = "encoded_histogram")]
+    tls: Histogram<u64>,
+    #[serde(with = "encoded_histogram")]
+    ttfb: Histogram<u64>,
+    #[serde(with = "encoded_histogram")]
+    transfer: Histogram<u64>,
+}
+
+impl PhaseHistograms {
+    fn new() -> Self {
+        Self {
+            dns: latency_histogram(),
+            connect: latency_histogram(),
+            tls: latency_histogram(),
+            ttfb: latency_histogram(),
+            transfer: latency_histogram(),
         }
     }
+
+    fn record(&mut self, phases: &PhaseTimings) {
+        for (histogram, phase) in [(&mut self.dns, phases.dns), (&mut self.connect, phases.connect), (&mut self.tls, phases.tls)] {
+            if let Some(phase) = phase {
+                record_micros(histogram, phase);
+            }
+        }
+        record_micros(&mut self.ttfb, phases.ttfb);
+        record_micros(&mut self.transfer, phases.transfer);
+    }
+
+    fn merge(&mut self, other: &PhaseHistograms) {
+        let _ = self.dns.add(&other.dns);
+        let _ = self.connect.add(&other.connect);
+        let _ = self.tls.add(&other.tls);
+        let _ = self.ttfb.add(&other.ttfb);
+        let _ = self.transfer.add(&other.transfer);
+    }
+
+    fn summary(&self) -> Option<PhaseMetrics> {
+        Some(PhaseMetrics {
+            dns: LatencySummary::from_histogram(&self.dns),
+            connect: LatencySummary::from_histogram(&self.connect),
+            tls: LatencySummary::from_histogram(&self.tls),
+            ttfb: LatencySummary::from_histogram(&self.ttfb)?,
+            transfer: LatencySummary::from_histogram(&self.transfer)?,
+        })
+    }
+}
+
+/// Counts of responses by body hash.
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct ResponseCounts {
+    counts: HashMap<u64, usize>,
+    saturated: bool,
+}
+
+impl ResponseCounts {
+    fn record(&mut self, hash: u64) {
+        if let Some(count) = self.counts.get_mut(&hash) {
+            *count += 1;
+        } else if self.counts.len() < MAX_DISTINCT_RESPONSES {
+            self.counts.insert(hash, 1);
+        } else {
+            self.saturated = true;
+        }
+    }
+
+    fn merge(&mut self, other: &ResponseCounts) {
+        for (hash, count) in &other.counts {
+            if let Some(mine) = self.counts.get_mut(hash) {
+                *mine += count;
+            } else if self.counts.len() < MAX_DISTINCT_RESPONSES {
+                self.counts.insert(*hash, *count);
+            } else {
+                self.saturated = true;
+            }
+        }
+        self.saturated |= other.saturated;
+    }
+
+    fn summary(&self) -> Option<ResponseUniqueness> {
+        let most_common = self.counts.values().copied().max()?;
+        let total: usize = self.counts.values().sum();
+        Some(ResponseUniqueness {
+            distinct: self.counts.len(),
+            most_common,
+            most_common_percent: most_common as f64 / total as f64 * 100.0,
+            saturated: self.saturated,
+        })
+    }
+}
+
+/// Stats for one timeline window.
+///
+/// Uses a small auto-resizing histogram, since a long run has many windows.
+#[derive(Clone, Serialize, Deserialize)]
+struct TimelineWindow {
+    #[serde(with = "encoded_histogram")]
+    histogram: Histogram<u32>,
+    failed: usize,
+}
+
+impl TimelineWindow {
+    fn new() -> Self {
+        Self {
+            histogram: Histogram::new(2).expect("Failed to create histogram"),
+            failed: 0,
+        }
+    }
+}
+
+/// Records a request in the window of `windows` that `offset` falls in.
+fn record_window(windows: &mut Vec<TimelineWindow>, window: Duration, offset: Duration, duration: Duration, success: bool) {
+    let index = (offset.as_nanos() / window.as_nanos()) as usize;
+    if windows.len() <= index {
+        windows.resize_with(index + 1, TimelineWindow::new);
+    }
+    let bucket = &mut windows[index];
+    let _ = bucket.histogram.record((duration.as_micros() as u64).max(1));
+    if !success {
+        bucket.failed += 1;
+    }
+}
+
+/// Adds windows recorded by another collector, matched by position.
+fn merge_windows(windows: &mut Vec<TimelineWindow>, other: &[TimelineWindow]) {
+    if windows.len() < other.len() {
+        windows.resize_with(other.len(), TimelineWindow::new);
+    }
+    for (window, theirs) in windows.iter_mut().zip(other) {
+        let _ = window.histogram.add(&theirs.histogram);
+        window.failed += theirs.failed;
+    }
+}
+
+/// Summarizes each window; the last one ends with the run.
+fn window_points(windows: &[TimelineWindow], window: Duration, total_duration: Duration) -> Vec<TimelinePoint> {
+    let to_ms = |micros: u64| micros as f64 / 1000.0;
+    windows
+        .iter()
+        .enumerate()
+        .map(|(i, bucket)| {
+            let start = window * i as u32;
+            let end = (start + window).min(total_duration.max(start));
+            let requests = bucket.histogram.len() as usize;
+            let seconds = (end - start).as_secs_f64();
+            TimelinePoint {
+                start_ms: start.as_secs_f64() * 1000.0,
+                end_ms: end.as_secs_f64() * 1000.0,
+                requests,
+                failed_requests: bucket.failed,
+                requests_per_second: if seconds > 0.0 { requests as f64 / seconds } else { 0.0 },
+                latency_p50_ms: to_ms(bucket.histogram.value_at_quantile(0.50)),
+                latency_p95_ms: to_ms(bucket.histogram.value_at_quantile(0.95)),
+                latency_p99_ms: to_ms(bucket.histogram.value_at_quantile(0.99)),
+            }
+        })
+        .collect()
+}
+
+/// Stats for one load stage.
+#[derive(Clone, Serialize, Deserialize)]
+struct StageBucket {
+    label: String,
+    start: Duration,
+    length: Option<Duration>,
+    stats: StatsBucket,
 }
 
 /// Collects timing data during performance tests.
 ///
 /// Records individual request durations and computes aggregate metrics.
 /// Also maintains separate statistics for each unique endpoint label.
+///
+/// Collectors can be combined with [`merge`](Self::merge) or `+=`, so
+/// each worker can record into its own collector without contention.
+/// They also serialize, to be merged across processes in a distributed
+/// run; the start and finish times and the SLO are left out, since they
+/// belong to the process that reports.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct MetricsCollector {
     global: StatsBucket,
     endpoints: HashMap<String, StatsBucket>,
+    status_classes: HashMap<String, StatsBucket>,
+    status_codes: BTreeMap<u16, usize>,
+    error_categories: BTreeMap<String, usize>,
+    http_versions: BTreeMap<String, usize>,
+    #[serde(default)]
+    cache_statuses: BTreeMap<String, usize>,
+    validation: Vec<AssertionMetrics>,
+    timeline_window: Option<Duration>,
+    timeline: Vec<TimelineWindow>,
+    #[serde(skip)]
+    slo: Option<Slo>,
+    slo_windows: Vec<TimelineWindow>,
+    responses: ResponseCounts,
+    endpoint_responses: HashMap<String, ResponseCounts>,
+    #[serde(with = "encoded_histogram")]
+    delay: Histogram<u64>,
+    #[serde(with = "encoded_histogram")]
+    network: Histogram<u64>,
+    delay_total: Duration,
+    latency_total: Duration,
+    #[serde(default = "PhaseHistograms::new")]
+    phases: PhaseHistograms,
+    #[serde(default)]
+    bytes_sent: u64,
+    #[serde(default)]
+    bytes_received: u64,
+    #[serde(default)]
+    sized_requests: usize,
+    #[serde(default)]
+    sized_responses: usize,
+    stages: Vec<StageBucket>,
+    #[serde(default = "StatsBucket::new")]
+    iterations: StatsBucket,
+    #[serde(default)]
+    priority_labels: Vec<String>,
+    /// Every latency in microseconds, until there are more than `exact_limit`
+    #[serde(default)]
+    exact: Vec<u64>,
+    #[serde(default = "default_exact_limit")]
+    exact_limit: usize,
+    /// Whether the run outgrew `exact_limit`, dropping the exact samples
+    #[serde(default)]
+    exact_overflow: bool,
+    #[serde(skip)]
     start_time: Option<std::time::Instant>,
+    #[serde(skip)]
     end_time: Option<std::time::Instant>,
 }
 
@@ -133,6 +805,33 @@ impl MetricsCollector {
         Self {
             global: StatsBucket::new(),
             endpoints: HashMap::new(),
+            status_classes: HashMap::new(),
+            status_codes: BTreeMap::new(),
+            error_categories: BTreeMap::new(),
+            http_versions: BTreeMap::new(),
+            cache_statuses: BTreeMap::new(),
+            validation: Vec::new(),
+            timeline_window: None,
+            timeline: Vec::new(),
+            slo: None,
+            slo_windows: Vec::new(),
+            responses: ResponseCounts::default(),
+            endpoint_responses: HashMap::new(),
+            delay: latency_histogram(),
+            network: latency_histogram(),
+            delay_total: Duration::ZERO,
+            latency_total: Duration::ZERO,
+            phases: PhaseHistograms::new(),
+            bytes_sent: 0,
+            bytes_received: 0,
+            sized_requests: 0,
+            sized_responses: 0,
+            stages: Vec::new(),
+            iterations: StatsBucket::new(),
+            priority_labels: Vec::new(),
+            exact: Vec::new(),
+            exact_limit: DEFAULT_EXACT_SAMPLES,
+            exact_overflow: false,
             start_time: None,
             end_time: None,
         }
@@ -148,11 +847,36 @@ impl MetricsCollector {
         self.end_time = Some(std::time::Instant::now());
     }
 
+    /// Sets how many requests a run may have and still get exact
+    /// percentiles (default [`DEFAULT_EXACT_SAMPLES`]; 0 never does).
+    ///
+    /// Up to that many latencies are kept as they are; a longer run drops
+    /// them and falls back to the histogram, whose bucketed values can be
+    /// visibly off in short tests.
+    pub fn set_exact_samples(&mut self, limit: usize) {
+        self.exact_limit = limit;
+        self.keep_exact(Vec::new());
+    }
+
+    /// Adds latencies to the exact samples, or drops them all once there
+    /// are too many.
+    fn keep_exact(&mut self, micros: Vec<u64>) {
+        if self.exact_overflow {
+            return;
+        }
+        self.exact.extend(micros);
+        if self.exact.len() > self.exact_limit {
+            self.exact = Vec::new();
+            self.exact_overflow = true;
+        }
+    }
+
     /// Records a successful request with its duration.
     ///
     /// If a `label` is provided, the metric is also recorded in the corresponding
     /// endpoint bucket.
     pub fn record_success(&mut self, duration: Duration, label: Option<&str>) {
+        self.keep_exact(vec![duration.as_micros() as u64]);
         self.global.record_success(duration);
         if let Some(lbl) = label {
             self.endpoints
@@ -167,6 +891,7 @@ impl MetricsCollector {
     /// If a `label` is provided, the metric is also recorded in the corresponding
     /// endpoint bucket.
     pub fn record_failure(&mut self, duration: Duration, label: Option<&str>) {
+        self.keep_exact(vec![duration.as_micros() as u64]);
         self.global.record_failure(duration);
         if let Some(lbl) = label {
             self.endpoints
@@ -176,6 +901,268 @@ impl MetricsCollector {
         }
     }
 
+    /// Records the latency of a response in its status class bucket.
+    ///
+    /// Responses are grouped by the first digit of the status code, so that
+    /// fast error paths don't skew the percentiles of successful requests.
+    /// Within the `3xx` class, a `304 Not Modified` counts as a success.
+    /// The exact status code is also counted.
+    /// This is independent of [`record_success`](Self::record_success) and
+    /// [`record_failure`](Self::record_failure), which maintain the totals.
+    pub fn record_status(&mut self, duration: Duration, status: u16) {
+        *self.status_codes.entry(status).or_insert(0) += 1;
+        let bucket = self.status_classes
+            .entry(format!("{}xx", status / 100))
+            .or_insert_with(StatsBucket::new);
+        if (200..300).contains(&status) || status == 304 {
+            bucket.record_success(duration);
+        } else {
+            bucket.record_failure(duration);
+        }
+    }
+
+    /// Counts a failed request under its cause.
+    ///
+    /// `category` is an error kind such as `timeout` or `dns` (see
+    /// [`RurlError::kind`](crate::error::RurlError::kind)), or `non_2xx`
+    /// for a response with an unsuccessful status.
+    pub fn record_error(&mut self, category: &str) {
+        *self.error_categories.entry(category.to_string()).or_insert(0) += 1;
+    }
+
+    /// Counts a response under the protocol version it arrived over.
+    pub fn record_http_version(&mut self, version: &str) {
+        *self.http_versions.entry(version.to_string()).or_insert(0) += 1;
+    }
+
+    /// Counts a response under how the response cache answered it.
+    pub fn record_cache_status(&mut self, status: &str) {
+        *self.cache_statuses.entry(status.to_string()).or_insert(0) += 1;
+    }
+
+    /// Sets the window length of the latency-over-time timeline.
+    ///
+    /// With `None` (the default) no timeline is kept.
+    pub fn set_timeline_window(&mut self, window: Option<Duration>) {
+        self.timeline_window = window.filter(|w| !w.is_zero());
+    }
+
+    /// Sets the SLO checked per window; its windows are kept separately
+    /// from the timeline's.
+    pub fn set_slo(&mut self, slo: Option<Slo>) {
+        self.slo = slo;
+    }
+
+    /// Records a request in the timeline and SLO windows it finished in.
+    ///
+    /// Ignored unless a window is set and the collector was started.
+    pub fn record_timeline(&mut self, finished: std::time::Instant, duration: Duration, success: bool) {
+        let Some(start) = self.start_time else { return };
+        let offset = finished.saturating_duration_since(start);
+        if let Some(window) = self.timeline_window {
+            record_window(&mut self.timeline, window, offset, duration, success);
+        }
+        if let Some(slo) = &self.slo {
+            record_window(&mut self.slo_windows, slo.window(), offset, duration, success);
+        }
+    }
+
+    /// Counts a response by a hash of its status and body.
+    ///
+    /// Only the first [`MAX_DISTINCT_RESPONSES`] distinct hashes are kept,
+    /// overall and per endpoint.
+    pub fn record_response_hash(&mut self, hash: u64, label: Option<&str>) {
+        self.responses.record(hash);
+        if let Some(label) = label {
+            self.endpoint_responses.entry(label.to_string()).or_default().record(hash);
+        }
+    }
+
+    /// Sets the response assertions results can be recorded against.
+    pub fn set_assertions(&mut self, assertions: Vec<String>) {
+        self.validation = assertions
+            .into_iter()
+            .map(|assertion| AssertionMetrics { assertion, passed: 0, failed: 0, examples: Vec::new() })
+            .collect();
+    }
+
+    /// Records the outcome of one assertion for one response.
+    ///
+    /// `failure` is `None` if the assertion held, or why it didn't. Ignored
+    /// if `index` is not one of the assertions given to
+    /// [`set_assertions`](Self::set_assertions).
+    pub fn record_assertion(&mut self, index: usize, failure: Option<String>) {
+        let Some(assertion) = self.validation.get_mut(index) else { return };
+        match failure {
+            None => assertion.passed += 1,
+            Some(failure) => {
+                assertion.failed += 1;
+                if assertion.examples.len() < ASSERTION_EXAMPLES {
+                    assertion.examples.push(failure);
+                }
+            }
+        }
+    }
+
+    /// Records how much of a request's latency was dispatch delay.
+    ///
+    /// `latency` is the full time from issuing the request to completion,
+    /// as passed to [`record_success`](Self::record_success); `delay` is the
+    /// part spent before the request was handed to the HTTP client.
+    pub fn record_dispatch_delay(&mut self, latency: Duration, delay: Duration) {
+        let delay = delay.min(latency);
+        record_micros(&mut self.delay, delay);
+        record_micros(&mut self.network, latency - delay);
+        self.delay_total += delay;
+        self.latency_total += latency;
+    }
+
+    /// Records how long each phase of a request took.
+    pub fn record_phases(&mut self, phases: &PhaseTimings) {
+        self.phases.record(phases);
+    }
+
+    /// Records the body sizes of a request and its response.
+    ///
+    /// `received` is `None` if the request got no response.
+    pub fn record_bytes(&mut self, sent: u64, received: Option<u64>) {
+        self.bytes_sent += sent;
+        self.sized_requests += 1;
+        if let Some(received) = received {
+            self.bytes_received += received;
+            self.sized_responses += 1;
+        }
+    }
+
+    /// Sets the load stages requests can be recorded against.
+    ///
+    /// Each stage is a label, its start offset in the run, and its length
+    /// (`None` if it lasts until the end of the run).
+    pub fn set_stages(&mut self, stages: Vec<(String, Duration, Option<Duration>)>) {
+        self.stages = stages
+            .into_iter()
+            .map(|(label, start, length)| StageBucket { label, start, length, stats: StatsBucket::new() })
+            .collect();
+    }
+
+    /// Records a request in the stage it was started in.
+    ///
+    /// Ignored if `stage` is not one of the stages given to
+    /// [`set_stages`](Self::set_stages).
+    pub fn record_stage(&mut self, stage: usize, duration: Duration, success: bool) {
+        if let Some(bucket) = self.stages.get_mut(stage) {
+            if success {
+                bucket.stats.record_success(duration);
+            } else {
+                bucket.stats.record_failure(duration);
+            }
+        }
+    }
+
+    /// Records one scenario iteration of a virtual user.
+    ///
+    /// `success` is false if any request of the iteration failed.
+    pub fn record_iteration(&mut self, duration: Duration, success: bool) {
+        if success {
+            self.iterations.record_success(duration);
+        } else {
+            self.iterations.record_failure(duration);
+        }
+    }
+
+    /// Sets the endpoint labels of priority requests, to report them
+    /// apart from the rest in [`PerfMetrics::traffic_classes`].
+    pub fn set_priority_labels(&mut self, labels: Vec<String>) {
+        self.priority_labels = labels;
+    }
+
+    /// Adds everything recorded by another collector to this one, also
+    /// available as `+=`.
+    ///
+    /// A local run records into the single collector its aggregator task
+    /// owns, so this is for collectors recorded apart, such as the workers
+    /// of a distributed run, which the controller merges.
+    ///
+    /// The merged run spans from the earlier start to the later finish.
+    /// Timeline windows are matched by position, so collectors should be
+    /// started together. Stages are matched by position; `other`'s stages
+    /// are only used if this collector has none.
+    pub fn merge(&mut self, other: &MetricsCollector) {
+        self.global.merge(&other.global);
+        for (label, bucket) in &other.endpoints {
+            self.endpoints.entry(label.clone()).or_insert_with(StatsBucket::new).merge(bucket);
+        }
+        for (class, bucket) in &other.status_classes {
+            self.status_classes.entry(class.clone()).or_insert_with(StatsBucket::new).merge(bucket);
+        }
+        for (status, count) in &other.status_codes {
+            *self.status_codes.entry(*status).or_insert(0) += count;
+        }
+        for (category, count) in &other.error_categories {
+            *self.error_categories.entry(category.clone()).or_insert(0) += count;
+        }
+        for (version, count) in &other.http_versions {
+            *self.http_versions.entry(version.clone()).or_insert(0) += count;
+        }
+        for (status, count) in &other.cache_statuses {
+            *self.cache_statuses.entry(status.clone()).or_insert(0) += count;
+        }
+        self.timeline_window = self.timeline_window.or(other.timeline_window);
+        merge_windows(&mut self.timeline, &other.timeline);
+        self.slo = self.slo.take().or_else(|| other.slo.clone());
+        merge_windows(&mut self.slo_windows, &other.slo_windows);
+        self.responses.merge(&other.responses);
+        for (label, counts) in &other.endpoint_responses {
+            self.endpoint_responses.entry(label.clone()).or_default().merge(counts);
+        }
+        if self.validation.is_empty() {
+            self.validation = other.validation.clone();
+        } else {
+            for (assertion, theirs) in self.validation.iter_mut().zip(&other.validation) {
+                assertion.passed += theirs.passed;
+                assertion.failed += theirs.failed;
+                let room = ASSERTION_EXAMPLES.saturating_sub(assertion.examples.len());
+                assertion.examples.extend(theirs.examples.iter().take(room).cloned());
+            }
+        }
+        let _ = self.delay.add(&other.delay);
+        let _ = self.network.add(&other.network);
+        self.delay_total += other.delay_total;
+        self.latency_total += other.latency_total;
+        self.phases.merge(&other.phases);
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+        self.sized_requests += other.sized_requests;
+        self.sized_responses += other.sized_responses;
+
+        if self.stages.is_empty() {
+            self.stages = other.stages.clone();
+        } else {
+            for (stage, bucket) in self.stages.iter_mut().zip(&other.stages) {
+                stage.stats.merge(&bucket.stats);
+            }
+        }
+        self.iterations.merge(&other.iterations);
+        if self.priority_labels.is_empty() {
+            self.priority_labels = other.priority_labels.clone();
+        }
+        if other.exact_overflow {
+            self.exact = Vec::new();
+            self.exact_overflow = true;
+        } else {
+            self.keep_exact(other.exact.clone());
+        }
+
+        self.start_time = match (self.start_time, other.start_time) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.end_time = match (self.end_time, other.end_time) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
     /// Computes final metrics from collected data.
     ///
     /// Returns a [`PerfMetrics`] struct with all aggregate statistics.
@@ -184,17 +1171,215 @@ impl MetricsCollector {
             (Some(start), Some(end)) => end.duration_since(start),
             _ => Duration::ZERO,
         };
+        let mut metrics = self.metrics_for(total_duration);
+        if let Some(window) = self.timeline_window {
+            metrics.timeline = window_points(&self.timeline, window, total_duration);
+        }
+        if let Some(slo) = &self.slo {
+            metrics.slo = Some(slo.evaluate(&window_points(&self.slo_windows, slo.window(), total_duration)));
+        }
+        metrics
+    }
+
+    /// Computes metrics for a run that is still in progress.
+    ///
+    /// Like [`compute_metrics`](Self::compute_metrics), but measures the
+    /// duration up to now instead of up to [`finish`](Self::finish).
+    pub fn snapshot(&self) -> PerfMetrics {
+        let total_duration = self.start_time
+            .map(|start| start.elapsed())
+            .unwrap_or(Duration::ZERO);
+        self.metrics_for(total_duration)
+    }
 
+    fn metrics_for(&self, total_duration: Duration) -> PerfMetrics {
         let mut metrics = self.global.compute_metrics(total_duration);
+        metrics.percentile_method = Some(self.apply_exact_percentiles(&mut metrics));
         
         let endpoint_metrics: HashMap<String, PerfMetrics> = self.endpoints
             .iter()
-            .map(|(k, v)| (k.clone(), v.compute_metrics(total_duration)))
+            .map(|(k, v)| {
+                let mut endpoint = v.compute_metrics(total_duration);
+                endpoint.responses = self.endpoint_responses.get(k).and_then(ResponseCounts::summary);
+                (k.clone(), endpoint)
+            })
             .collect();
 
         metrics.endpoints = endpoint_metrics;
+        metrics.status_classes = self.status_classes
+            .iter()
+            .map(|(k, v)| (k.clone(), v.compute_metrics(total_duration)))
+            .collect();
+        metrics.status_codes = self.status_codes.clone();
+        metrics.error_categories = self.error_categories.clone();
+        metrics.http_versions = self.http_versions.clone();
+        metrics.cache_statuses = self.cache_statuses.clone();
+        metrics.validation = self.validation.clone();
+        metrics.histogram = latency_buckets(&self.global.histogram, HISTOGRAM_BUCKETS);
+        metrics.dispatch_delay = self.dispatch_delay_metrics();
+        metrics.transfer = self.transfer_metrics(total_duration);
+        metrics.phases = self.phases.summary();
+        metrics.responses = self.responses.summary();
+        metrics.iterations = self.iteration_metrics(total_duration);
+        metrics.traffic_classes = self.traffic_classes(total_duration);
+        metrics.stages = self.stages
+            .iter()
+            .filter(|stage| stage.start < total_duration)
+            .map(|stage| {
+                // A stage cut short by the end of the run only counts the time it ran
+                let ran = total_duration - stage.start;
+                let length = stage.length.map_or(ran, |length| length.min(ran));
+                StageMetrics {
+                    stage: stage.label.clone(),
+                    metrics: stage.stats.compute_metrics(length),
+                }
+            })
+            .collect();
         metrics
     }
+
+    fn transfer_metrics(&self, total_duration: Duration) -> Option<TransferMetrics> {
+        if self.sized_requests == 0 {
+            return None;
+        }
+        let seconds = total_duration.as_secs_f64();
+        let mb_per_second = |bytes: u64| if seconds > 0.0 { bytes as f64 / 1_000_000.0 / seconds } else { 0.0 };
+        Some(TransferMetrics {
+            bytes_sent: self.bytes_sent,
+            bytes_received: self.bytes_received,
+            avg_request_bytes: self.bytes_sent as f64 / self.sized_requests as f64,
+            avg_response_bytes: self.bytes_received as f64 / self.sized_responses.max(1) as f64,
+            sent_mb_per_second: mb_per_second(self.bytes_sent),
+            received_mb_per_second: mb_per_second(self.bytes_received),
+        })
+    }
+
+    /// Replaces the overall latency figures with exact ones if every
+    /// sample was kept, and returns the method the figures came from.
+    fn apply_exact_percentiles(&self, metrics: &mut PerfMetrics) -> PercentileMethod {
+        if self.exact_overflow || self.exact.is_empty() {
+            return PercentileMethod::Histogram;
+        }
+        let mut sorted = self.exact.clone();
+        sorted.sort_unstable();
+        let to_ms = |micros: u64| micros as f64 / 1000.0;
+        metrics.latency_min_ms = to_ms(sorted[0]);
+        metrics.latency_max_ms = to_ms(sorted[sorted.len() - 1]);
+        metrics.latency_avg_ms = sorted.iter().sum::<u64>() as f64 / sorted.len() as f64 / 1000.0;
+        metrics.latency_p50_ms = to_ms(nearest_rank(&sorted, 50.0));
+        metrics.latency_p95_ms = to_ms(nearest_rank(&sorted, 95.0));
+        metrics.latency_p99_ms = to_ms(nearest_rank(&sorted, 99.0));
+        PercentileMethod::Exact
+    }
+
+    fn traffic_classes(&self, total_duration: Duration) -> Vec<TrafficClassMetrics> {
+        if self.priority_labels.is_empty() {
+            return Vec::new();
+        }
+        let mut labels: Vec<&String> = self.endpoints.keys().collect();
+        labels.sort();
+        let (priority, background): (Vec<_>, Vec<_>) = labels
+            .into_iter()
+            .partition(|label| self.priority_labels.contains(label));
+        [("priority", priority), ("background", background)]
+            .into_iter()
+            .filter(|(_, labels)| !labels.is_empty())
+            .map(|(class, labels)| {
+                let mut stats = StatsBucket::new();
+                for label in &labels {
+                    stats.merge(&self.endpoints[*label]);
+                }
+                TrafficClassMetrics {
+                    class: class.to_string(),
+                    labels: labels.into_iter().cloned().collect(),
+                    metrics: stats.compute_metrics(total_duration),
+                }
+            })
+            .collect()
+    }
+
+    fn iteration_metrics(&self, total_duration: Duration) -> Option<IterationMetrics> {
+        let iterations = self.iterations.successful + self.iterations.failed;
+        let seconds = total_duration.as_secs_f64();
+        Some(IterationMetrics {
+            iterations,
+            failed_iterations: self.iterations.failed,
+            iterations_per_second: if seconds > 0.0 { iterations as f64 / seconds } else { 0.0 },
+            duration: LatencySummary::from_histogram(&self.iterations.histogram)?,
+        })
+    }
+
+    fn dispatch_delay_metrics(&self) -> Option<DispatchDelayMetrics> {
+        if self.delay.is_empty() {
+            return None;
+        }
+        let to_ms = |micros: u64| micros as f64 / 1000.0;
+        let delay_percent = if self.latency_total.is_zero() {
+            0.0
+        } else {
+            self.delay_total.as_secs_f64() / self.latency_total.as_secs_f64() * 100.0
+        };
+        Some(DispatchDelayMetrics {
+            delay_avg_ms: self.delay.mean() / 1000.0,
+            delay_p99_ms: to_ms(self.delay.value_at_percentile(99.0)),
+            delay_percent,
+            network_p50_ms: to_ms(self.network.value_at_percentile(50.0)),
+            network_p95_ms: to_ms(self.network.value_at_percentile(95.0)),
+            network_p99_ms: to_ms(self.network.value_at_percentile(99.0)),
+        })
+    }
+}
+
+/// Percentiles over the most recent request latencies.
+///
+/// Unlike [`MetricsCollector`], which covers the whole run, this only keeps
+/// a fixed-size window, so live displays reflect current behavior rather
+/// than being smoothed out by everything that came before.
+#[derive(Debug, Clone)]
+pub struct RollingLatency {
+    window: VecDeque<Duration>,
+    capacity: usize,
+}
+
+impl RollingLatency {
+    /// Creates a window holding the last `capacity` latencies.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            window: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Adds a latency, evicting the oldest if the window is full.
+    pub fn record(&mut self, duration: Duration) {
+        if self.window.len() == self.capacity {
+            self.window.pop_front();
+        }
+        self.window.push_back(duration);
+    }
+
+    /// Returns the given percentile (0-100) of the window, if it has any samples.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.window.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.window.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.clamp(1, sorted.len()) - 1])
+    }
+}
+
+impl AddAssign<&MetricsCollector> for MetricsCollector {
+    fn add_assign(&mut self, other: &MetricsCollector) {
+        self.merge(other);
+    }
+}
+
+impl AddAssign for MetricsCollector {
+    fn add_assign(&mut self, other: MetricsCollector) {
+        self.merge(&other);
+    }
 }
 
 impl Default for MetricsCollector {
@@ -225,6 +1410,70 @@ mod tests {
         assert_eq!(metrics.failed_requests, 0);
     }
 
+    #[test]
+    fn test_iterations() {
+        let mut collector = MetricsCollector::new();
+        assert!(collector.compute_metrics().iterations.is_none());
+        collector.record_iteration(Duration::from_millis(100), true);
+        let mut other = MetricsCollector::new();
+        other.record_iteration(Duration::from_millis(300), false);
+        collector.merge(&other);
+        let iterations = collector.compute_metrics().iterations.unwrap();
+        assert_eq!(iterations.iterations, 2);
+        assert_eq!(iterations.failed_iterations, 1);
+        assert_eq!(iterations.duration.requests, 2);
+        assert!((iterations.duration.max_ms - 300.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_traffic_classes() {
+        let mut collector = MetricsCollector::new();
+        collector.record_success(Duration::from_millis(10), Some("checkout"));
+        collector.record_success(Duration::from_millis(50), Some("search"));
+        collector.record_failure(Duration::from_millis(70), Some("GET /feed"));
+        assert!(collector.compute_metrics().traffic_classes.is_empty());
+
+        collector.set_priority_labels(vec!["checkout".to_string()]);
+        let classes = collector.compute_metrics().traffic_classes;
+        assert_eq!(classes.len(), 2);
+        assert_eq!(classes[0].class, "priority");
+        assert_eq!(classes[0].labels, ["checkout"]);
+        assert_eq!(classes[0].metrics.total_requests, 1);
+        assert_eq!(classes[1].labels, ["GET /feed", "search"]);
+        assert_eq!(classes[1].metrics.failed_requests, 1);
+    }
+
+    #[test]
+    fn test_exact_percentiles() {
+        let mut collector = MetricsCollector::new();
+        for ms in [1, 2, 3, 4, 1003] {
+            collector.record_success(Duration::from_micros(ms * 1000 + 1), None);
+        }
+        let metrics = collector.compute_metrics();
+        assert_eq!(metrics.percentile_method, Some(PercentileMethod::Exact));
+        assert_eq!(metrics.latency_p50_ms, 3.001);
+        assert_eq!(metrics.latency_p95_ms, 1003.001);
+        assert_eq!(metrics.latency_max_ms, 1003.001);
+
+        // Past the limit, the histogram is used and the samples dropped
+        collector.set_exact_samples(4);
+        let metrics = collector.compute_metrics();
+        assert_eq!(metrics.percentile_method, Some(PercentileMethod::Histogram));
+        assert_ne!(metrics.latency_max_ms, 1003.001);
+        assert!(collector.exact.is_empty());
+
+        // Merged runs stay exact while they fit together
+        let mut a = MetricsCollector::new();
+        let mut b = MetricsCollector::new();
+        a.record_success(Duration::from_millis(10), None);
+        b.record_failure(Duration::from_millis(30), None);
+        a.merge(&b);
+        let metrics = a.compute_metrics();
+        assert_eq!(metrics.percentile_method, Some(PercentileMethod::Exact));
+        assert_eq!(metrics.latency_p99_ms, 30.0);
+        assert_eq!(metrics.latency_avg_ms, 20.0);
+    }
+
     #[test]
     fn test_record_failure_global() {
         let mut collector = MetricsCollector::new();
@@ -233,6 +1482,73 @@ mod tests {
         assert_eq!(metrics.failed_requests, 1);
     }
 
+    #[test]
+    fn test_success_and_failure_latency() {
+        let mut collector = MetricsCollector::new();
+        for _ in 0..9 {
+            collector.record_success(Duration::from_millis(200), Some("GET /"));
+        }
+        // Connection refused comes back almost immediately
+        collector.record_failure(Duration::from_millis(1), Some("GET /"));
+        let metrics = collector.compute_metrics();
+        assert!(metrics.latency_min_ms < 2.0);
+
+        let success = metrics.success_latency.unwrap();
+        assert_eq!(success.requests, 9);
+        assert!(success.min_ms >= 199.0 && success.p50_ms >= 199.0);
+        let failure = metrics.failure_latency.unwrap();
+        assert_eq!(failure.requests, 1);
+        assert!(failure.p99_ms < 2.0);
+        assert_eq!(metrics.endpoints["GET /"].success_latency.as_ref().unwrap().requests, 9);
+
+        let mut collector = MetricsCollector::new();
+        collector.record_success(Duration::from_millis(5), None);
+        assert!(collector.compute_metrics().failure_latency.is_none());
+    }
+
+    #[test]
+    fn test_transfer() {
+        let mut collector = MetricsCollector::new();
+        assert!(collector.compute_metrics().transfer.is_none());
+        collector.start();
+        collector.record_bytes(100, Some(2_000_000));
+        collector.record_bytes(100, Some(0));
+        collector.record_bytes(100, None);
+        collector.finish();
+
+        let mut merged = MetricsCollector::new();
+        merged.merge(&collector);
+        let transfer = merged.compute_metrics().transfer.unwrap();
+        assert_eq!(transfer.bytes_sent, 300);
+        assert_eq!(transfer.bytes_received, 2_000_000);
+        assert_eq!(transfer.avg_request_bytes, 100.0);
+        assert_eq!(transfer.avg_response_bytes, 1_000_000.0);
+        assert!(transfer.received_mb_per_second > 0.0);
+    }
+
+    #[test]
+    fn test_phases() {
+        let mut collector = MetricsCollector::new();
+        assert!(collector.compute_metrics().phases.is_none());
+        collector.record_phases(&PhaseTimings {
+            dns: Some(Duration::from_millis(3)),
+            connect: Some(Duration::from_millis(5)),
+            tls: None,
+            ttfb: Duration::from_millis(40),
+            transfer: Duration::from_millis(2),
+        });
+        collector.record_phases(&PhaseTimings { ttfb: Duration::from_millis(30), ..PhaseTimings::default() });
+
+        let mut merged = MetricsCollector::new();
+        merged.merge(&collector);
+        let phases = merged.compute_metrics().phases.unwrap();
+        assert_eq!(phases.dns.as_ref().unwrap().requests, 1);
+        assert!(phases.tls.is_none());
+        assert_eq!(phases.ttfb.requests, 2);
+        let names: Vec<_> = phases.phases().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["dns", "connect", "ttfb", "transfer"]);
+    }
+
     #[test]
     fn test_record_with_endpoints() {
         let mut collector = MetricsCollector::new();
@@ -258,4 +1574,229 @@ mod tests {
         assert_eq!(login_metrics.total_requests, 1);
         assert_eq!(login_metrics.failed_requests, 1);
     }
+
+    #[test]
+    fn test_snapshot_while_running() {
+        let mut collector = MetricsCollector::new();
+        collector.start();
+        collector.record_success(Duration::from_millis(10), None);
+        let metrics = collector.snapshot();
+        assert_eq!(metrics.total_requests, 1);
+        assert!(metrics.total_duration_ms >= 0.0);
+    }
+
+    #[test]
+    fn test_record_dispatch_delay() {
+        let mut collector = MetricsCollector::new();
+        assert!(collector.compute_metrics().dispatch_delay.is_none());
+
+        collector.record_dispatch_delay(Duration::from_millis(100), Duration::from_millis(60));
+        collector.record_dispatch_delay(Duration::from_millis(100), Duration::from_millis(20));
+
+        let delay = collector.compute_metrics().dispatch_delay.unwrap();
+        assert!((delay.delay_percent - 40.0).abs() < 0.1);
+        assert!((delay.delay_avg_ms - 40.0).abs() < 0.1);
+        assert!(delay.network_p99_ms >= 79.0 && delay.network_p99_ms <= 81.0);
+    }
+
+    #[test]
+    fn test_rolling_latency_window() {
+        let mut rolling = RollingLatency::new(100);
+        assert_eq!(rolling.percentile(99.0), None);
+
+        for ms in 1..=100 {
+            rolling.record(Duration::from_millis(ms));
+        }
+        assert_eq!(rolling.percentile(99.0), Some(Duration::from_millis(99)));
+
+        // Old slow samples fall out of the window
+        for _ in 0..100 {
+            rolling.record(Duration::from_millis(5));
+        }
+        assert_eq!(rolling.percentile(99.0), Some(Duration::from_millis(5)));
+    }
+
+    #[test]
+    fn test_record_status_classes() {
+        let mut collector = MetricsCollector::new();
+        collector.record_status(Duration::from_millis(100), 200);
+        collector.record_status(Duration::from_millis(120), 201);
+        collector.record_status(Duration::from_millis(5), 404);
+        collector.record_status(Duration::from_millis(900), 503);
+
+        let metrics = collector.compute_metrics();
+        assert_eq!(metrics.status_classes.len(), 3);
+        assert_eq!(metrics.status_codes, [(200, 1), (201, 1), (404, 1), (503, 1)].into());
+
+        let ok = metrics.status_classes.get("2xx").unwrap();
+        assert_eq!(ok.total_requests, 2);
+        assert!(ok.latency_min_ms >= 99.0);
+
+        let client_errors = metrics.status_classes.get("4xx").unwrap();
+        assert_eq!(client_errors.failed_requests, 1);
+        assert!(client_errors.latency_max_ms < 10.0);
+    }
+
+    #[test]
+    fn test_record_stages() {
+        let mut collector = MetricsCollector::new();
+        collector.set_stages(vec![
+            ("0 → 10 over 1m".to_string(), Duration::ZERO, Some(Duration::from_secs(60))),
+            ("hold at 10".to_string(), Duration::from_secs(60), None),
+        ]);
+        collector.start();
+        collector.record_stage(0, Duration::from_millis(10), true);
+        collector.record_stage(0, Duration::from_millis(30), false);
+        collector.record_stage(5, Duration::from_millis(30), true);
+        collector.finish();
+
+        // The run ended during the first stage, so the second isn't reported
+        let metrics = collector.compute_metrics();
+        assert_eq!(metrics.stages.len(), 1);
+        assert_eq!(metrics.stages[0].stage, "0 → 10 over 1m");
+        assert_eq!(metrics.stages[0].metrics.total_requests, 2);
+        assert_eq!(metrics.stages[0].metrics.failed_requests, 1);
+    }
+
+    #[test]
+    fn test_latency_histogram() {
+        let mut collector = MetricsCollector::new();
+        for ms in [10, 11, 12, 10, 500, 510] {
+            collector.record_success(Duration::from_millis(ms), None);
+        }
+        let metrics = collector.compute_metrics();
+        assert_eq!(metrics.histogram.len(), HISTOGRAM_BUCKETS);
+        assert_eq!(metrics.histogram.iter().map(|b| b.count).sum::<u64>(), 6);
+        // Fast and slow requests land at opposite ends
+        assert_eq!(metrics.histogram[0].count + metrics.histogram[1].count, 4);
+        assert_eq!(metrics.histogram[HISTOGRAM_BUCKETS - 1].count, 2);
+        assert!(metrics.endpoints.values().all(|m| m.histogram.is_empty()));
+
+        let mut single = MetricsCollector::new();
+        single.record_success(Duration::from_millis(5), None);
+        assert_eq!(single.compute_metrics().histogram.len(), 1);
+        assert!(MetricsCollector::new().compute_metrics().histogram.is_empty());
+    }
+
+    #[test]
+    fn test_timeline() {
+        let mut collector = MetricsCollector::new();
+        collector.record_timeline(std::time::Instant::now(), Duration::from_millis(5), true);
+        collector.set_timeline_window(Some(Duration::from_millis(100)));
+        collector.start();
+        let start = collector.start_time.unwrap();
+        collector.record_timeline(start + Duration::from_millis(10), Duration::from_millis(10), true);
+        collector.record_timeline(start + Duration::from_millis(50), Duration::from_millis(20), false);
+        collector.record_timeline(start + Duration::from_millis(250), Duration::from_millis(300), true);
+        collector.end_time = Some(start + Duration::from_millis(280));
+
+        let timeline = collector.compute_metrics().timeline;
+        assert_eq!(timeline.len(), 3);
+        assert_eq!((timeline[0].requests, timeline[0].failed_requests), (2, 1));
+        assert!((timeline[0].requests_per_second - 20.0).abs() < 0.01);
+        assert!(timeline[0].latency_p99_ms >= 19.0);
+        assert_eq!(timeline[1].requests, 0);
+        // The last window is cut short by the end of the run
+        assert_eq!(timeline[2].end_ms, 280.0);
+        assert!((timeline[2].requests_per_second - 1.0 / 0.08).abs() < 0.01);
+        assert!(collector.snapshot().timeline.is_empty());
+    }
+
+    #[test]
+    fn test_record_assertions() {
+        let mut collector = MetricsCollector::new();
+        collector.set_assertions(vec!["status == 200".to_string()]);
+        collector.record_assertion(0, None);
+        for i in 0..5 {
+            collector.record_assertion(0, Some(format!("failure {}", i)));
+        }
+        collector.record_assertion(7, None);
+
+        let validation = collector.compute_metrics().validation;
+        assert_eq!(validation.len(), 1);
+        assert_eq!(validation[0].passed, 1);
+        assert_eq!(validation[0].failed, 5);
+        assert_eq!(validation[0].examples, vec!["failure 0", "failure 1", "failure 2"]);
+    }
+
+    #[test]
+    fn test_response_uniqueness() {
+        let mut a = MetricsCollector::new();
+        for hash in [1, 1, 1, 2] {
+            a.record_success(Duration::from_millis(10), Some("GET /a"));
+            a.record_response_hash(hash, Some("GET /a"));
+        }
+        let mut b = MetricsCollector::new();
+        b.record_success(Duration::from_millis(10), Some("GET /b"));
+        b.record_response_hash(1, Some("GET /b"));
+        a.merge(&b);
+
+        let metrics = a.compute_metrics();
+        let overall = metrics.responses.unwrap();
+        assert_eq!(overall.distinct, 2);
+        assert_eq!(overall.most_common, 4);
+        assert_eq!(overall.most_common_percent, 80.0);
+        assert!(!overall.saturated);
+        assert_eq!(metrics.endpoints["GET /b"].responses.as_ref().unwrap().distinct, 1);
+        assert!(MetricsCollector::new().compute_metrics().responses.is_none());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = MetricsCollector::new();
+        a.start();
+        a.record_success(Duration::from_millis(10), Some("GET /a"));
+        a.record_status(Duration::from_millis(10), 200);
+
+        let mut b = MetricsCollector::new();
+        b.record_success(Duration::from_millis(20), Some("GET /a"));
+        b.record_failure(Duration::from_millis(500), Some("GET /b"));
+        b.record_status(Duration::from_millis(500), 503);
+        b.record_error("non_2xx");
+        b.record_dispatch_delay(Duration::from_millis(500), Duration::from_millis(100));
+
+        a.merge(&b);
+        a += MetricsCollector::new();
+        a.finish();
+
+        let metrics = a.compute_metrics();
+        assert_eq!(metrics.total_requests, 3);
+        assert_eq!(metrics.failed_requests, 1);
+        assert!(metrics.latency_max_ms >= 499.0);
+        assert_eq!(metrics.endpoints["GET /a"].total_requests, 2);
+        assert_eq!(metrics.endpoints["GET /b"].failed_requests, 1);
+        assert_eq!(metrics.status_classes.len(), 2);
+        assert_eq!(metrics.status_codes, [(200, 1), (503, 1)].into());
+        assert_eq!(metrics.error_categories, [("non_2xx".to_string(), 1)].into());
+        assert!(metrics.dispatch_delay.is_some());
+        assert_eq!(metrics.failure_latency.unwrap().requests, 1);
+        assert!(metrics.total_duration_ms > 0.0);
+    }
+    #[test]
+    fn test_serialized_collector_merges() {
+        let mut worker = MetricsCollector::new();
+        worker.set_timeline_window(Some(Duration::from_secs(1)));
+        worker.start();
+        worker.record_success(Duration::from_millis(12), Some("GET /a"));
+        worker.record_status(Duration::from_millis(12), 200);
+        worker.record_timeline(std::time::Instant::now(), Duration::from_millis(12), true);
+        worker.record_response_hash(7, Some("GET /a"));
+        worker.record_dispatch_delay(Duration::from_millis(12), Duration::from_millis(2));
+
+        let json = serde_json::to_string(&worker).unwrap();
+        let received: MetricsCollector = serde_json::from_str(&json).unwrap();
+
+        let mut controller = MetricsCollector::new();
+        controller.start();
+        controller.merge(&received);
+        controller.finish();
+        let metrics = controller.compute_metrics();
+        assert_eq!(metrics.total_requests, 1);
+        assert!((metrics.latency_p50_ms - 12.0).abs() < 0.1);
+        assert_eq!(metrics.endpoints["GET /a"].successful_requests, 1);
+        assert_eq!(metrics.status_codes, [(200, 1)].into());
+        assert_eq!(metrics.timeline.iter().map(|p| p.requests).sum::<usize>(), 1);
+        assert_eq!(metrics.responses.unwrap().distinct, 1);
+        assert!(metrics.dispatch_delay.is_some());
+    }
 }
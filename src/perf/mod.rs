@@ -4,16 +4,61 @@
 //! HTTP endpoints with:
 //!
 //! - [`Dataset`] - JSON dataset parsing for varied requests
+//! - [`openapi`] - Dataset generation from OpenAPI 3 specifications
 //! - [`PerfRunner`] - Concurrent request execution with progress tracking
+//! - [`RunPlan`] - Dry-run plan of requests, load schedule and duration (`--plan`)
 //! - [`PerfMetrics`] - Latency percentiles and throughput metrics
-//! - [`PerfReport`] - Text and JSON output formatting
+//! - [`RunEvent`] - Live events for progress displays and other consumers
+//! - [`RequestLog`] - Per-request NDJSON log, optionally gzip-compressed
+//! - [`StatsdSink`] - Live StatsD/DogStatsD metrics while a run is in progress
+//! - [`ReportSink`] - Text, JSON, HTML and JUnit reports, selected per `--output` target
+//! - [`PerfReport`] - Text and JSON output formatting (requires the `cli` feature)
+//! - [`SweepPoint`] - Results of a concurrency sweep, with CSV export
+//! - [`WorkerJob`] - Distributed runs split across `hurley worker` processes
+//! - [`KeepAliveBenchmark`] - Idle keep-alive connection lifetime diagnostic
+//! - [`LoadProfile`] - Ramp-up and multi-stage load profiles
+//! - [`Assertion`] - Response checks counted per request (`--assert`)
+//! - [`Threshold`] - Pass/fail limits on latency and error rate, per endpoint or tag
+//! - [`Slo`] - Per-window SLO compliance and error-budget burn for soak tests
+//! - [`HostAllowlist`] - Safety guard restricting which hosts may be load tested
+//! - [`DestructiveLoad`] - `DELETE`/`PUT`/`PATCH` load on non-test hosts, confirmed before a run
 
+pub mod assertions;
 pub mod dataset;
+pub mod distributed;
+pub mod events;
+pub mod guard;
+pub mod keepalive;
 pub mod metrics;
+pub mod openapi;
+pub mod plan;
+pub mod request_log;
 pub mod runner;
+pub mod sink;
+pub mod slo;
+pub mod stages;
+pub mod statsd;
+pub mod sweep;
+pub mod thresholds;
+#[cfg(feature = "cli")]
 pub mod report;
 
-pub use dataset::Dataset;
-pub use metrics::PerfMetrics;
-pub use runner::PerfRunner;
+pub use assertions::Assertion;
+pub use dataset::{Dataset, Sample};
+pub use distributed::{run_distributed, serve_job, WorkerJob, WorkerProgress};
+pub use events::{IntervalStats, RunEvent};
+pub use guard::{DestructiveLoad, HostAllowlist};
+pub use keepalive::{KeepAliveBenchmark, KeepAliveReport};
+pub use metrics::{AssertionMetrics, DispatchDelayMetrics, IterationMetrics, LatencyBucket, LatencySummary, PercentileMethod, PerfMetrics, PhaseMetrics, ResponseUniqueness, RollingLatency, StageMetrics, TimelinePoint, TrafficClassMetrics, TransferMetrics, DEFAULT_EXACT_SAMPLES};
+pub use plan::{PlannedEntry, PlannedPhase, RunPlan};
+pub use request_log::{LogFormat, RequestLog};
+pub use runner::{PerfRunner, ProgressHook, DEFAULT_MAX_DEFERRED};
+pub use sink::{OutputTarget, ReportFormat, ReportSink, RunReport};
+pub use slo::{Slo, SloReport, SloWindow};
+pub use stages::{LoadProfile, StageUnit};
+pub use statsd::StatsdSink;
+pub use sweep::SweepPoint;
+pub use thresholds::{Threshold, ThresholdResult};
+pub use tokio_util::sync::CancellationToken;
+#[cfg(feature = "cli")]
 pub use report::PerfReport;
@@ -0,0 +1,418 @@
+//! Dataset generation from OpenAPI 3 specifications.
+//!
+//! Synthesizes one [`DatasetEntry`] per operation in the spec, filling in
+//! path and query parameters and request bodies from the spec's examples,
+//! defaults, or schemas, so a whole API surface can be load tested without
+//! hand-writing a dataset.
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::{Result, RurlError};
+use crate::encode::percent_encode;
+use super::dataset::{Dataset, DatasetEntry};
+
+/// HTTP methods that may appear as operations on an OpenAPI path item.
+const METHODS: [&str; 7] = ["get", "post", "put", "patch", "delete", "head", "options"];
+
+/// Maximum nesting depth when synthesizing sample values from schemas.
+///
+/// Guards against recursive schemas (e.g. a tree node referencing itself).
+const MAX_SCHEMA_DEPTH: usize = 8;
+
+/// Loads an OpenAPI document (JSON or YAML) and builds a dataset from it.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or parsed, or if it defines
+/// no operations.
+pub fn dataset_from_file(path: &Path) -> Result<Dataset> {
+    let content = std::fs::read_to_string(path)?;
+    let spec: Value = match serde_json::from_str(&content) {
+        Ok(spec) => spec,
+        Err(_) => serde_yaml::from_str(&content)
+            .map_err(|e| RurlError::DatasetError(format!("Failed to parse OpenAPI spec: {}", e)))?,
+    };
+    dataset_from_spec(&spec)
+}
+
+/// Builds a dataset with one entry per operation in an OpenAPI document.
+///
+/// Operations are ordered by path and then by method, so the generated
+/// dataset is stable across runs.
+///
+/// # Errors
+///
+/// Returns [`RurlError::DatasetError`] if the document has no operations.
+pub fn dataset_from_spec(spec: &Value) -> Result<Dataset> {
+    let paths = spec
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or_else(|| RurlError::DatasetError("OpenAPI spec has no paths".to_string()))?;
+
+    let mut sorted_paths: Vec<_> = paths.iter().collect();
+    sorted_paths.sort_by_key(|(path, _)| *path);
+
+    let mut entries = Vec::new();
+    for (path, item) in sorted_paths {
+        let shared_params = item.get("parameters").and_then(Value::as_array);
+
+        for method in METHODS {
+            if let Some(operation) = item.get(method) {
+                entries.push(build_entry(spec, path, method, operation, shared_params));
+            }
+        }
+    }
+
+    if entries.is_empty() {
+        return Err(RurlError::DatasetError("OpenAPI spec defines no operations".to_string()));
+    }
+
+    Ok(Dataset::new(entries))
+}
+
+fn build_entry(
+    spec: &Value,
+    path: &str,
+    method: &str,
+    operation: &Value,
+    shared_params: Option<&Vec<Value>>,
+) -> DatasetEntry {
+    let params: Vec<&Value> = shared_params
+        .into_iter()
+        .flatten()
+        .chain(operation.get("parameters").and_then(Value::as_array).into_iter().flatten())
+        .map(|p| resolve(spec, p))
+        .collect();
+
+    let mut resolved_path = path.to_string();
+    let mut query = Vec::new();
+    let mut headers = HashMap::new();
+
+    for param in params {
+        let Some(name) = param.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let required = param.get("required").and_then(Value::as_bool).unwrap_or(false);
+        let value = value_to_param(&parameter_sample(spec, param));
+
+        match param.get("in").and_then(Value::as_str) {
+            Some("path") => {
+                let encoded = percent_encode(value.as_bytes());
+                resolved_path = resolved_path.replace(&format!("{{{}}}", name), &encoded);
+            }
+            Some("query") if required => {
+                query.push(format!("{}={}", percent_encode(name.as_bytes()), percent_encode(value.as_bytes())))
+            }
+            Some("header") if required => {
+                headers.insert(name.to_string(), value);
+            }
+            _ => {}
+        }
+    }
+
+    if !query.is_empty() {
+        resolved_path = format!("{}?{}", resolved_path, query.join("&"));
+    }
+
+    let body = operation
+        .get("requestBody")
+        .map(|body| resolve(spec, body))
+        .and_then(|body| body.get("content"))
+        .and_then(Value::as_object)
+        .and_then(|content| {
+            content
+                .iter()
+                .find(|(media_type, _)| media_type.contains("json"))
+                .or_else(|| content.iter().next())
+        })
+        .map(|(media_type, media)| {
+            headers.insert("Content-Type".to_string(), media_type.clone());
+            media_sample(spec, media)
+        });
+
+    DatasetEntry {
+        method: method.to_uppercase(),
+        path: Some(resolved_path),
+        body,
+        headers: if headers.is_empty() { None } else { Some(headers) },
+        tag: None,
+        graphql: None,
+        timeout_ms: None,
+        http_version: None,
+    }
+}
+
+/// Follows a local `$ref` (e.g. `#/components/schemas/User`), if present.
+fn resolve<'a>(spec: &'a Value, value: &'a Value) -> &'a Value {
+    let mut current = value;
+    // Bounded to avoid looping on references that point at each other
+    for _ in 0..MAX_SCHEMA_DEPTH {
+        match current.get("$ref").and_then(Value::as_str) {
+            Some(reference) => match reference.strip_prefix('#').and_then(|ptr| spec.pointer(ptr)) {
+                Some(target) => current = target,
+                None => return current,
+            },
+            None => return current,
+        }
+    }
+    current
+}
+
+fn parameter_sample(spec: &Value, param: &Value) -> Value {
+    if let Some(example) = param.get("example") {
+        return example.clone();
+    }
+    if let Some(example) = first_example(spec, param) {
+        return example;
+    }
+    param
+        .get("schema")
+        .map(|schema| schema_sample(spec, schema, 0))
+        .unwrap_or_else(|| Value::String("1".to_string()))
+}
+
+fn media_sample(spec: &Value, media: &Value) -> Value {
+    if let Some(example) = media.get("example") {
+        return example.clone();
+    }
+    if let Some(example) = first_example(spec, media) {
+        return example;
+    }
+    media
+        .get("schema")
+        .map(|schema| schema_sample(spec, schema, 0))
+        .unwrap_or(Value::Null)
+}
+
+/// Returns the value of the first entry in an `examples` map.
+fn first_example(spec: &Value, node: &Value) -> Option<Value> {
+    let examples = node.get("examples")?.as_object()?;
+    let (_, example) = examples.iter().next()?;
+    resolve(spec, example).get("value").cloned()
+}
+
+/// Synthesizes a sample value that conforms to a JSON schema.
+fn schema_sample(spec: &Value, schema: &Value, depth: usize) -> Value {
+    let schema = resolve(spec, schema);
+
+    if let Some(example) = schema.get("example").or_else(|| schema.get("default")) {
+        return example.clone();
+    }
+    if let Some(first) = schema.get("enum").and_then(Value::as_array).and_then(|e| e.first()) {
+        return first.clone();
+    }
+    if depth >= MAX_SCHEMA_DEPTH {
+        return Value::Null;
+    }
+    for combinator in ["allOf", "oneOf", "anyOf"] {
+        if let Some(variants) = schema.get(combinator).and_then(Value::as_array) {
+            if combinator == "allOf" {
+                let mut merged = Map::new();
+                for variant in variants {
+                    if let Value::Object(fields) = schema_sample(spec, variant, depth + 1) {
+                        merged.extend(fields);
+                    }
+                }
+                return Value::Object(merged);
+            }
+            if let Some(first) = variants.first() {
+                return schema_sample(spec, first, depth + 1);
+            }
+        }
+    }
+
+    let schema_type = schema.get("type").and_then(Value::as_str).unwrap_or_else(|| {
+        if schema.get("properties").is_some() { "object" } else { "string" }
+    });
+
+    match schema_type {
+        "object" => {
+            let properties = schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .map(|props| {
+                    props
+                        .iter()
+                        .map(|(name, prop)| (name.clone(), schema_sample(spec, prop, depth + 1)))
+                        .collect()
+                })
+                .unwrap_or_default();
+            Value::Object(properties)
+        }
+        "array" => {
+            let item = schema
+                .get("items")
+                .map(|items| schema_sample(spec, items, depth + 1))
+                .unwrap_or(Value::Null);
+            Value::Array(vec![item])
+        }
+        "integer" => Value::from(schema.get("minimum").and_then(Value::as_i64).unwrap_or(1)),
+        "number" => Value::from(schema.get("minimum").and_then(Value::as_f64).unwrap_or(1.0)),
+        "boolean" => Value::Bool(true),
+        _ => Value::String(string_sample(schema)),
+    }
+}
+
+fn string_sample(schema: &Value) -> String {
+    match schema.get("format").and_then(Value::as_str) {
+        Some("date") => "2024-01-01".to_string(),
+        Some("date-time") => "2024-01-01T00:00:00Z".to_string(),
+        Some("email") => "user@example.com".to_string(),
+        Some("uuid") => "00000000-0000-0000-0000-000000000000".to_string(),
+        Some("uri") | Some("url") => "https://example.com".to_string(),
+        _ => "string".to_string(),
+    }
+}
+
+/// Renders a sample value for use in a URL path or query string.
+fn value_to_param(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec() -> Value {
+        json!({
+            "openapi": "3.0.0",
+            "paths": {
+                "/users/{id}": {
+                    "parameters": [{"name": "id", "in": "path", "required": true, "schema": {"type": "integer", "example": 42}}],
+                    "get": {},
+                    "delete": {}
+                },
+                "/users": {
+                    "get": {
+                        "parameters": [{"name": "limit", "in": "query", "required": true, "schema": {"type": "integer"}}]
+                    },
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {"schema": {"$ref": "#/components/schemas/User"}}
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "User": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string", "example": "Ada"},
+                            "email": {"type": "string", "format": "email"},
+                            "tags": {"type": "array", "items": {"type": "string"}},
+                            "role": {"type": "string", "enum": ["admin", "user"]}
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_one_entry_per_operation() {
+        let dataset = dataset_from_spec(&spec()).unwrap();
+        let labels: Vec<String> = dataset
+            .entries
+            .iter()
+            .map(|e| format!("{} {}", e.method, e.path.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec!["GET /users?limit=1", "POST /users", "GET /users/42", "DELETE /users/42"]
+        );
+    }
+
+    #[test]
+    fn test_body_from_schema_ref() {
+        let dataset = dataset_from_spec(&spec()).unwrap();
+        let post = &dataset.entries[1];
+        let body = post.body.as_ref().unwrap();
+        assert_eq!(body["name"], "Ada");
+        assert_eq!(body["email"], "user@example.com");
+        assert_eq!(body["tags"], json!(["string"]));
+        assert_eq!(body["role"], "admin");
+        assert_eq!(
+            post.headers.as_ref().unwrap().get("Content-Type"),
+            Some(&"application/json".to_string())
+        );
+    }
+
+    #[test]
+    fn test_media_example_preferred() {
+        let spec = json!({
+            "paths": {
+                "/login": {
+                    "post": {
+                        "requestBody": {
+                            "content": {"application/json": {"example": {"user": "demo"}, "schema": {"type": "object"}}}
+                        }
+                    }
+                }
+            }
+        });
+        let dataset = dataset_from_spec(&spec).unwrap();
+        assert_eq!(dataset.entries[0].body, Some(json!({"user": "demo"})));
+    }
+
+    #[test]
+    fn test_parameters_percent_encoded() {
+        let spec = json!({
+            "paths": {
+                "/files/{name}": {
+                    "get": {
+                        "parameters": [
+                            {"name": "name", "in": "path", "required": true, "example": "a b/c"},
+                            {"name": "q", "in": "query", "required": true, "example": "x&y=z"}
+                        ]
+                    }
+                }
+            }
+        });
+        let dataset = dataset_from_spec(&spec).unwrap();
+        assert_eq!(dataset.entries[0].path.as_deref(), Some("/files/a%20b%2Fc?q=x%26y%3Dz"));
+    }
+
+    #[test]
+    fn test_non_json_string_body_sent_as_is() {
+        let spec = json!({
+            "paths": {
+                "/form": {
+                    "post": {
+                        "requestBody": {
+                            "content": {"application/x-www-form-urlencoded": {"example": "user=demo&pass=x"}}
+                        }
+                    }
+                }
+            }
+        });
+        let dataset = dataset_from_spec(&spec).unwrap();
+        let entry = &dataset.entries[0];
+        let content_type = entry.headers.as_ref().unwrap().get("Content-Type").map(String::as_str);
+        assert_eq!(entry.body_for(content_type).as_deref(), Some("user=demo&pass=x"));
+    }
+
+    #[test]
+    fn test_recursive_schema_terminates() {
+        let spec = json!({
+            "paths": {"/tree": {"post": {"requestBody": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/Node"}}}}}}},
+            "components": {"schemas": {"Node": {"type": "object", "properties": {"child": {"$ref": "#/components/schemas/Node"}}}}}
+        });
+        assert!(dataset_from_spec(&spec).is_ok());
+    }
+
+    #[test]
+    fn test_no_operations() {
+        assert!(dataset_from_spec(&json!({"paths": {}})).is_err());
+        assert!(dataset_from_spec(&json!({})).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Dry-run execution plans for performance tests (`--plan`).
+//!
+//! [`PerfRunner::plan`](super::PerfRunner::plan) works out what a run
+//! would do without sending anything: how many requests each dataset
+//! entry gets, the load over time, and how long the run should take. CI
+//! can check the JSON before committing to a long or expensive run:
+//!
+//! ```text
+//! hurley https://api.example.com --perf data.json -z 10m --rate 200 --plan | jq .total_requests
+//! ```
+//!
+//! Counts for time-based runs at a fixed concurrency depend on how fast
+//! the server answers, so they are left out (`null`).
+
+use serde::Serialize;
+
+/// What a performance run would do.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunPlan {
+    /// Base URL of the run
+    pub url: String,
+    /// Total measured requests; `None` when it depends on response times
+    pub total_requests: Option<usize>,
+    /// Warmup requests sent before the measured run
+    pub warmup_requests: usize,
+    /// Length of a time-based warmup phase, in seconds
+    pub warmup_seconds: Option<f64>,
+    /// Configured run length in seconds, for time-based runs
+    pub duration_seconds: Option<f64>,
+    /// Expected run length in seconds; `None` when it depends on response times
+    pub estimated_duration_seconds: Option<f64>,
+    /// Requests per dataset entry, in dataset order
+    pub entries: Vec<PlannedEntry>,
+    /// Load over time: one phase for a constant load, else one per stage
+    pub schedule: Vec<PlannedPhase>,
+}
+
+/// Requests planned for one dataset entry.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedEntry {
+    /// Label the entry is reported under
+    pub label: String,
+    /// HTTP method
+    pub method: String,
+    /// Full request URL
+    pub url: String,
+    /// Number of requests; `None` when the total is unknown
+    pub requests: Option<usize>,
+}
+
+/// A phase of the load schedule.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedPhase {
+    /// Description, e.g. "0 → 50 over 1m"
+    pub label: String,
+    /// Start, in seconds since the measured run started
+    pub start_seconds: f64,
+    /// Length in seconds; `None` when the phase lasts until the run ends
+    pub duration_seconds: Option<f64>,
+    /// Load at the start of the phase
+    pub from: f64,
+    /// Load at the end of the phase
+    pub to: f64,
+    /// What the load is: `concurrency` or `rate` (requests per second)
+    pub unit: &'static str,
+}
+
+/// Splits `total` requests over `entries` entries the way the runner cycles through them.
+pub(crate) fn requests_per_entry(total: usize, entries: usize) -> Vec<usize> {
+    if entries == 0 {
+        return Vec::new();
+    }
+    (0..entries)
+        .map(|index| total / entries + usize::from(index < total % entries))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_per_entry() {
+        assert_eq!(requests_per_entry(10, 3), vec![4, 3, 3]);
+        assert_eq!(requests_per_entry(2, 4), vec![1, 1, 0, 0]);
+        assert!(requests_per_entry(5, 0).is_empty());
+    }
+}
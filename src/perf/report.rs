@@ -2,9 +2,72 @@
 //!
 //! Supports text output with colored formatting and JSON export.
 
-
+use std::io::{self, Write};
 use colored::Colorize;
-use super::metrics::PerfMetrics;
+use crate::error::Result;
+use super::keepalive::{KeepAliveReport, ProbeOutcome};
+use super::metrics::{PerfMetrics, TimelinePoint};
+use super::sink::{ReportSink, RunReport};
+use super::slo::SloReport;
+use super::sweep::SweepPoint;
+use super::thresholds::ThresholdResult;
+
+/// Dispatch delay share of latency above which the report warns.
+const DISPATCH_DELAY_WARN_PERCENT: f64 = 20.0;
+
+/// Width in characters of the longest latency histogram bar.
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Maximum number of characters in a timeline sparkline.
+const SPARKLINE_WIDTH: usize = 60;
+
+/// Number of missed SLO windows listed in the text report.
+const SLO_VIOLATIONS_SHOWN: usize = 5;
+
+/// Sparkline bars, from lowest to highest.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Formats an offset into the run as `HH:MM:SS`.
+fn clock(ms: f64) -> String {
+    let secs = (ms / 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Formats a byte count with a decimal unit (`512 B`, `1.50 KB`, `2.25 MB`).
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1000.0 {
+        return format!("{:.0} B", bytes);
+    }
+    let mut value = bytes;
+    let mut unit = UNITS[0];
+    for next in UNITS {
+        value /= 1000.0;
+        unit = next;
+        if value < 1000.0 {
+            break;
+        }
+    }
+    format!("{:.2} {}", value, unit)
+}
+
+/// Draws values as a sparkline scaled between their min and max.
+///
+/// Neighbouring values are averaged so long runs fit in
+/// [`SPARKLINE_WIDTH`] characters.
+fn sparkline(values: &[f64]) -> String {
+    let chunk = values.len().div_ceil(SPARKLINE_WIDTH).max(1);
+    let values: Vec<f64> = values.chunks(chunk).map(|c| c.iter().sum::<f64>() / c.len() as f64).collect();
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|value| {
+            let level = if max > min { ((value - min) / (max - min) * 7.0).round() as usize } else { 0 };
+            SPARKS[level]
+        })
+        .collect()
+}
 
 /// Performance report formatter.
 ///
@@ -16,72 +79,452 @@ impl PerfReport {
     ///
     /// Includes request summary, timing information, and latency distribution.
     pub fn print_text(metrics: &PerfMetrics) {
-        println!();
-        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
-        println!("{}", "                    PERFORMANCE RESULTS                     ".cyan().bold());
-        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
-        println!();
+        if let Err(e) = Self::write_text(&mut std::io::stdout(), metrics) {
+            eprintln!("Failed to write report: {}", e);
+        }
+    }
+
+    /// Writes metrics in colored text format.
+    pub fn write_text(out: &mut dyn Write, metrics: &PerfMetrics) -> io::Result<()> {
+        writeln!(out)?;
+        writeln!(out, "{}", "═══════════════════════════════════════════════════════════".cyan())?;
+        writeln!(out, "{}", "                    PERFORMANCE RESULTS                     ".cyan().bold())?;
+        writeln!(out, "{}", "═══════════════════════════════════════════════════════════".cyan())?;
+        writeln!(out)?;
 
-        Self::print_metrics_details(metrics);
+        Self::write_metrics_details(out, metrics)?;
+
+        if !metrics.validation.is_empty() {
+            Self::write_validation(out, metrics)?;
+        }
+
+        if metrics.timeline.len() > 1 {
+            Self::write_timeline(out, metrics)?;
+        }
+
+        if let Some(slo) = &metrics.slo {
+            Self::write_slo(out, slo)?;
+        }
 
         if !metrics.endpoints.is_empty() {
-            println!();
-            println!("{}", "═══════════════════════════════════════════════════════════".cyan());
-            println!("{}", "                    ENDPOINT BREAKDOWN                      ".cyan().bold());
-            println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+            writeln!(out)?;
+            writeln!(out, "{}", "═══════════════════════════════════════════════════════════".cyan())?;
+            writeln!(out, "{}", "                    ENDPOINT BREAKDOWN                      ".cyan().bold())?;
+            writeln!(out, "{}", "═══════════════════════════════════════════════════════════".cyan())?;
             
             let mut sorted_endpoints: Vec<_> = metrics.endpoints.iter().collect();
             sorted_endpoints.sort_by_key(|(k, _)| *k);
 
             for (label, stats) in sorted_endpoints {
-                println!();
-                println!("📍 {}", label.magenta().bold());
-                println!("{}", "───────────────────────────────────────────────────────────".dimmed());
-                Self::print_metrics_details(stats);
+                writeln!(out)?;
+                writeln!(out, "📍 {}", label.magenta().bold())?;
+                writeln!(out, "{}", "───────────────────────────────────────────────────────────".dimmed())?;
+                Self::write_metrics_details(out, stats)?;
             }
         }
 
-        println!();
-        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+        if !metrics.traffic_classes.is_empty() {
+            Self::write_traffic_classes(out, metrics)?;
+        }
+
+        if !metrics.stages.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{}", "═══════════════════════════════════════════════════════════".cyan())?;
+            writeln!(out, "{}", "                    STAGE BREAKDOWN                         ".cyan().bold())?;
+            writeln!(out, "{}", "═══════════════════════════════════════════════════════════".cyan())?;
+            writeln!(out)?;
+
+            let width = metrics.stages.iter().map(|s| s.stage.chars().count()).max().unwrap_or(0).max(5);
+            writeln!(
+                out,
+                "   {:<width$}  {:>7}  {:>6}  {:>8}  {:>9}  {:>9}",
+                "Stage", "Reqs", "Err%", "RPS", "p50 ms", "p99 ms",
+                width = width
+            )?;
+            for stage in &metrics.stages {
+                let m = &stage.metrics;
+                writeln!(
+                    out,
+                    "   {:<width$}  {:>7}  {:>6.2}  {:>8.2}  {:>9.2}  {:>9.2}",
+                    stage.stage,
+                    m.total_requests,
+                    m.error_rate_percent,
+                    m.requests_per_second,
+                    m.latency_p50_ms,
+                    m.latency_p99_ms,
+                    width = width
+                )?;
+            }
+        }
+
+        writeln!(out)?;
+        writeln!(out, "{}", "═══════════════════════════════════════════════════════════".cyan())?;
+        Ok(())
+    }
+
+    /// Writes priority and background traffic side by side, each followed
+    /// by the achieved rate of its endpoints.
+    fn write_traffic_classes(out: &mut dyn Write, metrics: &PerfMetrics) -> io::Result<()> {
+        writeln!(out)?;
+        writeln!(out, "{}", "═══════════════════════════════════════════════════════════".cyan())?;
+        writeln!(out, "{}", "                    TRAFFIC PRIORITY                        ".cyan().bold())?;
+        writeln!(out, "{}", "═══════════════════════════════════════════════════════════".cyan())?;
+        writeln!(out)?;
+
+        let width = metrics
+            .traffic_classes
+            .iter()
+            .flat_map(|c| c.labels.iter().map(|l| l.chars().count() + 2))
+            .max()
+            .unwrap_or(0)
+            .max(10);
+        writeln!(
+            out,
+            "   {:<width$}  {:>7}  {:>6}  {:>8}  {:>9}  {:>9}",
+            "Traffic", "Reqs", "Err%", "RPS", "p50 ms", "p99 ms",
+            width = width
+        )?;
+        let row = |out: &mut dyn Write, name: String, m: &PerfMetrics| {
+            writeln!(
+                out,
+                "   {:<width$}  {:>7}  {:>6.2}  {:>8.2}  {:>9.2}  {:>9.2}",
+                name,
+                m.total_requests,
+                m.error_rate_percent,
+                m.requests_per_second,
+                m.latency_p50_ms,
+                m.latency_p99_ms,
+                width = width
+            )
+        };
+        for class in &metrics.traffic_classes {
+            row(out, class.class.clone(), &class.metrics)?;
+            for label in &class.labels {
+                if let Some(endpoint) = metrics.endpoints.get(label) {
+                    row(out, format!("  {}", label), endpoint)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_timeline(out: &mut dyn Write, metrics: &PerfMetrics) -> io::Result<()> {
+        writeln!(out)?;
+        writeln!(out, "{}", "📉 Over Time".white().bold())?;
+        let points = metrics.charted_timeline();
+        let series = |value: fn(&TimelinePoint) -> f64| points.iter().map(|p| value(p)).collect::<Vec<_>>();
+        let rows = [
+            ("RPS", series(|p| p.requests_per_second), ""),
+            ("p50", series(|p| p.latency_p50_ms), " ms"),
+            ("p99", series(|p| p.latency_p99_ms), " ms"),
+        ];
+        for (name, values, unit) in rows {
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            writeln!(
+                out,
+                "   {:<4} {}  {:.2} - {:.2}{}",
+                name,
+                sparkline(&values).cyan(),
+                min,
+                max,
+                unit
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_slo(out: &mut dyn Write, slo: &SloReport) -> io::Result<()> {
+        writeln!(out)?;
+        writeln!(out, "{}", format!("📐 SLO Compliance ({} per {} window)", slo.objective, slo.window).white().bold())?;
+        writeln!(
+            out,
+            "   Windows Met:         {}/{} ({:.2}%)",
+            slo.compliant_windows, slo.windows, slo.compliance_percent
+        )?;
+        if let (Some(target), Some(met)) = (slo.target_percent, slo.met) {
+            let verdict = if met { "PASS".green().bold() } else { "FAIL".red().bold() };
+            writeln!(out, "   Target:              {:.2}%  {}", target, verdict)?;
+        }
+        if let (Some(rate), Some(budget)) = (slo.burn_rate, slo.error_budget_remaining_percent) {
+            let rate = format!("{:.2}x", rate);
+            let rate = if budget < 0.0 { rate.red() } else { rate.normal() };
+            writeln!(out, "   Burn Rate:           {} (budget left {:.1}%)", rate, budget)?;
+        }
+        for window in slo.violations.iter().take(SLO_VIOLATIONS_SHOWN) {
+            writeln!(
+                out,
+                "   {}  {} - {}  {:.2}{}",
+                "MISS".red().bold(),
+                clock(window.start_ms),
+                clock(window.end_ms),
+                window.value,
+                slo.unit
+            )?;
+        }
+        if slo.violations.len() > SLO_VIOLATIONS_SHOWN {
+            let more = format!("... and {} more missed windows", slo.violations.len() - SLO_VIOLATIONS_SHOWN);
+            writeln!(out, "   {}", more.dimmed())?;
+        }
+        Ok(())
+    }
+
+    fn write_validation(out: &mut dyn Write, metrics: &PerfMetrics) -> io::Result<()> {
+        writeln!(out)?;
+        writeln!(out, "{}", "✅ Validation".white().bold())?;
+
+        let width = metrics.validation.iter().map(|a| a.assertion.len()).max().unwrap_or(0);
+        for assertion in &metrics.validation {
+            let verdict = if assertion.failed == 0 { "PASS".green().bold() } else { "FAIL".red().bold() };
+            writeln!(
+                out,
+                "   {}  {:<width$}  {:>7} passed  {:>7} failed",
+                verdict,
+                assertion.assertion,
+                assertion.passed,
+                assertion.failed,
+                width = width
+            )?;
+            for example in &assertion.examples {
+                writeln!(out, "         {}", example.dimmed())?;
+            }
+        }
+        Ok(())
     }
 
-    fn print_metrics_details(metrics: &PerfMetrics) {
+    fn write_metrics_details(out: &mut dyn Write, metrics: &PerfMetrics) -> io::Result<()> {
         // Request Summary
-        println!("{}", "📊 Request Summary".white().bold());
-        println!("   Total Requests:      {}", metrics.total_requests.to_string().cyan());
-        println!("   Successful:          {}", metrics.successful_requests.to_string().green());
-        println!("   Failed:              {}", 
+        writeln!(out, "{}", "📊 Request Summary".white().bold())?;
+        writeln!(out, "   Total Requests:      {}", metrics.total_requests.to_string().cyan())?;
+        writeln!(out, "   Successful:          {}", metrics.successful_requests.to_string().green())?;
+        writeln!(out, "   Failed:              {}", 
             if metrics.failed_requests > 0 {
                 metrics.failed_requests.to_string().red()
             } else {
                 metrics.failed_requests.to_string().green()
             }
-        );
-        println!("   Error Rate:          {:.2}%", metrics.error_rate_percent);
-        println!();
+        )?;
+        writeln!(out, "   Error Rate:          {:.2}%", metrics.error_rate_percent)?;
+        if let Some(not_modified) = metrics.status_codes.get(&304) {
+            let share = *not_modified as f64 / metrics.total_requests.max(1) as f64 * 100.0;
+            writeln!(out, "   Not Modified (304):  {} ({:.2}%)", not_modified.to_string().cyan(), share)?;
+        }
+        if let Some(responses) = &metrics.responses {
+            let distinct = format!("{}{}", responses.distinct, if responses.saturated { "+" } else { "" });
+            // A single response across many requests is worth a second look
+            let distinct = if responses.distinct == 1 && responses.most_common > 1 { distinct.yellow() } else { distinct.cyan() };
+            writeln!(
+                out,
+                "   Distinct Responses:  {} (most common {:.2}%)",
+                distinct, responses.most_common_percent
+            )?;
+        }
+        writeln!(out)?;
 
         // Timing
-        println!("{}", "⏱️  Timing".white().bold());
-        println!("   Total Duration:      {:.2} ms", metrics.total_duration_ms);
-        println!("   Requests/sec:        {}", format!("{:.2}", metrics.requests_per_second).yellow().bold());
-        println!();
+        writeln!(out, "{}", "⏱️  Timing".white().bold())?;
+        writeln!(out, "   Total Duration:      {:.2} ms", metrics.total_duration_ms)?;
+        writeln!(out, "   Requests/sec:        {}", format!("{:.2}", metrics.requests_per_second).yellow().bold())?;
+        writeln!(out)?;
+
+        if let Some(iterations) = &metrics.iterations {
+            writeln!(out, "{}", "🔁 Iterations".white().bold())?;
+            writeln!(
+                out,
+                "   Completed:           {} ({} failed)",
+                iterations.iterations.to_string().cyan(),
+                iterations.failed_iterations
+            )?;
+            writeln!(out, "   Iterations/sec:      {:.2}", iterations.iterations_per_second)?;
+            writeln!(
+                out,
+                "   Duration:            p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms, max {:.2} ms",
+                iterations.duration.p50_ms, iterations.duration.p95_ms, iterations.duration.p99_ms, iterations.duration.max_ms
+            )?;
+            writeln!(out)?;
+        }
+
+        if let Some(transfer) = &metrics.transfer {
+            writeln!(out, "{}", "📦 Data Transfer".white().bold())?;
+            writeln!(
+                out,
+                "   Sent:                {} (avg {} per request)",
+                format_bytes(transfer.bytes_sent as f64),
+                format_bytes(transfer.avg_request_bytes)
+            )?;
+            writeln!(
+                out,
+                "   Received:            {} (avg {} per response)",
+                format_bytes(transfer.bytes_received as f64),
+                format_bytes(transfer.avg_response_bytes)
+            )?;
+            writeln!(
+                out,
+                "   Throughput:          {} received, {:.2} MB/s sent",
+                format!("{:.2} MB/s", transfer.received_mb_per_second).yellow(),
+                transfer.sent_mb_per_second
+            )?;
+            writeln!(out)?;
+        }
 
         // Latency Distribution
-        println!("{}", "📈 Latency Distribution".white().bold());
-        println!("   Min:                 {:.2} ms", metrics.latency_min_ms);
-        println!("   Max:                 {:.2} ms", metrics.latency_max_ms);
-        println!("   Avg:                 {:.2} ms", metrics.latency_avg_ms);
-        println!("   p50 (Median):        {:.2} ms", metrics.latency_p50_ms);
-        println!("   p95:                 {:.2} ms", metrics.latency_p95_ms);
-        println!("   p99:                 {:.2} ms", metrics.latency_p99_ms);
+        writeln!(out, "{}", "📈 Latency Distribution".white().bold())?;
+        writeln!(out, "   Min:                 {:.2} ms", metrics.latency_min_ms)?;
+        writeln!(out, "   Max:                 {:.2} ms", metrics.latency_max_ms)?;
+        writeln!(out, "   Avg:                 {:.2} ms", metrics.latency_avg_ms)?;
+        writeln!(out, "   p50 (Median):        {:.2} ms", metrics.latency_p50_ms)?;
+        writeln!(out, "   p95:                 {:.2} ms", metrics.latency_p95_ms)?;
+        writeln!(out, "   p99:                 {:.2} ms", metrics.latency_p99_ms)?;
+        if let Some(method) = metrics.percentile_method {
+            writeln!(out, "   Percentiles:         {}", method.to_string().dimmed())?;
+        }
+
+        if !metrics.histogram.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{}", "📶 Latency Histogram".white().bold())?;
+            let peak = metrics.histogram.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+            for bucket in &metrics.histogram {
+                let width = (bucket.count as f64 / peak as f64 * HISTOGRAM_WIDTH as f64).ceil() as usize;
+                let bar = format!("{:<w$}", "█".repeat(width), w = HISTOGRAM_WIDTH);
+                writeln!(
+                    out,
+                    "   {:>9.2} - {:>9.2} ms  {}  {}",
+                    bucket.from_ms, bucket.to_ms, bar.cyan(), bucket.count
+                )?;
+            }
+        }
+
+        if !metrics.status_classes.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{}", "🚦 Latency by Status Class".white().bold())?;
+
+            let mut sorted_classes: Vec<_> = metrics.status_classes.iter().collect();
+            sorted_classes.sort_by_key(|(k, _)| *k);
+
+            for (class, stats) in sorted_classes {
+                let label = match class.chars().next() {
+                    Some('2') => class.green(),
+                    Some('4') => class.yellow(),
+                    Some('5') => class.red(),
+                    _ => class.normal(),
+                };
+                writeln!(
+                    out,
+                    "   {}  {:>7} reqs   p50 {:>9.2} ms   p95 {:>9.2} ms   p99 {:>9.2} ms",
+                    label,
+                    stats.total_requests,
+                    stats.latency_p50_ms,
+                    stats.latency_p95_ms,
+                    stats.latency_p99_ms,
+                )?;
+            }
+        }
+
+        // Only worth a section when the run had both outcomes
+        if let (Some(success), Some(failure)) = (&metrics.success_latency, &metrics.failure_latency) {
+            writeln!(out)?;
+            writeln!(out, "{}", "⚖️  Latency by Outcome".white().bold())?;
+            for (label, summary) in [("success".green(), success), ("failure".red(), failure)] {
+                writeln!(
+                    out,
+                    "   {}  {:>7} reqs   p50 {:>9.2} ms   p95 {:>9.2} ms   p99 {:>9.2} ms",
+                    label, summary.requests, summary.p50_ms, summary.p95_ms, summary.p99_ms,
+                )?;
+            }
+        }
+
+        if let Some(phases) = &metrics.phases {
+            writeln!(out)?;
+            writeln!(out, "{}", "🔌 Connection Phases".white().bold())?;
+            for (phase, summary) in phases.phases() {
+                writeln!(
+                    out,
+                    "   {:<8}  {:>7} reqs   p50 {:>9.2} ms   p95 {:>9.2} ms   p99 {:>9.2} ms",
+                    phase, summary.requests, summary.p50_ms, summary.p95_ms, summary.p99_ms,
+                )?;
+            }
+        }
+
+        if !metrics.status_codes.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{}", "🔢 Status Codes".white().bold())?;
+            for (status, count) in &metrics.status_codes {
+                let code = status.to_string();
+                let code = match status / 100 {
+                    2 => code.green(),
+                    4 => code.yellow(),
+                    5 => code.red(),
+                    _ => code.normal(),
+                };
+                let share = *count as f64 / metrics.total_requests.max(1) as f64 * 100.0;
+                writeln!(out, "   {}  {:>7}  ({:.2}%)", code, count, share)?;
+            }
+        }
+
+        if !metrics.http_versions.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{}", "🔗 Protocol Versions".white().bold())?;
+            for (version, count) in &metrics.http_versions {
+                let share = *count as f64 / metrics.total_requests.max(1) as f64 * 100.0;
+                writeln!(out, "   {:<10} {:>7}  ({:.2}%)", version, count, share)?;
+            }
+        }
+
+        if !metrics.cache_statuses.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{}", "🗄️ Response Cache".white().bold())?;
+            let answered: usize = metrics.cache_statuses.values().sum();
+            for (status, count) in &metrics.cache_statuses {
+                let share = *count as f64 / answered.max(1) as f64 * 100.0;
+                writeln!(out, "   {:<12} {:>7}  ({:.2}%)", status, count, share)?;
+            }
+            // Answered without a full response from the server
+            let saved = ["hit", "revalidated"].iter().filter_map(|s| metrics.cache_statuses.get(*s)).sum::<usize>();
+            let rate = saved as f64 / answered.max(1) as f64 * 100.0;
+            writeln!(out, "   {:<12} {:>7.2}%", "Hit rate:", rate)?;
+        }
+
+        if !metrics.error_categories.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{}", "❌ Failures by Cause".white().bold())?;
+            for (category, count) in &metrics.error_categories {
+                writeln!(out, "   {:<20} {}", format!("{}:", category), count.to_string().red())?;
+            }
+        }
+
+        if let Some(delay) = &metrics.dispatch_delay {
+            writeln!(out)?;
+            writeln!(out, "{}", "⏳ Dispatch Delay".white().bold())?;
+            writeln!(out, "   Avg / p99:           {:.2} ms / {:.2} ms", delay.delay_avg_ms, delay.delay_p99_ms)?;
+            let share = format!("{:.1}%", delay.delay_percent);
+            writeln!(
+                out,
+                "   Share of Latency:    {}",
+                if delay.delay_percent >= DISPATCH_DELAY_WARN_PERCENT { share.red().bold() } else { share.normal() }
+            )?;
+            writeln!(
+                out,
+                "   Network p50/p95/p99: {:.2} / {:.2} / {:.2} ms",
+                delay.network_p50_ms, delay.network_p95_ms, delay.network_p99_ms
+            )?;
+            if delay.delay_percent >= DISPATCH_DELAY_WARN_PERCENT {
+                writeln!(
+                    out,
+                    "   {}",
+                    "Latency is dominated by contention in hurley; try lower concurrency".yellow()
+                )?;
+            }
+        }
+        Ok(())
     }
 
     /// Prints metrics in JSON format.
     ///
     /// Useful for programmatic consumption and integration with other tools.
     pub fn print_json(metrics: &PerfMetrics) {
-        match serde_json::to_string_pretty(metrics) {
-            Ok(json) => println!("{}", json),
+        let mut out = std::io::stdout().lock();
+        match serde_json::to_writer_pretty(&mut out, metrics) {
+            Ok(()) => println!(),
             Err(e) => eprintln!("Failed to serialize metrics: {}", e),
         }
     }
@@ -100,10 +543,275 @@ impl PerfReport {
     }
 }
 
+impl PerfReport {
+    /// Prints keep-alive benchmark results in the specified format.
+    ///
+    /// # Arguments
+    ///
+    /// * `report` - Keep-alive benchmark results
+    /// * `format` - Output format ("json" or "text")
+    pub fn print_keepalive(report: &KeepAliveReport, format: &str) {
+        if format.eq_ignore_ascii_case("json") {
+            match serde_json::to_string_pretty(report) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("Failed to serialize report: {}", e),
+            }
+            return;
+        }
+
+        println!();
+        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+        println!("{}", "                  KEEP-ALIVE RESULTS                        ".cyan().bold());
+        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+        println!();
+
+        for probe in &report.probes {
+            let outcome = match &probe.outcome {
+                ProbeOutcome::Reused => format!(
+                    "reused ({:.2} ms)",
+                    probe.probe_latency_ms.unwrap_or_default()
+                )
+                .green(),
+                ProbeOutcome::ClosedByServer => format!(
+                    "closed by server after {:.1} s idle",
+                    probe.closed_after_ms.unwrap_or_default() / 1000.0
+                )
+                .yellow(),
+                ProbeOutcome::NotKeptAlive => "server does not keep connections alive".yellow(),
+                ProbeOutcome::Reset(reason) => format!("reset on reuse: {}", reason).red(),
+                ProbeOutcome::Failed(reason) => format!("failed: {}", reason).red(),
+            };
+            println!(
+                "   #{:<3} idle {:>8.1} s   {}",
+                probe.connection,
+                probe.idle_ms / 1000.0,
+                outcome
+            );
+        }
+
+        println!();
+        println!("{}", "🔌 Summary".white().bold());
+        match report.max_reused_idle_ms() {
+            Some(ms) => println!("   Longest reused idle:  {:.1} s", ms / 1000.0),
+            None => println!("   Longest reused idle:  {}", "none".red()),
+        }
+        match report.min_closed_after_ms() {
+            Some(ms) => println!("   Server idle timeout:  ~{:.1} s", ms / 1000.0),
+            None => println!("   Server idle timeout:  not reached"),
+        }
+        println!();
+        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+    }
+}
+
+impl PerfReport {
+    /// Prints a side-by-side latency comparison of several backends.
+    ///
+    /// # Arguments
+    ///
+    /// * `results` - Backend label and metrics for each run, in run order
+    /// * `format` - Output format ("json" or "text")
+    pub fn print_comparison(results: &[(String, PerfMetrics)], format: &str) {
+        if format.eq_ignore_ascii_case("json") {
+            let runs: Vec<_> = results
+                .iter()
+                .map(|(backend, metrics)| serde_json::json!({ "backend": backend, "metrics": metrics }))
+                .collect();
+            match serde_json::to_string_pretty(&runs) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("Failed to serialize metrics: {}", e),
+            }
+            return;
+        }
+
+        println!();
+        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+        println!("{}", "                  BACKEND COMPARISON                        ".cyan().bold());
+        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+        println!();
+
+        let width = results.iter().map(|(backend, _)| backend.len()).max().unwrap_or(0).max(7);
+        println!(
+            "   {:<width$}  {:>6}  {:>6}  {:>8}  {:>9}  {:>9}  {:>9}",
+            "Backend", "Reqs", "Err%", "RPS", "p50 ms", "p95 ms", "p99 ms",
+            width = width
+        );
+        let fastest = results
+            .iter()
+            .map(|(_, m)| m.latency_p50_ms)
+            .fold(f64::INFINITY, f64::min);
+        for (backend, m) in results {
+            let p50 = format!("{:>9.2}", m.latency_p50_ms);
+            let p50 = if results.len() > 1 && m.latency_p50_ms == fastest {
+                p50.green()
+            } else {
+                p50.normal()
+            };
+            println!(
+                "   {:<width$}  {:>6}  {:>6.2}  {:>8.2}  {}  {:>9.2}  {:>9.2}",
+                backend,
+                m.total_requests,
+                m.error_rate_percent,
+                m.requests_per_second,
+                p50,
+                m.latency_p95_ms,
+                m.latency_p99_ms,
+                width = width
+            );
+        }
+
+        println!();
+        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+    }
+}
+
+impl PerfReport {
+    /// Prints throughput and latency for each level of a concurrency sweep.
+    ///
+    /// The level with the highest RPS is highlighted.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - Metrics per concurrency level
+    /// * `format` - Output format ("json" or "text")
+    pub fn print_sweep(points: &[SweepPoint], format: &str) {
+        if format.eq_ignore_ascii_case("json") {
+            match serde_json::to_string_pretty(points) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("Failed to serialize metrics: {}", e),
+            }
+            return;
+        }
+
+        println!();
+        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+        println!("{}", "                  CONCURRENCY SWEEP                         ".cyan().bold());
+        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+        println!();
+
+        println!(
+            "   {:>11}  {:>6}  {:>6}  {:>8}  {:>9}  {:>9}  {:>9}",
+            "Concurrency", "Reqs", "Err%", "RPS", "p50 ms", "p95 ms", "p99 ms"
+        );
+        let peak = SweepPoint::peak(points).map(|p| p.concurrency);
+        for point in points {
+            let m = &point.metrics;
+            let rps = format!("{:>8.2}", m.requests_per_second);
+            let rps = if points.len() > 1 && peak == Some(point.concurrency) {
+                rps.green()
+            } else {
+                rps.normal()
+            };
+            println!(
+                "   {:>11}  {:>6}  {:>6.2}  {}  {:>9.2}  {:>9.2}  {:>9.2}",
+                point.concurrency,
+                m.total_requests,
+                m.error_rate_percent,
+                rps,
+                m.latency_p50_ms,
+                m.latency_p95_ms,
+                m.latency_p99_ms,
+            );
+        }
+
+        println!();
+        println!("{}", "═══════════════════════════════════════════════════════════".cyan());
+    }
+}
+
+impl PerfReport {
+    /// Prints a pass/fail table for performance thresholds.
+    pub fn print_thresholds(results: &[ThresholdResult]) {
+        if let Err(e) = Self::write_thresholds(&mut std::io::stdout(), results) {
+            eprintln!("Failed to write report: {}", e);
+        }
+    }
+
+    /// Writes a pass/fail table for performance thresholds.
+    pub fn write_thresholds(out: &mut dyn Write, results: &[ThresholdResult]) -> io::Result<()> {
+        writeln!(out)?;
+        writeln!(out, "{}", "🎯 Thresholds".white().bold())?;
+
+        let width = results
+            .iter()
+            .map(|r| r.threshold.to_string().len())
+            .max()
+            .unwrap_or(0);
+        for result in results {
+            let verdict = if result.passed { "PASS".green().bold() } else { "FAIL".red().bold() };
+            let actual = match result.actual {
+                Some(value) => format!("{:.2}{}", value, result.threshold.metric.unit()),
+                None => "no requests".to_string(),
+            };
+            writeln!(
+                out,
+                "   {}  {:<width$}  actual {}",
+                verdict,
+                result.threshold.to_string(),
+                actual,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the colored text report, followed by the thresholds table.
+///
+/// Colors are stripped when writing to a file.
+pub struct TextSink<W: Write> {
+    out: W,
+    color: bool,
+}
+
+impl<W: Write> TextSink<W> {
+    /// Creates a sink writing to `out`, keeping colors if `color` is set.
+    pub fn new(out: W, color: bool) -> Self {
+        Self { out, color }
+    }
+}
+
+impl<W: Write> ReportSink for TextSink<W> {
+    fn write(&mut self, report: &RunReport<'_>) -> Result<()> {
+        let mut text = Vec::new();
+        PerfReport::write_text(&mut text, report.metrics)?;
+        if !report.thresholds.is_empty() {
+            PerfReport::write_thresholds(&mut text, report.thresholds)?;
+        }
+        if self.color {
+            self.out.write_all(&text)?;
+        } else {
+            self.out.write_all(strip_ansi(&String::from_utf8_lossy(&text)).as_bytes())?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Removes ANSI color escape sequences from text.
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip to the end of the sequence (`ESC [ ... m`)
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::perf::{AssertionMetrics, IterationMetrics, LatencyBucket, LatencySummary, PercentileMethod, PhaseMetrics, ResponseUniqueness, SloWindow, TrafficClassMetrics, TransferMetrics};
+    use std::collections::{BTreeMap, HashMap};
 
     fn sample_metrics() -> PerfMetrics {
         PerfMetrics {
@@ -120,6 +828,25 @@ mod tests {
             requests_per_second: 100.0,
             error_rate_percent: 5.0,
             endpoints: HashMap::new(),
+            status_classes: HashMap::new(),
+            status_codes: BTreeMap::new(),
+            error_categories: BTreeMap::new(),
+            http_versions: BTreeMap::new(),
+            cache_statuses: BTreeMap::new(),
+            validation: Vec::new(),
+            timeline: Vec::new(),
+            success_latency: None,
+            failure_latency: None,
+            transfer: None,
+            phases: None,
+            histogram: Vec::new(),
+            dispatch_delay: None,
+            stages: Vec::new(),
+            slo: None,
+            responses: None,
+            iterations: None,
+            traffic_classes: Vec::new(),
+            percentile_method: None,
         }
     }
 
@@ -138,4 +865,205 @@ mod tests {
         assert_eq!(metrics.failed_requests, 5);
         assert!((metrics.error_rate_percent - 5.0).abs() < 0.01);
     }
+
+    #[test]
+    fn test_text_sink_strips_colors_for_files() {
+        colored::control::set_override(true);
+        let metrics = sample_metrics();
+        let mut out = Vec::new();
+        TextSink::new(&mut out, false)
+            .write(&RunReport { metrics: &metrics, thresholds: &[], records: None })
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("PERFORMANCE RESULTS"));
+        assert!(!text.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0.0), "0 B");
+        assert_eq!(format_bytes(999.0), "999 B");
+        assert_eq!(format_bytes(1500.0), "1.50 KB");
+        assert_eq!(format_bytes(2_250_000.0), "2.25 MB");
+        assert_eq!(format_bytes(3e15), "3000.00 TB");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[1.0, 2.0, 3.0, 8.0]), "▁▂▃█");
+        assert_eq!(sparkline(&[5.0, 5.0]), "▁▁");
+        let long: Vec<f64> = (0..600).map(f64::from).collect();
+        assert_eq!(sparkline(&long).chars().count(), SPARKLINE_WIDTH);
+    }
+
+    #[test]
+    fn test_slo_in_text_report() {
+        let mut metrics = sample_metrics();
+        metrics.slo = Some(SloReport {
+            objective: "p99 < 300ms".to_string(),
+            window: "5m".to_string(),
+            unit: "ms",
+            windows: 12,
+            compliant_windows: 11,
+            compliance_percent: 11.0 / 12.0 * 100.0,
+            target_percent: Some(99.0),
+            met: Some(false),
+            burn_rate: Some(8.33),
+            error_budget_remaining_percent: Some(-733.0),
+            violations: vec![SloWindow { start_ms: 300_000.0, end_ms: 600_000.0, value: 412.5 }],
+        });
+
+        let mut out = Vec::new();
+        TextSink::new(&mut out, false)
+            .write(&RunReport { metrics: &metrics, thresholds: &[], records: None })
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("SLO Compliance (p99 < 300ms per 5m window)"));
+        assert!(text.contains("   Windows Met:         11/12 (91.67%)"));
+        assert!(text.contains("   Target:              99.00%  FAIL"));
+        assert!(text.contains("   Burn Rate:           8.33x (budget left -733.0%)"));
+        assert!(text.contains("   MISS  00:05:00 - 00:10:00  412.50ms"));
+    }
+
+    #[test]
+    fn test_distinct_responses_in_text_report() {
+        let mut metrics = sample_metrics();
+        metrics.responses = Some(ResponseUniqueness {
+            distinct: 3,
+            most_common: 98,
+            most_common_percent: 98.0,
+            saturated: false,
+        });
+        let mut out = Vec::new();
+        PerfReport::write_text(&mut out, &metrics).unwrap();
+        let text = strip_ansi(&String::from_utf8(out).unwrap());
+        assert!(text.contains("   Distinct Responses:  3 (most common 98.00%)"));
+    }
+
+    #[test]
+    fn test_not_modified_in_text_report() {
+        let mut metrics = sample_metrics();
+        let mut out = Vec::new();
+        PerfReport::write_text(&mut out, &metrics).unwrap();
+        assert!(!String::from_utf8(out).unwrap().contains("Not Modified"));
+
+        metrics.status_codes = [(200, 10), (304, 90)].into();
+        let mut out = Vec::new();
+        PerfReport::write_text(&mut out, &metrics).unwrap();
+        let text = strip_ansi(&String::from_utf8(out).unwrap());
+        assert!(text.contains("   Not Modified (304):  90 (90.00%)"));
+    }
+
+    #[test]
+    fn test_cache_statuses_in_text_report() {
+        let mut metrics = sample_metrics();
+        metrics.cache_statuses = [("hit".to_string(), 80), ("miss".to_string(), 5), ("revalidated".to_string(), 15)].into();
+        let mut out = Vec::new();
+        PerfReport::write_text(&mut out, &metrics).unwrap();
+        let text = strip_ansi(&String::from_utf8(out).unwrap());
+        assert!(text.contains("Response Cache"));
+        assert!(text.contains("   hit               80  (80.00%)"));
+        assert!(text.contains("   Hit rate:      95.00%"));
+    }
+
+    #[test]
+    fn test_traffic_classes_in_text_report() {
+        let mut metrics = sample_metrics();
+        let mut checkout = sample_metrics();
+        checkout.total_requests = 40;
+        checkout.requests_per_second = 4.0;
+        metrics.endpoints.insert("checkout".to_string(), checkout.clone());
+        metrics.traffic_classes = vec![TrafficClassMetrics {
+            class: "priority".to_string(),
+            labels: vec!["checkout".to_string()],
+            metrics: checkout,
+        }];
+        let mut out = Vec::new();
+        PerfReport::write_text(&mut out, &metrics).unwrap();
+        let text = strip_ansi(&String::from_utf8(out).unwrap());
+        assert!(text.contains("TRAFFIC PRIORITY"));
+        assert!(!text.contains("Percentiles:"));
+        assert!(text.contains("   priority         40"));
+        assert!(text.contains("     checkout       40"));
+    }
+
+    #[test]
+    fn test_breakdowns_in_text_report() {
+        let mut metrics = sample_metrics();
+        metrics.status_codes = [(200, 95), (500, 5)].into();
+        metrics.error_categories = [("non_2xx".to_string(), 5)].into();
+        metrics.validation = vec![AssertionMetrics {
+            assertion: "status == 200".to_string(),
+            passed: 95,
+            failed: 5,
+            examples: vec!["GET /: status was 500".to_string()],
+        }];
+        let summary = |requests, p50_ms| LatencySummary {
+            requests,
+            min_ms: 1.0,
+            max_ms: 90.0,
+            avg_ms: p50_ms,
+            p50_ms,
+            p95_ms: p50_ms * 2.0,
+            p99_ms: p50_ms * 3.0,
+        };
+        metrics.success_latency = Some(summary(95, 20.0));
+        metrics.failure_latency = Some(summary(5, 1.5));
+        metrics.transfer = Some(TransferMetrics {
+            bytes_sent: 12_000,
+            bytes_received: 4_500_000,
+            avg_request_bytes: 120.0,
+            avg_response_bytes: 45_000.0,
+            sent_mb_per_second: 0.01,
+            received_mb_per_second: 3.75,
+        });
+        metrics.phases = Some(PhaseMetrics {
+            dns: Some(summary(10, 2.0)),
+            connect: Some(summary(10, 1.0)),
+            tls: None,
+            ttfb: summary(100, 15.0),
+            transfer: summary(100, 0.5),
+        });
+        metrics.iterations = Some(IterationMetrics {
+            iterations: 50,
+            failed_iterations: 2,
+            iterations_per_second: 5.0,
+            duration: summary(50, 200.0),
+        });
+        metrics.percentile_method = Some(PercentileMethod::Exact);
+        metrics.histogram = vec![
+            LatencyBucket { from_ms: 10.0, to_ms: 20.0, count: 80 },
+            LatencyBucket { from_ms: 20.0, to_ms: 40.0, count: 0 },
+            LatencyBucket { from_ms: 40.0, to_ms: 80.0, count: 20 },
+        ];
+
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json["status_codes"]["200"], 95);
+        assert_eq!(json["status_codes"]["500"], 5);
+
+        let mut out = Vec::new();
+        TextSink::new(&mut out, false)
+            .write(&RunReport { metrics: &metrics, thresholds: &[], records: None })
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("Status Codes"));
+        assert!(text.contains("   500        5  (5.00%)"));
+        assert!(text.contains("   success       95 reqs   p50     20.00 ms   p95     40.00 ms   p99     60.00 ms"));
+        assert!(text.contains("   failure        5 reqs   p50      1.50 ms   p95      3.00 ms   p99      4.50 ms"));
+        assert!(text.contains("   dns            10 reqs   p50      2.00 ms   p95      4.00 ms   p99      6.00 ms"));
+        assert!(text.contains("   transfer      100 reqs   p50      0.50 ms"));
+        assert!(text.contains("   Sent:                12.00 KB (avg 120 B per request)"));
+        assert!(text.contains("   Received:            4.50 MB (avg 45.00 KB per response)"));
+        assert!(text.contains("   Throughput:          3.75 MB/s received, 0.01 MB/s sent"));
+        assert!(text.contains("   Completed:           50 (2 failed)"));
+        assert!(text.contains("   Percentiles:         exact"));
+        assert!(text.contains("   Duration:            p50 200.00 ms, p95 400.00 ms, p99 600.00 ms, max 90.00 ms"));
+        assert!(!text.contains("   tls "));
+        assert!(text.contains("   non_2xx:             5"));
+        assert!(text.contains("   FAIL  status == 200       95 passed        5 failed"));
+        assert!(text.contains("         GET /: status was 500"));
+        assert!(text.contains(&format!("       10.00 -     20.00 ms  {}  80", "█".repeat(HISTOGRAM_WIDTH))));
+        assert!(text.contains(&format!("       20.00 -     40.00 ms  {}  0", " ".repeat(HISTOGRAM_WIDTH))));
+        assert!(text.contains(&format!("       40.00 -     80.00 ms  {:<40}  20", "█".repeat(10))));
+    }
 }
@@ -0,0 +1,337 @@
+//! Per-request log of a performance run.
+//!
+//! Writes one NDJSON line, or CSV row, per completed request. Targets
+//! ending in `.gz` are gzip-compressed as they are written, so
+//! multi-million-request runs don't fill the disk with raw records.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use crate::error::Result;
+use super::events::RunEvent;
+
+/// Layout of the request log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One JSON object per line (`--request-log`)
+    Ndjson,
+    /// Comma-separated values with a header row (`--results-csv`)
+    Csv,
+}
+
+/// Column names of the CSV log, in [`LogLine`] field order.
+const CSV_HEADER: &str = "seq,timestamp_ms,label,method,path,status,duration_ms,bytes,success,error";
+
+/// One line of the request log.
+#[derive(Debug, Serialize)]
+struct LogLine<'a> {
+    seq: usize,
+    /// When the request was issued, in milliseconds since the Unix epoch
+    timestamp_ms: u64,
+    label: &'a str,
+    method: &'a str,
+    path: &'a str,
+    status: Option<u16>,
+    duration_ms: f64,
+    bytes: u64,
+    success: bool,
+    error: Option<&'a str>,
+    /// `--capture` values; NDJSON only, as their names vary per run
+    #[serde(skip_serializing_if = "<[_]>::is_empty", serialize_with = "serialize_pairs")]
+    captures: &'a [(String, String)],
+}
+
+/// Serializes `(name, value)` pairs as a JSON object.
+fn serialize_pairs<S: serde::Serializer>(
+    pairs: &&[(String, String)],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_map(pairs.iter().map(|(name, value)| (name, value)))
+}
+
+impl LogLine<'_> {
+    fn write_csv(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{:.3},{},{},{}",
+            self.seq,
+            self.timestamp_ms,
+            csv_field(self.label),
+            self.method,
+            csv_field(self.path),
+            self.status.map(|s| s.to_string()).unwrap_or_default(),
+            self.duration_ms,
+            self.bytes,
+            self.success,
+            self.error.unwrap_or_default(),
+        )
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Returns the path and query of a URL (`/users?page=2`).
+fn url_path(url: &str) -> &str {
+    let after_scheme = url.find("://").map_or(url, |i| &url[i + 3..]);
+    after_scheme.find('/').map_or("/", |i| &after_scheme[i..])
+}
+
+/// Output file, plain or gzip-compressed.
+enum Sink {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl Sink {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Sink::Plain(w) => w,
+            Sink::Gzip(w) => w,
+        }
+    }
+}
+
+/// Writer for the per-request log.
+///
+/// Safe to share between worker tasks. Write errors are kept and reported
+/// by [`finish`](Self::finish) rather than interrupting the run.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let log = Arc::new(RequestLog::create(Path::new("requests.ndjson.gz"))?);
+/// let writer = Arc::clone(&log);
+/// runner.run_with_events(&dataset, move |event| writer.record(&event)).await?;
+/// log.finish()?;
+/// ```
+pub struct RequestLog {
+    format: LogFormat,
+    inner: Mutex<(Option<Sink>, Option<std::io::Error>)>,
+}
+
+impl RequestLog {
+    /// Creates an NDJSON log file, compressing it if the path ends in `.gz`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created.
+    pub fn create(path: &Path) -> Result<Self> {
+        Self::create_with_format(path, LogFormat::Ndjson)
+    }
+
+    /// Creates a log file in the given format, compressing it if the path
+    /// ends in `.gz`. A CSV log starts with a header row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created.
+    pub fn create_with_format(path: &Path, format: LogFormat) -> Result<Self> {
+        Self::from_file(File::create(path)?, path, format)
+    }
+
+    /// Creates a new NDJSON log file that only the current user can read,
+    /// for spooling records in a shared temporary directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file already exists or cannot be created.
+    pub fn create_private(path: &Path) -> Result<Self> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        Self::from_file(options.open(path)?, path, LogFormat::Ndjson)
+    }
+
+    fn from_file(file: File, path: &Path, format: LogFormat) -> Result<Self> {
+        let file = BufWriter::new(file);
+        let gzip = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"));
+        let mut sink = if gzip {
+            Sink::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            Sink::Plain(file)
+        };
+        if format == LogFormat::Csv {
+            writeln!(sink.writer(), "{}", CSV_HEADER)?;
+        }
+        Ok(Self {
+            format,
+            inner: Mutex::new((Some(sink), None)),
+        })
+    }
+
+    /// Appends a line for a [`RunEvent::RequestDone`]; other events are ignored.
+    pub fn record(&self, event: &RunEvent) {
+        let RunEvent::RequestDone {
+            completed, label, request, started, status, duration, bytes, success, error, captures, ..
+        } = event
+        else {
+            return;
+        };
+        let request = request.request();
+        let line = LogLine {
+            seq: *completed,
+            timestamp_ms: started.duration_since(UNIX_EPOCH).map_or(0, |t| t.as_millis() as u64),
+            label,
+            method: request.method.as_str(),
+            path: url_path(&request.url),
+            status: *status,
+            duration_ms: duration.as_secs_f64() * 1000.0,
+            bytes: *bytes,
+            success: *success,
+            error: *error,
+            captures,
+        };
+
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let (sink, error) = &mut *inner;
+        if error.is_some() {
+            return;
+        }
+        if let Some(sink) = sink {
+            let result = match self.format {
+                LogFormat::Ndjson => serde_json::to_writer(sink.writer(), &line)
+                    .map_err(std::io::Error::from)
+                    .and_then(|_| sink.writer().write_all(b"\n")),
+                LogFormat::Csv => line.write_csv(sink.writer()),
+            };
+            if let Err(e) = result {
+                *error = Some(e);
+            }
+        }
+    }
+
+    /// Flushes the log and completes the gzip stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error hit while writing, or an error from flushing.
+    pub fn finish(&self) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let (sink, error) = &mut *inner;
+        if let Some(e) = error.take() {
+            return Err(e.into());
+        }
+        match sink.take() {
+            Some(Sink::Plain(mut w)) => w.flush()?,
+            Some(Sink::Gzip(w)) => w.finish()?.flush()?,
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use crate::http::HttpRequest;
+    use std::io::Read;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn done(completed: usize) -> RunEvent {
+        RunEvent::RequestDone {
+            completed,
+            total: 2,
+            label: "GET /".to_string(),
+            request: Arc::new(HttpRequest::new("https://example.com/users?a=1,2").prepare().unwrap()),
+            started: UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+            status: Some(200),
+            duration: Duration::from_millis(12),
+            bytes: 42,
+            success: true,
+            error: None,
+            phases: None,
+            captures: vec![("reqid".to_string(), "r-1".to_string())],
+        }
+    }
+
+    #[test]
+    fn test_gzip_log() {
+        let path = std::env::temp_dir().join("hurley_test_requests.ndjson.gz");
+        let log = RequestLog::create(&path).unwrap();
+        log.record(&done(1));
+        log.record(&done(2));
+        log.finish().unwrap();
+
+        let mut content = String::new();
+        GzDecoder::new(File::open(&path).unwrap()).read_to_string(&mut content).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let lines: Vec<serde_json::Value> = content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["seq"], 2);
+        assert_eq!(lines[0]["status"], 200);
+    }
+
+    #[test]
+    fn test_plain_log() {
+        let path = std::env::temp_dir().join("hurley_test_requests.ndjson");
+        let log = RequestLog::create(&path).unwrap();
+        log.record(&done(1));
+        log.finish().unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(content.starts_with("{\"seq\":1,\"timestamp_ms\":1700000000123,"));
+        assert!(content.contains("\"captures\":{\"reqid\":\"r-1\"}"));
+    }
+
+    #[test]
+    fn test_private_log() {
+        let path = std::env::temp_dir().join("hurley_test_private_requests.ndjson");
+        std::fs::remove_file(&path).ok();
+        let log = RequestLog::create_private(&path).unwrap();
+        log.record(&done(1));
+        log.finish().unwrap();
+        // Never opens a file that is already there, or a symlink
+        assert!(RequestLog::create_private(&path).is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(content.starts_with("{\"seq\":1,"));
+    }
+
+    #[test]
+    fn test_csv_log() {
+        let path = std::env::temp_dir().join("hurley_test_results.csv");
+        let log = RequestLog::create_with_format(&path, LogFormat::Csv).unwrap();
+        log.record(&done(1));
+        log.record(&RunEvent::WarmupFinished);
+        log.finish().unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines, vec![
+            CSV_HEADER,
+            "1,1700000000123,GET /,GET,\"/users?a=1,2\",200,12.000,42,true,",
+        ]);
+    }
+
+    #[test]
+    fn test_url_path() {
+        assert_eq!(url_path("https://example.com/a/b?c=1"), "/a/b?c=1");
+        assert_eq!(url_path("http://example.com"), "/");
+    }
+}
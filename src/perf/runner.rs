@@ -2,20 +2,335 @@
 //!
 //! Executes concurrent HTTP requests using tokio and collects timing metrics.
 
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::iter::Peekable;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::Mutex;
-use indicatif::{ProgressBar, ProgressStyle};
+use std::time::{Duration, Instant, SystemTime};
+use reqwest::header::{HeaderValue, RANGE};
+use reqwest::{Method, StatusCode};
+use tokio::sync::{mpsc, Notify};
+use tokio_util::sync::CancellationToken;
 
-use crate::http::{HttpClient, HttpRequest};
-use crate::error::Result;
+use crate::captures::Capture;
+use crate::http::zone::strip_zone;
+use crate::http::version::version_name;
+use crate::http::range::{self, ContentRange};
+use crate::http::{ByteRange, CacheStatus, HttpClient, HttpRequest, HttpResponse, Middleware, PhaseTimings, PreparedRequest, ResolveEntry};
+use crate::error::{Result, RurlError};
+use super::assertions::Assertion;
 use super::dataset::{Dataset, DatasetEntry};
-use super::metrics::{MetricsCollector, PerfMetrics};
+use super::events::{IntervalStats, RunEvent};
+use super::metrics::{MetricsCollector, PerfMetrics, DEFAULT_EXACT_SAMPLES};
+use super::plan::{requests_per_entry, PlannedEntry, PlannedPhase, RunPlan};
+use super::slo::Slo;
+use super::stages::{LoadProfile, StageUnit};
+
+/// Returns true for methods that don't change anything on the server.
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE)
+}
+
+/// Upper bound on requests in flight when running at a fixed rate.
+const MAX_RATE_IN_FLIGHT: usize = 10_000;
+
+/// How often a concurrency profile re-checks its target while waiting for a free slot.
+const STAGE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Background requests that may be held back behind priority ones by default.
+pub const DEFAULT_MAX_DEFERRED: usize = 100;
+
+/// Callback invoked after each request with `(completed, total)` counts.
+pub type ProgressHook = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
+/// Outcome of one request, sent from its task to the metrics aggregator.
+struct Sample {
+    label: String,
+    finished: Instant,
+    duration: Duration,
+    dispatch_delay: Duration,
+    status: Option<u16>,
+    success: bool,
+    /// Why the request failed, see [`MetricsCollector::record_error`]
+    error: Option<&'static str>,
+    /// Outcome of each assertion, empty if there was no response
+    checks: Vec<Option<String>>,
+    /// Hash of the status and body, if responses are hashed
+    response_hash: Option<u64>,
+    /// Protocol version of the response
+    version: Option<&'static str>,
+    /// How the response cache answered, with `--cache`
+    cache: Option<CacheStatus>,
+    /// Time spent in each phase, if there was a response
+    phases: Option<PhaseTimings>,
+    /// Size of the request body
+    request_bytes: u64,
+    /// Size of the response body, if there was a response
+    response_bytes: Option<u64>,
+    stage: Option<usize>,
+}
+
+impl Sample {
+    fn record(self, c: &mut MetricsCollector) {
+        c.record_dispatch_delay(self.duration, self.dispatch_delay);
+        c.record_timeline(self.finished, self.duration, self.success);
+        if self.success {
+            c.record_success(self.duration, Some(&self.label));
+        } else {
+            c.record_failure(self.duration, Some(&self.label));
+        }
+        if let Some(status) = self.status {
+            c.record_status(self.duration, status);
+        }
+        if let Some(category) = self.error {
+            c.record_error(category);
+        }
+        if let Some(version) = self.version {
+            c.record_http_version(version);
+        }
+        if let Some(cache) = self.cache {
+            c.record_cache_status(&cache.to_string());
+        }
+        if let Some(phases) = &self.phases {
+            c.record_phases(phases);
+        }
+        c.record_bytes(self.request_bytes, self.response_bytes);
+        if let Some(hash) = self.response_hash {
+            c.record_response_hash(hash, Some(&self.label));
+        }
+        for (index, failure) in self.checks.into_iter().enumerate() {
+            c.record_assertion(index, failure);
+        }
+        if let Some(stage) = self.stage {
+            c.record_stage(stage, self.duration, self.success);
+        }
+    }
+}
+
+/// Hashes a response's status and body for [`PerfRunner::hash_responses`].
+fn response_hash(response: &HttpResponse) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    response.status.as_u16().hash(&mut hasher);
+    response.body.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// What every request of a run shares.
+struct RequestContext<F> {
+    client: Arc<HttpClient>,
+    samples: mpsc::UnboundedSender<Sample>,
+    completed: Arc<AtomicUsize>,
+    on_event: Arc<F>,
+    assertions: Arc<[Assertion]>,
+    captures: Arc<[Capture]>,
+    hash_responses: bool,
+    /// Whether response bodies are kept, see [`PerfRunner::assertions`]
+    keep_body: bool,
+    /// Total reported in [`RunEvent::RequestDone`]
+    total: usize,
+    cancel: CancellationToken,
+    /// Byte ranges every request asks for, see [`PerfRunner::ranges`]
+    ranges: Arc<[ByteRange]>,
+    /// Length of a random range and size of the object it is read from
+    random_range: Option<(u64, u64)>,
+}
+
+impl<F> RequestContext<F>
+where
+    F: Fn(RunEvent) + Send + Sync + 'static,
+{
+    /// Sends one request, hands its sample to the aggregator and emits
+    /// [`RunEvent::RequestDone`].
+    ///
+    /// Returns whether the request succeeded, or `None` if the run was
+    /// cancelled before it finished.
+    async fn send(&self, request: Arc<PreparedRequest>, label: String, issued: Instant, stage: Option<usize>) -> Option<bool> {
+        let scheduled = issued.elapsed();
+        let started = SystemTime::now() - scheduled;
+        let random = self
+            .random_range
+            .map(|(length, size)| ByteRange::random(length, size, &mut rand::thread_rng()));
+        let request = match random {
+            Some(range) => {
+                let value = HeaderValue::from_str(&range::header_value(&[range])).expect("range header is ASCII");
+                Arc::new(request.with_header(RANGE, value))
+            }
+            None => request,
+        };
+        let ranges = random.as_ref().map_or(&self.ranges[..], std::slice::from_ref);
+        let send = async {
+            if self.keep_body {
+                self.client.execute_prepared(&request).await.map(|r| {
+                    let size = r.body.len() as u64;
+                    (r, size)
+                })
+            } else {
+                self.client.execute_discarding(&request).await
+            }
+        };
+        let result = tokio::select! {
+            result = send => result,
+            _ = self.cancel.cancelled() => return None,
+        };
+        let duration = issued.elapsed();
+        let bytes = result.as_ref().map_or(0, |(_, size)| *size);
+        let result = result.map(|(response, _)| response);
+        let setup = result.as_ref().map(|r| r.client_setup).unwrap_or_default();
+
+        let (checks, captured) = match &result {
+            Ok(response) => (
+                self.assertions
+                    .iter()
+                    .map(|a| a.check(response).map(|failure| format!("{}: {}", label, failure)))
+                    .collect(),
+                self.captures
+                    .iter()
+                    .filter_map(|c| c.extract(response).ok().map(|value| (c.name.clone(), value)))
+                    .collect(),
+            ),
+            Err(_) => (Vec::new(), Vec::new()),
+        };
+        let request_bytes = request.body().map_or(0, |body| body.len() as u64);
+        // A cache hit never went over the network
+        let sent = result.as_ref().ok().filter(|r| r.cache != Some(CacheStatus::Hit));
+        let version = sent.map(|r| version_name(r.version));
+        let phases = sent.map(|r| r.phases);
+        let cache = result.as_ref().ok().and_then(|r| r.cache);
+        let response_hash = match &result {
+            Ok(response) if self.hash_responses => Some(response_hash(response)),
+            _ => None,
+        };
+        let range_ok = match &result {
+            Ok(response) if !ranges.is_empty() => range::check(ranges, response, bytes).is_ok(),
+            _ => true,
+        };
+        let (status, success, error) = match result {
+            Ok(response) if !range_ok => (Some(response.status.as_u16()), false, Some("range")),
+            Ok(response) if response.is_success() => (Some(response.status.as_u16()), true, None),
+            // A revalidated resource, which is what a conditional request asks for
+            Ok(response) if response.status == StatusCode::NOT_MODIFIED && request.request().is_conditional() => {
+                (Some(response.status.as_u16()), true, None)
+            }
+            Ok(response) => (Some(response.status.as_u16()), false, Some("non_2xx")),
+            Err(e) => (None, false, Some(e.kind())),
+        };
+        // The aggregator only stops once every sender is gone
+        let _ = self.samples.send(Sample {
+            label: label.clone(),
+            finished: Instant::now(),
+            duration,
+            dispatch_delay: scheduled + setup,
+            status,
+            success,
+            error,
+            checks,
+            response_hash,
+            version,
+            cache,
+            phases,
+            request_bytes,
+            response_bytes: status.map(|_| bytes),
+            stage,
+        });
+
+        let done = self.completed.fetch_add(1, Ordering::Relaxed) + 1;
+        (self.on_event)(RunEvent::RequestDone {
+            completed: done,
+            total: self.total,
+            label,
+            request,
+            started,
+            status,
+            duration,
+            bytes,
+            success,
+            error,
+            phases,
+            captures: captured,
+        });
+        Some(success)
+    }
+}
+
+/// Runs one virtual user: every template in order, `count` times.
+///
+/// Returns the duration of each completed iteration and whether all of
+/// its requests succeeded. Stops at the first request abandoned on
+/// cancellation; that iteration is left out.
+async fn run_iterations<F>(
+    context: Arc<RequestContext<F>>,
+    templates: Arc<[(Arc<PreparedRequest>, String)]>,
+    count: usize,
+) -> Vec<(Duration, bool)>
+where
+    F: Fn(RunEvent) + Send + Sync + 'static,
+{
+    let mut iterations = Vec::with_capacity(count);
+    for _ in 0..count {
+        let began = Instant::now();
+        let mut success = true;
+        for (request, label) in templates.iter() {
+            match context.send(Arc::clone(request), label.clone(), Instant::now(), None).await {
+                Some(ok) => success &= ok,
+                None => return iterations,
+            }
+        }
+        iterations.push((began.elapsed(), success));
+    }
+    iterations
+}
+
+/// Order in which the dispatcher sends requests.
+///
+/// Requests go out in dataset order, except that while the client is
+/// saturated, requests for priority entries skip ahead of background ones.
+/// Skipped requests are sent first once a slot frees up with no priority
+/// request next, and at most `max_deferred` are held back at a time, so
+/// background traffic is delayed but never starved.
+struct DispatchQueue {
+    upcoming: Peekable<Box<dyn Iterator<Item = usize> + Send>>,
+    /// Whether each template is a priority entry
+    priority: Vec<bool>,
+    deferred: VecDeque<usize>,
+    max_deferred: usize,
+}
+
+impl DispatchQueue {
+    fn new(upcoming: Box<dyn Iterator<Item = usize> + Send>, priority: Vec<bool>, max_deferred: usize) -> Self {
+        Self {
+            upcoming: upcoming.peekable(),
+            priority,
+            deferred: VecDeque::new(),
+            max_deferred,
+        }
+    }
+
+    fn is_empty(&mut self) -> bool {
+        self.deferred.is_empty() && self.upcoming.peek().is_none()
+    }
+
+    /// Returns the template to send next.
+    fn next(&mut self, saturated: bool) -> Option<usize> {
+        if saturated && self.priority.contains(&true) {
+            while self.deferred.len() <= self.max_deferred {
+                match self.upcoming.next() {
+                    Some(template) if self.priority[template] => return Some(template),
+                    Some(template) => self.deferred.push_back(template),
+                    None => break,
+                }
+            }
+        }
+        self.deferred.pop_front().or_else(|| self.upcoming.next())
+    }
+}
 
 /// Performance test runner.
 ///
 /// Executes HTTP requests concurrently using tokio with configurable
-/// concurrency limits and progress tracking.
+/// concurrency limits and progress tracking via [`on_progress`](Self::on_progress).
 ///
 /// # Example
 ///
@@ -35,6 +350,31 @@ pub struct PerfRunner {
     concurrency: usize,
     total_requests: usize,
     verbose: bool,
+    progress: Option<ProgressHook>,
+    stats_interval: Duration,
+    cancel: CancellationToken,
+    run_timeout: Option<Duration>,
+    duration: Option<Duration>,
+    rate: Option<f64>,
+    ramp_up: Option<Duration>,
+    stages: Option<LoadProfile>,
+    warmup: Option<Duration>,
+    warmup_requests: usize,
+    assertions: Arc<[Assertion]>,
+    captures: Arc<[Capture]>,
+    timeline_window: Option<Duration>,
+    slo: Option<Slo>,
+    hash_responses: bool,
+    keepalive: bool,
+    iterations: Option<usize>,
+    priority: Vec<String>,
+    max_deferred: usize,
+    exact_samples: usize,
+    ranges: Vec<ByteRange>,
+    random_range: Option<u64>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    /// Client of the run this one warms up, so both share connections
+    client: Option<Arc<HttpClient>>,
 }
 
 impl PerfRunner {
@@ -60,106 +400,809 @@ impl PerfRunner {
             concurrency,
             total_requests,
             verbose,
+            progress: None,
+            stats_interval: Duration::from_secs(1),
+            cancel: CancellationToken::new(),
+            run_timeout: None,
+            duration: None,
+            rate: None,
+            ramp_up: None,
+            stages: None,
+            warmup: None,
+            warmup_requests: 0,
+            assertions: Arc::new([]),
+            captures: Arc::new([]),
+            timeline_window: Some(Duration::from_secs(1)),
+            slo: None,
+            hash_responses: false,
+            keepalive: true,
+            iterations: None,
+            priority: Vec::new(),
+            max_deferred: DEFAULT_MAX_DEFERRED,
+            exact_samples: DEFAULT_EXACT_SAMPLES,
+            ranges: Vec::new(),
+            random_range: None,
+            middleware: Vec::new(),
+            client: None,
         }
     }
 
+    /// Registers a callback that is invoked after each completed request.
+    ///
+    /// The callback receives the number of completed requests and the total
+    /// number of requests, and is called from the worker tasks, so it must be
+    /// cheap and thread-safe. The CLI uses this to drive its progress bar.
+    pub fn on_progress<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(usize, usize) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets how often [`RunEvent::IntervalStats`] events are emitted.
+    ///
+    /// Defaults to one second.
+    pub fn stats_interval(mut self, interval: Duration) -> Self {
+        self.stats_interval = interval;
+        self
+    }
+
+    /// Sets a token that stops the run when cancelled.
+    ///
+    /// On cancellation no new requests are started, requests in flight are
+    /// abandoned without being recorded, and the run returns the metrics
+    /// collected so far.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Sets an upper bound on the duration of the whole run.
+    ///
+    /// When the timeout elapses the run stops as if cancelled and returns
+    /// the metrics collected so far. This is independent of the per-request
+    /// timeout configured on the base request.
+    pub fn run_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.run_timeout = timeout;
+        self
+    }
+
+    /// Runs for a fixed wall-clock duration instead of a request count.
+    ///
+    /// Requests keep being issued, cycling through the dataset, until the
+    /// duration elapses; requests already in flight are allowed to finish.
+    /// The total request count is ignored, and [`RunEvent::RequestDone`]
+    /// reports a `total` of 0.
+    pub fn duration(mut self, duration: Option<Duration>) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Starts requests at a fixed rate (requests per second).
+    ///
+    /// This is an open model: requests start on schedule regardless of how
+    /// long earlier ones take, and the concurrency limit is not used.
+    /// Latency is measured from when each request was due, so a client
+    /// that falls behind schedule shows up in the numbers instead of
+    /// silently lowering the load.
+    pub fn rate(mut self, rate: Option<f64>) -> Self {
+        self.rate = rate.filter(|r| *r > 0.0);
+        self
+    }
+
+    /// Ramps the load up linearly from zero over the given time, then holds it.
+    ///
+    /// The load is the concurrency limit, or the arrival rate if one is
+    /// set. The run length is still set by the request count or duration.
+    pub fn ramp_up(mut self, ramp_up: Option<Duration>) -> Self {
+        self.ramp_up = ramp_up;
+        self
+    }
+
+    /// Varies the load over time following a stage profile.
+    ///
+    /// Concurrency targets limit the requests in flight; rate targets
+    /// (`/s`) set the arrival rate, as with [`rate`](Self::rate). The run
+    /// ends with the last stage and the request count is ignored. Takes
+    /// precedence over [`ramp_up`](Self::ramp_up).
+    pub fn stages(mut self, stages: Option<LoadProfile>) -> Self {
+        self.stages = stages;
+        self
+    }
+
+    /// Runs requests for this long before the measured run, discarding their results.
+    ///
+    /// Warmup runs at the configured concurrency or rate, so connection
+    /// setup and cold server caches don't pollute the percentiles. Takes
+    /// precedence over [`warmup_requests`](Self::warmup_requests).
+    pub fn warmup(mut self, warmup: Option<Duration>) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Runs this many requests before the measured run, discarding their results.
+    ///
+    /// The warmup requests come on top of the measured request count.
+    pub fn warmup_requests(mut self, requests: usize) -> Self {
+        self.warmup_requests = requests;
+        self
+    }
+
+    /// Checks every response against these assertions.
+    ///
+    /// Results are reported per assertion in [`PerfMetrics::validation`]
+    /// and don't affect whether a request counts as failed. Response bodies
+    /// are only kept in memory when an assertion, capture or
+    /// [`hash_responses`](Self::hash_responses) needs them; otherwise they
+    /// are counted and dropped as they arrive.
+    pub fn assertions(mut self, assertions: Vec<Assertion>) -> Self {
+        self.assertions = assertions.into();
+        self
+    }
+
+    /// Extracts these values from every response.
+    ///
+    /// Values are passed on in [`RunEvent::RequestDone`], e.g. to be
+    /// written to the request log; missing values are left out.
+    pub fn captures(mut self, captures: Vec<Capture>) -> Self {
+        self.captures = captures.into();
+        self
+    }
+
+    /// Sets the window length of the latency-over-time timeline.
+    ///
+    /// Defaults to one second; `None` leaves [`PerfMetrics::timeline`] empty.
+    pub fn timeline_window(mut self, window: Option<Duration>) -> Self {
+        self.timeline_window = window;
+        self
+    }
+
+    /// Sets an SLO to check per window, reported in [`PerfMetrics::slo`].
+    pub fn slo(mut self, slo: Option<Slo>) -> Self {
+        self.slo = slo;
+        self
+    }
+
+    /// Hashes every response body to count distinct responses, reported in
+    /// [`PerfMetrics::responses`].
+    pub fn hash_responses(mut self, enabled: bool) -> Self {
+        self.hash_responses = enabled;
+        self
+    }
+
+    /// Reuses connections between requests (the default).
+    ///
+    /// All requests of a run share one client and connection pool. Without
+    /// keepalive, every request opens a new connection, as separate clients
+    /// would.
+    pub fn keepalive(mut self, enabled: bool) -> Self {
+        self.keepalive = enabled;
+        self
+    }
+
+    /// Runs as virtual users that each go through the dataset this many times.
+    ///
+    /// The concurrency is the number of virtual users. Each runs the
+    /// dataset entries in order as a scenario, waiting for every response
+    /// before sending the next request, and the time for each pass is
+    /// reported in [`PerfMetrics::iterations`]. Takes precedence over the
+    /// request count, duration, rate and stages.
+    pub fn iterations(mut self, iterations: Option<usize>) -> Self {
+        self.iterations = iterations.filter(|n| *n > 0);
+        self
+    }
+
+    /// Sends requests for these endpoint labels (usually dataset tags)
+    /// ahead of the rest whenever every slot is busy.
+    ///
+    /// Priority and background requests are reported apart in
+    /// [`PerfMetrics::traffic_classes`], to show how background traffic
+    /// affects the latency of the critical path. The mix of requests over
+    /// the whole run is unchanged; only their order is.
+    pub fn priority(mut self, labels: Vec<String>) -> Self {
+        self.priority = labels;
+        self
+    }
+
+    /// Caps how many background requests [`priority`](Self::priority)
+    /// entries may hold back at once (default [`DEFAULT_MAX_DEFERRED`]).
+    ///
+    /// Lower values are fairer to background traffic; 0 disables
+    /// reordering but keeps the split report.
+    pub fn max_deferred(mut self, max_deferred: usize) -> Self {
+        self.max_deferred = max_deferred;
+        self
+    }
+
+    /// Computes exact latency percentiles for runs of up to this many
+    /// requests (default [`DEFAULT_EXACT_SAMPLES`]); longer runs use the
+    /// histogram. [`PerfMetrics::percentile_method`] says which was used.
+    pub fn exact_samples(mut self, limit: usize) -> Self {
+        self.exact_samples = limit;
+        self
+    }
+
+    /// Asks every request for these byte ranges.
+    ///
+    /// A response that doesn't answer them with a matching `206 Partial
+    /// Content` (see [`range::check`]) fails with the `range` error.
+    pub fn ranges(mut self, ranges: Vec<ByteRange>) -> Self {
+        self.ranges = ranges;
+        self
+    }
+
+    /// Reads `length` bytes at a random offset of the target per request,
+    /// instead of [`ranges`](Self::ranges).
+    ///
+    /// The object's size is probed with a one-byte range before the run;
+    /// the run fails if the server doesn't answer it with a `Content-Range`.
+    pub fn random_range(mut self, length: Option<u64>) -> Self {
+        self.random_range = length.filter(|n| *n > 0);
+        self
+    }
+
+    /// Adds a middleware run around every request of the run, warmup and
+    /// preflight included, like [`HttpClient::middleware`].
+    ///
+    /// Each request is compiled again after the middleware changed it, so
+    /// middleware add some client-side time to every request.
+    pub fn middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Registers the run's middleware on `client`.
+    fn with_middleware(&self, client: HttpClient) -> HttpClient {
+        self.middleware.iter().fold(client, |client, middleware| client.middleware(Arc::clone(middleware)))
+    }
+
+    /// Returns the size of the object a request reads, from the
+    /// `Content-Range` of a one-byte range request.
+    async fn object_size(&self, client: &HttpClient, request: &PreparedRequest) -> Result<u64> {
+        let probe = request.with_header(RANGE, HeaderValue::from_static("bytes=0-0"));
+        let (response, _) = client.execute_discarding(&probe).await?;
+        ContentRange::from_headers(&response.headers)
+            .and_then(|content_range| content_range.size)
+            .filter(|_| response.status.as_u16() == 206)
+            .ok_or_else(|| {
+                RurlError::PerfError(format!(
+                    "{} doesn't serve byte ranges (answered {} to a one-byte range), so no random ranges can be read",
+                    request.request().url,
+                    response.status
+                ))
+            })
+    }
+
+    /// Runs the warmup phase, if any, discarding its metrics and events.
+    ///
+    /// Warmup requests go through the run's `client`, leaving its pool warm.
+    async fn warm_up(&self, dataset: &Dataset, client: &Arc<HttpClient>) -> Result<bool> {
+        if self.warmup.is_none() && self.warmup_requests == 0 {
+            return Ok(false);
+        }
+        let warmup = PerfRunner::new(
+            self.base_url.clone(),
+            self.base_request.clone(),
+            self.concurrency,
+            self.warmup_requests,
+            self.verbose,
+        )
+        .cancellation_token(self.cancel.clone())
+        .duration(self.warmup)
+        .rate(self.rate)
+        .ranges(self.ranges.clone())
+        .random_range(self.random_range);
+        let warmup = PerfRunner {
+            client: Some(Arc::clone(client)),
+            ..warmup
+        };
+        Box::pin(warmup.run_with_events(dataset, |_| {})).await?;
+        Ok(true)
+    }
+
+    /// Sends one canary request for the first dataset entry with a safe
+    /// method (`GET`, `HEAD`, `OPTIONS` or `TRACE`) before the run.
+    ///
+    /// Fails with a diagnostic if the request can't be sent at all (DNS,
+    /// connect or TLS failure, timeout) or is rejected as unauthorized, so a
+    /// misconfigured run stops before producing thousands of identical
+    /// failures. The canary is not part of the measured metrics. A dataset
+    /// of only unsafe requests gets no canary, as it would be an extra,
+    /// unmeasured change on the server.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::PreflightFailed`] describing what went wrong.
+    pub async fn preflight(&self, dataset: &Dataset) -> Result<()> {
+        let mut safe = dataset.entries.iter().filter_map(|entry| {
+            let request = self.build_request(entry, &dataset.hosts);
+            match &request {
+                Ok(request) if !is_safe(&request.method) => None,
+                _ => Some((entry, request)),
+            }
+        });
+        let Some((entry, request)) = safe.next() else {
+            return Ok(());
+        };
+        let request = request?.prepare()?;
+        let host = url::Url::parse(&strip_zone(&request.request().url))
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| request.request().url.clone());
+
+        let client = self.with_middleware(HttpClient::new(self.verbose));
+        let response = tokio::select! {
+            result = client.execute_prepared(&request) => result,
+            _ = self.cancel.cancelled() => return Ok(()),
+        };
+        let reason = match response {
+            Ok(response) => match response.status.as_u16() {
+                401 | 403 | 407 => format!(
+                    "{} returned {}; check the credentials",
+                    entry.label(),
+                    response.status
+                ),
+                _ => return Ok(()),
+            },
+            Err(e) => match e.kind() {
+                "dns" => format!("DNS lookup for {} failed: {}", host, e),
+                "connect" => format!("could not connect to {}: {}", host, e),
+                "tls" => format!("TLS handshake with {} failed: {}", host, e),
+                "timeout" => format!("{} timed out: {}", entry.label(), e),
+                _ => format!("{}: {}", entry.label(), e),
+            },
+        };
+        Err(RurlError::PreflightFailed(reason))
+    }
+
+    /// Returns the load profile from `stages` or `ramp_up`, if any.
+    fn load_profile(&self) -> Option<LoadProfile> {
+        if self.iterations.is_some() {
+            return None;
+        }
+        self.stages.clone().or_else(|| {
+            self.ramp_up.map(|ramp_up| match self.rate {
+                Some(rate) => LoadProfile::ramp_up(ramp_up, rate, StageUnit::Rate),
+                None => LoadProfile::ramp_up(ramp_up, self.concurrency as f64, StageUnit::Concurrency),
+            })
+        })
+    }
+
+    /// Returns how long a time-based run lasts: the duration or the end of
+    /// the last stage, whichever comes first.
+    fn run_length(&self, profile: Option<&LoadProfile>) -> Option<Duration> {
+        if self.iterations.is_some() {
+            return None;
+        }
+        match (self.duration, profile.and_then(LoadProfile::end)) {
+            (Some(duration), Some(end)) => Some(duration.min(end)),
+            (duration, end) => duration.or(end),
+        }
+    }
+
+    /// Works out what [`run`](Self::run) would do, without sending anything.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a dataset entry can't be turned into a request.
+    pub fn plan(&self, dataset: &Dataset) -> Result<RunPlan> {
+        let profile = self.load_profile();
+        let rate_profile = profile.as_ref().filter(|p| p.unit() == StageUnit::Rate);
+        let run_length = self.run_length(profile.as_ref());
+
+        // Request counts are only known up front for a fixed count or an
+        // arrival rate; a closed model's throughput depends on the server
+        let (total_requests, estimated_duration) = match run_length {
+            None if self.iterations.is_some() => {
+                (self.iterations.map(|n| n * self.concurrency * dataset.len()), None)
+            }
+            None => {
+                let total = self.total_requests;
+                let estimate = match (rate_profile, self.rate) {
+                    (Some(profile), _) => profile.arrival_time(total as f64).map(|t| t.as_secs_f64()),
+                    (None, Some(rate)) => Some(total as f64 / rate),
+                    (None, None) => None,
+                };
+                (Some(total), estimate)
+            }
+            Some(length) => {
+                let total = match (rate_profile, self.rate) {
+                    (Some(profile), _) => Some(profile.arrivals_by(length).ceil() as usize),
+                    (None, Some(rate)) => Some((rate * length.as_secs_f64()).ceil() as usize),
+                    (None, None) => None,
+                };
+                (total, Some(length.as_secs_f64()))
+            }
+        };
+
+        let counts = total_requests.map(|total| requests_per_entry(total, dataset.len()));
+        let entries = dataset
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let request = self.build_request(entry, &dataset.hosts)?;
+                Ok(PlannedEntry {
+                    label: entry.label(),
+                    method: request.method.to_string(),
+                    url: request.url,
+                    requests: counts.as_ref().map(|counts| counts[index]),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let schedule = match &profile {
+            Some(profile) => {
+                let unit = match profile.unit() {
+                    StageUnit::Concurrency => "concurrency",
+                    StageUnit::Rate => "rate",
+                };
+                let mut from = 0.0;
+                let targets = profile.stages().iter().map(|stage| {
+                    let phase = (from, stage.target);
+                    from = stage.target;
+                    phase
+                });
+                // A held profile ends with an open-ended phase at its last target
+                let last = profile.stages().last().map_or(0.0, |stage| stage.target);
+                let targets: Vec<_> = targets.chain(std::iter::once((last, last))).collect();
+                profile
+                    .stage_spans()
+                    .into_iter()
+                    .zip(targets)
+                    .map(|((label, start, length), (from, to))| PlannedPhase {
+                        label,
+                        start_seconds: start.as_secs_f64(),
+                        duration_seconds: length.map(|l| l.as_secs_f64()),
+                        from,
+                        to,
+                        unit,
+                    })
+                    .collect()
+            }
+            None => {
+                let (load, unit, label) = match (self.iterations, self.rate) {
+                    (Some(iterations), _) => (
+                        self.concurrency as f64,
+                        "concurrency",
+                        format!("{} VUs x {} iterations", self.concurrency, iterations),
+                    ),
+                    (None, Some(rate)) => (rate, "rate", format!("constant {}/s", rate)),
+                    (None, None) => (self.concurrency as f64, "concurrency", format!("constant {}", self.concurrency)),
+                };
+                vec![PlannedPhase {
+                    label,
+                    start_seconds: 0.0,
+                    duration_seconds: run_length.map(|l| l.as_secs_f64()),
+                    from: load,
+                    to: load,
+                    unit,
+                }]
+            }
+        };
+
+        Ok(RunPlan {
+            url: self.base_url.clone(),
+            total_requests,
+            warmup_requests: if self.warmup.is_some() { 0 } else { self.warmup_requests },
+            warmup_seconds: self.warmup.map(|w| w.as_secs_f64()),
+            duration_seconds: run_length.map(|l| l.as_secs_f64()),
+            estimated_duration_seconds: estimated_duration,
+            entries,
+            schedule,
+        })
+    }
+
     /// Runs the performance test and returns collected metrics.
     ///
     /// Executes requests concurrently according to the concurrency limit,
     /// cycling through dataset entries if needed to reach the total request count.
     pub async fn run(&self, dataset: &Dataset) -> Result<PerfMetrics> {
-        let collector = Arc::new(Mutex::new(MetricsCollector::new()));
-        
-        // Create progress bar
-        let pb = ProgressBar::new(self.total_requests as u64);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({per_sec})")
-                .expect("Invalid progress bar template")
-                .progress_chars("#>-")
+        let progress = self.progress.clone();
+        self.run_with_events(dataset, move |event| {
+            if let (Some(hook), RunEvent::RequestDone { completed, total, .. }) = (&progress, &event) {
+                hook(*completed, *total);
+            }
+        })
+        .await
+    }
+
+    /// Runs the performance test, reporting live [`RunEvent`]s to a callback.
+    ///
+    /// The callback receives [`RunEvent::WarmupFinished`] after any warmup
+    /// phase, a [`RunEvent::RequestDone`] for every measured request,
+    /// [`RunEvent::IntervalStats`] at the configured stats interval, and a
+    /// final [`RunEvent::Finished`]. It is called from worker tasks, so it
+    /// must be cheap and thread-safe.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let metrics = runner.run_with_events(&dataset, |event| {
+    ///     if let RunEvent::IntervalStats(stats) = event {
+    ///         println!("{:.1} req/s", stats.interval_requests_per_second);
+    ///     }
+    /// }).await?;
+    /// ```
+    pub async fn run_with_events<F>(&self, dataset: &Dataset, on_event: F) -> Result<PerfMetrics>
+    where
+        F: Fn(RunEvent) + Send + Sync + 'static,
+    {
+        let on_event = Arc::new(on_event);
+        let collector = self.run_collector(dataset, Arc::clone(&on_event)).await?;
+        let metrics = collector.compute_metrics();
+        on_event(RunEvent::Finished(metrics.clone()));
+        Ok(metrics)
+    }
+
+    /// Runs the performance test and returns the finished collector.
+    ///
+    /// Like [`run_with_events`](Self::run_with_events), but without the
+    /// final [`RunEvent::Finished`], for callers that merge collectors.
+    pub(crate) async fn run_collector<F>(&self, dataset: &Dataset, on_event: Arc<F>) -> Result<MetricsCollector>
+    where
+        F: Fn(RunEvent) + Send + Sync + 'static,
+    {
+        // Compile every dataset entry once up front; each send then only
+        // clones reference-counted headers and body
+        let templates = dataset
+            .entries
+            .iter()
+            .map(|entry| {
+                let request = self.build_request(entry, &dataset.hosts)?.prepare()?;
+                Ok((Arc::new(request), entry.label()))
+            })
+            .collect::<Result<Arc<[_]>>>()?;
+
+        // One client for the whole run, so connections are reused
+        let client = match &self.client {
+            Some(client) => Arc::clone(client),
+            None => Arc::new(self.with_middleware(HttpClient::pooled(
+                templates.iter().map(|(request, _)| request.as_ref()),
+                self.keepalive,
+                self.verbose,
+            )?)),
+        };
+
+        let random_range = match (self.random_range, templates.first()) {
+            (Some(length), Some((request, _))) => Some((length, self.object_size(&client, request).await?)),
+            _ => None,
+        };
+
+        if self.warm_up(dataset, &client).await? {
+            on_event(RunEvent::WarmupFinished);
+        }
+
+        let completed = Arc::new(AtomicUsize::new(0));
+
+        let profile = self.load_profile();
+        let rate_profile = profile.as_ref().filter(|p| p.unit() == StageUnit::Rate);
+        let concurrency_profile = profile.as_ref().filter(|p| p.unit() == StageUnit::Concurrency);
+        let open_model = self.rate.is_some() || rate_profile.is_some();
+
+        // Determine which requests to make: a fixed count, or cycling
+        // through the dataset until the run duration or last stage ends
+        let run_length = self.run_length(profile.as_ref());
+        let requests_to_make: Box<dyn Iterator<Item = usize> + Send> = match run_length {
+            Some(_) => Box::new((0..templates.len()).cycle()),
+            None => Box::new((0..templates.len()).cycle().take(self.total_requests)),
+        };
+        let deadline = run_length.map(|length| tokio::time::Instant::now() + length);
+        let total = match (self.iterations, run_length) {
+            (Some(iterations), _) => self.concurrency * iterations * templates.len(),
+            (None, Some(_)) => 0,
+            (None, None) => self.total_requests,
+        };
+
+        // Record start time; request tasks send their samples to a single
+        // aggregator that owns the collector, so recording never blocks
+        let mut collector = MetricsCollector::new();
+        collector.start();
+        if let Some(profile) = &profile {
+            collector.set_stages(profile.stage_spans());
+        }
+        collector.set_assertions(self.assertions.iter().map(ToString::to_string).collect());
+        collector.set_timeline_window(self.timeline_window);
+        collector.set_slo(self.slo.clone());
+        collector.set_priority_labels(self.priority.clone());
+        collector.set_exact_samples(self.exact_samples);
+        let (samples, received) = mpsc::unbounded_channel();
+        let aggregator = self.spawn_aggregator(
+            collector,
+            received,
+            Arc::clone(&completed),
+            Arc::clone(&on_event),
         );
 
-        // Determine how many requests to make
-        let requests_to_make: Vec<DatasetEntry> = if dataset.len() >= self.total_requests {
-            dataset.entries.iter().take(self.total_requests).cloned().collect()
+        // Stop on external cancellation or when the run timeout elapses,
+        // without cancelling the caller's token
+        let cancel = self.cancel.child_token();
+        if let Some(timeout) = self.run_timeout {
+            let cancel = cancel.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                cancel.cancel();
+            });
+        }
+
+        // Bodies are only kept when something inspects them; otherwise they
+        // are counted and dropped, so large responses don't pile up in memory
+        let keep_body = self.hash_responses
+            || self.assertions.iter().any(Assertion::needs_body)
+            || self.captures.iter().any(Capture::needs_body);
+        let context = Arc::new(RequestContext {
+            client,
+            samples,
+            completed: Arc::clone(&completed),
+            on_event: Arc::clone(&on_event),
+            assertions: Arc::clone(&self.assertions),
+            captures: Arc::clone(&self.captures),
+            hash_responses: self.hash_responses,
+            keep_body,
+            total,
+            cancel: cancel.clone(),
+            ranges: self.ranges.iter().copied().collect(),
+            random_range,
+        });
+
+        let mut iterations = Vec::new();
+        if let Some(count) = self.iterations {
+            // Each virtual user runs the whole dataset in order, one
+            // request at a time, `count` times over
+            let users: Vec<_> = (0..self.concurrency)
+                .map(|_| tokio::spawn(run_iterations(Arc::clone(&context), Arc::clone(&templates), count)))
+                .collect();
+            for user in users {
+                iterations.extend(user.await.map_err(|e| RurlError::PerfError(e.to_string()))?);
+            }
         } else {
-            // Cycle through dataset entries
-            dataset.entries
-                .iter()
-                .cycle()
-                .take(self.total_requests)
-                .cloned()
-                .collect()
-        };
-
-        // Record start time
-        {
-            let mut c = collector.lock().await;
-            c.start();
-        }
-
-        // Create semaphore for concurrency control
-        let semaphore = Arc::new(tokio::sync::Semaphore::new(self.concurrency));
-
-        let mut handles = Vec::new();
-
-        for entry in requests_to_make {
-            let permit = semaphore.clone().acquire_owned().await.unwrap();
-            let collector = Arc::clone(&collector);
-            let pb = pb.clone();
-            let request = self.build_request(&entry)?;
-            let verbose = self.verbose;
-            
-            // Create label for metrics (e.g., "GET /api/v1/users")
-            let path_label = entry.path.as_deref().unwrap_or("/");
-            let label = format!("{} {}", entry.method, path_label);
-
-            let handle = tokio::spawn(async move {
-                let client = HttpClient::new(verbose);
-                let start = Instant::now();
-                let result = client.execute(&request).await;
-                let duration = start.elapsed();
-
-                {
-                    let mut c = collector.lock().await;
-                    match result {
-                        Ok(response) if response.is_success() => {
-                            c.record_success(duration, Some(&label));
-                        }
-                        Ok(_) => {
-                            c.record_failure(duration, Some(&label));
-                        }
-                        Err(_) => {
-                            c.record_failure(duration, Some(&label));
+            // Create semaphore for concurrency control; at an arrival rate
+            // it only caps requests in flight as a safety net
+            let limit = if open_model {
+                MAX_RATE_IN_FLIGHT
+            } else {
+                concurrency_profile.map_or(self.concurrency, |p| (p.max_target().ceil() as usize).max(1))
+            };
+            let semaphore = Arc::new(tokio::sync::Semaphore::new(limit));
+            let slot_freed = Arc::new(Notify::new());
+            let start = tokio::time::Instant::now();
+
+            let priority = templates.iter().map(|(_, label)| self.priority.contains(label)).collect();
+            let mut queue = DispatchQueue::new(requests_to_make, priority, self.max_deferred);
+            let mut index = 0;
+            'dispatch: while !queue.is_empty() {
+                let scheduled = if open_model {
+                    // At an arrival rate, wait for this request's start slot
+                    let due = match rate_profile {
+                        Some(profile) => profile.arrival_time(index as f64),
+                        None => self.rate.map(|rate| Duration::from_secs_f64(index as f64 / rate)),
+                    };
+                    let Some(due) = due else { break };
+                    let at = start + due;
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(at) => {}
+                        _ = cancel.cancelled() => break,
+                        _ = sleep_until(deadline) => break,
+                    }
+                    Some(at.into_std())
+                } else {
+                    // Following a concurrency profile, wait until fewer requests
+                    // are in flight than the current target
+                    if let Some(profile) = concurrency_profile {
+                        loop {
+                            let Some(target) = profile.target_at(start.elapsed()) else { break 'dispatch };
+                            if limit - semaphore.available_permits() < target.ceil() as usize {
+                                break;
+                            }
+                            tokio::select! {
+                                _ = slot_freed.notified() => {}
+                                _ = tokio::time::sleep(STAGE_POLL_INTERVAL) => {}
+                                _ = cancel.cancelled() => break 'dispatch,
+                                _ = sleep_until(deadline) => break 'dispatch,
+                            }
                         }
                     }
+                    None
+                };
+
+                // With every slot busy, the request that gets the next one is
+                // picked once it frees up, so priority entries can go first
+                let saturated = semaphore.available_permits() == 0;
+                let permit = tokio::select! {
+                    permit = semaphore.clone().acquire_owned() => permit.unwrap(),
+                    _ = cancel.cancelled() => break,
+                    _ = sleep_until(deadline) => break,
+                };
+                if deadline.is_some_and(|deadline| tokio::time::Instant::now() >= deadline) {
+                    break;
                 }
+                let Some(template) = queue.next(saturated) else { break };
+                index += 1;
+                let context = Arc::clone(&context);
+                let slot_freed = Arc::clone(&slot_freed);
+                let (request, label) = &templates[template];
+                let request = Arc::clone(request);
+                let label = label.clone();
 
-                pb.inc(1);
-                drop(permit);
-            });
+                // Latency is measured from when the request is issued (or was
+                // due, at a fixed rate), so time waiting for the task to be
+                // scheduled counts as dispatch delay
+                let issued = scheduled.unwrap_or_else(Instant::now);
+                let stage = profile
+                    .as_ref()
+                    .and_then(|p| p.stage_at(issued.saturating_duration_since(start.into_std())));
+                tokio::spawn(async move {
+                    if context.send(request, label, issued, stage).await.is_some() {
+                        drop(permit);
+                        slot_freed.notify_one();
+                    }
+                });
+            }
 
-            handles.push(handle);
+            // Wait for all requests to complete: each task holds a permit until
+            // it finishes, so all permits being free means all tasks are done
+            let _ = semaphore.acquire_many(limit as u32).await;
         }
 
-        // Wait for all requests to complete
-        for handle in handles {
-            let _ = handle.await;
-        }
+        cancel.cancel();
 
-        // Record end time
-        {
-            let mut c = collector.lock().await;
-            c.finish();
+        // Once the last sender is dropped the aggregator drains the
+        // remaining samples and hands back the collector
+        drop(context);
+        let mut collector = aggregator.await.map_err(|e| RurlError::PerfError(e.to_string()))?;
+        for (duration, success) in iterations {
+            collector.record_iteration(duration, success);
         }
+        collector.finish();
+        Ok(collector)
+    }
 
-        pb.finish_with_message("Done!");
+    /// Spawns the task that records samples and emits [`RunEvent::IntervalStats`].
+    ///
+    /// Runs until every sample sender is dropped, then returns the collector.
+    fn spawn_aggregator<F>(
+        &self,
+        mut collector: MetricsCollector,
+        mut samples: mpsc::UnboundedReceiver<Sample>,
+        completed: Arc<AtomicUsize>,
+        on_event: Arc<F>,
+    ) -> tokio::task::JoinHandle<MetricsCollector>
+    where
+        F: Fn(RunEvent) + Send + Sync + 'static,
+    {
+        let period = self.stats_interval;
+        tokio::spawn(async move {
+            let start = Instant::now();
+            let mut ticker = tokio::time::interval(period);
+            ticker.tick().await; // the first tick completes immediately
+            let mut last_completed = 0;
 
-        let metrics = collector.lock().await.compute_metrics();
-        Ok(metrics)
+            loop {
+                tokio::select! {
+                    sample = samples.recv() => match sample {
+                        Some(sample) => sample.record(&mut collector),
+                        None => return collector,
+                    },
+                    _ = ticker.tick() => {
+                        let done = completed.load(Ordering::Relaxed);
+                        let interval_requests = done - last_completed;
+                        last_completed = done;
+
+                        on_event(RunEvent::IntervalStats(IntervalStats {
+                            elapsed: start.elapsed(),
+                            completed: done,
+                            interval_requests,
+                            interval_requests_per_second: interval_requests as f64 / period.as_secs_f64(),
+                            metrics: collector.snapshot(),
+                        }));
+                    }
+                }
+            }
+        })
     }
 
-    fn build_request(&self, entry: &DatasetEntry) -> Result<HttpRequest> {
+    fn build_request(&self, entry: &DatasetEntry, hosts: &HashMap<String, IpAddr>) -> Result<HttpRequest> {
         let url = if let Some(path) = &entry.path {
             if path.starts_with("http://") || path.starts_with("https://") {
                 path.clone()
@@ -170,16 +1213,49 @@ impl PerfRunner {
             self.base_url.clone()
         };
 
+        // GraphQL entries default to POST
+        let method = if entry.graphql.is_some() && entry.method.eq_ignore_ascii_case("GET") {
+            "POST"
+        } else {
+            entry.method.as_str()
+        };
+
         let mut request = HttpRequest::new(url)
-            .method(&entry.method)?
+            .method(method)?
             .timeout(self.base_request.timeout)
-            .follow_redirects(self.base_request.follow_redirects);
+            .connect_timeout(self.base_request.connect_timeout)
+            .read_timeout(self.base_request.read_timeout)
+            .follow_redirects(self.base_request.follow_redirects)
+            .max_redirects(self.base_request.max_redirects)
+            .pool_idle_timeout(self.base_request.pool_idle_timeout)
+            .retry_on_connection_reset(self.base_request.retry_on_connection_reset)
+            .resolve(self.base_request.resolve.clone())
+            .strict_redirects(self.base_request.strict_redirects)
+            .http_version(entry.http_version.or(self.base_request.http_version))
+            .auth(self.base_request.auth.clone())
+            .client_identity(self.base_request.client_identity.clone());
+        request.compressed = self.base_request.compressed;
+        request.compress_body = self.base_request.compress_body;
+        request.dns = self.base_request.dns.clone();
+        request.socket = self.base_request.socket;
+        request.cache = self.base_request.cache.clone();
+        if let Some(timeout) = entry.timeout_ms {
+            request = request.timeout(Duration::from_millis(timeout));
+        }
 
         // Merge headers from base request
         for (key, value) in &self.base_request.headers {
             request = request.header(key, value);
         }
 
+        // Requested byte ranges; a random range replaces the first bytes
+        // of the object per send, so preflight and warmup stay small
+        match self.random_range {
+            Some(length) => request = request.header("Range", format!("bytes=0-{}", length - 1)),
+            None if !self.ranges.is_empty() => request = request.header("Range", range::header_value(&self.ranges)),
+            None => {}
+        }
+
         // Override with entry-specific headers
         if let Some(headers) = &entry.headers {
             for (key, value) in headers {
@@ -187,8 +1263,17 @@ impl PerfRunner {
             }
         }
 
+        // Dataset-level host overrides, unless --resolve already covers the target
+        if let Some(target) = dataset_host_override(&request, hosts) {
+            if !request.resolve.iter().any(|e| e.host == target.host && e.port == target.port) {
+                request.resolve.push(target);
+            }
+        }
+
         // Set body
-        if let Some(body) = entry.get_body_string() {
+        if let Some(graphql) = &entry.graphql {
+            request = request.graphql(graphql);
+        } else if let Some(body) = entry.body_for(request.header_value("content-type")) {
             request = request.body(body);
         } else if let Some(body) = &self.base_request.body {
             request = request.body(body.clone());
@@ -197,3 +1282,514 @@ impl PerfRunner {
         Ok(request)
     }
 }
+
+/// Sleeps until `deadline`, or forever if there is none.
+async fn sleep_until(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Returns the resolve override for the request's host from a dataset `hosts` map.
+fn dataset_host_override(request: &HttpRequest, hosts: &HashMap<String, IpAddr>) -> Option<ResolveEntry> {
+    if hosts.is_empty() {
+        return None;
+    }
+    let url = url::Url::parse(&request.url).ok()?;
+    let host = url.host_str()?.to_ascii_lowercase();
+    let addr = *hosts.get(&host)?;
+    Some(ResolveEntry {
+        host,
+        port: url.port_or_known_default()?,
+        addr,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Starts a server that answers every connection with an empty 200.
+    async fn spawn_ok_server() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let _ = socket.read(&mut buf).await;
+                    let _ = socket
+                        .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                        .await;
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    /// Starts a keep-alive server, returning its URL and a count of
+    /// accepted connections.
+    async fn spawn_keepalive_server() -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&accepted);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    while matches!(socket.read(&mut buf).await, Ok(n) if n > 0) {
+                        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+                        if socket.write_all(response).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (format!("http://{}", addr), accepted)
+    }
+
+    #[tokio::test]
+    async fn test_requests_share_connections() {
+        let (url, accepted) = spawn_keepalive_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 5, false).warmup_requests(2);
+        let metrics = runner.run(&Dataset::simple(5)).await.unwrap();
+        assert_eq!(metrics.successful_requests, 5);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        let (url, accepted) = spawn_keepalive_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 5, false).keepalive(false);
+        runner.run(&Dataset::simple(5)).await.unwrap();
+        assert_eq!(accepted.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn test_connection_phases() {
+        crate::http::timing::enable().unwrap();
+        let (url, accepted) = spawn_keepalive_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 4, false);
+        let phases = runner.run(&Dataset::simple(4)).await.unwrap().phases.unwrap();
+        // Only the request that opened the connection has connection phases
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+        assert_eq!(phases.dns.unwrap().requests, 1);
+        assert_eq!(phases.connect.unwrap().requests, 1);
+        assert!(phases.tls.is_none());
+        assert_eq!(phases.ttfb.requests, 4);
+    }
+
+    #[tokio::test]
+    async fn test_response_bodies_counted() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let _ = socket.read(&mut buf).await;
+                    let head = "HTTP/1.1 200 OK\r\nContent-Length: 100000\r\nConnection: close\r\n\r\n";
+                    let _ = socket.write_all(head.as_bytes()).await;
+                    let _ = socket.write_all(&[b'x'; 100_000]).await;
+                });
+            }
+        });
+
+        // Without anything inspecting them, bodies are only counted
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url.clone()), 2, 3, false);
+        let transfer = runner.run(&Dataset::simple(3)).await.unwrap().transfer.unwrap();
+        assert_eq!(transfer.bytes_received, 300_000);
+
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 2, 3, false).hash_responses(true);
+        let metrics = runner.run(&Dataset::simple(3)).await.unwrap();
+        assert_eq!(metrics.transfer.unwrap().bytes_received, 300_000);
+        assert_eq!(metrics.responses.unwrap().distinct, 1);
+    }
+
+    #[tokio::test]
+    async fn test_per_entry_http_version() {
+        // Answers in the version each request was sent with
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    let version = if request.contains("HTTP/1.0") { "1.0" } else { "1.1" };
+                    let response = format!("HTTP/{} 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", version);
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        let dataset = Dataset::from_json(r#"[{"http_version": "1.0"}, {"http_version": "1.1"}, {}]"#).unwrap();
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 3, false);
+        let metrics = runner.run(&dataset).await.unwrap();
+        assert_eq!(metrics.successful_requests, 3);
+        assert_eq!(metrics.http_versions, [("HTTP/1.0".to_string(), 1), ("HTTP/1.1".to_string(), 2)].into());
+    }
+
+    #[tokio::test]
+    async fn test_rate_spaces_request_starts() {
+        let url = spawn_ok_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 5, false)
+            .rate(Some(20.0));
+
+        let start = Instant::now();
+        let metrics = runner.run(&Dataset::simple(5)).await.unwrap();
+        // Five starts at 20/s span four 50 ms intervals
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert_eq!(metrics.total_requests, 5);
+    }
+
+    #[tokio::test]
+    async fn test_duration_run_stops_after_duration() {
+        let url = spawn_ok_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 2, 1, false)
+            .duration(Some(Duration::from_millis(300)));
+
+        let start = Instant::now();
+        let metrics = runner.run(&Dataset::simple(1)).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(metrics.total_requests > 1);
+        assert_eq!(metrics.failed_requests, 0);
+        assert_eq!(metrics.timeline.len(), 1);
+        assert_eq!(metrics.timeline[0].requests, metrics.total_requests);
+    }
+
+    #[tokio::test]
+    async fn test_hash_responses() {
+        let url = spawn_ok_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url.clone()), 2, 6, false);
+        assert!(runner.run(&Dataset::simple(6)).await.unwrap().responses.is_none());
+
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 2, 6, false).hash_responses(true);
+        let responses = runner.run(&Dataset::simple(6)).await.unwrap().responses.unwrap();
+        assert_eq!(responses.distinct, 1);
+        assert_eq!(responses.most_common, 6);
+    }
+
+    /// Starts a server that answers `Range: bytes=FIRST-LAST` requests for
+    /// a 1000-byte object.
+    async fn spawn_range_server() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    let head = String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase();
+                    let (first, last) = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("range: bytes="))
+                        .and_then(|range| range.trim().split_once('-'))
+                        .map(|(first, last)| (first.parse::<usize>().unwrap(), last.parse().map_or(999, |last: usize| last.min(999))))
+                        .unwrap();
+                    let response = format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/1000\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        first,
+                        last,
+                        last - first + 1,
+                        "x".repeat(last - first + 1)
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_range_reads() {
+        let url = spawn_range_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url.clone()), 2, 10, false).random_range(Some(100));
+        let metrics = runner.run(&Dataset::simple(10)).await.unwrap();
+        assert_eq!(metrics.successful_requests, 10);
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 2, 4, false).ranges(vec![ByteRange::From(990)]);
+        assert_eq!(runner.run(&Dataset::simple(4)).await.unwrap().successful_requests, 4);
+
+        // A server that ignores the range fails every request, and can't be
+        // probed for random ranges
+        let url = spawn_ok_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url.clone()), 2, 4, false).ranges(vec![ByteRange::Span(0, 9)]);
+        let metrics = runner.run(&Dataset::simple(4)).await.unwrap();
+        assert_eq!(metrics.failed_requests, 4);
+        assert_eq!(metrics.error_categories, [("range".to_string(), 4)].into());
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 2, 4, false).random_range(Some(100));
+        assert!(runner.run(&Dataset::simple(4)).await.unwrap_err().to_string().contains("doesn't serve byte ranges"));
+    }
+
+    #[tokio::test]
+    async fn test_slo_windows() {
+        let url = spawn_ok_server().await;
+        let slo = Slo::new("p99 < 10s".parse().unwrap(), Duration::from_millis(100)).unwrap();
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 2, 1, false)
+            .duration(Some(Duration::from_millis(350)))
+            .slo(Some(slo.target(Some(99.0))));
+
+        let metrics = runner.run(&Dataset::simple(1)).await.unwrap();
+        let slo = metrics.slo.unwrap();
+        assert!(slo.windows >= 3);
+        assert_eq!(slo.compliant_windows, slo.windows);
+        assert_eq!(slo.met, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_warmup_requests_are_not_measured() {
+        let url = spawn_ok_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 2, 3, false)
+            .warmup_requests(4);
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = Arc::clone(&events);
+        let metrics = runner
+            .run_with_events(&Dataset::simple(3), move |event| {
+                let name = match event {
+                    RunEvent::WarmupFinished => "warmup",
+                    RunEvent::RequestDone { .. } => "request",
+                    _ => return,
+                };
+                seen.lock().unwrap().push(name);
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(metrics.total_requests, 3);
+        assert_eq!(*events.lock().unwrap(), vec!["warmup", "request", "request", "request"]);
+    }
+
+    #[test]
+    fn test_dispatch_queue_prefers_priority_when_saturated() {
+        let order = |saturated: bool, max_deferred: usize| {
+            let upcoming: Box<dyn Iterator<Item = usize> + Send> = Box::new((0..2).cycle().take(6));
+            let mut queue = DispatchQueue::new(upcoming, vec![false, true], max_deferred);
+            let mut order = Vec::new();
+            while !queue.is_empty() {
+                order.extend(queue.next(saturated));
+            }
+            order
+        };
+        assert_eq!(order(false, 10), [0, 1, 0, 1, 0, 1]);
+        assert_eq!(order(true, 10), [1, 1, 1, 0, 0, 0]);
+        assert_eq!(order(true, 1), [1, 0, 1, 0, 1, 0]);
+        assert_eq!(order(true, 0), [0, 1, 0, 1, 0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_priority_traffic_is_reported_apart() {
+        let url = spawn_ok_server().await;
+        let dataset = Dataset::from_json(r#"[{"path": "/feed", "tag": "feed"}, {"path": "/buy", "tag": "checkout"}]"#).unwrap();
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 2, 10, false)
+            .priority(vec!["checkout".to_string()]);
+
+        let metrics = runner.run(&dataset).await.unwrap();
+        assert_eq!(metrics.total_requests, 10);
+        let classes = &metrics.traffic_classes;
+        assert_eq!(classes.len(), 2);
+        assert_eq!((classes[0].class.as_str(), classes[0].metrics.total_requests), ("priority", 5));
+        assert_eq!((classes[1].class.as_str(), classes[1].metrics.total_requests), ("background", 5));
+    }
+
+    #[tokio::test]
+    async fn test_virtual_user_iterations() {
+        let url = spawn_ok_server().await;
+        let dataset = Dataset::from_json(r#"[{"path": "/login"}, {"path": "/cart", "tag": "cart"}]"#).unwrap();
+        // The request count is ignored in favour of 3 users x 4 iterations x 2 entries
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 3, 1000, false)
+            .iterations(Some(4));
+
+        let metrics = runner.run(&dataset).await.unwrap();
+        assert_eq!(metrics.total_requests, 24);
+        assert_eq!(metrics.endpoints["cart"].total_requests, 12);
+        let iterations = metrics.iterations.unwrap();
+        assert_eq!(iterations.iterations, 12);
+        assert_eq!(iterations.failed_iterations, 0);
+        assert!(iterations.duration.p50_ms > 0.0);
+
+        let plan = runner.plan(&dataset).unwrap();
+        assert_eq!(plan.total_requests, Some(24));
+        assert_eq!(plan.schedule[0].label, "3 VUs x 4 iterations");
+    }
+
+    #[tokio::test]
+    async fn test_stages_end_run_and_split_report() {
+        let url = spawn_ok_server().await;
+        let stages: LoadProfile = "200ms:4,200ms:4".parse().unwrap();
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 1, false)
+            .stages(Some(stages));
+
+        let start = Instant::now();
+        let metrics = runner.run(&Dataset::simple(1)).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(400));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(metrics.stages.len(), 2);
+        assert_eq!(
+            metrics.stages.iter().map(|s| s.metrics.total_requests).sum::<usize>(),
+            metrics.total_requests
+        );
+        assert!(metrics.stages[1].metrics.total_requests > 0);
+    }
+
+    #[tokio::test]
+    async fn test_assertions_are_counted() {
+        let url = spawn_ok_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 3, false)
+            .assertions(vec!["status == 200".parse().unwrap(), "status == 201".parse().unwrap()]);
+
+        let metrics = runner.run(&Dataset::simple(3)).await.unwrap();
+        assert_eq!(metrics.failed_requests, 0);
+        assert_eq!((metrics.validation[0].passed, metrics.validation[0].failed), (3, 0));
+        assert_eq!((metrics.validation[1].passed, metrics.validation[1].failed), (0, 3));
+        assert_eq!(metrics.validation[1].examples[0], "GET /: status was 200");
+    }
+
+    #[tokio::test]
+    async fn test_captures_are_reported() {
+        let url = spawn_ok_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 2, false)
+            .captures(vec!["conn=header:Connection".parse().unwrap(), "id=$.id".parse().unwrap()]);
+
+        let captured = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = Arc::clone(&captured);
+        runner
+            .run_with_events(&Dataset::simple(2), move |event| {
+                if let RunEvent::RequestDone { captures, .. } = event {
+                    seen.lock().unwrap().push(captures);
+                }
+            })
+            .await
+            .unwrap();
+        let expected = vec![("conn".to_string(), "close".to_string())];
+        assert_eq!(*captured.lock().unwrap(), vec![expected.clone(), expected]);
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_categorized() {
+        // Bind and drop a listener so nothing is accepting on the port
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let url = format!("http://{}", addr);
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 2, false);
+
+        let metrics = runner.run(&Dataset::simple(2)).await.unwrap();
+        assert_eq!(metrics.failed_requests, 2);
+        assert_eq!(metrics.error_categories, [("connect".to_string(), 2)].into());
+    }
+
+    #[tokio::test]
+    async fn test_preflight() {
+        let url = spawn_ok_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 1, false);
+        runner.preflight(&Dataset::simple(1)).await.unwrap();
+
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let url = format!("http://{}", addr);
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 1, false);
+        let error = runner.preflight(&Dataset::simple(1)).await.unwrap_err();
+        assert!(matches!(&error, RurlError::PreflightFailed(reason) if reason.starts_with("could not connect to 127.0.0.1")));
+
+        // Unsafe entries are never sent as the canary
+        let unsafe_only = Dataset::from_json(r#"[{"method": "POST"}, {"method": "DELETE"}]"#).unwrap();
+        runner.preflight(&unsafe_only).await.unwrap();
+        let mixed = Dataset::from_json(r#"[{"method": "POST"}, {"method": "GET", "path": "/health"}]"#).unwrap();
+        let error = runner.preflight(&mixed).await.unwrap_err();
+        assert!(matches!(&error, RurlError::PreflightFailed(reason) if reason.starts_with("could not connect")));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_entry_fails_before_sending() {
+        let url = spawn_ok_server().await;
+        let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 1, 2, false);
+        let dataset = Dataset::from_json(r#"[{"path": "/"}, {"path": "/", "headers": {"Bad Name": "x"}}]"#).unwrap();
+
+        let sent = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&sent);
+        let result = runner
+            .run_with_events(&dataset, move |event| {
+                if let RunEvent::RequestDone { .. } = event {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .await;
+        assert!(matches!(result, Err(RurlError::InvalidHeader(_))));
+        assert_eq!(sent.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_plan() {
+        let dataset = Dataset::from_json(r#"[{"path": "/a"}, {"method": "POST", "path": "/b", "tag": "create"}]"#).unwrap();
+        let request = HttpRequest::new("http://api.test");
+
+        let plan = PerfRunner::new("http://api.test".to_string(), request.clone(), 4, 5, false)
+            .rate(Some(10.0))
+            .plan(&dataset)
+            .unwrap();
+        assert_eq!(plan.total_requests, Some(5));
+        assert_eq!(plan.estimated_duration_seconds, Some(0.5));
+        assert_eq!(plan.entries[0].url, "http://api.test/a");
+        assert_eq!(plan.entries.iter().map(|e| e.requests).collect::<Vec<_>>(), [Some(3), Some(2)]);
+        assert_eq!(plan.entries[1].label, "create");
+        assert_eq!(plan.schedule.len(), 1);
+        assert_eq!(plan.schedule[0].unit, "rate");
+
+        // A closed model over a fixed time can't predict its request count
+        let plan = PerfRunner::new("http://api.test".to_string(), request.clone(), 4, 5, false)
+            .duration(Some(Duration::from_secs(60)))
+            .plan(&dataset)
+            .unwrap();
+        assert_eq!(plan.total_requests, None);
+        assert_eq!(plan.entries[0].requests, None);
+        assert_eq!(plan.estimated_duration_seconds, Some(60.0));
+
+        let plan = PerfRunner::new("http://api.test".to_string(), request, 4, 5, false)
+            .stages(Some("10s:20/s,10s:20/s".parse().unwrap()))
+            .plan(&dataset)
+            .unwrap();
+        assert_eq!(plan.total_requests, Some(300));
+        assert_eq!(plan.schedule.len(), 2);
+        assert_eq!((plan.schedule[1].from, plan.schedule[1].to), (20.0, 20.0));
+        assert_eq!(plan.schedule[1].start_seconds, 10.0);
+    }
+
+    #[test]
+    fn test_dataset_hosts_become_resolve_entries() {
+        let hosts: HashMap<String, IpAddr> = [("api.internal".to_string(), "10.1.2.3".parse().unwrap())].into();
+        let base = HttpRequest::new("https://api.internal")
+            .resolve(vec!["api.internal:8443:127.0.0.1".parse().unwrap()]);
+        let runner = PerfRunner::new(base.url.clone(), base, 1, 1, false);
+
+        let dataset = Dataset::from_json(
+            r#"[{"path": "/users"}, {"path": "http://API.internal:8443/x"}, {"path": "https://other.host/"}]"#,
+        )
+        .unwrap();
+
+        let request = runner.build_request(&dataset.entries[0], &hosts).unwrap();
+        assert_eq!(request.resolve.last().unwrap().to_string(), "api.internal:443:10.1.2.3");
+
+        // --resolve for the same target wins
+        let request = runner.build_request(&dataset.entries[1], &hosts).unwrap();
+        assert_eq!(request.resolve.len(), 1);
+        assert_eq!(request.resolve[0].addr, "127.0.0.1".parse::<IpAddr>().unwrap());
+
+        let request = runner.build_request(&dataset.entries[2], &hosts).unwrap();
+        assert_eq!(request.resolve.len(), 1);
+    }
+
+    #[test]
+    fn test_requests_carry_client_identity() {
+        let identity = Arc::new(crate::http::tls::tests::test_identity());
+        let base = HttpRequest::new("https://api.internal").client_identity(Some(Arc::clone(&identity)));
+        let runner = PerfRunner::new(base.url.clone(), base, 1, 1, false);
+
+        let dataset = Dataset::from_json(r#"[{"path": "/users"}, {"method": "POST", "path": "/users", "body": {}}]"#).unwrap();
+        for entry in &dataset.entries {
+            let request = runner.build_request(entry, &HashMap::new()).unwrap();
+            assert!(request.client_identity.as_ref().is_some_and(|sent| Arc::ptr_eq(sent, &identity)));
+        }
+    }
+}
@@ -0,0 +1,720 @@
+//! Report sinks for performance test results.
+//!
+//! A [`ReportSink`] writes the results of a finished run in one format.
+//! Sinks are created from `--output FORMAT[=PATH]` targets, so several can
+//! be used in one run, e.g. text on stdout and JSON to a file:
+//!
+//! ```text
+//! --output text --output json=report.json --output junit=results.xml
+//! ```
+//!
+//! JSON reports are serialized straight into their writer. Per-request
+//! records, when enabled, are streamed from an NDJSON spool file one line at
+//! a time, so a report of millions of requests is never held in memory.
+
+use serde::ser::{Error as _, SerializeSeq};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::{Result, RurlError};
+use super::metrics::{LatencyBucket, PerfMetrics, TimelinePoint};
+use super::thresholds::ThresholdResult;
+
+/// Results of a finished performance run.
+#[derive(Debug, Clone, Copy)]
+pub struct RunReport<'a> {
+    /// Final metrics
+    pub metrics: &'a PerfMetrics,
+    /// Threshold results, empty when no thresholds were given
+    pub thresholds: &'a [ThresholdResult],
+    /// NDJSON request log to embed as `records` in JSON reports
+    pub records: Option<&'a Path>,
+}
+
+/// Destination for a run's report in one format.
+pub trait ReportSink {
+    /// Writes the report.
+    fn write(&mut self, report: &RunReport<'_>) -> Result<()>;
+}
+
+/// Report format selectable with `--output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Colored human-readable text
+    Text,
+    /// Metrics as pretty-printed JSON
+    Json,
+    /// Standalone HTML page
+    Html,
+    /// JUnit XML, one test case per threshold
+    Junit,
+}
+
+impl ReportFormat {
+    /// Returns the name used on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportFormat::Text => "text",
+            ReportFormat::Json => "json",
+            ReportFormat::Html => "html",
+            ReportFormat::Junit => "junit",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "html" => Ok(ReportFormat::Html),
+            "junit" => Ok(ReportFormat::Junit),
+            other => Err(RurlError::PerfError(format!(
+                "unknown output format '{}' (use text, json, html, or junit)",
+                other
+            ))),
+        }
+    }
+}
+
+/// A report format and where to write it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputTarget {
+    /// Report format
+    pub format: ReportFormat,
+    /// File to write, or `None` for stdout
+    pub path: Option<PathBuf>,
+}
+
+impl OutputTarget {
+    /// Creates the sink for this target, creating or truncating its file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created.
+    pub fn open(&self) -> Result<Box<dyn ReportSink>> {
+        let out: Box<dyn Write> = match &self.path {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(std::io::stdout()),
+        };
+        Ok(match self.format {
+            #[cfg(feature = "cli")]
+            ReportFormat::Text => Box::new(super::report::TextSink::new(out, self.path.is_none())),
+            #[cfg(not(feature = "cli"))]
+            ReportFormat::Text => {
+                return Err(RurlError::PerfError(
+                    "text reports require the `cli` feature".to_string(),
+                ))
+            }
+            ReportFormat::Json => Box::new(JsonSink::new(out)),
+            ReportFormat::Html => Box::new(HtmlSink::new(out)),
+            ReportFormat::Junit => Box::new(JunitSink::new(out)),
+        })
+    }
+}
+
+impl FromStr for OutputTarget {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (format, path) = match s.split_once('=') {
+            Some((format, path)) if !path.trim().is_empty() => (format, Some(PathBuf::from(path.trim()))),
+            Some(_) => return Err(RurlError::PerfError(format!("missing file name in '{}'", s))),
+            None => (s, None),
+        };
+        Ok(Self {
+            format: format.parse()?,
+            path,
+        })
+    }
+}
+
+impl fmt::Display for OutputTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}={}", self.format.as_str(), path.display()),
+            None => write!(f, "{}", self.format.as_str()),
+        }
+    }
+}
+
+/// JSON report body: the metrics, followed by the per-request records.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(flatten)]
+    metrics: &'a PerfMetrics,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    records: Option<Records<'a>>,
+}
+
+/// Per-request records read from an NDJSON log while serializing.
+struct Records<'a>(&'a Path);
+
+impl Serialize for Records<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let file = File::open(self.0).map_err(S::Error::custom)?;
+        let mut seq = serializer.serialize_seq(None)?;
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(S::Error::custom)?;
+            if line.is_empty() {
+                continue;
+            }
+            let record: serde_json::Value = serde_json::from_str(&line).map_err(S::Error::custom)?;
+            seq.serialize_element(&record)?;
+        }
+        seq.end()
+    }
+}
+
+/// Writes the metrics, and any per-request records, as pretty-printed JSON.
+pub struct JsonSink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonSink<W> {
+    /// Creates a sink writing to `out`.
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> ReportSink for JsonSink<W> {
+    fn write(&mut self, report: &RunReport<'_>) -> Result<()> {
+        let body = JsonReport {
+            metrics: report.metrics,
+            records: report.records.map(Records),
+        };
+        serde_json::to_writer_pretty(&mut self.out, &body)?;
+        writeln!(self.out)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Width of the SVG charts in the HTML report.
+const CHART_WIDTH: f64 = 720.0;
+
+/// Height of the SVG charts in the HTML report.
+const CHART_HEIGHT: f64 = 220.0;
+
+/// Space left of and below a chart's plot area, for axis labels.
+const CHART_MARGIN: f64 = 48.0;
+
+/// Writes a standalone HTML page with the metrics tables and inline SVG
+/// charts, so the file can be shared without any other assets.
+pub struct HtmlSink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> HtmlSink<W> {
+    /// Creates a sink writing to `out`.
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+/// Renders a titled table with one row of metrics per label.
+fn html_metrics_table(title: &str, rows: &[(&str, &PerfMetrics)]) -> String {
+    let mut html = format!(
+        "<h2>{}</h2>\n<table>\n<tr><th></th><th>Requests</th><th>Errors</th><th>RPS</th>\
+         <th>Min</th><th>Avg</th><th>p50</th><th>p95</th><th>p99</th><th>Max</th></tr>\n",
+        escape_xml(title)
+    );
+    for (label, m) in rows {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{:.2}%</td><td>{:.2}</td><td>{:.2}</td><td>{:.2}</td>\
+             <td>{:.2}</td><td>{:.2}</td><td>{:.2}</td><td>{:.2}</td></tr>\n",
+            escape_xml(label),
+            m.total_requests,
+            m.error_rate_percent,
+            m.requests_per_second,
+            m.latency_min_ms,
+            m.latency_avg_ms,
+            m.latency_p50_ms,
+            m.latency_p95_ms,
+            m.latency_p99_ms,
+            m.latency_max_ms,
+        ));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// Opens an SVG chart with its axes, labelled with the y maximum and x range.
+fn svg_chart_start(y_max: &str, x_start: &str, x_end: &str) -> String {
+    let (left, bottom, right) = (CHART_MARGIN, CHART_HEIGHT - CHART_MARGIN / 2.0, CHART_WIDTH - 10.0);
+    format!(
+        "<svg width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-size=\"11\">\n\
+         <polyline fill=\"none\" stroke=\"#888\" points=\"{l},10 {l},{b} {r},{b}\"/>\n\
+         <text x=\"{lt}\" y=\"14\" text-anchor=\"end\">{y_max}</text>\n\
+         <text x=\"{lt}\" y=\"{b}\" text-anchor=\"end\">0</text>\n\
+         <text x=\"{l}\" y=\"{bt}\">{x_start}</text>\n\
+         <text x=\"{r}\" y=\"{bt}\" text-anchor=\"end\">{x_end}</text>\n",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT,
+        l = left,
+        b = bottom,
+        r = right,
+        lt = left - 4.0,
+        bt = bottom + 14.0,
+        y_max = escape_xml(y_max),
+        x_start = escape_xml(x_start),
+        x_end = escape_xml(x_end),
+    )
+}
+
+/// Renders series over the run's timeline as an SVG line chart.
+///
+/// Each series is `(name, color, values)`, one value per timeline point.
+fn svg_line_chart(points: &[&TimelinePoint], unit: &str, series: &[(&str, &str, Vec<f64>)]) -> String {
+    let end_ms = points.last().map_or(1.0, |p| p.end_ms).max(1.0);
+    let y_max = series
+        .iter()
+        .flat_map(|(_, _, values)| values.iter().copied())
+        .fold(0.0, f64::max)
+        .max(f64::EPSILON);
+    let plot_width = CHART_WIDTH - 10.0 - CHART_MARGIN;
+    let plot_height = CHART_HEIGHT - CHART_MARGIN / 2.0 - 10.0;
+
+    let mut svg = svg_chart_start(
+        &format!("{:.1}{}", y_max, unit),
+        "0s",
+        &format!("{:.1}s", end_ms / 1000.0),
+    );
+    for (index, (name, color, values)) in series.iter().enumerate() {
+        let coords: Vec<String> = points
+            .iter()
+            .zip(values)
+            .map(|(point, value)| {
+                let x = CHART_MARGIN + point.end_ms / end_ms * plot_width;
+                let y = 10.0 + plot_height * (1.0 - value / y_max);
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect();
+        svg.push_str(&format!(
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>\n\
+             <text x=\"{}\" y=\"14\" fill=\"{}\">{}</text>\n",
+            color,
+            coords.join(" "),
+            CHART_MARGIN + 10.0 + index as f64 * 60.0,
+            color,
+            escape_xml(name),
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Renders the latency histogram as an SVG bar chart.
+fn svg_histogram(buckets: &[LatencyBucket]) -> String {
+    let (Some(first), Some(last)) = (buckets.first(), buckets.last()) else {
+        return String::new();
+    };
+    let peak = buckets.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    let plot_width = CHART_WIDTH - 10.0 - CHART_MARGIN;
+    let plot_height = CHART_HEIGHT - CHART_MARGIN / 2.0 - 10.0;
+    let bar_width = plot_width / buckets.len() as f64;
+
+    let mut svg = svg_chart_start(
+        &peak.to_string(),
+        &format!("{:.2} ms", first.from_ms),
+        &format!("{:.2} ms", last.to_ms),
+    );
+    for (index, bucket) in buckets.iter().enumerate() {
+        let height = plot_height * bucket.count as f64 / peak as f64;
+        svg.push_str(&format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"#36c\">\
+             <title>{:.2} - {:.2} ms: {}</title></rect>\n",
+            CHART_MARGIN + index as f64 * bar_width + 1.0,
+            10.0 + plot_height - height,
+            (bar_width - 2.0).max(1.0),
+            height,
+            bucket.from_ms,
+            bucket.to_ms,
+            bucket.count,
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+impl<W: Write> ReportSink for HtmlSink<W> {
+    fn write(&mut self, report: &RunReport<'_>) -> Result<()> {
+        let m = report.metrics;
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>hurley performance report</title>\n\
+             <style>\nbody { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; margin-bottom: 1.5em; }\n\
+             th, td { border: 1px solid #ccc; padding: 4px 10px; text-align: right; }\n\
+             td:first-child { text-align: left; }\n.pass { color: #080; }\n.fail { color: #c00; }\n\
+             </style>\n</head>\n<body>\n<h1>Performance Results</h1>\n",
+        );
+        html.push_str(&format!(
+            "<p>{} requests ({} failed) in {:.2} ms, latency in ms.</p>\n",
+            m.total_requests, m.failed_requests, m.total_duration_ms
+        ));
+        html.push_str(&html_metrics_table("Summary", &[("All requests", m)]));
+
+        let points = m.charted_timeline();
+        if points.len() >= 2 {
+            let series = |value: fn(&TimelinePoint) -> f64| points.iter().map(|p| value(p)).collect::<Vec<_>>();
+            html.push_str("<h2>Latency Over Time</h2>\n");
+            html.push_str(&svg_line_chart(&points, " ms", &[
+                ("p50", "#2a7", series(|p| p.latency_p50_ms)),
+                ("p95", "#e90", series(|p| p.latency_p95_ms)),
+                ("p99", "#c33", series(|p| p.latency_p99_ms)),
+            ]));
+            html.push_str("<h2>Throughput Over Time</h2>\n");
+            html.push_str(&svg_line_chart(&points, " req/s", &[("RPS", "#36c", series(|p| p.requests_per_second))]));
+        }
+
+        if m.histogram.iter().any(|b| b.count > 0) {
+            html.push_str("<h2>Latency Histogram</h2>\n");
+            html.push_str(&svg_histogram(&m.histogram));
+        }
+
+        let mut endpoints: Vec<_> = m.endpoints.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        endpoints.sort_by_key(|(k, _)| *k);
+        if !endpoints.is_empty() {
+            html.push_str(&html_metrics_table("Endpoints", &endpoints));
+        }
+
+        let mut classes: Vec<_> = m.status_classes.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        classes.sort_by_key(|(k, _)| *k);
+        if !classes.is_empty() {
+            html.push_str(&html_metrics_table("Status Classes", &classes));
+        }
+
+        if !m.status_codes.is_empty() {
+            html.push_str("<h2>Status Codes</h2>\n<table>\n<tr><th>Status</th><th>Requests</th></tr>\n");
+            for (status, count) in &m.status_codes {
+                html.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", status, count));
+            }
+            html.push_str("</table>\n");
+        }
+
+        if !m.validation.is_empty() {
+            html.push_str("<h2>Validation</h2>\n<table>\n<tr><th>Assertion</th><th>Passed</th><th>Failed</th><th>Examples</th></tr>\n");
+            for assertion in &m.validation {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td class=\"{}\">{}</td><td>{}</td></tr>\n",
+                    escape_xml(&assertion.assertion),
+                    assertion.passed,
+                    if assertion.failed == 0 { "pass" } else { "fail" },
+                    assertion.failed,
+                    assertion.examples.iter().map(|e| escape_xml(e)).collect::<Vec<_>>().join("<br>"),
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        if !m.error_categories.is_empty() {
+            html.push_str("<h2>Failures by Cause</h2>\n<table>\n<tr><th>Cause</th><th>Requests</th></tr>\n");
+            for (category, count) in &m.error_categories {
+                html.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", escape_xml(category), count));
+            }
+            html.push_str("</table>\n");
+        }
+
+        let stages: Vec<_> = m.stages.iter().map(|s| (s.stage.as_str(), &s.metrics)).collect();
+        if !stages.is_empty() {
+            html.push_str(&html_metrics_table("Stages", &stages));
+        }
+
+        if let Some(slo) = &m.slo {
+            html.push_str(&format!(
+                "<h2>SLO Compliance</h2>\n<p>{} per {} window: {} of {} windows met ({:.2}%)",
+                escape_xml(&slo.objective),
+                escape_xml(&slo.window),
+                slo.compliant_windows,
+                slo.windows,
+                slo.compliance_percent
+            ));
+            if let (Some(target), Some(met), Some(rate)) = (slo.target_percent, slo.met, slo.burn_rate) {
+                html.push_str(&format!(
+                    ", target {:.2}% <span class=\"{}\">{}</span>, burn rate {:.2}x",
+                    target,
+                    if met { "pass" } else { "fail" },
+                    if met { "PASS" } else { "FAIL" },
+                    rate
+                ));
+            }
+            html.push_str("</p>\n");
+            if !slo.violations.is_empty() {
+                html.push_str("<table>\n<tr><th>Missed window</th><th>Value</th></tr>\n");
+                for window in &slo.violations {
+                    html.push_str(&format!(
+                        "<tr><td>{:.0}s - {:.0}s</td><td class=\"fail\">{:.2}{}</td></tr>\n",
+                        window.start_ms / 1000.0,
+                        window.end_ms / 1000.0,
+                        window.value,
+                        slo.unit
+                    ));
+                }
+                html.push_str("</table>\n");
+            }
+        }
+
+        if !report.thresholds.is_empty() {
+            html.push_str("<h2>Thresholds</h2>\n<table>\n<tr><th>Threshold</th><th>Actual</th><th>Result</th></tr>\n");
+            for result in report.thresholds {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td class=\"{}\">{}</td></tr>\n",
+                    escape_xml(&result.threshold.to_string()),
+                    escape_xml(&format_actual(result)),
+                    if result.passed { "pass" } else { "fail" },
+                    if result.passed { "PASS" } else { "FAIL" },
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        self.out.write_all(html.as_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes JUnit XML so CI systems can show the run as test results.
+///
+/// Each threshold is a test case; without thresholds, a single test case
+/// fails if any request failed.
+pub struct JunitSink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JunitSink<W> {
+    /// Creates a sink writing to `out`.
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> ReportSink for JunitSink<W> {
+    fn write(&mut self, report: &RunReport<'_>) -> Result<()> {
+        let m = report.metrics;
+        let cases: Vec<(String, Option<String>)> = if report.thresholds.is_empty() {
+            let failure = (m.failed_requests > 0)
+                .then(|| format!("{} of {} requests failed", m.failed_requests, m.total_requests));
+            vec![("requests succeed".to_string(), failure)]
+        } else {
+            report
+                .thresholds
+                .iter()
+                .map(|r| (r.threshold.to_string(), (!r.passed).then(|| format!("actual {}", format_actual(r)))))
+                .collect()
+        };
+        let failures = cases.iter().filter(|(_, failure)| failure.is_some()).count();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+        xml.push_str(&format!(
+            "  <testsuite name=\"hurley\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+            cases.len(),
+            failures,
+            m.total_duration_ms / 1000.0
+        ));
+        xml.push_str("    <properties>\n");
+        for (name, value) in [
+            ("total_requests", m.total_requests.to_string()),
+            ("failed_requests", m.failed_requests.to_string()),
+            ("requests_per_second", format!("{:.2}", m.requests_per_second)),
+            ("latency_p50_ms", format!("{:.2}", m.latency_p50_ms)),
+            ("latency_p95_ms", format!("{:.2}", m.latency_p95_ms)),
+            ("latency_p99_ms", format!("{:.2}", m.latency_p99_ms)),
+        ] {
+            xml.push_str(&format!("      <property name=\"{}\" value=\"{}\"/>\n", name, value));
+        }
+        xml.push_str("    </properties>\n");
+        for (name, failure) in &cases {
+            match failure {
+                Some(message) => xml.push_str(&format!(
+                    "    <testcase classname=\"hurley.performance\" name=\"{}\">\n      \
+                     <failure message=\"{}\"/>\n    </testcase>\n",
+                    escape_xml(name),
+                    escape_xml(message)
+                )),
+                None => xml.push_str(&format!(
+                    "    <testcase classname=\"hurley.performance\" name=\"{}\"/>\n",
+                    escape_xml(name)
+                )),
+            }
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+
+        self.out.write_all(xml.as_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Formats a threshold's measured value with its unit.
+fn format_actual(result: &ThresholdResult) -> String {
+    match result.actual {
+        Some(value) => format!("{:.2}{}", value, result.threshold.metric.unit()),
+        None => "no requests".to_string(),
+    }
+}
+
+/// Escapes text for use in XML and HTML content and attributes.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perf::metrics::MetricsCollector;
+    use crate::perf::thresholds;
+    use crate::perf::{Slo, Threshold};
+    use std::time::Duration;
+
+    fn sample_metrics() -> PerfMetrics {
+        let mut collector = MetricsCollector::new();
+        collector.start();
+        collector.record_success(Duration::from_millis(20), Some("GET /users"));
+        collector.record_failure(Duration::from_millis(900), Some("GET /users"));
+        collector.finish();
+        collector.compute_metrics()
+    }
+
+    fn render(sink: fn(&mut Vec<u8>, &RunReport<'_>) -> Result<()>, report: &RunReport<'_>) -> String {
+        let mut out = Vec::new();
+        sink(&mut out, report).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            "json=out/report.json".parse::<OutputTarget>().unwrap(),
+            OutputTarget { format: ReportFormat::Json, path: Some(PathBuf::from("out/report.json")) }
+        );
+        assert_eq!("TEXT".parse::<OutputTarget>().unwrap().path, None);
+        assert_eq!("junit=r.xml".parse::<OutputTarget>().unwrap().to_string(), "junit=r.xml");
+        assert!("csv".parse::<OutputTarget>().is_err());
+        assert!("json=".parse::<OutputTarget>().is_err());
+    }
+
+    #[test]
+    fn test_json_sink() {
+        let metrics = sample_metrics();
+        let report = RunReport { metrics: &metrics, thresholds: &[], records: None };
+        let json = render(|out, r| JsonSink::new(out).write(r), &report);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["total_requests"], 2);
+        assert!(value.get("records").is_none());
+    }
+
+    #[test]
+    fn test_json_sink_records() {
+        let path = std::env::temp_dir().join("hurley_test_records.ndjson");
+        std::fs::write(&path, "{\"seq\":1,\"status\":200}\n{\"seq\":2,\"status\":500}\n").unwrap();
+        let metrics = sample_metrics();
+        let report = RunReport { metrics: &metrics, thresholds: &[], records: Some(&path) };
+        let json = render(|out, r| JsonSink::new(out).write(r), &report);
+        std::fs::remove_file(&path).ok();
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["total_requests"], 2);
+        assert_eq!(value["records"][1]["status"], 500);
+        assert_eq!(value["records"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_junit_sink() {
+        let metrics = sample_metrics();
+        let report = RunReport { metrics: &metrics, thresholds: &[], records: None };
+        let xml = render(|out, r| JunitSink::new(out).write(r), &report);
+        assert!(xml.contains("tests=\"1\" failures=\"1\""));
+        assert!(xml.contains("<failure message=\"1 of 2 requests failed\"/>"));
+
+        let limits: Vec<Threshold> = vec!["p99 < 2s".parse().unwrap(), "error_rate < 10%".parse().unwrap()];
+        let results = thresholds::evaluate(&limits, &metrics);
+        let report = RunReport { metrics: &metrics, thresholds: &results, records: None };
+        let xml = render(|out, r| JunitSink::new(out).write(r), &report);
+        assert!(xml.contains("tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("name=\"p99 &lt; 2000ms\"/>"));
+        assert!(xml.contains("<failure message=\"actual 50.00%\"/>"));
+    }
+
+    #[test]
+    fn test_html_sink() {
+        let metrics = sample_metrics();
+        let report = RunReport { metrics: &metrics, thresholds: &[], records: None };
+        let html = render(|out, r| HtmlSink::new(out).write(r), &report);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h2>Endpoints</h2>"));
+        assert!(html.contains("<td>GET /users</td>"));
+        assert!(!html.contains("Thresholds"));
+        assert!(html.contains("<h2>Latency Histogram</h2>\n<svg"));
+        assert!(!html.contains("Over Time"));
+    }
+
+    #[test]
+    fn test_html_charts() {
+        let mut metrics = sample_metrics();
+        metrics.timeline = (0..3)
+            .map(|i| TimelinePoint {
+                start_ms: i as f64 * 1000.0,
+                end_ms: (i + 1) as f64 * 1000.0,
+                requests: 10,
+                failed_requests: 0,
+                requests_per_second: 10.0 * (i + 1) as f64,
+                latency_p50_ms: 5.0,
+                latency_p95_ms: 8.0,
+                latency_p99_ms: 20.0,
+            })
+            .collect();
+        let report = RunReport { metrics: &metrics, thresholds: &[], records: None };
+        let html = render(|out, r| HtmlSink::new(out).write(r), &report);
+        assert!(html.contains("<h2>Latency Over Time</h2>\n<svg"));
+        assert!(html.contains(">20.0 ms</text>"));
+        assert!(html.contains(">30.0 req/s</text>"));
+        assert!(html.contains(">3.0s</text>"));
+        assert_eq!(html.matches("<svg").count(), 3);
+    }
+
+    #[test]
+    fn test_html_slo() {
+        let mut metrics = sample_metrics();
+        let windows: Vec<_> = [100.0, 350.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &p99)| TimelinePoint {
+                start_ms: i as f64 * 60_000.0,
+                end_ms: (i + 1) as f64 * 60_000.0,
+                requests: 10,
+                failed_requests: 0,
+                requests_per_second: 1.0,
+                latency_p50_ms: 5.0,
+                latency_p95_ms: 8.0,
+                latency_p99_ms: p99,
+            })
+            .collect();
+        let slo = Slo::new("p99 < 300ms".parse().unwrap(), Duration::from_secs(60)).unwrap();
+        metrics.slo = Some(slo.target(Some(90.0)).evaluate(&windows));
+        let report = RunReport { metrics: &metrics, thresholds: &[], records: None };
+        let html = render(|out, r| HtmlSink::new(out).write(r), &report);
+        assert!(html.contains("p99 &lt; 300ms per 1m window: 1 of 2 windows met (50.00%)"));
+        assert!(html.contains("<span class=\"fail\">FAIL</span>, burn rate 5.00x"));
+        assert!(html.contains("<td>60s - 120s</td><td class=\"fail\">350.00ms</td>"));
+    }
+}
@@ -0,0 +1,235 @@
+//! Windowed SLO compliance for soak tests.
+//!
+//! Instead of a single pass/fail on the whole run, an SLO objective is
+//! checked in fixed windows (five minutes by default) and the report says
+//! how many windows met it:
+//!
+//! ```text
+//! --slo 'p99 < 300ms' --slo-window 5m --slo-target 99
+//! ```
+//!
+//! With a target, the share of failing windows is expressed as an error
+//! budget: a burn rate of 1.0 uses up exactly the budget over the run, and
+//! anything above it exhausts the budget early.
+
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::duration::format_duration;
+use crate::error::{Result, RurlError};
+use super::metrics::TimelinePoint;
+use super::thresholds::{Threshold, ThresholdMetric};
+
+/// A latency or error-rate objective checked per window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slo {
+    objective: Threshold,
+    window: Duration,
+    target_percent: Option<f64>,
+}
+
+impl Slo {
+    /// Creates an SLO checking `objective` in windows of `window`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the objective is scoped to an endpoint, uses a
+    /// metric not tracked per window (`avg`, `max`), or the window is zero.
+    pub fn new(objective: Threshold, window: Duration) -> Result<Self> {
+        if let Some(scope) = &objective.scope {
+            return Err(RurlError::PerfError(format!(
+                "SLO '{}' cannot be scoped to '{}'; SLOs apply to the whole run",
+                objective, scope
+            )));
+        }
+        if matches!(objective.metric, ThresholdMetric::Avg | ThresholdMetric::Max) {
+            return Err(RurlError::PerfError(format!(
+                "SLO '{}' must use p50, p95, p99 or error_rate",
+                objective
+            )));
+        }
+        if window.is_zero() {
+            return Err(RurlError::PerfError("SLO window must be longer than zero".to_string()));
+        }
+        Ok(Self {
+            objective,
+            window,
+            target_percent: None,
+        })
+    }
+
+    /// Sets the percentage of windows that must meet the objective.
+    pub fn target(mut self, percent: Option<f64>) -> Self {
+        self.target_percent = percent;
+        self
+    }
+
+    /// Returns the objective checked in every window.
+    pub fn objective(&self) -> &Threshold {
+        &self.objective
+    }
+
+    /// Returns the window length.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Checks every window against the objective.
+    ///
+    /// Windows without requests are skipped, as is a last window cut
+    /// shorter than half its length by the end of the run.
+    pub fn evaluate(&self, windows: &[TimelinePoint]) -> SloReport {
+        let full_ms = self.window.as_secs_f64() * 1000.0;
+        let mut checked = 0;
+        let mut violations = Vec::new();
+        for point in windows {
+            if point.requests == 0 || point.end_ms - point.start_ms < full_ms / 2.0 {
+                continue;
+            }
+            checked += 1;
+            let value = self.value(point);
+            let met = if self.objective.inclusive { value <= self.objective.limit } else { value < self.objective.limit };
+            if !met {
+                violations.push(SloWindow {
+                    start_ms: point.start_ms,
+                    end_ms: point.end_ms,
+                    value,
+                });
+            }
+        }
+
+        let compliant = checked - violations.len();
+        let compliance_percent = if checked > 0 { compliant as f64 / checked as f64 * 100.0 } else { 100.0 };
+        // Burn rate: share of failing windows relative to the share allowed
+        let burn_rate = self.target_percent.map(|target| {
+            let allowed = 100.0 - target;
+            let failing = 100.0 - compliance_percent;
+            match (failing > 0.0, allowed > 0.0) {
+                (false, _) => 0.0,
+                (true, true) => failing / allowed,
+                (true, false) => f64::INFINITY,
+            }
+        });
+
+        SloReport {
+            objective: self.objective.to_string(),
+            window: format_duration(self.window),
+            unit: self.objective.metric.unit(),
+            windows: checked,
+            compliant_windows: compliant,
+            compliance_percent,
+            target_percent: self.target_percent,
+            met: self.target_percent.map(|target| compliance_percent >= target),
+            burn_rate,
+            error_budget_remaining_percent: burn_rate.map(|rate| (1.0 - rate) * 100.0),
+            violations,
+        }
+    }
+
+    fn value(&self, point: &TimelinePoint) -> f64 {
+        match self.objective.metric {
+            ThresholdMetric::P50 => point.latency_p50_ms,
+            ThresholdMetric::P95 => point.latency_p95_ms,
+            ThresholdMetric::P99 | ThresholdMetric::Avg | ThresholdMetric::Max => point.latency_p99_ms,
+            ThresholdMetric::ErrorRate => point.failed_requests as f64 / point.requests as f64 * 100.0,
+        }
+    }
+}
+
+/// A window that missed the objective.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SloWindow {
+    /// Window start, in milliseconds since the run started
+    pub start_ms: f64,
+    /// Window end, in milliseconds since the run started
+    pub end_ms: f64,
+    /// Value of the objective's metric in the window
+    pub value: f64,
+}
+
+/// Window compliance of a run against an SLO.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SloReport {
+    /// The objective as given (e.g. "p99 < 300ms")
+    pub objective: String,
+    /// Window length (e.g. "5m")
+    pub window: String,
+    /// Unit of the objective's limit and window values (`ms` or `%`)
+    pub unit: &'static str,
+    /// Number of windows checked
+    pub windows: usize,
+    /// Number of those windows that met the objective
+    pub compliant_windows: usize,
+    /// Percentage of windows that met the objective
+    pub compliance_percent: f64,
+    /// Percentage of windows required to meet it, if set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_percent: Option<f64>,
+    /// Whether the target was met
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub met: Option<bool>,
+    /// Failing windows relative to those the target allows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burn_rate: Option<f64>,
+    /// Error budget left at the end of the run; negative once overspent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_budget_remaining_percent: Option<f64>,
+    /// Windows that missed the objective
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<SloWindow>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(index: usize, p99: f64, failed: usize) -> TimelinePoint {
+        TimelinePoint {
+            start_ms: index as f64 * 60_000.0,
+            end_ms: (index + 1) as f64 * 60_000.0,
+            requests: 100,
+            failed_requests: failed,
+            requests_per_second: 100.0 / 60.0,
+            latency_p50_ms: p99 / 2.0,
+            latency_p95_ms: p99 * 0.9,
+            latency_p99_ms: p99,
+        }
+    }
+
+    fn slo(objective: &str) -> Slo {
+        Slo::new(objective.parse().unwrap(), Duration::from_secs(60)).unwrap()
+    }
+
+    #[test]
+    fn test_window_compliance() {
+        let windows: Vec<_> = (0..10).map(|i| window(i, if i == 3 { 450.0 } else { 200.0 }, 0)).collect();
+        let report = slo("p99 < 300ms").target(Some(95.0)).evaluate(&windows);
+        assert_eq!(report.windows, 10);
+        assert_eq!(report.compliant_windows, 9);
+        assert_eq!(report.compliance_percent, 90.0);
+        assert_eq!(report.met, Some(false));
+        assert!((report.burn_rate.unwrap() - 2.0).abs() < 1e-9);
+        assert!((report.error_budget_remaining_percent.unwrap() + 100.0).abs() < 1e-9);
+        assert_eq!(report.violations, vec![SloWindow { start_ms: 180_000.0, end_ms: 240_000.0, value: 450.0 }]);
+    }
+
+    #[test]
+    fn test_skips_empty_and_short_windows() {
+        let mut windows = vec![window(0, 100.0, 5), window(1, 100.0, 0), window(2, 100.0, 0)];
+        windows[1].requests = 0;
+        windows[2].end_ms = windows[2].start_ms + 10_000.0;
+        let report = slo("error_rate < 1%").evaluate(&windows);
+        assert_eq!(report.windows, 1);
+        assert_eq!(report.compliant_windows, 0);
+        assert_eq!(report.violations[0].value, 5.0);
+        assert_eq!(report.burn_rate, None);
+    }
+
+    #[test]
+    fn test_rejects_unsupported_objectives() {
+        let window = Duration::from_secs(60);
+        assert!(Slo::new("checkout p99 < 300ms".parse().unwrap(), window).is_err());
+        assert!(Slo::new("avg < 300ms".parse().unwrap(), window).is_err());
+        assert!(Slo::new("p99 < 300ms".parse().unwrap(), Duration::ZERO).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! Multi-stage load profiles.
+//!
+//! A [`LoadProfile`] varies the load over time: each stage ramps linearly
+//! from the previous stage's target (0 for the first) to its own target
+//! over its duration. Targets are concurrency levels, or arrival rates
+//! when written with a `/s` suffix:
+//!
+//! ```text
+//! --stages 1m:50,2m:200,1m:0         # concurrency
+//! --stages 30s:100/s,5m:100/s        # requests per second
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::duration::{format_duration, parse_duration};
+use crate::error::{Result, RurlError};
+
+/// What a stage target controls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageUnit {
+    /// Number of requests in flight
+    Concurrency,
+    /// Requests started per second
+    Rate,
+}
+
+/// One stage: ramp to `target` over `duration`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stage {
+    /// How long the stage lasts
+    pub duration: Duration,
+    /// Load reached at the end of the stage
+    pub target: f64,
+}
+
+/// A load profile made of consecutive stages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadProfile {
+    stages: Vec<Stage>,
+    unit: StageUnit,
+    /// Whether the last target is held after the stages end
+    hold: bool,
+}
+
+impl LoadProfile {
+    /// Creates a profile that ramps from zero to `target` and then holds it.
+    ///
+    /// Used for `--ramp-up`; the run length is still set by `-n` or
+    /// `--duration`.
+    pub fn ramp_up(duration: Duration, target: f64, unit: StageUnit) -> Self {
+        Self {
+            stages: vec![Stage { duration, target }],
+            unit,
+            hold: true,
+        }
+    }
+
+    /// Returns the stages in order.
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// Returns what the targets control.
+    pub fn unit(&self) -> StageUnit {
+        self.unit
+    }
+
+    /// Returns the highest target in the profile.
+    pub fn max_target(&self) -> f64 {
+        self.stages.iter().map(|s| s.target).fold(0.0, f64::max)
+    }
+
+    /// Returns when the profile ends, or `None` if it holds its last target.
+    pub fn end(&self) -> Option<Duration> {
+        (!self.hold).then(|| self.stages.iter().map(|s| s.duration).sum())
+    }
+
+    /// Returns the target load at `elapsed`, or `None` once the profile has ended.
+    pub fn target_at(&self, elapsed: Duration) -> Option<f64> {
+        let elapsed = elapsed.as_secs_f64();
+        let mut offset = 0.0;
+        let mut previous = 0.0;
+        for stage in &self.stages {
+            let length = stage.duration.as_secs_f64();
+            if elapsed < offset + length {
+                return Some(previous + (stage.target - previous) * (elapsed - offset) / length);
+            }
+            offset += length;
+            previous = stage.target;
+        }
+        self.hold.then_some(previous)
+    }
+
+    /// Returns the index of the stage running at `elapsed`.
+    ///
+    /// When the last target is held, the time after the stages is reported
+    /// as one extra stage with index `stages().len()`.
+    pub fn stage_at(&self, elapsed: Duration) -> Option<usize> {
+        let mut end = Duration::ZERO;
+        for (index, stage) in self.stages.iter().enumerate() {
+            end += stage.duration;
+            if elapsed < end {
+                return Some(index);
+            }
+        }
+        self.hold.then_some(self.stages.len())
+    }
+
+    /// Returns when the `n`th request (counting from 0) is due at the
+    /// profile's arrival rate, or `None` if the profile ends first.
+    ///
+    /// The number of arrivals by time `t` is the integral of the rate, so
+    /// this solves that integral for `n` within the stage it falls in.
+    pub fn arrival_time(&self, n: f64) -> Option<Duration> {
+        let mut remaining = n;
+        let mut offset = 0.0;
+        let mut previous = 0.0;
+        for stage in &self.stages {
+            let length = stage.duration.as_secs_f64();
+            let arrivals = (previous + stage.target) / 2.0 * length;
+            if remaining <= arrivals {
+                if remaining <= 0.0 {
+                    return Some(Duration::from_secs_f64(offset));
+                }
+                // Solves previous*t + (target - previous)*t²/(2*length) = remaining
+                let slope = (stage.target - previous) / length;
+                let discriminant = (previous * previous + 2.0 * slope * remaining).max(0.0);
+                let t = 2.0 * remaining / (previous + discriminant.sqrt());
+                return Some(Duration::from_secs_f64(offset + t.min(length)));
+            }
+            remaining -= arrivals;
+            offset += length;
+            previous = stage.target;
+        }
+        (self.hold && previous > 0.0).then(|| Duration::from_secs_f64(offset + remaining / previous))
+    }
+
+    /// Returns how many requests are due by `elapsed` at the profile's
+    /// arrival rate: the integral of the rate up to then.
+    pub fn arrivals_by(&self, elapsed: Duration) -> f64 {
+        let elapsed = elapsed.as_secs_f64();
+        let mut arrivals = 0.0;
+        let mut offset = 0.0;
+        let mut previous = 0.0;
+        for stage in &self.stages {
+            let length = stage.duration.as_secs_f64();
+            let within = (elapsed - offset).clamp(0.0, length);
+            let reached = previous + (stage.target - previous) * within / length;
+            arrivals += (previous + reached) / 2.0 * within;
+            offset += length;
+            previous = stage.target;
+        }
+        if self.hold && elapsed > offset {
+            arrivals += previous * (elapsed - offset);
+        }
+        arrivals
+    }
+
+    /// Returns a label, start offset and length (open-ended when held) for each stage.
+    pub fn stage_spans(&self) -> Vec<(String, Duration, Option<Duration>)> {
+        let mut spans = Vec::with_capacity(self.stages.len() + 1);
+        let mut start = Duration::ZERO;
+        let mut previous = 0.0;
+        for stage in &self.stages {
+            let label = format!(
+                "{} → {} over {}",
+                self.format_target(previous),
+                self.format_target(stage.target),
+                format_duration(stage.duration)
+            );
+            spans.push((label, start, Some(stage.duration)));
+            start += stage.duration;
+            previous = stage.target;
+        }
+        if self.hold {
+            spans.push((format!("hold at {}", self.format_target(previous)), start, None));
+        }
+        spans
+    }
+
+    fn format_target(&self, target: f64) -> String {
+        match self.unit {
+            StageUnit::Concurrency => format!("{}", target),
+            StageUnit::Rate => format!("{}/s", target),
+        }
+    }
+}
+
+impl FromStr for LoadProfile {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: String| {
+            RurlError::PerfError(format!("invalid stages '{}': {} (expected e.g. '1m:50,2m:200,1m:0')", s, reason))
+        };
+
+        let mut stages = Vec::new();
+        let mut unit = None;
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (duration, target) = part
+                .split_once(':')
+                .ok_or_else(|| invalid(format!("'{}' is not duration:target", part)))?;
+            let duration = parse_duration(duration).map_err(invalid)?;
+            if duration.is_zero() {
+                return Err(invalid(format!("stage '{}' has no duration", part)));
+            }
+
+            let (target, stage_unit) = match target.trim().strip_suffix("/s") {
+                Some(rate) => (rate, StageUnit::Rate),
+                None => (target.trim(), StageUnit::Concurrency),
+            };
+            if unit.is_some_and(|u| u != stage_unit) {
+                return Err(invalid("stages mix concurrency and rate targets".to_string()));
+            }
+            unit = Some(stage_unit);
+
+            let target: f64 = target
+                .parse()
+                .ok()
+                .filter(|t: &f64| *t >= 0.0 && t.is_finite())
+                .ok_or_else(|| invalid(format!("'{}' is not a valid target", target)))?;
+            stages.push(Stage { duration, target });
+        }
+
+        match unit {
+            Some(unit) => Ok(Self { stages, unit, hold: false }),
+            None => Err(invalid("no stages given".to_string())),
+        }
+    }
+}
+
+impl fmt::Display for LoadProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stages: Vec<String> = self
+            .stages
+            .iter()
+            .map(|s| format!("{}:{}", format_duration(s.duration), self.format_target(s.target)))
+            .collect();
+        write!(f, "{}", stages.join(","))?;
+        if self.hold {
+            write!(f, " then hold")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let profile: LoadProfile = "1m:50, 2m:200,1m:0".parse().unwrap();
+        assert_eq!(profile.unit(), StageUnit::Concurrency);
+        assert_eq!(profile.stages().len(), 3);
+        assert_eq!(profile.end(), Some(Duration::from_secs(240)));
+        assert_eq!(profile.max_target(), 200.0);
+        assert_eq!(profile.to_string(), "1m:50,2m:200,1m:0");
+
+        let rate: LoadProfile = "30s:100/s,1m:100/s".parse().unwrap();
+        assert_eq!(rate.unit(), StageUnit::Rate);
+
+        assert!("".parse::<LoadProfile>().is_err());
+        assert!("1m".parse::<LoadProfile>().is_err());
+        assert!("0s:10".parse::<LoadProfile>().is_err());
+        assert!("1m:-5".parse::<LoadProfile>().is_err());
+        assert!("1m:10,1m:10/s".parse::<LoadProfile>().is_err());
+    }
+
+    #[test]
+    fn test_target_interpolation() {
+        let profile: LoadProfile = "10s:100,10s:100,10s:0".parse().unwrap();
+        assert_eq!(profile.target_at(Duration::ZERO), Some(0.0));
+        assert_eq!(profile.target_at(Duration::from_secs(5)), Some(50.0));
+        assert_eq!(profile.target_at(Duration::from_secs(15)), Some(100.0));
+        assert_eq!(profile.target_at(Duration::from_secs(25)), Some(50.0));
+        assert_eq!(profile.target_at(Duration::from_secs(30)), None);
+        assert_eq!(profile.stage_at(Duration::from_secs(12)), Some(1));
+        assert_eq!(profile.stage_at(Duration::from_secs(30)), None);
+    }
+
+    #[test]
+    fn test_ramp_up_holds() {
+        let profile = LoadProfile::ramp_up(Duration::from_secs(10), 20.0, StageUnit::Concurrency);
+        assert_eq!(profile.end(), None);
+        assert_eq!(profile.target_at(Duration::from_secs(5)), Some(10.0));
+        assert_eq!(profile.target_at(Duration::from_secs(60)), Some(20.0));
+        assert_eq!(profile.stage_at(Duration::from_secs(60)), Some(1));
+        let labels: Vec<_> = profile.stage_spans().into_iter().map(|(label, _, _)| label).collect();
+        assert_eq!(labels, vec!["0 → 20 over 10s", "hold at 20"]);
+    }
+
+    #[test]
+    fn test_arrival_time() {
+        let secs = |d: Option<Duration>| d.map(|d| (d.as_secs_f64() * 1000.0).round() / 1000.0);
+
+        // Ramp to 10/s over 1s (5 arrivals), then steady at 10/s
+        let steady: LoadProfile = "1s:10/s,10s:10/s".parse().unwrap();
+        assert_eq!(secs(steady.arrival_time(0.0)), Some(0.0));
+        assert_eq!(secs(steady.arrival_time(15.0)), Some(2.0));
+
+        // Ramp 0 → 20/s over 10s: 100 arrivals in total, n(t) = t²
+        let ramp: LoadProfile = "10s:20/s".parse().unwrap();
+        assert_eq!(secs(ramp.arrival_time(25.0)), Some(5.0));
+        assert_eq!(secs(ramp.arrival_time(100.0)), Some(10.0));
+        assert_eq!(ramp.arrival_time(101.0), None);
+
+        // Held afterwards at 20/s
+        let held = LoadProfile::ramp_up(Duration::from_secs(10), 20.0, StageUnit::Rate);
+        assert_eq!(secs(held.arrival_time(120.0)), Some(11.0));
+
+        assert_eq!(ramp.arrivals_by(Duration::from_secs(5)), 25.0);
+        assert_eq!(ramp.arrivals_by(Duration::from_secs(60)), 100.0);
+        assert_eq!(held.arrivals_by(Duration::from_secs(11)), 120.0);
+
+        // Ramp down 20/s → 0 over 10s after a steady stage
+        let down: LoadProfile = "1s:20/s,1s:20/s,10s:0/s".parse().unwrap();
+        assert_eq!(secs(down.arrival_time(30.0 + 75.0)), Some(7.0));
+    }
+}
@@ -0,0 +1,223 @@
+//! Live StatsD metrics for a performance run.
+//!
+//! Emits a timing and a counter per request, an error counter per failed
+//! request, and the interval throughput as a gauge, so dashboards update
+//! while the run is in progress:
+//!
+//! ```text
+//! hurley.request.duration:12.4|ms|#endpoint:GET_/users,status:200
+//! hurley.request.count:1|c|#endpoint:GET_/users,status:200
+//! hurley.request.errors:1|c|#endpoint:GET_/users,error:timeout
+//! hurley.rps:480.5|g
+//! ```
+//!
+//! Tags use the DogStatsD `|#key:value` extension and are only sent when
+//! enabled. Lines are batched into UDP packets small enough not to be
+//! fragmented; send errors are ignored, as StatsD is best-effort.
+
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::Mutex;
+
+use crate::error::{Result, RurlError};
+use super::events::RunEvent;
+
+/// Largest payload sent in one packet, leaving room for IP and UDP headers
+/// within a common 1500-byte MTU.
+const MAX_PACKET: usize = 1432;
+
+/// Emits run events as StatsD metrics over UDP.
+///
+/// Like [`RequestLog`](super::RequestLog), it is fed from a
+/// [`PerfRunner::run_with_events`](super::PerfRunner::run_with_events)
+/// callback and can be shared between worker tasks.
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+    dogstatsd: bool,
+    tags: Vec<String>,
+    buffer: Mutex<String>,
+}
+
+impl StatsdSink {
+    /// Creates a sink sending to a StatsD server at `host:port`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be resolved or no socket can
+    /// be opened.
+    pub fn connect(addr: &str) -> Result<Self> {
+        let unreachable = |e: std::io::Error| RurlError::PerfError(format!("cannot reach StatsD at {}: {}", addr, e));
+        let target = addr
+            .to_socket_addrs()
+            .map_err(unreachable)?
+            .next()
+            .ok_or_else(|| RurlError::PerfError(format!("cannot resolve StatsD address {}", addr)))?;
+        let local = if target.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+        let socket = UdpSocket::bind(local).map_err(unreachable)?;
+        socket.connect(target).map_err(unreachable)?;
+        socket.set_nonblocking(true).map_err(unreachable)?;
+        Ok(Self {
+            socket,
+            prefix: "hurley".to_string(),
+            dogstatsd: false,
+            tags: Vec::new(),
+            buffer: Mutex::new(String::new()),
+        })
+    }
+
+    /// Sets the metric name prefix (default `hurley`).
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Sends DogStatsD tags: endpoint, status and error per request.
+    pub fn dogstatsd(mut self, enabled: bool) -> Self {
+        self.dogstatsd = enabled;
+        self
+    }
+
+    /// Adds `key:value` tags to every metric; implies [`dogstatsd`](Self::dogstatsd).
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.dogstatsd |= !tags.is_empty();
+        self.tags = tags.iter().map(|tag| sanitize_tag(tag)).collect();
+        self
+    }
+
+    /// Emits the metrics for one event.
+    ///
+    /// Buffered lines are sent when a packet fills up, at every
+    /// [`RunEvent::IntervalStats`], and at [`RunEvent::Finished`].
+    pub fn record(&self, event: &RunEvent) {
+        match event {
+            RunEvent::RequestDone { label, status, duration, error, .. } => {
+                let endpoint = format!("endpoint:{}", sanitize_tag(label));
+                let status = status.map_or("status:none".to_string(), |s| format!("status:{}", s));
+                let ms = duration.as_secs_f64() * 1000.0;
+                self.push(&format!("request.duration:{:.3}|ms", ms), &[&endpoint, &status]);
+                self.push("request.count:1|c", &[&endpoint, &status]);
+                if let Some(error) = error {
+                    self.push("request.errors:1|c", &[&endpoint, &format!("error:{}", error)]);
+                }
+            }
+            RunEvent::IntervalStats(stats) => {
+                self.push(&format!("rps:{:.2}|g", stats.interval_requests_per_second), &[]);
+                self.flush();
+            }
+            RunEvent::Finished(_) => self.flush(),
+            RunEvent::WarmupFinished => {}
+        }
+    }
+
+    /// Sends any buffered lines.
+    pub fn flush(&self) {
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        self.send(&mut buffer);
+    }
+
+    /// Appends one metric line, sending the buffer first if it would overflow.
+    fn push(&self, metric: &str, tags: &[&str]) {
+        let mut line = format!("{}.{}", self.prefix, metric);
+        if self.dogstatsd {
+            let all: Vec<&str> = self.tags.iter().map(String::as_str).chain(tags.iter().copied()).collect();
+            if !all.is_empty() {
+                line.push_str("|#");
+                line.push_str(&all.join(","));
+            }
+        }
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        if !buffer.is_empty() && buffer.len() + 1 + line.len() > MAX_PACKET {
+            self.send(&mut buffer);
+        }
+        if !buffer.is_empty() {
+            buffer.push('\n');
+        }
+        buffer.push_str(&line);
+    }
+
+    fn send(&self, buffer: &mut String) {
+        if !buffer.is_empty() {
+            let _ = self.socket.send(buffer.as_bytes());
+            buffer.clear();
+        }
+    }
+}
+
+/// Replaces characters with a meaning in the StatsD line format.
+fn sanitize_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| if matches!(c, ',' | '|' | '#' | '\n' | '\r') || c.is_whitespace() { '_' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpRequest;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    fn done(error: Option<&'static str>) -> RunEvent {
+        RunEvent::RequestDone {
+            completed: 1,
+            total: 1,
+            label: "GET /users".to_string(),
+            request: Arc::new(HttpRequest::new("http://x/users").prepare().unwrap()),
+            started: SystemTime::now(),
+            status: error.is_none().then_some(200),
+            duration: Duration::from_micros(12_500),
+            bytes: 0,
+            success: error.is_none(),
+            error,
+            phases: None,
+            captures: Vec::new(),
+        }
+    }
+
+    fn receive(sink: StatsdSink, events: &[RunEvent], server: &UdpSocket) -> Vec<String> {
+        for event in events {
+            sink.record(event);
+        }
+        sink.flush();
+        let mut buf = [0u8; MAX_PACKET];
+        let len = server.recv(&mut buf).unwrap();
+        String::from_utf8_lossy(&buf[..len]).lines().map(str::to_string).collect()
+    }
+
+    fn server() -> (UdpSocket, String) {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        (server, addr)
+    }
+
+    #[test]
+    fn test_plain_statsd() {
+        let (server, addr) = server();
+        let sink = StatsdSink::connect(&addr).unwrap();
+        let lines = receive(sink, &[done(None)], &server);
+        assert_eq!(lines, vec!["hurley.request.duration:12.500|ms", "hurley.request.count:1|c"]);
+    }
+
+    #[test]
+    fn test_dogstatsd_tags() {
+        let (server, addr) = server();
+        let sink = StatsdSink::connect(&addr).unwrap().prefix("load").tags(vec!["env:staging".to_string()]);
+        let lines = receive(sink, &[done(Some("timeout"))], &server);
+        assert_eq!(lines[0], "load.request.duration:12.500|ms|#env:staging,endpoint:GET_/users,status:none");
+        assert_eq!(lines[2], "load.request.errors:1|c|#env:staging,endpoint:GET_/users,error:timeout");
+    }
+
+    #[test]
+    fn test_batches_fit_in_a_packet() {
+        let (server, addr) = server();
+        let sink = StatsdSink::connect(&addr).unwrap().dogstatsd(true);
+        for _ in 0..100 {
+            sink.record(&done(None));
+        }
+        let mut buf = [0u8; 65536];
+        let len = server.recv(&mut buf).unwrap();
+        assert!(len <= MAX_PACKET);
+        assert!(String::from_utf8_lossy(&buf[..len]).starts_with("hurley.request.duration:"));
+    }
+}
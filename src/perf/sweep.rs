@@ -0,0 +1,90 @@
+//! Concurrency sweep results.
+//!
+//! `hurley URL -n 1000 sweep --concurrency 1,2,4,8` runs the same workload
+//! once per concurrency level; the results show how throughput and tail
+//! latency scale with concurrency.
+
+use serde::Serialize;
+use std::io::Write;
+
+use crate::error::Result;
+use super::metrics::PerfMetrics;
+
+/// Column names of the sweep CSV.
+const CSV_HEADER: &str = "concurrency,requests,failed,error_rate_percent,requests_per_second,p50_ms,p95_ms,p99_ms";
+
+/// Metrics of one concurrency level in a sweep.
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepPoint {
+    /// Concurrency the workload ran at
+    pub concurrency: usize,
+    /// Metrics of that run
+    pub metrics: PerfMetrics,
+}
+
+impl SweepPoint {
+    /// Returns the level with the highest throughput, if any.
+    pub fn peak(points: &[SweepPoint]) -> Option<&SweepPoint> {
+        points
+            .iter()
+            .max_by(|a, b| a.metrics.requests_per_second.total_cmp(&b.metrics.requests_per_second))
+    }
+}
+
+/// Writes one CSV row per concurrency level.
+///
+/// # Errors
+///
+/// Returns an error if writing fails.
+pub fn write_csv<W: Write>(points: &[SweepPoint], mut out: W) -> Result<()> {
+    writeln!(out, "{}", CSV_HEADER)?;
+    for point in points {
+        let m = &point.metrics;
+        writeln!(
+            out,
+            "{},{},{},{:.2},{:.2},{:.3},{:.3},{:.3}",
+            point.concurrency,
+            m.total_requests,
+            m.failed_requests,
+            m.error_rate_percent,
+            m.requests_per_second,
+            m.latency_p50_ms,
+            m.latency_p95_ms,
+            m.latency_p99_ms,
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perf::metrics::MetricsCollector;
+
+    fn point(concurrency: usize, rps: f64) -> SweepPoint {
+        let mut metrics = MetricsCollector::new().compute_metrics();
+        metrics.total_requests = 100;
+        metrics.requests_per_second = rps;
+        metrics.latency_p99_ms = 12.5;
+        SweepPoint { concurrency, metrics }
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let points = [point(1, 50.0), point(4, 180.0)];
+        let mut out = Vec::new();
+        write_csv(&points, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[2], "4,100,0,0.00,180.00,0.000,0.000,12.500");
+    }
+
+    #[test]
+    fn test_peak() {
+        let points = [point(1, 50.0), point(4, 180.0), point(8, 170.0)];
+        assert_eq!(SweepPoint::peak(&points).unwrap().concurrency, 4);
+        assert!(SweepPoint::peak(&[]).is_none());
+    }
+}
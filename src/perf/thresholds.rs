@@ -0,0 +1,257 @@
+//! Pass/fail thresholds for performance runs.
+//!
+//! A threshold is a latency or error-rate limit, optionally scoped to one
+//! endpoint or dataset tag:
+//!
+//! ```text
+//! p99 < 500ms                # whole run
+//! error_rate < 1%            # whole run
+//! checkout p99 < 400ms       # entries tagged "checkout"
+//! GET /search p95 <= 150ms   # one endpoint
+//! ```
+
+use serde::Serialize;
+use std::str::FromStr;
+
+use crate::duration::parse_duration;
+use crate::error::{Result, RurlError};
+use super::metrics::PerfMetrics;
+
+/// Metric a threshold applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdMetric {
+    /// Median latency
+    P50,
+    /// 95th percentile latency
+    P95,
+    /// 99th percentile latency
+    P99,
+    /// Average latency
+    Avg,
+    /// Maximum latency
+    Max,
+    /// Percentage of failed requests
+    ErrorRate,
+}
+
+impl ThresholdMetric {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "p50" | "median" => Some(Self::P50),
+            "p95" => Some(Self::P95),
+            "p99" => Some(Self::P99),
+            "avg" | "mean" => Some(Self::Avg),
+            "max" => Some(Self::Max),
+            "error_rate" | "errors" => Some(Self::ErrorRate),
+            _ => None,
+        }
+    }
+
+    fn value(self, metrics: &PerfMetrics) -> f64 {
+        match self {
+            Self::P50 => metrics.latency_p50_ms,
+            Self::P95 => metrics.latency_p95_ms,
+            Self::P99 => metrics.latency_p99_ms,
+            Self::Avg => metrics.latency_avg_ms,
+            Self::Max => metrics.latency_max_ms,
+            Self::ErrorRate => metrics.error_rate_percent,
+        }
+    }
+
+    /// Returns the unit limits and values are expressed in.
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::ErrorRate => "%",
+            _ => "ms",
+        }
+    }
+}
+
+/// A single threshold, e.g. `checkout p99 < 400ms`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Threshold {
+    /// Endpoint label or tag, or `None` for the whole run
+    pub scope: Option<String>,
+    /// Metric being checked
+    pub metric: ThresholdMetric,
+    /// Whether the limit itself is allowed (`<=`) or not (`<`)
+    pub inclusive: bool,
+    /// Limit in milliseconds, or percent for the error rate
+    pub limit: f64,
+}
+
+impl Threshold {
+    /// Checks the threshold against the metrics of a run.
+    pub fn evaluate(&self, metrics: &PerfMetrics) -> ThresholdResult {
+        let scoped = match &self.scope {
+            None => Some(metrics),
+            Some(scope) => metrics.endpoints.get(scope),
+        };
+        let actual = scoped.map(|m| self.metric.value(m));
+        let passed = match actual {
+            Some(value) if self.inclusive => value <= self.limit,
+            Some(value) => value < self.limit,
+            None => false,
+        };
+        ThresholdResult {
+            threshold: self.clone(),
+            actual,
+            passed,
+        }
+    }
+}
+
+impl std::fmt::Display for Threshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(scope) = &self.scope {
+            write!(f, "{} ", scope)?;
+        }
+        let metric = serde_json::to_value(self.metric).ok();
+        write!(
+            f,
+            "{} {} {}{}",
+            metric.as_ref().and_then(|m| m.as_str()).unwrap_or("?"),
+            if self.inclusive { "<=" } else { "<" },
+            self.limit,
+            self.metric.unit()
+        )
+    }
+}
+
+impl FromStr for Threshold {
+    type Err = RurlError;
+
+    /// Parses `[scope] metric < limit`; `<=` is also accepted.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            RurlError::PerfError(format!("invalid threshold '{}': {} (expected e.g. 'p99 < 400ms')", s, reason))
+        };
+
+        let (left, inclusive, right) = if let Some((left, right)) = s.split_once("<=") {
+            (left, true, right)
+        } else if let Some((left, right)) = s.split_once('<') {
+            (left, false, right)
+        } else {
+            return Err(invalid("missing '<' or '<='"));
+        };
+
+        let left = left.trim();
+        let (scope, metric_name) = match left.rsplit_once(char::is_whitespace) {
+            Some((scope, metric)) => (Some(scope.trim().to_string()), metric),
+            None => (None, left),
+        };
+        let metric = ThresholdMetric::parse(metric_name)
+            .ok_or_else(|| invalid(&format!("unknown metric '{}'", metric_name)))?;
+
+        let right = right.trim();
+        let limit = if metric == ThresholdMetric::ErrorRate {
+            right.trim_end_matches('%').trim().parse::<f64>().map_err(|_| invalid("invalid percentage"))?
+        } else {
+            parse_duration(right).map_err(|e| invalid(&e))?.as_secs_f64() * 1000.0
+        };
+
+        Ok(Self {
+            scope: scope.filter(|s| !s.is_empty()),
+            metric,
+            inclusive,
+            limit,
+        })
+    }
+}
+
+/// Outcome of checking one [`Threshold`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ThresholdResult {
+    /// The threshold that was checked
+    pub threshold: Threshold,
+    /// Measured value, or `None` if the scope had no requests
+    pub actual: Option<f64>,
+    /// Whether the threshold was met
+    pub passed: bool,
+}
+
+/// Checks every threshold against the metrics of a run.
+pub fn evaluate(thresholds: &[Threshold], metrics: &PerfMetrics) -> Vec<ThresholdResult> {
+    thresholds.iter().map(|t| t.evaluate(metrics)).collect()
+}
+
+/// Checks every threshold against each of several runs, such as the
+/// backends of a comparison or the levels of a sweep, pairing each result
+/// with the label of its run.
+pub fn evaluate_each<'a, I>(thresholds: &[Threshold], runs: I) -> Vec<(String, ThresholdResult)>
+where
+    I: IntoIterator<Item = (String, &'a PerfMetrics)>,
+{
+    runs.into_iter()
+        .flat_map(|(label, metrics)| evaluate(thresholds, metrics).into_iter().map(move |r| (label.clone(), r)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perf::metrics::MetricsCollector;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse() {
+        let t: Threshold = "p99 < 500ms".parse().unwrap();
+        assert_eq!(t.scope, None);
+        assert_eq!(t.metric, ThresholdMetric::P99);
+        assert!(!t.inclusive);
+        assert_eq!(t.limit, 500.0);
+
+        let t: Threshold = "GET /search p95<=1s".parse().unwrap();
+        assert_eq!(t.scope.as_deref(), Some("GET /search"));
+        assert!(t.inclusive);
+        assert_eq!(t.limit, 1000.0);
+        assert_eq!(t.to_string(), "GET /search p95 <= 1000ms");
+
+        let t: Threshold = "checkout error_rate < 0.5%".parse().unwrap();
+        assert_eq!(t.metric, ThresholdMetric::ErrorRate);
+        assert_eq!(t.limit, 0.5);
+
+        assert!("p99 > 5ms".parse::<Threshold>().is_err());
+        assert!("p42 < 5ms".parse::<Threshold>().is_err());
+        assert!("p99 < fast".parse::<Threshold>().is_err());
+    }
+
+    #[test]
+    fn test_evaluate_scopes() {
+        let mut collector = MetricsCollector::new();
+        collector.record_success(Duration::from_millis(100), Some("checkout"));
+        collector.record_success(Duration::from_millis(300), Some("search"));
+        let metrics = collector.compute_metrics();
+
+        let thresholds: Vec<Threshold> = ["checkout p99 < 150ms", "search p99 < 150ms", "login p99 < 1s", "max < 1s"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let results = evaluate(&thresholds, &metrics);
+
+        assert!(results[0].passed);
+        assert!(!results[1].passed);
+        assert!(!results[2].passed);
+        assert_eq!(results[2].actual, None);
+        assert!(results[3].passed);
+    }
+
+    #[test]
+    fn test_evaluate_each() {
+        let run = |millis| {
+            let mut collector = MetricsCollector::new();
+            collector.record_success(Duration::from_millis(millis), None);
+            collector.compute_metrics()
+        };
+        let (fast, slow) = (run(100), run(300));
+        let thresholds: Vec<Threshold> = vec!["p99 < 200ms".parse().unwrap()];
+        let results = evaluate_each(&thresholds, [("10.0.0.1:80".to_string(), &fast), ("10.0.0.2:80".to_string(), &slow)]);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "10.0.0.1:80");
+        assert!(results[0].1.passed);
+        assert_eq!(results[1].0, "10.0.0.2:80");
+        assert!(!results[1].1.passed);
+    }
+}
@@ -0,0 +1,173 @@
+//! Single-request template files.
+//!
+//! A request file holds one shareable request: YAML frontmatter with the
+//! method, URL, headers and default template variables, followed by the raw
+//! body. Run one with `hurley send request.hurl`:
+//!
+//! ```text
+//! ---
+//! method: POST
+//! url: https://api.example.com/users/{{id}}
+//! headers:
+//!   Content-Type: application/json
+//! vars:
+//!   id: 42
+//! ---
+//! {"name": "{{name}}"}
+//! ```
+//!
+//! The URL, headers and body go through the same `{{var}}` and `${ENV}`
+//! expansion as command-line requests.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::error::{Result, RurlError};
+
+/// Frontmatter fields as written in the file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Frontmatter {
+    #[serde(default)]
+    method: Option<String>,
+    url: String,
+    #[serde(default)]
+    headers: BTreeMap<String, serde_yaml::Value>,
+    #[serde(default)]
+    vars: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// A request loaded from a template file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestFile {
+    /// HTTP method, if given
+    pub method: Option<String>,
+    /// Target URL, possibly relative to a profile's base URL
+    pub url: String,
+    /// Request headers as `(name, value)` pairs
+    pub headers: Vec<(String, String)>,
+    /// Default template variables
+    pub vars: Vec<(String, String)>,
+    /// Raw request body, if any
+    pub body: Option<String>,
+}
+
+impl RequestFile {
+    /// Loads a request file from disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is malformed.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content)
+            .map_err(|e| RurlError::TemplateError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parses request file contents.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::TemplateError`] if the frontmatter is missing or
+    /// invalid.
+    pub fn parse(content: &str) -> Result<Self> {
+        let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+        let rest = strip_delimiter(content)
+            .ok_or_else(|| RurlError::TemplateError("expected '---' frontmatter at the start of the file".to_string()))?;
+
+        let (frontmatter, body) = match find_delimiter(rest) {
+            Some((end, body_start)) => (&rest[..end], &rest[body_start..]),
+            None => return Err(RurlError::TemplateError("frontmatter is not closed with '---'".to_string())),
+        };
+
+        let frontmatter: Frontmatter = serde_yaml::from_str(frontmatter)
+            .map_err(|e| RurlError::TemplateError(format!("invalid frontmatter: {}", e)))?;
+
+        let body = body.strip_suffix('\n').unwrap_or(body);
+        let body = body.strip_suffix('\r').unwrap_or(body);
+
+        Ok(Self {
+            method: frontmatter.method,
+            url: frontmatter.url,
+            headers: scalar_pairs(frontmatter.headers, "header")?,
+            vars: scalar_pairs(frontmatter.vars, "var")?,
+            body: (!body.trim().is_empty()).then(|| body.to_string()),
+        })
+    }
+}
+
+/// Strips an opening `---` line, returning the rest of the content.
+fn strip_delimiter(content: &str) -> Option<&str> {
+    let rest = content.strip_prefix("---")?;
+    let rest = rest.trim_start_matches([' ', '\t']);
+    rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n'))
+}
+
+/// Finds the closing `---` line, returning where it starts and where the body begins.
+fn find_delimiter(rest: &str) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some((offset, offset + line.len()));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Converts a YAML map of scalars to string pairs, so `id: 42` works as well as `id: "42"`.
+fn scalar_pairs(map: BTreeMap<String, serde_yaml::Value>, what: &str) -> Result<Vec<(String, String)>> {
+    map.into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                serde_yaml::Value::String(s) => s,
+                serde_yaml::Value::Number(n) => n.to_string(),
+                serde_yaml::Value::Bool(b) => b.to_string(),
+                _ => return Err(RurlError::TemplateError(format!("{} '{}' must be a string, number or boolean", what, key))),
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_file() {
+        let file = RequestFile::parse(
+            "---\nmethod: POST\nurl: https://api.example.com/users/{{id}}\nheaders:\n  Content-Type: application/json\nvars:\n  id: 42\n---\n{\"name\": \"test\"}\n",
+        )
+        .unwrap();
+
+        assert_eq!(file.method.as_deref(), Some("POST"));
+        assert_eq!(file.url, "https://api.example.com/users/{{id}}");
+        assert_eq!(file.headers, vec![("Content-Type".to_string(), "application/json".to_string())]);
+        assert_eq!(file.vars, vec![("id".to_string(), "42".to_string())]);
+        assert_eq!(file.body.as_deref(), Some("{\"name\": \"test\"}"));
+    }
+
+    #[test]
+    fn test_parse_without_body() {
+        let file = RequestFile::parse("---\nurl: /health\n---\n").unwrap();
+        assert_eq!(file.method, None);
+        assert_eq!(file.body, None);
+    }
+
+    #[test]
+    fn test_body_keeps_dashes_and_blank_lines() {
+        let file = RequestFile::parse("---\nurl: /x\n---\nline one\n\n---\nline two\n").unwrap();
+        assert_eq!(file.body.as_deref(), Some("line one\n\n---\nline two"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(RequestFile::parse("url: /x\n").is_err());
+        assert!(RequestFile::parse("---\nurl: /x\n").is_err());
+        assert!(RequestFile::parse("---\nmethod: GET\n---\n").is_err());
+        assert!(RequestFile::parse("---\nurl: /x\nbogus: 1\n---\n").is_err());
+        assert!(RequestFile::parse("---\nurl: /x\nvars:\n  id: [1, 2]\n---\n").is_err());
+    }
+}
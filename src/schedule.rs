@@ -0,0 +1,69 @@
+//! Scheduled starts for performance runs.
+//!
+//! `--start-at 02:00` waits until the next 02:00 local time before starting
+//! the run; `--start-in 30m` waits a fixed delay. The wait can be
+//! cancelled with Ctrl+C like the run itself.
+
+use chrono::{Local, NaiveDateTime, NaiveTime, TimeZone};
+use std::time::Duration;
+
+/// Parses a local time of day such as `02:00` or `23:30:15`.
+///
+/// Used as a clap value parser for `--start-at`.
+pub fn parse_time_of_day(s: &str) -> Result<NaiveTime, String> {
+    let s = s.trim();
+    NaiveTime::parse_from_str(s, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+        .map_err(|_| format!("invalid time '{}' (use HH:MM or HH:MM:SS)", s))
+}
+
+/// Returns how long to wait from `now` until the next `at`.
+///
+/// A time that has already passed today means the same time tomorrow.
+pub fn delay_until(at: NaiveTime, now: NaiveDateTime) -> Duration {
+    let mut start = now.date().and_time(at);
+    if start <= now {
+        start += chrono::Duration::days(1);
+    }
+    (start - now).to_std().unwrap_or_default()
+}
+
+/// Returns how long to wait until the next `at` in the local time zone.
+///
+/// Around a daylight saving change the earliest matching instant is used;
+/// a time skipped by the change falls back to the naive difference.
+pub fn delay_until_local(at: NaiveTime) -> Duration {
+    let now = Local::now();
+    let naive = delay_until(at, now.naive_local());
+    let start = now.naive_local() + chrono::Duration::from_std(naive).unwrap_or_default();
+    match Local.from_local_datetime(&start).earliest() {
+        Some(start) => (start - now).to_std().unwrap_or(naive),
+        None => naive,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap().and_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_time_of_day() {
+        assert_eq!(parse_time_of_day("02:00"), Ok(NaiveTime::from_hms_opt(2, 0, 0).unwrap()));
+        assert_eq!(parse_time_of_day("23:30:15"), Ok(NaiveTime::from_hms_opt(23, 30, 15).unwrap()));
+        assert!(parse_time_of_day("25:00").is_err());
+        assert!(parse_time_of_day("2am").is_err());
+    }
+
+    #[test]
+    fn test_delay_until() {
+        let two = NaiveTime::from_hms_opt(2, 0, 0).unwrap();
+        assert_eq!(delay_until(two, at(1, 30)), Duration::from_secs(30 * 60));
+        assert_eq!(delay_until(two, at(22, 0)), Duration::from_secs(4 * 3600));
+        assert_eq!(delay_until(two, at(2, 0)), Duration::from_secs(24 * 3600));
+    }
+}
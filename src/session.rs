@@ -0,0 +1,381 @@
+//! Named sessions (`--session`, `--save-session`).
+//!
+//! A session keeps the request last sent with it (method, URL, headers and
+//! body), the `--auth` providers it used, and the cookies responses set, so
+//! a stateful workflow can continue across invocations:
+//!
+//! ```bash
+//! hurley -X POST https://api.example.com/login -d @creds.json --save-session api
+//! hurley https://api.example.com/cart --session api
+//! hurley --session api
+//! ```
+//!
+//! `--session NAME` without a URL replays the stored request; with a URL,
+//! it sends a new request with the session's headers and `--auth`. Either
+//! way the command line takes precedence, the cookies that apply to the URL
+//! are sent, and the cookies of the response are stored back into the
+//! session. `--save-session NAME` records a new session from the request, starting
+//! from the cookies of `--session` if both are given.
+//!
+//! Sessions are kept in `sessions/NAME.json` in the config directory; a
+//! name containing a `/` is taken as a file path. They may hold
+//! credentials, so on Unix they are only readable by their owner.
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+use crate::config;
+use crate::error::{Result, RurlError};
+use crate::http::cookies::SetCookie;
+use crate::http::HttpRequest;
+
+/// Request headers not stored with a session: cookies are kept on their
+/// own, and the others describe a single message.
+const UNSAVED_HEADERS: [&str; 3] = ["cookie", "content-length", "host"];
+
+/// The request of a session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionRequest {
+    /// HTTP method
+    pub method: String,
+    /// Target URL
+    pub url: String,
+    /// Request headers, as given with `-H` and by config profiles
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Request body, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+/// A cookie kept by a session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionCookie {
+    /// Cookie name
+    pub name: String,
+    /// Cookie value
+    pub value: String,
+    /// Host the cookie is sent to, lowercase
+    pub domain: String,
+    /// Whether only `domain` itself gets the cookie, not its subdomains
+    /// (no `Domain` attribute was set)
+    pub host_only: bool,
+    /// Path prefix of the URLs the cookie is sent to
+    pub path: String,
+    /// Whether the cookie is only sent over HTTPS
+    #[serde(default)]
+    pub secure: bool,
+    /// Expiry in seconds since the Unix epoch, `None` for a session cookie
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<i64>,
+}
+
+impl SessionCookie {
+    /// Returns the cookie a `Set-Cookie` received from `url` stores, or
+    /// `None` if its `Domain` doesn't cover the URL's host.
+    fn from_set_cookie(cookie: SetCookie, url: &Url) -> Option<Self> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let (domain, host_only) = match cookie.domain {
+            Some(domain) if domain_matches(&host, &domain) => (domain, false),
+            Some(_) => return None,
+            None => (host, true),
+        };
+        let path = match cookie.path {
+            Some(path) if path.starts_with('/') => path,
+            _ => default_path(url.path()),
+        };
+        // Max-Age wins over Expires; an unparseable Expires is ignored
+        let expires = match (cookie.max_age, &cookie.expires) {
+            (Some(age), _) => Some(now().saturating_add(age)),
+            (None, Some(expires)) => DateTime::parse_from_rfc2822(expires).ok().map(|date| date.timestamp()),
+            (None, None) => None,
+        };
+        Some(Self { name: cookie.name, value: cookie.value, domain, host_only, path, secure: cookie.secure, expires })
+    }
+
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// Returns true if the cookie is sent with a request to `url`
+    /// (RFC 6265 section 5.4).
+    fn applies_to(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+            return false;
+        };
+        let host_ok = match self.host_only {
+            true => host == self.domain,
+            false => domain_matches(&host, &self.domain),
+        };
+        host_ok && path_matches(url.path(), &self.path) && (!self.secure || url.scheme() == "https")
+    }
+}
+
+/// A named session, see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// Request last recorded in the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<SessionRequest>,
+    /// `--auth` providers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub auth: Vec<String>,
+    /// Cookies set by responses
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cookies: Vec<SessionCookie>,
+}
+
+impl Session {
+    /// Returns the file a session is kept in: `sessions/NAME.json` in the
+    /// config directory, or `name` itself if it contains a `/`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RurlError::ConfigError`] if the name is empty or the config
+    /// directory can't be located.
+    pub fn path(name: &str) -> Result<PathBuf> {
+        if name.contains('/') {
+            return Ok(PathBuf::from(name));
+        }
+        if name.is_empty() || name.starts_with('.') {
+            return Err(RurlError::ConfigError(format!("invalid session name '{}'", name)));
+        }
+        let dir = config::config_dir()
+            .ok_or_else(|| RurlError::ConfigError("cannot locate config directory to store sessions".to_string()))?;
+        Ok(dir.join("sessions").join(format!("{}.json", name)))
+    }
+
+    /// Loads a session, returning an empty one if the file doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| RurlError::ConfigError(format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the session, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        // The mode only applies to new files; tighten existing ones too
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        std::io::Write::write_all(&mut file, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Records the request to replay.
+    ///
+    /// `headers` are the `Name: value` headers given for it; headers the
+    /// request gained from other options, such as `--compressed`, come back
+    /// with those options instead. The `Content-Type` of the body is kept
+    /// however it was set. `body` is the body as given, before `${ENV_VAR}`
+    /// expansion, and replaces the request's if it has one.
+    pub fn record(&mut self, request: &HttpRequest, headers: &[String], auth: &[String], body: Option<&str>) {
+        let mut saved: BTreeMap<String, String> = headers
+            .iter()
+            .filter_map(|header| header.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .filter(|(name, _)| !UNSAVED_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+            .collect();
+        if let (Some(_), Some(content_type)) = (&request.body, request.header_value("Content-Type")) {
+            if !saved.keys().any(|name| name.eq_ignore_ascii_case("Content-Type")) {
+                saved.insert("Content-Type".to_string(), content_type.to_string());
+            }
+        }
+        self.request = Some(SessionRequest {
+            method: request.method.to_string(),
+            url: request.url.clone(),
+            headers: saved,
+            body: request.body.as_deref().map(|sent| body.unwrap_or(sent).to_string()),
+        });
+        self.auth = auth.to_vec();
+    }
+
+    /// Stores the cookies a response from `url` sets, replacing cookies of
+    /// the same name, domain and path and dropping expired ones.
+    ///
+    /// Returns the number of cookies set or deleted.
+    pub fn store_cookies(&mut self, url: &str, set_cookies: impl IntoIterator<Item = SetCookie>) -> usize {
+        let Ok(url) = Url::parse(url) else {
+            return 0;
+        };
+        let mut changed = 0;
+        for cookie in set_cookies.into_iter().filter_map(|cookie| SessionCookie::from_set_cookie(cookie, &url)) {
+            let same = |c: &SessionCookie| c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path;
+            match self.cookies.iter().position(same) {
+                Some(index) => self.cookies[index] = cookie,
+                None => self.cookies.push(cookie),
+            }
+            changed += 1;
+        }
+        let now = now();
+        self.cookies.retain(|cookie| !cookie.is_expired(now));
+        changed
+    }
+
+    /// Returns the `Cookie` header for a request to `url`, or `None` if no
+    /// stored cookie applies.
+    ///
+    /// Cookies with longer paths come first, as browsers send them.
+    pub fn cookie_header(&self, url: &str) -> Option<String> {
+        let url = Url::parse(url).ok()?;
+        let now = now();
+        let mut cookies: Vec<&SessionCookie> = self
+            .cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired(now) && cookie.applies_to(&url))
+            .collect();
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+        let pairs: Vec<String> = cookies.iter().map(|cookie| format!("{}={}", cookie.name, cookie.value)).collect();
+        (!pairs.is_empty()).then(|| pairs.join("; "))
+    }
+}
+
+/// Returns true if `host` is `domain` or one of its subdomains.
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.strip_suffix(domain).is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Returns true if `path` is `cookie_path` or below it.
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    match path.strip_prefix(cookie_path) {
+        Some(rest) => rest.is_empty() || cookie_path.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// Returns the path a cookie without a `Path` attribute applies to: the
+/// request path up to its last `/` (RFC 6265 section 5.1.4).
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(end) => path[..end].to_string(),
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_cookies(headers: &[&str]) -> Vec<SetCookie> {
+        headers.iter().filter_map(|header| SetCookie::parse(header)).collect()
+    }
+
+    #[test]
+    fn test_cookie_matching() {
+        let mut session = Session::default();
+        let stored = session.store_cookies(
+            "https://api.example.com/auth/login",
+            set_cookies(&[
+                "sid=abc; Path=/; Secure; HttpOnly",
+                "pref=dark; Domain=example.com; Path=/",
+                "step=2",
+                "evil=1; Domain=other.com",
+            ]),
+        );
+        assert_eq!(stored, 3);
+
+        assert_eq!(session.cookie_header("https://api.example.com/auth/me").as_deref(), Some("step=2; sid=abc; pref=dark"));
+        assert_eq!(session.cookie_header("https://api.example.com/cart").as_deref(), Some("sid=abc; pref=dark"));
+        assert_eq!(session.cookie_header("http://api.example.com/cart").as_deref(), Some("pref=dark"));
+        assert_eq!(session.cookie_header("https://www.example.com/").as_deref(), Some("pref=dark"));
+        assert_eq!(session.cookie_header("https://example.org/"), None);
+    }
+
+    #[test]
+    fn test_cookie_replace_and_expire() {
+        let mut session = Session::default();
+        let url = "https://example.com/";
+        session.store_cookies(url, set_cookies(&["sid=abc", "theme=dark; Max-Age=3600"]));
+        session.store_cookies(url, set_cookies(&["sid=def"]));
+        assert_eq!(session.cookie_header(url).as_deref(), Some("sid=def; theme=dark"));
+
+        session.store_cookies(url, set_cookies(&["sid=; Max-Age=0", "theme=; Expires=Thu, 01 Jan 1970 00:00:00 GMT"]));
+        assert!(session.cookies.is_empty());
+        assert_eq!(session.cookie_header(url), None);
+    }
+
+    #[test]
+    fn test_record_request() {
+        let request = HttpRequest::new("https://api.example.com/login")
+            .method("POST")
+            .unwrap()
+            .header("Content-Type", "application/json")
+            .header("Accept-Encoding", "gzip")
+            .body(r#"{"user": "ana"}"#);
+        let headers = ["X-Tenant: acme".to_string(), "Cookie: old=1".to_string()];
+        let mut session = Session::default();
+        session.record(&request, &headers, &["basic:ana:secret".to_string()], None);
+
+        let saved = session.request.as_ref().unwrap();
+        assert_eq!((saved.method.as_str(), saved.url.as_str()), ("POST", "https://api.example.com/login"));
+        let names: Vec<_> = saved.headers.keys().map(String::as_str).collect();
+        assert_eq!(names, ["Content-Type", "X-Tenant"]);
+        assert_eq!(saved.body.as_deref(), Some(r#"{"user": "ana"}"#));
+        assert_eq!(session.auth, ["basic:ana:secret"]);
+
+        // The body as given, with its ${ENV_VAR} references
+        let given = r#"{"user": "ana", "password": "${PASSWORD}"}"#;
+        let request = request.body(r#"{"user": "ana", "password": "hunter2"}"#);
+        session.record(&request, &headers, &[], Some(given));
+        assert_eq!(session.request.as_ref().unwrap().body.as_deref(), Some(given));
+        // Nothing to replace without a body, as with --get
+        session.record(&HttpRequest::new("https://api.example.com/?q=1"), &headers, &[], Some("q=1"));
+        assert_eq!(session.request.as_ref().unwrap().body, None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("hurley-session-{}/api.json", std::process::id()));
+        assert_eq!(Session::load(&path).unwrap(), Session::default());
+
+        let mut session = Session::default();
+        session.record(&HttpRequest::new("https://example.com/"), &[], &[], None);
+        session.store_cookies("https://example.com/", set_cookies(&["sid=abc"]));
+        session.save(&path).unwrap();
+        assert_eq!(Session::load(&path).unwrap(), session);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+            // An existing file readable by others is tightened
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            session.save(&path).unwrap();
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_session_path() {
+        assert_eq!(Session::path("./api.json").unwrap(), PathBuf::from("./api.json"));
+        assert!(Session::path("").is_err());
+        assert!(Session::path("..").is_err());
+        if let Some(dir) = config::config_dir() {
+            assert_eq!(Session::path("api").unwrap(), dir.join("sessions").join("api.json"));
+        }
+    }
+}
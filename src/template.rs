@@ -0,0 +1,174 @@
+//! Variable templating for requests.
+//!
+//! Replaces `{{name}}` placeholders in URLs, headers, and bodies with values
+//! supplied on the command line via `--var name=value`, and expands
+//! `${ENV_VAR}` references from the environment.
+
+use std::collections::HashMap;
+
+use crate::error::{Result, RurlError};
+
+/// Template variables, keyed by name.
+pub type Vars = HashMap<String, String>;
+
+/// Parses a `name=value` variable definition.
+///
+/// Used as a clap value parser for `--var`.
+pub fn parse_var(s: &str) -> std::result::Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected name=value, got '{}'", s)),
+    }
+}
+
+/// Replaces `{{name}}` placeholders in `template` with values from `vars`.
+///
+/// Whitespace inside the braces is ignored, so `{{ id }}` and `{{id}}` are
+/// equivalent. When `vars` is empty the template is returned unchanged, so
+/// bodies that legitimately contain `{{` are unaffected unless templating
+/// is in use.
+///
+/// # Errors
+///
+/// Returns [`RurlError::TemplateError`] if a placeholder names an undefined
+/// variable or is not closed.
+pub fn render(template: &str, vars: &Vars) -> Result<String> {
+    if vars.is_empty() {
+        return Ok(template.to_string());
+    }
+
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| {
+            RurlError::TemplateError(format!("unclosed placeholder in '{}'", template))
+        })?;
+        let name = after[..end].trim();
+        let value = vars.get(name).ok_or_else(|| {
+            RurlError::TemplateError(format!("undefined variable '{}'", name))
+        })?;
+        output.push_str(value);
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+/// Expands `${NAME}` references with values from the environment.
+///
+/// Write `$${NAME}` to produce a literal `${NAME}`. A `$` that is not
+/// followed by `{` is left as is.
+///
+/// # Errors
+///
+/// Returns [`RurlError::TemplateError`] if a referenced variable is not set
+/// or a reference is not closed.
+pub fn expand_env(input: &str) -> Result<String> {
+    expand_with(input, |name| std::env::var(name).ok())
+}
+
+/// Expands `${NAME}` references like [`expand_env`], looking names up
+/// with `lookup`.
+pub(crate) fn expand_with<F>(input: &str, lookup: F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+
+        if let Some(escaped) = after.strip_prefix("${") {
+            // "$${" is an escaped literal "${"
+            output.push_str("${");
+            rest = escaped;
+        } else if let Some(reference) = after.strip_prefix('{') {
+            let end = reference.find('}').ok_or_else(|| {
+                RurlError::TemplateError(format!("unclosed environment reference in '{}'", input))
+            })?;
+            let name = &reference[..end];
+            let value = lookup(name).ok_or_else(|| {
+                RurlError::TemplateError(format!("environment variable '{}' is not set", name))
+            })?;
+            output.push_str(&value);
+            rest = &reference[end + 1..];
+        } else {
+            output.push('$');
+            rest = after;
+        }
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> Vars {
+        [("id", "42"), ("tenant", "acme")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_var() {
+        assert_eq!(parse_var("id=42"), Ok(("id".to_string(), "42".to_string())));
+        assert_eq!(parse_var("q=a=b"), Ok(("q".to_string(), "a=b".to_string())));
+        assert!(parse_var("novalue").is_err());
+        assert!(parse_var("=value").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let rendered = render("https://{{tenant}}.example.com/users/{{ id }}", &vars()).unwrap();
+        assert_eq!(rendered, "https://acme.example.com/users/42");
+    }
+
+    #[test]
+    fn test_render_without_vars_is_noop() {
+        let body = r#"{"template": "{{unchanged}}"}"#;
+        assert_eq!(render(body, &Vars::new()).unwrap(), body);
+    }
+
+    #[test]
+    fn test_render_errors() {
+        assert!(render("/users/{{missing}}", &vars()).is_err());
+        assert!(render("/users/{{id", &vars()).is_err());
+    }
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "API_TOKEN" => Some("s3cret".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_env() {
+        let expanded = expand_with("Bearer ${API_TOKEN}", lookup).unwrap();
+        assert_eq!(expanded, "Bearer s3cret");
+    }
+
+    #[test]
+    fn test_expand_env_escape() {
+        let expanded = expand_with("literal $${API_TOKEN} and $5 and ${API_TOKEN}", lookup).unwrap();
+        assert_eq!(expanded, "literal ${API_TOKEN} and $5 and s3cret");
+    }
+
+    #[test]
+    fn test_expand_env_errors() {
+        assert!(expand_with("${MISSING}", lookup).is_err());
+        assert!(expand_with("${API_TOKEN", lookup).is_err());
+    }
+}
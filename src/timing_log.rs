@@ -0,0 +1,291 @@
+//! Request timing log (`--timing-log`).
+//!
+//! Appends one JSON line per request with its status and the time spent
+//! in each phase, in single-request and performance mode alike:
+//!
+//! ```text
+//! {"timestamp_ms":1700000000123,"method":"GET","url":"https://api.example.com/users","status":200,"total_ms":84.2,"dns_ms":1.9,"connect_ms":11.3,"tls_ms":24.8,"ttfb_ms":45.1,"transfer_ms":0.6,"bytes":512}
+//! ```
+//!
+//! The file is never truncated, so repeated invocations accumulate a
+//! dataset that can be read back with [`read_records`]. Phases that didn't
+//! happen, such as DNS on a reused connection or anything after a failed
+//! connect, are left out.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{Result, RurlError};
+use crate::http::{HttpResponse, PhaseTimings};
+use crate::perf::RunEvent;
+
+/// One line of the timing log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimingRecord {
+    /// When the request was sent, in milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// Request method
+    pub method: String,
+    /// Request URL
+    pub url: String,
+    /// HTTP status code, or `None` if there was no response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Why the request failed without a response (an error kind such as `timeout`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Request latency in milliseconds
+    pub total_ms: f64,
+    /// Host name resolution in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_ms: Option<f64>,
+    /// TCP connection setup in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<f64>,
+    /// TLS handshake in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_ms: Option<f64>,
+    /// Time to first byte in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttfb_ms: Option<f64>,
+    /// Response body transfer in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer_ms: Option<f64>,
+    /// Size of the response body in bytes
+    #[serde(default)]
+    pub bytes: u64,
+}
+
+impl TimingRecord {
+    /// Builds the record of a request that got a response.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - Request method
+    /// * `url` - Request URL
+    /// * `started` - When the request was sent
+    /// * `response` - The response
+    /// * `bytes` - Size of the response body
+    pub fn from_response(method: &str, url: &str, started: SystemTime, response: &HttpResponse, bytes: u64) -> Self {
+        Self::new(method, url, started, response.duration)
+            .status(Some(response.status.as_u16()), Some(&response.phases), bytes)
+    }
+
+    /// Builds the record of a request that failed without a response.
+    pub fn from_error(method: &str, url: &str, started: SystemTime, duration: Duration, error: &RurlError) -> Self {
+        Self {
+            error: Some(error.kind().to_string()),
+            ..Self::new(method, url, started, duration)
+        }
+    }
+
+    /// Builds the record of a finished performance test request, or
+    /// `None` for any other event.
+    pub fn from_event(event: &RunEvent) -> Option<Self> {
+        let RunEvent::RequestDone { request, started, status, duration, bytes, error, phases, .. } = event else {
+            return None;
+        };
+        let request = request.request();
+        let mut record = Self::new(request.method.as_str(), &request.url, *started, *duration)
+            .status(*status, phases.as_ref(), *bytes);
+        if status.is_none() {
+            record.error = error.map(str::to_string);
+        }
+        Some(record)
+    }
+
+    fn new(method: &str, url: &str, started: SystemTime, duration: Duration) -> Self {
+        Self {
+            timestamp_ms: started.duration_since(UNIX_EPOCH).map_or(0, |t| t.as_millis() as u64),
+            method: method.to_string(),
+            url: url.to_string(),
+            status: None,
+            error: None,
+            total_ms: ms(duration),
+            dns_ms: None,
+            connect_ms: None,
+            tls_ms: None,
+            ttfb_ms: None,
+            transfer_ms: None,
+            bytes: 0,
+        }
+    }
+
+    fn status(self, status: Option<u16>, phases: Option<&PhaseTimings>, bytes: u64) -> Self {
+        Self {
+            status,
+            dns_ms: phases.and_then(|p| p.dns).map(ms),
+            connect_ms: phases.and_then(|p| p.connect).map(ms),
+            tls_ms: phases.and_then(|p| p.tls).map(ms),
+            ttfb_ms: phases.map(|p| ms(p.ttfb)),
+            transfer_ms: phases.map(|p| ms(p.transfer)),
+            bytes,
+            ..self
+        }
+    }
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Appender for the timing log.
+///
+/// Safe to share between worker tasks. Write errors are kept and reported
+/// by [`finish`](Self::finish) rather than interrupting a run.
+pub struct TimingLog {
+    inner: Mutex<(BufWriter<File>, Option<std::io::Error>)>,
+}
+
+impl TimingLog {
+    /// Opens a timing log for appending, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            inner: Mutex::new((BufWriter::new(file), None)),
+        })
+    }
+
+    /// Appends a record.
+    pub fn record(&self, record: &TimingRecord) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let (out, error) = &mut *inner;
+        if error.is_some() {
+            return;
+        }
+        let result = serde_json::to_writer(&mut *out, record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| out.write_all(b"\n"));
+        if let Err(e) = result {
+            *error = Some(e);
+        }
+    }
+
+    /// Appends a line for a [`RunEvent::RequestDone`]; other events are ignored.
+    pub fn record_event(&self, event: &RunEvent) {
+        if let Some(record) = TimingRecord::from_event(event) {
+            self.record(&record);
+        }
+    }
+
+    /// Flushes the log.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error hit while writing, or an error from flushing.
+    pub fn finish(&self) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let (out, error) = &mut *inner;
+        if let Some(e) = error.take() {
+            return Err(e.into());
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads every record of a timing log, oldest first.
+///
+/// Blank lines are skipped.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or a line is not a record.
+pub fn read_records(path: &Path) -> Result<Vec<TimingRecord>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .map_err(|e| RurlError::DatasetError(format!("{} line {}: {}", path.display(), index + 1, e)))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpRequest;
+    use reqwest::header::HeaderMap;
+    use reqwest::StatusCode;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_log_appends_across_opens() {
+        let path = std::env::temp_dir().join("hurley_test_timings.ndjson");
+        std::fs::remove_file(&path).ok();
+        let started = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+
+        let mut response = HttpResponse::new(StatusCode::OK, HeaderMap::new(), "hello".to_string(), Duration::from_millis(50));
+        response.phases = PhaseTimings {
+            dns: Some(Duration::from_millis(2)),
+            connect: Some(Duration::from_millis(5)),
+            tls: None,
+            ttfb: Duration::from_millis(40),
+            transfer: Duration::from_millis(1),
+        };
+        let log = TimingLog::open(&path).unwrap();
+        log.record(&TimingRecord::from_response("GET", "http://x/users", started, &response, 5));
+        log.finish().unwrap();
+
+        let error = RurlError::ResolveError("no".to_string());
+        let log = TimingLog::open(&path).unwrap();
+        log.record(&TimingRecord::from_error("POST", "http://x/orders", started, Duration::from_millis(3), &error));
+        log.finish().unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let records = read_records(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(
+            text.lines().next().unwrap(),
+            r#"{"timestamp_ms":1700000000123,"method":"GET","url":"http://x/users","status":200,"total_ms":50.0,"dns_ms":2.0,"connect_ms":5.0,"ttfb_ms":40.0,"transfer_ms":1.0,"bytes":5}"#
+        );
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].error.as_deref(), Some("resolve"));
+        assert_eq!(records[1].status, None);
+        assert_eq!(records[1].ttfb_ms, None);
+    }
+
+    #[test]
+    fn test_from_event() {
+        let event = RunEvent::RequestDone {
+            completed: 1,
+            total: 1,
+            label: "GET /".to_string(),
+            request: Arc::new(HttpRequest::new("http://x/").prepare().unwrap()),
+            started: UNIX_EPOCH,
+            status: None,
+            duration: Duration::from_millis(7),
+            bytes: 0,
+            success: false,
+            error: Some("connect"),
+            phases: None,
+            captures: Vec::new(),
+        };
+        let record = TimingRecord::from_event(&event).unwrap();
+        assert_eq!(record.error.as_deref(), Some("connect"));
+        assert_eq!(record.total_ms, 7.0);
+        assert!(TimingRecord::from_event(&RunEvent::WarmupFinished).is_none());
+    }
+
+    #[test]
+    fn test_read_rejects_garbage() {
+        let path = std::env::temp_dir().join("hurley_test_timings_bad.ndjson");
+        std::fs::write(&path, "\n{\"nope\": 1}\n").unwrap();
+        let error = read_records(&path).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert!(error.to_string().contains("line 2"));
+    }
+}
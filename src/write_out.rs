@@ -0,0 +1,184 @@
+//! `-w` / `--write-out` formats.
+//!
+//! A write-out format is printed after the response, with `%{variable}`
+//! placeholders filled in from it, much like curl's option of the same name:
+//!
+//! ```text
+//! %{http_code} %{time_total}s %{header:x-request-id}\n
+//! ```
+//!
+//! Supported variables are `http_code` (alias `response_code`),
+//! `time_total` (seconds), `size_download` (body bytes), `content_type`,
+//! `url`, and `header:NAME` for any response header. `\n`, `\t`, `\r` and
+//! `\\` are unescaped.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::captures::header_value;
+use crate::error::{Result, RurlError};
+use crate::http::HttpResponse;
+
+/// A `%{...}` placeholder.
+#[derive(Debug, Clone, PartialEq)]
+enum Variable {
+    HttpCode,
+    TimeTotal,
+    SizeDownload,
+    ContentType,
+    Url,
+    Header(String),
+}
+
+impl Variable {
+    fn render(&self, response: &HttpResponse, url: &str) -> String {
+        match self {
+            Variable::HttpCode => response.status.as_u16().to_string(),
+            Variable::TimeTotal => format!("{:.6}", response.duration.as_secs_f64()),
+            Variable::SizeDownload => response.body.len().to_string(),
+            Variable::ContentType => header_value(response, "content-type").unwrap_or_default(),
+            Variable::Url => url.to_string(),
+            Variable::Header(name) => header_value(response, name).unwrap_or_default(),
+        }
+    }
+}
+
+impl FromStr for Variable {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(name) = s.strip_prefix("header:") {
+            return match name.trim() {
+                "" => Err(RurlError::TemplateError("missing header name in %{header:}".to_string())),
+                name => Ok(Variable::Header(name.to_string())),
+            };
+        }
+        match s {
+            "http_code" | "response_code" => Ok(Variable::HttpCode),
+            "time_total" => Ok(Variable::TimeTotal),
+            "size_download" => Ok(Variable::SizeDownload),
+            "content_type" => Ok(Variable::ContentType),
+            "url" | "url_effective" => Ok(Variable::Url),
+            other => Err(RurlError::TemplateError(format!(
+                "unknown write-out variable '%{{{}}}' (use http_code, time_total, size_download, content_type, url or header:NAME)",
+                other
+            ))),
+        }
+    }
+}
+
+/// One piece of a write-out format.
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Variable(Variable),
+}
+
+/// A parsed `--write-out` format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteOut {
+    format: String,
+    segments: Vec<Segment>,
+}
+
+impl WriteOut {
+    /// Renders the format for a response to the request sent to `url`.
+    pub fn render(&self, response: &HttpResponse, url: &str) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(text) => text.clone(),
+                Segment::Variable(variable) => variable.render(response, url),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for WriteOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format)
+    }
+}
+
+impl FromStr for WriteOut {
+    type Err = RurlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = s;
+        while let Some(c) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix("%{") {
+                let end = after
+                    .find('}')
+                    .ok_or_else(|| RurlError::TemplateError(format!("unclosed '%{{' in write-out '{}'", s)))?;
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Variable(after[..end].trim().parse()?));
+                rest = &after[end + 1..];
+                continue;
+            }
+            let escaped = match rest.strip_prefix('\\').and_then(|after| after.chars().next()) {
+                Some('n') => Some('\n'),
+                Some('t') => Some('\t'),
+                Some('r') => Some('\r'),
+                Some('\\') => Some('\\'),
+                _ => None,
+            };
+            match escaped {
+                Some(unescaped) => {
+                    literal.push(unescaped);
+                    rest = &rest[2..];
+                }
+                None => {
+                    literal.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self {
+            format: s.to_string(),
+            segments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    fn response() -> HttpResponse {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", HeaderValue::from_static("req-42"));
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+        HttpResponse::new(StatusCode::CREATED, headers, "{}".to_string(), Duration::from_millis(1500))
+    }
+
+    fn render(format: &str) -> String {
+        format.parse::<WriteOut>().unwrap().render(&response(), "https://example.com/users")
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(render("%{http_code}\\n"), "201\n");
+        assert_eq!(render("id=%{header:X-Request-Id} t=%{time_total}"), "id=req-42 t=1.500000");
+        assert_eq!(render("%{size_download}\\t%{content_type}\\t%{url}"), "2\tapplication/json\thttps://example.com/users");
+        assert_eq!(render("[%{header:etag}]"), "[]");
+        assert_eq!(render("100% done ✓"), "100% done ✓");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("%{http_code".parse::<WriteOut>().is_err());
+        assert!("%{bogus}".parse::<WriteOut>().is_err());
+        assert!("%{header:}".parse::<WriteOut>().is_err());
+        assert_eq!("%{http_code}\\n".parse::<WriteOut>().unwrap().to_string(), "%{http_code}\\n");
+    }
+}
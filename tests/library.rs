@@ -0,0 +1,115 @@
+//! Embeds hurley as a library against a local server.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use hurley::http::{Middleware, SetHeaders};
+use hurley::{Dataset, HttpClient, HttpRequest, HttpResponse, PerfRunner, RurlError};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Starts a keep-alive server answering every request with `200` and the
+/// value of its `X-Echo` header, or `ok`, and returns its URL.
+async fn serve() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else { return };
+            tokio::spawn(async move {
+                let mut received = Vec::new();
+                let mut buf = [0u8; 1024];
+                loop {
+                    let Ok(n) = socket.read(&mut buf).await else { return };
+                    if n == 0 {
+                        return;
+                    }
+                    received.extend_from_slice(&buf[..n]);
+                    // Requests carry no body, so each ends at its blank line
+                    while let Some(end) = received.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&received[..end]).to_lowercase();
+                        received.drain(..end + 4);
+                        let body = head
+                            .lines()
+                            .find_map(|line| line.strip_prefix("x-echo: "))
+                            .unwrap_or("ok")
+                            .to_string();
+                        let reply = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+                        if socket.write_all(reply.as_bytes()).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_execute_request() {
+    let url = serve().await;
+    let response = HttpClient::new(false).execute(&HttpRequest::new(format!("{}/get", url))).await.unwrap();
+    assert_eq!(response.status.as_u16(), 200);
+    assert_eq!(response.body, "ok");
+}
+
+#[tokio::test]
+async fn test_perf_run() {
+    let url = serve().await;
+    let request = HttpRequest::new(url.clone());
+    let runner = PerfRunner::new(url, request, 4, 40, false);
+    let metrics = runner.run(&Dataset::simple(40)).await.unwrap();
+    assert_eq!(metrics.total_requests, 40);
+    assert_eq!(metrics.successful_requests, 40);
+    assert_eq!(metrics.status_codes.get(&200), Some(&40));
+}
+
+#[tokio::test]
+async fn test_errors_are_returned() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let result = HttpClient::new(false).execute(&HttpRequest::new(url)).await;
+    assert!(matches!(result, Err(RurlError::RequestError(_))), "{:?}", result.err());
+}
+
+/// Counts the responses it sees and fails requests to `/forbidden`.
+#[derive(Default)]
+struct Counter {
+    responses: AtomicUsize,
+}
+
+impl Middleware for Counter {
+    fn before(&self, request: &mut HttpRequest) -> hurley::Result<()> {
+        match request.url.ends_with("/forbidden") {
+            true => Err(RurlError::ConfigError("blocked by middleware".to_string())),
+            false => Ok(()),
+        }
+    }
+
+    fn after(&self, _request: &HttpRequest, _response: &HttpResponse) {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[tokio::test]
+async fn test_middleware() {
+    let url = serve().await;
+    let counter = Arc::new(Counter::default());
+    let client = HttpClient::new(false)
+        .middleware(Arc::new(SetHeaders::new().header("X-Echo", "injected")))
+        .middleware(counter.clone());
+
+    let response = client.execute(&HttpRequest::new(format!("{}/get", url))).await.unwrap();
+    assert_eq!(response.body, "injected");
+    let result = client.execute(&HttpRequest::new(format!("{}/forbidden", url))).await;
+    assert!(matches!(result, Err(RurlError::ConfigError(_))));
+    assert_eq!(counter.responses.load(Ordering::Relaxed), 1);
+
+    let counter = Arc::new(Counter::default());
+    let runner = PerfRunner::new(url.clone(), HttpRequest::new(url), 2, 10, false).middleware(counter.clone());
+    let metrics = runner.run(&Dataset::simple(10)).await.unwrap();
+    assert_eq!(metrics.successful_requests, 10);
+    assert_eq!(counter.responses.load(Ordering::Relaxed), 10);
+}